@@ -142,7 +142,7 @@ fn main() {
     eprintln!("\n--- RECONSTRUCT ---");
 
     bench("reconstruct::run('iris')", 20, || {
-        let _ = amaranthine::reconstruct::run(&dir, "iris").unwrap();
+        let _ = amaranthine::reconstruct::run(&dir, "iris", "summary", None, None).unwrap();
     });
 
     // --- CORPUS PATH ---
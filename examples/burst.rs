@@ -60,7 +60,7 @@ fn main() {
 
     // Phase 6: 20 reconstructs
     for _ in 0..20 {
-        let _ = amaranthine::reconstruct::run(&dir, "iris");
+        let _ = amaranthine::reconstruct::run(&dir, "iris", "summary", None, None);
         iters += 1;
     }
 
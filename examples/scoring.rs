@@ -0,0 +1,219 @@
+// Scoring-loop benchmark on a synthetic 100k+ posting corpus. Phase 1 (BM25
+// accumulation) two ways over the same index: the per-posting loop search used
+// before, which reads EntryMeta and recomputes length norm and conf·recency
+// for every posting, and the current one, which does that once per entry on
+// first touch and caches it. Both numbers and their ratio are printed, the
+// scores cross-checked against each other and against search_v2_or. Then
+// search_v2_or end to end, and top-K selection cost at large limits (heap vs
+// insertion sort over the same candidate scores).
+// Run: cargo run --release --example scoring
+
+use std::mem::size_of;
+use std::time::Instant;
+use amaranthine::cffi::RawResult;
+use amaranthine::format::{EntryMeta, Posting, hash_term};
+
+const ENTRIES: usize = 40_000;
+const TERMS: &[&str] = &["alpha", "bravo", "charlie", "delta", "echo"];
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("amr-scoring-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let log_path = amaranthine::datalog::ensure_log(&dir).unwrap();
+    let mut f = std::fs::OpenOptions::new().append(true).open(&log_path).unwrap();
+    let base_ts = (amaranthine::time::LocalTime::now().to_minutes() - 90 * 1440) as i32;
+    for i in 0..ENTRIES {
+        // Each entry carries 3 of the 5 query terms → ~24k postings per term
+        let mut body = String::with_capacity(96);
+        for k in 0..3 { body.push_str(TERMS[(i + k) % TERMS.len()]); body.push(' '); }
+        body.push_str(&format!("filler{} word{} note{}", i % 97, i % 13, i));
+        let ts = base_ts + (i as i32 % 90) * 1440;
+        amaranthine::datalog::append_entry_to(&mut f, &format!("topic{}", i % 40), &body, ts).unwrap();
+    }
+    drop(f);
+    let (_, index) = amaranthine::inverted::rebuild(&dir).unwrap();
+    let query = TERMS.join(" ");
+    let postings: usize = TERMS.iter()
+        .map(|t| amaranthine::binquery::term_stats(&index, t).map_or(0, |(df, _)| df as usize))
+        .sum();
+    let pred = amaranthine::binquery::FilterPred::none();
+
+    eprintln!("=== SCORING LOOP BENCHMARK ===");
+    eprintln!("entries: {ENTRIES}, postings scored per query: {postings}");
+
+    let mut ref_state = RefState::new(ENTRIES);
+    let mut cached_state = RefState::new(ENTRIES);
+    let ref_best = per_posting_scores(&index, &query, &mut ref_state);
+    let cached_best = first_touch_scores(&index, &query, &mut cached_state);
+    let hits = amaranthine::binquery::search_v2_or(&index, &query, &pred, 1).unwrap();
+    // Top hit is the max-scoring entry in all three; recency/diversity are identical inputs.
+    let best = hits.first().map_or(0.0, |h| h.score);
+    eprintln!("top score: per-posting={ref_best:.6} first-touch={cached_best:.6} search_v2_or={best:.6}");
+    assert!((ref_best - best).abs() < 1e-9 * ref_best.max(1.0), "score mismatch");
+    assert!((ref_best - cached_best).abs() < 1e-9 * ref_best.max(1.0), "score mismatch");
+
+    eprintln!();
+    eprintln!("--- PHASE 1: BM25 ACCUMULATION ---");
+    let old = bench("per-posting loop (before)", 100, || {
+        let _ = per_posting_scores(&index, &query, &mut ref_state);
+    });
+    let new = bench("first-touch cached loop (now)", 100, || {
+        let _ = first_touch_scores(&index, &query, &mut cached_state);
+    });
+    eprintln!("speedup: {:.2}x ({:.0} → {:.0} Mpost/s)", old / new, postings as f64 / old, postings as f64 / new);
+
+    eprintln!();
+    eprintln!("--- END TO END ---");
+    let t = bench("search_v2_or limit=10", 100, || {
+        let _ = amaranthine::binquery::search_v2_or(&index, &query, &pred, 10).unwrap();
+    });
+    eprintln!("throughput: {:.0} Mpost/s end to end (scoring, selection, hydration)", postings as f64 / t);
+
+    eprintln!();
+    eprintln!("--- TOP-K (large limits, 40k candidates) ---");
+    let hashes: Vec<u64> = TERMS.iter().map(|t| hash_term(t)).collect();
+    let mut cstate = amaranthine::cffi::QueryState::new(ENTRIES);
+    let _ = per_posting_scores(&index, &query, &mut ref_state);
+    for &k in &[10usize, 100, 1000, 5000] {
+        bench(&format!("search_v2_or limit={k}"), 30, || {
+            let _ = amaranthine::binquery::search_v2_or(&index, &query, &pred, k).unwrap();
+        });
+        let mut out = vec![RawResult { entry_id: 0, score_x1000: 0 }; k];
+        bench(&format!("cffi::search_raw k={k}"), 30, || {
+            let _ = amaranthine::cffi::search_raw(&index, &hashes, &mut cstate, &mut out).unwrap();
+        });
+        bench(&format!("insertion-sort select only k={k}"), 30, || {
+            let _ = insertion_topk(&ref_state, k);
+        });
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// The pre-heap selection: shift-insert into a sorted K buffer. O(N·K) worst case.
+fn insertion_topk(st: &RefState, k: usize) -> usize {
+    let mut out: Vec<(u32, u32)> = vec![(0, 0); k];
+    let mut n = 0usize;
+    for eid in 0..st.scores.len() {
+        if st.entry_gen[eid] != st.gen { continue; }
+        let s = (st.scores[eid] * 1000.0) as u32;
+        if n < k {
+            let mut pos = n;
+            while pos > 0 && out[pos - 1].1 < s { out[pos] = out[pos - 1]; pos -= 1; }
+            out[pos] = (eid as u32, s);
+            n += 1;
+        } else if s > out[n - 1].1 {
+            let mut pos = n - 1;
+            while pos > 0 && out[pos - 1].1 < s { out[pos] = out[pos - 1]; pos -= 1; }
+            out[pos] = (eid as u32, s);
+        }
+    }
+    std::hint::black_box(n)
+}
+
+struct RefState { gen: u32, entry_gen: Vec<u32>, scores: Vec<f64>, knorm: Vec<f64>, weight: Vec<f64> }
+
+impl RefState {
+    fn new(n: usize) -> Self {
+        Self { gen: 0, entry_gen: vec![0; n], scores: vec![0.0; n], knorm: vec![0.0; n], weight: vec![0.0; n] }
+    }
+}
+
+/// Scoring inputs shared by both loops: (term postings (base, len), meta
+/// offset, per-topic k1·0.75/avgdl, today in epoch days).
+fn setup(data: &[u8], query: &str) -> (Vec<(usize, usize)>, usize, Vec<f64>, u16) {
+    let hdr = amaranthine::binquery::read_header(data).unwrap();
+    let table_cap = { hdr.table_cap } as usize;
+    let post_off = { hdr.postings_off } as usize;
+    let mut lists = Vec::new();
+    for term in amaranthine::text::query_terms(query) {
+        let h = hash_term(&term);
+        let mut idx = (h as usize) & (table_cap - 1);
+        loop {
+            let slot = amaranthine::binquery::read_slot(data, idx).unwrap();
+            if { slot.hash } == 0 { break; }
+            if { slot.hash } == h {
+                lists.push((post_off + { slot.postings_off } as usize * size_of::<Posting>(), { slot.postings_len } as usize));
+                break;
+            }
+            idx = (idx + 1) & (table_cap - 1);
+        }
+    }
+    let slopes = amaranthine::binquery::topic_avgdls(data).unwrap().into_iter()
+        .map(|a| 1.2 * 0.75 / a.max(1.0)).collect();
+    let today = (amaranthine::time::LocalTime::now().to_minutes() / 1440) as u16;
+    (lists, { hdr.meta_off } as usize, slopes, today)
+}
+
+fn conf_recency(m: &EntryMeta, today: u16) -> f64 {
+    let ed = { m.epoch_days };
+    let recency = if ed == 0 { 1.0 } else { 30.0 / (30.0 + today.saturating_sub(ed) as f64) };
+    let conf = { m.confidence } as f64 / 255.0;
+    conf * recency
+}
+
+fn best_of(st: &RefState) -> f64 {
+    st.scores.iter().zip(&st.entry_gen).filter(|(_, &g)| g == st.gen).map(|(&s, _)| s).fold(0.0, f64::max)
+}
+
+/// The loop search used before: EntryMeta read, length norm and
+/// conf·recency recomputed for every posting. Leaves each candidate's score
+/// in `st` for the selection-only runs; returns the best.
+fn per_posting_scores(data: &[u8], query: &str, st: &mut RefState) -> f64 {
+    let (lists, meta_off, slopes, today) = setup(data, query);
+    st.gen += 1;
+    let gen = st.gen;
+    for (base, len) in lists {
+        for i in 0..len {
+            let p: Posting = amaranthine::binquery::read_at(data, base + i * size_of::<Posting>()).unwrap();
+            let eid = { p.entry_id } as usize;
+            let m: EntryMeta = amaranthine::binquery::read_at(data, meta_off + eid * size_of::<EntryMeta>()).unwrap();
+            if st.entry_gen[eid] != gen { st.scores[eid] = 0.0; st.entry_gen[eid] = gen; }
+            let k = 1.2 * (1.0 - 0.75) + slopes[{ m.topic_id } as usize] * { m.word_count } as f64;
+            let tf = { p.tf } as f64;
+            st.scores[eid] += { p.idf_x1000 } as f64 / 1000.0 * (tf * 2.2) / (tf + k) * conf_recency(&m, today);
+        }
+    }
+    best_of(st)
+}
+
+/// The loop search uses now: EntryMeta read once per entry on first touch,
+/// its k1·len_norm and conf·recency weight cached for later postings.
+fn first_touch_scores(data: &[u8], query: &str, st: &mut RefState) -> f64 {
+    let (lists, meta_off, slopes, today) = setup(data, query);
+    st.gen += 1;
+    let gen = st.gen;
+    for (base, len) in lists {
+        for i in 0..len {
+            let p: Posting = amaranthine::binquery::read_at(data, base + i * size_of::<Posting>()).unwrap();
+            let eid = { p.entry_id } as usize;
+            if st.entry_gen[eid] != gen {
+                let m: EntryMeta = amaranthine::binquery::read_at(data, meta_off + eid * size_of::<EntryMeta>()).unwrap();
+                st.entry_gen[eid] = gen;
+                st.scores[eid] = 0.0;
+                st.weight[eid] = conf_recency(&m, today);
+                st.knorm[eid] = 1.2 * (1.0 - 0.75) + slopes[{ m.topic_id } as usize] * { m.word_count } as f64;
+            }
+            let tf = { p.tf } as f64;
+            st.scores[eid] += { p.idf_x1000 } as f64 / 1000.0 * (tf * 2.2) / (tf + st.knorm[eid]) * st.weight[eid];
+        }
+    }
+    best_of(st)
+}
+
+/// Run `f` `iters` times after warmup; print p50/p99/min, return min in microseconds.
+/// Min is the comparison basis — least sensitive to scheduler noise.
+fn bench<F: FnMut()>(name: &str, iters: usize, mut f: F) -> f64 {
+    for _ in 0..3 { f(); }
+    let mut times: Vec<f64> = (0..iters).map(|_| {
+        let start = Instant::now();
+        f();
+        start.elapsed().as_secs_f64() * 1_000_000.0
+    }).collect();
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let p50 = times[times.len() / 2];
+    let p99 = times[(times.len() * 99 / 100).min(times.len() - 1)];
+    eprintln!("  {name:42} p50={p50:>9.1}µs  p99={p99:>9.1}µs  min={:>9.1}µs", times[0]);
+    times[0]
+}
@@ -17,15 +17,42 @@ typedef struct {
     uint32_t score_x1000;
 } AmrResult;
 
+/* Index statistics from amr_info_struct */
+typedef struct {
+    uint32_t entries;
+    uint32_t terms;
+    uint32_t topics;
+    uint32_t xrefs;
+    uint32_t tags;
+    uint32_t version;
+    uint64_t bytes;       /* size of index.bin */
+    uint64_t generation;  /* index.gen generation at open/reload; 0 without index.gen */
+} AmrInfo;
+
+/* Error codes: returned by amr_reload/amr_info_struct, and by amr_last_error() after a
+   failed call (NULL from amr_open, "error: ..." text from amr_search/amr_info). */
+#define AMR_OK              0
+#define AMR_E_NOT_FOUND    -1
+#define AMR_E_CORRUPT      -2
+#define AMR_E_LOCKED       -3
+#define AMR_E_INVALID_ARG  -4
+#define AMR_E_IO           -5
+
 /* --- Standard API (~1μs, convenient) --- */
 
 AmrIndex* amr_open(const char* index_path);
 char*     amr_search(const AmrIndex* idx, const char* query, uint32_t limit);
 char*     amr_info(const AmrIndex* idx);
+int       amr_info_struct(const AmrIndex* idx, AmrInfo* out);
 int       amr_is_stale(const AmrIndex* idx);
 int       amr_reload(AmrIndex* idx);
 void      amr_free_str(char* s);
 void      amr_close(AmrIndex* idx);
+int       amr_last_error(void);
+
+/* Autocomplete: corpus terms starting with prefix, most common first, as
+   "term\tdf\n" lines (df = entries containing it). Free with amr_free_str. */
+char*     amr_terms(const AmrIndex* idx, const char* prefix, uint32_t limit);
 
 /* --- Zero-alloc API (~100-200ns, no heap allocation) --- */
 
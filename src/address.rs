@@ -0,0 +1,93 @@
+//! Entry addresses: one string naming a single entry, accepted in place of a
+//! topic + index pair and printed on search results so operations chain.
+//!   `topic:3`            entry 3 of topic (0-based, as listed by `entries`)
+//!   `topic:uid:ab12cd`   entry whose uid starts with ab12cd (4–16 hex digits)
+//! The uid hashes the entry's timestamp and content lines, so it survives tag
+//! edits, deletes of other entries, and compaction; revise/append change it.
+
+use crate::error::AmrError;
+use crate::fxhash::FxHashMap;
+use std::path::Path;
+
+const MIN_UID_HEX: usize = 4;
+
+pub enum Selector { Index(usize), Uid(String) }
+
+pub struct Address<'a> {
+    pub topic: &'a str,
+    pub sel: Selector,
+}
+
+/// Topic names never contain ':' (sanitize_topic), so any ':' means an address.
+pub fn is_address(s: &str) -> bool { s.contains(':') }
+
+pub fn parse(s: &str) -> Result<Address<'_>, AmrError> {
+    let bad = || AmrError::InvalidArg(format!(
+        "bad entry address '{s}' (expected topic:N or topic:uid:HEX)"));
+    let (topic, rest) = s.trim().split_once(':').ok_or_else(bad)?;
+    if topic.is_empty() { return Err(bad()); }
+    if let Some(hex) = rest.strip_prefix("uid:") {
+        let ok = (MIN_UID_HEX..=16).contains(&hex.len()) && hex.bytes().all(|b| b.is_ascii_hexdigit());
+        if !ok { return Err(bad()); }
+        return Ok(Address { topic, sel: Selector::Uid(hex.to_ascii_lowercase()) });
+    }
+    let idx = rest.parse().map_err(|_| bad())?;
+    Ok(Address { topic, sel: Selector::Index(idx) })
+}
+
+/// Stable entry uid: FNV-1a over the timestamp and non-metadata lines.
+pub fn entry_uid(timestamp_min: i32, body: &str) -> u64 {
+    let mut key = timestamp_min.to_string();
+    for line in body.lines().filter(|l| !crate::text::is_metadata_line(l)) {
+        key.push('\n');
+        key.push_str(line.trim_end());
+    }
+    crate::format::hash_term(&key)
+}
+
+/// Display form of a uid: the first 8 of its 16 hex digits.
+pub fn format_uid(uid: u64) -> String { format!("{:08x}", uid >> 32) }
+
+/// "topic:3 · topic:uid:ab12cd01"
+pub fn format_both(topic: &str, idx: usize, uid: u64) -> String {
+    format!("{topic}:{idx} · {topic}:uid:{}", format_uid(uid))
+}
+
+/// 0-based index within `addr.topic` of the addressed entry. Index addresses
+/// pass through unchecked (the operation reports out-of-range itself).
+pub fn resolve(dir: &Path, addr: &Address) -> Result<usize, AmrError> {
+    let hex = match &addr.sel {
+        Selector::Index(i) => return Ok(*i),
+        Selector::Uid(hex) => hex,
+    };
+    let matches: Vec<usize> = crate::cache::with_corpus(dir, |cached| {
+        cached.iter().filter(|e| e.topic.as_str() == addr.topic).enumerate()
+            .filter(|(_, e)| format!("{:016x}", entry_uid(e.timestamp_min, &e.body)).starts_with(hex.as_str()))
+            .map(|(i, _)| i)
+            .collect()
+    })?;
+    match matches.as_slice() {
+        [i] => Ok(*i),
+        [] => Err(AmrError::NotFound(format!("no entry {}:uid:{hex}", addr.topic))),
+        _ => Err(AmrError::InvalidArg(format!(
+            "{}:uid:{hex} is ambiguous ({} entries) — use more hex digits", addr.topic, matches.len()))),
+    }
+}
+
+/// (index within topic, uid) for each data.log offset, for labeling results.
+pub fn lookup(dir: &Path, offsets: &[u32]) -> FxHashMap<u32, (usize, u64)> {
+    let mut out = FxHashMap::default();
+    if offsets.is_empty() { return out; }
+    let wanted: crate::fxhash::FxHashSet<u32> = offsets.iter().copied().collect();
+    let _ = crate::cache::with_corpus(dir, |cached| {
+        let mut seen: FxHashMap<&str, usize> = FxHashMap::default();
+        for e in cached {
+            let n = seen.entry(e.topic.as_str()).or_insert(0);
+            if wanted.contains(&e.offset) {
+                out.insert(e.offset, (*n, entry_uid(e.timestamp_min, &e.body)));
+            }
+            *n += 1;
+        }
+    });
+    out
+}
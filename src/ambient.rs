@@ -0,0 +1,355 @@
+//! Ambient context for the PreToolUse hook: up to five search layers over
+//! index.bin for the file being edited, deduplicated against each other and
+//! the session, then rendered as the hook's additionalContext. Layers and
+//! their caps are configurable per knowledge base (`AmbientConfig`); the
+//! `ambient_preview` tool shows a plan without injecting it.
+
+use std::path::Path;
+
+/// Smart Ambient Context: multi-layer search with cross-invocation deduplication.
+/// v10.1: Unified function — Option<Session> for session dedup + auto-focus topics.
+///
+/// Layers (each deduplicates against all prior via FxHashSet<u32>):
+///   1. Source-path matches — entries with [source:] metadata for this file
+///   2. Symbol-based OR search — fn/struct/enum names extracted from file
+///   3. Global BM25 search — stem keyword
+///   4. Structural coupling — "structural <stem>" query
+///   5. Refactor impact — removed symbols (Edit only)
+///
+/// When session=Some: skips entries already injected this session, marks new ones,
+/// and auto-infers focus topics from entry topic names (3+ hits threshold).
+/// While the session is debugging a failing build, layer 3 searches gotchas for
+/// the stem and the build errors instead, and layer 4 is skipped.
+/// Layers can be switched off or capped per knowledge base (AmbientConfig).
+pub fn query_ambient(
+    dir: &Path, data: &[u8], stem: &str, file_path: &str, syms: &[&str],
+    session: Option<&mut crate::session::Session>,
+) -> String {
+    let mut cfg = AmbientConfig::load(dir);
+    cfg.build_errors = session.as_ref().and_then(|s| s.failing_build()).map(<[String]>::to_vec);
+    let plan = plan_ambient(data, stem, file_path, syms, &cfg, session.as_ref().map(|s| &s.injected));
+    if plan.picks.is_empty() { return String::new(); }
+
+    // Session bookkeeping: mark injected + auto-infer focus topics
+    if let Some(session) = session {
+        for p in &plan.picks {
+            session.mark_injected(p.entry_id);
+        }
+        // Auto-infer focus topics: count hits per topic, add topics with 3+ hits
+        let mut topic_counts: crate::fxhash::FxHashMap<u16, u16> = crate::fxhash::map_with_capacity(8);
+        for p in &plan.picks {
+            if let Ok(tid) = crate::binquery::entry_topic_id(data, p.entry_id) {
+                *topic_counts.entry(tid).or_insert(0) += 1;
+            }
+        }
+        for (&tid, &count) in &topic_counts {
+            if count >= 3 {
+                if let Ok(name) = crate::binquery::topic_name(data, tid) {
+                    session.add_focus_topic(&name);
+                }
+            }
+        }
+    }
+    render_ambient(&plan, stem, file_path, syms)
+}
+
+/// Ambient layer labels, in layer order (see query_ambient).
+pub const AMBIENT_LAYERS: [&str; 5] = ["source-linked", "symbol context", "related", "structural coupling", "REFACTOR IMPACT"];
+
+/// Layer names in `<dir>/config`, in layer order.
+pub const AMBIENT_LAYER_KEYS: [&str; 5] = ["source", "symbols", "related", "structural", "refactor"];
+
+/// Which ambient layers run and how many entries each may add:
+///   ambient_layers = source, symbols      # only these (default: all five)
+///   ambient_caps = source:3, related:1    # per-layer maximum (refactor: per symbol)
+pub struct AmbientConfig {
+    pub enabled: [bool; 5],
+    pub caps: [usize; 5],
+    /// Set from the session while it debugs a failing build: its errors.
+    pub build_errors: Option<Vec<String>>,
+}
+
+/// Tag of the entries layer 3 turns to while debugging a failing build.
+const DEBUG_TAG: &str = "gotcha";
+
+impl Default for AmbientConfig {
+    fn default() -> Self { Self { enabled: [true; 5], caps: [usize::MAX, 5, 3, 3, 3], build_errors: None } }
+}
+
+impl AmbientConfig {
+    pub fn load(dir: &Path) -> Self {
+        let settings = crate::settings::load(dir);
+        let mut cfg = Self::default();
+        let layers = settings.list("ambient_layers");
+        if !layers.is_empty() {
+            for (i, key) in AMBIENT_LAYER_KEYS.iter().enumerate() { cfg.enabled[i] = layers.contains(key); }
+        }
+        for kv in settings.list("ambient_caps") {
+            let Some((k, v)) = kv.split_once(':') else { continue };
+            let (Some(i), Ok(n)) = (AMBIENT_LAYER_KEYS.iter().position(|l| *l == k.trim()), v.trim().parse()) else { continue };
+            cfg.caps[i] = n;
+        }
+        cfg
+    }
+}
+
+/// One entry an ambient layer picked. `score` is the BM25 score (None for
+/// source-linked, a metadata match).
+pub struct AmbientPick<'a> {
+    pub layer: usize,
+    pub entry_id: u32,
+    pub score: Option<f64>,
+    pub snippet: std::borrow::Cow<'a, str>,
+}
+
+/// What query_ambient would inject, before rendering or session bookkeeping.
+pub struct AmbientPlan<'a> {
+    /// In layer order.
+    pub picks: Vec<AmbientPick<'a>>,
+    /// (layer, entry_id) of hits dropped because the session already injected them.
+    pub already_injected: Vec<(usize, u32)>,
+    /// Layer 2's OR query, if it ran.
+    pub symbol_query: Option<String>,
+    /// Layer 2 skipped: source-linked entries already cover the file.
+    pub symbols_skipped: bool,
+    /// Layer 2's query went over the cost cap; which terms were dropped.
+    pub symbols_truncated: Option<String>,
+    /// Debugging a failing build: layer 3 held gotchas, layer 4 didn't run.
+    pub debugging: bool,
+}
+
+/// Run the enabled ambient layers against `data`. `injected` is the session's
+/// set of entries already shown; it is only read. Searches fetch a little past
+/// each cap so entries deduplicated against earlier layers can be replaced.
+pub fn plan_ambient<'a>(
+    data: &'a [u8], stem: &str, file_path: &str, syms: &[&str], cfg: &AmbientConfig,
+    injected: Option<&crate::fxhash::FxHashSet<u32>>,
+) -> AmbientPlan<'a> {
+    let filename = std::path::Path::new(file_path)
+        .file_name().and_then(|f| f.to_str()).unwrap_or(stem);
+    let mut seen = crate::fxhash::FxHashSet::default();
+    let mut plan = AmbientPlan {
+        picks: Vec::with_capacity(32), already_injected: Vec::new(),
+        symbol_query: None, symbols_skipped: false, symbols_truncated: None,
+        debugging: cfg.build_errors.is_some(),
+    };
+
+    // Dedup: local seen set + session injected (if available)
+    let mut check_add = |plan: &mut AmbientPlan, layer: usize, eid: u32| -> bool {
+        if injected.is_some_and(|inj| inj.contains(&eid)) {
+            if seen.insert(eid) { plan.already_injected.push((layer, eid)); }
+            return false;
+        }
+        seen.insert(eid)
+    };
+    let in_layer = |plan: &AmbientPlan, layer: usize| plan.picks.iter().filter(|p| p.layer == layer).count();
+
+    // Layer 1: Source-path matches
+    let source_ids = if cfg.enabled[0] {
+        crate::binquery::source_entries_for_file(data, filename).unwrap_or_default()
+    } else { Vec::new() };
+    for &eid in &source_ids {
+        if in_layer(&plan, 0) >= cfg.caps[0] { break; }
+        if check_add(&mut plan, 0, eid) {
+            if let Ok(snip) = crate::binquery::entry_snippet_ref(data, eid) {
+                if !snip.is_empty() {
+                    plan.picks.push(AmbientPick { layer: 0, entry_id: eid, score: None,
+                        snippet: std::borrow::Cow::Borrowed(snip) });
+                }
+            }
+        }
+    }
+
+    // Layer 2: Symbol-based search — skip if Layer 1 already provided enough context.
+    if !cfg.enabled[1] || cfg.caps[1] == 0 {
+    } else if source_ids.len() < 5 {
+        let file_symbols = crate::hook::cached_file_symbols(file_path);
+        if !file_symbols.is_empty() {
+            let query = crate::hook::build_symbol_query(&file_symbols, stem);
+            if !query.is_empty() {
+                let filter = crate::binquery::FilterPred::none();
+                let hits = crate::binquery::search_v2_or(data, &query, &filter, cfg.caps[1].saturating_add(3))
+                    .unwrap_or_default();
+                plan.symbols_truncated = crate::costcap::take_truncation();
+                for h in hits {
+                    if check_add(&mut plan, 1, h.entry_id) {
+                        plan.picks.push(AmbientPick { layer: 1, entry_id: h.entry_id, score: Some(h.score),
+                            snippet: std::borrow::Cow::Owned(h.snippet) });
+                        if in_layer(&plan, 1) >= cfg.caps[1] { break; }
+                    }
+                }
+                plan.symbol_query = Some(query);
+            }
+        }
+    } else { plan.symbols_skipped = true; }
+
+    // Layer 3: Global BM25 search (stem keyword). Debugging a failing build:
+    // gotchas matching the stem or the errors instead.
+    if cfg.enabled[2] && cfg.caps[2] > 0 {
+        let global = match &cfg.build_errors {
+            None => crate::binquery::search_v2(data, stem, cfg.caps[2].saturating_add(2)).unwrap_or_default(),
+            Some(errors) => match crate::binquery::resolve_tag(data, DEBUG_TAG) {
+                Some(bit) => {
+                    let filter = crate::binquery::FilterPred { tag_mask: 1 << bit, ..crate::binquery::FilterPred::none() };
+                    let query = format!("{stem} {}", errors.join(" "));
+                    crate::binquery::search_v2_or(data, &query, &filter, cfg.caps[2].saturating_add(2)).unwrap_or_default()
+                }
+                None => Vec::new(),
+            },
+        };
+        for h in global {
+            if check_add(&mut plan, 2, h.entry_id) {
+                plan.picks.push(AmbientPick { layer: 2, entry_id: h.entry_id, score: Some(h.score),
+                    snippet: std::borrow::Cow::Owned(h.snippet) });
+                if in_layer(&plan, 2) >= cfg.caps[2] { break; }
+            }
+        }
+    }
+
+    // Layer 4: Structural coupling (architecture: noise while debugging)
+    let l4_limit = if cfg.enabled[3] && !plan.debugging { cfg.caps[3] } else { 0 };
+    let mut sq_buf = [0u8; 128];
+    let sq_prefix = b"structural ";
+    let sq_len = sq_prefix.len() + stem.len();
+    let structural = if l4_limit == 0 {
+        Vec::new()
+    } else if sq_len <= sq_buf.len() {
+        sq_buf[..sq_prefix.len()].copy_from_slice(sq_prefix);
+        sq_buf[sq_prefix.len()..sq_len].copy_from_slice(stem.as_bytes());
+        let sq = unsafe { std::str::from_utf8_unchecked(&sq_buf[..sq_len]) };
+        crate::binquery::search_v2(data, sq, l4_limit).unwrap_or_default()
+    } else {
+        let mut sq = String::with_capacity(sq_len);
+        sq.push_str("structural ");
+        sq.push_str(stem);
+        crate::binquery::search_v2(data, &sq, l4_limit).unwrap_or_default()
+    };
+    for h in structural {
+        if check_add(&mut plan, 3, h.entry_id) {
+            plan.picks.push(AmbientPick { layer: 3, entry_id: h.entry_id, score: Some(h.score),
+                snippet: std::borrow::Cow::Owned(h.snippet) });
+        }
+    }
+
+    // Layer 5: Refactor impact (Edit only)
+    let syms = if cfg.enabled[4] && cfg.caps[4] > 0 { syms } else { &[] };
+    for sym in syms {
+        let hits = crate::binquery::search_v2(data, sym, cfg.caps[4]).unwrap_or_default();
+        for hit in hits {
+            if check_add(&mut plan, 4, hit.entry_id) {
+                plan.picks.push(AmbientPick { layer: 4, entry_id: hit.entry_id, score: Some(hit.score),
+                    snippet: std::borrow::Cow::Owned(hit.snippet) });
+            }
+        }
+    }
+    plan
+}
+
+/// The additionalContext text for `plan`: one section per non-empty layer.
+pub fn render_ambient(plan: &AmbientPlan, stem: &str, file_path: &str, syms: &[&str]) -> String {
+    let filename = std::path::Path::new(file_path)
+        .file_name().and_then(|f| f.to_str()).unwrap_or(stem);
+    let est_cap = plan.picks.iter().map(|p| p.snippet.len() + 4).sum::<usize>() + 5 * 40;
+    let mut out = String::with_capacity(est_cap);
+    let mut last_layer = None;
+    for p in &plan.picks {
+        if last_layer != Some(p.layer) {
+            if !out.is_empty() { out.push_str("---\n"); }
+            match p.layer {
+                0 => { out.push_str("source-linked ("); out.push_str(filename); out.push_str("):\n"); }
+                2 if plan.debugging => out.push_str("gotchas (build failing):\n"),
+                1 if plan.symbols_truncated.is_some() => {
+                    out.push_str(AMBIENT_LAYERS[1]);
+                    out.push_str(" (truncated: commonest symbols dropped):\n");
+                }
+                2 => { out.push_str("related ("); out.push_str(stem); out.push_str("):\n"); }
+                4 => {
+                    out.push_str("REFACTOR IMPACT (symbols modified: ");
+                    for (j, sym) in syms.iter().enumerate() {
+                        if j > 0 { out.push_str(", "); }
+                        out.push_str(sym);
+                    }
+                    out.push_str("):\n");
+                }
+                i => { out.push_str(AMBIENT_LAYERS[i]); out.push_str(":\n"); }
+            }
+            last_layer = Some(p.layer);
+        }
+        out.push_str("  ");
+        out.push_str(&p.snippet);
+        out.push('\n');
+    }
+    out
+}
+
+/// `ambient_preview` tool: what the PreToolUse hook would inject for
+/// `file_path`, per layer with scores and entry addresses. Reads the session's
+/// injected set to show what dedup would hide, but changes nothing.
+pub fn ambient_preview(dir: &Path, data: &[u8], file_path: &str, syms: &[&str])
+    -> Result<String, crate::error::AmrError> {
+    use std::fmt::Write;
+    let stem = Path::new(file_path).file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    if stem.len() < 3 {
+        return Ok(format!("no injection: the hook skips files whose stem is under 3 chars ('{stem}')\n"));
+    }
+    let session = crate::session::Session::load_or_new(dir);
+    let mut cfg = AmbientConfig::load(dir);
+    cfg.build_errors = session.failing_build().map(<[String]>::to_vec);
+    let plan = plan_ambient(data, stem, file_path, syms, &cfg, Some(&session.injected));
+
+    let ids: Vec<u32> = plan.picks.iter().map(|p| p.entry_id)
+        .chain(plan.already_injected.iter().map(|&(_, e)| e)).collect();
+    let offsets: Vec<(u32, u32)> = ids.iter()
+        .filter_map(|&e| Some((e, crate::binquery::entry_log_offset(data, e).ok()?))).collect();
+    let placed = crate::address::lookup(dir, &offsets.iter().map(|&(_, o)| o).collect::<Vec<_>>());
+    let addr = |eid: u32| -> String {
+        let topic = crate::binquery::entry_topic_id(data, eid).ok()
+            .and_then(|t| crate::binquery::topic_name(data, t).ok()).unwrap_or_else(|| "?".into());
+        match offsets.iter().find(|&&(e, _)| e == eid).and_then(|(_, o)| placed.get(o)) {
+            Some((idx, _)) => format!("{topic}:{idx}"),
+            None => format!("{topic}:#{eid}"),
+        }
+    };
+
+    let mut out = format!("ambient preview: {file_path} (stem \"{stem}\")\n");
+    for (layer, label) in AMBIENT_LAYERS.iter().enumerate() {
+        if layer == 4 && syms.is_empty() { continue; }
+        let picks: Vec<&AmbientPick> = plan.picks.iter().filter(|p| p.layer == layer).collect();
+        let _ = write!(out, "\n{}. {label}: {}", layer + 1, picks.len());
+        if !cfg.enabled[layer] { out.push_str(" (disabled: ambient_layers)"); }
+        else if cfg.caps[layer] != AmbientConfig::default().caps[layer] {
+            let _ = write!(out, " (cap {}: ambient_caps)", cfg.caps[layer]);
+        }
+        if layer == 1 && cfg.enabled[1] {
+            match (&plan.symbol_query, plan.symbols_skipped) {
+                (_, true) => out.push_str(" (skipped: 5+ source-linked entries)"),
+                (Some(q), _) => { let _ = write!(out, " (query \"{q}\")"); }
+                (None, _) => out.push_str(" (no symbols read from the file)"),
+            }
+            if let Some(t) = &plan.symbols_truncated { let _ = write!(out, "\n   truncated: {t}"); }
+        }
+        if plan.debugging && cfg.enabled[layer] {
+            if layer == 2 { let _ = write!(out, " (debugging a failing build: '{DEBUG_TAG}' entries for the stem and errors)"); }
+            if layer == 3 { out.push_str(" (skipped: debugging a failing build)"); }
+        }
+        out.push('\n');
+        for p in picks {
+            let score = p.score.map_or_else(|| "source".to_string(), |s| format!("{s:.2}"));
+            let _ = writeln!(out, "  {:<20} {score:>6}  {}", addr(p.entry_id), crate::text::truncate(&p.snippet, 90));
+        }
+    }
+    if !plan.already_injected.is_empty() {
+        let hidden: Vec<String> = plan.already_injected.iter()
+            .map(|&(layer, e)| format!("{} ({})", addr(e), AMBIENT_LAYERS[layer])).collect();
+        let _ = writeln!(out, "\nalready injected this session (dedup hides them): {}", hidden.join(", "));
+    }
+    let text = render_ambient(&plan, stem, file_path, syms);
+    if text.is_empty() {
+        out.push_str("\nwould inject nothing\n");
+    } else {
+        let _ = writeln!(out, "\n--- would inject ({} lines, {}) ---", text.lines().count(),
+            crate::text::format_tokens(crate::text::approx_tokens_of(&text)));
+        out.push_str(&text);
+    }
+    Ok(out)
+}
@@ -0,0 +1,96 @@
+//! `answer`: extractive answers for simple factual lookups. Retrieves the top
+//! entries for the question with the normal search, splits their bodies into
+//! sentences, and returns the sentences that cover the most query terms —
+//! each cited by entry address — instead of whole entries. Nothing is
+//! generated: every line is quoted from an entry.
+
+use crate::error::AmrError;
+use crate::score::Filter;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Entries the sentences are drawn from.
+const SOURCE_ENTRIES: usize = 8;
+
+/// Question scaffolding that would otherwise match every sentence.
+const QUESTION_WORDS: &[&str] = &[
+    "what", "whats", "why", "how", "where", "who", "which", "when", "is", "are", "was", "do",
+    "does", "did", "can", "the", "a", "an", "of", "to", "in", "for", "on", "it", "we", "our", "i",
+];
+
+pub fn run(dir: &Path, question: &str, limit: usize, filter: &Filter,
+           index_data: Option<&[u8]>) -> Result<String, AmrError> {
+    let terms: Vec<String> = crate::text::query_terms(question).into_iter()
+        .filter(|t| !QUESTION_WORDS.contains(&t.as_str())).collect();
+    if terms.is_empty() { return Err(AmrError::InvalidArg("question has no searchable terms".into())); }
+    let (results, _) = crate::score::search_scored(dir, &terms, filter, Some(SOURCE_ENTRIES), index_data, true)?;
+    if results.is_empty() { return Ok(format!("no entries match '{question}'\n")); }
+    let offsets: Vec<u32> = results.iter().map(|r| r.offset).collect();
+    let addrs = crate::address::lookup(dir, &offsets);
+    let top = results[0].score.max(f64::EPSILON);
+
+    // (score, sentence, result index)
+    let mut picked: Vec<(f64, String, usize)> = Vec::new();
+    for (ri, r) in results.iter().enumerate() {
+        let text: Vec<&str> = r.lines.iter().map(|l| l.trim())
+            .filter(|l| !l.is_empty() && crate::entryfmt::header_date(l).is_none() && !crate::text::is_metadata_line(l))
+            .collect();
+        for s in sentences(&text.join("\n")) {
+            let mut tf = crate::fxhash::FxHashMap::default();
+            crate::text::tokenize_into_tfmap(&s, &mut tf);
+            let hits = terms.iter().filter(|t| tf.contains_key(*t)).count();
+            if hits == 0 { continue; }
+            // Term coverage dominates; the entry's own rank breaks ties
+            let score = hits as f64 / terms.len() as f64 + 0.25 * r.score / top;
+            if picked.iter().any(|(_, p, _)| p.eq_ignore_ascii_case(&s)) { continue; }
+            picked.push((score, s, ri));
+        }
+    }
+    if picked.is_empty() { return Ok(format!("no sentence in the top entries mentions '{question}'\n")); }
+    picked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    picked.truncate(limit.max(1));
+
+    let cite = |ri: usize| {
+        let r = &results[ri];
+        addrs.get(&r.offset).map_or_else(|| r.name.clone(), |&(idx, _)| format!("{}:{idx}", r.name))
+    };
+    let mut cited: Vec<usize> = Vec::new();
+    let mut out = String::new();
+    for (_, s, ri) in &picked {
+        let _ = writeln!(out, "- {} [{}]", crate::text::truncate(s, 300), cite(*ri));
+        if !cited.contains(ri) { cited.push(*ri); }
+    }
+    let _ = writeln!(out, "\nsources:");
+    for ri in cited {
+        let r = &results[ri];
+        match addrs.get(&r.offset) {
+            Some(&(idx, uid)) => { let _ = writeln!(out, "  {}", crate::address::format_both(&r.name, idx, uid)); }
+            None => { let _ = writeln!(out, "  {}", r.name); }
+        }
+    }
+    Ok(out)
+}
+
+/// Sentences of a body: split at line breaks and at `.`/`?`/`!` followed by
+/// whitespace, so `foo.rs` and `1.5` stay whole. Bullets are stripped.
+fn sentences(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for line in text.lines() {
+        let line = line.trim().trim_start_matches(['-', '*', '•']).trim();
+        let mut start = 0;
+        let b = line.as_bytes();
+        for i in 0..b.len() {
+            if matches!(b[i], b'.' | b'?' | b'!') && b.get(i + 1).is_none_or(|c| c.is_ascii_whitespace()) {
+                push(&mut out, &line[start..=i]);
+                start = i + 1;
+            }
+        }
+        push(&mut out, &line[start..]);
+    }
+    out
+}
+
+fn push(out: &mut Vec<String>, s: &str) {
+    let s = s.trim();
+    if s.split_whitespace().count() >= 3 { out.push(s.to_string()); }
+}
@@ -2,28 +2,43 @@
 //! All reads are pointer arithmetic on a &[u8] slice.
 //! v3 adds: FilterPred, recency decay, confidence, tag bitmap, diversity cap.
 
+use std::ops::ControlFlow;
 use std::sync::Mutex;
+use crate::error::AmrError;
+use crate::score::MinMatch;
 use crate::format::*;
+use crate::fxhash::FxHashMap;
 
 // --- Filter predicate: nanosecond-speed pre-scoring filter ---
 
 pub struct FilterPred {
     pub topic_id: Option<u16>,
+    /// Topic-pattern scope, indexed by topic id (glob/regex filters).
+    pub topic_set: Option<Vec<bool>>,
     pub after_days: u16,
     pub before_days: u16,
     pub tag_mask: u32,
+    /// Provenance::code to require; 0 = any.
+    pub provenance: u8,
+    /// Lang::code to require; 0 = any.
+    pub lang: u8,
 }
 
 impl FilterPred {
     pub fn none() -> Self {
-        Self { topic_id: None, after_days: 0, before_days: u16::MAX, tag_mask: 0 }
+        Self { topic_id: None, topic_set: None, after_days: 0, before_days: u16::MAX, tag_mask: 0, provenance: 0, lang: 0 }
     }
     fn passes(&self, m: &EntryMeta) -> bool {
         if let Some(t) = self.topic_id { if { m.topic_id } != t { return false; } }
+        if let Some(set) = &self.topic_set {
+            if !set.get({ m.topic_id } as usize).copied().unwrap_or(false) { return false; }
+        }
         let ed = { m.epoch_days };
         if ed < self.after_days { return false; }
         if self.before_days < u16::MAX && ed > self.before_days { return false; }
         if self.tag_mask != 0 && ({ m.tag_bitmap } & self.tag_mask) != self.tag_mask { return false; }
+        if self.provenance != 0 && { m.provenance } != self.provenance { return false; }
+        if self.lang != 0 && { m.lang } != self.lang { return false; }
         true
     }
 }
@@ -35,6 +50,13 @@ pub struct QueryState {
     entry_gen: Vec<u32>,
     scores: Vec<f64>,
     hit_count: Vec<u16>,
+    /// Query term that last counted a hit for the entry, so a prefix's
+    /// expansions count once between them.
+    last_slot: Vec<u16>,
+    /// Per-entry k1·len_norm, valid when entry_gen matches (computed on first touch).
+    knorm: Vec<f64>,
+    /// Per-entry conf·recency weight; negative = rejected by FilterPred.
+    weight: Vec<f64>,
 }
 
 impl QueryState {
@@ -44,6 +66,9 @@ impl QueryState {
             entry_gen: vec![0; num_entries],
             scores: vec![0.0; num_entries],
             hit_count: vec![0; num_entries],
+            last_slot: vec![0; num_entries],
+            knorm: vec![0.0; num_entries],
+            weight: vec![0.0; num_entries],
         }
     }
     fn ensure(&mut self, n: usize) {
@@ -51,6 +76,9 @@ impl QueryState {
             self.entry_gen.resize(n, 0);
             self.scores.resize(n, 0.0);
             self.hit_count.resize(n, 0);
+            self.last_slot.resize(n, 0);
+            self.knorm.resize(n, 0.0);
+            self.weight.resize(n, 0.0);
         }
     }
     fn advance(&mut self) -> u32 {
@@ -61,7 +89,8 @@ impl QueryState {
 }
 
 static QUERY_STATE: Mutex<QueryState> = Mutex::new(QueryState {
-    generation: 0, entry_gen: Vec::new(), scores: Vec::new(), hit_count: Vec::new(),
+    generation: 0, entry_gen: Vec::new(), scores: Vec::new(), hit_count: Vec::new(), last_slot: Vec::new(),
+    knorm: Vec::new(), weight: Vec::new(),
 });
 
 pub fn reset_query_state(num_entries: usize) {
@@ -71,7 +100,7 @@ pub fn reset_query_state(num_entries: usize) {
 // --- Formatted search (MCP path) ---
 
 /// v6.6: zero format!() — all output built with push_str + itoa.
-pub fn search(data: &[u8], query: &str, limit: usize) -> Result<String, String> {
+pub fn search(data: &[u8], query: &str, limit: usize) -> Result<String, AmrError> {
     let hits = search_v2(data, query, limit)?;
     if hits.is_empty() {
         let mut out = String::with_capacity(20 + query.len());
@@ -104,23 +133,30 @@ pub struct SearchHit {
     pub log_offset: u32,
 }
 
-pub fn search_v2(data: &[u8], query: &str, limit: usize) -> Result<Vec<SearchHit>, String> {
+pub fn search_v2(data: &[u8], query: &str, limit: usize) -> Result<Vec<SearchHit>, AmrError> {
     search_v2_filtered(data, query, &FilterPred::none(), limit)
 }
 
 /// Full-featured search with pre-scoring filter, recency, confidence,
-/// insertion sort top-K, and diversity cap.
+/// bounded-heap top-K, and diversity cap.
 pub fn search_v2_filtered(
     data: &[u8], query: &str, filter: &FilterPred, limit: usize,
-) -> Result<Vec<SearchHit>, String> {
-    search_v2_core(data, query, filter, limit, true)
+) -> Result<Vec<SearchHit>, AmrError> {
+    search_v2_core(data, query, filter, limit, MinMatch::All)
 }
 
 /// OR mode: entries matching ANY query term (not all).
 pub fn search_v2_or(
     data: &[u8], query: &str, filter: &FilterPred, limit: usize,
-) -> Result<Vec<SearchHit>, String> {
-    search_v2_core(data, query, filter, limit, false)
+) -> Result<Vec<SearchHit>, AmrError> {
+    search_v2_core(data, query, filter, limit, MinMatch::Count(1))
+}
+
+/// Minimum-should-match: entries containing at least `need` of the query terms.
+pub fn search_v2_min(
+    data: &[u8], query: &str, filter: &FilterPred, need: MinMatch, limit: usize,
+) -> Result<Vec<SearchHit>, AmrError> {
+    search_v2_core(data, query, filter, limit, need)
 }
 
 /// Lightweight heap entry for top-K selection — no snippet String allocation.
@@ -128,10 +164,12 @@ pub fn search_v2_or(
 /// v7.4: carries snippet_off/len from Phase 1 EntryMeta — avoids re-read in Phase 3.
 struct HeapHit {
     score: f64, entry_id: u32, topic_id: u16, date_minutes: i32, log_offset: u32,
-    snippet_off: u32, snippet_len: u16,
+    snippet_off: u32, snippet_len: u16, importance: u8,
 }
 impl PartialEq for HeapHit {
-    fn eq(&self, other: &Self) -> bool { self.score.to_bits() == other.score.to_bits() }
+    fn eq(&self, other: &Self) -> bool {
+        self.score.to_bits() == other.score.to_bits() && self.importance == other.importance
+    }
 }
 impl Eq for HeapHit {}
 impl PartialOrd for HeapHit {
@@ -139,98 +177,167 @@ impl PartialOrd for HeapHit {
 }
 impl Ord for HeapHit {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Equal scores: more important entry ranks first
         self.score.partial_cmp(&other.score).unwrap_or(std::cmp::Ordering::Equal)
+            .then(self.importance.cmp(&other.importance))
     }
 }
 
 fn search_v2_core(
-    data: &[u8], query: &str, filter: &FilterPred, limit: usize, require_all: bool,
-) -> Result<Vec<SearchHit>, String> {
+    data: &[u8], query: &str, filter: &FilterPred, limit: usize, need: MinMatch,
+) -> Result<Vec<SearchHit>, AmrError> {
+    let mut results: Vec<SearchHit> = Vec::with_capacity(limit.min(64));
+    visit_hits(data, query, filter, limit, need, |h| {
+        results.push(SearchHit {
+            entry_id: h.entry_id, topic_id: h.topic_id, score: h.score,
+            snippet: h.snippet.to_string(), date_minutes: h.date_minutes, log_offset: h.log_offset,
+        });
+        ControlFlow::Continue(())
+    })?;
+    Ok(results)
+}
+
+/// A ranked hit borrowing its snippet from the index bytes — no allocation.
+pub struct HitRef<'a> {
+    pub entry_id: u32,
+    pub topic_id: u16,
+    pub score: f64,
+    pub snippet: &'a str,
+    pub date_minutes: i32,
+    pub log_offset: u32,
+}
+
+/// Streaming form of search_v2_filtered/search_v2_or: ranks the top `limit`,
+/// then hands hits to `f` best-first. Returning Break stops before any further
+/// snippet work. `need`: query terms an entry must contain (`All` = AND mode).
+/// Returns the number of hits visited.
+/// The query-state lock is released before `f` runs, so `f` may search again.
+pub fn visit_hits<'a, F>(
+    data: &'a [u8], query: &str, filter: &FilterPred, limit: usize, need: MinMatch, mut f: F,
+) -> Result<usize, AmrError>
+where F: FnMut(HitRef<'a>) -> ControlFlow<()> {
+    let (ranked, snip_off) = top_k(data, query, filter, limit, need)?;
+    let mut visited = 0;
+    for h in ranked {
+        let so = snip_off + h.snippet_off as usize;
+        let sl = h.snippet_len as usize;
+        let snippet = data.get(so..so + sl)
+            .and_then(|b| std::str::from_utf8(b).ok()).unwrap_or("");
+        visited += 1;
+        let flow = f(HitRef {
+            entry_id: h.entry_id, topic_id: h.topic_id, score: h.score,
+            snippet, date_minutes: h.date_minutes, log_offset: h.log_offset,
+        });
+        if flow.is_break() { break; }
+    }
+    Ok(visited)
+}
+
+/// Phases 1–2: score postings and select the top `limit`, best-first.
+/// Returns the heap hits plus the snippet section offset for Phase 3.
+fn top_k(
+    data: &[u8], query: &str, filter: &FilterPred, limit: usize, need: MinMatch,
+) -> Result<(Vec<HeapHit>, usize), AmrError> {
     let hdr = read_header(data)?;
-    let terms = crate::text::query_terms(query);
-    if terms.is_empty() { return Err("empty query".into()); }
+    // Quoted phrases and NEAR narrow the hits below; their words score as usual
+    let parsed = crate::phrase::parse(query);
+    let terms = crate::text::query_terms(&parsed.text);
+    if terms.is_empty() { return Err(AmrError::InvalidArg("empty query".into())); }
+    let prefixes = crate::expand::prefix_terms(&parsed.text);
 
     let num_entries = { hdr.num_entries } as usize;
-    let table_cap = { hdr.table_cap } as usize;
-    let avgdl = { hdr.avgdl_x100 } as f64 / 100.0;
     let post_off = { hdr.postings_off } as usize;
     let meta_off = { hdr.meta_off } as usize;
     let snip_off = { hdr.snippet_off } as usize;
     let data_len = data.len();
     if post_off > data_len || meta_off > data_len || snip_off > data_len {
-        return Err("index.bin truncated: section offsets exceed file size".into());
+        return Err(AmrError::Corrupt("index.bin truncated: section offsets exceed file size".into()));
     }
     // Validate section end bounds — meta section must fit all entries
     let meta_end = meta_off + num_entries * std::mem::size_of::<EntryMeta>();
     if meta_end > data_len {
-        return Err(format!("index.bin truncated: meta section ends at {} but file is {} bytes", meta_end, data_len));
+        return Err(AmrError::Corrupt(format!("index.bin truncated: meta section ends at {} but file is {} bytes", meta_end, data_len)));
     }
-    let mask = table_cap - 1;
-    let num_terms = terms.len() as u16;
 
     // Recency: compute today as epoch_days
     let today_days = (crate::time::LocalTime::now().to_minutes() / 1440) as u16;
 
     // Acquire QueryState with generation counter
-    let mut state_guard = QUERY_STATE.lock().map_err(|e| e.to_string())?;
+    let mut state_guard = QUERY_STATE.lock().map_err(|e| AmrError::Locked(e.to_string()))?;
     state_guard.ensure(num_entries);
     let gen = state_guard.advance();
     let state = &mut *state_guard;
 
     // Phase 1: BM25 scoring — accumulate scores in QueryState arrays
-    let mut any_hit = false;
-    for term in &terms {
-        let h = hash_term(term);
-        let mut idx = (h as usize) & mask;
-        for _ in 0..table_cap {
-            let slot = read_slot(data, idx)?;
-            let sh = { slot.hash };
-            if sh == 0 { break; }
-            if sh == h {
-                let p_off = { slot.postings_off } as usize;
-                let p_len = { slot.postings_len } as usize;
-                let base = post_off + p_off * std::mem::size_of::<Posting>();
-                // Validate postings range once, then use unchecked reads in inner loop.
-                let post_end = base + p_len * std::mem::size_of::<Posting>();
-                if post_end > data_len { break; }
-                let meta_size = std::mem::size_of::<EntryMeta>();
-                for i in 0..p_len {
-                    // Safety: post_end validated above, meta_end validated at line 164.
-                    let p: Posting = unsafe { read_at_unchecked(data, base + i * std::mem::size_of::<Posting>()) };
-                    let eid = { p.entry_id } as usize;
-                    if eid >= num_entries { continue; }
-                    let m: EntryMeta = unsafe { read_at_unchecked(data, meta_off + eid * meta_size) };
-
-                    if !filter.passes(&m) { continue; }
-
-                    if state.entry_gen[eid] != gen {
-                        state.scores[eid] = 0.0;
-                        state.hit_count[eid] = 0;
-                        state.entry_gen[eid] = gen;
-                    }
+    // k1·len_norm = k_base + k_slope[topic]·doc_len — folds the per-topic avgdl
+    // division out of the loop
+    let k_base = 1.2 * (1.0 - 0.75);
+    let k_slope: Vec<f64> = topic_avgdls(data)?.into_iter().map(|a| 1.2 * 0.75 / a.max(1.0)).collect();
+    // Postings lists (base, len) per term — a prefix's expansions, a typo's
+    // nearest term — so the cost cap can drop the commonest terms before any
+    // are visited
+    let slots = crate::expand::resolve_terms(data, &hdr, &terms, &prefixes, matches!(need, MinMatch::All))?;
+    let lens: Vec<Option<usize>> = slots.iter()
+        .map(|s| (!s.is_empty()).then(|| s.iter().map(|l| l.1).sum())).collect();
+    // Postings of every phrase/NEAR word, for the position checks in Phase 2;
+    // query terms bring their prefix/typo resolution along
+    let mut phrase_lists: FxHashMap<&str, Vec<(usize, usize)>> = FxHashMap::default();
+    for t in parsed.constraints.iter().flat_map(|c| c.terms()) {
+        if phrase_lists.contains_key(t.as_str()) { continue; }
+        let lists = match terms.iter().position(|q| q == t) {
+            Some(i) => slots[i].clone(),
+            None => postings_of(data, &hdr, t)?.into_iter().collect(),
+        };
+        phrase_lists.insert(t, lists);
+    }
+    let keep = crate::costcap::apply(&terms, &lens);
+    let min_hits = need.required(keep.iter().filter(|k| **k).count()) as u16;
 
-                    let doc_len = { m.word_count } as f64;
-                    let idf = { p.idf_x1000 } as f64 / 1000.0;
-                    let tf = { p.tf } as f64;
-                    let len_norm = 1.0 - 0.75 + 0.75 * doc_len / avgdl.max(1.0);
-                    let tf_sat = (tf * 2.2) / (tf + 1.2 * len_norm);
-                    let conf = { m.confidence } as f64 / 255.0;
+    let mut any_hit = false;
+    let kept = slots.iter().enumerate().zip(&keep).filter(|(_, k)| **k)
+        .flat_map(|((s, lists), _)| lists.iter().map(move |&l| (s as u16, l)));
+    for (slot, (base, p_len)) in kept {
+        // Validate postings range once, then use unchecked reads in inner loop.
+        let post_end = base + p_len * std::mem::size_of::<Posting>();
+        if post_end > data_len { continue; }
+        let meta_size = std::mem::size_of::<EntryMeta>();
+        for i in 0..p_len {
+            // Safety: post_end validated above, meta_end validated at header check.
+            let p: Posting = unsafe { read_at_unchecked(data, base + i * std::mem::size_of::<Posting>()) };
+            let eid = { p.entry_id } as usize;
+            if eid >= num_entries { continue; }
+            // First touch this query: one meta read → per-entry length norm
+            // and conf·recency weight, reused by every later term hitting eid.
+            if state.entry_gen[eid] != gen {
+                let m: EntryMeta = unsafe { read_at_unchecked(data, meta_off + eid * meta_size) };
+                state.entry_gen[eid] = gen;
+                state.scores[eid] = 0.0;
+                state.hit_count[eid] = 0;
+                state.last_slot[eid] = u16::MAX;
+                state.weight[eid] = if filter.passes(&m) {
+                    let conf = { m.confidence } as f64 * (1.0 / 255.0);
                     let ed = { m.epoch_days };
                     let recency = if ed == 0 { 1.0 } else {
-                        1.0 / (1.0 + today_days.saturating_sub(ed) as f64 / 30.0)
+                        30.0 / (30.0 + today_days.saturating_sub(ed) as f64)
                     };
-
-                    state.scores[eid] += idf * tf_sat * conf * recency;
-                    state.hit_count[eid] += 1;
-                    any_hit = true;
-                }
-                break;
+                    conf * recency
+                } else { -1.0 };
+                let slope = k_slope.get({ m.topic_id } as usize).copied().unwrap_or(0.0);
+                state.knorm[eid] = k_base + slope * { m.word_count } as f64;
             }
-            idx = (idx + 1) & mask;
+            let w = state.weight[eid];
+            if w < 0.0 { continue; } // filtered out
+            let tf = { p.tf } as f64;
+            let idf = { p.idf_x1000 } as f64 / 1000.0;
+            state.scores[eid] += idf * (tf * 2.2) / (tf + state.knorm[eid]) * w;
+            // One hit per query term, however many of its postings lists match
+            state.hit_count[eid] += u16::from(state.last_slot[eid] != slot);
+            state.last_slot[eid] = slot;
+            any_hit = true;
         }
     }
 
-    if !any_hit { return Ok(Vec::new()); }
+    if !any_hit { return Ok((Vec::new(), snip_off)); }
 
     // Phase 2: Top-K selection — lightweight HeapHit, NO snippet allocation
     use std::collections::BinaryHeap;
@@ -241,11 +348,13 @@ fn search_v2_core(
 
     for eid in 0..num_entries {
         if state.entry_gen[eid] != gen { continue; }
-        let min_hits = if require_all { num_terms } else { 1 };
         if state.hit_count[eid] < min_hits { continue; }
 
         let score = state.scores[eid];
         if score <= 0.0 { continue; }
+        if !parsed.constraints.is_empty() && !phrases_hold(data, &hdr, &parsed.constraints, &phrase_lists, eid as u32) { continue; }
+        // Full heap + not above its floor → can never be admitted; skip the meta read.
+        if heap.len() >= limit && score <= heap.peek().map(|r| r.0.score).unwrap_or(0.0) { continue; }
 
         let m = read_at::<EntryMeta>(data, meta_off + eid * std::mem::size_of::<EntryMeta>())?;
         let tid = { m.topic_id } as usize;
@@ -258,7 +367,7 @@ fn search_v2_core(
         let hit = HeapHit {
             score, entry_id: eid as u32, topic_id: { m.topic_id },
             date_minutes: { m.date_minutes }, log_offset: { m.log_offset },
-            snippet_off: { m.snippet_off }, snippet_len: { m.snippet_len },
+            snippet_off: { m.snippet_off }, snippet_len: { m.snippet_len }, importance: { m.importance },
         };
 
         if heap.len() < limit {
@@ -273,23 +382,87 @@ fn search_v2_core(
         }
     }
 
-    // Phase 3: Extract snippets ONLY for final K entries — deferred allocation
+    // Phase 3 (snippet extraction) is deferred to the caller — only for hits it visits.
     // v7.4: snippet_off/len cached in HeapHit from Phase 2 — no EntryMeta re-read.
-    let mut results: Vec<SearchHit> = Vec::with_capacity(heap.len());
-    for r in heap.into_vec() {
-        let h = r.0;
-        let so = snip_off + h.snippet_off as usize;
-        let sl = h.snippet_len as usize;
-        let snippet = if so + sl <= data_len {
-            std::str::from_utf8(&data[so..so + sl]).unwrap_or("").to_string()
-        } else { String::new() };
-        results.push(SearchHit {
-            entry_id: h.entry_id, topic_id: h.topic_id, score: h.score,
-            snippet, date_minutes: h.date_minutes, log_offset: h.log_offset,
-        });
+    let mut ranked: Vec<HeapHit> = heap.into_vec().into_iter().map(|r| r.0).collect();
+    ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    Ok((ranked, snip_off))
+}
+
+/// (byte offset, length) of `term`'s postings list, None if not indexed.
+pub(crate) fn postings_of(data: &[u8], hdr: &Header, term: &str) -> Result<Option<(usize, usize)>, AmrError> {
+    let table_cap = { hdr.table_cap } as usize;
+    let post_off = { hdr.postings_off } as usize;
+    let h = hash_term(term);
+    let mut idx = (h as usize) & (table_cap - 1);
+    for _ in 0..table_cap {
+        let slot = read_slot(data, idx)?;
+        let sh = { slot.hash };
+        if sh == 0 { break; }
+        if sh == h {
+            let p_off = { slot.postings_off } as usize;
+            return Ok(Some((post_off + p_off * std::mem::size_of::<Posting>(), { slot.postings_len } as usize)));
+        }
+        idx = (idx + 1) & (table_cap - 1);
     }
-    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-    Ok(results)
+    Ok(None)
+}
+
+/// Whether entry `eid` satisfies every phrase/NEAR constraint, reading each
+/// word's positions from its postings for `eid` (sorted by entry, so binary
+/// searched); a prefix's expansions pool their positions.
+fn phrases_hold(data: &[u8], hdr: &Header, constraints: &[crate::phrase::Constraint],
+                lists: &FxHashMap<&str, Vec<(usize, usize)>>, eid: u32) -> bool {
+    let pos_base = { hdr.positions_off } as usize;
+    let size = std::mem::size_of::<Posting>();
+    let mut cache: FxHashMap<&str, Vec<u16>> = FxHashMap::default();
+    for t in constraints.iter().flat_map(|c| c.terms()) {
+        if cache.contains_key(t.as_str()) { continue; }
+        let mut found = Vec::new();
+        for &(base, len) in lists.get(t.as_str()).map_or(&[][..], |l| l.as_slice()) {
+            let (mut lo, mut hi) = (0usize, len);
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                let Ok(p) = read_at::<Posting>(data, base + mid * size) else { break };
+                match { p.entry_id }.cmp(&eid) {
+                    std::cmp::Ordering::Less => lo = mid + 1,
+                    std::cmp::Ordering::Greater => hi = mid,
+                    std::cmp::Ordering::Equal => {
+                        let at = pos_base + { p.pos_off } as usize * 2;
+                        if let Some(b) = data.get(at..at + { p.pos_len } as usize * 2) {
+                            found.extend(b.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])));
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        cache.insert(t, found);
+    }
+    let positions = |t: &str| -> &[u16] { cache.get(t).map_or(&[][..], |v| v.as_slice()) };
+    constraints.iter().all(|c| c.holds(&positions))
+}
+
+/// Document frequency + stored IDF for a single term. None if term not indexed.
+pub fn term_stats(data: &[u8], term: &str) -> Option<(u32, f64)> {
+    let hdr = read_header(data).ok()?;
+    let table_cap = { hdr.table_cap } as usize;
+    let post_off = { hdr.postings_off } as usize;
+    let h = hash_term(term);
+    let mut idx = (h as usize) & (table_cap - 1);
+    for _ in 0..table_cap {
+        let slot = read_slot(data, idx).ok()?;
+        let sh = { slot.hash };
+        if sh == 0 { return None; }
+        if sh == h {
+            let p_off = { slot.postings_off } as usize;
+            let first = read_at::<Posting>(data, post_off + p_off * std::mem::size_of::<Posting>()).ok()?;
+            return Some(({ slot.postings_len }, { first.idf_x1000 } as f64 / 1000.0));
+        }
+        idx = (idx + 1) & (table_cap - 1);
+    }
+    None
 }
 
 // --- Tag resolution ---
@@ -315,15 +488,39 @@ pub fn resolve_tag(data: &[u8], tag_name: &str) -> Option<u8> {
     None
 }
 
-/// Resolve topic name to topic_id for FilterPred.
-pub fn resolve_topic(data: &[u8], topic_name: &str) -> Option<u16> {
+/// Resolve a topic name or UID (`#` + 16 hex digits) to topic_id for FilterPred.
+pub fn resolve_topic(data: &[u8], topic: &str) -> Option<u16> {
+    if let Some(uid) = crate::topicuid::parse(topic) { return crate::topicuid::topic_by_uid(data, uid).map(|(id, _)| id); }
     let topics = topic_table(data).ok()?;
-    topics.iter().find(|(_, n, _)| n == topic_name).map(|(id, _, _)| *id)
+    topics.iter().find(|(_, n, _)| n == topic).map(|(id, _, _)| *id)
+}
+
+/// Cohort avgdl of each topic (score::cohort_avgdl), indexed by topic_id.
+pub fn topic_avgdls(data: &[u8]) -> Result<Vec<f64>, AmrError> {
+    let hdr = read_header(data)?;
+    let top_off = { hdr.topics_off } as usize;
+    (0..{ hdr.num_topics } as usize)
+        .map(|i| read_at::<TopicEntry>(data, top_off + i * std::mem::size_of::<TopicEntry>())
+            .map(|te| { te.avgdl_x100 } as f64 / 100.0))
+        .collect()
+}
+
+/// Total tokenizer words per topic_id (sum of EntryMeta.word_count).
+pub fn topic_words(data: &[u8]) -> Result<Vec<usize>, AmrError> {
+    let hdr = read_header(data)?;
+    let meta_off = { hdr.meta_off } as usize;
+    let mut out = vec![0usize; { hdr.num_topics } as usize];
+    let n = { hdr.num_entries } as usize;
+    for i in 0..n {
+        let m = read_at::<EntryMeta>(data, meta_off + i * std::mem::size_of::<EntryMeta>())?;
+        if let Some(w) = out.get_mut({ m.topic_id } as usize) { *w += { m.word_count } as usize; }
+    }
+    Ok(out)
 }
 
 // --- V3 section readers ---
 
-pub fn topic_table(data: &[u8]) -> Result<Vec<(u16, String, u16)>, String> {
+pub fn topic_table(data: &[u8]) -> Result<Vec<(u16, String, u16)>, AmrError> {
     let hdr = read_header(data)?;
     let top_off = { hdr.topics_off } as usize;
     let tname_off = { hdr.topic_names_off } as usize;
@@ -342,20 +539,20 @@ pub fn topic_table(data: &[u8]) -> Result<Vec<(u16, String, u16)>, String> {
 }
 
 #[inline]
-pub fn topic_name(data: &[u8], topic_id: u16) -> Result<String, String> {
+pub fn topic_name(data: &[u8], topic_id: u16) -> Result<String, AmrError> {
     let hdr = read_header(data)?;
     let top_off = { hdr.topics_off } as usize;
     let tname_off = { hdr.topic_names_off } as usize;
     let n = { hdr.num_topics } as usize;
-    if topic_id as usize >= n { return Err("topic_id out of range".into()); }
+    if topic_id as usize >= n { return Err(AmrError::NotFound("topic_id out of range".into())); }
     let te = read_at::<TopicEntry>(data, top_off + topic_id as usize * std::mem::size_of::<TopicEntry>())?;
     let no = tname_off + { te.name_off } as usize;
     let nl = { te.name_len } as usize;
-    if no + nl > data.len() { return Err("name out of bounds".into()); }
+    if no + nl > data.len() { return Err(AmrError::Corrupt("name out of bounds".into())); }
     Ok(std::str::from_utf8(&data[no..no + nl]).unwrap_or("?").to_string())
 }
 
-pub fn xref_edges(data: &[u8]) -> Result<Vec<(u16, u16, u16)>, String> {
+pub fn xref_edges(data: &[u8]) -> Result<Vec<(u16, u16, u16)>, AmrError> {
     let hdr = read_header(data)?;
     let off = { hdr.xref_off } as usize;
     let n = { hdr.num_xrefs } as usize;
@@ -377,7 +574,7 @@ pub struct SourcedHit {
 /// Find entry IDs whose [source:] path contains the given filename.
 /// O(N) scan over EntryMeta, byte-level substring match on source pool.
 /// Zero allocation for non-matching entries.
-pub fn source_entries_for_file(data: &[u8], filename: &str) -> Result<Vec<u32>, String> {
+pub fn source_entries_for_file(data: &[u8], filename: &str) -> Result<Vec<u32>, AmrError> {
     let hdr = read_header(data)?;
     let meta_off = { hdr.meta_off } as usize;
     let src_off = { hdr.source_off } as usize;
@@ -398,7 +595,7 @@ pub fn source_entries_for_file(data: &[u8], filename: &str) -> Result<Vec<u32>,
     Ok(matches)
 }
 
-pub fn sourced_entries(data: &[u8]) -> Result<Vec<SourcedHit>, String> {
+pub fn sourced_entries(data: &[u8]) -> Result<Vec<SourcedHit>, AmrError> {
     let hdr = read_header(data)?;
     let meta_off = { hdr.meta_off } as usize;
     let src_off = { hdr.source_off } as usize;
@@ -421,11 +618,11 @@ pub fn sourced_entries(data: &[u8]) -> Result<Vec<SourcedHit>, String> {
 
 /// Reconstruct "[tags: x, y, z]" line from tag_bitmap + tag names section.
 /// Returns None if no tags set. Zero data.log I/O.
-pub fn reconstruct_tags(data: &[u8], entry_id: u32) -> Result<Option<String>, String> {
+pub fn reconstruct_tags(data: &[u8], entry_id: u32) -> Result<Option<String>, AmrError> {
     let hdr = read_header(data)?;
     let meta_off = { hdr.meta_off } as usize;
     let n = { hdr.num_entries } as usize;
-    if entry_id as usize >= n { return Err("entry_id out of range".into()); }
+    if entry_id as usize >= n { return Err(AmrError::NotFound("entry_id out of range".into())); }
     let m = read_at::<EntryMeta>(data, meta_off + entry_id as usize * std::mem::size_of::<EntryMeta>())?;
     let bitmap = { m.tag_bitmap };
     if bitmap == 0 { return Ok(None); }
@@ -446,8 +643,13 @@ pub fn reconstruct_tags(data: &[u8], entry_id: u32) -> Result<Option<String>, St
     Ok(Some(out))
 }
 
+/// Tag names in bit order: name i is bit i of EntryMeta.tag_bitmap.
+pub fn tag_names(data: &[u8]) -> Result<Vec<String>, AmrError> {
+    read_tag_names(data, &read_header(data)?)
+}
+
 /// Read all tag names from the tag_names section.
-fn read_tag_names(data: &[u8], hdr: &Header) -> Result<Vec<String>, String> {
+fn read_tag_names(data: &[u8], hdr: &Header) -> Result<Vec<String>, AmrError> {
     let off = { hdr.tag_names_off } as usize;
     if off >= data.len() { return Ok(Vec::new()); }
     let count = data[off] as usize;
@@ -465,18 +667,18 @@ fn read_tag_names(data: &[u8], hdr: &Header) -> Result<Vec<String>, String> {
 }
 
 /// Read snippet string for an entry directly from index.
-pub fn entry_snippet(data: &[u8], entry_id: u32) -> Result<String, String> {
+pub fn entry_snippet(data: &[u8], entry_id: u32) -> Result<String, AmrError> {
     entry_snippet_ref(data, entry_id).map(|s| s.to_string())
 }
 
 /// Borrow snippet directly from index data — zero allocation.
 /// Use when the caller doesn't need ownership (e.g. hook mmap path).
-pub fn entry_snippet_ref(data: &[u8], entry_id: u32) -> Result<&str, String> {
+pub fn entry_snippet_ref(data: &[u8], entry_id: u32) -> Result<&str, AmrError> {
     let hdr = read_header(data)?;
     let meta_off = { hdr.meta_off } as usize;
     let snip_off = { hdr.snippet_off } as usize;
     let n = { hdr.num_entries } as usize;
-    if entry_id as usize >= n { return Err("entry_id out of range".into()); }
+    if entry_id as usize >= n { return Err(AmrError::NotFound("entry_id out of range".into())); }
     let m = read_at::<EntryMeta>(data, meta_off + entry_id as usize * std::mem::size_of::<EntryMeta>())?;
     let s_off = snip_off + { m.snippet_off } as usize;
     let s_len = { m.snippet_len } as usize;
@@ -485,25 +687,25 @@ pub fn entry_snippet_ref(data: &[u8], entry_id: u32) -> Result<&str, String> {
 }
 
 /// Get topic_id for an entry. O(1) — single EntryMeta read.
-pub fn entry_topic_id(data: &[u8], entry_id: u32) -> Result<u16, String> {
+pub fn entry_topic_id(data: &[u8], entry_id: u32) -> Result<u16, AmrError> {
     let hdr = read_header(data)?;
     let meta_off = { hdr.meta_off } as usize;
     let n = { hdr.num_entries } as usize;
-    if entry_id as usize >= n { return Err("entry_id out of range".into()); }
+    if entry_id as usize >= n { return Err(AmrError::NotFound("entry_id out of range".into())); }
     let m = read_at::<EntryMeta>(data, meta_off + entry_id as usize * std::mem::size_of::<EntryMeta>())?;
     Ok(m.topic_id)
 }
 
-pub fn entry_log_offset(data: &[u8], entry_id: u32) -> Result<u32, String> {
+pub fn entry_log_offset(data: &[u8], entry_id: u32) -> Result<u32, AmrError> {
     let hdr = read_header(data)?;
     let meta_off = { hdr.meta_off } as usize;
     let n = { hdr.num_entries } as usize;
-    if entry_id as usize >= n { return Err("entry_id out of range".into()); }
+    if entry_id as usize >= n { return Err(AmrError::NotFound("entry_id out of range".into())); }
     let m = read_at::<EntryMeta>(data, meta_off + entry_id as usize * std::mem::size_of::<EntryMeta>())?;
     Ok(m.log_offset)
 }
 
-pub fn entries_for_topic(data: &[u8], topic_id: u16) -> Result<Vec<u32>, String> {
+pub fn entries_for_topic(data: &[u8], topic_id: u16) -> Result<Vec<u32>, AmrError> {
     let hdr = read_header(data)?;
     let meta_off = { hdr.meta_off } as usize;
     let n = { hdr.num_entries } as usize;
@@ -518,25 +720,25 @@ pub fn entries_for_topic(data: &[u8], topic_id: u16) -> Result<Vec<u32>, String>
     Ok(entries.into_iter().map(|(id, _)| id).collect())
 }
 
-pub fn find_topic_id(data: &[u8], name: &str) -> Result<u16, String> {
+pub fn find_topic_id(data: &[u8], name: &str) -> Result<u16, AmrError> {
     let topics = topic_table(data)?;
     topics.iter().find(|(_, n, _)| n == name)
         .map(|(id, _, _)| *id)
-        .ok_or_else(|| format!("topic '{}' not found in index", name))
+        .ok_or_else(|| AmrError::NotFound(format!("topic '{}' not found in index", name)))
 }
 
-pub fn index_version(data: &[u8]) -> Result<u32, String> {
-    if data.len() < 8 { return Err("too small".into()); }
+pub fn index_version(data: &[u8]) -> Result<u32, AmrError> {
+    if data.len() < 8 { return Err(AmrError::Corrupt("too small".into())); }
     let v = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
     Ok(v)
 }
 
-pub fn entry_count(data: &[u8]) -> Result<usize, String> {
+pub fn entry_count(data: &[u8]) -> Result<usize, AmrError> {
     let hdr = read_header(data)?;
     Ok({ hdr.num_entries } as usize)
 }
 
-pub fn index_info(data: &[u8]) -> Result<String, String> {
+pub fn index_info(data: &[u8]) -> Result<String, AmrError> {
     let hdr = read_header(data)?;
     let ne = { hdr.num_entries };
     let nt = { hdr.num_terms };
@@ -551,22 +753,22 @@ pub fn index_info(data: &[u8]) -> Result<String, String> {
 
 // --- Low-level readers (pub for cffi.rs) ---
 
-pub fn read_header(data: &[u8]) -> Result<Header, String> {
-    if data.len() < std::mem::size_of::<Header>() { return Err("index too small".into()); }
+pub fn read_header(data: &[u8]) -> Result<Header, AmrError> {
+    if data.len() < std::mem::size_of::<Header>() { return Err(AmrError::Corrupt("index too small".into())); }
     let hdr: Header = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const Header) };
-    if hdr.magic != MAGIC { return Err("bad index magic".into()); }
+    if hdr.magic != MAGIC { return Err(AmrError::Corrupt("bad index magic".into())); }
     let v = { hdr.version };
-    if v != VERSION { return Err(format!("index version {v} != {VERSION} — run reindex")); }
+    if v != VERSION { return Err(AmrError::Corrupt(format!("index version {v} != {VERSION} — run reindex"))); }
     Ok(hdr)
 }
 
-pub fn read_slot(data: &[u8], idx: usize) -> Result<TermSlot, String> {
+pub fn read_slot(data: &[u8], idx: usize) -> Result<TermSlot, AmrError> {
     let off = std::mem::size_of::<Header>() + idx * std::mem::size_of::<TermSlot>();
     read_at::<TermSlot>(data, off)
 }
 
-pub fn read_at<T: Copy>(data: &[u8], off: usize) -> Result<T, String> {
-    if off + std::mem::size_of::<T>() > data.len() { return Err("read out of bounds".into()); }
+pub fn read_at<T: Copy>(data: &[u8], off: usize) -> Result<T, AmrError> {
+    if off + std::mem::size_of::<T>() > data.len() { return Err(AmrError::Corrupt("read out of bounds".into())); }
     Ok(unsafe { std::ptr::read_unaligned(data.as_ptr().add(off) as *const T) })
 }
 
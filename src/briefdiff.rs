@@ -0,0 +1,127 @@
+//! `brief diff=true`: what changed since the last briefing for the same query.
+//! Every diff run records a fingerprint per entry — its category, one-liner and
+//! whether its source was stale — in `<dir>/brief.fp`. The next run for that
+//! query shows only entries that are new, whose category or one-liner changed,
+//! or whose source went stale since, so a repeated session-start briefing costs
+//! a few lines instead of the whole picture. The first run records a baseline.
+//!
+//! Entries are keyed by topic and timestamp, which edits keep, so an edited
+//! entry reads as changed rather than as one removed and one new. Entries that
+//! share a minute are paired by identical one-liners first.
+
+use crate::compress::{Compressed, first_content};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Most items listed per section.
+const SECTION_MAX: usize = 30;
+
+pub fn state_path(dir: &Path) -> PathBuf { dir.join("brief.fp") }
+
+/// One entry as last briefed.
+struct Print { key: String, category: String, stale: bool, line: String }
+
+pub fn render(dir: &Path, query: &str, entries: &[Compressed], focus: Option<&[String]>) -> String {
+    let qkey = query.trim().to_lowercase().replace('\t', " ");
+    let now = crate::time::LocalTime::now().to_minutes() as i32;
+    let cats = crate::briefing::category_names(entries);
+    let git = crate::git::enabled(dir);
+    let current: Vec<Print> = entries.iter().zip(cats).map(|(e, category)| {
+        let key = format!("{}@{}", e.topic, e.date);
+        let stale = e.source.as_deref().is_some_and(|src| {
+            let path = src.rsplit_once(':').filter(|(_, l)| l.parse::<usize>().is_ok()).map_or(src, |(p, _)| p);
+            let commit = crate::text::extract_commit(&e.body).filter(|_| git);
+            crate::config::check_staleness(dir, path, &e.date, commit).is_some()
+        });
+        // The chain note carries entries compression folded in, so a new one
+        // shows up as a change to the entry it was folded into
+        let chain = e.chain.as_deref().map(|c| format!(" ({})", crate::text::truncate(c, 80))).unwrap_or_default();
+        let line = format!("{}{chain}", crate::text::truncate(first_content(&e.body).trim(), 100)).replace('\t', " ");
+        Print { key, category, stale, line }
+    }).collect();
+
+    let text = std::fs::read_to_string(state_path(dir)).unwrap_or_default();
+    let mut others = String::new();
+    let mut last_run: Option<i32> = None;
+    let mut previous: crate::fxhash::FxHashMap<&str, Vec<Print>> = crate::fxhash::FxHashMap::default();
+    for l in text.lines() {
+        let f: Vec<&str> = l.splitn(5, '\t').collect();
+        if f.first() != Some(&qkey.as_str()) {
+            if !l.is_empty() { let _ = writeln!(others, "{l}"); }
+            continue;
+        }
+        match f.as_slice() {
+            [_, "@", ts] => last_run = ts.parse().ok(),
+            [_, key, cat, stale, line] => {
+                previous.entry(key).or_default().push(Print { key: key.to_string(), category: cat.to_string(),
+                    stale: *stale == "1", line: line.to_string() });
+            }
+            _ => {}
+        }
+    }
+
+    let in_focus = |p: &Print| focus.is_none_or(|f| f.iter().any(|c| p.category.contains(c.as_str()) || c.contains(&p.category)));
+    // Pair each entry with its previous print: an identical one first, then any
+    // left under the same key (an edit); none left → new
+    let mut was: Vec<Option<Print>> = current.iter().map(|p| {
+        let pool = previous.get_mut(p.key.as_str())?;
+        let at = pool.iter().position(|o| o.category == p.category && o.line == p.line)?;
+        Some(pool.swap_remove(at))
+    }).collect();
+    for (i, p) in current.iter().enumerate() {
+        if was[i].is_none() { was[i] = previous.get_mut(p.key.as_str()).and_then(Vec::pop); }
+    }
+    let gone: usize = previous.values().map(Vec::len).sum();
+    let (mut new, mut changed, mut stale) = (Vec::new(), Vec::new(), Vec::new());
+    let mut unchanged = 0usize;
+    for (i, p) in current.iter().enumerate() {
+        if !in_focus(p) { continue; }
+        match &was[i] {
+            None => new.push(i),
+            Some(old) if old.category != p.category || old.line != p.line => changed.push(i),
+            Some(old) if p.stale && !old.stale => stale.push(i),
+            Some(_) => unchanged += 1,
+        }
+    }
+
+    let mut out = String::new();
+    let since = last_run.map_or("first run".into(), |m| format!("since {}", crate::time::minutes_to_date_str(m)));
+    if last_run.is_none() {
+        let _ = writeln!(out, "=== BRIEF DIFF: {} === first run, baseline recorded ({} entries)",
+            query.to_uppercase(), new.len());
+    } else if new.is_empty() && changed.is_empty() && stale.is_empty() {
+        let _ = writeln!(out, "=== BRIEF DIFF: {} === nothing new {since} ({unchanged} unchanged{})",
+            query.to_uppercase(), if gone > 0 { format!(", {gone} gone") } else { String::new() });
+    } else {
+        let _ = writeln!(out, "=== BRIEF DIFF: {} === {since}: {} new, {} changed, {} newly stale ({unchanged} unchanged{})",
+            query.to_uppercase(), new.len(), changed.len(), stale.len(),
+            if gone > 0 { format!(", {gone} gone") } else { String::new() });
+    }
+    let item = |out: &mut String, i: usize| {
+        let (e, p) = (&entries[i], &current[i]);
+        let src = e.source.as_deref().filter(|_| p.stale).map(|s| format!(" \u{2192} {s}")).unwrap_or_default();
+        let _ = writeln!(out, "  [{}] {}: {}{src}", e.addr(), p.category, p.line);
+    };
+    for (title, group) in [("NEW", &new), ("CHANGED", &changed), ("NEWLY STALE", &stale)] {
+        if group.is_empty() { continue; }
+        let _ = writeln!(out, "\n{title}");
+        for &i in group.iter().take(SECTION_MAX) {
+            item(&mut out, i);
+            if title == "CHANGED" {
+                if let Some(old) = &was[i] {
+                    let _ = writeln!(out, "    was {}: {}", old.category, old.line);
+                }
+            }
+        }
+        if group.len() > SECTION_MAX { let _ = writeln!(out, "  ...{} more", group.len() - SECTION_MAX); }
+    }
+
+    let _ = writeln!(others, "{qkey}\t@\t{now}");
+    for p in &current {
+        let _ = writeln!(others, "{qkey}\t{}\t{}\t{}\t{}", p.key, p.category, if p.stale { 1 } else { 0 }, p.line);
+    }
+    if let Err(e) = crate::config::atomic_write(&state_path(dir), &others) {
+        let _ = writeln!(out, "(fingerprints not saved: {e})");
+    }
+    out
+}
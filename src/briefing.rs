@@ -9,14 +9,32 @@ use crate::compress::{Compressed, first_content};
 
 pub enum Detail { Summary, Scan, Full }
 
+/// What a briefing covers and how it is headed, shared by every detail level.
+#[derive(Clone, Copy)]
+pub struct View<'a> {
+    pub query: &'a str,
+    /// Entries before compression, for the header and STATS.
+    pub raw_count: usize,
+    pub primary: &'a [String],
+    pub since: Option<u64>,
+    /// Categories to keep (scan and full).
+    pub focus: Option<&'a [String]>,
+    /// Structural gaps from stored call graphs (see `code_gaps`), listed
+    /// under GAPS after the missing core tags in summary and full.
+    pub code_gaps: &'a [String],
+}
+
 impl Detail {
     pub fn from_str(s: &str) -> Self {
         match s { "scan" => Detail::Scan, "full" => Detail::Full, _ => Detail::Summary }
     }
 }
 
+/// Tags that file an entry under ARCHITECTURE.
+pub const ARCHITECTURE_TAGS: &[&str] = &["architecture", "module-map", "overview", "dependency-graph"];
+
 const CATEGORIES: &[(&str, &[&str])] = &[
-    ("ARCHITECTURE", &["architecture", "module-map", "overview", "dependency-graph"]),
+    ("ARCHITECTURE", ARCHITECTURE_TAGS),
     ("DATA FLOW", &["pipeline", "data-flow"]),
     ("INVARIANTS", &["invariant", "constraint", "limit"]),
     ("CHANGE IMPACT", &["change-impact"]),
@@ -152,28 +170,69 @@ fn classify(entries: &[Compressed]) -> Classification {
     Classification { structural, categories, dynamic, untagged }
 }
 
+/// Category each entry is filed under, as headed in the briefing (dynamic
+/// tag categories upper-cased; raw data that isn't structural → RAW DATA).
+pub fn category_names(entries: &[Compressed]) -> Vec<String> {
+    let cls = classify(entries);
+    let mut names = vec!["RAW DATA".to_string(); entries.len()];
+    for &i in &cls.structural { names[i] = "STRUCTURAL".into(); }
+    for (cat, group) in &cls.categories {
+        for &i in group { names[i] = cat.to_string(); }
+    }
+    for (tag, group) in &cls.dynamic {
+        for &i in group { names[i] = tag.to_uppercase(); }
+    }
+    for &i in &cls.untagged { names[i] = "UNTAGGED".into(); }
+    names
+}
+
 // --- Public entry point ---
 
-pub fn format(entries: &[Compressed], query: &str, raw_count: usize,
-              primary: &[String], detail: Detail, since: Option<u64>,
-              focus: Option<&[String]>) -> String {
+pub fn format(entries: &[Compressed], detail: Detail, view: &View) -> String {
     match detail {
-        Detail::Summary => format_summary(entries, query, raw_count, primary, since),
+        Detail::Summary => format_summary(entries, view),
         Detail::Scan => {
             let cls = classify(entries);
-            format_scan_filtered(entries, query, raw_count, primary, since, &cls, focus)
+            format_scan_filtered(entries, view, &cls)
         }
         Detail::Full => {
             let cls = classify(entries);
-            format_full_filtered(entries, query, raw_count, primary, since, &cls, focus)
+            format_full_filtered(entries, view, &cls)
         }
     }
 }
 
+/// HUB and CORE modules (reverse.rs thresholds) in the stored graph of each
+/// primary topic that no architecture-tagged entry covers — by `[source:]` or
+/// by naming the file — each with a store command to fill it. `covered` holds
+/// (source, body) of every architecture entry in the corpus.
+pub fn code_gaps(dir: &std::path::Path, primary: &[String], covered: &[(Option<&str>, &str)]) -> Vec<String> {
+    let graphs = crate::tracegraph::topics(dir);
+    let mut out = Vec::new();
+    for topic in primary.iter().filter(|t| graphs.contains(t)) {
+        let Ok(g) = crate::tracegraph::load(dir, topic) else { continue };
+        let mut n = 0;
+        for (file, i, o) in crate::tracegraph::module_degrees(&g) {
+            let role = if i + o >= 10 { "HUB" } else if i >= 5 { "CORE" } else { continue };
+            let name = file.rsplit('/').next().unwrap_or(&file);
+            let known = covered.iter().any(|(src, body)| {
+                src.is_some_and(|s| s.split(':').next().is_some_and(|p| p.ends_with(name))) || body.contains(name)
+            });
+            if known { continue; }
+            out.push(format!(
+                "  {file} is a {role} (in={i} out={o}) with no architecture entry:\n    \
+                store topic=\"{topic}\" tags=\"architecture\" source=\"{file}\" text=\"TODO: what {name} owns and who depends on it\""));
+            n += 1;
+            if n == 5 { break; }
+        }
+    }
+    out
+}
+
 // --- Tier 1: Summary (~15 lines) ---
 
-fn format_summary(entries: &[Compressed], query: &str, raw_count: usize,
-                  primary: &[String], since: Option<u64>) -> String {
+fn format_summary(entries: &[Compressed], view: &View) -> String {
+    let View { query, raw_count, primary, since, code_gaps, .. } = *view;
     let cls = classify(entries);
     let n_topics = entries.iter().map(|e| e.topic.as_str())
         .collect::<BTreeSet<_>>().len();
@@ -217,7 +276,7 @@ fn format_summary(entries: &[Compressed], query: &str, raw_count: usize,
     }
 
     // Gaps
-    write_gaps(&mut out, entries, primary);
+    write_gaps(&mut out, entries, primary, code_gaps);
 
     // Stats + hint
     let pct = if raw_count > 0 { 100 - (entries.len() * 100 / raw_count) } else { 0 };
@@ -228,9 +287,8 @@ fn format_summary(entries: &[Compressed], query: &str, raw_count: usize,
 
 // --- Tier 2: Scan (~50 lines) ---
 
-fn format_scan_filtered(entries: &[Compressed], query: &str, raw_count: usize,
-               primary: &[String], since: Option<u64>,
-               cls: &Classification, focus: Option<&[String]>) -> String {
+fn format_scan_filtered(entries: &[Compressed], view: &View, cls: &Classification) -> String {
+    let View { query, raw_count, primary, since, focus, .. } = *view;
     let n_topics = entries.iter().map(|e| e.topic.as_str())
         .collect::<BTreeSet<_>>().len();
     let mut out = String::new();
@@ -289,9 +347,8 @@ fn format_scan_filtered(entries: &[Compressed], query: &str, raw_count: usize,
 
 // --- Tier 3: Full (current behavior) ---
 
-fn format_full_filtered(entries: &[Compressed], query: &str, raw_count: usize,
-               primary: &[String], since: Option<u64>,
-               cls: &Classification, focus: Option<&[String]>) -> String {
+fn format_full_filtered(entries: &[Compressed], view: &View, cls: &Classification) -> String {
+    let View { query, raw_count, primary, since, focus, code_gaps } = *view;
     let n_topics = entries.iter().map(|e| e.topic.as_str())
         .collect::<BTreeSet<_>>().len();
     let mut out = String::new();
@@ -313,7 +370,7 @@ fn format_full_filtered(entries: &[Compressed], query: &str, raw_count: usize,
                 .or_else(|| e.body.lines().nth(1))
                 .unwrap_or("");
             let _ = writeln!(out, "  [{}] {}{}",
-                e.topic,
+                e.addr(),
                 crate::text::truncate(summary.trim_start_matches("## ")
                     .trim_start_matches("Summary").trim(), 100),
                 freshness_tag(e.days_old));
@@ -386,7 +443,7 @@ fn format_full_filtered(entries: &[Compressed], query: &str, raw_count: usize,
         let _ = writeln!(out);
     }
 
-    write_gaps(&mut out, entries, primary);
+    write_gaps(&mut out, entries, primary, code_gaps);
     write_stats(&mut out, entries, raw_count);
     out
 }
@@ -462,7 +519,7 @@ fn write_graph(out: &mut String, entries: &[Compressed], primary: &[String]) {
     }
 }
 
-fn write_gaps(out: &mut String, entries: &[Compressed], primary: &[String]) {
+fn write_gaps(out: &mut String, entries: &[Compressed], primary: &[String], code_gaps: &[String]) {
     let mut suggestions: Vec<String> = Vec::new();
     for topic in primary {
         let count = entries.iter().filter(|e| e.topic == *topic).count();
@@ -483,6 +540,10 @@ fn write_gaps(out: &mut String, entries: &[Compressed], primary: &[String]) {
         let _ = writeln!(out, "\nGAPS ({} missing core tags):", suggestions.len());
         for s in &suggestions { let _ = writeln!(out, "{}", s); }
     }
+    if !code_gaps.is_empty() {
+        let _ = writeln!(out, "\nGAPS ({} undocumented modules, from stored call graphs):", code_gaps.len());
+        for s in code_gaps { let _ = writeln!(out, "{}", s); }
+    }
 }
 
 fn write_stats(out: &mut String, entries: &[Compressed], raw_count: usize) {
@@ -505,8 +566,8 @@ fn format_entry_n(out: &mut String, e: &Compressed, max_lines: usize) {
         None => "",
     };
     let refs = if e.link_in >= 2 { format!(" ({} refs)", e.link_in) } else { String::new() };
-    let _ = writeln!(out, "[{}] {}{}{}{}{}{}", e.topic, e.date, freshness_tag(e.days_old),
-        src, also, chain_note, refs);
+    let _ = writeln!(out, "[{}] {}{}{}{}{}{}{} @ {}:uid:{}", e.addr(), e.date, freshness_tag(e.days_old),
+        src, also, chain_note, refs, agent_tag(e), e.topic, crate::address::format_uid(e.uid));
     if let Some(ref chain) = e.chain {
         let _ = writeln!(out, "  {}", crate::text::truncate(chain, 120));
     }
@@ -530,8 +591,13 @@ fn format_oneliner(out: &mut String, e: &Compressed) {
         None => String::new(),
     };
     let refs = if e.link_in >= 2 { format!(" ({} refs)", e.link_in) } else { String::new() };
-    let _ = writeln!(out, "  [{}] {}{}{}{}{}{}", e.topic, fc, src, also, chain,
-        freshness_tag(e.days_old), refs);
+    let _ = writeln!(out, "  [{}] {}{}{}{}{}{}{}", e.addr(), fc, src, also, chain,
+        freshness_tag(e.days_old), refs, agent_tag(e));
+}
+
+/// `agent_entries` marker: written by an agent, never validated by a person.
+fn agent_tag(e: &Compressed) -> &'static str {
+    if e.unvalidated { " [agent, unvalidated]" } else { "" }
 }
 
 fn format_also(topics: &[String]) -> String {
@@ -1,12 +1,14 @@
 //! In-memory corpus cache with data.log mtime invalidation.
 //! Eliminates file I/O + tokenization on repeated corpus-path searches.
 //! Cache holds pre-tokenized entries; metadata parsed lazily on first access.
+//! One corpus per KB dir (canonical path), so a call with a `dir` override
+//! never reads or extends another dir's entries.
 
 use crate::fxhash::FxHashMap;
 use crate::intern::InternedStr;
 use std::sync::Mutex;
 use std::time::SystemTime;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub struct CachedEntry {
     pub topic: InternedStr,
@@ -20,6 +22,13 @@ pub struct CachedEntry {
 }
 
 impl CachedEntry {
+    /// Assemble an entry from already-tokenized parts (corpus.cache load path).
+    pub(crate) fn from_parts(topic: InternedStr, body: String, timestamp_min: i32, offset: u32,
+                             tf_map: FxHashMap<String, usize>, word_count: usize,
+                             snippet: String) -> Self {
+        Self { topic, body, timestamp_min, offset, tf_map, word_count, snippet,
+               meta: std::cell::OnceCell::new() }
+    }
     /// Lazily parse metadata from body on first access.
     fn meta(&self) -> &crate::text::EntryMetadata {
         self.meta.get_or_init(|| crate::text::extract_all_metadata(&self.body))
@@ -30,6 +39,12 @@ impl CachedEntry {
     pub fn source(&self) -> Option<&str> { self.meta().source.as_deref() }
     /// Confidence value (0.0-1.0, default 1.0). Lazy.
     pub fn confidence(&self) -> f64 { self.meta().confidence }
+    /// Who wrote it, from [provenance: ...] metadata. Lazy.
+    pub fn provenance(&self) -> Option<crate::provenance::Provenance> { self.meta().provenance }
+    /// Language from [lang: ...] metadata, else classified from the body (lang.rs).
+    pub fn lang(&self) -> Option<crate::lang::Lang> {
+        self.meta().lang.or_else(|| crate::lang::detect(&self.body))
+    }
     /// Narrative links from [links: ...] metadata. Lazy.
     pub fn links(&self) -> &[(String, usize)] { &self.meta().links }
     /// Check if entry has a specific tag.
@@ -70,33 +85,58 @@ struct CachedCorpus {
 
 /// Invalidate cache (call after any write to data.log).
 pub fn invalidate() {
-    if let Ok(mut g) = CACHE.lock() { *g = None; }
+    if let Ok(mut g) = CACHE.lock() { g.clear(); }
+}
+
+static CACHE: Mutex<Vec<(PathBuf, CachedCorpus)>> = Mutex::new(Vec::new());
+
+/// Cache key: the canonical dir, so `kb` and `./kb/` share one corpus.
+fn key(dir: &Path) -> PathBuf {
+    std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
 }
 
-static CACHE: Mutex<Option<CachedCorpus>> = Mutex::new(None);
+/// `dir`'s slot in the cache, if it has one.
+fn slot<'a>(cache: &'a mut [(PathBuf, CachedCorpus)], key: &Path) -> Option<&'a mut CachedCorpus> {
+    cache.iter_mut().find(|(d, _)| d == key).map(|(_, c)| c)
+}
+
+fn put(cache: &mut Vec<(PathBuf, CachedCorpus)>, key: PathBuf, corpus: CachedCorpus) {
+    cache.retain(|(d, _)| *d != key);
+    cache.push((key, corpus));
+}
 
 /// Access cached corpus via closure. Reloads from data.log only if mtime changed.
 /// The closure receives all entries (unfiltered). Filter in the closure.
-pub fn with_corpus<F, R>(dir: &Path, f: F) -> Result<R, String>
+pub fn with_corpus<F, R>(dir: &Path, f: F) -> Result<R, crate::error::AmrError>
 where F: FnOnce(&[CachedEntry]) -> R {
     let log_path = crate::config::log_path(dir);
-    let cur_mtime = std::fs::metadata(&log_path)
-        .and_then(|m| m.modified())
+    let log_meta = std::fs::metadata(&log_path).ok();
+    let cur_mtime = log_meta.as_ref().and_then(|m| m.modified().ok())
         .unwrap_or(SystemTime::UNIX_EPOCH);
+    let log_len = log_meta.map_or(0, |m| m.len());
 
-    let mut guard = CACHE.lock().map_err(|e| e.to_string())?;
+    let key = key(dir);
+    let mut guard = CACHE.lock().map_err(|e| crate::error::AmrError::Locked(e.to_string()))?;
 
     // Check if cache is fresh
-    if let Some(ref cache) = *guard {
+    if let Some(cache) = slot(&mut guard, &key) {
         if cache.mtime == cur_mtime {
             return Ok(f(&cache.entries));
         }
     }
 
-    // Cache miss: reload from data.log (metadata parsed lazily on first access)
+    // Cache miss: try the corpus.cache sidecar before re-tokenizing data.log
+    let mut intern_pool: FxHashMap<String, InternedStr> = FxHashMap::default();
+    if let Some(entries) = crate::cachefile::load(dir, cur_mtime, log_len, &mut intern_pool) {
+        let result = f(&entries);
+        put(&mut guard, key, CachedCorpus { mtime: cur_mtime, entries, intern_pool });
+        return Ok(result);
+    }
+
+    // Reload from data.log (metadata parsed lazily on first access)
     let raw_entries = crate::datalog::iter_live(&log_path)?;
     let mut entries = Vec::with_capacity(raw_entries.len());
-    let mut intern_pool: FxHashMap<String, InternedStr> = FxHashMap::default();
+    let style = SnippetStyle::from_settings(dir);
     for e in raw_entries {
         let topic = match intern_pool.get(e.topic.as_str()) {
             Some(t) => t.clone(),
@@ -104,7 +144,7 @@ where F: FnOnce(&[CachedEntry]) -> R {
         };
         let mut tf_map: FxHashMap<String, usize> = crate::fxhash::map_with_capacity(32);
         let word_count = crate::text::tokenize_into_tfmap(&e.body, &mut tf_map);
-        let snippet = build_snippet(topic.as_str(), e.timestamp_min, &e.body);
+        let snippet = build_snippet(topic.as_str(), e.timestamp_min, &e.body, &style);
         entries.push(CachedEntry {
             topic, body: e.body, timestamp_min: e.timestamp_min, offset: e.offset,
             tf_map, word_count, snippet, meta: std::cell::OnceCell::new(),
@@ -112,26 +152,33 @@ where F: FnOnce(&[CachedEntry]) -> R {
     }
 
     let result = f(&entries);
-    *guard = Some(CachedCorpus { mtime: cur_mtime, entries, intern_pool });
+    if log_len > 0 { let _ = crate::cachefile::save(dir, &entries, cur_mtime, log_len); }
+    put(&mut guard, key, CachedCorpus { mtime: cur_mtime, entries, intern_pool });
     Ok(result)
 }
 
-/// Append a new entry to the in-memory cache and update mtime.
+/// Warm the in-memory cache (from corpus.cache if valid). Returns entry count.
+pub fn preload(dir: &Path) -> Result<usize, crate::error::AmrError> {
+    with_corpus(dir, |cached| cached.len())
+}
+
+/// Append a new entry to `dir`'s cached corpus and update mtime.
 /// Avoids cache invalidation after store (eliminates double corpus load).
-/// No-op if cache is empty (cold start — next read will do full load).
+/// No-op if `dir` has no cached corpus (cold start — next read will do full load).
 pub fn append_to_cache(dir: &Path, topic: &str, body: &str, ts_min: i32, offset: u32) {
     let log_path = crate::config::log_path(dir);
     let cur_mtime = std::fs::metadata(&log_path)
         .and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+    let key = key(dir);
     let mut guard = match CACHE.lock() { Ok(g) => g, Err(_) => return };
-    let cache = match guard.as_mut() { Some(c) => c, None => return };
+    let Some(cache) = slot(&mut guard, &key) else { return };
     let topic_interned = match cache.intern_pool.get(topic) {
         Some(t) => t.clone(),
         None => { let t = InternedStr::new(topic); cache.intern_pool.insert(topic.to_string(), t.clone()); t }
     };
     let mut tf_map = crate::fxhash::map_with_capacity(32);
     let word_count = crate::text::tokenize_into_tfmap(body, &mut tf_map);
-    let snippet = build_snippet(topic, ts_min, body);
+    let snippet = build_snippet(topic, ts_min, body, &SnippetStyle::from_settings(dir));
     cache.entries.push(CachedEntry {
         topic: topic_interned, body: body.to_string(), timestamp_min: ts_min,
         offset, tf_map, word_count, snippet, meta: std::cell::OnceCell::new(),
@@ -139,46 +186,115 @@ pub fn append_to_cache(dir: &Path, topic: &str, body: &str, ts_min: i32, offset:
     cache.mtime = cur_mtime;
 }
 
+/// After compaction: move a cache that matched data.log as of `old_mtime` to
+/// the compacted log's offsets instead of reloading it, and refresh the
+/// corpus.cache sidecar. A cache that was already stale is dropped.
+pub fn remap_offsets(dir: &Path, old_mtime: SystemTime, remap: &crate::datalog::Remap) {
+    let log_path = crate::config::log_path(dir);
+    let Ok(meta) = std::fs::metadata(&log_path) else { return invalidate() };
+    let key = key(dir);
+    let mut guard = match CACHE.lock() { Ok(g) => g, Err(_) => return };
+    let Some(cache) = slot(&mut guard, &key).filter(|c| c.mtime == old_mtime) else {
+        guard.retain(|(d, _)| *d != key);
+        return;
+    };
+    for e in cache.entries.iter_mut() {
+        match remap.get(e.offset) {
+            Some(new) => e.offset = new,
+            None => { guard.retain(|(d, _)| *d != key); return; }
+        }
+    }
+    cache.mtime = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    let _ = crate::cachefile::save(dir, &cache.entries, cache.mtime, meta.len());
+}
+
 pub struct CacheStats {
     pub entries: usize,
     pub cached: bool,
 }
 
-pub fn stats() -> CacheStats {
+/// What is cached for `dir`.
+pub fn stats(dir: &Path) -> CacheStats {
+    let key = key(dir);
     let guard = CACHE.lock().unwrap();
-    match guard.as_ref() {
-        Some(c) => CacheStats { entries: c.entries.len(), cached: true },
+    match guard.iter().find(|(d, _)| *d == key) {
+        Some((_, c)) => CacheStats { entries: c.entries.len(), cached: true },
         None => CacheStats { entries: 0, cached: false },
     }
 }
 
+/// How index snippets are cut: `snippet_length` (content bytes, default 120),
+/// `snippet_metadata` (keep `[tags: ...]`-style lines, default off) and
+/// `snippet_prose` (open at the first sentence outside code, default off).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SnippetStyle { pub length: usize, pub metadata: bool, pub prose: bool }
+
+impl SnippetStyle {
+    pub const DEFAULT: Self = Self { length: 120, metadata: false, prose: false };
+
+    pub fn from_settings(dir: &Path) -> Self {
+        let s = crate::settings::load(dir);
+        Self {
+            length: s.get("snippet_length").and_then(|v| v.parse().ok()).filter(|&n| n > 0)
+                .unwrap_or(Self::DEFAULT.length),
+            metadata: s.flag("snippet_metadata").unwrap_or(Self::DEFAULT.metadata),
+            prose: s.flag("snippet_prose").unwrap_or(Self::DEFAULT.prose),
+        }
+    }
+
+    /// One line for snippet.fp; the default style records nothing.
+    fn fingerprint(&self) -> String {
+        if *self == Self::DEFAULT { return String::new(); }
+        format!("length={} metadata={} prose={}", self.length, self.metadata, self.prose)
+    }
+}
+
+pub fn snippet_fp_path(dir: &Path) -> std::path::PathBuf { dir.join("snippet.fp") }
+
+/// Check `dir`'s snippet settings against its built state. Snippets live in
+/// the corpus cache and index.bin, so a style other than the one recorded in
+/// snippet.fp drops corpus.cache and rebuilds the index, as a tokenizer change does.
+pub fn configure(dir: &Path) {
+    let style = SnippetStyle::from_settings(dir);
+    let fp = snippet_fp_path(dir);
+    let recorded = std::fs::read_to_string(&fp).unwrap_or_default();
+    let want = style.fingerprint();
+    if recorded.trim_end_matches('\n') == want { return; }
+    let _ = if want.is_empty() { std::fs::remove_file(&fp) } else { std::fs::write(&fp, format!("{want}\n")) };
+    let _ = std::fs::remove_file(crate::cachefile::cache_path(dir));
+    invalidate();
+    if crate::config::data_log_exists(dir) { let _ = crate::inverted::rebuild_and_persist(dir); }
+}
+
+
 /// Build index snippet: "[topic] date content_preview". Computed once, reused by rebuild.
 /// v7.4: single allocation — direct push_str replaces format! + Vec + join (was 4 allocs).
-fn build_snippet(topic: &str, ts_min: i32, body: &str) -> String {
-    // Pre-size: "[topic] YYYY-MM-DD HH:MM " + ~120 chars content
-    let mut buf = String::with_capacity(topic.len() + 20 + 120);
+fn build_snippet(topic: &str, ts_min: i32, body: &str, style: &SnippetStyle) -> String {
+    let cap = style.length;
+    let mut buf = String::with_capacity(topic.len() + 20 + cap);
     buf.push('[');
     buf.push_str(topic);
     buf.push_str("] ");
     crate::time::minutes_to_date_str_into(ts_min, &mut buf);
     buf.push(' ');
-    // Inline content preview: take first 2 non-metadata, non-empty lines joined by space
     let content_start = buf.len();
-    let mut line_count = 0u8;
-    for line in body.lines() {
-        if crate::text::is_metadata_line(line) || line.trim().is_empty() { continue; }
-        if line_count > 0 { buf.push(' '); }
-        buf.push_str(line.trim());
-        line_count += 1;
-        if line_count >= 2 { break; }
-        // Cap content at ~120 chars
-        if buf.len() - content_start >= 120 { break; }
-    }
-    // Truncate content portion to ~120 bytes at a word boundary (char-safe).
+    if !(style.prose && push_prose(&mut buf, body, cap)) {
+        // Inline content preview: take first 2 non-empty lines (metadata skipped by default) joined by space
+        let mut line_count = 0u8;
+        for line in body.lines() {
+            if (!style.metadata && crate::text::is_metadata_line(line)) || line.trim().is_empty() { continue; }
+            if line_count > 0 { buf.push(' '); }
+            buf.push_str(line.trim());
+            line_count += 1;
+            if line_count >= 2 { break; }
+            if buf.len() - content_start >= cap { break; }
+        }
+    }
+    // Truncate content portion to `cap` bytes at a word boundary (char-safe).
     let content_len = buf.len() - content_start;
-    if content_len > 120 {
-        // Find the largest char boundary <= 120
-        let mut boundary = 120;
+    if content_len > cap {
+        // Find the largest char boundary <= cap
+        let mut boundary = cap;
         while boundary > 0 && !buf.is_char_boundary(content_start + boundary) {
             boundary -= 1;
         }
@@ -188,3 +304,27 @@ fn build_snippet(topic: &str, ts_min: i32, body: &str) -> String {
     }
     buf
 }
+
+/// snippet_prose: push the body's prose (outside ``` fences, indented code and
+/// metadata) up to the end of its first sentence or `cap` bytes. False when
+/// the body has no prose, so the caller falls back to the plain preview.
+fn push_prose(buf: &mut String, body: &str, cap: usize) -> bool {
+    let start = buf.len();
+    let mut in_fence = false;
+    for line in body.lines() {
+        let t = line.trim();
+        if t.starts_with("```") { in_fence = !in_fence; continue; }
+        if in_fence || t.is_empty() || line.starts_with("    ") || line.starts_with('\t')
+            || crate::text::is_metadata_line(line) { continue; }
+        if buf.len() > start { buf.push(' '); }
+        let from = buf.len();
+        buf.push_str(t);
+        let end = buf[from..].char_indices()
+            .find(|&(i, c)| matches!(c, '.' | '!' | '?')
+                && buf[from + i + 1..].chars().next().is_none_or(char::is_whitespace))
+            .map(|(i, _)| from + i + 1);
+        if let Some(end) = end { buf.truncate(end); break; }
+        if buf.len() - start >= cap { break; }
+    }
+    buf.len() > start
+}
@@ -0,0 +1,131 @@
+//! corpus.cache sidecar: tokenized corpus persisted next to data.log.
+//! Lets CLI invocations and hooks skip data.log parsing + re-tokenization.
+//! Validated against data.log mtime + length; any mismatch → rebuild.
+//!
+//! Layout (little-endian):
+//!   magic "AMRC" | version u32 | mtime_secs u64 | mtime_nanos u32 | log_len u64 | count u32
+//!   per entry: offset u32 | ts_min i32 | word_count u32
+//!              | topic_len u16 | topic | body_len u32 | body | snippet_len u16 | snippet
+//!              | nterms u32 | (term_len u16 | term | tf u32)*
+
+use crate::cache::CachedEntry;
+use crate::fxhash::FxHashMap;
+use crate::intern::InternedStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAGIC: [u8; 4] = *b"AMRC";
+const VERSION: u32 = 2;
+
+/// Numbers this process's tmp files, so concurrent saves never share one.
+static TMP_SEQ: AtomicU32 = AtomicU32::new(0);
+
+pub fn cache_path(dir: &Path) -> PathBuf { dir.join("corpus.cache") }
+
+/// Split mtime into (secs, nanos) for exact round-tripping.
+fn mtime_parts(t: SystemTime) -> (u64, u32) {
+    t.duration_since(UNIX_EPOCH).map(|d| (d.as_secs(), d.subsec_nanos())).unwrap_or((0, 0))
+}
+
+/// Load entries if the sidecar matches data.log's current mtime + length.
+/// Returns None on any mismatch or corruption — caller rebuilds from data.log.
+pub fn load(dir: &Path, log_mtime: SystemTime, log_len: u64,
+            pool: &mut FxHashMap<String, InternedStr>) -> Option<Vec<CachedEntry>> {
+    let data = std::fs::read(cache_path(dir)).ok()?;
+    let mut r = Reader { data: &data, pos: 0 };
+    if r.bytes(4)? != MAGIC || r.u32()? != VERSION { return None; }
+    let (secs, nanos) = mtime_parts(log_mtime);
+    if r.u64()? != secs || r.u32()? != nanos || r.u64()? != log_len { return None; }
+    let count = r.u32()? as usize;
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        let offset = r.u32()?;
+        let ts_min = r.u32()? as i32;
+        let word_count = r.u32()? as usize;
+        let topic_len = r.u16()? as usize;
+        let topic_str = r.str(topic_len)?;
+        let topic = match pool.get(topic_str) {
+            Some(t) => t.clone(),
+            None => { let t = InternedStr::new(topic_str); pool.insert(topic_str.to_string(), t.clone()); t }
+        };
+        let body_len = r.u32()? as usize;
+        let body = r.str(body_len)?.to_string();
+        let snip_len = r.u16()? as usize;
+        let snippet = r.str(snip_len)?.to_string();
+        let nterms = r.u32()? as usize;
+        let mut tf_map: FxHashMap<String, usize> = crate::fxhash::map_with_capacity(nterms);
+        for _ in 0..nterms {
+            let tl = r.u16()? as usize;
+            let term = r.str(tl)?.to_string();
+            tf_map.insert(term, r.u32()? as usize);
+        }
+        entries.push(CachedEntry::from_parts(topic, body, ts_min, offset, tf_map, word_count, snippet));
+    }
+    if r.pos != data.len() { return None; }
+    Some(entries)
+}
+
+/// Persist entries atomically (tmp + rename). Best-effort: callers ignore errors,
+/// a missing or stale sidecar only costs one rebuild.
+pub fn save(dir: &Path, entries: &[CachedEntry], log_mtime: SystemTime, log_len: u64)
+    -> Result<(), String>
+{
+    let body_bytes: usize = entries.iter().map(|e| e.body.len() + e.snippet.len()).sum();
+    let mut buf: Vec<u8> = Vec::with_capacity(32 + body_bytes + entries.len() * 256);
+    let (secs, nanos) = mtime_parts(log_mtime);
+    buf.extend_from_slice(&MAGIC);
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.extend_from_slice(&secs.to_le_bytes());
+    buf.extend_from_slice(&nanos.to_le_bytes());
+    buf.extend_from_slice(&log_len.to_le_bytes());
+    buf.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for e in entries {
+        if e.topic.len() > u16::MAX as usize || e.snippet.len() > u16::MAX as usize {
+            return Err("corpus.cache: field too long".into());
+        }
+        buf.extend_from_slice(&e.offset.to_le_bytes());
+        buf.extend_from_slice(&e.timestamp_min.to_le_bytes());
+        buf.extend_from_slice(&(e.word_count as u32).to_le_bytes());
+        buf.extend_from_slice(&(e.topic.len() as u16).to_le_bytes());
+        buf.extend_from_slice(e.topic.as_bytes());
+        buf.extend_from_slice(&(e.body.len() as u32).to_le_bytes());
+        buf.extend_from_slice(e.body.as_bytes());
+        buf.extend_from_slice(&(e.snippet.len() as u16).to_le_bytes());
+        buf.extend_from_slice(e.snippet.as_bytes());
+        // A term too long for its u16 length is left out (it can't be a
+        // query term anyway); cutting it could split a UTF-8 character
+        let fits = |term: &&String| term.len() <= u16::MAX as usize;
+        buf.extend_from_slice(&(e.tf_map.keys().filter(fits).count() as u32).to_le_bytes());
+        for (term, tf) in e.tf_map.iter().filter(|(t, _)| fits(t)) {
+            buf.extend_from_slice(&(term.len() as u16).to_le_bytes());
+            buf.extend_from_slice(term.as_bytes());
+            buf.extend_from_slice(&(*tf as u32).to_le_bytes());
+        }
+    }
+    let target = cache_path(dir);
+    let tmp = dir.join(format!("corpus.cache.{}.{}.tmp", std::process::id(), TMP_SEQ.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&tmp, &buf).map_err(|e| format!("write corpus.cache: {e}"))?;
+    std::fs::rename(&tmp, &target).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        format!("rename corpus.cache: {e}")
+    })
+}
+
+/// Bounds-checked little-endian cursor. Every read returns None past EOF.
+struct Reader<'a> { data: &'a [u8], pos: usize }
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        let s = self.data.get(self.pos..end)?;
+        self.pos = end;
+        Some(s)
+    }
+    fn u16(&mut self) -> Option<u16> { self.bytes(2).map(|b| u16::from_le_bytes([b[0], b[1]])) }
+    fn u32(&mut self) -> Option<u32> { self.bytes(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])) }
+    fn u64(&mut self) -> Option<u64> {
+        self.bytes(8).map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+    }
+    fn str(&mut self, n: usize) -> Option<&'a str> { std::str::from_utf8(self.bytes(n)?).ok() }
+}
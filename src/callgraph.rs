@@ -3,34 +3,37 @@
 
 use std::collections::BTreeSet;
 use std::fmt::Write;
-use std::path::Path;
+use crate::codepath::Roots;
 
 struct FnDef { name: String, file: String, line: usize, end_line: usize }
 struct CallRef { caller: String, file: String, line: usize, snippet: String }
 
-pub fn run(pattern: &str, path: &Path, glob_suffix: &str, depth: usize, direction: &str)
+pub fn run(pattern: &str, roots: &Roots, glob_suffix: &str, depth: usize, direction: &str)
     -> Result<String, String>
 {
     if pattern.is_empty() { return Err("pattern is required".into()); }
-    if !path.is_dir() { return Err(format!("{} is not a directory", path.display())); }
     let suffix = glob_suffix.trim_start_matches('*');
-    let mut fps = Vec::new();
-    crate::codepath::walk_files(path, suffix, &mut fps)?;
-    fps.sort();
+    let fps = roots.files(suffix)?;
 
     let mut all_fns: Vec<FnDef> = Vec::new();
     let mut files: Vec<(String, String)> = Vec::new();
-    for fp in &fps {
+    let total = fps.len() as u64;
+    for (i, (fp, rel)) in fps.iter().enumerate() {
+        crate::cancel::check()?;
+        if crate::progress::active() {
+            let msg = format!("parsed {i}/{total} files, {} fns", all_fns.len());
+            crate::progress::report(i as u64, Some(total), &msg);
+        }
         let content = match std::fs::read_to_string(fp) { Ok(c) => c, Err(_) => continue };
-        let rel = fp.strip_prefix(path).unwrap_or(fp).to_string_lossy().to_string();
         for (name, line, end) in extract_fns(&content) {
             all_fns.push(FnDef { name, file: rel.clone(), line, end_line: end });
         }
-        files.push((rel, content));
+        files.push((rel.clone(), content));
     }
+    crate::progress::report(total, Some(total), "parsed all files");
 
     let mut out = String::new();
-    let _ = writeln!(out, "# callgraph: `{}` in {} ({})\n", pattern, path.display(), glob_suffix);
+    let _ = writeln!(out, "# callgraph: `{}` in {} ({})\n", pattern, roots, glob_suffix);
 
     for d in all_fns.iter().filter(|f| f.name == pattern) {
         let _ = writeln!(out, "DEF: {} ({}:{})", d.name, d.file, d.line);
@@ -42,6 +45,7 @@ pub fn run(pattern: &str, path: &Path, glob_suffix: &str, depth: usize, directio
         let mut seen = BTreeSet::new();
         seen.insert(pattern.to_string());
         for d in 0..depth.min(3) {
+            crate::cancel::check()?;
             let refs = find_callers(&targets, &files, &all_fns, &seen);
             if refs.is_empty() { break; }
             let indent = "  ".repeat(d + 1);
@@ -65,9 +69,44 @@ pub fn run(pattern: &str, path: &Path, glob_suffix: &str, depth: usize, directio
     }
 
     let _ = writeln!(out, "\n{} functions across {} files", all_fns.len(), files.len());
+    out.push_str(&roots.tally("files", files.iter().map(|(f, _)| f.as_str())));
     Ok(out)
 }
 
+/// Every definition and every call between defined functions across `roots`,
+/// for the stored graph (tracegraph.rs). Calls to names never defined in the
+/// codebase (std, external crates) are dropped.
+pub(crate) fn graph(roots: &Roots, glob_suffix: &str) -> Result<crate::tracegraph::Graph, String> {
+    use crate::tracegraph::{Call, Def};
+    let suffix = glob_suffix.trim_start_matches('*');
+    let mut all_fns: Vec<FnDef> = Vec::new();
+    let mut files: Vec<(String, String)> = Vec::new();
+    for (fp, rel) in roots.files(suffix)? {
+        crate::cancel::check()?;
+        let content = match std::fs::read_to_string(&fp) { Ok(c) => c, Err(_) => continue };
+        for (name, line, end) in extract_fns(&content) {
+            all_fns.push(FnDef { name, file: rel.clone(), line, end_line: end });
+        }
+        files.push((rel, content));
+    }
+    let defined: BTreeSet<&str> = all_fns.iter().map(|f| f.name.as_str()).collect();
+    let mut calls = Vec::new();
+    for def in &all_fns {
+        crate::cancel::check()?;
+        for (callee, line) in callees_in_body(def, &files) {
+            // callees_in_body includes the definition line itself
+            if callee == def.name && line == def.line { continue; }
+            if !defined.contains(callee.as_str()) { continue; }
+            calls.push(Call { caller: def.name.clone(), callee, file: def.file.clone(), line });
+        }
+    }
+    let defs = all_fns.into_iter().map(|f| Def { name: f.name, file: f.file, line: f.line }).collect();
+    Ok(crate::tracegraph::Graph {
+        roots: roots.to_string(), glob: glob_suffix.to_string(),
+        built_min: crate::time::LocalTime::now().to_minutes() as i32, defs, calls,
+    })
+}
+
 fn extract_fns(content: &str) -> Vec<(String, usize, usize)> {
     let lines: Vec<&str> = content.lines().collect();
     let mut fns: Vec<(String, usize, usize)> = Vec::new();
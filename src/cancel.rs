@@ -0,0 +1,34 @@
+//! Cooperative cancellation for long operations (trace, reindex).
+//! The MCP server installs a per-thread flag around each tools/call and sets it
+//! from its stdin reader when notifications/cancelled names that request.
+//! Loops in deep code call `check()` between units of work (one file, one entry)
+//! and bail out with `CANCELLED`. With no flag installed `check` never fails.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Error message returned by `check` once the request is cancelled.
+pub const CANCELLED: &str = "cancelled";
+
+thread_local! {
+    static FLAG: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `flag` as this thread's cancellation flag; restores the previous one after.
+pub fn with_flag<R>(flag: Arc<AtomicBool>, f: impl FnOnce() -> R) -> R {
+    let prev = FLAG.with(|s| s.borrow_mut().replace(flag));
+    let out = f();
+    FLAG.with(|s| *s.borrow_mut() = prev);
+    out
+}
+
+/// True once the installed flag has been set.
+pub fn is_cancelled() -> bool {
+    FLAG.with(|s| s.borrow().as_ref().is_some_and(|f| f.load(Ordering::Relaxed)))
+}
+
+/// Err(CANCELLED) once the current request has been cancelled.
+pub fn check() -> Result<(), String> {
+    if is_cancelled() { Err(CANCELLED.into()) } else { Ok(()) }
+}
@@ -3,6 +3,7 @@
 
 use crate::format::*;
 use crate::binquery::{read_header, read_slot, read_at};
+use crate::error::AmrError;
 
 #[derive(Clone, Copy)]
 #[repr(C)]
@@ -11,6 +12,32 @@ pub struct RawResult {
     pub score_x1000: u32,
 }
 
+/// Index statistics as numbers, for bindings that shouldn't parse `amr_info` text.
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct IndexInfo {
+    pub entries: u32,
+    pub terms: u32,
+    pub topics: u32,
+    pub xrefs: u32,
+    pub tags: u32,
+    pub version: u32,
+    /// Size of index.bin in bytes.
+    pub bytes: u64,
+    /// index.gen generation when the handle was (re)loaded; 0 without index.gen.
+    pub generation: u64,
+}
+
+pub fn info(data: &[u8], generation: u64) -> Result<IndexInfo, AmrError> {
+    let hdr = read_header(data)?;
+    Ok(IndexInfo {
+        entries: hdr.num_entries, terms: hdr.num_terms,
+        topics: { hdr.num_topics } as u32, xrefs: { hdr.num_xrefs } as u32,
+        tags: hdr.num_tags, version: hdr.version,
+        bytes: data.len() as u64, generation,
+    })
+}
+
 pub struct QueryState {
     pub generation: u32,
     pub entry_gen: Vec<u32>,
@@ -36,7 +63,7 @@ pub fn search_raw(
     let hdr = read_header(data)?;
     let num_entries = { hdr.num_entries } as usize;
     let table_cap = { hdr.table_cap } as usize;
-    let avgdl = { hdr.avgdl_x100 } as f64 / 100.0;
+    let avgdls = crate::binquery::topic_avgdls(data)?;
     let post_off = { hdr.postings_off } as usize;
     let meta_off = { hdr.meta_off } as usize;
     let mask = table_cap - 1;
@@ -71,6 +98,7 @@ pub fn search_raw(
                     let doc_len = { m.word_count } as f64;
                     let idf = { p.idf_x1000 } as f64 / 1000.0;
                     let tf = { p.tf } as f64;
+                    let avgdl = avgdls.get({ m.topic_id } as usize).copied().unwrap_or(1.0);
                     let len_norm = 1.0 - 0.75 + 0.75 * doc_len / avgdl.max(1.0);
                     let tf_sat = (tf * 2.2) / (tf + 1.2 * len_norm);
                     let conf = { m.confidence } as f64 / 255.0;
@@ -87,25 +115,55 @@ pub fn search_raw(
     }
     if !any_hit { return Ok(0); }
 
+    // Bounded min-heap in the caller's buffer: O(N log K), zero allocation.
+    // Root = worst kept hit; candidates only pay log K when they beat it.
     let limit = out.len();
+    if limit == 0 { return Ok(0); }
     let mut n = 0usize;
     for eid in 0..num_entries {
         if state.entry_gen[eid] != gen { continue; }
-        let s = (state.scores[eid] * 1000.0) as u32;
+        let hit = RawResult { entry_id: eid as u32, score_x1000: (state.scores[eid] * 1000.0) as u32 };
         if n < limit {
-            let mut pos = n;
-            while pos > 0 && out[pos - 1].score_x1000 < s { out[pos] = out[pos - 1]; pos -= 1; }
-            out[pos] = RawResult { entry_id: eid as u32, score_x1000: s };
+            out[n] = hit;
             n += 1;
-        } else if s > out[n - 1].score_x1000 {
-            let mut pos = n - 1;
-            while pos > 0 && out[pos - 1].score_x1000 < s { out[pos] = out[pos - 1]; pos -= 1; }
-            out[pos] = RawResult { entry_id: eid as u32, score_x1000: s };
+            sift_up(&mut out[..n], n - 1);
+        } else if hit.score_x1000 > out[0].score_x1000 {
+            out[0] = hit;
+            sift_down(&mut out[..n], 0);
         }
     }
+    // Best first; ties by ascending entry_id (matches the old stable insertion order).
+    out[..n].sort_unstable_by(|a, b| b.score_x1000.cmp(&a.score_x1000).then(a.entry_id.cmp(&b.entry_id)));
     Ok(n)
 }
 
+/// Heap order: `a` sits above `b` when it is the worse hit (lower score, later id on ties).
+#[inline]
+fn worse(a: &RawResult, b: &RawResult) -> bool {
+    a.score_x1000 < b.score_x1000 || (a.score_x1000 == b.score_x1000 && a.entry_id > b.entry_id)
+}
+
+fn sift_up(h: &mut [RawResult], mut i: usize) {
+    while i > 0 {
+        let parent = (i - 1) / 2;
+        if !worse(&h[i], &h[parent]) { break; }
+        h.swap(i, parent);
+        i = parent;
+    }
+}
+
+fn sift_down(h: &mut [RawResult], mut i: usize) {
+    loop {
+        let (l, r) = (2 * i + 1, 2 * i + 2);
+        let mut m = i;
+        if l < h.len() && worse(&h[l], &h[m]) { m = l; }
+        if r < h.len() && worse(&h[r], &h[m]) { m = r; }
+        if m == i { break; }
+        h.swap(i, m);
+        i = m;
+    }
+}
+
 pub fn snippet(data: &[u8], entry_id: u16) -> Option<&str> {
     snippet_u32(data, entry_id as u32)
 }
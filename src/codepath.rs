@@ -12,53 +12,172 @@ struct Hit {
     category: &'static str,
 }
 
-/// Search `path` for `pattern` in files matching `glob_suffix`, categorize each hit.
-pub fn run(pattern: &str, path: &Path, glob_suffix: &str, context: usize)
+/// Search `roots` for `pattern` in files matching `glob_suffix`, categorize each hit.
+pub fn run(pattern: &str, roots: &Roots, glob_suffix: &str, context: usize)
     -> Result<String, String>
 {
     if pattern.is_empty() { return Err("pattern is required".into()); }
-    if !path.is_dir() { return Err(format!("{} is not a directory", path.display())); }
 
     let suffix = glob_suffix.trim_start_matches('*');
-    let mut files = Vec::new();
-    walk_files(path, suffix, &mut files)?;
-    files.sort();
+    let files = roots.files(suffix)?;
 
     let mut all_hits: Vec<Hit> = Vec::new();
-    for file in &files {
+    let total = files.len() as u64;
+    for (i, (file, rel_str)) in files.iter().enumerate() {
+        crate::cancel::check()?;
+        if crate::progress::active() {
+            let msg = format!("scanned {i}/{total} files, {} hits", all_hits.len());
+            crate::progress::report(i as u64, Some(total), &msg);
+        }
         let content = match std::fs::read_to_string(file) {
             Ok(c) => c,
             Err(_) => continue, // skip binary/unreadable files
         };
-        let rel = file.strip_prefix(path).unwrap_or(file);
-        let rel_str = rel.to_string_lossy().to_string();
-        for hit in search_file(&content, pattern, &rel_str, context) {
+        for hit in search_file(&content, pattern, rel_str, context) {
             all_hits.push(hit);
         }
     }
 
+    if crate::progress::active() {
+        let msg = format!("scanned {total}/{total} files, {} hits", all_hits.len());
+        crate::progress::report(total, Some(total), &msg);
+    }
+
     if all_hits.is_empty() {
-        return Ok(format!("no matches for `{pattern}` in {} ({glob_suffix})\n",
-            path.display()));
+        return Ok(format!("no matches for `{pattern}` in {roots} ({glob_suffix})\n"));
     }
 
-    format_results(&all_hits, pattern, path, glob_suffix)
+    format_results(&all_hits, pattern, roots, glob_suffix)
+}
+
+/// Directories a trace runs over. `path` may list several, comma-separated
+/// (workspace members, app + shared library); their files are analysed as one
+/// codebase. With several roots, file paths in output are relative to the
+/// roots' common parent, so each one names the root it came from.
+pub struct Roots {
+    /// (root, label): label is the root relative to the common parent.
+    dirs: Vec<(PathBuf, String)>,
+    /// The root itself, or the common parent of several.
+    base: PathBuf,
+    ignore: crate::ignore::Ignore,
 }
 
-pub fn walk_files(dir: &Path, suffix: &str, out: &mut Vec<PathBuf>) -> Result<(), String> {
+impl Roots {
+    pub fn parse(spec: &str, ignore: crate::ignore::Ignore) -> Result<Self, String> {
+        let paths: Vec<PathBuf> = spec.split(',').map(str::trim)
+            .filter(|s| !s.is_empty()).map(PathBuf::from).collect();
+        if paths.is_empty() { return Err("path is required".into()); }
+        for p in &paths {
+            if !p.is_dir() { return Err(format!("{} is not a directory", p.display())); }
+        }
+        if paths.len() == 1 {
+            return Ok(Self { base: paths[0].clone(), dirs: vec![(paths[0].clone(), String::new())], ignore });
+        }
+        let abs: Vec<PathBuf> = paths.iter()
+            .map(|p| std::fs::canonicalize(p).unwrap_or_else(|_| p.clone())).collect();
+        let mut base = abs[0].clone();
+        while !abs.iter().all(|p| p.starts_with(&base)) {
+            if !base.pop() { break; }
+        }
+        let mut seen = std::collections::BTreeSet::new();
+        let mut dirs = Vec::new();
+        for (p, a) in paths.into_iter().zip(&abs) {
+            let label = a.strip_prefix(&base).unwrap_or(a).to_string_lossy().to_string();
+            if label.is_empty() { return Err(format!("{} contains the other roots", p.display())); }
+            if seen.insert(label.clone()) { dirs.push((p, label)); }
+        }
+        Ok(Self { dirs, base, ignore })
+    }
+
+    pub fn is_multi(&self) -> bool { self.dirs.len() > 1 }
+
+    /// `[source: …]` glob covering every root, for stored trace results.
+    pub fn source_glob(&self, glob: &str) -> String {
+        format!("{}/**/{glob}", self.base.display())
+    }
+
+    /// Every file under every root ending in `suffix`, sorted within each root,
+    /// as (path to read, path to show).
+    pub fn files(&self, suffix: &str) -> Result<Vec<(PathBuf, String)>, String> {
+        let mut out = Vec::new();
+        for (root, label) in &self.dirs {
+            let mut fps = Vec::new();
+            walk_files(root, suffix, &self.ignore, &mut fps)?;
+            fps.sort();
+            for fp in fps {
+                let rel = fp.strip_prefix(root).unwrap_or(&fp).to_string_lossy().to_string();
+                let shown = if label.is_empty() { rel } else { format!("{label}/{rel}") };
+                out.push((fp, shown));
+            }
+        }
+        Ok(out)
+    }
+
+    /// The root a shown path belongs to.
+    pub fn root_of(&self, shown: &str) -> Option<&str> {
+        self.dirs.iter().map(|(_, l)| l.as_str())
+            .find(|l| !l.is_empty() && shown.strip_prefix(l).is_some_and(|r| r.starts_with('/')))
+    }
+
+    /// "  roots: app (12), shared (8)\n" — per-root counts of `shown` paths;
+    /// empty for a single root.
+    pub fn tally<'a>(&self, what: &str, shown: impl Iterator<Item = &'a str>) -> String {
+        if !self.is_multi() { return String::new(); }
+        let mut counts = vec![0usize; self.dirs.len()];
+        for s in shown {
+            if let Some(i) = self.root_of(s).and_then(|l| self.dirs.iter().position(|(_, d)| d == l)) {
+                counts[i] += 1;
+            }
+        }
+        let parts: Vec<String> = self.dirs.iter().zip(&counts)
+            .map(|((_, l), n)| format!("{l} ({n})")).collect();
+        format!("{what} per root: {}\n", parts.join(", "))
+    }
+}
+
+impl std::fmt::Display for Roots {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let shown: Vec<String> = self.dirs.iter().map(|(p, _)| p.display().to_string()).collect();
+        f.write_str(&shown.join(" + "))
+    }
+}
+
+/// Files under `root` ending in `suffix`, minus what `ignore` skips
+/// (ignore.rs: exclude globs, .gitignore, size cap) and hidden dirs.
+pub fn walk_files(root: &Path, suffix: &str, ignore: &crate::ignore::Ignore, out: &mut Vec<PathBuf>)
+    -> Result<(), String>
+{
+    let abs = std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let mut rules = ignore.rules_at(&abs);
+    walk_dir(root, Path::new(""), &abs, suffix, ignore, &mut rules, out)
+}
+
+fn walk_dir(
+    dir: &Path, rel: &Path, abs: &Path, suffix: &str, ignore: &crate::ignore::Ignore,
+    rules: &mut Vec<crate::ignore::Rule>, out: &mut Vec<PathBuf>,
+) -> Result<(), String> {
     let entries = std::fs::read_dir(dir)
         .map_err(|e| format!("read_dir {}: {e}", dir.display()))?;
     for entry in entries.flatten() {
+        crate::cancel::check()?;
         let path = entry.path();
+        let name = entry.file_name();
+        let (rel, abs) = (rel.join(&name), abs.join(&name));
         if path.is_dir() {
-            // Skip hidden dirs and common noise
-            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            if name.starts_with('.') || name == "target" || name == "node_modules" {
-                continue;
-            }
-            walk_files(&path, suffix, out)?;
+            if name.to_string_lossy().starts_with('.') { continue; }
+            if ignore.skips(&rel, &abs, true, rules) { continue; }
+            let depth = rules.len();
+            rules.extend(ignore.rules_in(&abs));
+            walk_dir(&path, &rel, &abs, suffix, ignore, rules, out)?;
+            rules.truncate(depth);
         } else if path.to_string_lossy().ends_with(suffix) {
+            if ignore.skips(&rel, &abs, false, rules) { continue; }
+            if entry.metadata().is_ok_and(|m| m.len() > ignore.max_bytes) { continue; }
             out.push(path);
+            if crate::progress::active() {
+                let msg = format!("discovered {} files", out.len());
+                crate::progress::report(0, None, &msg);
+            }
         }
     }
     Ok(())
@@ -133,12 +252,11 @@ fn categorize(line: &str, pattern: &str) -> &'static str {
     "field_access"
 }
 
-fn format_results(hits: &[Hit], pattern: &str, path: &Path, glob: &str)
+fn format_results(hits: &[Hit], pattern: &str, roots: &Roots, glob: &str)
     -> Result<String, String>
 {
     let mut out = String::new();
-    let _ = writeln!(out, "# codepath: `{pattern}` in {} ({glob})\n",
-        path.display());
+    let _ = writeln!(out, "# codepath: `{pattern}` in {roots} ({glob})\n");
 
     // Group by file
     let mut current_file = "";
@@ -164,6 +282,7 @@ fn format_results(hits: &[Hit], pattern: &str, path: &Path, glob: &str)
 
     let _ = writeln!(out, "\n## Summary");
     let _ = writeln!(out, "{} sites across {file_count} files", hits.len());
+    out.push_str(&roots.tally("sites", hits.iter().map(|h| h.file.as_str())));
     for (cat, count) in &cats {
         let _ = writeln!(out, "  {cat:<16} {count}");
     }
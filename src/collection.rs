@@ -0,0 +1,139 @@
+//! Collections: named, ordered reading lists of entries across topics
+//! ("onboarding", "release-checklist"), kept in `<dir>/collections/<name>.list`
+//! as one `topic:uid:HEX` address per line. Uids survive tag edits and
+//! compaction, so a list keeps pointing at its entries as topics grow.
+//! `show` renders the whole list like a briefing, full bodies in list order.
+
+use crate::error::AmrError;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+fn collections_dir(dir: &Path) -> PathBuf { dir.join("collections") }
+
+fn list_path(dir: &Path, name: &str) -> PathBuf {
+    collections_dir(dir).join(format!("{}.list", crate::config::sanitize_topic(name)))
+}
+
+fn load(dir: &Path, name: &str) -> Result<Vec<String>, AmrError> {
+    let path = list_path(dir, name);
+    let text = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AmrError::NotFound(format!("no collection '{name}'")),
+        _ => AmrError::Io(format!("{}: {e}", path.display())),
+    })?;
+    Ok(text.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+}
+
+/// Writes the list; an empty one removes the file.
+fn save(dir: &Path, name: &str, items: &[String]) -> Result<(), AmrError> {
+    let path = list_path(dir, name);
+    if items.is_empty() {
+        return std::fs::remove_file(&path).or_else(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Ok(()),
+            _ => Err(AmrError::Io(format!("{}: {e}", path.display()))),
+        });
+    }
+    std::fs::create_dir_all(collections_dir(dir)).map_err(|e| AmrError::io("create collections dir", e))?;
+    crate::config::atomic_write(&path, &(items.join("\n") + "\n")).map_err(AmrError::Io)
+}
+
+/// Any entry address → its stable `topic:uid:<16 hex>` form.
+fn stable_address(dir: &Path, raw: &str) -> Result<String, AmrError> {
+    let addr = crate::address::parse(raw)?;
+    let idx = crate::address::resolve(dir, &addr)?;
+    let uid = crate::cache::with_corpus(dir, |cached| {
+        cached.iter().filter(|e| e.topic.as_str() == addr.topic).nth(idx)
+            .map(|e| crate::address::entry_uid(e.timestamp_min, &e.body))
+    })?.ok_or_else(|| AmrError::NotFound(format!("no entry {raw}")))?;
+    Ok(format!("{}:uid:{uid:016x}", addr.topic))
+}
+
+/// Append entries (addresses, space or comma separated) to `name`, creating it.
+pub fn add(dir: &Path, name: &str, entries: &str) -> Result<String, AmrError> {
+    if name.trim().is_empty() { return Err(AmrError::InvalidArg("collection name is required".into())); }
+    let mut items = load(dir, name).or_else(|e| match e {
+        AmrError::NotFound(_) => Ok(Vec::new()),
+        e => Err(e),
+    })?;
+    let mut added = 0;
+    for raw in entries.split([' ', ',']).map(str::trim).filter(|t| !t.is_empty()) {
+        let stable = stable_address(dir, raw)?;
+        if !items.contains(&stable) { items.push(stable); added += 1; }
+    }
+    if added == 0 { return Err(AmrError::InvalidArg("no new entries to add".into())); }
+    save(dir, name, &items)?;
+    Ok(format!("added {added} to {name} ({} entries)\n", items.len()))
+}
+
+/// Drop entries by address or by 1-based position from `show`.
+pub fn remove(dir: &Path, name: &str, entries: &str) -> Result<String, AmrError> {
+    let mut items = load(dir, name)?;
+    let before = items.len();
+    let mut positions = Vec::new();
+    let mut addrs = Vec::new();
+    for raw in entries.split([' ', ',']).map(str::trim).filter(|t| !t.is_empty()) {
+        match raw.parse::<usize>() {
+            Ok(p) if (1..=before).contains(&p) => positions.push(p - 1),
+            Ok(p) => return Err(AmrError::NotFound(format!("position {p} out of range (1-{before})"))),
+            Err(_) => addrs.push(stable_address(dir, raw)?),
+        }
+    }
+    let mut i = 0;
+    items.retain(|a| { let keep = !positions.contains(&i) && !addrs.contains(a); i += 1; keep });
+    let removed = before - items.len();
+    if removed == 0 { return Err(AmrError::NotFound(format!("none of those entries are in {name}"))); }
+    save(dir, name, &items)?;
+    Ok(format!("removed {removed} from {name} ({} left)\n", items.len()))
+}
+
+/// Every collection with its entry count.
+pub fn list(dir: &Path) -> Result<String, AmrError> {
+    let Ok(rd) = std::fs::read_dir(collections_dir(dir)) else { return Ok("no collections\n".into()) };
+    let mut names: Vec<String> = rd.flatten()
+        .filter_map(|e| e.file_name().to_str()?.strip_suffix(".list").map(str::to_string))
+        .collect();
+    if names.is_empty() { return Ok("no collections\n".into()); }
+    names.sort();
+    let mut out = String::new();
+    for n in &names {
+        let _ = writeln!(out, "  {n} ({} entries)", load(dir, n).map_or(0, |l| l.len()));
+    }
+    Ok(out)
+}
+
+/// The collection as a reading list: header per entry (position, address,
+/// date, source), then its body without metadata lines.
+pub fn show(dir: &Path, name: &str) -> Result<String, AmrError> {
+    let items = load(dir, name)?;
+    crate::cache::with_corpus(dir, |cached| {
+        let mut index: crate::fxhash::FxHashMap<&str, usize> = crate::fxhash::FxHashMap::default();
+        let mut found: crate::fxhash::FxHashMap<String, (usize, &crate::cache::CachedEntry)> =
+            crate::fxhash::FxHashMap::default();
+        for e in cached {
+            let slot = index.entry(e.topic.as_str()).or_default();
+            let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+            found.insert(format!("{}:uid:{uid:016x}", e.topic), (*slot, e));
+            *slot += 1;
+        }
+        let mut body = String::new();
+        let mut tokens = 0;
+        let mut missing = 0;
+        for (pos, a) in items.iter().enumerate() {
+            let Some(&(idx, e)) = found.get(a) else {
+                missing += 1;
+                let _ = writeln!(body, "{}. {a} — gone (deleted or rewritten)\n", pos + 1);
+                continue;
+            };
+            tokens += crate::text::approx_tokens_of(&e.body);
+            let src = e.source().map(|s| format!(" \u{2192} {s}")).unwrap_or_default();
+            let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+            let _ = writeln!(body, "{}. [{}:{idx}] {}{src} @ {}:uid:{}", pos + 1, e.topic,
+                crate::time::minutes_to_date_str(e.timestamp_min), e.topic, crate::address::format_uid(uid));
+            for l in e.body.lines().filter(|l| !crate::text::is_metadata_line(l)) {
+                let _ = writeln!(body, "  {}", l.trim_end());
+            }
+            body.push('\n');
+        }
+        let gone = if missing > 0 { format!(", {missing} gone") } else { String::new() };
+        format!("# {name} — {} entries{gone} ({})\n\n{body}", items.len(), crate::text::format_tokens(tokens))
+    })
+}
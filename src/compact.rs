@@ -1,12 +1,13 @@
+use crate::error::AmrError;
 use std::fmt::Write;
 use std::path::Path;
 
 /// Find duplicate/similar entries within a topic and optionally merge them.
-pub fn run(dir: &Path, topic: &str, apply: bool) -> Result<String, String> {
+pub fn run(dir: &Path, topic: &str, apply: bool) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
-    if entries.is_empty() { return Err(format!("topic '{}' not found", topic)); }
+    if entries.is_empty() { return Err(AmrError::NotFound(format!("topic '{}' not found", topic))); }
     if entries.len() < 2 {
         return Ok(format!("{topic}: {} entry, nothing to compact", entries.len()));
     }
@@ -7,6 +7,10 @@ use crate::fxhash::FxHashSet;
 /// Input: one matching entry collected by the orchestrator.
 pub struct RawEntry {
     pub topic: String,
+    /// Position within the topic, for the `topic:N` address.
+    pub index: usize,
+    /// address::entry_uid of the stored entry.
+    pub uid: u64,
     pub body: String,
     pub timestamp_min: i32,
     pub days_old: i64,
@@ -14,11 +18,15 @@ pub struct RawEntry {
     pub relevance: f64,
     pub confidence: f64,
     pub link_in: u16,
+    /// Agent-written and never validated (provenance.rs); briefings mark it.
+    pub unvalidated: bool,
 }
 
 /// Output: a compressed fact ready for the briefing formatter.
 pub struct Compressed {
     pub topic: String,
+    pub index: usize,
+    pub uid: u64,
     pub body: String,
     pub date: String,
     pub days_old: i64,
@@ -29,6 +37,12 @@ pub struct Compressed {
     pub also_in: Vec<String>,
     pub confidence: f64,
     pub link_in: u16,
+    pub unvalidated: bool,
+}
+
+impl Compressed {
+    /// `topic:N`, accepted wherever an entry address is (address.rs).
+    pub fn addr(&self) -> String { format!("{}:{}", self.topic, self.index) }
 }
 
 /// Run all compression passes. Returns compressed entries sorted by relevance.
@@ -37,10 +51,10 @@ pub fn compress(entries: Vec<RawEntry>) -> Vec<Compressed> {
         let source = crate::text::extract_source(&e.body);
         let date = crate::time::minutes_to_date_str(e.timestamp_min);
         Compressed {
-            topic: e.topic, body: e.body, date, days_old: e.days_old,
+            topic: e.topic, index: e.index, uid: e.uid, body: e.body, date, days_old: e.days_old,
             tags: e.tags, relevance: e.relevance, source,
             chain: None, also_in: Vec::new(),
-            confidence: e.confidence, link_in: e.link_in,
+            confidence: e.confidence, link_in: e.link_in, unvalidated: e.unvalidated,
         }
     }).collect();
     dedup(&mut out);
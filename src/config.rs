@@ -21,10 +21,10 @@ pub fn init(path: Option<&str>) -> Result<(), String> {
     Ok(())
 }
 
-pub fn ensure_dir(dir: &Path) -> Result<(), String> {
+pub fn ensure_dir(dir: &Path) -> Result<(), crate::error::AmrError> {
     if !dir.exists() {
         fs::create_dir_all(dir)
-            .map_err(|e| format!("{} doesn't exist, can't create: {e}", dir.display()))?;
+            .map_err(|e| crate::error::AmrError::Io(format!("{} doesn't exist, can't create: {e}", dir.display())))?;
     }
     Ok(())
 }
@@ -66,8 +66,7 @@ pub fn atomic_write(path: &Path, data: &str) -> Result<(), String> {
 /// Parse [source: path/to/file:line] from entry lines.
 pub fn parse_source(lines: &[&str]) -> Option<(String, Option<usize>)> {
     for line in lines {
-        if let Some(inner) = line.strip_prefix("[source: ").and_then(|s| s.strip_suffix(']')) {
-            let inner = inner.trim();
+        if let Some(("source", inner)) = crate::entryfmt::parse_line(line) {
             if let Some((path, line_num)) = inner.rsplit_once(':') {
                 if let Ok(n) = line_num.parse::<usize>() {
                     return Some((path.to_string(), Some(n)));
@@ -79,11 +78,54 @@ pub fn parse_source(lines: &[&str]) -> Option<(String, Option<usize>)> {
     None
 }
 
-/// Resolve a source path: try as-is, then try one level of CWD subdirectories.
-/// Handles entries with relative paths like `src/foo.rs` when CWD is the project root.
-pub fn resolve_source(source: &str) -> Option<PathBuf> {
+/// Store-time form of a `[source:]` value (`path`, `path:line` or a glob):
+/// a path inside a git work tree is rewritten relative to the tree's top
+/// level, so the entry still resolves after the repo moves or on another
+/// machine. Paths that don't exist or sit outside any repo are kept as given.
+pub fn normalize_source(source: &str) -> String {
+    let (path, line) = match source.rsplit_once(':') {
+        Some((p, n)) if n.parse::<usize>().is_ok() => (p, Some(n)),
+        _ => (source, None),
+    };
+    // For a glob, the directory before the first wildcard anchors it
+    let (anchor, rest) = match path.find('*') {
+        Some(i) => path[..i].rsplit_once('/').map_or((".", path), |(a, _)| (a, &path[a.len() + 1..])),
+        None => (path, ""),
+    };
+    let Ok(abs) = Path::new(if anchor.is_empty() { "/" } else { anchor }).canonicalize() else { return source.to_string() };
+    let Some(root) = crate::git::root(&abs) else { return source.to_string() };
+    let Ok(rel) = abs.strip_prefix(&root) else { return source.to_string() };
+    let mut out = rel.to_string_lossy().into_owned();
+    if !rest.is_empty() {
+        if !out.is_empty() { out.push('/'); }
+        out.push_str(rest);
+    }
+    if out.is_empty() { return source.to_string(); }
+    match line {
+        Some(n) => format!("{out}:{n}"),
+        None => out,
+    }
+}
+
+/// The directory repo-relative sources resolve against: `workspace_root` in
+/// `<dir>/config`, else the git work tree holding the knowledge base.
+pub fn workspace_root(dir: &Path) -> Option<PathBuf> {
+    if let Some(r) = crate::settings::load(dir).get("workspace_root") {
+        return Some(crate::settings::expand_home(r));
+    }
+    crate::git::root(&dir.canonicalize().ok()?)
+}
+
+/// Resolve a source path: try as-is, then against the workspace root, then
+/// one level of CWD subdirectories. Handles entries with relative paths like
+/// `src/foo.rs` whether or not CWD is the project root.
+pub fn resolve_source(dir: &Path, source: &str) -> Option<PathBuf> {
     let p = PathBuf::from(source);
     if p.exists() { return Some(p); }
+    if let Some(root) = workspace_root(dir).filter(|_| p.is_relative()) {
+        let candidate = root.join(source);
+        if candidate.exists() { return Some(candidate); }
+    }
     for entry in fs::read_dir(".").ok()?.flatten() {
         if entry.file_type().ok()?.is_dir() {
             let candidate = entry.path().join(source);
@@ -95,12 +137,21 @@ pub fn resolve_source(source: &str) -> Option<PathBuf> {
 
 /// Check if a source file (or glob pattern) is newer than the entry timestamp.
 /// Handles both single files (`src/cache.rs:42`) and globs (`src/**/*.rs`).
-pub fn check_staleness(source: &str, entry_header: &str) -> Option<String> {
+/// With a recorded `commit` (git freshness) a single file is judged by its
+/// last-changed commit instead, falling back to mtime for uncommitted edits.
+pub fn check_staleness(dir: &Path, source: &str, entry_header: &str, commit: Option<&str>) -> Option<String> {
     let entry_secs = crate::time::parse_date_minutes(entry_header)? * 60;
     if source.contains('*') {
-        return check_staleness_glob(source, entry_secs);
+        return check_staleness_glob(dir, source, entry_secs);
+    }
+    let resolved = resolve_source(dir, source)?;
+    if let Some(c) = commit {
+        match crate::git::is_stale(&resolved, c) {
+            Some(true) => return Some(format!("STALE (source changed since commit {c})")),
+            Some(false) => return None,
+            None => {}
+        }
     }
-    let resolved = resolve_source(source)?;
     let mtime = fs::metadata(&resolved).ok()?.modified().ok()?;
     let file_secs = mtime.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64;
     if file_secs > entry_secs {
@@ -112,10 +163,10 @@ pub fn check_staleness(source: &str, entry_header: &str) -> Option<String> {
 
 /// Check if any file matching a glob pattern is newer than entry_secs.
 /// Supports `dir/**/*.ext` patterns: extracts root dir and suffix, walks recursively.
-fn check_staleness_glob(pattern: &str, entry_secs: i64) -> Option<String> {
+fn check_staleness_glob(dir: &Path, pattern: &str, entry_secs: i64) -> Option<String> {
     // Parse "path/to/dir/**/*.rs" → root="path/to/dir", suffix=".rs"
     let (root, suffix) = parse_glob_pattern(pattern)?;
-    let root_path = resolve_source(&root).or_else(|| {
+    let root_path = resolve_source(dir, &root).or_else(|| {
         let p = PathBuf::from(&root);
         if p.is_dir() { Some(p) } else { None }
     })?;
@@ -166,6 +217,35 @@ fn count_stale_files(dir: &Path, suffix: &str, entry_secs: i64, count: &mut usiz
     }
 }
 
+/// Knowledge bases for a federated search (`search --dirs a,b,c`, MCP `dirs`),
+/// each with the label its results carry: the project's name for a
+/// `<project>/.amaranthine` dir, else the dir's own name. Duplicates are
+/// dropped; a dir without a data.log is an error, not an empty source.
+pub fn resolve_dirs(spec: &str) -> Result<Vec<(String, PathBuf)>, crate::error::AmrError> {
+    let mut out: Vec<(String, PathBuf)> = Vec::new();
+    for raw in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let path = crate::settings::expand_home(raw);
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        if !data_log_exists(&path) {
+            return Err(crate::error::AmrError::NotFound(format!("{raw}: no knowledge base (no data.log)")));
+        }
+        if out.iter().any(|(_, p)| *p == path) { continue; }
+        let name = |p: &Path| p.file_name().map(|n| n.to_string_lossy().trim_start_matches('.').to_string());
+        let label = match path.file_name() {
+            Some(n) if n == ".amaranthine" => path.parent().and_then(name),
+            _ => name(&path),
+        }.filter(|l| !l.is_empty()).unwrap_or_else(|| raw.to_string());
+        out.push((label, path));
+    }
+    if out.is_empty() { return Err(crate::error::AmrError::InvalidArg("dirs: list at least one directory".into())); }
+    // Same-named projects fall back to their full paths
+    let labels: Vec<String> = out.iter().map(|(l, _)| l.clone()).collect();
+    for (label, path) in out.iter_mut() {
+        if labels.iter().filter(|l| *l == label).count() > 1 { *label = path.display().to_string(); }
+    }
+    Ok(out)
+}
+
 /// Path to the append-only data log.
 pub fn log_path(dir: &Path) -> PathBuf {
     dir.join("data.log")
@@ -1,15 +1,17 @@
+use crate::error::AmrError;
 use std::collections::BTreeMap;
 use std::fmt::Write;
 use std::path::Path;
+use crate::theme::{paint, Role};
 
-pub fn run_inner_pub(dir: &Path, query: Option<&str>, plain: bool, brief: bool) -> Result<String, String> {
-    run_inner(dir, query, plain, brief)
+pub fn run_inner_pub(dir: &Path, query: Option<&str>, plain: bool, brief: bool) -> Result<String, AmrError> {
+    if !dir.exists() {
+        return Err(AmrError::NotFound(format!("{} not found", dir.display())));
+    }
+    Ok(run_inner(dir, query, plain, brief)?)
 }
 
 fn run_inner(dir: &Path, query: Option<&str>, plain: bool, brief: bool) -> Result<String, String> {
-    if !dir.exists() {
-        return Err(format!("{} not found", dir.display()));
-    }
 
     // Query provided → delegate to reconstruct for one-shot briefing
     if let Some(q) = query {
@@ -17,7 +19,7 @@ fn run_inner(dir: &Path, query: Option<&str>, plain: bool, brief: bool) -> Resul
     }
 
     // Synthesized meta-briefing for cold starts
-    crate::cache::with_corpus(dir, |cached| {
+    let mut out = crate::cache::with_corpus(dir, |cached| {
         let mut out = String::new();
         let now_days = crate::time::LocalTime::now().to_days();
 
@@ -81,14 +83,108 @@ fn run_inner(dir: &Path, query: Option<&str>, plain: bool, brief: bool) -> Resul
         }
 
         out
-    })
+    }).map_err(String::from)?;
+
+    // 4-week trends from index metadata (epoch_days + tag bitmap, no corpus scan)
+    let trends = crate::mcp::with_index(|data| trends(data, plain, brief))
+        .flatten()
+        .or_else(|| {
+            crate::indexview::fresh(dir).ok().flatten()
+                .and_then(|map| trends(map.bytes(), plain, brief))
+        });
+    if let Some(t) = trends { out.push_str(&t); }
+    Ok(out)
+}
+
+const WEEKS: usize = 4;
+
+/// Entries per week, newest first: [0] = last 7 days, [3] = 21–27 days ago.
+type Weekly = [u32; WEEKS];
+
+fn trends(data: &[u8], plain: bool, brief: bool) -> Option<String> {
+    use crate::format::EntryMeta;
+    let hdr = crate::binquery::read_header(data).ok()?;
+    let meta_off = { hdr.meta_off } as usize;
+    let n = { hdr.num_entries } as usize;
+    let now_days = crate::time::LocalTime::now().to_days();
+    let mut topics: BTreeMap<u16, Weekly> = BTreeMap::new();
+    let mut tags = [[0u32; WEEKS]; 32];
+    for i in 0..n {
+        let off = meta_off + i * std::mem::size_of::<EntryMeta>();
+        let m = crate::binquery::read_at::<EntryMeta>(data, off).ok()?;
+        let ed = { m.epoch_days };
+        if ed == 0 { continue; }
+        let week = (now_days - ed as i64).max(0) as usize / 7;
+        if week >= WEEKS { continue; }
+        let tid = { m.topic_id };
+        topics.entry(tid).or_default()[week] += 1;
+        let bitmap = { m.tag_bitmap };
+        for (bit, w) in tags.iter_mut().enumerate() {
+            if bitmap & (1u32 << bit) != 0 { w[week] += 1; }
+        }
+    }
+    if topics.is_empty() { return None; }
+    let names: BTreeMap<u16, String> = crate::binquery::topic_table(data).ok()?
+        .into_iter().map(|(id, name, _)| (id, name)).collect();
+    let name = |id: &u16| names.get(id).map_or("?", |s| s.as_str());
+
+    let mut out = String::new();
+    let mut active: Vec<(&u16, &Weekly)> = topics.iter().collect();
+    active.sort_by_key(|(_, w)| std::cmp::Reverse((w.iter().sum::<u32>(), w[0])));
+    section(&mut out, "Velocity (4w, oldest → this week)", plain);
+    let limit = if brief { 5 } else { 8 };
+    for (id, w) in active.iter().take(limit) {
+        let _ = writeln!(out, "  {} {}/{}/{}/{} {}", name(id), w[3], w[2], w[1], w[0], arrow(w));
+    }
+    if brief { return Some(out); }
+
+    // Emerging: this week at least double the prior weekly average
+    let tag_names = crate::binquery::tag_names(data).ok()?;
+    let mut emerging: Vec<(&str, u32, f64)> = tag_names.iter().zip(tags.iter())
+        .filter(|(t, _)| t.as_str() != "raw-data")
+        .map(|(t, w)| (t.as_str(), w[0], prior_avg(w)))
+        .filter(|&(_, now, before)| now >= 2 && now as f64 >= 2.0 * before)
+        .collect();
+    emerging.sort_by(|a, b| (b.1 as f64 - b.2).total_cmp(&(a.1 as f64 - a.2)));
+    if !emerging.is_empty() {
+        section(&mut out, "Emerging Tags", plain);
+        for (tag, now, before) in emerging.iter().take(6) {
+            let _ = writeln!(out, "  {tag} ({now} this week, {before:.1}/wk before)");
+        }
+    }
+
+    // Cooling down: had steady activity, this week under half of it
+    let mut cooling: Vec<(&u16, u32, f64)> = topics.iter()
+        .map(|(id, w)| (id, w[0], prior_avg(w)))
+        .filter(|&(_, now, before)| before >= 1.0 && (now as f64) < before / 2.0)
+        .collect();
+    cooling.sort_by(|a, b| (b.2 - b.1 as f64).total_cmp(&(a.2 - a.1 as f64)));
+    if !cooling.is_empty() {
+        section(&mut out, "Cooling Down", plain);
+        for (id, now, before) in cooling.iter().take(6) {
+            let _ = writeln!(out, "  {} ({now} this week, was {before:.1}/wk)", name(id));
+        }
+    }
+    Some(out)
+}
+
+/// Mean of the three weeks before this one.
+fn prior_avg(w: &Weekly) -> f64 {
+    (w[1] + w[2] + w[3]) as f64 / 3.0
+}
+
+fn arrow(w: &Weekly) -> &'static str {
+    let (now, before) = (w[0] as f64, prior_avg(w));
+    if now > before * 1.25 && now - before >= 1.0 { "↑" }
+    else if now < before * 0.75 && before - now >= 1.0 { "↓" }
+    else { "→" }
 }
 
 fn section(out: &mut String, title: &str, plain: bool) {
     if plain {
         let _ = writeln!(out, "\n== {title} ==");
     } else {
-        let _ = writeln!(out, "\n\x1b[1;35m== {title} ==\x1b[0m");
+        let _ = writeln!(out, "\n{}", paint(Role::Section, &format!("== {title} ==")));
     }
 }
 
@@ -0,0 +1,46 @@
+//! `correct <address> <text>`: the fast path when an entry (often one the
+//! ambient hook just injected) turns out to be wrong. The wrong entry is tagged
+//! `superseded`, the correction is stored in the same topic tagged `correction`
+//! and linked to it, and the wrong entry is marked injected in the current
+//! session so the hook doesn't surface it again before the session ends.
+
+use crate::error::AmrError;
+use crate::provenance::Provenance;
+use std::path::Path;
+
+pub const TAG: &str = "superseded";
+
+pub fn run(dir: &Path, topic: &str, idx: usize, text: &str, provenance: Option<Provenance>)
+    -> Result<String, AmrError>
+{
+    if text.trim().is_empty() { return Err(AmrError::InvalidArg("correction text is required".into())); }
+    crate::edit::tag_entry(dir, topic, Some(idx), None, Some(TAG), None)?;
+    // The retagged entry was rewritten to the end of its topic
+    let log_path = crate::config::log_path(dir);
+    let entries = crate::delete::topic_entries(&log_path, topic)?;
+    let wrong_idx = entries.len() - 1;
+    let wrong_offset = entries[wrong_idx].offset;
+    let link = format!("{topic}:{wrong_idx}");
+    let stored = crate::store::run_full_ext(dir, topic, text, &crate::store::StoreOptions {
+        force: true, links: Some(&link), provenance, ..crate::store::StoreOptions::tags("correction")
+    })?;
+    let session = match hide_in_session(dir, wrong_offset) {
+        Ok(true) => "hidden from ambient injection for this session",
+        Ok(false) => "not in the index; nothing to hide this session",
+        Err(_) => "session not updated",
+    };
+    Ok(format!("{link} tagged {TAG}, {session}\n{stored}"))
+}
+
+/// Mark the index entry at `log_offset` injected in the current session, so
+/// ambient dedup skips it. Entry ids are per build, hence the fresh index.
+fn hide_in_session(dir: &Path, log_offset: u32) -> Result<bool, AmrError> {
+    let Some(map) = crate::indexview::fresh(dir)? else { return Ok(false) };
+    let n = { crate::binquery::read_header(map.bytes())?.num_entries };
+    let Some(eid) = (0..n).find(|&e| crate::binquery::entry_log_offset(map.bytes(), e).ok() == Some(log_offset))
+        else { return Ok(false) };
+    let mut session = crate::session::Session::load_or_new(dir);
+    session.mark_injected(eid);
+    session.save(dir).map_err(AmrError::Io)?;
+    Ok(true)
+}
@@ -0,0 +1,60 @@
+//! Query cost cap for index search: a query may visit at most `max_postings`
+//! postings (setting, 0 = no cap). Past that, binquery drops its commonest
+//! terms before visiting any, keeping the rarest (highest idf) — big
+//! symbol-derived OR queries from hooks otherwise touch most of the corpus.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Postings a query may visit before its commonest terms are dropped.
+pub const DEFAULT_MAX_POSTINGS: usize = 200_000;
+
+static MAX_POSTINGS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_POSTINGS);
+
+thread_local! {
+    /// Why this thread's last query was truncated, if it was.
+    static TRUNCATED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Apply `dir`'s `max_postings` setting (0 = no cap) for this process.
+pub fn configure(dir: &std::path::Path) {
+    let cap = crate::settings::load(dir).get("max_postings")
+        .and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_MAX_POSTINGS);
+    MAX_POSTINGS.store(cap, Ordering::Relaxed);
+}
+
+/// Note left by the last over-cap query on this thread (which terms were
+/// dropped), cleared by reading it.
+pub fn take_truncation() -> Option<String> {
+    TRUNCATED.with(|t| t.borrow_mut().take())
+}
+
+/// Which of `terms` to keep under the configured cap, given each one's
+/// postings count (`lens`, None: not indexed). Leaves the truncation note.
+pub(crate) fn apply(terms: &[String], lens: &[Option<usize>]) -> Vec<bool> {
+    let cap = MAX_POSTINGS.load(Ordering::Relaxed);
+    let keep = within_cap(lens, cap);
+    let dropped: Vec<&str> = terms.iter().zip(&keep).filter(|(_, k)| !**k).map(|(t, _)| t.as_str()).collect();
+    TRUNCATED.with(|t| *t.borrow_mut() = (!dropped.is_empty()).then(|| format!(
+        "over the cost cap (max_postings = {cap}): kept the rarest {} of {} terms, dropped {}",
+        terms.len() - dropped.len(), terms.len(), dropped.join(" "))));
+    keep
+}
+
+/// Of postings lists `lens` (None: term not indexed), the ones to keep under
+/// `cap`: rarest first (highest idf), always at least one.
+fn within_cap(lens: &[Option<usize>], cap: usize) -> Vec<bool> {
+    let total: usize = lens.iter().flatten().sum();
+    if cap == 0 || total <= cap { return vec![true; lens.len()]; }
+    let mut order: Vec<usize> = (0..lens.len()).filter(|&i| lens[i].is_some()).collect();
+    order.sort_by_key(|&i| lens[i]);
+    let mut keep: Vec<bool> = lens.iter().map(Option::is_none).collect();
+    let mut spent = 0;
+    for (n, i) in order.into_iter().enumerate() {
+        let len = lens[i].unwrap_or(0);
+        if n > 0 && spent + len > cap { break; }
+        spent += len;
+        keep[i] = true;
+    }
+    keep
+}
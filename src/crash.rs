@@ -2,7 +2,7 @@
 //! in codebase, annotate causal chain with code context and crash cause patterns.
 
 use std::fmt::Write;
-use std::path::Path;
+use crate::codepath::Roots;
 
 struct Frame {
     func: String,
@@ -10,20 +10,16 @@ struct Frame {
     line: Option<usize>,
 }
 
-pub fn run(input: &str, path: &Path, glob_suffix: &str) -> Result<String, String> {
+pub fn run(input: &str, roots: &Roots, glob_suffix: &str) -> Result<String, String> {
     if input.is_empty() { return Err("input (crash/stack trace text) is required".into()); }
-    if !path.is_dir() { return Err(format!("{} is not a directory", path.display())); }
 
     let suffix = glob_suffix.trim_start_matches('*');
-    let mut fps = Vec::new();
-    crate::codepath::walk_files(path, suffix, &mut fps)?;
-    fps.sort();
+    let fps = roots.files(suffix)?;
 
     // Load all files
     let mut files: Vec<(String, String)> = Vec::new();
-    for fp in &fps {
-        let content = match std::fs::read_to_string(fp) { Ok(c) => c, Err(_) => continue };
-        let rel = fp.strip_prefix(path).unwrap_or(fp).to_string_lossy().to_string();
+    for (fp, rel) in fps {
+        let content = match std::fs::read_to_string(&fp) { Ok(c) => c, Err(_) => continue };
         files.push((rel, content));
     }
 
@@ -0,0 +1,137 @@
+//! `import --csv file --map topic=Team,text=Gotcha,tags=Area,date=Added`:
+//! one entry per spreadsheet row, for moving tribal knowledge (oncall
+//! runbooks, QA gotchas) in without ad-hoc scripts. The first row is the
+//! header; a mapped column is a header name (case-insensitive) or a 1-based
+//! column number. `text` is required; `topic` too unless a default topic is
+//! given. Tag cells split on commas or semicolons; dates take `YYYY-MM-DD`,
+//! `YYYY-MM-DD HH:MM` or ISO `T`-separated with seconds. Every row is checked
+//! before anything is written, so a bad date aborts the import with its row
+//! number instead of leaving half a sheet behind. Rows with empty text are
+//! skipped. Entries are written like JSON import: provenance `imported`.
+
+use crate::error::AmrError;
+use std::path::Path;
+
+/// Target field → column index, resolved against the header row.
+struct Mapping { topic: Option<usize>, text: usize, tags: Option<usize>, date: Option<usize>, source: Option<usize> }
+
+const FIELDS: &[&str] = &["topic", "text", "tags", "date", "source"];
+
+/// Import `csv` into `dir`. `map` is `field=column,...`; rows without a topic
+/// column (or with an empty cell) go to `default_topic`.
+pub fn import(dir: &Path, csv: &str, map: &str, default_topic: Option<&str>) -> Result<String, AmrError> {
+    let rows = parse(csv)?;
+    let Some((header, rows)) = rows.split_first() else {
+        return Err(AmrError::InvalidArg("CSV is empty".into()));
+    };
+    let m = mapping(map, header)?;
+    if m.topic.is_none() && default_topic.is_none() {
+        return Err(AmrError::InvalidArg("map a topic column (topic=COL) or pass a default topic".into()));
+    }
+    let cell = |row: &[String], col: Option<usize>| -> String {
+        col.and_then(|c| row.get(c)).map_or(String::new(), |s| s.trim().to_string())
+    };
+    let now = crate::time::LocalTime::now().to_minutes() as i32;
+    struct Row { topic: String, body: String, tags: Option<String>, ts: i32 }
+    let mut pending: Vec<Row> = Vec::new();
+    let mut skipped = 0;
+    for (i, row) in rows.iter().enumerate() {
+        // Row numbers as a spreadsheet shows them: header is row 1
+        let line = i + 2;
+        let text = cell(row, Some(m.text));
+        if text.is_empty() { skipped += 1; continue; }
+        let topic = match cell(row, m.topic) {
+            t if t.is_empty() => default_topic.map(String::from).ok_or_else(|| AmrError::InvalidArg(format!(
+                "row {line}: empty topic cell and no default topic")))?,
+            t => t,
+        };
+        let ts = match cell(row, m.date) {
+            d if d.is_empty() => now,
+            d => parse_date(&d).ok_or_else(|| AmrError::InvalidArg(format!(
+                "row {line}: can't read date '{d}' (expected YYYY-MM-DD [HH:MM])")))?,
+        };
+        let tags = cell(row, m.tags).replace(';', ",");
+        let source = cell(row, m.source);
+        let body = if source.is_empty() { text } else { format!("{}\n{text}", crate::entryfmt::line("source", &source)) };
+        pending.push(Row { topic: crate::config::sanitize_topic(&topic), body,
+            tags: (!tags.is_empty()).then_some(tags), ts });
+    }
+    let _lock = crate::lock::FileLock::acquire(dir)?;
+    let mut topics: Vec<&str> = Vec::new();
+    for r in &pending {
+        crate::store::import_entry(dir, &r.topic, &r.body, r.tags.as_deref(), r.ts)?;
+        if !topics.contains(&r.topic.as_str()) { topics.push(&r.topic); }
+    }
+    let mut out = format!("imported {} entries across {} topics", pending.len(), topics.len());
+    if skipped > 0 { out.push_str(&format!(" ({skipped} rows with empty text skipped)")); }
+    Ok(out)
+}
+
+fn mapping(map: &str, header: &[String]) -> Result<Mapping, AmrError> {
+    let mut m = Mapping { topic: None, text: usize::MAX, tags: None, date: None, source: None };
+    for pair in map.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (field, col) = pair.split_once('=').ok_or_else(|| AmrError::InvalidArg(format!(
+            "bad mapping '{pair}' (expected field=column)")))?;
+        let col = column(col.trim(), header)?;
+        match field.trim().to_ascii_lowercase().as_str() {
+            "topic" => m.topic = Some(col),
+            "text" => m.text = col,
+            "tags" => m.tags = Some(col),
+            "date" => m.date = Some(col),
+            "source" => m.source = Some(col),
+            f => return Err(AmrError::InvalidArg(format!("unknown field '{f}' (fields: {})", FIELDS.join(", ")))),
+        }
+    }
+    if m.text == usize::MAX { return Err(AmrError::InvalidArg("map a text column (text=COL)".into())); }
+    Ok(m)
+}
+
+/// Header name (case-insensitive) or 1-based column number.
+fn column(name: &str, header: &[String]) -> Result<usize, AmrError> {
+    if let Some(i) = header.iter().position(|h| h.trim().eq_ignore_ascii_case(name)) { return Ok(i); }
+    match name.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= header.len() => Ok(n - 1),
+        _ => Err(AmrError::InvalidArg(format!("no column '{name}' (header: {})", header.join(", ")))),
+    }
+}
+
+/// `YYYY-MM-DD`, `YYYY-MM-DD HH:MM`, `YYYY-MM-DDTHH:MM[:SS...]`.
+fn parse_date(s: &str) -> Option<i32> {
+    let s = s.replacen('T', " ", 1);
+    let s = s.get(..16).filter(|p| p.as_bytes()[13] == b':').unwrap_or(&s);
+    crate::time::parse_date_minutes(s).map(|m| m as i32)
+}
+
+/// RFC 4180 rows: quoted fields may hold commas, newlines and `""` escapes.
+fn parse(csv: &str) -> Result<Vec<Vec<String>>, AmrError> {
+    let csv = csv.strip_prefix('\u{feff}').unwrap_or(csv);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => { chars.next(); field.push('"'); }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) { rows.push(std::mem::take(&mut row)); } else { row.clear(); }
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted { return Err(AmrError::InvalidArg("CSV ends inside a quoted field".into())); }
+    row.push(field);
+    if row.iter().any(|f| !f.is_empty()) { rows.push(row); }
+    Ok(rows)
+}
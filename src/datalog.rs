@@ -5,10 +5,11 @@
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::fs::{self, File, OpenOptions};
 use std::path::{Path, PathBuf};
+use crate::error::AmrError;
 
 pub const LOG_MAGIC: [u8; 4] = *b"AMRL";
 pub const LOG_VERSION: u32 = 1;
-const LOG_HEADER_SIZE: u64 = 8;
+pub const LOG_HEADER_SIZE: u64 = 8;
 const ENTRY_HEADER_SIZE: usize = 12;
 const DELETE_RECORD_SIZE: usize = 8;
 
@@ -21,62 +22,62 @@ pub struct LogEntry {
 }
 
 /// Create data.log with header if absent. Returns path.
-pub fn ensure_log(dir: &Path) -> Result<PathBuf, String> {
+pub fn ensure_log(dir: &Path) -> Result<PathBuf, AmrError> {
     let path = dir.join("data.log");
     if path.exists() { return Ok(path); }
-    let mut f = File::create(&path).map_err(|e| format!("create data.log: {e}"))?;
-    f.write_all(&LOG_MAGIC).map_err(|e| e.to_string())?;
-    f.write_all(&LOG_VERSION.to_le_bytes()).map_err(|e| e.to_string())?;
-    f.sync_all().map_err(|e| e.to_string())?;
+    let mut f = File::create(&path).map_err(|e| AmrError::io("create data.log", e))?;
+    f.write_all(&LOG_MAGIC)?;
+    f.write_all(&LOG_VERSION.to_le_bytes())?;
+    f.sync_all()?;
     Ok(path)
 }
 
 /// Append one entry. Returns log offset of the written record.
-pub fn append_entry(log_path: &Path, topic: &str, body: &str, ts_min: i32) -> Result<u32, String> {
+pub fn append_entry(log_path: &Path, topic: &str, body: &str, ts_min: i32) -> Result<u32, AmrError> {
     let mut f = OpenOptions::new().append(true).open(log_path)
-        .map_err(|e| format!("open data.log: {e}"))?;
-    let offset = f.seek(SeekFrom::End(0)).map_err(|e| e.to_string())? as u32;
+        .map_err(|e| AmrError::io("open data.log", e))?;
+    let offset = f.seek(SeekFrom::End(0))? as u32;
     let tb = topic.as_bytes();
     let bb = body.as_bytes();
     let hdr: [u8; ENTRY_HEADER_SIZE] = entry_header(tb.len() as u8, bb.len() as u32, ts_min);
-    f.write_all(&hdr).map_err(|e| e.to_string())?;
-    f.write_all(tb).map_err(|e| e.to_string())?;
-    f.write_all(bb).map_err(|e| e.to_string())?;
-    f.sync_data().map_err(|e| e.to_string())?;
+    f.write_all(&hdr)?;
+    f.write_all(tb)?;
+    f.write_all(bb)?;
+    f.sync_data()?;
     Ok(offset)
 }
 
 /// Append a delete tombstone referencing target entry offset.
-pub fn append_delete(log_path: &Path, target_offset: u32) -> Result<(), String> {
+pub fn append_delete(log_path: &Path, target_offset: u32) -> Result<(), AmrError> {
     let mut f = OpenOptions::new().append(true).open(log_path)
-        .map_err(|e| format!("open data.log: {e}"))?;
+        .map_err(|e| AmrError::io("open data.log", e))?;
     let mut rec = [0u8; DELETE_RECORD_SIZE];
     rec[0] = 0x02;
     rec[4..8].copy_from_slice(&target_offset.to_le_bytes());
-    f.write_all(&rec).map_err(|e| e.to_string())?;
-    f.sync_data().map_err(|e| e.to_string())?;
+    f.write_all(&rec)?;
+    f.sync_data()?;
     Ok(())
 }
 
 /// Read a single entry from log at given offset.
-pub fn read_entry(log_path: &Path, offset: u32) -> Result<LogEntry, String> {
-    let mut f = File::open(log_path).map_err(|e| format!("open data.log: {e}"))?;
+pub fn read_entry(log_path: &Path, offset: u32) -> Result<LogEntry, AmrError> {
+    let mut f = File::open(log_path).map_err(|e| AmrError::io("open data.log", e))?;
     read_entry_from(&mut f, offset)
 }
 
 /// Read a single entry from an already-open file handle (avoids re-open per call).
-pub fn read_entry_from(f: &mut File, offset: u32) -> Result<LogEntry, String> {
-    f.seek(SeekFrom::Start(offset as u64)).map_err(|e| e.to_string())?;
+pub fn read_entry_from(f: &mut File, offset: u32) -> Result<LogEntry, AmrError> {
+    f.seek(SeekFrom::Start(offset as u64))?;
     let mut hdr = [0u8; ENTRY_HEADER_SIZE];
-    f.read_exact(&mut hdr).map_err(|e| format!("read entry header: {e}"))?;
-    if hdr[0] != 0x01 { return Err("not an entry record".into()); }
+    f.read_exact(&mut hdr).map_err(|e| AmrError::io("read entry header", e))?;
+    if hdr[0] != 0x01 { return Err(AmrError::Corrupt("not an entry record".into())); }
     let topic_len = hdr[1] as usize;
     let body_len = u32::from_le_bytes([hdr[2], hdr[3], hdr[4], hdr[5]]) as usize;
     let ts_min = i32::from_le_bytes([hdr[6], hdr[7], hdr[8], hdr[9]]);
     let mut topic_buf = vec![0u8; topic_len];
-    f.read_exact(&mut topic_buf).map_err(|e| e.to_string())?;
+    f.read_exact(&mut topic_buf)?;
     let mut body_buf = vec![0u8; body_len];
-    f.read_exact(&mut body_buf).map_err(|e| e.to_string())?;
+    f.read_exact(&mut body_buf)?;
     Ok(LogEntry {
         offset,
         topic: String::from_utf8_lossy(&topic_buf).into(),
@@ -85,15 +86,82 @@ pub fn read_entry_from(f: &mut File, offset: u32) -> Result<LogEntry, String> {
     })
 }
 
+/// Borrowed view of one entry record inside a mapped/loaded data.log.
+pub struct EntryRef<'a> {
+    pub topic: &'a str,
+    pub body: &'a str,
+    pub timestamp_min: i32,
+}
+
+/// Parse the entry at `offset` without copying. None if out of bounds,
+/// not an entry record, or not valid UTF-8 (callers fall back to read_entry_from).
+#[inline]
+pub fn entry_ref(data: &[u8], offset: u32) -> Option<EntryRef<'_>> {
+    let pos = offset as usize;
+    let hdr = data.get(pos..pos.checked_add(ENTRY_HEADER_SIZE)?)?;
+    if hdr[0] != 0x01 { return None; }
+    let tl = hdr[1] as usize;
+    let bl = u32::from_le_bytes([hdr[2], hdr[3], hdr[4], hdr[5]]) as usize;
+    let ts = i32::from_le_bytes([hdr[6], hdr[7], hdr[8], hdr[9]]);
+    let t_start = pos + ENTRY_HEADER_SIZE;
+    let topic = std::str::from_utf8(data.get(t_start..t_start + tl)?).ok()?;
+    let body = std::str::from_utf8(data.get(t_start + tl..t_start + tl + bl)?).ok()?;
+    Some(EntryRef { topic, body, timestamp_min: ts })
+}
+
+/// Length of the complete record starting at `pos`. None if the record is
+/// cut short (still being appended) or `pos` isn't a record.
+pub fn record_len(data: &[u8], pos: usize) -> Option<usize> {
+    match *data.get(pos)? {
+        0x01 => {
+            let hdr = data.get(pos..pos + ENTRY_HEADER_SIZE)?;
+            let bl = u32::from_le_bytes([hdr[2], hdr[3], hdr[4], hdr[5]]) as usize;
+            let len = ENTRY_HEADER_SIZE + hdr[1] as usize + bl;
+            (pos + len <= data.len()).then_some(len)
+        }
+        0x02 => (pos + DELETE_RECORD_SIZE <= data.len()).then_some(DELETE_RECORD_SIZE),
+        _ => None,
+    }
+}
+
 /// Iterate all live entries (skipping tombstoned ones).
 /// Single-pass: collects entries and deleted offsets simultaneously, then filters.
-pub fn iter_live(log_path: &Path) -> Result<Vec<LogEntry>, String> {
-    let data = fs::read(log_path).map_err(|e| format!("read data.log: {e}"))?;
-    if data.len() < LOG_HEADER_SIZE as usize { return Err("data.log too small".into()); }
-    if data[..4] != LOG_MAGIC { return Err("bad data.log magic".into()); }
+pub fn iter_live(log_path: &Path) -> Result<Vec<LogEntry>, AmrError> {
+    let (mut entries, deleted) = scan(log_path)?;
+    if !deleted.is_empty() {
+        entries.retain(|e| !deleted.contains_key(&e.offset));
+    }
+    Ok(entries)
+}
+
+/// Entries a tombstone removed, in log order, each with the offset of the
+/// entry that replaced it: edits, renames and merges append the new version
+/// with the same timestamp right before tombstoning the old one, so a
+/// tombstone directly after such a record marks a replacement; otherwise the
+/// entry was deleted. Compaction drops them, so this is history since the
+/// last compact.
+pub fn iter_dead(log_path: &Path) -> Result<Vec<(LogEntry, Option<u32>)>, AmrError> {
+    let (entries, deleted) = scan(log_path)?;
+    // Record end offset → (offset, timestamp) of the entry ending there
+    let ends: crate::fxhash::FxHashMap<u32, (u32, i32)> = entries.iter().map(|e| {
+        let len = ENTRY_HEADER_SIZE + e.topic.len() + e.body.len();
+        (e.offset + len as u32, (e.offset, e.timestamp_min))
+    }).collect();
+    Ok(entries.into_iter().filter_map(|e| {
+        let tomb = *deleted.get(&e.offset)?;
+        let by = ends.get(&tomb).filter(|(o, ts)| *ts == e.timestamp_min && *o != e.offset).map(|(o, _)| *o);
+        Some((e, by))
+    }).collect())
+}
+
+/// Every entry record, and tombstoned offset → position of its tombstone.
+fn scan(log_path: &Path) -> Result<(Vec<LogEntry>, crate::fxhash::FxHashMap<u32, u32>), AmrError> {
+    let data = fs::read(log_path).map_err(|e| AmrError::io("read data.log", e))?;
+    if data.len() < LOG_HEADER_SIZE as usize { return Err(AmrError::Corrupt("data.log too small".into())); }
+    if data[..4] != LOG_MAGIC { return Err(AmrError::Corrupt("bad data.log magic".into())); }
 
     let mut entries = Vec::new();
-    let mut deleted = crate::fxhash::FxHashSet::default();
+    let mut deleted = crate::fxhash::FxHashMap::default();
     let mut pos = LOG_HEADER_SIZE as usize;
 
     while pos < data.len() {
@@ -130,30 +198,26 @@ pub fn iter_live(log_path: &Path) -> Result<Vec<LogEntry>, String> {
                 let target = u32::from_le_bytes([
                     data[pos+4], data[pos+5], data[pos+6], data[pos+7]
                 ]);
-                deleted.insert(target);
+                deleted.insert(target, pos as u32);
                 pos += DELETE_RECORD_SIZE;
             }
             _ => break,
         }
     }
-
-    if !deleted.is_empty() {
-        entries.retain(|e| !deleted.contains(&e.offset));
-    }
-    Ok(entries)
+    Ok((entries, deleted))
 }
 
 /// Migrate .md files into data.log. Returns entry count.
-pub fn migrate_from_md(dir: &Path) -> Result<usize, String> {
+pub fn migrate_from_md(dir: &Path) -> Result<usize, AmrError> {
     let log_path = ensure_log(dir)?;
-    let files = crate::config::list_topic_files(dir)?;
+    let files = crate::config::list_topic_files(dir).map_err(AmrError::Io)?;
     let mut count = 0;
     for path in &files {
-        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let content = fs::read_to_string(path)?;
         let name = path.file_stem().unwrap().to_string_lossy().to_string();
         let sections = crate::delete::split_sections(&content);
         for (header, body) in &sections {
-            let ts_str = header.strip_prefix("## ").unwrap_or("");
+            let ts_str = crate::entryfmt::header_date(header).unwrap_or("");
             let ts_min = crate::time::parse_date_minutes(ts_str).unwrap_or(0) as i32;
             let body_text = body.strip_prefix('\n').unwrap_or(body).trim_end();
             append_entry(&log_path, &name, body_text, ts_min)?;
@@ -163,41 +227,102 @@ pub fn migrate_from_md(dir: &Path) -> Result<usize, String> {
     Ok(count)
 }
 
-/// Compact: rewrite data.log without deleted entries.
-pub fn compact_log(dir: &Path) -> Result<String, String> {
+/// Old → new offsets of live entries from the last compaction, and the
+/// data.log generation that compaction produced. Persisted as `data.log.remap`
+/// (`generation N` line, then one `old new` pair per line, ascending by old) so
+/// anything holding offsets from before — a stale index.bin, an external
+/// consumer — can translate them instead of silently reading the wrong entry.
+pub struct Remap {
+    pub generation: u64,
+    pub offsets: Vec<(u32, u32)>,
+}
+
+impl Remap {
+    /// New offset for a pre-compaction one; None if that entry was dropped.
+    pub fn get(&self, old: u32) -> Option<u32> {
+        self.offsets.binary_search_by_key(&old, |&(o, _)| o).ok().map(|i| self.offsets[i].1)
+    }
+}
+
+fn remap_path(dir: &Path) -> PathBuf { dir.join("data.log.remap") }
+
+/// The remap written by the last compaction. None if data.log was never compacted.
+pub fn load_remap(dir: &Path) -> Option<Remap> {
+    let text = fs::read_to_string(remap_path(dir)).ok()?;
+    let mut lines = text.lines();
+    let generation = lines.next()?.strip_prefix("generation ")?.trim().parse().ok()?;
+    let offsets = lines.filter_map(|l| {
+        let (a, b) = l.split_once(' ')?;
+        Some((a.parse().ok()?, b.parse().ok()?))
+    }).collect();
+    Some(Remap { generation, offsets })
+}
+
+/// data.log generation: bumped by every compaction, 0 if never compacted.
+/// Offsets are only comparable within one generation.
+pub fn log_generation(dir: &Path) -> u64 {
+    fs::read_to_string(remap_path(dir)).ok()
+        .and_then(|t| t.lines().next()?.strip_prefix("generation ")?.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Compact: rewrite data.log without deleted entries, bump the log generation
+/// and persist the old → new offset remap. A corpus cache or index.bin that
+/// was current before is patched with the new offsets rather than rebuilt;
+/// reads.log is translated the same way. Callers hold the directory lock.
+pub fn compact_log(dir: &Path) -> Result<String, AmrError> {
     let log_path = dir.join("data.log");
     let entries = iter_live(&log_path)?;
     let before = fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+    let old_mtime = fs::metadata(&log_path).and_then(|m| m.modified()).ok();
+    let index_current = crate::indexview::is_current(dir);
     // Write to tmp, rename over
     let tmp = dir.join("data.log.tmp");
+    let mut offsets = Vec::with_capacity(entries.len());
     {
-        let mut f = File::create(&tmp).map_err(|e| e.to_string())?;
-        f.write_all(&LOG_MAGIC).map_err(|e| e.to_string())?;
-        f.write_all(&LOG_VERSION.to_le_bytes()).map_err(|e| e.to_string())?;
-        for e in &entries {
+        let mut f = File::create(&tmp)?;
+        f.write_all(&LOG_MAGIC)?;
+        f.write_all(&LOG_VERSION.to_le_bytes())?;
+        let mut pos = LOG_HEADER_SIZE as u32;
+        let total = entries.len() as u64;
+        for (i, e) in entries.iter().enumerate() {
+            crate::progress::report(i as u64, Some(total), "rewriting data.log");
             let tb = e.topic.as_bytes();
             let bb = e.body.as_bytes();
             let hdr = entry_header(tb.len() as u8, bb.len() as u32, e.timestamp_min);
-            f.write_all(&hdr).map_err(|e| e.to_string())?;
-            f.write_all(tb).map_err(|e| e.to_string())?;
-            f.write_all(bb).map_err(|e| e.to_string())?;
+            f.write_all(&hdr)?;
+            f.write_all(tb)?;
+            f.write_all(bb)?;
+            offsets.push((e.offset, pos));
+            pos += (ENTRY_HEADER_SIZE + tb.len() + bb.len()) as u32;
         }
-        f.sync_all().map_err(|e| e.to_string())?;
+        f.sync_all()?;
     }
-    fs::rename(&tmp, &log_path).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &log_path)?;
+    let remap = Remap { generation: log_generation(dir) + 1, offsets };
+    let mut text = format!("generation {}\n", remap.generation);
+    for (a, b) in &remap.offsets { text.push_str(&format!("{a} {b}\n")); }
+    let rtmp = dir.join("data.log.remap.tmp");
+    fs::write(&rtmp, text).and_then(|_| fs::rename(&rtmp, remap_path(dir)))
+        .map_err(|e| AmrError::io("write data.log.remap", e))?;
+    crate::progress::report(entries.len() as u64, Some(entries.len() as u64), "data.log compacted");
+    if let Some(m) = old_mtime { crate::cache::remap_offsets(dir, m, &remap); }
+    crate::importance::remap_reads(dir, &remap).map_err(|e| AmrError::io("rewrite reads.log", e))?;
+    let index = if index_current && crate::indexview::remap(dir, &remap).is_ok() { ", index.bin patched" } else { "" };
     let after = fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
-    Ok(format!("compacted: {} entries, {} → {} bytes", entries.len(), before, after))
+    Ok(format!("compacted: {} entries, {} → {} bytes (data.log generation {}{index})",
+        entries.len(), before, after, remap.generation))
 }
 
 /// Append one entry to an already-open file handle (no fsync). For batch writes.
-pub fn append_entry_to(f: &mut File, topic: &str, body: &str, ts_min: i32) -> Result<u32, String> {
-    let offset = f.seek(SeekFrom::End(0)).map_err(|e| e.to_string())? as u32;
+pub fn append_entry_to(f: &mut File, topic: &str, body: &str, ts_min: i32) -> Result<u32, AmrError> {
+    let offset = f.seek(SeekFrom::End(0))? as u32;
     let tb = topic.as_bytes();
     let bb = body.as_bytes();
     let hdr: [u8; ENTRY_HEADER_SIZE] = entry_header(tb.len() as u8, bb.len() as u32, ts_min);
-    f.write_all(&hdr).map_err(|e| e.to_string())?;
-    f.write_all(tb).map_err(|e| e.to_string())?;
-    f.write_all(bb).map_err(|e| e.to_string())?;
+    f.write_all(&hdr)?;
+    f.write_all(tb)?;
+    f.write_all(bb)?;
     Ok(offset)
 }
 
@@ -0,0 +1,156 @@
+//! Decision log: structured decision records. A decision is an entry tagged
+//! `decision` whose body carries `[status: proposed|accepted|superseded]`,
+//! optional `[alternatives: A | B]` (what was considered and not chosen) and
+//! `[links: …]` to the entries that implement it. `decisions` lists records
+//! grouped by status. Recording one with `supersedes` marks the old record
+//! superseded and writes `[supersedes: topic:uid:HEX]` on the new one, so the
+//! replacement chain reads from either end. Decision-tagged entries written
+//! before this (no status line) count as accepted.
+
+use crate::error::AmrError;
+use crate::provenance::Provenance;
+use std::fmt::Write;
+use std::path::Path;
+
+pub const TAG: &str = "decision";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Status { Proposed, Accepted, Superseded }
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Proposed, Status::Accepted, Status::Superseded];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "proposed" => Some(Status::Proposed),
+            "accepted" => Some(Status::Accepted),
+            "superseded" => Some(Status::Superseded),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self { Status::Proposed => "proposed", Status::Accepted => "accepted", Status::Superseded => "superseded" }
+    }
+}
+
+/// Status recorded in a body; None when the entry has no status line.
+pub fn status_of(body: &str) -> Option<Status> {
+    crate::entryfmt::field(body, "status").and_then(Status::parse)
+}
+
+/// What `record` writes besides the text.
+pub struct Record<'a> {
+    pub status: Status,
+    /// Options considered and not chosen.
+    pub alternatives: Vec<&'a str>,
+    /// Space-separated `topic:index` of implementing entries.
+    pub links: Option<&'a str>,
+    /// (topic, index) of the decision this one replaces.
+    pub supersedes: Option<(&'a str, usize)>,
+    pub provenance: Option<Provenance>,
+}
+
+/// Store a decision record in `topic`. `supersedes` is marked superseded
+/// first, then linked from the new record.
+pub fn record(dir: &Path, topic: &str, text: &str, r: &Record) -> Result<String, AmrError> {
+    let status = r.status;
+    if text.trim().is_empty() { return Err(AmrError::InvalidArg("decision text is required".into())); }
+    if status == Status::Superseded {
+        return Err(AmrError::InvalidArg("a new decision can't start superseded".into()));
+    }
+    use crate::entryfmt::line;
+    let mut head = format!("{}\n", line("status", status.as_str()));
+    let mut links: Vec<String> = r.links.map(|l| l.split_whitespace().map(String::from).collect()).unwrap_or_default();
+    let mut note = String::new();
+    if let Some((old_topic, old_idx)) = r.supersedes {
+        let (idx, uid) = set_status_inner(dir, old_topic, old_idx, Status::Superseded)?;
+        let _ = writeln!(head, "{}", line("supersedes", &format!("{old_topic}:uid:{}", crate::address::format_uid(uid))));
+        links.push(format!("{old_topic}:{idx}"));
+        note = format!("\n{old_topic}:{idx} marked superseded");
+    }
+    let alternatives: Vec<&str> = r.alternatives.iter().map(|a| a.trim()).filter(|a| !a.is_empty()).collect();
+    if !alternatives.is_empty() { let _ = writeln!(head, "{}", line("alternatives", &alternatives.join(" | "))); }
+    let links = links.join(" ");
+    let stored = crate::store::run_full_ext(dir, topic, &format!("{head}{}", text.trim()), &crate::store::StoreOptions {
+        tags: Some(TAG), force: true, links: (!links.is_empty()).then_some(links.as_str()), provenance: r.provenance,
+        ..Default::default()
+    })?;
+    Ok(format!("{stored}{note}"))
+}
+
+/// Change the status of decision `topic:idx` (e.g. proposed → accepted).
+pub fn set_status(dir: &Path, topic: &str, idx: usize, status: Status) -> Result<String, AmrError> {
+    let (new_idx, _) = set_status_inner(dir, topic, idx, status)?;
+    Ok(format!("{topic}:{new_idx} is now {}", status.as_str()))
+}
+
+/// Rewrite the status line (append + tombstone, timestamp kept; the entry
+/// moves to the end of its topic). Returns its new index and uid.
+fn set_status_inner(dir: &Path, topic: &str, idx: usize, status: Status) -> Result<(usize, u64), AmrError> {
+    let _lock = crate::lock::FileLock::acquire(dir)?;
+    let log_path = crate::config::log_path(dir);
+    let entries = crate::delete::topic_entries(&log_path, topic)?;
+    let e = entries.get(idx).ok_or_else(|| AmrError::NotFound(format!(
+        "index {idx} out of range (topic has {} entries)", entries.len())))?;
+    let tagged = e.body.lines().any(|l| crate::text::parse_tags_raw(Some(l)).iter().any(|t| t.eq_ignore_ascii_case(TAG)));
+    if !tagged { return Err(AmrError::InvalidArg(format!("{topic}:{idx} is not a decision (no '{TAG}' tag)"))); }
+    let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+    let last = entries.len() - 1;
+    if status_of(&e.body) == Some(status) { return Ok((idx, uid)); }
+    let body = crate::entryfmt::set(&e.body, "status", Some(status.as_str()));
+    crate::datalog::append_entry(&log_path, topic, &body, e.timestamp_min)?;
+    crate::datalog::append_delete(&log_path, e.offset)?;
+    Ok((last, uid))
+}
+
+/// Decision records grouped by status, optionally only one status.
+pub fn list(dir: &Path, only: Option<Status>) -> Result<String, AmrError> {
+    struct Rec { addr: String, uid_addr: String, date: String, status: Status, line: String,
+                 alternatives: Option<String>, supersedes: Option<String>, links: Vec<String> }
+    let recs = crate::cache::with_corpus(dir, |cached| {
+        let mut counters: crate::fxhash::FxHashMap<&str, usize> = crate::fxhash::FxHashMap::default();
+        let mut recs: Vec<Rec> = Vec::new();
+        for e in cached {
+            let slot = counters.entry(e.topic.as_str()).or_default();
+            let idx = *slot;
+            *slot += 1;
+            if !e.has_tag(TAG) { continue; }
+            let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+            recs.push(Rec {
+                addr: format!("{}:{idx}", e.topic),
+                uid_addr: format!("{}:uid:{}", e.topic, crate::address::format_uid(uid)),
+                date: e.date_str().get(..10).unwrap_or_default().to_string(),
+                status: status_of(&e.body).unwrap_or(Status::Accepted),
+                line: crate::text::truncate(crate::compress::first_content(&e.body).trim(), 90).to_string(),
+                alternatives: crate::entryfmt::field(&e.body, "alternatives").map(String::from),
+                supersedes: crate::entryfmt::field(&e.body, "supersedes").map(String::from),
+                links: e.links().iter().map(|(t, i)| format!("{t}:{i}")).collect(),
+            });
+        }
+        recs
+    })?;
+    if recs.is_empty() { return Ok(format!("no decisions recorded (store with tags '{TAG}', or use decide)\n")); }
+
+    let count = |s: Status| recs.iter().filter(|r| r.status == s).count();
+    let mut out = format!("{} decisions: {} proposed, {} accepted, {} superseded\n",
+        recs.len(), count(Status::Proposed), count(Status::Accepted), count(Status::Superseded));
+    for status in Status::ALL {
+        if only.is_some_and(|o| o != status) || count(status) == 0 { continue; }
+        let _ = writeln!(out, "\n{} ({})", status.as_str().to_uppercase(), count(status));
+        for r in recs.iter().filter(|r| r.status == status) {
+            let _ = writeln!(out, "  [{}] {} {}", r.addr, r.date, r.line);
+            if let Some(a) = &r.alternatives { let _ = writeln!(out, "    alternatives: {a}"); }
+            if let Some(s) = &r.supersedes { let _ = writeln!(out, "    supersedes {s}"); }
+            let by: Vec<&str> = recs.iter()
+                .filter(|n| n.supersedes.as_deref() == Some(r.uid_addr.as_str()))
+                .map(|n| n.addr.as_str()).collect();
+            if !by.is_empty() { let _ = writeln!(out, "    superseded by {}", by.join(", ")); }
+            // Links to other decisions are the supersede chain, shown above
+            let implemented: Vec<&str> = r.links.iter().map(String::as_str)
+                .filter(|l| !recs.iter().any(|o| o.addr == *l)).collect();
+            if !implemented.is_empty() { let _ = writeln!(out, "    implemented by {}", implemented.join(", ")); }
+        }
+    }
+    Ok(out)
+}
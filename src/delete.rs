@@ -1,12 +1,13 @@
+use crate::error::AmrError;
 use std::path::Path;
 
 /// Delete entries from a topic via data.log tombstones.
-pub fn run(dir: &Path, topic: &str, last: bool, all: bool, match_str: Option<&str>) -> Result<String, String> {
+pub fn run(dir: &Path, topic: &str, last: bool, all: bool, match_str: Option<&str>) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = topic_entries(&log_path, topic)?;
 
-    if entries.is_empty() { return Err(format!("topic '{}' not found", topic)); }
+    if entries.is_empty() { return Err(AmrError::NotFound(format!("topic '{}' not found", topic))); }
 
     if all {
         for e in &entries { crate::datalog::append_delete(&log_path, e.offset)?; }
@@ -16,7 +17,7 @@ pub fn run(dir: &Path, topic: &str, last: bool, all: bool, match_str: Option<&st
     if let Some(needle) = match_str {
         let lower = needle.to_lowercase();
         let entry = entries.iter().find(|e| e.body.to_lowercase().contains(&lower))
-            .ok_or_else(|| format!("no entry matching \"{}\"", needle))?;
+            .ok_or_else(|| AmrError::NotFound(format!("no entry matching \"{}\"", needle)))?;
         crate::datalog::append_delete(&log_path, entry.offset)?;
         return Ok(format!("removed entry matching \"{}\" from {} ({} remaining)",
             needle, topic, entries.len() - 1));
@@ -29,15 +30,42 @@ pub fn run(dir: &Path, topic: &str, last: bool, all: bool, match_str: Option<&st
     Ok(format!("removed last entry from {} ({} remaining)", topic, entries.len() - 1))
 }
 
+/// `run` over every topic matching `pattern` (name, glob or /regex/). A
+/// pattern that matches more than one topic needs `confirm`; with `match_str`,
+/// topics without a matching entry are skipped.
+pub fn run_pattern(dir: &Path, pattern: &str, last: bool, all: bool, match_str: Option<&str>,
+                   confirm: bool) -> Result<String, AmrError> {
+    let names = crate::pattern::expand(dir, pattern)?;
+    if let [name] = names.as_slice() { return run(dir, name, last, all, match_str); }
+    if !confirm {
+        return Err(AmrError::InvalidArg(format!(
+            "{pattern} matches {} topics ({}); pass confirm=true to delete across all of them",
+            names.len(), names.join(", "))));
+    }
+    let mut lines = Vec::new();
+    for name in &names {
+        match run(dir, name, last, all, match_str) {
+            Ok(line) => lines.push(line),
+            Err(AmrError::NotFound(_)) if match_str.is_some() => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if lines.is_empty() {
+        return Err(AmrError::NotFound(format!("no entry matching \"{}\" in {pattern}",
+            match_str.unwrap_or_default())));
+    }
+    Ok(lines.join("\n"))
+}
+
 /// Delete entry by 0-based index.
-pub fn run_by_index(dir: &Path, topic: &str, idx: usize) -> Result<String, String> {
+pub fn run_by_index(dir: &Path, topic: &str, idx: usize) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = topic_entries(&log_path, topic)?;
 
     if idx >= entries.len() {
-        return Err(format!("index {idx} out of range (topic has {} entries, 0-{})",
-            entries.len(), entries.len().saturating_sub(1)));
+        return Err(AmrError::NotFound(format!("index {idx} out of range (topic has {} entries, 0-{})",
+            entries.len(), entries.len().saturating_sub(1))));
     }
 
     crate::datalog::append_delete(&log_path, entries[idx].offset)?;
@@ -45,7 +73,7 @@ pub fn run_by_index(dir: &Path, topic: &str, idx: usize) -> Result<String, Strin
 }
 
 /// Get all live entries for a topic from data.log, in log order.
-pub fn topic_entries(log_path: &Path, topic: &str) -> Result<Vec<crate::datalog::LogEntry>, String> {
+pub fn topic_entries(log_path: &Path, topic: &str) -> Result<Vec<crate::datalog::LogEntry>, AmrError> {
     let all = crate::datalog::iter_live(log_path)?;
     Ok(all.into_iter().filter(|e| e.topic == topic).collect())
 }
@@ -165,5 +165,5 @@ fn run_via_corpus(dir: &Path, focus: Option<&str>) -> Result<String, String> {
             let _ = writeln!(out);
         }
         out
-    })
+    }).map_err(Into::into)
 }
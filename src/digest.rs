@@ -1,15 +1,25 @@
+//! MEMORY.md digest: one bullet per entry, grouped by topic.
+//! Every run saves a snapshot (`digest.snapshot`: uid, timestamp, topic,
+//! bullet per entry) so `--since last` can show only what changed since the
+//! previous digest: added, superseded (same entry revised in place), moved
+//! (renamed/merged topic) and removed. `--since <date>` lists entries written
+//! on or after that date; removals are only known relative to a snapshot.
+
+use crate::cache::CachedEntry;
+use crate::fxhash::FxHashMap;
+use std::collections::BTreeMap;
 use std::fmt::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub fn run(dir: &Path) -> Result<String, String> {
     let log_path = crate::config::log_path(dir);
     if !log_path.exists() { return Ok("no data.log found\n".into()); }
     crate::cache::with_corpus(dir, |cached| {
         if cached.is_empty() { return "no entries\n".into(); }
+        save_snapshot(dir, cached);
         // Group by topic preserving order
         let mut topic_order: Vec<String> = Vec::new();
-        let mut grouped: std::collections::BTreeMap<&str, Vec<&crate::cache::CachedEntry>> =
-            std::collections::BTreeMap::new();
+        let mut grouped: BTreeMap<&str, Vec<&CachedEntry>> = BTreeMap::new();
         for e in cached {
             if !grouped.contains_key(e.topic.as_str()) { topic_order.push(e.topic.to_string()); }
             grouped.entry(e.topic.as_str()).or_default().push(e);
@@ -22,11 +32,254 @@ pub fn run(dir: &Path) -> Result<String, String> {
                 .unwrap_or_else(|| "empty".into());
             let _ = writeln!(out, "### {} ({} entries, last: {})", name, group.len(), latest);
             for e in group {
-                let preview = e.preview();
-                let preview = if preview.is_empty() { "(empty)" } else { preview };
-                let _ = writeln!(out, "- {}", crate::text::truncate(preview.trim().trim_start_matches("- "), 100));
+                let _ = writeln!(out, "- {}", bullet(e));
             }
         }
         out
-    })
+    }).map_err(Into::into)
+}
+
+/// Digest of changes only. `since` is "last" (the previous digest's snapshot)
+/// or a date ("YYYY-MM-DD [HH:MM]", or a shortcut like "yesterday").
+pub fn since(dir: &Path, since: &str) -> Result<String, String> {
+    let log_path = crate::config::log_path(dir);
+    if !log_path.exists() { return Ok("no data.log found\n".into()); }
+    let (label, baseline) = if since == "last" {
+        let snap = load_snapshot(dir)
+            .ok_or("no previous digest snapshot — run `digest` once first")?;
+        (format!("last digest ({})", crate::time::minutes_to_date_str(snap.taken)), Ok(snap))
+    } else {
+        let date = crate::time::resolve_date_shortcut(since);
+        let min = crate::time::parse_date_minutes(&date)
+            .ok_or_else(|| format!("--since: expected a date or 'last', got '{since}'"))?;
+        (date, Err(min))
+    };
+    crate::cache::with_corpus(dir, |cached| {
+        let changes = match &baseline {
+            Ok(snap) => diff(snap, cached),
+            Err(min) => cached.iter().filter(|e| e.timestamp_min as i64 >= *min)
+                .map(|e| (e.topic.to_string(), Change::Added(bullet(e)))).collect(),
+        };
+        save_snapshot(dir, cached);
+        render(&label, &changes)
+    }).map_err(Into::into)
+}
+
+enum Change {
+    Added(String),
+    /// (new bullet, old bullet)
+    Superseded(String, String),
+    /// (bullet, old topic)
+    Moved(String, String),
+    Removed(String),
+}
+
+struct Snapshot {
+    taken: i32,
+    entries: Vec<SnapEntry>,
+}
+
+struct SnapEntry {
+    uid: u64,
+    ts: i32,
+    topic: String,
+    bullet: String,
+}
+
+fn snapshot_path(dir: &Path) -> PathBuf { dir.join("digest.snapshot") }
+
+fn bullet(e: &CachedEntry) -> String {
+    let preview = e.preview();
+    let preview = if preview.is_empty() { "(empty)" } else { preview };
+    crate::text::truncate(preview.trim().trim_start_matches("- "), 100).to_string()
+}
+
+/// Best-effort: a failed write only means the next `--since last` has no baseline.
+fn save_snapshot(dir: &Path, cached: &[CachedEntry]) {
+    let mut text = format!("{}\n", crate::time::LocalTime::now().to_minutes());
+    for e in cached {
+        let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+        let b = bullet(e).replace(['\t', '\n'], " ");
+        let _ = writeln!(text, "{uid:016x}\t{}\t{}\t{b}", e.timestamp_min, e.topic);
+    }
+    let tmp = dir.join("digest.snapshot.tmp");
+    let _ = std::fs::write(&tmp, text).and_then(|_| std::fs::rename(&tmp, snapshot_path(dir)));
+}
+
+fn load_snapshot(dir: &Path) -> Option<Snapshot> {
+    let text = std::fs::read_to_string(snapshot_path(dir)).ok()?;
+    let mut lines = text.lines();
+    let taken = lines.next()?.trim().parse().ok()?;
+    let entries = lines.filter_map(|l| {
+        let mut f = l.splitn(4, '\t');
+        Some(SnapEntry {
+            uid: u64::from_str_radix(f.next()?, 16).ok()?,
+            ts: f.next()?.parse().ok()?,
+            topic: f.next()?.to_string(),
+            bullet: f.next().unwrap_or("").to_string(),
+        })
+    }).collect();
+    Some(Snapshot { taken, entries })
+}
+
+/// Match current entries against the snapshot: same uid = unchanged (or moved
+/// if the topic differs); same topic + timestamp with a new uid = superseded.
+fn diff(snap: &Snapshot, cached: &[CachedEntry]) -> Vec<(String, Change)> {
+    let mut by_uid: FxHashMap<u64, Vec<usize>> = FxHashMap::default();
+    let mut by_slot: FxHashMap<(&str, i32), Vec<usize>> = FxHashMap::default();
+    for (i, s) in snap.entries.iter().enumerate() {
+        by_uid.entry(s.uid).or_default().push(i);
+        by_slot.entry((s.topic.as_str(), s.ts)).or_default().push(i);
+    }
+    let mut matched = vec![false; snap.entries.len()];
+    let take = |ids: Option<&Vec<usize>>, matched: &mut Vec<bool>| -> Option<usize> {
+        let i = *ids?.iter().find(|&&i| !matched[i])?;
+        matched[i] = true;
+        Some(i)
+    };
+    let mut changes = Vec::new();
+    let mut pending = Vec::new();
+    for e in cached {
+        let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+        match take(by_uid.get(&uid), &mut matched) {
+            Some(i) if snap.entries[i].topic != e.topic.as_str() => changes.push((e.topic.to_string(),
+                Change::Moved(bullet(e), snap.entries[i].topic.clone()))),
+            Some(_) => {}
+            None => pending.push(e),
+        }
+    }
+    // Second pass so an unchanged entry never gets claimed as a revision
+    for e in pending {
+        let change = match take(by_slot.get(&(e.topic.as_str(), e.timestamp_min)), &mut matched) {
+            Some(i) => Change::Superseded(bullet(e), snap.entries[i].bullet.clone()),
+            None => Change::Added(bullet(e)),
+        };
+        changes.push((e.topic.to_string(), change));
+    }
+    for (s, _) in snap.entries.iter().zip(&matched).filter(|(_, m)| !**m) {
+        changes.push((s.topic.clone(), Change::Removed(s.bullet.clone())));
+    }
+    changes
+}
+
+fn render(label: &str, changes: &[(String, Change)]) -> String {
+    if changes.is_empty() { return format!("no changes since {label}\n"); }
+    let mut grouped: BTreeMap<&str, Vec<&Change>> = BTreeMap::new();
+    let mut counts = [0usize; 4];
+    for (topic, c) in changes {
+        grouped.entry(topic.as_str()).or_default().push(c);
+        counts[match c { Change::Added(_) => 0, Change::Superseded(..) => 1, Change::Moved(..) => 2, Change::Removed(_) => 3 }] += 1;
+    }
+    let mut out = format!("changes since {label}: {} added, {} superseded, {} moved, {} removed\n",
+        counts[0], counts[1], counts[2], counts[3]);
+    for (topic, list) in &grouped {
+        let _ = writeln!(out, "\n### {topic}");
+        for c in list {
+            let _ = match c {
+                Change::Added(b) => writeln!(out, "+ {b}"),
+                Change::Superseded(b, old) => writeln!(out, "~ {b}\n  (supersedes: {old})"),
+                Change::Moved(b, from) => writeln!(out, "> {b} (moved from {from})"),
+                Change::Removed(b) => writeln!(out, "- ~~{b}~~ (removed)"),
+            };
+        }
+    }
+    out
+}
+
+// --- Topic digests: which topics changed since a time ---
+//
+// `topics.digest` keeps one row per topic: `topic\tdigest\tchanged\tpending`.
+// The digest hashes the topic's live entries in order (uids), so any store,
+// edit, delete, reorder, rename or merge changes it. Writes through the MCP
+// server call `note_write`, which only stamps `pending` with the write time
+// (no corpus work on the write path). `changed_since` recomputes digests from
+// the corpus and, for each topic whose digest moved, records `changed` as the
+// pending stamp or, for a write nobody noted (CLI, another process), the time
+// it was noticed — later than the write, never missed by a poller. A topic
+// that disappears keeps a row with digest 0 and is reported as removed.
+
+fn topic_digest_path(dir: &Path) -> PathBuf { dir.join("topics.digest") }
+
+struct TopicRow { digest: u64, changed: i32, pending: i32 }
+
+fn load_topic_digests(dir: &Path) -> Option<BTreeMap<String, TopicRow>> {
+    let text = std::fs::read_to_string(topic_digest_path(dir)).ok()?;
+    Some(text.lines().filter_map(|l| {
+        let mut f = l.split('\t');
+        let topic = f.next()?.to_string();
+        Some((topic, TopicRow {
+            digest: u64::from_str_radix(f.next()?, 16).ok()?,
+            changed: f.next()?.parse().ok()?,
+            pending: f.next()?.parse().ok()?,
+        }))
+    }).collect())
+}
+
+/// Best-effort, like the digest snapshot: a lost write only delays a change
+/// to the next `changed_since`.
+fn save_topic_digests(dir: &Path, rows: &BTreeMap<String, TopicRow>) {
+    let mut text = String::new();
+    for (topic, r) in rows {
+        let _ = writeln!(text, "{topic}\t{:016x}\t{}\t{}", r.digest, r.changed, r.pending);
+    }
+    let tmp = dir.join("topics.digest.tmp");
+    let _ = std::fs::write(&tmp, text).and_then(|_| std::fs::rename(&tmp, topic_digest_path(dir)));
+}
+
+/// Stamp `topic` as written now; its digest is recomputed on the next
+/// `changed_since`. Without a digest file yet there is nothing to keep current.
+pub fn note_write(dir: &Path, topic: &str) {
+    if topic.is_empty() { return; }
+    let Some(mut rows) = load_topic_digests(dir) else { return };
+    let now = crate::time::LocalTime::now().to_minutes() as i32;
+    rows.entry(topic.to_string()).or_insert(TopicRow { digest: 0, changed: now, pending: 0 }).pending = now;
+    save_topic_digests(dir, &rows);
+}
+
+/// Topics whose content changed at or after `since` ("YYYY-MM-DD [HH:MM]" or a
+/// shortcut like "yesterday"), with the `as of` time to pass next time.
+pub fn changed_since(dir: &Path, since: &str) -> Result<String, String> {
+    let date = crate::time::resolve_date_shortcut(since);
+    let since_min = crate::time::parse_date_minutes(&date)
+        .ok_or_else(|| format!("--changed-since: expected a date, got '{since}'"))? as i32;
+    let now = crate::time::LocalTime::now().to_minutes() as i32;
+    // Per topic: (digest, entries, newest timestamp)
+    let current = crate::cache::with_corpus(dir, |cached| {
+        let mut cur: BTreeMap<String, (u64, usize, i32)> = BTreeMap::new();
+        for e in cached {
+            let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+            let c = cur.entry(e.topic.to_string()).or_insert((0xcbf2_9ce4_8422_2325, 0, 0));
+            c.0 = (c.0 ^ uid).wrapping_mul(0x0100_0000_01b3);
+            c.1 += 1;
+            c.2 = c.2.max(e.timestamp_min);
+        }
+        cur
+    })?;
+    let loaded = load_topic_digests(dir);
+    let first_run = loaded.is_none();
+    let mut rows = loaded.unwrap_or_default();
+    for (topic, &(digest, _, newest)) in &current {
+        match rows.get_mut(topic) {
+            Some(r) if r.digest == digest => r.pending = 0,
+            Some(r) => *r = TopicRow { digest, changed: if r.pending > 0 { r.pending } else { now }, pending: 0 },
+            // Without history, a topic's newest entry is the best guess
+            None => { rows.insert(topic.clone(), TopicRow { digest, changed: if first_run { newest } else { now }, pending: 0 }); }
+        }
+    }
+    for (_, r) in rows.iter_mut().filter(|(t, r)| r.digest != 0 && !current.contains_key(*t)) {
+        *r = TopicRow { digest: 0, changed: if r.pending > 0 { r.pending } else { now }, pending: 0 };
+    }
+    save_topic_digests(dir, &rows);
+
+    let changed: Vec<(&String, &TopicRow)> = rows.iter().filter(|(_, r)| r.changed >= since_min).collect();
+    let mut out = format!("{} topics changed since {date} ({} topics now)\n", changed.len(), current.len());
+    for (topic, r) in &changed {
+        let when = crate::time::minutes_to_date_str(r.changed);
+        match current.get(*topic) {
+            Some((digest, n, _)) => { let _ = writeln!(out, "  {topic:<24} {when}  {n:>3} entries  digest {digest:016x}"); }
+            None => { let _ = writeln!(out, "  {topic:<24} {when}  removed"); }
+        }
+    }
+    let _ = writeln!(out, "as of {} (pass as --changed-since next time)", crate::time::minutes_to_date_str(now));
+    Ok(out)
 }
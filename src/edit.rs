@@ -1,67 +1,75 @@
+use crate::error::AmrError;
 use std::path::Path;
 
+/// A replaced entry is re-appended, so it moves to the end of its topic and
+/// (if the text changed) gets a new uid. Reported so callers can keep chaining.
+fn now_at(topic: &str, entries: &[crate::datalog::LogEntry], old: &crate::datalog::LogEntry, new_body: &str) -> String {
+    let uid = crate::address::entry_uid(old.timestamp_min, new_body);
+    format!(" (now {})", crate::address::format_both(topic, entries.len() - 1, uid))
+}
+
 /// Replace the content of the first entry matching `needle`.
-pub fn run(dir: &Path, topic: &str, needle: &str, new_text: &str) -> Result<String, String> {
+pub fn run(dir: &Path, topic: &str, needle: &str, new_text: &str) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
     let lower = needle.to_lowercase();
     let entry = entries.iter().find(|e| e.body.to_lowercase().contains(&lower))
-        .ok_or_else(|| format!("no entry matching \"{}\"", needle))?;
+        .ok_or_else(|| AmrError::NotFound(format!("no entry matching \"{}\"", needle)))?;
     let new_body = add_modified_marker(new_text);
     crate::datalog::append_entry(&log_path, topic, &new_body, entry.timestamp_min)?;
     crate::datalog::append_delete(&log_path, entry.offset)?;
-    Ok(format!("updated entry matching \"{}\" in {}", needle, topic))
+    Ok(format!("updated entry matching \"{}\" in {}{}", needle, topic, now_at(topic, &entries, entry, &new_body)))
 }
 
 /// Replace entry by 0-based index.
-pub fn run_by_index(dir: &Path, topic: &str, idx: usize, new_text: &str) -> Result<String, String> {
+pub fn run_by_index(dir: &Path, topic: &str, idx: usize, new_text: &str) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
     if idx >= entries.len() {
-        return Err(format!("index {idx} out of range (topic has {} entries, 0-{})",
-            entries.len(), entries.len().saturating_sub(1)));
+        return Err(AmrError::NotFound(format!("index {idx} out of range (topic has {} entries, 0-{})",
+            entries.len(), entries.len().saturating_sub(1))));
     }
     let entry = &entries[idx];
     let new_body = add_modified_marker(new_text);
     crate::datalog::append_entry(&log_path, topic, &new_body, entry.timestamp_min)?;
     crate::datalog::append_delete(&log_path, entry.offset)?;
-    Ok(format!("updated entry [{idx}] in {}", topic))
+    Ok(format!("updated entry [{idx}] in {}{}", topic, now_at(topic, &entries, entry, &new_body)))
 }
 
 /// Append text to the first entry matching `needle`.
-pub fn append(dir: &Path, topic: &str, needle: &str, extra: &str) -> Result<String, String> {
+pub fn append(dir: &Path, topic: &str, needle: &str, extra: &str) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
     let lower = needle.to_lowercase();
     let entry = entries.iter().find(|e| e.body.to_lowercase().contains(&lower))
-        .ok_or_else(|| format!("no entry matching \"{}\"", needle))?;
+        .ok_or_else(|| AmrError::NotFound(format!("no entry matching \"{}\"", needle)))?;
     let new_body = format!("{}\n{extra}", entry.body.trim_end());
     crate::datalog::append_entry(&log_path, topic, &new_body, entry.timestamp_min)?;
     crate::datalog::append_delete(&log_path, entry.offset)?;
-    Ok(format!("appended to entry matching \"{}\" in {}", needle, topic))
+    Ok(format!("appended to entry matching \"{}\" in {}{}", needle, topic, now_at(topic, &entries, entry, &new_body)))
 }
 
 /// Append text to entry by 0-based index.
-pub fn append_by_index(dir: &Path, topic: &str, idx: usize, extra: &str) -> Result<String, String> {
+pub fn append_by_index(dir: &Path, topic: &str, idx: usize, extra: &str) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
     if idx >= entries.len() {
-        return Err(format!("index {idx} out of range (topic has {} entries, 0-{})",
-            entries.len(), entries.len().saturating_sub(1)));
+        return Err(AmrError::NotFound(format!("index {idx} out of range (topic has {} entries, 0-{})",
+            entries.len(), entries.len().saturating_sub(1))));
     }
     let entry = &entries[idx];
     let new_body = format!("{}\n{extra}", entry.body.trim_end());
     crate::datalog::append_entry(&log_path, topic, &new_body, entry.timestamp_min)?;
     crate::datalog::append_delete(&log_path, entry.offset)?;
-    Ok(format!("appended to entry [{idx}] in {}", topic))
+    Ok(format!("appended to entry [{idx}] in {}{}", topic, now_at(topic, &entries, entry, &new_body)))
 }
 
 /// Append text to the most recent entry with a given tag.
-pub fn append_by_tag(dir: &Path, topic: &str, tag: &str, extra: &str) -> Result<String, String> {
+pub fn append_by_tag(dir: &Path, topic: &str, tag: &str, extra: &str) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
@@ -71,22 +79,22 @@ pub fn append_by_tag(dir: &Path, topic: &str, tag: &str, extra: &str) -> Result<
             crate::text::parse_tags_raw(Some(line))
                 .iter().any(|t| t.to_lowercase() == tag_lower)
         })
-    }).ok_or_else(|| format!("no entry with tag '{}' in {}", tag, topic))?;
+    }).ok_or_else(|| AmrError::NotFound(format!("no entry with tag '{}' in {}", tag, topic)))?;
     let new_body = format!("{}\n{extra}", entry.body.trim_end());
     crate::datalog::append_entry(&log_path, topic, &new_body, entry.timestamp_min)?;
     crate::datalog::append_delete(&log_path, entry.offset)?;
-    Ok(format!("appended to most recent entry tagged '{}' in {}", tag, topic))
+    Ok(format!("appended to most recent entry tagged '{}' in {}{}", tag, topic, now_at(topic, &entries, entry, &new_body)))
 }
 
 /// Rename a topic: rewrite all entries with new name, tombstone old ones.
-pub fn rename_topic(dir: &Path, old_name: &str, new_name: &str) -> Result<String, String> {
+pub fn rename_topic(dir: &Path, old_name: &str, new_name: &str) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, old_name)?;
-    if entries.is_empty() { return Err(format!("topic '{}' not found", old_name)); }
+    if entries.is_empty() { return Err(AmrError::NotFound(format!("topic '{}' not found", old_name))); }
     let all = crate::datalog::iter_live(&log_path)?;
     if all.iter().any(|e| e.topic == new_name) {
-        return Err(format!("topic '{}' already has entries", new_name));
+        return Err(AmrError::InvalidArg(format!("topic '{}' already has entries", new_name)));
     }
     for e in &entries {
         crate::datalog::append_entry(&log_path, new_name, &e.body, e.timestamp_min)?;
@@ -100,20 +108,20 @@ pub fn tag_entry(
     dir: &Path, topic: &str,
     idx: Option<usize>, needle: Option<&str>,
     add: Option<&str>, remove: Option<&str>,
-) -> Result<String, String> {
+) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
 
     let target_idx = if let Some(i) = idx {
         if i >= entries.len() {
-            return Err(format!("index {i} out of range (0-{})", entries.len().saturating_sub(1)));
+            return Err(AmrError::NotFound(format!("index {i} out of range (0-{})", entries.len().saturating_sub(1))));
         }
         i
     } else if let Some(n) = needle {
         let lower = n.to_lowercase();
         entries.iter().position(|e| e.body.to_lowercase().contains(&lower))
-            .ok_or_else(|| format!("no entry matching \"{}\"", n))?
+            .ok_or_else(|| AmrError::NotFound(format!("no entry matching \"{}\"", n)))?
     } else {
         return Err("provide index or match_str".into());
     };
@@ -142,22 +150,23 @@ pub fn tag_entry(
     tags.dedup();
 
     let mut new_body = String::new();
-    if !tags.is_empty() { new_body.push_str(&format!("[tags: {}]\n", tags.join(", "))); }
+    if !tags.is_empty() { new_body.push_str(&crate::entryfmt::line("tags", &tags.join(", "))); new_body.push('\n'); }
     new_body.push_str(&body_lines.join("\n"));
 
     crate::datalog::append_entry(&log_path, topic, &new_body, entry.timestamp_min)?;
     crate::datalog::append_delete(&log_path, entry.offset)?;
-    Ok(format!("tags updated on entry [{target_idx}] in {}: [{}]", topic, tags.join(", ")))
+    Ok(format!("tags updated on entry [{target_idx}] in {}: [{}]{}", topic, tags.join(", "),
+        now_at(topic, &entries, entry, &new_body)))
 }
 
-pub fn merge_topics(dir: &Path, from: &str, into: &str) -> Result<String, String> {
+pub fn merge_topics(dir: &Path, from: &str, into: &str) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let from_t = crate::config::sanitize_topic(from);
     let into_t = crate::config::sanitize_topic(into);
     let log_path = crate::config::log_path(dir);
     let entries = crate::datalog::iter_live(&log_path)?;
     let src: Vec<_> = entries.iter().filter(|e| e.topic == from_t).collect();
-    if src.is_empty() { return Err(format!("topic '{}' not found", from)); }
+    if src.is_empty() { return Err(AmrError::NotFound(format!("topic '{}' not found", from))); }
     let mut moved = 0;
     for e in &src {
         crate::datalog::append_entry(&log_path, &into_t, &e.body, e.timestamp_min)?;
@@ -169,5 +178,5 @@ pub fn merge_topics(dir: &Path, from: &str, into: &str) -> Result<String, String
 
 fn add_modified_marker(text: &str) -> String {
     let now = crate::time::LocalTime::now();
-    format!("[modified: {now}]\n{text}")
+    format!("{}\n{text}", crate::entryfmt::line("modified", &now.to_string()))
 }
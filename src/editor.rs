@@ -0,0 +1,119 @@
+//! `edit-topic <topic>`: round-trip a whole topic through $VISUAL / $EDITOR.
+//! The topic is written to a temp markdown file, one section per entry under a
+//! `## @@ topic:uid:HEX · date` marker line. On save the file is diffed back:
+//! a changed body is rewritten (append + tombstone, timestamp kept), a removed
+//! section is deleted, a `## @@ new` section is stored as a new entry. Moving
+//! sections around changes nothing — log order stays the order of record.
+//!
+//! If the topic changed while the editor was open, nothing is applied and the
+//! temp file is kept so the edits aren't lost.
+
+use crate::error::AmrError;
+use std::fmt::Write;
+use std::path::Path;
+
+const MARK: &str = "## @@ ";
+
+pub fn run(dir: &Path, topic: &str) -> Result<String, AmrError> {
+    let topic = crate::stats::single_topic(dir, topic)?;
+    let log_path = crate::config::log_path(dir);
+    let before = crate::delete::topic_entries(&log_path, &topic)?;
+    if before.is_empty() { return Err(AmrError::NotFound(format!("topic '{topic}' not found"))); }
+
+    let path = std::env::temp_dir().join(format!("amaranthine-{topic}-{}.md", std::process::id()));
+    let original = render(&topic, &before);
+    std::fs::write(&path, &original).map_err(|e| AmrError::io(&path.display().to_string(), e))?;
+    let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")).unwrap_or_else(|_| "vi".into());
+    let status = std::process::Command::new("sh").arg("-c").arg(format!("{editor} \"$1\""))
+        .arg("sh").arg(&path).status()
+        .map_err(|e| AmrError::io(&format!("run {editor}"), e))?;
+    if !status.success() {
+        let _ = std::fs::remove_file(&path);
+        return Err(AmrError::InvalidArg(format!("{editor} exited with {status}; nothing applied")));
+    }
+    let edited = std::fs::read_to_string(&path).map_err(|e| AmrError::io(&path.display().to_string(), e))?;
+    if edited == original {
+        let _ = std::fs::remove_file(&path);
+        return Ok(format!("no changes to {topic}\n"));
+    }
+
+    let _lock = crate::lock::FileLock::acquire(dir)?;
+    let now = crate::delete::topic_entries(&log_path, &topic)?;
+    if now.iter().map(|e| e.offset).ne(before.iter().map(|e| e.offset)) {
+        return Err(AmrError::Locked(format!(
+            "{topic} changed while the editor was open; nothing applied (edits kept in {})", path.display())));
+    }
+    let sections = parse(&edited)?;
+    // Match every section to its entry before writing anything, so a bad
+    // section really leaves the topic untouched
+    let mut kept = vec![false; before.len()];
+    let mut matched: Vec<Option<usize>> = Vec::with_capacity(sections.len());
+    for s in &sections {
+        let Some(uid) = s.uid else { matched.push(None); continue };
+        let i = before.iter().enumerate()
+            .position(|(i, e)| !kept[i] && crate::address::entry_uid(e.timestamp_min, &e.body) == uid)
+            .ok_or_else(|| AmrError::InvalidArg(format!(
+                "section {uid:016x} doesn't match an entry of {topic}; nothing applied (edits kept in {})", path.display())))?;
+        kept[i] = true;
+        matched.push(Some(i));
+    }
+    let (mut updated, mut added) = (0, 0);
+    let ts_now = crate::time::LocalTime::now().to_minutes() as i32;
+    for (s, m) in sections.iter().zip(matched) {
+        let Some(i) = m else {
+            if s.body.trim().is_empty() { continue; }
+            crate::datalog::append_entry(&log_path, &topic,
+                &format!("{}\n{}", crate::entryfmt::line("provenance", &crate::provenance::Provenance::Human.to_string()),
+                    s.body.trim()), ts_now)?;
+            added += 1;
+            continue;
+        };
+        let e = &before[i];
+        if s.body.trim() == e.body.trim() { continue; }
+        crate::datalog::append_entry(&log_path, &topic, s.body.trim(), e.timestamp_min)?;
+        crate::datalog::append_delete(&log_path, e.offset)?;
+        updated += 1;
+    }
+    let mut deleted = 0;
+    for (e, _) in before.iter().zip(&kept).filter(|(_, k)| !**k) {
+        crate::datalog::append_delete(&log_path, e.offset)?;
+        deleted += 1;
+    }
+    let _ = std::fs::remove_file(&path);
+    Ok(format!("{topic}: {updated} updated, {deleted} deleted, {added} added\n"))
+}
+
+/// The editable file: a comment on how it works, then one section per entry.
+fn render(topic: &str, entries: &[crate::datalog::LogEntry]) -> String {
+    let mut out = format!("<!-- {topic}: edit bodies in place, delete a section to delete its entry,\n     \
+        add sections headed `{MARK}new` for new entries. Keep the `{MARK}` lines. -->\n\n");
+    for e in entries {
+        let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+        let _ = writeln!(out, "{MARK}{topic}:uid:{uid:016x} · {}", crate::time::minutes_to_date_str(e.timestamp_min));
+        let _ = writeln!(out, "{}\n", e.body.trim_end());
+    }
+    out
+}
+
+/// One edited section: `uid` None for `new`.
+struct Section { uid: Option<u64>, body: String }
+
+fn parse(text: &str) -> Result<Vec<Section>, AmrError> {
+    let mut out: Vec<Section> = Vec::new();
+    for line in text.lines() {
+        if let Some(head) = line.strip_prefix(MARK) {
+            let head = head.trim();
+            let uid = if head == "new" { None } else {
+                let hex = head.split_whitespace().next().and_then(|a| a.rsplit_once(":uid:")).map(|(_, h)| h)
+                    .ok_or_else(|| AmrError::InvalidArg(format!("bad section marker '{line}'")))?;
+                Some(u64::from_str_radix(hex, 16)
+                    .map_err(|_| AmrError::InvalidArg(format!("bad uid in '{line}'")))?)
+            };
+            out.push(Section { uid, body: String::new() });
+        } else if let Some(s) = out.last_mut() {
+            s.body.push_str(line);
+            s.body.push('\n');
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,344 @@
+//! Semantic search: per-entry embedding vectors in a `vectors.bin` sidecar,
+//! blended with BM25 by `semantic_search` (CLI `semantic`), so paraphrased
+//! knowledge ("token refresh" for an entry about "renewing credentials") is
+//! found. Off until `embed_backend` is set; the backend is an `Embedder`:
+//!
+//! - `hash`: built in, no model — hashed words and character trigrams. Catches
+//!   word forms and shared stems, not synonyms; a baseline and for tests.
+//! - `command`: `embed_command` run through `sh -c`, fed a JSON array of texts
+//!   on stdin, printing a JSON array of vectors — how a local ONNX (or any
+//!   other) model plugs in without a dependency here.
+//! - `http`: an OpenAI-compatible `/embeddings` endpoint (`embed_url`,
+//!   `embed_model`, bearer key from the env var named by `embed_key_env`),
+//!   POSTed through `curl` as webhook.rs does.
+//!
+//! Vectors are keyed by entry uid (timestamp + content), so an edit re-embeds
+//! and a tag change doesn't. Each search syncs the sidecar first: up to
+//! `SYNC_LIMIT` entries without a vector are embedded in batches (the rest on
+//! later searches, ranking on BM25 alone meanwhile), vectors of deleted entries
+//! are dropped, and a different backend id (backend + model) or dimension starts
+//! over. A failing backend call keeps the batches embedded before it. The score
+//! is `w·cosine + (1−w)·bm25/max_bm25` (`semantic_weight`, or `weight` per call;
+//! default `DEFAULT_WEIGHT`), cosine clamped at 0.
+
+use crate::error::AmrError;
+use crate::fxhash::FxHashMap;
+use crate::json::Value;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_WEIGHT: f64 = 0.5;
+pub const DEFAULT_LIMIT: usize = 10;
+/// Texts per backend call while syncing.
+const BATCH: usize = 32;
+/// Entries embedded per search, so the first one on a big KB stays bounded.
+const SYNC_LIMIT: usize = 512;
+/// Entry text sent to the backend, in bytes.
+const MAX_TEXT: usize = 2000;
+/// BM25 candidates blended in; entries past them score on cosine alone.
+const BM25_POOL: usize = 200;
+const HASH_DIM: usize = 256;
+const MAGIC: [u8; 4] = *b"AMRE";
+const VERSION: u32 = 1;
+const TIMEOUT_SECS: &str = "30";
+
+/// An embedding backend.
+pub trait Embedder {
+    /// Backend and model, recorded in vectors.bin: vectors from another id are rebuilt.
+    fn id(&self) -> String;
+    /// One vector per text, all the same length.
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AmrError>;
+}
+
+/// The configured backend; an error says how to configure one.
+pub fn backend(dir: &Path) -> Result<Box<dyn Embedder>, AmrError> {
+    let s = crate::settings::load(dir);
+    let need = |k: &str| s.get(k).map(str::to_string)
+        .ok_or_else(|| AmrError::InvalidArg(format!("embed_backend = {} needs {k} in config", s.get("embed_backend").unwrap_or(""))));
+    match s.get("embed_backend") {
+        Some("hash") => Ok(Box::new(HashEmbedder)),
+        Some("command") => Ok(Box::new(CommandEmbedder { command: need("embed_command")? })),
+        Some("http") => Ok(Box::new(HttpEmbedder {
+            url: need("embed_url")?, model: need("embed_model")?,
+            key_env: s.get("embed_key_env").map(str::to_string),
+        })),
+        Some(other) => Err(AmrError::InvalidArg(format!("embed_backend = {other}: expected hash, command or http"))),
+        None => Err(AmrError::InvalidArg("semantic search is off: set embed_backend (hash, command or http) in config".into())),
+    }
+}
+
+/// Hashed words (weight 1) and their character trigrams (weight 0.5).
+pub struct HashEmbedder;
+
+impl Embedder for HashEmbedder {
+    fn id(&self) -> String { format!("hash/{HASH_DIM}") }
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AmrError> {
+        Ok(texts.iter().map(|t| {
+            let mut v = vec![0f32; HASH_DIM];
+            let mut add = |s: &str, w: f32| {
+                let h = crate::format::hash_term(s);
+                v[(h % HASH_DIM as u64) as usize] += if h >> 63 == 0 { w } else { -w };
+            };
+            for word in crate::text::tokenize(t).iter().filter(|w| !crate::text::is_stop_word(w)) {
+                add(word, 1.0);
+                let padded: Vec<char> = format!("^{word}$").chars().collect();
+                for g in padded.windows(3) { add(&g.iter().collect::<String>(), 0.5); }
+            }
+            v
+        }).collect())
+    }
+}
+
+/// A local model behind a command: JSON array of texts in, of vectors out.
+pub struct CommandEmbedder { command: String }
+
+impl Embedder for CommandEmbedder {
+    fn id(&self) -> String { format!("command/{}", self.command) }
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AmrError> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+        let input = Value::Arr(texts.iter().map(|t| Value::Str(t.to_string())).collect()).to_string();
+        let mut child = Command::new("sh").arg("-c").arg(&self.command)
+            .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+            .spawn().map_err(|e| AmrError::Io(format!("embed_command: {e}")))?;
+        if let Some(mut stdin) = child.stdin.take() { let _ = stdin.write_all(input.as_bytes()); }
+        let out = child.wait_with_output().map_err(|e| AmrError::Io(format!("embed_command: {e}")))?;
+        if !out.status.success() {
+            return Err(AmrError::Io(format!("embed_command failed: {}", String::from_utf8_lossy(&out.stderr).trim())));
+        }
+        let v = crate::json::parse(&String::from_utf8_lossy(&out.stdout))
+            .map_err(|e| AmrError::Io(format!("embed_command output: {e}")))?;
+        vectors(&v, texts.len(), "embed_command")
+    }
+}
+
+/// An OpenAI-compatible embeddings API.
+pub struct HttpEmbedder { url: String, model: String, key_env: Option<String> }
+
+impl Embedder for HttpEmbedder {
+    fn id(&self) -> String { format!("http/{}/{}", self.url, self.model) }
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, AmrError> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+        let body = Value::Obj(vec![
+            ("model".into(), Value::Str(self.model.clone())),
+            ("input".into(), Value::Arr(texts.iter().map(|t| Value::Str(t.to_string())).collect())),
+        ]).to_string();
+        // Request as a curl config on stdin, so the bearer key never reaches
+        // curl's argv (visible in the local process list)
+        let mut config = format!("header = \"Content-Type: application/json\"\ndata-binary = {}\n", curl_quote(&body));
+        if let Some(key) = self.key_env.as_deref().and_then(|k| std::env::var(k).ok()) {
+            config.push_str(&format!("header = {}\n", curl_quote(&format!("Authorization: Bearer {key}"))));
+        }
+        let mut child = Command::new("curl")
+            .args(["-sS", "-f", "-X", "POST", "--max-time", TIMEOUT_SECS, "-K", "-", "--url", &self.url])
+            .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+            .spawn().map_err(|e| AmrError::Io(format!("can't run curl: {e}")))?;
+        if let Some(mut stdin) = child.stdin.take() { let _ = stdin.write_all(config.as_bytes()); }
+        let out = child.wait_with_output().map_err(|e| AmrError::Io(e.to_string()))?;
+        if !out.status.success() {
+            return Err(AmrError::Io(format!("{}: {}", self.url, String::from_utf8_lossy(&out.stderr).trim())));
+        }
+        let v = crate::json::parse(&String::from_utf8_lossy(&out.stdout))
+            .map_err(|e| AmrError::Io(format!("{}: {e}", self.url)))?;
+        let data = match v.get("data") {
+            Some(Value::Arr(items)) => Value::Arr(items.iter().filter_map(|i| i.get("embedding").cloned()).collect()),
+            _ => return Err(AmrError::Io(format!("{}: no data in response", self.url))),
+        };
+        vectors(&data, texts.len(), &self.url)
+    }
+}
+
+/// `s` as a double-quoted curl config value.
+fn curl_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `n` equal-length vectors from a JSON array of number arrays.
+fn vectors(v: &Value, n: usize, from: &str) -> Result<Vec<Vec<f32>>, AmrError> {
+    let bad = |why: &str| AmrError::Io(format!("{from}: {why}"));
+    let Value::Arr(rows) = v else { return Err(bad("expected a JSON array of vectors")) };
+    if rows.len() != n { return Err(bad(&format!("{} vectors for {n} texts", rows.len()))); }
+    let out: Vec<Vec<f32>> = rows.iter().map(|r| match r {
+        Value::Arr(xs) => xs.iter().map(|x| x.as_f64().map(|f| f as f32)).collect::<Option<Vec<f32>>>(),
+        _ => None,
+    }).collect::<Option<_>>().ok_or_else(|| bad("vectors must be arrays of numbers"))?;
+    if out.iter().any(|r| r.is_empty() || r.len() != out[0].len()) { return Err(bad("vectors differ in length")); }
+    Ok(out)
+}
+
+// --- vectors.bin ---
+
+pub fn vectors_path(dir: &Path) -> PathBuf { dir.join("vectors.bin") }
+
+/// Unit-length vectors by entry uid, for one backend id.
+struct Store { id: String, dim: usize, vecs: FxHashMap<u64, Vec<f32>> }
+
+/// [magic][version u32][dim u32][id_len u16][id][count u32] then count ×
+/// [uid u64][dim × f32], little-endian. None if missing or unreadable.
+fn load(dir: &Path) -> Option<Store> {
+    let b = std::fs::read(vectors_path(dir)).ok()?;
+    let u32_at = |at: usize| b.get(at..at + 4).map(|s| u32::from_le_bytes([s[0], s[1], s[2], s[3]]));
+    if b.get(..4)? != MAGIC || u32_at(4)? != VERSION { return None; }
+    let dim = u32_at(8)? as usize;
+    let id_len = u16::from_le_bytes([*b.get(12)?, *b.get(13)?]) as usize;
+    let id = String::from_utf8(b.get(14..14 + id_len)?.to_vec()).ok()?;
+    let count = u32_at(14 + id_len)? as usize;
+    let mut at = 18 + id_len;
+    let mut vecs = FxHashMap::default();
+    for _ in 0..count {
+        let uid = u64::from_le_bytes(b.get(at..at + 8)?.try_into().ok()?);
+        let v = b.get(at + 8..at + 8 + dim * 4)?.chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect();
+        vecs.insert(uid, v);
+        at += 8 + dim * 4;
+    }
+    Some(Store { id, dim, vecs })
+}
+
+fn save(dir: &Path, s: &Store) -> Result<(), AmrError> {
+    let mut b = Vec::with_capacity(32 + s.vecs.len() * (8 + s.dim * 4));
+    b.extend_from_slice(&MAGIC);
+    b.extend_from_slice(&VERSION.to_le_bytes());
+    b.extend_from_slice(&(s.dim as u32).to_le_bytes());
+    b.extend_from_slice(&(s.id.len() as u16).to_le_bytes());
+    b.extend_from_slice(s.id.as_bytes());
+    b.extend_from_slice(&(s.vecs.len() as u32).to_le_bytes());
+    let mut uids: Vec<&u64> = s.vecs.keys().collect();
+    uids.sort_unstable();
+    for uid in uids {
+        b.extend_from_slice(&uid.to_le_bytes());
+        for x in &s.vecs[uid] { b.extend_from_slice(&x.to_le_bytes()); }
+    }
+    let path = vectors_path(dir);
+    let tmp = path.with_extension("bin.tmp");
+    std::fs::write(&tmp, &b).and_then(|_| std::fs::rename(&tmp, &path))
+        .map_err(|e| AmrError::io("vectors.bin", e))
+}
+
+fn normalize(mut v: Vec<f32>) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 { for x in &mut v { *x /= norm; } }
+    v
+}
+
+/// What the backend sees of an entry: topic, then its prose without metadata.
+fn entry_text(topic: &str, body: &str) -> String {
+    let mut t = format!("{topic}\n");
+    for l in body.lines().filter(|l| !crate::text::is_metadata_line(l)) { t.push_str(l); t.push('\n'); }
+    crate::text::truncate(&t, MAX_TEXT).to_string()
+}
+
+/// Bring vectors.bin up to date with the corpus, embedding at most
+/// `SYNC_LIMIT` entries; `dim` is what the backend returns now (the query's).
+/// Returns the store, how many entries were embedded now and how many still
+/// lack a vector.
+fn sync(dir: &Path, emb: &dyn Embedder, dim: usize) -> Result<(Store, usize, usize), AmrError> {
+    let id = emb.id();
+    let mut store = load(dir).filter(|s| s.id == id && s.dim == dim)
+        .unwrap_or(Store { id, dim, vecs: FxHashMap::default() });
+    let (live, todo) = crate::cache::with_corpus(dir, |cached| {
+        let mut live = crate::fxhash::FxHashSet::default();
+        let mut todo = Vec::new();
+        for e in cached {
+            let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+            if live.insert(uid) && !store.vecs.contains_key(&uid) { todo.push((uid, entry_text(&e.topic, &e.body))); }
+        }
+        (live, todo)
+    })?;
+    let before = store.vecs.len();
+    store.vecs.retain(|uid, _| live.contains(uid));
+    let mut changed = store.vecs.len() != before;
+    let mut embedded = 0;
+    let mut failed = None;
+    for chunk in todo[..todo.len().min(SYNC_LIMIT)].chunks(BATCH) {
+        let texts: Vec<&str> = chunk.iter().map(|(_, t)| t.as_str()).collect();
+        let vecs = match emb.embed(&texts) {
+            Ok(v) => v,
+            Err(e) => { failed = Some(e); break; }
+        };
+        if vecs.iter().any(|v| v.len() != store.dim) {
+            failed = Some(AmrError::Io(format!("{} returned vectors of {} dims, the query's has {}",
+                store.id, vecs.first().map_or(0, Vec::len), store.dim)));
+            break;
+        }
+        for ((uid, _), v) in chunk.iter().zip(vecs) { store.vecs.insert(*uid, normalize(v)); }
+        embedded += chunk.len();
+        changed = true;
+    }
+    if changed { save(dir, &store)?; }
+    if let Some(e) = failed { return Err(e); }
+    let pending = live.iter().filter(|uid| !store.vecs.contains_key(uid)).count();
+    Ok((store, embedded, pending))
+}
+
+/// Entries ranked by blended cosine and BM25 for `query`.
+pub fn run(dir: &Path, query: &str, limit: usize, weight: Option<f64>,
+           filter: &crate::score::Filter) -> Result<String, AmrError> {
+    let query = query.trim();
+    if query.is_empty() { return Err(AmrError::InvalidArg("query is required".into())); }
+    let weight = weight
+        .or_else(|| crate::settings::load(dir).get("semantic_weight").and_then(|v| v.parse().ok()))
+        .unwrap_or(DEFAULT_WEIGHT).clamp(0.0, 1.0);
+    let emb = backend(dir)?;
+    let qv = normalize(emb.embed(&[query])?.pop().unwrap_or_default());
+    let (store, embedded, pending) = sync(dir, &*emb, qv.len())?;
+
+    // BM25 over any query term, normalized to the best hit
+    let terms = crate::text::query_terms(query);
+    let mut bm25: FxHashMap<u32, f64> = FxHashMap::default();
+    if !terms.is_empty() {
+        let mut any = crate::score::Filter {
+            mode: crate::score::SearchMode::Or, boost_topics: Vec::new(), ..filter.clone()
+        };
+        any.min_match = crate::score::MinMatch::Count(1);
+        let (hits, _) = crate::score::search_scored(dir, &terms, &any, Some(BM25_POOL), None, false)?;
+        let top = hits.iter().map(|h| h.score).fold(0.0, f64::max);
+        if top > 0.0 { bm25 = hits.iter().map(|h| (h.offset, h.score / top)).collect(); }
+    }
+
+    let scope = filter.topic_pattern();
+    let ranked = crate::cache::with_corpus(dir, |cached| {
+        let mut ranked: Vec<(f64, f64, f64, u32, String, String)> = cached.iter()
+            .filter(|e| crate::filter::in_scope(&scope, &e.topic) && crate::filter::passes_filter_cached(e, filter))
+            .filter_map(|e| {
+                // Not embedded yet: BM25 alone
+                let cos = store.vecs.get(&crate::address::entry_uid(e.timestamp_min, &e.body))
+                    .map_or(0.0, |v| v.iter().zip(&qv).map(|(a, b)| a * b).sum::<f32>().max(0.0) as f64);
+                let kw = bm25.get(&e.offset).copied().unwrap_or(0.0);
+                let score = weight * cos + (1.0 - weight) * kw;
+                (score > 0.0).then(|| (score, cos, kw, e.offset, e.topic.as_str().to_string(),
+                    crate::text::truncate(e.preview().trim(), 100).to_string()))
+            }).collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked.truncate(limit.max(1));
+        ranked
+    })?;
+
+    let mut out = String::new();
+    let offsets: Vec<u32> = ranked.iter().map(|r| r.3).collect();
+    let addrs = crate::address::lookup(dir, &offsets);
+    for (score, cos, kw, off, topic, preview) in &ranked {
+        let addr = addrs.get(off).map_or_else(|| topic.clone(), |&(i, _)| format!("{topic}:{i}"));
+        let _ = writeln!(out, "  [{addr}] {preview}  ({score:.2}: cos {cos:.2}, bm25 {kw:.2})");
+    }
+    if ranked.is_empty() { let _ = writeln!(out, "no entries close to '{query}'"); }
+    let _ = write!(out, "{} result(s), semantic_weight {weight:.2}, {} ({} vectors", ranked.len(), store.id, store.vecs.len());
+    if embedded > 0 { let _ = write!(out, ", {embedded} embedded now"); }
+    if pending > 0 { let _ = write!(out, ", {pending} not yet embedded: BM25 only until later searches"); }
+    out.push_str(")\n");
+    Ok(out)
+}
@@ -0,0 +1,142 @@
+//! Entry text format, in one place. A stored body is metadata lines
+//! (`[key: value]`) followed by content; a displayed entry is headed by
+//! `## YYYY-MM-DD HH:MM`. `FORMAT` describes both: the header prefix, and each
+//! metadata key with how its value splits into items. Reading (`field`,
+//! `values`, `is_metadata_line`) and writing (`line`, `set`) go through here,
+//! so a new metadata line is one `Field` row and parses the same everywhere.
+
+use crate::provenance::Provenance;
+
+/// How a metadata value splits into items.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Items { One, Comma, Space, Pipe }
+
+pub struct Field {
+    pub key: &'static str,
+    pub items: Items,
+}
+
+pub struct Format {
+    /// Prefix of the display header line; the date follows it.
+    pub header: &'static str,
+    pub fields: &'static [Field],
+}
+
+pub const FORMAT: Format = Format {
+    header: "## ",
+    fields: &[
+        Field { key: "tags", items: Items::Comma },
+        Field { key: "source", items: Items::One },
+        Field { key: "type", items: Items::One },
+        Field { key: "modified", items: Items::One },
+        Field { key: "tier", items: Items::One },
+        Field { key: "confidence", items: Items::One },
+        Field { key: "links", items: Items::Space },
+        Field { key: "linked from", items: Items::One },
+        Field { key: "commit", items: Items::One },
+        Field { key: "provenance", items: Items::One },
+        Field { key: "lang", items: Items::One },
+        Field { key: "order", items: Items::One },
+        Field { key: "status", items: Items::One },
+        Field { key: "alternatives", items: Items::Pipe },
+        Field { key: "supersedes", items: Items::One },
+        Field { key: "attachment", items: Items::One },
+    ],
+};
+
+/// Display header for an entry dated `date` ("YYYY-MM-DD HH:MM").
+pub fn header(date: &str) -> String { format!("{}{date}", FORMAT.header) }
+
+/// The date of a display header line, None if `line` isn't one.
+pub fn header_date(line: &str) -> Option<&str> { line.strip_prefix(FORMAT.header) }
+
+/// Is `line` one of the metadata lines in `FORMAT`? Fast reject on '['.
+#[inline]
+pub fn is_metadata_line(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix('[') else { return false };
+    let Some((key, _)) = rest.split_once(':') else { return false };
+    FORMAT.fields.iter().any(|f| f.key == key)
+}
+
+/// (key, value) of a `[key: value]` line.
+pub fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.strip_prefix('[')?.strip_suffix(']')?.split_once(": ")?;
+    Some((key, value.trim()))
+}
+
+/// Is `line` the metadata line for `key`?
+pub fn is_field(line: &str, key: &str) -> bool {
+    parse_line(line).is_some_and(|(k, _)| k == key)
+}
+
+/// `[key: value]`.
+pub fn line(key: &str, value: &str) -> String { format!("[{key}: {value}]") }
+
+/// Value of the first `key` line in `body`.
+pub fn field<'a>(body: &'a str, key: &str) -> Option<&'a str> {
+    body.lines().filter_map(parse_line).find(|(k, _)| *k == key).map(|(_, v)| v)
+}
+
+/// Items of a `key` value, split as `FORMAT` says for that key.
+pub fn split<'a>(key: &str, value: &'a str) -> Vec<&'a str> {
+    let items = FORMAT.fields.iter().find(|f| f.key == key).map_or(Items::One, |f| f.items);
+    let parts: Vec<&str> = match items {
+        Items::One => vec![value],
+        Items::Comma => value.split(',').collect(),
+        Items::Space => value.split_whitespace().collect(),
+        Items::Pipe => value.split('|').collect(),
+    };
+    parts.into_iter().map(str::trim).filter(|s| !s.is_empty()).collect()
+}
+
+/// Items of the first `key` line in `body`.
+pub fn values<'a>(body: &'a str, key: &str) -> Vec<&'a str> {
+    field(body, key).map(|v| split(key, v)).unwrap_or_default()
+}
+
+/// `body` with its `key` line replaced by `value`, or removed when None. A new
+/// line goes after the leading metadata, with the other metadata.
+pub fn set(body: &str, key: &str, value: Option<&str>) -> String {
+    let new = value.map(|v| line(key, v));
+    let mut lines: Vec<&str> = Vec::new();
+    let mut placed = false;
+    for l in body.lines() {
+        if !is_field(l, key) { lines.push(l); continue; }
+        if let (Some(n), false) = (&new, placed) { lines.push(n); placed = true; }
+    }
+    if let (Some(n), false) = (&new, placed) {
+        let at = lines.iter().position(|l| !is_metadata_line(l)).unwrap_or(lines.len());
+        lines.insert(at, n);
+    }
+    lines.join("\n")
+}
+
+/// All metadata extracted from an entry body in a single pass.
+pub struct EntryMetadata {
+    pub source: Option<String>,
+    pub tags: Vec<String>,
+    pub confidence: f64,
+    pub links: Vec<(String, usize)>,
+    pub provenance: Option<Provenance>,
+    pub lang: Option<crate::lang::Lang>,
+}
+
+/// Extract all metadata from entry body in one scan.
+pub fn extract_all_metadata(body: &str) -> EntryMetadata {
+    let mut meta = EntryMetadata { source: None, tags: Vec::new(), confidence: 1.0, links: Vec::new(), provenance: None, lang: None };
+    for (key, value) in body.lines().filter(|l| l.starts_with('[')).filter_map(parse_line) {
+        match key {
+            "tags" => meta.tags = split(key, value).into_iter().map(String::from).collect(),
+            "source" => meta.source = Some(value.to_string()),
+            "confidence" => if let Ok(c) = value.parse() { meta.confidence = c },
+            "links" => meta.links = split(key, value).into_iter().filter_map(|pair| {
+                let (topic, idx) = pair.rsplit_once(':')?;
+                Some((topic.to_string(), idx.parse().ok()?))
+            }).collect(),
+            "provenance" => meta.provenance = Provenance::parse(value),
+            "lang" => meta.lang = crate::lang::Lang::parse(value),
+            _ => {}
+        }
+    }
+    meta
+}
@@ -0,0 +1,110 @@
+//! Typed library errors. Display is the bare message, so CLI output and
+//! `?` into legacy `Result<_, String>` callers read exactly as before.
+//! Kinds map to stable integer codes for the C API and JSON-RPC codes for MCP.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AmrError {
+    /// Topic, entry, or file that doesn't exist.
+    NotFound(String),
+    /// index.bin / data.log failed validation (magic, version, bounds).
+    Corrupt(String),
+    /// Another writer holds the lock, or an in-process mutex is poisoned.
+    Locked(String),
+    /// Caller supplied a missing or malformed argument.
+    InvalidArg(String),
+    /// Filesystem read/write failure.
+    Io(String),
+}
+
+impl AmrError {
+    pub fn message(&self) -> &str {
+        match self {
+            AmrError::NotFound(m) | AmrError::Corrupt(m) | AmrError::Locked(m)
+            | AmrError::InvalidArg(m) | AmrError::Io(m) => m,
+        }
+    }
+
+    /// Short machine-readable kind, used in MCP error `data`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AmrError::NotFound(_) => "not_found",
+            AmrError::Corrupt(_) => "corrupt",
+            AmrError::Locked(_) => "locked",
+            AmrError::InvalidArg(_) => "invalid_arg",
+            AmrError::Io(_) => "io",
+        }
+    }
+
+    /// C API code (negative; 0 is success). Mirrors AMR_E_* in amaranthine.h.
+    pub fn code(&self) -> i32 {
+        match self {
+            AmrError::NotFound(_) => -1,
+            AmrError::Corrupt(_) => -2,
+            AmrError::Locked(_) => -3,
+            AmrError::InvalidArg(_) => -4,
+            AmrError::Io(_) => -5,
+        }
+    }
+
+    /// CLI process exit status. 1 stays the catch-all (IO and anything untyped).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AmrError::NotFound(_) => 2,
+            AmrError::Locked(_) => 3,
+            AmrError::Corrupt(_) => 4,
+            AmrError::InvalidArg(_) => 5,
+            AmrError::Io(_) => 1,
+        }
+    }
+
+    /// JSON-RPC error code: -32602 for bad params, -32603 for internal
+    /// failures, and the server-defined -32001..-32003 range for the rest.
+    pub fn rpc_code(&self) -> i32 {
+        match self {
+            AmrError::InvalidArg(_) => -32602,
+            AmrError::NotFound(_) => -32001,
+            AmrError::Corrupt(_) => -32002,
+            AmrError::Locked(_) => -32003,
+            AmrError::Io(_) => -32603,
+        }
+    }
+
+    /// Tag an io::Error with context, mapping ErrorKind::NotFound to NotFound.
+    pub fn io(ctx: &str, e: std::io::Error) -> Self {
+        let msg = if ctx.is_empty() { e.to_string() } else { format!("{ctx}: {e}") };
+        if e.kind() == std::io::ErrorKind::NotFound { AmrError::NotFound(msg) } else { AmrError::Io(msg) }
+    }
+}
+
+impl fmt::Display for AmrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(self.message()) }
+}
+
+impl std::error::Error for AmrError {}
+
+impl From<std::io::Error> for AmrError {
+    fn from(e: std::io::Error) -> Self { AmrError::io("", e) }
+}
+
+impl From<AmrError> for String {
+    fn from(e: AmrError) -> Self {
+        match e {
+            AmrError::NotFound(m) | AmrError::Corrupt(m) | AmrError::Locked(m)
+            | AmrError::InvalidArg(m) | AmrError::Io(m) => m,
+        }
+    }
+}
+
+/// Untyped errors from modules that still return `Result<_, String>`: mostly
+/// failed reads, writes and rebuilds, so they take the IO kind. Callers that
+/// know better (missing topic, bad argument) build the typed variant.
+impl From<String> for AmrError {
+    fn from(m: String) -> Self { AmrError::Io(m) }
+}
+
+/// Literal messages, which in the CLI and tool handlers are usage errors.
+impl From<&str> for AmrError {
+    fn from(m: &str) -> Self { AmrError::InvalidArg(m.to_string()) }
+}
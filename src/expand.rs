@@ -0,0 +1,104 @@
+//! Query term expansion over index.bin's vocabulary section (format v11):
+//! `word*` becomes the most common indexed terms starting with `word`, and in
+//! AND queries a term nothing contains becomes its nearest indexed term one
+//! edit away (`tokenzie→tokenize`). The vocabulary is sorted, so a prefix is
+//! one binary-searched run; typos scan it once.
+
+use std::cell::RefCell;
+use crate::binquery::{postings_of, read_at, read_header};
+use crate::error::AmrError;
+use crate::format::*;
+
+/// Indexed terms a `prefix*` expands to (most common first).
+pub const MAX_EXPANSIONS: usize = 16;
+/// Shorter unknown terms are left alone: one edit turns them into anything.
+const TYPO_MIN_LEN: usize = 4;
+
+thread_local! {
+    /// How this thread's last query resolved prefixes and typos, if it did.
+    static RESOLVED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Note left by the last query on this thread that expanded a `prefix*` or
+/// replaced a typo (`tokenzie→tokenize`), cleared by reading it.
+pub fn take_resolution() -> Option<String> {
+    RESOLVED.with(|t| t.borrow_mut().take())
+}
+
+/// Query words written `word*`, as the term their last token becomes.
+pub(crate) fn prefix_terms(text: &str) -> Vec<String> {
+    text.split_whitespace().filter(|w| w.ends_with('*'))
+        .filter_map(|w| crate::text::tokenize(w).pop()).collect()
+}
+
+/// Vocabulary entry `i`: (term, postings base, postings len).
+fn vocab_at<'a>(data: &'a [u8], hdr: &Header, i: usize) -> Option<(&'a str, usize, usize)> {
+    let v = read_at::<VocabEntry>(data, { hdr.vocab_off } as usize + i * std::mem::size_of::<VocabEntry>()).ok()?;
+    let at = { hdr.vocab_names_off } as usize + { v.name_off } as usize;
+    let name = std::str::from_utf8(data.get(at..at + { v.name_len } as usize)?).ok()?;
+    let base = { hdr.postings_off } as usize + { v.postings_off } as usize * std::mem::size_of::<Posting>();
+    Some((name, base, { v.postings_len } as usize))
+}
+
+/// Indexed terms starting with `prefix`, most common first, at most `max`.
+/// The vocabulary is sorted, so the matches are one binary-searched run.
+pub fn expand_prefix<'a>(data: &'a [u8], prefix: &str, max: usize) -> Result<Vec<(&'a str, usize, usize)>, AmrError> {
+    let hdr = read_header(data)?;
+    let n = { hdr.num_terms } as usize;
+    let (mut lo, mut hi) = (0, n);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        let name = vocab_at(data, &hdr, mid).ok_or_else(|| AmrError::Corrupt("vocab out of bounds".into()))?.0;
+        if name < prefix { lo = mid + 1 } else { hi = mid }
+    }
+    let mut out: Vec<_> = (lo..n).map_while(|i| vocab_at(data, &hdr, i).filter(|v| v.0.starts_with(prefix))).collect();
+    out.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(b.0)));
+    out.truncate(max);
+    Ok(out)
+}
+
+/// The indexed term one edit from `term` (insert, delete, substitute, swap
+/// adjacent), the most common on ties.
+fn nearest_term<'a>(data: &'a [u8], hdr: &Header, term: &str) -> Option<(&'a str, usize, usize)> {
+    let chars: Vec<char> = term.chars().collect();
+    if chars.len() < TYPO_MIN_LEN { return None; }
+    let mut best: Option<(&str, usize, usize)> = None;
+    let n = { hdr.num_terms } as usize;
+    for i in 0..n {
+        let Some(v) = vocab_at(data, hdr, i) else { break };
+        if v.0.len().abs_diff(term.len()) > 2 { continue; }
+        if crate::spell::distance(&chars, v.0, 1).is_none() { continue; }
+        if best.is_none_or(|b| v.2 > b.2) { best = Some(v); }
+    }
+    best
+}
+
+/// Postings lists per query term: its own, a `prefix*`'s expansions, or for
+/// a term nothing contains, its nearest indexed term's (`typos`: AND queries,
+/// where one unknown term empties the result; OR queries just skip it, so the
+/// vocabulary scan stays off the hook path). Empty if none.
+pub(crate) fn resolve_terms(data: &[u8], hdr: &Header, terms: &[String], prefixes: &[String], typos: bool)
+    -> Result<Vec<Vec<(usize, usize)>>, AmrError> {
+    let mut notes = Vec::new();
+    let mut slots = Vec::with_capacity(terms.len());
+    for t in terms {
+        if prefixes.contains(t) {
+            let found = expand_prefix(data, t, MAX_EXPANSIONS)?;
+            let names: Vec<&str> = found.iter().map(|v| v.0).collect();
+            if !names.is_empty() && names != [t.as_str()] { notes.push(format!("{t}*→{}", names.join(","))); }
+            slots.push(found.iter().map(|v| (v.1, v.2)).collect());
+            continue;
+        }
+        let found = match postings_of(data, hdr, t)? {
+            Some(l) => Some(l),
+            None if typos => nearest_term(data, hdr, t).map(|(name, base, len)| {
+                notes.push(format!("{t}→{name}"));
+                (base, len)
+            }),
+            None => None,
+        };
+        slots.push(found.into_iter().collect());
+    }
+    RESOLVED.with(|r| *r.borrow_mut() = (!notes.is_empty()).then(|| notes.join(" ")));
+    Ok(slots)
+}
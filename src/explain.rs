@@ -0,0 +1,208 @@
+//! Query planner explanation: how a query is tokenized, which path serves it
+//! (binary index vs corpus scan), per-term DFs, applied filters/boosts, and a
+//! per-result score breakdown. Debugging aid for "why didn't my entry show up".
+
+use std::path::Path;
+use crate::fxhash::FxHashMap;
+use crate::score::{Filter, SearchMode};
+
+/// Explain how `query` would be planned and ranked under `filter`.
+pub fn run(dir: &Path, query: &str, filter: &Filter, limit: usize,
+           index_data: Option<&[u8]>) -> Result<String, String> {
+    let terms = crate::text::query_terms(query);
+    let mut out = String::with_capacity(1024);
+    out.push_str(&format!("query: \"{query}\"\n"));
+    explain_tokens(&mut out, query, &terms);
+    if terms.is_empty() {
+        out.push_str("plan: no searchable terms — search would list entries unscored\n");
+        return Ok(out);
+    }
+
+    let disk;
+    let data = match index_data {
+        Some(d) => Some(d),
+        None => { disk = crate::indexview::fresh(dir).ok().flatten(); disk.as_deref().map(|m| m.bytes()) }
+    };
+    let plan = plan_path(data, filter);
+    match &plan {
+        Ok(_) => out.push_str("plan: binary index (score_via_index)\n"),
+        Err(why) => { out.push_str("plan: corpus scan — "); out.push_str(why); out.push('\n'); }
+    }
+    explain_filter(&mut out, filter);
+    if !filter.boost_topics.is_empty() {
+        out.push_str(&format!("boost_topics: {} (x{:.1})\n", filter.boost_topics.join(", "), crate::score::TOPIC_BOOST));
+    }
+
+    let scope = filter.topic_pattern();
+    crate::cache::with_corpus(dir, |cached| {
+        let scoped: Vec<&crate::cache::CachedEntry> = cached.iter()
+            .filter(|e| scope.as_ref().is_none_or(|p| p.matches(&e.topic)))
+            .filter(|e| passes_dates_and_tag(e, filter))
+            .collect();
+        let n = scoped.len() as f64;
+        let (avgdl, avgdls) = crate::score::cohort_avgdls(&scoped);
+        let mut dfs = vec![0usize; terms.len()];
+        for e in &scoped {
+            for (i, t) in terms.iter().enumerate() {
+                if e.tf_map.contains_key(t) { dfs[i] += 1; }
+            }
+        }
+
+        out.push_str(&format!("terms ({} entries in scope, avgdl={avgdl:.1}):\n", scoped.len()));
+        for (i, t) in terms.iter().enumerate() {
+            out.push_str(&format!("  {t:<20} scan df={}", dfs[i]));
+            if let (Ok(()), Some(d)) = (&plan, data) {
+                match crate::binquery::term_stats(d, t) {
+                    Some((df, idf)) => out.push_str(&format!("  index df={df} idf={idf:.3}")),
+                    None => out.push_str("  not in index"),
+                }
+            }
+            if dfs[i] == 0 { out.push_str("  (no entry contains this term)"); }
+            out.push('\n');
+        }
+
+        // Mode + AND→OR relaxation, mirroring score::score_on_cache
+        let count = |mode: SearchMode| scoped.iter()
+            .filter(|e| crate::score::matches_tokens(&e.tf_map, &terms, filter.need(mode))).count();
+        let mut mode = filter.mode;
+        let primary = count(mode);
+        let label = match (mode, filter.min_match) {
+            (SearchMode::Or, _) => "OR".to_string(),
+            (SearchMode::And, crate::score::MinMatch::All) => "AND".to_string(),
+            (SearchMode::And, need) => format!("AND (≥{} of {} terms)", need.required(terms.len()), terms.len()),
+        };
+        out.push_str(&format!("mode: {label} → {primary} match(es)"));
+        if primary == 0 && mode == SearchMode::And && terms.len() >= 2 {
+            mode = SearchMode::Or;
+            out.push_str(&format!(", relaxed to OR → {} match(es)", count(mode)));
+        }
+        out.push('\n');
+
+        // Rank with the scan path's own scoring, then break down top-K
+        let boost = filter.boost_patterns();
+        let mut ranked: Vec<(f64, &crate::cache::CachedEntry)> = scoped.iter()
+            .filter(|e| crate::score::matches_tokens(&e.tf_map, &terms, filter.need(mode)))
+            .map(|e| (breakdown(e, &terms, &dfs, n, &avgdls, &boost).total, *e))
+            .filter(|(s, _)| *s > 0.0)
+            .collect();
+        ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        if ranked.is_empty() {
+            out.push_str("results: none\n");
+            return;
+        }
+        let index_pos = index_positions(&plan, data, query, filter, mode, limit);
+        let now_days = crate::time::LocalTime::now().to_days();
+        out.push_str("top results:\n");
+        for (rank, (_, e)) in ranked.iter().take(limit).enumerate() {
+            let b = breakdown(e, &terms, &dfs, n, &avgdls, &boost);
+            out.push_str(&format!("  {}. [{}] {} score={:.3}", rank + 1, e.topic, e.date_str(), b.total));
+            if let Some(pos) = index_pos.get(&e.offset) {
+                out.push_str(&format!(" (index rank {})", pos + 1));
+            } else if plan.is_ok() {
+                out.push_str(" (outside index top-K)");
+            }
+            out.push('\n');
+            out.push_str("     ");
+            for (t, tf, part) in &b.parts {
+                out.push_str(&format!("{t}: tf={tf} bm25={part:.3}  "));
+            }
+            out.push_str(&format!("len={} (topic avgdl={:.1})\n", e.word_count,
+                avgdls.get(e.topic.as_str()).copied().unwrap_or(avgdl)));
+            out.push_str(&format!("     boosts: topic x{:.1}, tags x{:.1}, proximity x{:.2}",
+                b.boosts.topic, b.boosts.tags, b.boosts.proximity));
+            if b.boosts.focus != 1.0 { out.push_str(&format!(", boost_topics x{:.1}", b.boosts.focus)); }
+            if plan.is_ok() {
+                let days = e.days_old(now_days).max(0) as f64;
+                out.push_str(&format!(", conf {:.2}, index recency {:.2}",
+                    e.confidence(), 1.0 / (1.0 + days / 30.0)));
+            }
+            out.push('\n');
+            out.push_str("     ");
+            out.push_str(crate::text::truncate(e.preview(), 90));
+            out.push('\n');
+        }
+        if ranked.len() > limit {
+            out.push_str(&format!("  ... {} more below limit={limit}\n", ranked.len() - limit));
+        }
+    })?;
+    Ok(out)
+}
+
+/// Per-entry score components: (term, tf, bm25 part), multipliers, total.
+struct Breakdown {
+    parts: Vec<(String, usize, f64)>,
+    boosts: crate::score::Boosts,
+    total: f64,
+}
+
+fn breakdown(e: &crate::cache::CachedEntry, terms: &[String], dfs: &[usize], n: f64,
+             avgdls: &FxHashMap<&str, f64>, boost: &[crate::pattern::TopicPattern]) -> Breakdown {
+    let mut parts = Vec::with_capacity(terms.len());
+    let (total, boosts) = crate::score::score_entry(e, terms, n, dfs, avgdls, boost,
+        |i, tf, part| parts.push((terms[i].clone(), tf, part)));
+    Breakdown { parts, boosts, total }
+}
+
+/// Which path search_scored would take, or why it would fall back to scan.
+fn plan_path(data: Option<&[u8]>, filter: &Filter) -> Result<(), String> {
+    let data = data.ok_or("no index.bin")?;
+    crate::binquery::read_header(data)?;
+    if let Some(tag) = &filter.tag {
+        if crate::binquery::resolve_tag(data, tag).is_none() {
+            return Err(format!("tag '{tag}' not in index top-32 tag bitmap"));
+        }
+    }
+    Ok(())
+}
+
+/// Log offset → rank in the index path's top-K, for cross-checking both rankers.
+fn index_positions(plan: &Result<(), String>, data: Option<&[u8]>, query: &str,
+                   filter: &Filter, mode: SearchMode, limit: usize) -> FxHashMap<u32, usize> {
+    let mut pos = FxHashMap::default();
+    let (Ok(()), Some(data)) = (plan, data) else { return pos };
+    let pred = crate::filter::build_filter_pred(data, filter);
+    let hits = if mode == SearchMode::And {
+        crate::binquery::search_v2_min(data, query, &pred, filter.min_match, limit)
+    } else {
+        crate::binquery::search_v2_or(data, query, &pred, limit)
+    };
+    for (i, h) in hits.unwrap_or_default().iter().enumerate() { pos.insert(h.log_offset, i); }
+    pos
+}
+
+fn explain_tokens(out: &mut String, query: &str, terms: &[String]) {
+    let raw = crate::text::tokenize(query);
+    out.push_str("tokens: ");
+    out.push_str(&terms.join(", "));
+    let dropped: Vec<&str> = raw.iter().map(|s| s.as_str())
+        .filter(|t| !terms.iter().any(|k| k == t)).collect();
+    if !dropped.is_empty() {
+        out.push_str("  (stop words dropped: ");
+        out.push_str(&dropped.join(", "));
+        out.push(')');
+    }
+    out.push('\n');
+}
+
+fn explain_filter(out: &mut String, f: &Filter) {
+    if !f.is_active() { out.push_str("filters: none\n"); return; }
+    out.push_str("filters:");
+    if let Some(t) = &f.topic { out.push_str(&format!(" topic={t}")); }
+    if let Some(t) = &f.tag { out.push_str(&format!(" tag={t}")); }
+    if let Some(d) = f.after { out.push_str(" after="); out.push_str(&day_str(d)); }
+    if let Some(d) = f.before { out.push_str(" before="); out.push_str(&day_str(d)); }
+    out.push('\n');
+}
+
+fn day_str(day: i64) -> String {
+    let mut s = crate::time::minutes_to_date_str((day * 1440) as i32);
+    s.truncate(10);
+    s
+}
+
+fn passes_dates_and_tag(e: &crate::cache::CachedEntry, f: &Filter) -> bool {
+    let day = e.day();
+    if f.after.is_some_and(|a| day < a) { return false; }
+    if f.before.is_some_and(|b| day > b) { return false; }
+    f.tag.as_ref().is_none_or(|t| e.has_tag(t))
+}
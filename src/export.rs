@@ -1,18 +1,80 @@
 use crate::json::Value;
 use std::path::Path;
 
+/// Which entries `export_scoped` writes: a topic (name, glob or /regex/), a
+/// tag, and/or a query every entry must contain all terms of. All optional.
+#[derive(Default)]
+pub struct Scope {
+    pub topic: Option<String>,
+    pub query: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl Scope {
+    fn is_empty(&self) -> bool { self.topic.is_none() && self.query.is_none() && self.tag.is_none() }
+
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(t) = &self.topic { parts.push(format!("topic {t}")); }
+        if let Some(q) = &self.query { parts.push(format!("query \"{q}\"")); }
+        if let Some(t) = &self.tag { parts.push(format!("tag {t}")); }
+        parts.join(", ")
+    }
+}
+
+/// `export --rag` chunk bounds, in approximate LLM tokens (text::approx_tokens).
+pub struct Chunking {
+    pub max_tokens: usize,
+    /// Tokens repeated from the end of one chunk at the start of the next.
+    pub overlap: usize,
+}
+
+impl Default for Chunking {
+    fn default() -> Self { Self { max_tokens: 256, overlap: 32 } }
+}
+
+/// Entries `scope` selects, in log order, each with its index in its topic.
+fn select<'a>(cached: &'a [crate::cache::CachedEntry], scope: &Scope,
+              pattern: Option<&crate::pattern::TopicPattern>, terms: &[String])
+    -> Vec<(usize, &'a crate::cache::CachedEntry)> {
+    let mut counters: crate::fxhash::FxHashMap<&str, usize> = crate::fxhash::FxHashMap::default();
+    let mut out = Vec::new();
+    for e in cached {
+        let slot = counters.entry(e.topic.as_str()).or_default();
+        let idx = *slot;
+        *slot += 1;
+        if pattern.is_some_and(|p| !p.matches(&e.topic)) { continue; }
+        if scope.tag.as_deref().is_some_and(|t| !e.has_tag(t)) { continue; }
+        if !crate::score::matches_tokens(&e.tf_map, terms, crate::score::MinMatch::All) { continue; }
+        out.push((idx, e));
+    }
+    out
+}
+
 /// Export all topics as structured JSON from cached corpus.
 pub fn export(dir: &Path) -> Result<String, String> {
+    export_scoped(dir, &Scope::default())
+}
+
+/// Same JSON schema as `export`, restricted to the entries `scope` selects —
+/// a slice to hand to a teammate, who imports it like a full export.
+pub fn export_scoped(dir: &Path, scope: &Scope) -> Result<String, String> {
+    let pattern = scope.topic.as_deref().map(crate::pattern::TopicPattern::parse).transpose()
+        .map_err(|e| e.to_string())?;
+    let terms = scope.query.as_deref().map(crate::text::query_terms).unwrap_or_default();
     crate::cache::with_corpus(dir, |cached| {
         // Group by topic, preserving insertion order
         let mut topic_order: Vec<String> = Vec::new();
         let mut grouped: std::collections::BTreeMap<&str, Vec<&crate::cache::CachedEntry>> =
             std::collections::BTreeMap::new();
-        for e in cached {
+        for (_, e) in select(cached, scope, pattern.as_ref(), &terms) {
             if !grouped.contains_key(e.topic.as_str()) { topic_order.push(e.topic.to_string()); }
             grouped.entry(e.topic.as_str()).or_default().push(e);
         }
 
+        if topic_order.is_empty() && !scope.is_empty() {
+            return Err(format!("no entries match {}", scope.describe()));
+        }
         let mut topics: Vec<Value> = Vec::new();
         for name in &topic_order {
             let group = &grouped[name.as_str()];
@@ -22,7 +84,7 @@ pub fn export(dir: &Path) -> Result<String, String> {
                     .map(|t| Value::Str(t.clone())).collect();
                 let mut body_lines: Vec<&str> = Vec::new();
                 for line in e.body.lines() {
-                    if !line.starts_with("[tags: ") { body_lines.push(line); }
+                    if !crate::entryfmt::is_field(line, "tags") { body_lines.push(line); }
                 }
                 Value::Obj(vec![
                     ("timestamp".into(), Value::Str(date)),
@@ -40,8 +102,94 @@ pub fn export(dir: &Path) -> Result<String, String> {
             ("version".into(), Value::Str("4.0.0".into())),
             ("topics".into(), Value::Arr(topics)),
         ]);
-        root.pretty()
-    })
+        Ok(root.pretty())
+    })?
+}
+
+/// Entries `scope` selects as JSON Lines for vector-store ingestion: one record
+/// per chunk of an entry's text (metadata lines stripped), each chunk at most
+/// `c.max_tokens` and overlapping the previous by `c.overlap`. Records carry
+/// `id` (`<uid>-<chunk>`, stable across rebuilds), `uid`, `address`, `topic`,
+/// `tags`, `date`, `source`, `chunk`/`chunks` and `tokens`.
+pub fn export_rag(dir: &Path, scope: &Scope, c: &Chunking) -> Result<String, String> {
+    if c.max_tokens == 0 || c.overlap >= c.max_tokens {
+        return Err(format!("chunk size must be above 0 and above the overlap (got {} and {})", c.max_tokens, c.overlap));
+    }
+    let pattern = scope.topic.as_deref().map(crate::pattern::TopicPattern::parse).transpose()
+        .map_err(|e| e.to_string())?;
+    let terms = scope.query.as_deref().map(crate::text::query_terms).unwrap_or_default();
+    crate::cache::with_corpus(dir, |cached| {
+        let selected = select(cached, scope, pattern.as_ref(), &terms);
+        if selected.is_empty() && !scope.is_empty() {
+            return Err(format!("no entries match {}", scope.describe()));
+        }
+        let mut out = String::new();
+        for (idx, e) in selected {
+            let content: Vec<&str> = e.body.lines().filter(|l| !crate::text::is_metadata_line(l)).collect();
+            let content = content.join("\n");
+            let chunks = chunk(content.trim(), c);
+            let uid = crate::address::format_uid(crate::address::entry_uid(e.timestamp_min, &e.body));
+            let s = |v: &str| Value::Str(v.to_string());
+            for (n, text) in chunks.iter().enumerate() {
+                let record = Value::Obj(vec![
+                    ("id".into(), Value::Str(format!("{uid}-{n}"))),
+                    ("uid".into(), s(&uid)),
+                    ("address".into(), Value::Str(format!("{}:{idx}", e.topic))),
+                    ("topic".into(), s(&e.topic)),
+                    ("tags".into(), Value::Arr(e.tags().iter().map(|t| s(t)).collect())),
+                    ("date".into(), Value::Str(e.date_str())),
+                    ("source".into(), e.source().map_or(Value::Null, s)),
+                    ("chunk".into(), Value::Num(n as f64)),
+                    ("chunks".into(), Value::Num(chunks.len() as f64)),
+                    ("tokens".into(), Value::Num(crate::text::approx_tokens_of(text) as f64)),
+                    ("text".into(), s(text)),
+                ]);
+                out.push_str(&record.to_string());
+                out.push('\n');
+            }
+        }
+        Ok(out)
+    })?
+}
+
+/// Split `text` at whitespace into slices of at most `c.max_tokens` (a single
+/// longer word stands alone), each starting `c.overlap` tokens before the
+/// previous one ended. Slices keep the original line breaks.
+fn chunk<'a>(text: &'a str, c: &Chunking) -> Vec<&'a str> {
+    // (start, end, tokenizer words) of each whitespace-separated word
+    let mut words: Vec<(usize, usize, usize)> = Vec::new();
+    let mut start = None;
+    for (i, ch) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (start, ch.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(st), true) => {
+                words.push((st, i, crate::text::tokenize(&text[st..i]).len().max(1)));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    let mut first = 0;
+    while first < words.len() {
+        let fits = |n: usize, budget: usize| crate::text::approx_tokens(n) <= budget;
+        let (mut last, mut n) = (first, words[first].2);
+        while last + 1 < words.len() && fits(n + words[last + 1].2, c.max_tokens) {
+            last += 1;
+            n += words[last].2;
+        }
+        out.push(&text[words[first].0..words[last].1]);
+        if last + 1 == words.len() { break; }
+        // Back up over `overlap` tokens, always moving forward
+        let mut next = last + 1;
+        let mut back = 0;
+        while next - 1 > first && fits(back + words[next - 1].2, c.overlap) {
+            next -= 1;
+            back += words[next].2;
+        }
+        first = next;
+    }
+    out
 }
 
 /// Import topics from JSON (merges with existing — does not overwrite).
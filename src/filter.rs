@@ -0,0 +1,132 @@
+//! Search filters: date range, tag, topic scope (name, glob or /regex/),
+//! provenance, language, AND/OR mode with a minimum-should-match, soft topic
+//! boosts. Checked per entry on the corpus scan (`passes_filter_cached`) and
+//! compiled to a binquery `FilterPred` on the index path.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SearchMode { And, Or }
+
+/// Minimum-should-match for AND mode: how many distinct query terms an entry
+/// must contain. `All` is strict AND; OR mode is always one.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MinMatch { All, Count(usize), Percent(u8) }
+
+impl MinMatch {
+    /// "all", "3", or "75%".
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("all") { return Some(MinMatch::All); }
+        if let Some(p) = s.strip_suffix('%') {
+            return p.trim().parse::<u8>().ok().filter(|p| (1..=100).contains(p)).map(MinMatch::Percent);
+        }
+        s.parse::<usize>().ok().filter(|&n| n > 0).map(MinMatch::Count)
+    }
+
+    /// Terms required out of `n`, clamped to 1..=n. Percentages round down.
+    pub fn required(self, n: usize) -> usize {
+        let k = match self {
+            MinMatch::All => n,
+            MinMatch::Count(k) => k,
+            MinMatch::Percent(p) => n * p as usize / 100,
+        };
+        k.clamp(1, n.max(1))
+    }
+}
+
+/// Filter options for search (date range + tag + topic scope + mode).
+#[derive(Clone)]
+pub struct Filter {
+    pub after: Option<i64>,
+    pub before: Option<i64>,
+    pub tag: Option<String>,
+    pub topic: Option<String>,
+    /// Only entries recorded with this provenance (provenance.rs).
+    pub provenance: Option<crate::provenance::Provenance>,
+    /// Only entries in this language (lang.rs).
+    pub lang: Option<crate::lang::Lang>,
+    pub mode: SearchMode,
+    /// AND-mode threshold; `All` unless the caller relaxes it.
+    pub min_match: MinMatch,
+    /// Topics (names, globs or /regex/) whose scores are multiplied by
+    /// TOPIC_BOOST — a soft `topic`: others still rank, just lower.
+    pub boost_topics: Vec<String>,
+    /// Run the corpus scan beside the index and fuse the rankings when they
+    /// disagree (see `score::fuse`). Costs a full scan per query.
+    pub hybrid: bool,
+}
+
+impl Filter {
+    pub fn none() -> Self {
+        Self { after: None, before: None, tag: None, topic: None, provenance: None, lang: None,
+               mode: SearchMode::And, min_match: MinMatch::All, boost_topics: Vec::new(), hybrid: false }
+    }
+
+    /// Matching rule for `mode`: this filter's threshold for AND, any term for OR.
+    pub fn need(&self, mode: SearchMode) -> MinMatch {
+        match mode { SearchMode::And => self.min_match, SearchMode::Or => MinMatch::Count(1) }
+    }
+    pub fn is_active(&self) -> bool {
+        self.after.is_some() || self.before.is_some() || self.tag.is_some() || self.topic.is_some()
+            || self.provenance.is_some() || self.lang.is_some()
+    }
+
+    /// Compiled topic scope (exact name, glob, or /regex/). Callers taking user
+    /// input reject bad patterns up front; here one falls back to an exact name.
+    pub fn topic_pattern(&self) -> Option<crate::pattern::TopicPattern> {
+        let t = self.topic.as_deref()?;
+        Some(crate::pattern::TopicPattern::parse(t)
+            .unwrap_or_else(|_| crate::pattern::TopicPattern::Exact(t.to_string())))
+    }
+
+    /// Compiled `boost_topics`, same fallback as `topic_pattern`.
+    pub fn boost_patterns(&self) -> Vec<crate::pattern::TopicPattern> {
+        self.boost_topics.iter().map(|t| crate::pattern::TopicPattern::parse(t)
+            .unwrap_or_else(|_| crate::pattern::TopicPattern::Exact(t.to_string()))).collect()
+    }
+}
+
+/// Whether `topic` is inside `scope` (None: every topic is).
+pub(crate) fn in_scope(scope: &Option<crate::pattern::TopicPattern>, topic: &str) -> bool {
+    scope.as_ref().is_none_or(|p| p.matches(topic))
+}
+
+/// Corpus-scan filter check; held-for-review entries never pass.
+pub(crate) fn passes_filter_cached(e: &crate::cache::CachedEntry, f: &Filter) -> bool {
+    if e.has_tag(crate::review::TAG) { return false; }
+    if f.after.is_some() || f.before.is_some() {
+        let days = e.day();
+        if let Some(after) = f.after { if days < after { return false; } }
+        if let Some(before) = f.before { if days > before { return false; } }
+    }
+    if let Some(ref tag) = f.tag {
+        if !e.has_tag(tag) { return false; }
+    }
+    if f.provenance.is_some() && e.provenance() != f.provenance { return false; }
+    if f.lang.is_some() && e.lang() != f.lang { return false; }
+    true
+}
+
+/// `filter` as an index pre-scoring predicate: topic and tag names resolved
+/// to ids and bits in `index_data`.
+pub(crate) fn build_filter_pred(index_data: &[u8], filter: &Filter) -> crate::binquery::FilterPred {
+    let (topic_id, topic_set) = match filter.topic_pattern() {
+        None => (None, None),
+        Some(crate::pattern::TopicPattern::Exact(name)) => (crate::binquery::resolve_topic(index_data, &name), None),
+        Some(pat) => {
+            let table = crate::binquery::topic_table(index_data).unwrap_or_default();
+            let mut set = vec![false; table.iter().map(|(id, _, _)| *id as usize + 1).max().unwrap_or(0)];
+            for (id, name, _) in &table { if pat.matches(name) { set[*id as usize] = true; } }
+            (None, Some(set))
+        }
+    };
+    let after_days = filter.after.map(|d| d.max(0) as u16).unwrap_or(0);
+    let before_days = filter.before.map(|d| d.min(u16::MAX as i64) as u16).unwrap_or(u16::MAX);
+    let tag_mask = match &filter.tag {
+        Some(tag) => crate::binquery::resolve_tag(index_data, tag)
+            .map(|bit| 1u32 << bit).unwrap_or(0),
+        None => 0,
+    };
+    let provenance = filter.provenance.map_or(0, |p| p.code());
+    let lang = filter.lang.map_or(0, |l| l.code());
+    crate::binquery::FilterPred { topic_id, topic_set, after_days, before_days, tag_mask, provenance, lang }
+}
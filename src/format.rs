@@ -2,7 +2,7 @@
 //! All structs are repr(C, packed) for zero-copy access via pointer arithmetic.
 
 pub const MAGIC: [u8; 4] = [b'A', b'M', b'R', b'N'];
-pub const VERSION: u32 = 3;
+pub const VERSION: u32 = 11;
 
 #[derive(Clone, Copy)]
 #[repr(C, packed)]
@@ -25,6 +25,12 @@ pub struct Header {
     pub total_len: u32,
     pub tag_names_off: u32,
     pub num_tags: u32,
+    /// Positions pool: u16 token indexes, addressed by Posting::pos_off. v10+.
+    pub positions_off: u32,
+    /// Vocabulary: num_terms VocabEntry sorted by term, for prefix/typo lookup. v11+.
+    pub vocab_off: u32,
+    /// Term string pool, addressed by VocabEntry::name_off. v11+.
+    pub vocab_names_off: u32,
 }
 
 #[derive(Clone, Copy)]
@@ -41,7 +47,10 @@ pub struct Posting {
     pub entry_id: u32,
     pub tf: u16,
     pub idf_x1000: u32,
-    pub _pad: u16,
+    /// Positions recorded for this term in this entry (phrase.rs). v10+.
+    pub pos_len: u16,
+    /// First of them, in u16s from Header::positions_off.
+    pub pos_off: u32,
 }
 
 #[derive(Clone, Copy)]
@@ -58,7 +67,23 @@ pub struct EntryMeta {
     pub tag_bitmap: u32,
     pub confidence: u8,
     pub epoch_days: u16,
-    pub _pad: u8,
+    /// 0–255, see importance.rs. v4+.
+    pub importance: u8,
+    /// Provenance::code, 0 = not recorded (provenance.rs). v8+.
+    pub provenance: u8,
+    /// Lang::code, 0 = unclassified (lang.rs). v9+.
+    pub lang: u8,
+}
+
+/// An indexed term's text and postings list, so `tokeniz*` and typos can be
+/// resolved without the corpus (the TermTable holds hashes only).
+#[derive(Clone, Copy)]
+#[repr(C, packed)]
+pub struct VocabEntry {
+    pub name_off: u32,
+    pub name_len: u16,
+    pub postings_off: u32,
+    pub postings_len: u32,
 }
 
 #[derive(Clone, Copy)]
@@ -67,6 +92,10 @@ pub struct TopicEntry {
     pub name_off: u32,
     pub name_len: u16,
     pub entry_count: u16,
+    /// topic_uid(name). v5+.
+    pub uid: u64,
+    /// Topic-cohort average doc length ×100, see score::cohort_avgdl. v7+.
+    pub avgdl_x100: u32,
 }
 
 #[derive(Clone, Copy)]
@@ -86,6 +115,10 @@ pub fn hash_term(s: &str) -> u64 {
     h
 }
 
+/// Stable topic UID: FNV-1a of the (sanitized) topic name. Unlike topic_id,
+/// which is a dense per-build index, it survives rebuilds and compaction.
+pub fn topic_uid(name: &str) -> u64 { hash_term(name) }
+
 /// Reinterpret a packed struct as a byte slice for serialization.
 pub fn as_bytes<T: Sized>(val: &T) -> &[u8] {
     unsafe { std::slice::from_raw_parts(val as *const T as *const u8, std::mem::size_of::<T>()) }
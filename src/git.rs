@@ -0,0 +1,122 @@
+//! Git-aware source freshness (`freshness = git`). Checkouts and branch
+//! switches rewrite mtimes without changing content, so mtime staleness cries
+//! wolf. In git mode a sourced entry records `[commit: <sha>]` — the commit
+//! that last changed its file when it was stored — and is stale once the
+//! file's last-changed commit moves. Uncommitted edits still fall back to
+//! mtime. Lookups shell out to `git` once per file and are cached by
+//! (path, mtime, len), so index rebuilds after a write stay cheap.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Recorded commits are shortened to this many hex chars.
+pub const SHA_LEN: usize = 12;
+
+/// What git says about one file right now.
+#[derive(Clone)]
+pub struct FileState {
+    /// Last commit that changed the file (full sha).
+    pub commit: String,
+    /// Working-tree or index changes on top of `commit`.
+    pub dirty: bool,
+}
+
+type Key = (PathBuf, Option<SystemTime>, u64);
+static CACHE: Mutex<Vec<(Key, Option<FileState>)>> = Mutex::new(Vec::new());
+const CACHE_MAX: usize = 4096;
+
+/// `true` when `<dir>/config` has `freshness = git`.
+pub fn enabled(dir: &Path) -> bool {
+    crate::settings::load(dir).get("freshness") == Some("git")
+}
+
+/// Git state of `path`; None if git is missing, the file is outside a repo,
+/// or it has never been committed.
+pub fn file_state(path: &Path) -> Option<FileState> {
+    let meta = std::fs::metadata(path).ok()?;
+    let key = (path.to_path_buf(), meta.modified().ok(), meta.len());
+    if let Ok(cache) = CACHE.lock() {
+        if let Some((_, s)) = cache.iter().find(|(k, _)| *k == key) { return s.clone(); }
+    }
+    let state = query(path);
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.retain(|(k, _)| k.0 != key.0);
+        if cache.len() >= CACHE_MAX { cache.clear(); }
+        cache.push((key, state.clone()));
+    }
+    state
+}
+
+fn query(path: &Path) -> Option<FileState> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name()?;
+    let log = Command::new("git").arg("-C").arg(parent)
+        .args(["log", "-1", "--format=%H", "--"]).arg(name).output().ok()?;
+    let commit = String::from_utf8(log.stdout).ok()?.trim().to_string();
+    if !log.status.success() || commit.is_empty() { return None; }
+    let status = Command::new("git").arg("-C").arg(parent)
+        .args(["status", "--porcelain", "--"]).arg(name).output().ok()?;
+    Some(FileState { commit, dirty: !status.stdout.is_empty() })
+}
+
+/// The `[commit: …]` value for a store into `dir`: None unless git mode is on
+/// and `source` is a committed file.
+pub fn record(dir: &Path, source: Option<&str>) -> Option<String> {
+    source.filter(|_| enabled(dir)).and_then(|s| commit_for_source(dir, s))
+}
+
+/// The `[commit: …]` value to store for `source` (`path` or `path:line`).
+pub fn commit_for_source(dir: &Path, source: &str) -> Option<String> {
+    if source.contains('*') { return None; }
+    let path = source.rsplit_once(':')
+        .filter(|(_, n)| n.parse::<usize>().is_ok())
+        .map_or(source, |(p, _)| p);
+    let resolved = crate::config::resolve_source(dir, path)?;
+    let state = file_state(&resolved)?;
+    Some(state.commit[..SHA_LEN.min(state.commit.len())].to_string())
+}
+
+/// Git verdict for an entry recorded at `recorded`: Some(true) stale,
+/// Some(false) fresh, None when git can't tell (dirty file, no repo) and the
+/// caller should fall back to mtime.
+pub fn is_stale(path: &Path, recorded: &str) -> Option<bool> {
+    let state = file_state(path)?;
+    if !state.commit.starts_with(recorded) { return Some(true); }
+    if state.dirty { None } else { Some(false) }
+}
+
+static ROOTS: Mutex<Vec<(PathBuf, Option<PathBuf>)>> = Mutex::new(Vec::new());
+
+/// Top level of the git work tree holding `path` (a file or directory),
+/// canonicalized. Cached per directory; None outside a repo or without git.
+pub fn root(path: &Path) -> Option<PathBuf> {
+    let dir = if path.is_dir() { path } else { path.parent()? };
+    if let Some((_, r)) = ROOTS.lock().ok()?.iter().find(|(d, _)| d == dir) { return r.clone(); }
+    let found = Command::new("git").arg("-C").arg(dir).args(["rev-parse", "--show-toplevel"]).output().ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .and_then(|s| PathBuf::from(s.trim()).canonicalize().ok());
+    let mut roots = ROOTS.lock().ok()?;
+    if roots.len() >= CACHE_MAX { roots.clear(); }
+    roots.push((dir.to_path_buf(), found.clone()));
+    found
+}
+
+/// Renames in the history of the repo around the working directory, newest
+/// first, as (old, new) paths relative to the repo root. Bounded to the last
+/// `commits` commits; empty without git.
+pub fn renames(commits: usize) -> Vec<(String, String)> {
+    let Ok(out) = Command::new("git")
+        .args(["log", "-M", "--diff-filter=R", "--name-status", "--format=", "-n", &commits.to_string()])
+        .output() else { return Vec::new() };
+    if !out.status.success() { return Vec::new(); }
+    String::from_utf8_lossy(&out.stdout).lines()
+        .filter(|l| l.starts_with('R'))
+        .filter_map(|l| {
+            let mut f = l.split('\t').skip(1);
+            Some((f.next()?.to_string(), f.next()?.to_string()))
+        })
+        .collect()
+}
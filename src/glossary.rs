@@ -0,0 +1,117 @@
+//! `define <term>`: find the entries that introduce a term and keep a
+//! generated `glossary` topic. A sentence defines the term when it opens with
+//! it (past bullets, bold and backticks) followed by a connector — "X is",
+//! "X are", "X means", "X =", "X:", "X —", "X (…)". Without one, the term's
+//! first mention stands in. The best definition is written to the glossary as
+//! one entry per term (`**term**: … (from topic:uid:…)`, tagged `glossary`),
+//! rewritten when the definition changes, so the next lookup is a plain read.
+
+use crate::error::AmrError;
+use std::fmt::Write;
+use std::path::Path;
+
+pub const TOPIC: &str = "glossary";
+
+/// Connectors that turn "<term><connector>…" into a definition, strongest first.
+const CONNECTORS: &[&str] = &[" means ", " refers to ", " is ", " are ", " = ", ": ", " — ", " - ", " ("];
+
+struct Found { text: String, topic: String, idx: usize, uid: u64, ts: i32, strength: usize }
+
+pub fn define(dir: &Path, term: &str) -> Result<String, AmrError> {
+    let term = term.trim();
+    if term.is_empty() { return Err(AmrError::InvalidArg("term is required".into())); }
+    let needle = term.to_lowercase();
+    let (defs, first) = crate::cache::with_corpus(dir, |cached| {
+        let mut counters: crate::fxhash::FxHashMap<&str, usize> = crate::fxhash::FxHashMap::default();
+        let mut defs: Vec<Found> = Vec::new();
+        let mut first: Option<Found> = None;
+        for e in cached {
+            let slot = counters.entry(e.topic.as_str()).or_default();
+            let idx = *slot;
+            *slot += 1;
+            if e.topic.as_str() == TOPIC || !e.body.to_lowercase().contains(&needle) { continue; }
+            let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+            let found = |text: &str, strength| Found {
+                text: text.to_string(), topic: e.topic.to_string(), idx, uid, ts: e.timestamp_min, strength };
+            for line in e.body.lines().filter(|l| !crate::text::is_metadata_line(l)) {
+                for s in sentences(line) {
+                    if let Some(strength) = defines(s, &needle) {
+                        defs.push(found(s, strength));
+                    } else if s.to_lowercase().contains(&needle) && first.as_ref().is_none_or(|f| e.timestamp_min < f.ts) {
+                        first = Some(found(s, CONNECTORS.len()));
+                    }
+                }
+            }
+        }
+        // Strongest connector first, then the oldest (the introduction)
+        defs.sort_by_key(|d| (d.strength, d.ts));
+        (defs, first)
+    })?;
+
+    let Some(best) = defs.first().or(first.as_ref()) else {
+        return Err(AmrError::NotFound(format!("'{term}' isn't mentioned in any entry")));
+    };
+    let glossary = upsert(dir, term, best)?;
+    let mut out = String::new();
+    let _ = writeln!(out, "{term}: {}", crate::text::truncate(&best.text, 300));
+    let _ = writeln!(out, "  from {}", crate::address::format_both(&best.topic, best.idx, best.uid));
+    if defs.len() > 1 {
+        let _ = writeln!(out, "\nalso defined in:");
+        for d in defs.iter().skip(1).take(4) {
+            let _ = writeln!(out, "  [{}:{}] {}", d.topic, d.idx, crate::text::truncate(&d.text, 120));
+        }
+    }
+    if defs.is_empty() { let _ = writeln!(out, "  (no defining sentence — first mention shown)"); }
+    let _ = writeln!(out, "\n{glossary}");
+    Ok(out)
+}
+
+/// Some(rank) if `sentence` opens with the term followed by a connector.
+fn defines(sentence: &str, needle: &str) -> Option<usize> {
+    let s = sentence.trim_start_matches(['-', '*', '•', ' ', '`']).to_lowercase();
+    let rest = s.strip_prefix(needle)?;
+    let rest = rest.trim_start_matches(['*', '`', '"', '\'']);
+    CONNECTORS.iter().position(|c| rest.starts_with(c) && rest.len() > c.len() + 2)
+}
+
+/// Sentences of one line: split at `.`/`?`/`!` followed by a space.
+fn sentences(line: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let b = line.as_bytes();
+    for i in 0..b.len() {
+        if matches!(b[i], b'.' | b'?' | b'!') && b.get(i + 1).is_none_or(|c| *c == b' ') {
+            out.push(line[start..=i].trim());
+            start = i + 1;
+        }
+    }
+    out.push(line[start..].trim());
+    out.retain(|s| !s.is_empty());
+    out
+}
+
+/// Create or refresh the glossary entry for `term`. Returns what happened.
+fn upsert(dir: &Path, term: &str, d: &Found) -> Result<String, AmrError> {
+    let head = format!("**{term}**:");
+    let body = format!("{}\n{head} {} (from {}:uid:{})", crate::entryfmt::line("tags", TOPIC),
+        d.text, d.topic, crate::address::format_uid(d.uid));
+    let _lock = crate::lock::FileLock::acquire(dir)?;
+    let log_path = crate::datalog::ensure_log(dir)?;
+    let existing = crate::delete::topic_entries(&log_path, TOPIC)?;
+    let lower = head.to_lowercase();
+    let old = existing.iter().enumerate()
+        .find(|(_, e)| crate::compress::first_content(&e.body).to_lowercase().starts_with(&lower));
+    match old {
+        Some((i, e)) if e.body.trim().eq_ignore_ascii_case(&body) => Ok(format!("glossary:{i} is up to date")),
+        Some((_, e)) => {
+            crate::datalog::append_entry(&log_path, TOPIC, &body, e.timestamp_min)?;
+            crate::datalog::append_delete(&log_path, e.offset)?;
+            Ok(format!("glossary:{} updated", existing.len() - 1))
+        }
+        None => {
+            let ts = crate::time::LocalTime::now().to_minutes() as i32;
+            crate::datalog::append_entry(&log_path, TOPIC, &body, ts)?;
+            Ok(format!("glossary:{} added", existing.len()))
+        }
+    }
+}
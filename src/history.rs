@@ -0,0 +1,83 @@
+//! Search over entry history: the versions a tombstone removed but data.log
+//! still holds — old wordings from edits (`superseded`, the entry lives on
+//! under the same timestamp) and deleted entries. Lets `search
+//! include_history=true` find why an approach was abandoned after the entry
+//! was rewritten. History only reaches back to the last `compact log=true`,
+//! which drops tombstoned records. A version is superseded when the record
+//! written just before its tombstone replaced it (`datalog::iter_dead`);
+//! copies made by rename/merge (same uid still live) are not history and are
+//! skipped.
+
+use crate::error::AmrError;
+use std::fmt::Write;
+use std::path::Path;
+
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// Superseded and deleted versions matching `query` (every term, or any with
+/// `any_term`), best first, as a section to append to search results.
+pub fn search(dir: &Path, query: &str, any_term: bool, limit: usize) -> Result<String, AmrError> {
+    let log_path = crate::config::log_path(dir);
+    if !log_path.exists() { return Ok(String::new()); }
+    let terms = crate::text::query_terms(query);
+    if terms.is_empty() { return Ok(String::new()); }
+    let dead = crate::datalog::iter_dead(&log_path)?;
+    let live = crate::datalog::iter_live(&log_path)?;
+
+    let live_uids: crate::fxhash::FxHashSet<u64> = live.iter()
+        .map(|e| crate::address::entry_uid(e.timestamp_min, &e.body)).collect();
+    // Live offset → address, for where a superseded version's line ended up
+    let mut addrs: crate::fxhash::FxHashMap<u32, String> = crate::fxhash::FxHashMap::default();
+    let mut counters: crate::fxhash::FxHashMap<&str, usize> = crate::fxhash::FxHashMap::default();
+    for e in &live {
+        let slot = counters.entry(e.topic.as_str()).or_default();
+        addrs.insert(e.offset, format!("{}:{slot}", e.topic));
+        *slot += 1;
+    }
+    let replaced: crate::fxhash::FxHashMap<u32, Option<u32>> = dead.iter().map(|(e, by)| (e.offset, *by)).collect();
+    // Follow replacements to the live version; None if the chain ends deleted
+    let current = |mut at: u32| -> Option<&String> {
+        for _ in 0..replaced.len() + 1 {
+            if let Some(a) = addrs.get(&at) { return Some(a); }
+            at = (*replaced.get(&at)?)?;
+        }
+        None
+    };
+
+    let mut seen = crate::fxhash::FxHashSet::default();
+    let mut hits: Vec<(usize, &crate::datalog::LogEntry, Option<u32>)> = Vec::new();
+    for (e, by) in &dead {
+        let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+        if live_uids.contains(&uid) || !seen.insert(uid) { continue; }
+        let tokens: crate::fxhash::FxHashSet<String> = crate::text::tokenize(&e.body).into_iter().collect();
+        let matched = terms.iter().filter(|t| tokens.contains(*t)).count();
+        if matched == 0 || (!any_term && matched < terms.len()) { continue; }
+        hits.push((matched, e, *by));
+    }
+    hits.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.timestamp_min.cmp(&a.1.timestamp_min)));
+
+    let mut out = format!("\n--- history: {} superseded/deleted version(s) match", hits.len());
+    if hits.len() > limit { let _ = write!(out, ", showing {limit}"); }
+    out.push_str(" (since the last log compaction) ---\n");
+    if hits.is_empty() { return Ok(out); }
+    for (_, e, by) in hits.iter().take(limit) {
+        let date = crate::time::minutes_to_date_str(e.timestamp_min);
+        let status = match by.map(&current) {
+            Some(Some(now)) => format!("[superseded, now {now}]"),
+            Some(None) => "[superseded, since deleted]".to_string(),
+            None => "[deleted]".to_string(),
+        };
+        let _ = writeln!(out, "{status} {} {date}", e.topic);
+        let _ = writeln!(out, "  {}", crate::text::truncate(snippet(&e.body, &terms).trim(), 140));
+    }
+    Ok(out)
+}
+
+/// First content line holding a query term, else the first content line.
+fn snippet<'a>(body: &'a str, terms: &[String]) -> &'a str {
+    let content = || body.lines().filter(|l| !crate::text::is_metadata_line(l) && !l.trim().is_empty());
+    content().find(|l| {
+        let lower = l.to_lowercase();
+        terms.iter().any(|t| lower.contains(t.as_str()))
+    }).or_else(|| content().next()).unwrap_or("")
+}
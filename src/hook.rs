@@ -8,17 +8,18 @@ use std::path::Path;
 
 pub fn run(hook_type: &str, dir: &Path) -> Result<String, String> {
     // approve-mcp and stop need no stdin at all
-    match hook_type {
-        "approve-mcp" => return Ok(APPROVE_MCP_RESPONSE.into()),
-        "stop" => return stop(dir),
-        _ => {}
-    }
-
     let mut input = String::new();
-    std::io::stdin().read_to_string(&mut input).ok();
-    let input = input.trim();
+    if !matches!(hook_type, "approve-mcp" | "stop") {
+        std::io::stdin().read_to_string(&mut input).ok();
+    }
+    handle(hook_type, input.trim(), dir, true)
+}
 
+/// `debounce`: whether stop honors (and refreshes) its 120s stamp.
+fn handle(hook_type: &str, input: &str, dir: &Path, debounce: bool) -> Result<String, String> {
     match hook_type {
+        "approve-mcp" => Ok(APPROVE_MCP_RESPONSE.into()),
+        "stop" => stop(dir, debounce),
         "ambient" => ambient(input, dir),
         "post-build" => post_build(input, dir),
         "subagent-start" => subagent_start(dir),
@@ -26,28 +27,63 @@ pub fn run(hook_type: &str, dir: &Path) -> Result<String, String> {
     }
 }
 
-/// Memory-map index.bin for zero-copy queries — no socket overhead, no full file read.
-/// Uses mmap(2) directly — zero external dependencies.
-/// Returns None if file doesn't exist or is too small.
-/// Mapping lives until process exit (no munmap needed for short-lived hook processes).
-fn mmap_index(dir: &Path) -> Option<&'static [u8]> {
-    let path = dir.join("index.bin");
-    let f = std::fs::File::open(&path).ok()?;
-    let len = f.metadata().ok()?.len() as usize;
-    if len < std::mem::size_of::<crate::format::Header>() { return None; }
-
-    use std::os::unix::io::AsRawFd;
-    let fd = f.as_raw_fd();
-
-    extern "C" {
-        fn mmap(addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, off: i64) -> *mut u8;
+/// `hook test <type> [--input FILE] [--keep-state]`: feed a captured event
+/// through the same handler `run` uses and report the output, its
+/// additionalContext and the time taken. The stop debounce is bypassed, and
+/// session.json / sessions.log are put back afterwards unless `keep_state`.
+pub fn test(hook_type: &str, dir: &Path, input_path: Option<&str>, keep_state: bool) -> Result<String, String> {
+    use std::fmt::Write;
+    let input = match input_path {
+        Some("-") | None => {
+            let mut buf = String::new();
+            if !matches!(hook_type, "approve-mcp" | "stop") { std::io::stdin().read_to_string(&mut buf).ok(); }
+            buf
+        }
+        Some(p) => std::fs::read_to_string(p).map_err(|e| format!("{p}: {e}"))?,
+    };
+    let saved: Vec<(std::path::PathBuf, Option<Vec<u8>>)> = crate::session::state_files(dir).into_iter()
+        .map(|p| { let bytes = std::fs::read(&p).ok(); (p, bytes) }).collect();
+
+    let t0 = std::time::Instant::now();
+    let result = handle(hook_type, input.trim(), dir, false);
+    let elapsed = t0.elapsed();
+
+    if !keep_state {
+        for (path, bytes) in &saved {
+            let _ = match bytes {
+                Some(b) => std::fs::write(path, b),
+                None => std::fs::remove_file(path).or(Ok(())),
+            };
+        }
     }
+    let output = result?;
+    let mut out = String::new();
+    if output.is_empty() { out.push_str("(no output: the hook stays silent)\n"); }
+    else { let _ = writeln!(out, "{output}"); }
+    let _ = write!(out, "---\nhook: {hook_type}  time: {:.2}ms  output: {} bytes",
+        elapsed.as_secs_f64() * 1000.0, output.len());
+    if !output.is_empty() {
+        match crate::json::parse(&output) {
+            Ok(v) => {
+                out.push_str(", valid JSON\n");
+                let ctx = v.get("hookSpecificOutput").and_then(|h| h.get("additionalContext")).and_then(|c| c.as_str());
+                if let Some(ctx) = ctx {
+                    let _ = writeln!(out, "additionalContext ({} lines, {}):", ctx.lines().count(),
+                        crate::text::format_tokens(crate::text::approx_tokens_of(ctx)));
+                    for line in ctx.lines() { let _ = writeln!(out, "  {line}"); }
+                }
+            }
+            Err(e) => { let _ = writeln!(out, ", INVALID JSON: {e}"); }
+        }
+    } else { out.push('\n'); }
+    let _ = writeln!(out, "state: {}", if keep_state { "session changes kept" } else { "session.json and sessions.log restored" });
+    Ok(out)
+}
 
-    let ptr = unsafe { mmap(std::ptr::null_mut(), len, 1 /* PROT_READ */, 2 /* MAP_PRIVATE */, fd, 0) };
-    drop(f); // close fd — mapping persists
-
-    if ptr.is_null() || ptr as usize == usize::MAX { return None; } // MAP_FAILED
-    Some(unsafe { std::slice::from_raw_parts(ptr, len) })
+/// Shared index.bin mapping (indexview.rs): zero-copy, no socket, no full file read.
+/// Rebuilds only if nobody has indexed the latest data.log yet.
+fn mmap_index(dir: &Path) -> Option<std::sync::Arc<crate::indexview::IndexMap>> {
+    crate::indexview::fresh(dir).ok().flatten()
 }
 
 /// Build hook JSON output with direct string formatting — zero Value allocations.
@@ -107,15 +143,16 @@ fn ambient(input: &str, dir: &Path) -> Result<String, String> {
         }
     } else { vec![] };
 
-    let data = match mmap_index(dir) {
-        Some(d) => d,
+    let map = mmap_index(dir);
+    let data = match map.as_deref() {
+        Some(m) => m.bytes(),
         None => {
             session.save(dir).ok();
             return Ok(String::new());
         }
     };
     let sym_refs: Vec<&str> = syms.iter().map(|s| s.as_str()).collect();
-    let out = query_ambient(data, stem, file_path, &sym_refs, Some(&mut session));
+    let out = crate::ambient::query_ambient(dir, data, stem, file_path, &sym_refs, Some(&mut session));
 
     // Save session (writes dedup state + file tracking)
     session.save(dir).ok();
@@ -218,17 +255,19 @@ const POST_BUILD_FAIL_RESPONSE: &str = r#"{"systemMessage":"BUILD FAILED. Store
 
 /// Stop: flush pending notes from session, remind to store findings.
 /// v10: Session-aware — includes session summary in stop message.
-fn stop(dir: &Path) -> Result<String, String> {
+fn stop(dir: &Path, debounce: bool) -> Result<String, String> {
     let stamp = "/tmp/amaranthine-hook-stop.last";
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|d| d.as_secs()).unwrap_or(0);
-    if let Ok(content) = std::fs::read_to_string(stamp) {
-        if let Ok(last) = content.trim().parse::<u64>() {
-            if now.saturating_sub(last) < 120 { return Ok(String::new()); }
+    if debounce {
+        if let Ok(content) = std::fs::read_to_string(stamp) {
+            if let Ok(last) = content.trim().parse::<u64>() {
+                if now.saturating_sub(last) < 120 { return Ok(String::new()); }
+            }
         }
+        std::fs::write(stamp, now.to_string()).ok();
     }
-    std::fs::write(stamp, now.to_string()).ok();
 
     // Load session for summary
     let session = crate::session::Session::load(dir);
@@ -279,8 +318,8 @@ fn subagent_start(dir: &Path) -> Result<String, String> {
 
     // Fast path: mmap index.bin directly
     let topic_list = mmap_index(dir)
-        .and_then(|data| {
-            let topics = crate::binquery::topic_table(data).ok()?;
+        .and_then(|map| {
+            let topics = crate::binquery::topic_table(map.bytes()).ok()?;
             let mut list: Vec<String> = topics.iter()
                 .map(|(_, name, count)| format!("{name} ({count})"))
                 .collect();
@@ -327,188 +366,6 @@ pub fn extract_removed_syms(input: &crate::json::Value, stem: &str) -> Vec<Strin
     removed
 }
 
-/// Smart Ambient Context: multi-layer search with cross-invocation deduplication.
-/// v10.1: Unified function — Option<Session> for session dedup + auto-focus topics.
-///
-/// Layers (each deduplicates against all prior via FxHashSet<u32>):
-///   1. Source-path matches — entries with [source:] metadata for this file
-///   2. Symbol-based OR search — fn/struct/enum names extracted from file
-///   3. Global BM25 search — stem keyword
-///   4. Structural coupling — "structural <stem>" query
-///   5. Refactor impact — removed symbols (Edit only)
-///
-/// When session=Some: skips entries already injected this session, marks new ones,
-/// and auto-infers focus topics from entry topic names (3+ hits threshold).
-pub fn query_ambient(
-    data: &[u8], stem: &str, file_path: &str, syms: &[&str],
-    session: Option<&mut crate::session::Session>,
-) -> String {
-    let filename = std::path::Path::new(file_path)
-        .file_name().and_then(|f| f.to_str()).unwrap_or(stem);
-    let mut seen = crate::fxhash::FxHashSet::default();
-    let mut entry_ids: Vec<u32> = Vec::with_capacity(32);
-    let mut snippet_pool: Vec<std::borrow::Cow<str>> = Vec::with_capacity(32);
-
-    // Snapshot session injected set for dedup (immutable borrow)
-    let injected_snapshot: Option<crate::fxhash::FxHashSet<u32>> = session.as_ref()
-        .map(|s| s.injected.clone());
-
-    // Dedup: local seen set + session injected (if available)
-    let mut check_add = |eid: u32| -> bool {
-        if let Some(ref inj) = injected_snapshot {
-            if inj.contains(&eid) { return false; }
-        }
-        seen.insert(eid)
-    };
-
-    // Layer 1: Source-path matches
-    let source_ids = crate::binquery::source_entries_for_file(data, filename).unwrap_or_default();
-    let l1_start = snippet_pool.len();
-    for &eid in &source_ids {
-        if check_add(eid) {
-            if let Ok(snip) = crate::binquery::entry_snippet_ref(data, eid) {
-                if !snip.is_empty() {
-                    snippet_pool.push(std::borrow::Cow::Borrowed(snip));
-                    entry_ids.push(eid);
-                }
-            }
-        }
-    }
-    let l1_count = snippet_pool.len() - l1_start;
-
-    // Layer 2: Symbol-based search — skip if Layer 1 already provided enough context.
-    let l2_start = snippet_pool.len();
-    if source_ids.len() < 5 {
-        let file_symbols = cached_file_symbols(file_path);
-        if !file_symbols.is_empty() {
-            let query = build_symbol_query(&file_symbols, stem);
-            if !query.is_empty() {
-                let filter = crate::binquery::FilterPred::none();
-                let hits = crate::binquery::search_v2_or(data, &query, &filter, 8)
-                    .unwrap_or_default();
-                for h in hits {
-                    if check_add(h.entry_id) {
-                        snippet_pool.push(std::borrow::Cow::Owned(h.snippet));
-                        entry_ids.push(h.entry_id);
-                        if snippet_pool.len() - l2_start >= 5 { break; }
-                    }
-                }
-            }
-        }
-    }
-    let l2_count = snippet_pool.len() - l2_start;
-
-    // Layer 3: Global BM25 search (stem keyword)
-    let l3_start = snippet_pool.len();
-    let global = crate::binquery::search_v2(data, stem, 5).unwrap_or_default();
-    for h in global {
-        if check_add(h.entry_id) {
-            snippet_pool.push(std::borrow::Cow::Owned(h.snippet));
-            entry_ids.push(h.entry_id);
-            if snippet_pool.len() - l3_start >= 3 { break; }
-        }
-    }
-    let l3_count = snippet_pool.len() - l3_start;
-
-    // Layer 4: Structural coupling
-    let l4_start = snippet_pool.len();
-    let mut sq_buf = [0u8; 128];
-    let sq_prefix = b"structural ";
-    let sq_len = sq_prefix.len() + stem.len();
-    let structural = if sq_len <= sq_buf.len() {
-        sq_buf[..sq_prefix.len()].copy_from_slice(sq_prefix);
-        sq_buf[sq_prefix.len()..sq_len].copy_from_slice(stem.as_bytes());
-        let sq = unsafe { std::str::from_utf8_unchecked(&sq_buf[..sq_len]) };
-        crate::binquery::search_v2(data, sq, 3).unwrap_or_default()
-    } else {
-        let mut sq = String::with_capacity(sq_len);
-        sq.push_str("structural ");
-        sq.push_str(stem);
-        crate::binquery::search_v2(data, &sq, 3).unwrap_or_default()
-    };
-    for h in structural {
-        if check_add(h.entry_id) {
-            snippet_pool.push(std::borrow::Cow::Owned(h.snippet));
-            entry_ids.push(h.entry_id);
-        }
-    }
-    let l4_count = snippet_pool.len() - l4_start;
-
-    // Layer 5: Refactor impact (Edit only)
-    let l5_start = snippet_pool.len();
-    if !syms.is_empty() {
-        for sym in syms {
-            let hits = crate::binquery::search_v2(data, sym, 3).unwrap_or_default();
-            for hit in hits {
-                if check_add(hit.entry_id) {
-                    snippet_pool.push(std::borrow::Cow::Owned(hit.snippet));
-                    entry_ids.push(hit.entry_id);
-                }
-            }
-        }
-    }
-    let l5_count = snippet_pool.len() - l5_start;
-
-    if snippet_pool.is_empty() { return String::new(); }
-
-    // Session bookkeeping: mark injected + auto-infer focus topics
-    drop(check_add);
-    if let Some(session) = session {
-        for &eid in &entry_ids {
-            session.mark_injected(eid);
-        }
-        // Auto-infer focus topics: count hits per topic, add topics with 3+ hits
-        let mut topic_counts: crate::fxhash::FxHashMap<u16, u16> = crate::fxhash::map_with_capacity(8);
-        for &eid in &entry_ids {
-            if let Ok(tid) = crate::binquery::entry_topic_id(data, eid) {
-                *topic_counts.entry(tid).or_insert(0) += 1;
-            }
-        }
-        for (&tid, &count) in &topic_counts {
-            if count >= 3 {
-                if let Ok(name) = crate::binquery::topic_name(data, tid) {
-                    session.add_focus_topic(&name);
-                }
-            }
-        }
-    }
-
-    // Single output pass
-    let est_cap = snippet_pool.iter().map(|s| s.len() + 4).sum::<usize>() + 5 * 40;
-    let mut out = String::with_capacity(est_cap);
-
-    let counts = [l1_count, l2_count, l3_count, l4_count, l5_count];
-    let labels = ["source-linked", "symbol context", "related", "structural coupling", "REFACTOR IMPACT"];
-    let mut pool_idx = 0;
-    for (i, &count) in counts.iter().enumerate() {
-        if count == 0 { continue; }
-        if !out.is_empty() { out.push_str("---\n"); }
-
-        match i {
-            0 => { out.push_str("source-linked ("); out.push_str(filename); out.push_str("):\n"); }
-            2 => { out.push_str("related ("); out.push_str(stem); out.push_str("):\n"); }
-            4 => {
-                out.push_str("REFACTOR IMPACT (symbols modified: ");
-                for (j, sym) in syms.iter().enumerate() {
-                    if j > 0 { out.push_str(", "); }
-                    out.push_str(sym);
-                }
-                out.push_str("):\n");
-            }
-            _ => { out.push_str(labels[i]); out.push_str(":\n"); }
-        }
-
-        for _ in 0..count {
-            out.push_str("  ");
-            out.push_str(&snippet_pool[pool_idx]);
-            out.push('\n');
-            pool_idx += 1;
-        }
-    }
-
-    out
-}
-
 /// Extract key symbol names (fn/struct/enum/trait/class) from a source file.
 /// Reads the file directly — hook has filesystem access.
 /// Returns raw symbol names for tokenization into search terms.
@@ -560,7 +417,7 @@ fn extract_file_symbols(path: &str) -> Vec<String> {
 /// Keyed on (path, mtime_secs) — auto-invalidates when file is modified.
 const SYM_CACHE_PATH: &str = "/tmp/amr-sym-cache";
 
-fn cached_file_symbols(path: &str) -> Vec<String> {
+pub(crate) fn cached_file_symbols(path: &str) -> Vec<String> {
     let mtime = match std::fs::metadata(path) {
         Ok(m) => m.modified().ok()
             .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
@@ -608,7 +465,7 @@ fn itoa_push_u64(buf: &mut String, n: u64) {
 /// Build a search query from extracted symbols.
 /// Uses compound forms (CamelCase joined) for specificity.
 /// Excludes the stem to avoid redundancy with Layer 3.
-fn build_symbol_query(symbols: &[String], stem: &str) -> String {
+pub(crate) fn build_symbol_query(symbols: &[String], stem: &str) -> String {
     let mut terms = Vec::with_capacity(symbols.len());
     let stem_lower = stem.to_lowercase();
     for sym in symbols {
@@ -0,0 +1,161 @@
+//! What trace walks skip. Three filters, all applied by codepath::walk_files:
+//! exclude globs (a default set of build/vendor dirs plus `trace_exclude` in
+//! `<dir>/config`), `.gitignore` files (the root's ancestors up to the repo,
+//! then each directory walked), and a size cap (`trace_max_file_kb`) so a
+//! generated or vendored blob can't dominate a trace.
+//!
+//! Globs: `*` and `?` stay within a path segment, `**` spans segments,
+//! `[a-z]` classes. A pattern without `/` matches any file or directory name;
+//! one with `/` matches the path from its base (root or .gitignore dir).
+
+use std::path::{Path, PathBuf};
+
+/// Skipped unless the config says otherwise; hidden dirs are always skipped.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "target", "node_modules", "vendor", "third_party", "dist", "build",
+    "__pycache__", "*.min.js",
+];
+pub const DEFAULT_MAX_FILE_KB: u64 = 1024;
+
+pub struct Ignore {
+    excludes: Vec<Rule>,
+    pub max_bytes: u64,
+    gitignore: bool,
+}
+
+/// One exclude or .gitignore line.
+#[derive(Clone)]
+pub struct Rule {
+    base: PathBuf,
+    pat: String,
+    negate: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl Default for Ignore {
+    fn default() -> Self {
+        Self {
+            excludes: DEFAULT_EXCLUDES.iter().filter_map(|p| Rule::parse(p, Path::new(""))).collect(),
+            max_bytes: DEFAULT_MAX_FILE_KB * 1024,
+            gitignore: true,
+        }
+    }
+}
+
+impl Ignore {
+    /// Defaults plus `<dir>/config`:
+    ///   trace_exclude = generated, *.pb.rs   # added to the defaults; `!build` re-includes
+    ///   trace_max_file_kb = 512              # 0 = no cap
+    ///   trace_gitignore = off                # default on
+    pub fn load(dir: &Path) -> Self {
+        let settings = crate::settings::load(dir);
+        let mut ig = Self::default();
+        ig.excludes.extend(settings.list("trace_exclude").into_iter()
+            .filter_map(|p| Rule::parse(p, Path::new(""))));
+        if let Some(kb) = settings.get("trace_max_file_kb").and_then(|v| v.parse::<u64>().ok()) {
+            ig.max_bytes = if kb == 0 { u64::MAX } else { kb * 1024 };
+        }
+        if settings.flag("trace_gitignore") == Some(false) { ig.gitignore = false; }
+        ig
+    }
+
+    /// Rules in effect at `abs` (a canonical root): .gitignore files from the
+    /// enclosing repo's top down to the root itself (outermost first, so deeper
+    /// files win).
+    pub fn rules_at(&self, abs: &Path) -> Vec<Rule> {
+        if !self.gitignore { return Vec::new(); }
+        let mut chain = Vec::new();
+        for dir in abs.ancestors() {
+            chain.push(dir.to_path_buf());
+            if dir.join(".git").exists() { break; }
+        }
+        if !chain.last().is_some_and(|d| d.join(".git").exists()) { chain.truncate(1); }
+        chain.iter().rev().flat_map(|d| read_gitignore(d)).collect()
+    }
+
+    /// Rules from `abs_dir`'s own .gitignore, for a directory entered mid-walk.
+    pub fn rules_in(&self, abs_dir: &Path) -> Vec<Rule> {
+        if self.gitignore { read_gitignore(abs_dir) } else { Vec::new() }
+    }
+
+    /// A walked entry is excluded by config (matched on `rel`, its path from
+    /// the trace root) or by `gitignore` rules (matched on `abs`).
+    pub fn skips(&self, rel: &Path, abs: &Path, is_dir: bool, gitignore: &[Rule]) -> bool {
+        verdict(&self.excludes, rel, is_dir).unwrap_or(false)
+            || verdict(gitignore, abs, is_dir).unwrap_or(false)
+    }
+}
+
+impl Rule {
+    fn parse(line: &str, base: &Path) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') { return None; }
+        let (negate, line) = match line.strip_prefix('!') { Some(r) => (true, r), None => (false, line) };
+        let (dir_only, line) = match line.strip_suffix('/') { Some(r) => (true, r), None => (false, line) };
+        let anchored = line.contains('/');
+        let pat = line.trim_start_matches('/').to_string();
+        if pat.is_empty() { return None; }
+        Some(Self { base: base.to_path_buf(), pat, negate, dir_only, anchored })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir { return false; }
+        let Ok(rel) = path.strip_prefix(&self.base) else { return false };
+        if self.anchored {
+            glob(self.pat.as_bytes(), rel.to_string_lossy().as_bytes())
+        } else {
+            rel.file_name().is_some_and(|n| glob(self.pat.as_bytes(), n.to_string_lossy().as_bytes()))
+        }
+    }
+}
+
+/// Last matching rule decides (gitignore semantics); None if none match.
+fn verdict(rules: &[Rule], path: &Path, is_dir: bool) -> Option<bool> {
+    rules.iter().rev().find(|r| r.matches(path, is_dir)).map(|r| !r.negate)
+}
+
+fn read_gitignore(dir: &Path) -> Vec<Rule> {
+    std::fs::read_to_string(dir.join(".gitignore"))
+        .map(|text| text.lines().filter_map(|l| Rule::parse(l, dir)).collect())
+        .unwrap_or_default()
+}
+
+/// Path glob: `*`/`?` within a segment, `**` across segments, `[...]` classes.
+fn glob(p: &[u8], s: &[u8]) -> bool {
+    match p.first() {
+        None => s.is_empty(),
+        Some(b'*') if p.get(1) == Some(&b'*') => {
+            // `**/` also matches zero directories
+            let rest = &p[2..];
+            let rest_after_slash = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=s.len()).any(|i| glob(rest, &s[i..]) || glob(rest_after_slash, &s[i..]))
+        }
+        Some(b'*') => {
+            let seg = s.iter().position(|&c| c == b'/').unwrap_or(s.len());
+            (0..=seg).any(|i| glob(&p[1..], &s[i..]))
+        }
+        Some(b'?') => s.first().is_some_and(|&c| c != b'/') && glob(&p[1..], &s[1..]),
+        Some(b'[') => {
+            let Some(end) = p.iter().skip(2).position(|&c| c == b']').map(|i| i + 2) else {
+                return s.first() == Some(&b'[') && glob(&p[1..], &s[1..]);
+            };
+            let Some(&c) = s.first() else { return false };
+            let class = &p[1..end];
+            let (neg, class) = match class.first() { Some(b'!' | b'^') => (true, &class[1..]), _ => (false, class) };
+            let mut hit = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == b'-' {
+                    hit |= (class[i]..=class[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    hit |= class[i] == c;
+                    i += 1;
+                }
+            }
+            hit != neg && c != b'/' && glob(&p[end + 1..], &s[1..])
+        }
+        Some(&c) => s.first() == Some(&c) && glob(&p[1..], &s[1..]),
+    }
+}
@@ -0,0 +1,155 @@
+//! Impact analysis before a risky change: walk the call graph outward from a
+//! function (or every function in a file) to its callers, N hops, and join the
+//! affected files with the knowledge base: entries whose `[source:]` points at
+//! one of them, or that name the target. Invariants, gotchas and decisions are
+//! listed first — they are what a refactor breaks.
+//!
+//! The graph is built live when `path` is given, otherwise taken from the
+//! first stored graph (tracegraph.rs) that defines the target.
+
+use crate::error::AmrError;
+use crate::tracegraph::Graph;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Tags that mark an entry as a constraint rather than a note.
+const CONSTRAINT_TAGS: &[&str] = &["invariant", "gotcha", "decision", "pinned"];
+
+pub fn run(dir: &Path, target: &str, path: &str, glob: &str, depth: usize) -> Result<String, AmrError> {
+    if target.is_empty() { return Err(AmrError::InvalidArg("target (symbol or file) is required".into())); }
+    let (graph, from) = if path.is_empty() {
+        stored_graph(dir, target)?
+    } else {
+        let roots = crate::codepath::Roots::parse(path, crate::ignore::Ignore::load(dir))?;
+        (crate::callgraph::graph(&roots, glob)?, format!("live {roots}"))
+    };
+    let seeds = seeds(&graph, target);
+    if seeds.is_empty() {
+        return Err(AmrError::NotFound(format!("{target}: no such function or file in {from}")));
+    }
+
+    // name → (hop, file:line of the call that reached it)
+    let depth = depth.clamp(1, 5);
+    let mut reached: BTreeMap<&str, (usize, String)> = BTreeMap::new();
+    let mut seen: BTreeSet<&str> = seeds.iter().map(|(n, _)| *n).collect();
+    let mut frontier: Vec<&str> = seen.iter().copied().collect();
+    for hop in 1..=depth {
+        let mut next = Vec::new();
+        for c in &graph.calls {
+            if !frontier.contains(&c.callee.as_str()) || !seen.insert(c.caller.as_str()) { continue; }
+            reached.insert(c.caller.as_str(), (hop, format!("{}:{}", c.file, c.line)));
+            next.push(c.caller.as_str());
+        }
+        if next.is_empty() { break; }
+        frontier = next;
+    }
+
+    let mut files: BTreeMap<&str, usize> = BTreeMap::new();
+    for (_, file) in &seeds { *files.entry(file).or_insert(0) += 1; }
+    for (_, at) in reached.values() {
+        let file = at.rsplit_once(':').map_or(at.as_str(), |(f, _)| f);
+        *files.entry(file).or_insert(0) += 1;
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "# impact: {target} ({depth} hops, {from})\n");
+    let seed_names: BTreeSet<&str> = seeds.iter().map(|(n, _)| *n).collect();
+    let _ = writeln!(out, "changing {} likely affects {} functions in {} files:",
+        seed_names.iter().copied().collect::<Vec<_>>().join(", "), reached.len(), files.len());
+    let mut by_count: Vec<(&str, usize)> = files.iter().map(|(f, n)| (*f, *n)).collect();
+    by_count.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    for (file, n) in by_count.iter().take(40) {
+        let _ = writeln!(out, "  {file} ({n} fn{})", if *n == 1 { "" } else { "s" });
+    }
+    if by_count.len() > 40 { let _ = writeln!(out, "  ... {} more", by_count.len() - 40); }
+    for hop in 1..=depth {
+        let at_hop: Vec<String> = reached.iter().filter(|(_, (h, _))| *h == hop)
+            .map(|(name, (_, at))| format!("{name} ({at})")).collect();
+        if at_hop.is_empty() { continue; }
+        let _ = writeln!(out, "\nhop {hop} ({}):", at_hop.len());
+        for f in at_hop.iter().take(30) { let _ = writeln!(out, "  \u{2190} {f}"); }
+        if at_hop.len() > 30 { let _ = writeln!(out, "  ... {} more", at_hop.len() - 30); }
+    }
+
+    let knowledge = linked_entries(dir, &files.keys().copied().collect::<Vec<_>>(), &seed_names)?;
+    let (constraints, notes): (Vec<_>, Vec<_>) = knowledge.into_iter().partition(|k| k.constraint);
+    let _ = writeln!(out, "\ndocumented invariants/gotchas/decisions ({}):", constraints.len());
+    if constraints.is_empty() { let _ = writeln!(out, "  (none)"); }
+    for k in &constraints { let _ = writeln!(out, "  [{}] {} — {}", k.addr, k.preview, k.why); }
+    let _ = writeln!(out, "\nother linked knowledge ({}):", notes.len());
+    if notes.is_empty() { let _ = writeln!(out, "  (none)"); }
+    for k in notes.iter().take(20) { let _ = writeln!(out, "  [{}] {} — {}", k.addr, k.preview, k.why); }
+    if notes.len() > 20 { let _ = writeln!(out, "  ... {} more", notes.len() - 20); }
+    Ok(out)
+}
+
+/// Functions the target names: a function name, or every function in a file
+/// matching the target path (suffix match either way).
+fn seeds<'a>(g: &'a Graph, target: &str) -> Vec<(&'a str, &'a str)> {
+    let by_name: Vec<_> = g.defs.iter().filter(|d| d.name == target)
+        .map(|d| (d.name.as_str(), d.file.as_str())).collect();
+    if !by_name.is_empty() { return by_name; }
+    g.defs.iter().filter(|d| same_file(&d.file, target))
+        .map(|d| (d.name.as_str(), d.file.as_str())).collect()
+}
+
+/// `a` and `b` name the same file, one possibly with more leading dirs.
+fn same_file(a: &str, b: &str) -> bool {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    !short.is_empty() && long.strip_suffix(short)
+        .is_some_and(|rest| rest.is_empty() || rest.ends_with('/'))
+}
+
+fn stored_graph(dir: &Path, target: &str) -> Result<(Graph, String), AmrError> {
+    let topics = crate::tracegraph::topics(dir);
+    if topics.is_empty() {
+        return Err(AmrError::InvalidArg("no stored graphs — pass path, or run trace with store_topic first".into()));
+    }
+    for t in &topics {
+        let g = crate::tracegraph::load(dir, t)?;
+        if !seeds(&g, target).is_empty() {
+            let from = format!("graph {t}, built {}", crate::time::minutes_to_date_str(g.built_min));
+            return Ok((g, from));
+        }
+    }
+    Err(AmrError::NotFound(format!("{target}: not in any stored graph ({}) — pass path to trace live", topics.join(", "))))
+}
+
+/// `body` contains `sym` as a whole identifier.
+fn names(body: &str, sym: &str) -> bool {
+    let ident = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    body.match_indices(sym).any(|(i, _)| {
+        !ident(body[..i].chars().next_back()) && !ident(body[i + sym.len()..].chars().next())
+    })
+}
+
+struct Linked { addr: String, preview: String, why: String, constraint: bool }
+
+/// Entries sourced from one of `files` (by path suffix, since stored sources
+/// may be absolute or project-relative), or whose text names one of `symbols`.
+fn linked_entries(dir: &Path, files: &[&str], symbols: &BTreeSet<&str>) -> Result<Vec<Linked>, AmrError> {
+    crate::cache::with_corpus(dir, |cached| {
+        let mut idx: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut out = Vec::new();
+        for e in cached {
+            let i = idx.entry(e.topic.as_str()).or_insert(0);
+            let n = *i;
+            *i += 1;
+            let src = e.source().map(|s| s.rsplit_once(':')
+                .filter(|(_, l)| l.parse::<usize>().is_ok()).map_or(s, |(p, _)| p));
+            let why = if let Some(f) = src.and_then(|s| files.iter().find(|f| same_file(s, f))) {
+                format!("source {f}")
+            } else if let Some(sym) = symbols.iter().find(|s| names(&e.body, s)) {
+                format!("mentions {sym}")
+            } else { continue };
+            out.push(Linked {
+                addr: format!("{}:{n}", e.topic),
+                preview: crate::text::truncate(e.preview(), 80).to_string(),
+                why,
+                constraint: CONSTRAINT_TAGS.iter().any(|t| e.has_tag(t)),
+            });
+        }
+        out
+    })
+}
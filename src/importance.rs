@@ -0,0 +1,113 @@
+//! Entry importance: 0–255 per entry, stored in EntryMeta at index build.
+//! Points: +2 per inbound narrative link, +log2(1 + reads), tag weights
+//! (config `tag_weights = decision:2, gotcha:1`), +8 for the `pinned` tag.
+//! Score = points × 16, saturating. Used for search tie-breaking and prune tiers.
+//!
+//! Reads are explicit entry reads over MCP (`read`, `entries` by index),
+//! appended to reads.log as log offsets, one call per line. Index builds fold
+//! the file into `offset:count` tokens once it has grown to twice its folded
+//! size, and compaction rewrites it folded through the same remap as index.bin.
+
+use crate::cache::CachedEntry;
+use crate::fxhash::FxHashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Prune tiers: at or above HIGH a topic is never reported stale;
+/// below LOW it is reported stale at half the age threshold.
+pub const HIGH: u8 = 64;
+pub const LOW: u8 = 8;
+
+const DEFAULT_TAG_WEIGHTS: &[(&str, f64)] = &[
+    ("decision", 2.0), ("invariant", 2.0), ("architecture", 1.5), ("gotcha", 1.0),
+];
+
+pub fn reads_path(dir: &Path) -> PathBuf { dir.join("reads.log") }
+
+/// Record one read of each entry at `offsets`. Best-effort: a lost line only
+/// understates importance.
+pub fn record_reads(dir: &Path, offsets: &[u32]) {
+    if offsets.is_empty() { return; }
+    let mut line = offsets.iter().map(|o| o.to_string()).collect::<Vec<_>>().join(" ");
+    line.push('\n');
+    let _ = std::fs::OpenOptions::new().create(true).append(true).open(reads_path(dir))
+        .and_then(|mut f| f.write_all(line.as_bytes()));
+}
+
+/// Carry reads.log across a data.log compaction: each offset translated through
+/// `remap`, those of dropped entries removed. Callers hold the directory lock.
+pub fn remap_reads(dir: &Path, remap: &crate::datalog::Remap) -> std::io::Result<()> {
+    if !reads_path(dir).exists() { return Ok(()); }
+    let (counts, _) = read_counts(dir);
+    write_folded(dir, counts.into_iter().filter_map(|(off, n)| Some((remap.get(off)?, n))).collect())
+}
+
+/// Read count per data.log offset, and how many tokens the file held.
+/// A token is an offset (one read) or `offset:count`.
+pub fn read_counts(dir: &Path) -> (FxHashMap<u32, u32>, usize) {
+    let mut counts = FxHashMap::default();
+    let mut tokens = 0;
+    if let Ok(text) = std::fs::read_to_string(reads_path(dir)) {
+        for w in text.split_ascii_whitespace() {
+            let (off, n) = match w.split_once(':') {
+                Some((o, n)) => (o.parse::<u32>(), n.parse::<u32>().unwrap_or(0)),
+                None => (w.parse::<u32>(), 1),
+            };
+            let Ok(off) = off else { continue };
+            *counts.entry(off).or_insert(0) += n;
+            tokens += 1;
+        }
+    }
+    (counts, tokens)
+}
+
+/// Replace reads.log with one `offset:count` token per line, ascending.
+fn write_folded(dir: &Path, mut counts: Vec<(u32, u32)>) -> std::io::Result<()> {
+    counts.sort_unstable();
+    let mut out = String::with_capacity(counts.len() * 12);
+    for (off, n) in counts { out.push_str(&format!("{off}:{n}\n")); }
+    let tmp = dir.join(format!("reads.log.{}.tmp", std::process::id()));
+    std::fs::write(&tmp, out).and_then(|_| std::fs::rename(&tmp, reads_path(dir)))
+}
+
+/// Importance for every entry, aligned with `cached`.
+pub fn compute(dir: &Path, cached: &[CachedEntry]) -> Vec<u8> {
+    let settings = crate::settings::load(dir);
+    let configured: Vec<(String, f64)> = settings.list("tag_weights").into_iter()
+        .filter_map(|kv| kv.split_once(':'))
+        .filter_map(|(t, w)| Some((t.trim().to_lowercase(), w.trim().parse::<f64>().ok()?)))
+        .collect();
+    let tag_weight = |tag: &str| -> f64 {
+        if !configured.is_empty() {
+            return configured.iter().find(|(t, _)| t == tag).map_or(0.0, |(_, w)| *w);
+        }
+        DEFAULT_TAG_WEIGHTS.iter().find(|(t, _)| *t == tag).map_or(0.0, |(_, w)| *w)
+    };
+
+    // topic → corpus indices in topic order, to resolve `topic:index` links
+    let mut by_topic: FxHashMap<&str, Vec<usize>> = FxHashMap::default();
+    for (i, e) in cached.iter().enumerate() { by_topic.entry(e.topic.as_str()).or_default().push(i); }
+    let mut link_in = vec![0u32; cached.len()];
+    for e in cached.iter().filter(|e| e.has_links()) {
+        for (topic, idx) in e.links() {
+            if let Some(&target) = by_topic.get(topic.as_str()).and_then(|v| v.get(*idx)) {
+                link_in[target] += 1;
+            }
+        }
+    }
+
+    let (reads, tokens) = read_counts(dir);
+    // Fold once unfolded reads outnumber the entries they name. Best-effort,
+    // like record_reads: a read appended while this runs may be lost
+    if tokens > 2 * reads.len() && tokens > 256 {
+        let _ = write_folded(dir, reads.iter().map(|(&o, &n)| (o, n)).collect());
+    }
+    cached.iter().enumerate().map(|(i, e)| {
+        let mut points = 2.0 * link_in[i] as f64;
+        points += (1.0 + reads.get(&e.offset).copied().unwrap_or(0) as f64).log2();
+        for tag in e.tags() {
+            points += if tag == "pinned" { 8.0 } else { tag_weight(tag) };
+        }
+        (points * 16.0).round().clamp(0.0, 255.0) as u8
+    }).collect()
+}
@@ -0,0 +1,125 @@
+//! Unfiled knowledge: `store` without a topic lands in `inbox`, and
+//! `file_inbox` suggests where each entry belongs — the topics whose
+//! vocabulary it shares most (cosine over tf-idf, a topic's term counts summed
+//! across its entries, idf over topics) — or moves it there with `apply`.
+//! Filing by similarity keeps one-off stores from piling up in a "misc" topic.
+//! An entry no topic scores `MIN_SCORE` against stays in the inbox; `index`
+//! plus `topic` files one entry by hand.
+
+use crate::error::AmrError;
+use crate::fxhash::FxHashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+pub const TOPIC: &str = "inbox";
+/// Cosine similarity a topic needs to be suggested (and filed to).
+pub const MIN_SCORE: f64 = 0.1;
+const SUGGESTIONS: usize = 3;
+
+/// One inbox entry and its best-matching topics, best first.
+struct Plan { idx: usize, offset: u32, preview: String, topics: Vec<(String, f64)> }
+
+/// What to file: everything, or one entry (optionally to a chosen topic).
+pub struct Filing<'a> {
+    pub apply: bool,
+    pub index: Option<usize>,
+    /// With `index`: file there instead of the best suggestion.
+    pub topic: Option<&'a str>,
+}
+
+/// Suggest topics for inbox entries, or move them with `f.apply`.
+pub fn file(dir: &Path, f: &Filing) -> Result<String, AmrError> {
+    if f.topic.is_some() && f.index.is_none() {
+        return Err(AmrError::InvalidArg("topic needs index: name the inbox entry to file there".into()));
+    }
+    let mut plans = plan(dir)?;
+    if plans.is_empty() { return Ok(format!("{TOPIC} is empty\n")); }
+    if let Some(i) = f.index {
+        let total = plans.len();
+        plans.retain(|p| p.idx == i);
+        if plans.is_empty() {
+            return Err(AmrError::NotFound(format!("index {i} out of range ({TOPIC} has {total} entries)")));
+        }
+    }
+    if !f.apply && f.topic.is_none() { return Ok(suggestions(&plans)); }
+
+    let moves: Vec<(&Plan, String)> = plans.iter().filter_map(|p| {
+        let to = match f.topic {
+            Some(t) => crate::config::sanitize_topic(t),
+            None => p.topics.first().filter(|(_, s)| *s >= MIN_SCORE)?.0.clone(),
+        };
+        Some((p, to))
+    }).collect();
+    if moves.iter().any(|(_, to)| to == TOPIC) { return Err(AmrError::InvalidArg(format!("already in {TOPIC}"))); }
+    let _lock = crate::lock::FileLock::acquire(dir)?;
+    let log_path = crate::config::log_path(dir);
+    let live = crate::delete::topic_entries(&log_path, TOPIC)?;
+    let mut out = String::new();
+    let mut filed = 0;
+    for (p, to) in &moves {
+        // Skip entries changed or filed since the plan was read
+        let Some(e) = live.iter().find(|e| e.offset == p.offset) else { continue };
+        crate::datalog::append_entry(&log_path, to, &e.body, e.timestamp_min)?;
+        crate::datalog::append_delete(&log_path, e.offset)?;
+        filed += 1;
+        let _ = writeln!(out, "  {TOPIC}:{} → {to}  {}", p.idx, p.preview);
+    }
+    let mut head = format!("filed {filed} of {} {TOPIC} entries\n", plans.len());
+    let left = plans.len() - filed;
+    if left > 0 { let _ = writeln!(out, "left in {TOPIC}: {left} (no topic scores {MIN_SCORE:.2}+; file with index + topic)"); }
+    head.push_str(&out);
+    Ok(head)
+}
+
+fn suggestions(plans: &[Plan]) -> String {
+    let mut out = format!("{} {TOPIC} entries\n", plans.len());
+    for p in plans {
+        let _ = writeln!(out, "  {TOPIC}:{}  {}", p.idx, p.preview);
+        let good: Vec<String> = p.topics.iter().filter(|(_, s)| *s >= MIN_SCORE)
+            .map(|(t, s)| format!("{t} {s:.2}")).collect();
+        match (good.is_empty(), p.topics.first()) {
+            (false, _) => { let _ = writeln!(out, "    → {}", good.join(", ")); }
+            (true, Some((t, s))) => { let _ = writeln!(out, "    → no close topic (best: {t} {s:.2})"); }
+            (true, None) => out.push_str("    → no other topics yet\n"),
+        }
+    }
+    out.push_str("apply=true files each entry to its first suggestion\n");
+    out
+}
+
+/// Inbox entries with their closest topics, from the cached corpus.
+fn plan(dir: &Path) -> Result<Vec<Plan>, AmrError> {
+    crate::cache::with_corpus(dir, |cached| {
+        // Term counts per topic, and how many topics use each term
+        let mut profiles: FxHashMap<&str, FxHashMap<&str, f64>> = FxHashMap::default();
+        for e in cached.iter().filter(|e| e.topic.as_str() != TOPIC) {
+            let p = profiles.entry(e.topic.as_str()).or_default();
+            for (t, &n) in &e.tf_map { *p.entry(t.as_str()).or_insert(0.0) += n as f64; }
+        }
+        let mut spread: FxHashMap<&str, usize> = FxHashMap::default();
+        for p in profiles.values() {
+            for t in p.keys() { *spread.entry(t).or_insert(0) += 1; }
+        }
+        let n_topics = profiles.len() as f64;
+        let idf = |t: &str| (1.0 + n_topics / spread.get(t).copied().unwrap_or(1) as f64).ln();
+        let norms: FxHashMap<&str, f64> = profiles.iter()
+            .map(|(name, p)| (*name, p.iter().map(|(t, n)| (n * idf(t)).powi(2)).sum::<f64>().sqrt()))
+            .collect();
+
+        let mut plans = Vec::new();
+        for (idx, e) in cached.iter().filter(|e| e.topic.as_str() == TOPIC).enumerate() {
+            let weights: Vec<(&str, f64)> = e.tf_map.iter().map(|(t, &n)| (t.as_str(), n as f64 * idf(t))).collect();
+            let norm = weights.iter().map(|(_, w)| w * w).sum::<f64>().sqrt();
+            let mut topics: Vec<(String, f64)> = profiles.iter().map(|(name, p)| {
+                let dot: f64 = weights.iter().map(|(t, w)| w * p.get(t).copied().unwrap_or(0.0) * idf(t)).sum();
+                let denom = norm * norms[name];
+                (name.to_string(), if denom > 0.0 { dot / denom } else { 0.0 })
+            }).collect();
+            topics.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            topics.truncate(SUGGESTIONS);
+            let preview = crate::text::truncate(e.preview().trim(), 80).to_string();
+            plans.push(Plan { idx, offset: e.offset, preview, topics });
+        }
+        plans
+    })
+}
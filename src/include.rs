@@ -0,0 +1,93 @@
+//! Transclusion on read: a body line holding `{{include topic:N}}` (or
+//! `topic:uid:HEX`) renders with the referenced entry's content lines inlined
+//! as a `> ` quote headed by its address, so one entry can compose others
+//! without copying them. Applied by read, entries (single entry) and brief;
+//! search and the index see the stored text. Nested includes expand up to
+//! `MAX_DEPTH`; a cycle or a missing entry leaves a note in place of the block.
+
+use crate::cache::CachedEntry;
+use std::borrow::Cow;
+use std::path::Path;
+
+pub const MAX_DEPTH: usize = 4;
+const OPEN: &str = "{{include ";
+const CLOSE: &str = "}}";
+
+/// `body` with includes expanded against `dir`'s corpus.
+pub fn expand(dir: &Path, timestamp_min: i32, body: &str) -> String {
+    if !body.contains(OPEN) { return body.to_string(); }
+    crate::cache::with_corpus(dir, |cached| expand_in(cached, timestamp_min, body).into_owned())
+        .unwrap_or_else(|_| body.to_string())
+}
+
+/// `body` with includes expanded against an already-loaded corpus. Borrowed
+/// when there is nothing to expand.
+pub fn expand_in<'a>(cached: &[CachedEntry], timestamp_min: i32, body: &'a str) -> Cow<'a, str> {
+    if !body.contains(OPEN) { return Cow::Borrowed(body); }
+    let mut stack = vec![crate::address::entry_uid(timestamp_min, body)];
+    let mut out = String::with_capacity(body.len() * 2);
+    render(cached, body, &mut stack, &mut out);
+    Cow::Owned(out)
+}
+
+fn render(cached: &[CachedEntry], body: &str, stack: &mut Vec<u64>, out: &mut String) {
+    for line in body.lines() {
+        let mut rest = line;
+        while let Some((before, addr, after)) = directive(rest) {
+            if !before.trim().is_empty() { push_line(out, before.trim_end()); }
+            include(cached, addr, stack, out);
+            rest = after.trim_start();
+        }
+        if rest.len() < line.len() && rest.trim().is_empty() { continue; }
+        push_line(out, rest);
+    }
+    if out.ends_with('\n') && !body.ends_with('\n') { out.pop(); }
+}
+
+fn push_line(out: &mut String, line: &str) {
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// (text before, address, text after) of the first directive in `line`.
+fn directive(line: &str) -> Option<(&str, &str, &str)> {
+    let start = line.find(OPEN)?;
+    let end = start + line[start..].find(CLOSE)?;
+    Some((&line[..start], line[start + OPEN.len()..end].trim(), &line[end + CLOSE.len()..]))
+}
+
+fn include(cached: &[CachedEntry], addr: &str, stack: &mut Vec<u64>, out: &mut String) {
+    let Some((uid, body)) = find(cached, addr) else {
+        return push_line(out, &format!("> [{addr}: no such entry]"));
+    };
+    if stack.contains(&uid) { return push_line(out, &format!("> [{addr}: include cycle, not expanded]")); }
+    if stack.len() > MAX_DEPTH { return push_line(out, &format!("> [{addr}: includes nested past {MAX_DEPTH} levels]")); }
+    let content: String = body.lines().filter(|l| !crate::text::is_metadata_line(l))
+        .collect::<Vec<_>>().join("\n");
+    stack.push(uid);
+    let mut inner = String::new();
+    render(cached, content.trim(), stack, &mut inner);
+    stack.pop();
+    push_line(out, &format!("> [{addr}]"));
+    for l in inner.lines() {
+        if l.is_empty() { push_line(out, ">"); } else { push_line(out, &format!("> {l}")); }
+    }
+}
+
+/// (uid, body) of the entry `addr` names, indexed like every other address.
+fn find<'a>(cached: &'a [CachedEntry], addr: &str) -> Option<(u64, &'a str)> {
+    let a = crate::address::parse(addr).ok()?;
+    let topic = crate::config::sanitize_topic(a.topic);
+    let mut group = cached.iter().filter(|e| e.topic.as_str() == topic);
+    let e = match &a.sel {
+        crate::address::Selector::Index(i) => group.nth(*i)?,
+        crate::address::Selector::Uid(hex) => {
+            let mut hits = group.filter(|e| format!("{:016x}",
+                crate::address::entry_uid(e.timestamp_min, &e.body)).starts_with(hex.as_str()));
+            let e = hits.next()?;
+            if hits.next().is_some() { return None; }
+            e
+        }
+    };
+    Some((crate::address::entry_uid(e.timestamp_min, &e.body), &e.body))
+}
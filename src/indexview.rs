@@ -0,0 +1,187 @@
+//! Shared index.bin for every local consumer: server, CLI, hooks, KnowledgeBase.
+//! `index.gen` holds a generation counter and the data.log (len, mtime) the
+//! current index.bin was built from. All writers go through `publish` (tmp +
+//! rename, then bump the generation), so readers keep one read-only mmap per
+//! process and remap only when the generation moves.
+//! `fresh` rebuilds when data.log has moved past the recorded stamp. The build
+//! runs under `.index.lock` and re-checks first, so concurrent consumers wait
+//! for one rebuild instead of each doing their own.
+
+use crate::error::AmrError;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+extern "C" {
+    fn mmap(addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, off: i64) -> *mut u8;
+    fn munmap(addr: *mut u8, len: usize) -> i32;
+}
+
+const LOCK_NAME: &str = ".index.lock";
+
+/// A live mapping of index.bin at one generation. Unmapped when the last Arc drops.
+/// index.bin is only ever replaced by rename, so a held mapping stays valid.
+pub struct IndexMap {
+    ptr: *mut u8,
+    len: usize,
+    generation: u64,
+}
+
+// Safety: the mapping is PROT_READ and never mutated through `ptr`.
+unsafe impl Send for IndexMap {}
+unsafe impl Sync for IndexMap {}
+
+impl IndexMap {
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    pub fn generation(&self) -> u64 { self.generation }
+}
+
+impl std::ops::Deref for IndexMap {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] { self.bytes() }
+}
+
+impl Drop for IndexMap {
+    fn drop(&mut self) {
+        unsafe { munmap(self.ptr, self.len); }
+    }
+}
+
+/// data.log identity: (len, mtime in ns). None if data.log is missing.
+pub type LogStamp = (u64, u128);
+
+pub fn log_stamp(dir: &Path) -> Option<LogStamp> {
+    let meta = std::fs::metadata(crate::config::log_path(dir)).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+    Some((meta.len(), mtime))
+}
+
+fn gen_path(dir: &Path) -> PathBuf { dir.join("index.gen") }
+
+/// (generation, stamp index.bin was built from). None before the first publish.
+fn read_gen(dir: &Path) -> Option<(u64, LogStamp)> {
+    let text = std::fs::read_to_string(gen_path(dir)).ok()?;
+    let mut it = text.split_ascii_whitespace();
+    let generation = it.next()?.parse().ok()?;
+    let len = it.next()?.parse().ok()?;
+    let mtime = it.next()?.parse().ok()?;
+    Some((generation, (len, mtime)))
+}
+
+/// Generation of the published index.bin; 0 if none has been published.
+pub fn generation(dir: &Path) -> u64 {
+    read_gen(dir).map_or(0, |(g, _)| g)
+}
+
+/// True if index.bin was built from data.log as it is now.
+pub fn is_current(dir: &Path) -> bool {
+    matches!((read_gen(dir), log_stamp(dir)), (Some((_, built)), Some(now)) if built == now)
+}
+
+/// Atomically replace index.bin with `bytes`, built from data.log at `built_from`,
+/// and bump the generation. Returns the new generation.
+pub fn publish(dir: &Path, bytes: &[u8], built_from: Option<LogStamp>) -> Result<u64, AmrError> {
+    let _lock = crate::lock::FileLock::acquire_named(dir, LOCK_NAME)?;
+    publish_locked(dir, bytes, built_from)
+}
+
+fn publish_locked(dir: &Path, bytes: &[u8], built_from: Option<LogStamp>) -> Result<u64, AmrError> {
+    let write = |name: &str, data: &[u8]| -> Result<(), AmrError> {
+        let tmp = dir.join(format!("{name}.tmp"));
+        std::fs::write(&tmp, data)
+            .and_then(|_| std::fs::rename(&tmp, dir.join(name)))
+            .map_err(|e| AmrError::io(name, e))
+    };
+    write("index.bin", bytes)?;
+    let generation = generation(dir) + 1;
+    let (len, mtime) = built_from.unwrap_or((0, 0));
+    write("index.gen", format!("{generation} {len} {mtime}\n").as_bytes())?;
+    Ok(generation)
+}
+
+/// Republish the current index.bin with every entry's log offset moved through
+/// a compaction remap, stamped with the compacted data.log. Fails (leaving the
+/// next `fresh` to rebuild) if an indexed entry has no new offset.
+pub fn remap(dir: &Path, remap: &crate::datalog::Remap) -> Result<u64, AmrError> {
+    let _lock = crate::lock::FileLock::acquire_named(dir, LOCK_NAME)?;
+    let map = view(dir)?.ok_or_else(|| AmrError::NotFound("no index.bin".into()))?;
+    let mut bytes = map.bytes().to_vec();
+    let hdr = crate::binquery::read_header(&bytes)?;
+    let size = std::mem::size_of::<crate::format::EntryMeta>();
+    let field = std::mem::offset_of!(crate::format::EntryMeta, log_offset);
+    let (meta_off, n) = ({ hdr.meta_off } as usize, { hdr.num_entries } as usize);
+    for i in 0..n {
+        let at = meta_off + i * size + field;
+        let slot = bytes.get_mut(at..at + 4).ok_or_else(|| AmrError::Corrupt("entry meta out of bounds".into()))?;
+        let old = u32::from_le_bytes([slot[0], slot[1], slot[2], slot[3]]);
+        let new = remap.get(old).ok_or_else(|| AmrError::NotFound(format!("offset {old} not in remap")))?;
+        slot.copy_from_slice(&new.to_le_bytes());
+    }
+    publish_locked(dir, &bytes, log_stamp(dir))
+}
+
+static VIEWS: Mutex<Vec<(PathBuf, Arc<IndexMap>)>> = Mutex::new(Vec::new());
+
+/// Mapping of the published index.bin, remapped only when the generation
+/// changed. Never builds; Ok(None) if there is no index.bin.
+pub fn view(dir: &Path) -> Result<Option<Arc<IndexMap>>, AmrError> {
+    view_at(dir, generation(dir))
+}
+
+fn view_at(dir: &Path, generation: u64) -> Result<Option<Arc<IndexMap>>, AmrError> {
+    let mut views = VIEWS.lock().map_err(|e| AmrError::Locked(e.to_string()))?;
+    if let Some((_, m)) = views.iter().find(|(d, m)| d == dir && m.generation == generation) {
+        return Ok(Some(Arc::clone(m)));
+    }
+    let f = match std::fs::File::open(dir.join("index.bin")) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(AmrError::io("open index.bin", e)),
+    };
+    let len = f.metadata().map_err(|e| AmrError::io("stat index.bin", e))?.len() as usize;
+    if len < std::mem::size_of::<crate::format::Header>() {
+        return Err(AmrError::Corrupt("index.bin too small".into()));
+    }
+    use std::os::unix::io::AsRawFd;
+    let ptr = unsafe { mmap(std::ptr::null_mut(), len, 1 /* PROT_READ */, 2 /* MAP_PRIVATE */, f.as_raw_fd(), 0) };
+    drop(f); // mapping outlives the fd
+    if ptr.is_null() || ptr as usize == usize::MAX { return Err(AmrError::Io("mmap index.bin failed".into())); }
+    let map = Arc::new(IndexMap { ptr, len, generation });
+    crate::binquery::read_header(map.bytes())?;
+    views.retain(|(d, _)| d != dir);
+    views.push((dir.to_path_buf(), Arc::clone(&map)));
+    Ok(Some(map))
+}
+
+/// True if index.bin was written by another format version: rebuilt from
+/// data.log on the next `fresh`, whatever its stamp says.
+fn old_format(dir: &Path) -> bool {
+    let mut head = [0u8; 8];
+    let Ok(mut f) = std::fs::File::open(dir.join("index.bin")) else { return false };
+    if std::io::Read::read_exact(&mut f, &mut head).is_err() { return false; }
+    head[..4] == crate::format::MAGIC && u32::from_le_bytes([head[4], head[5], head[6], head[7]]) != crate::format::VERSION
+}
+
+/// Mapping of an index.bin that covers data.log as it is now, rebuilding and
+/// publishing first if needed. Ok(None) if there is neither data.log nor index.bin.
+pub fn fresh(dir: &Path) -> Result<Option<Arc<IndexMap>>, AmrError> {
+    let Some(now) = log_stamp(dir) else { return view(dir) };
+    if let Some((generation, built)) = read_gen(dir) {
+        if built == now && !old_format(dir) { return view_at(dir, generation); }
+    }
+    let _lock = crate::lock::FileLock::acquire_named(dir, LOCK_NAME)?;
+    // Another process may have rebuilt while we waited for the lock
+    let now = log_stamp(dir);
+    let generation = match read_gen(dir) {
+        Some((g, built)) if Some(built) == now && !old_format(dir) => g,
+        _ => {
+            let (_, bytes) = crate::inverted::rebuild(dir)?;
+            publish_locked(dir, &bytes, now)?
+        }
+    };
+    view_at(dir, generation)
+}
@@ -100,7 +100,7 @@ fn update_claude_json(path: &Path, exe: &str) -> Result<(), String> {
     Ok(())
 }
 
-fn update_hooks(path: &Path, exe: &str) -> Result<(), String> {
+pub(crate) fn update_hooks(path: &Path, exe: &str) -> Result<(), String> {
     use crate::json::Value;
 
     let content = if path.exists() {
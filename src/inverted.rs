@@ -1,6 +1,7 @@
 //! Binary inverted index v2: build from data.log, write to index.bin.
 //! Layout: [Header][TermTable][Postings][EntryMeta][Snippets]
-//!         [TopicTable][TopicNames][SourcePool][XrefTable]
+//!         [TopicTable][TopicNames][SourcePool][XrefTable][TagNames][Positions]
+//!         [Vocab][VocabNames]
 
 use std::path::Path;
 use crate::format::*;
@@ -17,6 +18,13 @@ struct EntryInfo {
     log_offset: u32,
     tags: Vec<String>,
     explicit_confidence: Option<f64>,
+    importance: u8,
+    provenance: u8,
+    lang: u8,
+    /// Token positions per term hash, for phrase/NEAR queries (phrase.rs).
+    positions: FxHashMap<u64, Vec<u16>>,
+    /// Recorded `[commit: …]`, consulted when `git_freshness` is on.
+    commit: Option<String>,
 }
 
 pub struct IndexBuilder {
@@ -26,6 +34,7 @@ pub struct IndexBuilder {
     topic_index: FxHashMap<String, u16>,
     total_words: usize,
     tag_freq: FxHashMap<String, usize>,
+    git_freshness: bool,
 }
 
 impl IndexBuilder {
@@ -33,6 +42,7 @@ impl IndexBuilder {
         Self {
             terms: FxHashMap::default(), entries: Vec::new(), topics: Vec::new(),
             topic_index: FxHashMap::default(), total_words: 0, tag_freq: FxHashMap::default(),
+            git_freshness: false,
         }
     }
 
@@ -70,8 +80,10 @@ impl IndexBuilder {
         self.entries.push(EntryInfo {
             topic_id, word_count: wc.min(u16::MAX as usize) as u16,
             snippet, date_minutes, source, log_offset, tags,
-            explicit_confidence: None,
+            explicit_confidence: None, importance: 0, provenance: 0, lang: 0,
+            positions: FxHashMap::default(), commit: None,
         });
+        self.set_positions(entry_id, &tokens);
         entry_id
     }
 
@@ -97,8 +109,10 @@ impl IndexBuilder {
         for tag in &tags { *self.tag_freq.entry(tag.clone()).or_insert(0) += 1; }
         self.entries.push(EntryInfo {
             topic_id, word_count: wc.min(u16::MAX as usize) as u16,
-            snippet, date_minutes, source, log_offset, tags, explicit_confidence,
+            snippet, date_minutes, source, log_offset, tags, explicit_confidence, importance: 0, provenance: 0, lang: 0,
+            positions: FxHashMap::default(), commit: None,
         });
+        self.set_positions(entry_id, tokens);
         entry_id
     }
 
@@ -126,11 +140,48 @@ impl IndexBuilder {
             topic_id, word_count: word_count.min(u16::MAX as usize) as u16,
             snippet: snippet.to_string(), date_minutes,
             source: source.to_string(), log_offset,
-            tags: tags.to_vec(), explicit_confidence,
+            tags: tags.to_vec(), explicit_confidence, importance: 0, provenance: 0, lang: 0,
+            positions: FxHashMap::default(), commit: None,
         });
         entry_id
     }
 
+    /// Set an entry's importance (importance::compute); defaults to 0.
+    pub fn set_importance(&mut self, entry_id: u32, importance: u8) {
+        if let Some(e) = self.entries.get_mut(entry_id as usize) { e.importance = importance; }
+    }
+
+    /// Set an entry's Provenance::code; defaults to 0 (not recorded).
+    pub fn set_provenance(&mut self, entry_id: u32, provenance: u8) {
+        if let Some(e) = self.entries.get_mut(entry_id as usize) { e.provenance = provenance; }
+    }
+
+    /// Set an entry's Lang::code; defaults to 0 (unclassified).
+    pub fn set_lang(&mut self, entry_id: u32, lang: u8) {
+        if let Some(e) = self.entries.get_mut(entry_id as usize) { e.lang = lang; }
+    }
+
+    /// Record where each term sits in an entry's `text::tokenize` output, for
+    /// phrase/NEAR queries. Entries added from a tf_map have none until set.
+    /// Positions past u16::MAX are not recorded.
+    pub fn set_positions(&mut self, entry_id: u32, tokens: &[String]) {
+        let Some(e) = self.entries.get_mut(entry_id as usize) else { return };
+        e.positions.clear();
+        for (i, t) in tokens.iter().enumerate().take(u16::MAX as usize + 1) {
+            if t.len() < 2 { continue; }
+            e.positions.entry(hash_term(t)).or_default().push(i as u16);
+        }
+    }
+
+    /// Record the commit an entry's source was stored at (git.rs).
+    pub fn set_commit(&mut self, entry_id: u32, commit: &str) {
+        if let Some(e) = self.entries.get_mut(entry_id as usize) { e.commit = Some(commit.to_string()); }
+    }
+
+    /// Judge sourced entries by git history instead of mtime where they have
+    /// a recorded commit (`freshness = git`).
+    pub fn set_git_freshness(&mut self, on: bool) { self.git_freshness = on; }
+
     /// F11: Xref detection via term index — O(topics × avg_posting) instead of O(entries × topics).
     fn compute_xrefs(&self) -> Vec<XrefEdge> {
         let mut edges: FxHashMap<(u16, u16), u16> = FxHashMap::default();
@@ -170,6 +221,26 @@ impl IndexBuilder {
         }).collect()
     }
 
+    /// Serialize the index. Output depends only on the entries added: terms
+    /// are laid out in sorted order and topics by name, never in hash-map or
+    /// insertion order, so rebuilding the same data.log reproduces index.bin
+    /// byte for byte (content-addressed syncing and caching rely on it).
+    ///
+    /// ```
+    /// use amaranthine::inverted::IndexBuilder;
+    ///
+    /// let build = |first: &str| {
+    ///     let mut b = IndexBuilder::new();
+    ///     b.add_topic(first);
+    ///     let (rust, go) = (b.add_topic("rust"), b.add_topic("go"));
+    ///     b.add_entry(rust, "ownership and borrowing", "ownership".into(), 0, String::new(), 8, vec!["gotcha".into()]);
+    ///     b.add_entry(go, "goroutines and channels in rust ffi", "goroutines".into(), 0, String::new(), 64,
+    ///         vec!["decision".into()]);
+    ///     b.build()
+    /// };
+    /// assert_eq!(build("rust"), build("rust"));
+    /// assert_eq!(build("rust"), build("go"));
+    /// ```
     pub fn build(&self) -> Vec<u8> {
         let n = self.entries.len() as f64;
         let avgdl = if n == 0.0 { 100.0 } else { self.total_words as f64 / n };
@@ -182,17 +253,29 @@ impl IndexBuilder {
 
         // Posting lists
         let mut post_buf: Vec<Posting> = Vec::new();
+        let mut positions: Vec<u16> = Vec::new();
         let mut term_entries: Vec<(u64, u32, u32)> = Vec::new();
-        for (term, postings) in &self.terms {
+        let mut vocab: Vec<VocabEntry> = Vec::with_capacity(num_terms);
+        let mut vocab_names = Vec::<u8>::new();
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        for (term, postings) in terms {
             let h = hash_term(term);
             let off = post_buf.len() as u32;
             let df = postings.len() as f64;
             let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
             let idf_x1000 = (idf * 1000.0) as u32;
             for &(eid, tf) in postings {
-                post_buf.push(Posting { entry_id: eid, tf, idf_x1000, _pad: 0 });
+                let pos = self.entries[eid as usize].positions.get(&h).map_or(&[][..], |p| p.as_slice());
+                let pos_len = pos.len().min(u16::MAX as usize);
+                post_buf.push(Posting { entry_id: eid, tf, idf_x1000, pos_len: pos_len as u16, pos_off: positions.len() as u32 });
+                positions.extend_from_slice(&pos[..pos_len]);
             }
             term_entries.push((h, off, postings.len() as u32));
+            let name = &term.as_bytes()[..term.len().min(u16::MAX as usize)];
+            vocab.push(VocabEntry { name_off: vocab_names.len() as u32, name_len: name.len() as u16,
+                postings_off: off, postings_len: postings.len() as u32 });
+            vocab_names.extend_from_slice(name);
         }
 
         // Hash table
@@ -209,6 +292,12 @@ impl IndexBuilder {
             }
         }
 
+        // Topic ids as laid out: sorted by name
+        let mut by_name: Vec<usize> = (0..self.topics.len()).collect();
+        by_name.sort_by(|&a, &b| self.topics[a].cmp(&self.topics[b]));
+        let mut topic_id = vec![0u16; self.topics.len()];
+        for (new, &old) in by_name.iter().enumerate() { topic_id[old] = new as u16; }
+
         // Snippet pool + source pool + entry metadata
         // F6: Cache fs::metadata calls for compute_confidence
         let mut mtime_cache: FxHashMap<String, Option<std::time::SystemTime>> = FxHashMap::default();
@@ -232,7 +321,8 @@ impl IndexBuilder {
             };
 
             let tag_bitmap = self.entry_tag_bitmap(&info.tags, &tag_to_bit);
-            let staleness_conf = compute_confidence_cached(&info.source, info.date_minutes, &mut mtime_cache);
+            let commit = info.commit.as_deref().filter(|_| self.git_freshness);
+            let staleness_conf = compute_confidence_cached(&info.source, info.date_minutes, commit, &mut mtime_cache);
             let confidence = match info.explicit_confidence {
                 Some(c) => ((c.clamp(0.0, 1.0) * 255.0) as u8).min(staleness_conf),
                 None => staleness_conf,
@@ -242,12 +332,13 @@ impl IndexBuilder {
             } else { 0 };
 
             metas.push(EntryMeta {
-                topic_id: info.topic_id, word_count: info.word_count,
+                topic_id: topic_id[info.topic_id as usize], word_count: info.word_count,
                 snippet_off: s_off, snippet_len: s_len,
                 date_minutes: info.date_minutes,
                 source_off: src_off, source_len: src_len,
                 log_offset: info.log_offset,
-                tag_bitmap, confidence, epoch_days, _pad: 0,
+                tag_bitmap, confidence, epoch_days, importance: info.importance,
+                provenance: info.provenance, lang: info.lang,
             });
         }
 
@@ -255,17 +346,31 @@ impl IndexBuilder {
         let mut tname_pool = Vec::<u8>::new();
         let mut ttable = Vec::<TopicEntry>::new();
         let mut tcounts = vec![0u16; self.topics.len()];
-        for e in &self.entries { tcounts[e.topic_id as usize] += 1; }
-        for (i, name) in self.topics.iter().enumerate() {
+        let mut twords = vec![0usize; self.topics.len()];
+        for e in &self.entries {
+            tcounts[e.topic_id as usize] += 1;
+            twords[e.topic_id as usize] += e.word_count as usize;
+        }
+        for &i in &by_name {
+            let name = &self.topics[i];
             let off = tname_pool.len() as u32;
             let nb = name.as_bytes();
             let len = nb.len().min(u16::MAX as usize) as u16;
             tname_pool.extend_from_slice(&nb[..len as usize]);
-            ttable.push(TopicEntry { name_off: off, name_len: len, entry_count: tcounts[i] });
+            let cohort = crate::score::cohort_avgdl(twords[i], tcounts[i] as usize, avgdl);
+            ttable.push(TopicEntry {
+                name_off: off, name_len: len, entry_count: tcounts[i], uid: topic_uid(name),
+                avgdl_x100: (cohort * 100.0) as u32,
+            });
         }
 
         // Xrefs
-        let xrefs = self.compute_xrefs();
+        let mut xrefs = self.compute_xrefs();
+        for x in &mut xrefs {
+            x.src_topic = topic_id[x.src_topic as usize];
+            x.dst_topic = topic_id[x.dst_topic as usize];
+        }
+        xrefs.sort_by_key(|x| (x.src_topic, x.dst_topic));
 
         // Tag names section: [count: u8][len: u8][name]...
         let tag_names_buf = self.build_tag_names(&tag_to_bit);
@@ -285,7 +390,10 @@ impl IndexBuilder {
         let xref_off = src_off + sources.len();
         let xref_sz = xrefs.len() * std::mem::size_of::<XrefEdge>();
         let tagn_off = xref_off + xref_sz;
-        let total = tagn_off + tag_names_buf.len();
+        let pos_off = tagn_off + tag_names_buf.len();
+        let vocab_off = pos_off + positions.len() * 2;
+        let vnames_off = vocab_off + vocab.len() * std::mem::size_of::<VocabEntry>();
+        let total = vnames_off + vocab_names.len();
 
         let header = Header {
             magic: MAGIC, version: VERSION,
@@ -297,6 +405,8 @@ impl IndexBuilder {
             topic_names_off: tname_off as u32, source_off: src_off as u32,
             xref_off: xref_off as u32, total_len: total as u32,
             tag_names_off: tagn_off as u32, num_tags: tag_to_bit.len() as u32,
+            positions_off: pos_off as u32,
+            vocab_off: vocab_off as u32, vocab_names_off: vnames_off as u32,
         };
 
         let mut buf = Vec::with_capacity(total);
@@ -310,12 +420,15 @@ impl IndexBuilder {
         buf.extend_from_slice(&sources);
         for x in &xrefs { buf.extend_from_slice(as_bytes(x)); }
         buf.extend_from_slice(&tag_names_buf);
+        for p in &positions { buf.extend_from_slice(&p.to_le_bytes()); }
+        for v in &vocab { buf.extend_from_slice(as_bytes(v)); }
+        buf.extend_from_slice(&vocab_names);
         buf
     }
 
     fn build_tag_map(&self) -> Vec<(String, u8)> {
         let mut sorted: Vec<_> = self.tag_freq.iter().collect();
-        sorted.sort_by(|a, b| b.1.cmp(a.1));
+        sorted.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
         sorted.iter().take(32)
             .enumerate().map(|(i, (name, _))| ((*name).clone(), i as u8)).collect()
     }
@@ -346,12 +459,12 @@ impl IndexBuilder {
 // --- Public functions ---
 
 /// Build index from corpus cache. Returns bytes without writing to disk.
-/// Used by ensure_index_fresh (hot path) — disk write deferred.
+/// Used by indexview::fresh, which publishes it under the index lock.
 pub fn rebuild(dir: &Path) -> Result<(String, Vec<u8>), String> {
     rebuild_inner(dir, false)
 }
 
-/// Build index AND write to disk. Used by explicit rebuild_index tool.
+/// Build index AND publish it as the shared index.bin (see indexview.rs).
 pub fn rebuild_and_persist(dir: &Path) -> Result<(String, Vec<u8>), String> {
     rebuild_inner(dir, true)
 }
@@ -369,40 +482,64 @@ fn rebuild_inner(dir: &Path, persist: bool) -> Result<(String, Vec<u8>), String>
         }
     }
 
+    // Stamp before reading: a write landing mid-build leaves the index marked stale
+    let built_from = crate::indexview::log_stamp(dir);
+
     // Try corpus cache first (pre-tokenized entries, skip tokenize() calls)
+    crate::progress::report(0, None, "loading corpus");
     let (bytes, ne, nt, ntop) = crate::cache::with_corpus(dir, |cached| {
         let mut builder = IndexBuilder::new();
-        for e in cached {
+        builder.set_git_freshness(crate::git::enabled(dir));
+        let importance = crate::importance::compute(dir, cached);
+        let total = cached.len() as u64;
+        for (i, e) in cached.iter().enumerate() {
+            // Every 256 entries: cheap enough to keep, fine-grained enough to stop promptly
+            if i % 256 == 0 { crate::cancel::check()?; }
+            if crate::progress::active() {
+                crate::progress::report(i as u64, Some(total + 1), "indexing entries");
+            }
+            // Queued entries stay unsearchable until approved (review.rs)
+            if e.has_tag(crate::review::TAG) { continue; }
             let tid = builder.add_topic(&e.topic);
             let conf = if e.confidence() < 1.0 { Some(e.confidence()) } else { None };
-            builder.add_entry_from_tfmap(
+            let eid = builder.add_entry_from_tfmap(
                 tid, &e.snippet, e.timestamp_min,
                 e.source().unwrap_or(""), e.offset, e.tags(),
                 &e.tf_map, e.word_count, conf,
             );
+            builder.set_importance(eid, importance[i]);
+            if let Some(p) = e.provenance() { builder.set_provenance(eid, p.code()); }
+            if let Some(l) = e.lang() { builder.set_lang(eid, l.code()); }
+            builder.set_positions(eid, &crate::text::tokenize(&e.body));
+            if let Some(c) = crate::text::extract_commit(&e.body) { builder.set_commit(eid, c); }
         }
         let ne = builder.entries.len();
         let nt = builder.terms.len();
         let ntop = builder.topics.len();
-        (builder.build(), ne, nt, ntop)
-    })?;
+        crate::progress::report(total, Some(total + 1), "building index");
+        Ok::<_, String>((builder.build(), ne, nt, ntop))
+    })??;
 
     if persist {
-        let index_path = dir.join("index.bin");
-        std::fs::write(&index_path, &bytes).map_err(|e| e.to_string())?;
+        crate::indexview::publish(dir, &bytes, built_from)?;
     }
+    crate::progress::report(ne as u64 + 1, Some(ne as u64 + 1), "index built");
     let msg = format!("index v2: {ne} entries, {nt} terms, {ntop} topics, {} bytes",
         bytes.len());
     Ok((msg, bytes))
 }
 
 /// F6: Cached variant — one stat() per unique source path instead of per entry.
+/// With a recorded `commit` git decides, unless the file has uncommitted edits.
 fn compute_confidence_cached(
-    source: &str, date_minutes: i32,
+    source: &str, date_minutes: i32, commit: Option<&str>,
     cache: &mut FxHashMap<String, Option<std::time::SystemTime>>,
 ) -> u8 {
     if source.is_empty() { return 255; }
     let path = source.split(':').next().unwrap_or(source);
+    if let Some(stale) = commit.and_then(|c| crate::git::is_stale(Path::new(path), c)) {
+        return if stale { 178 } else { 255 };
+    }
     let mtime = cache.entry(path.to_string()).or_insert_with(|| {
         std::fs::metadata(path).and_then(|m| m.modified()).ok()
     });
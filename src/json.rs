@@ -1,6 +1,19 @@
+//! Dependency-free JSON for MCP messages, session files and exports.
+//!
+//! `parse` is lenient: it takes the first value, keeps unknown escapes as the
+//! escaped character and replaces a lone surrogate with U+FFFD, so a sloppy
+//! client still gets an answer. `parse_strict` rejects all of that (plus raw
+//! control characters, malformed numbers and duplicate keys) for input that
+//! must be right. Both cap nesting at `MAX_DEPTH`. Serializers escape every
+//! control character, write non-finite numbers as `null`, and `canonical`
+//! also sorts object keys (RFC 8785 order), so equal values serialize equal.
+
 use std::fmt;
 
-#[derive(Clone)]
+/// Deepest array/object nesting either parser accepts.
+pub const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     Null,
     Bool(bool),
@@ -106,6 +119,13 @@ impl Value {
 /// Byte-level chunk-copy: scans for escape-needing bytes, memcpys clean chunks.
 /// Public for use by mcp.rs and hook.rs response formatting.
 pub fn escape_into(s: &str, buf: &mut String) {
+    let _ = escape_to(s, buf);
+}
+
+/// The one escaper behind every serializer: `"`, `\`, the short forms
+/// (\b \f \n \r \t), `\u00xx` for other control characters; the rest,
+/// non-BMP included, is written as UTF-8.
+fn escape_to<W: fmt::Write>(s: &str, w: &mut W) -> fmt::Result {
     let bytes = s.as_bytes();
     let mut i = 0;
     let mut last_copy = 0;
@@ -116,23 +136,40 @@ pub fn escape_into(s: &str, buf: &mut String) {
             b'\n' => "\\n",
             b'\r' => "\\r",
             b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0C => "\\f",
             c if c < 0x20 => {
                 // Safety: s is &str, slicing at ASCII positions = valid UTF-8
-                if last_copy < i { buf.push_str(&s[last_copy..i]); }
-                use fmt::Write;
-                let _ = write!(buf, "\\u{:04x}", c);
+                if last_copy < i { w.write_str(&s[last_copy..i])?; }
+                write!(w, "\\u{:04x}", c)?;
                 i += 1;
                 last_copy = i;
                 continue;
             }
             _ => { i += 1; continue; }
         };
-        if last_copy < i { buf.push_str(&s[last_copy..i]); }
-        buf.push_str(esc);
+        if last_copy < i { w.write_str(&s[last_copy..i])?; }
+        w.write_str(esc)?;
         i += 1;
         last_copy = i;
     }
-    if last_copy < bytes.len() { buf.push_str(&s[last_copy..]); }
+    if last_copy < bytes.len() { w.write_str(&s[last_copy..])?; }
+    Ok(())
+}
+
+/// A number as JSON: integers exactly up to 2^53, the shortest round-trip
+/// form otherwise (exponent past 1e21 or under 1e-6, as ECMAScript does),
+/// `null` for NaN and infinities, which JSON can't express.
+fn write_num<W: fmt::Write>(n: f64, w: &mut W) -> fmt::Result {
+    const EXACT: f64 = 9_007_199_254_740_992.0;
+    if !n.is_finite() { return w.write_str("null"); }
+    if n.fract() == 0.0 && n.abs() <= EXACT { return write!(w, "{}", n as i64); }
+    if (1e-6..1e21).contains(&n.abs()) { return write!(w, "{n}"); }
+    let e = format!("{n:e}");
+    match e.split_once('e') {
+        Some((m, exp)) if !exp.starts_with('-') => write!(w, "{m}e+{exp}"),
+        _ => w.write_str(&e),
+    }
 }
 
 impl fmt::Display for Value {
@@ -148,13 +185,10 @@ fn write_to_fmt(v: &Value, f: &mut fmt::Formatter) -> fmt::Result {
     match v {
         Value::Null => f.write_str("null"),
         Value::Bool(b) => f.write_str(if *b { "true" } else { "false" }),
-        Value::Num(n) => {
-            if n.fract() == 0.0 && n.is_finite() { write!(f, "{}", *n as i64) }
-            else { write!(f, "{n}") }
-        }
+        Value::Num(n) => write_num(*n, f),
         Value::Str(s) => {
             f.write_char('"')?;
-            escape_to_fmt(s, f)?;
+            escape_to(s, f)?;
             f.write_char('"')
         }
         Value::Arr(items) => {
@@ -170,7 +204,7 @@ fn write_to_fmt(v: &Value, f: &mut fmt::Formatter) -> fmt::Result {
             for (i, (k, v)) in pairs.iter().enumerate() {
                 if i > 0 { f.write_char(',')?; }
                 f.write_char('"')?;
-                escape_to_fmt(k, f)?;
+                escape_to(k, f)?;
                 f.write_str("\":")?;
                 write_to_fmt(v, f)?;
             }
@@ -179,44 +213,12 @@ fn write_to_fmt(v: &Value, f: &mut fmt::Formatter) -> fmt::Result {
     }
 }
 
-/// Escape string for JSON directly to fmt::Formatter — same chunk-copy as escape_into.
-fn escape_to_fmt(s: &str, f: &mut fmt::Formatter) -> fmt::Result {
-    let bytes = s.as_bytes();
-    let mut i = 0;
-    let mut last_copy = 0;
-    while i < bytes.len() {
-        let esc = match bytes[i] {
-            b'"' => "\\\"",
-            b'\\' => "\\\\",
-            b'\n' => "\\n",
-            b'\r' => "\\r",
-            b'\t' => "\\t",
-            c if c < 0x20 => {
-                if last_copy < i { f.write_str(&s[last_copy..i])?; }
-                write!(f, "\\u{:04x}", c)?;
-                i += 1; last_copy = i;
-                continue;
-            }
-            _ => { i += 1; continue; }
-        };
-        if last_copy < i { f.write_str(&s[last_copy..i])?; }
-        f.write_str(esc)?;
-        i += 1; last_copy = i;
-    }
-    if last_copy < bytes.len() { f.write_str(&s[last_copy..])?; }
-    Ok(())
-}
-
 /// Write Value as compact JSON to a String. Public for direct String building.
 pub fn write_compact(v: &Value, buf: &mut String) {
     match v {
         Value::Null => buf.push_str("null"),
         Value::Bool(b) => buf.push_str(if *b { "true" } else { "false" }),
-        Value::Num(n) => {
-            use fmt::Write;
-            if n.fract() == 0.0 && n.is_finite() { write!(buf, "{}", *n as i64).unwrap(); }
-            else { write!(buf, "{n}").unwrap(); }
-        }
+        Value::Num(n) => { let _ = write_num(*n, buf); }
         Value::Str(s) => {
             buf.push('"');
             escape_into(s, buf);
@@ -244,14 +246,100 @@ pub fn write_compact(v: &Value, buf: &mut String) {
     }
 }
 
+/// Canonical compact JSON (RFC 8785 style): `write_compact` with object
+/// keys sorted by UTF-16 code units at every level, so equal values always
+/// give equal bytes — for hashing, signing or diffing exports.
+/// ```
+/// use amaranthine::json::{canonical, parse_strict, Value};
+/// let v = parse_strict(r#"{"b":[1,2.5,1e300],"a":"\u00e9\ud83d\ude00\u0007"}"#).unwrap();
+/// assert_eq!(canonical(&v), "{\"a\":\"é😀\\u0007\",\"b\":[1,2.5,1e+300]}");
+/// assert_eq!(canonical(&Value::Num(f64::NAN)), "null");
+///
+/// // Round trip, over generated values: control characters, quotes,
+/// // non-BMP text, awkward numbers, nesting
+/// let mut seed = 0x9E3779B97F4A7C15u64;
+/// let mut rand = |n: u64| { seed ^= seed << 13; seed ^= seed >> 7; seed ^= seed << 17; seed % n };
+/// const CHARS: [char; 10] = ['a', '"', '\\', '\n', '\u{1}', '\u{7f}', 'é', '\u{2028}', '😀', '\u{10FFFF}'];
+/// fn gen(rand: &mut dyn FnMut(u64) -> u64, depth: u32) -> Value {
+///     let text = |rand: &mut dyn FnMut(u64) -> u64| (0..rand(6)).map(|_| CHARS[rand(10) as usize]).collect::<String>();
+///     match if depth > 3 { rand(4) } else { rand(6) } {
+///         0 => Value::Null,
+///         1 => Value::Bool(rand(2) == 1),
+///         2 => Value::Num([0.0, -1.0, 0.1, 1e-7, 123456789.125, 1e21, -2f64.powi(60)][rand(7) as usize]),
+///         3 => Value::Str(text(rand)),
+///         4 => Value::Arr((0..rand(4)).map(|_| gen(rand, depth + 1)).collect()),
+///         _ => Value::Obj((0..rand(4)).map(|i| (format!("{i}{}", text(rand)), gen(rand, depth + 1))).collect()),
+///     }
+/// }
+/// for _ in 0..500 {
+///     let v = gen(&mut rand, 0);
+///     assert_eq!(parse_strict(&v.to_string()).unwrap(), v);
+///     let again = parse_strict(&canonical(&v)).unwrap();
+///     assert_eq!(canonical(&again), canonical(&v));
+/// }
+/// ```
+pub fn canonical(v: &Value) -> String {
+    let mut buf = String::new();
+    write_canonical(v, &mut buf);
+    buf
+}
+
+fn write_canonical(v: &Value, buf: &mut String) {
+    match v {
+        Value::Arr(items) => {
+            buf.push('[');
+            for (i, v) in items.iter().enumerate() {
+                if i > 0 { buf.push(','); }
+                write_canonical(v, buf);
+            }
+            buf.push(']');
+        }
+        Value::Obj(pairs) => {
+            let mut sorted: Vec<&(String, Value)> = pairs.iter().collect();
+            sorted.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            buf.push('{');
+            for (i, (k, v)) in sorted.into_iter().enumerate() {
+                if i > 0 { buf.push(','); }
+                buf.push('"');
+                escape_into(k, buf);
+                buf.push_str("\":");
+                write_canonical(v, buf);
+            }
+            buf.push('}');
+        }
+        other => write_compact(other, buf),
+    }
+}
+
 // --- Parser ---
 
+/// Lenient parse of the first JSON value in `input` (see the module docs).
 pub fn parse(input: &str) -> Result<Value, String> {
-    let mut p = Parser { b: input.as_bytes(), pos: 0 };
-    p.value()
+    Parser { b: input.as_bytes(), pos: 0, strict: false, depth: 0 }.value()
 }
 
-struct Parser<'a> { b: &'a [u8], pos: usize }
+/// Strict parse: `input` must be exactly one valid JSON value, and an
+/// error names the first problem instead of passing it on.
+/// ```
+/// use amaranthine::json::{parse, parse_strict, Value};
+/// assert_eq!(parse_strict(r#""\ud83d\ude00""#).unwrap(), Value::Str("😀".into()));
+/// for bad in [r#""\ud83d""#, r#""\q""#, "\"tab\there\"", "01", "1.", "[1] x", r#"{"a":1,"a":2}"#] {
+///     assert!(parse_strict(bad).is_err(), "{bad}");
+/// }
+/// // The lenient parser lets each of those through
+/// assert_eq!(parse(r#""\ud83d!""#).unwrap(), Value::Str("\u{FFFD}!".into()));
+/// assert_eq!(parse("[1] x").unwrap(), Value::Arr(vec![Value::Num(1.0)]));
+/// assert!(parse(&"[".repeat(10_000)).is_err());
+/// ```
+pub fn parse_strict(input: &str) -> Result<Value, String> {
+    let mut p = Parser { b: input.as_bytes(), pos: 0, strict: true, depth: 0 };
+    let v = p.value()?;
+    p.ws();
+    if p.pos < p.b.len() { return Err(format!("trailing characters at byte {}", p.pos)); }
+    Ok(v)
+}
+
+struct Parser<'a> { b: &'a [u8], pos: usize, strict: bool, depth: usize }
 
 impl Parser<'_> {
     fn ws(&mut self) {
@@ -279,8 +367,13 @@ impl Parser<'_> {
         self.ws();
         match self.peek() {
             Some(b'"') => self.string().map(Value::Str),
-            Some(b'{') => self.object(),
-            Some(b'[') => self.array(),
+            Some(c @ (b'{' | b'[')) => {
+                if self.depth == MAX_DEPTH { return Err(format!("nesting deeper than {MAX_DEPTH}")); }
+                self.depth += 1;
+                let v = if c == b'{' { self.object() } else { self.array() };
+                self.depth -= 1;
+                v
+            }
             Some(b't') => { self.expect(b"true")?; Ok(Value::Bool(true)) }
             Some(b'f') => { self.expect(b"false")?; Ok(Value::Bool(false)) }
             Some(b'n') => { self.expect(b"null")?; Ok(Value::Null) }
@@ -306,7 +399,9 @@ impl Parser<'_> {
                     self.pos = p + 1;
                     return Ok(s);
                 }
-                b'\\' => break, // has escapes, fall through to slow path
+                // Escapes (and raw control characters) take the slow path
+                b'\\' => break,
+                c if c < 0x20 => break,
                 _ => p += 1,
             }
         }
@@ -322,20 +417,19 @@ impl Parser<'_> {
                     b'r' => s.push('\r'), b't' => s.push('\t'),
                     b'b' => s.push('\x08'), b'f' => s.push('\x0C'),
                     b'u' => {
-                        let mut cp = 0u32;
-                        for _ in 0..4 {
-                            let h = self.next()?;
-                            cp = cp * 16 + match h {
-                                b'0'..=b'9' => (h - b'0') as u32,
-                                b'a'..=b'f' => (h - b'a' + 10) as u32,
-                                b'A'..=b'F' => (h - b'A' + 10) as u32,
-                                _ => return Err("bad \\u hex".into()),
-                            };
+                        let cp = self.hex4()?;
+                        match self.code_point(cp)? {
+                            Some(c) => s.push(c),
+                            None if self.strict => return Err(format!("lone surrogate \\u{cp:04x}")),
+                            None => s.push('\u{FFFD}'),
                         }
-                        s.push(char::from_u32(cp).unwrap_or('\u{FFFD}'));
                     }
+                    c if self.strict => return Err(format!("bad escape '\\{}'", c as char)),
+                    // A backslash before a multibyte character: drop the backslash
+                    c if c >= 0x80 => self.pos -= 1,
                     c => s.push(c as char),
                 },
+                _ if b < 0x20 && self.strict => return Err(format!("unescaped control character 0x{b:02x} in string")),
                 _ if b < 0x80 => s.push(b as char),
                 _ => {
                     let start = self.pos - 1;
@@ -349,6 +443,36 @@ impl Parser<'_> {
         }
     }
 
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut cp = 0u32;
+        for _ in 0..4 {
+            let h = self.next()?;
+            cp = cp * 16 + match h {
+                b'0'..=b'9' => (h - b'0') as u32,
+                b'a'..=b'f' => (h - b'a' + 10) as u32,
+                b'A'..=b'F' => (h - b'A' + 10) as u32,
+                _ => return Err("bad \\u hex".into()),
+            };
+        }
+        Ok(cp)
+    }
+
+    /// The character for `\\u` code unit `cp`, joining a high surrogate with
+    /// the `\\u` low surrogate after it. None for a lone surrogate.
+    fn code_point(&mut self, cp: u32) -> Result<Option<char>, String> {
+        if !(0xD800..0xDC00).contains(&cp) { return Ok(char::from_u32(cp)); }
+        if !self.b[self.pos..].starts_with(b"\\u") { return Ok(None); }
+        let save = self.pos;
+        self.pos += 2;
+        let lo = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&lo) {
+            // Not a pair: leave the second escape to be read on its own
+            self.pos = save;
+            return Ok(None);
+        }
+        Ok(char::from_u32(0x10000 + ((cp - 0xD800) << 10) + (lo - 0xDC00)))
+    }
+
     fn number(&mut self) -> Result<Value, String> {
         let start = self.pos;
         if self.peek() == Some(b'-') { self.pos += 1; }
@@ -363,9 +487,10 @@ impl Parser<'_> {
             while self.pos < self.b.len() && self.b[self.pos].is_ascii_digit() { self.pos += 1; }
         }
         let s = std::str::from_utf8(&self.b[start..self.pos]).unwrap_or("0");
-        s.parse::<f64>()
-            .map(Value::Num)
-            .map_err(|e| e.to_string())
+        if self.strict && !strict_number(s) { return Err(format!("bad number '{s}'")); }
+        let n = s.parse::<f64>().map_err(|e| e.to_string())?;
+        if self.strict && !n.is_finite() { return Err(format!("number out of range '{s}'")); }
+        Ok(Value::Num(n))
     }
 
     fn object(&mut self) -> Result<Value, String> {
@@ -375,7 +500,11 @@ impl Parser<'_> {
         if self.peek() == Some(b'}') { self.pos += 1; return Ok(Value::Obj(pairs)); }
         loop {
             self.ws();
+            if self.peek() != Some(b'"') { return Err("expected string key".into()); }
             let key = self.string()?;
+            if self.strict && pairs.iter().any(|(k, _)| *k == key) {
+                return Err(format!("duplicate key \"{key}\""));
+            }
             self.ws();
             if self.next()? != b':' { return Err("expected ':'".into()); }
             pairs.push((key, self.value()?));
@@ -404,3 +533,20 @@ impl Parser<'_> {
         }
     }
 }
+
+/// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`
+fn strict_number(s: &str) -> bool {
+    let digits = |t: &str| !t.is_empty() && t.bytes().all(|b| b.is_ascii_digit());
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let (mantissa, exp) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let (int, frac) = match mantissa.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (mantissa, None),
+    };
+    digits(int) && (int == "0" || !int.starts_with('0'))
+        && frac.is_none_or(digits)
+        && exp.is_none_or(|e| digits(e.strip_prefix(['+', '-']).unwrap_or(e)))
+}
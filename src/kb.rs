@@ -0,0 +1,150 @@
+//! KnowledgeBase: embeddable facade over store/search/reconstruct/index.
+//! Owns dir resolution and data.log bootstrap; searches use the shared index.bin
+//! mapping, rebuilt lazily when data.log changes (including writes by other processes).
+//! This is the stable library surface; the per-module functions it wraps may
+//! change between releases.
+//!
+//! ```no_run
+//! use amaranthine::kb::{KnowledgeBase, StoreOptions};
+//! use amaranthine::score::Filter;
+//!
+//! let kb = KnowledgeBase::open_default()?;
+//! kb.store("rust-tips", "use #[repr(C)] for FFI structs", &StoreOptions::tags("rust,ffi"))?;
+//! let (hits, _relaxed) = kb.search("ffi structs", &Filter::none(), Some(5))?;
+//! for h in &hits { println!("[{}] {}", h.name, h.lines.join(" ")); }
+//! # Ok::<(), amaranthine::error::AmrError>(())
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::error::AmrError;
+use crate::indexview::IndexMap;
+use crate::score::{Filter, ScoredResult};
+pub use crate::store::StoreOptions;
+
+/// What `maintain` did, one human-readable line per step.
+pub struct Maintenance {
+    pub compact: String,
+    pub index: String,
+}
+
+pub struct KnowledgeBase {
+    dir: PathBuf,
+}
+
+impl KnowledgeBase {
+    /// Open (creating if needed) the knowledge base at `dir`.
+    /// Migrates legacy .md topic files into data.log on first open.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, AmrError> {
+        let dir = dir.as_ref().to_path_buf();
+        crate::config::ensure_dir(&dir)?;
+        if !crate::config::data_log_exists(&dir) {
+            let legacy = crate::config::list_topic_files(&dir).unwrap_or_default();
+            if legacy.is_empty() {
+                crate::datalog::ensure_log(&dir)?;
+            } else {
+                crate::datalog::migrate_from_md(&dir)?;
+            }
+        }
+        crate::settings::apply(&dir);
+        Ok(Self { dir })
+    }
+
+    /// Open the default location: $AMARANTHINE_DIR, else ~/.amaranthine.
+    pub fn open_default() -> Result<Self, AmrError> {
+        Self::open(crate::config::resolve_dir(std::env::var("AMARANTHINE_DIR").ok()))
+    }
+
+    pub fn dir(&self) -> &Path { &self.dir }
+
+    /// This KB's dir, with the process-wide settings (tokenizer plugin,
+    /// postings cap) pointed at it first, so instances over different dirs
+    /// can be used in turn. Corpus cache and snippet style are per dir anyway.
+    fn at(&self) -> &Path {
+        crate::settings::apply(&self.dir);
+        &self.dir
+    }
+
+    /// Store a new entry. Takes the directory lock; returns the store echo.
+    pub fn store(&self, topic: &str, text: &str, opts: &StoreOptions) -> Result<String, AmrError> {
+        if topic.trim().is_empty() { return Err(AmrError::InvalidArg("topic required".into())); }
+        if text.trim().is_empty() { return Err(AmrError::InvalidArg("text required".into())); }
+        let topic = crate::config::sanitize_topic(topic);
+        crate::store::run_full_ext(self.at(), &topic, text, opts)
+    }
+
+    /// Append text to the most recent entry in `topic`.
+    pub fn append(&self, topic: &str, text: &str) -> Result<String, AmrError> {
+        crate::store::append(self.at(), &crate::config::sanitize_topic(topic), text)
+    }
+
+    /// Ranked search. Returns (results, relaxed) where `relaxed` means no entry
+    /// matched every term and results are the AND→OR fallback.
+    pub fn search(&self, query: &str, filter: &Filter, limit: Option<usize>)
+        -> Result<(Vec<ScoredResult>, bool), AmrError>
+    {
+        let dir = self.at();
+        let terms = crate::text::query_terms(query);
+        if terms.is_empty() && !filter.is_active() {
+            return Err(AmrError::InvalidArg("provide a query or filter".into()));
+        }
+        let index = self.index()?;
+        crate::score::search_scored(dir, &terms, filter, limit, Some(&index), true)
+    }
+
+    /// Streaming search: same ranking as `search`, results handed to `f` best-first.
+    /// Return `ControlFlow::Break(())` to stop; unvisited hits are never materialized.
+    /// Returns (visited, relaxed).
+    pub fn visit_hits<F>(&self, query: &str, filter: &Filter, limit: Option<usize>, f: F)
+        -> Result<(usize, bool), AmrError>
+    where F: FnMut(ScoredResult) -> std::ops::ControlFlow<()> {
+        let dir = self.at();
+        let terms = crate::text::query_terms(query);
+        if terms.is_empty() && !filter.is_active() {
+            return Err(AmrError::InvalidArg("provide a query or filter".into()));
+        }
+        let index = self.index()?;
+        crate::score::visit_hits(dir, &terms, filter, limit, Some(&index), true, f)
+    }
+
+    /// Search rendered as plain text, same layout as `amaranthine search --plain`.
+    pub fn search_text(&self, query: &str, filter: &Filter, limit: Option<usize>)
+        -> Result<String, AmrError>
+    {
+        let index = self.index()?;
+        crate::search::run(&self.dir, query, true, limit, filter, Some(&index))
+    }
+
+    /// One-shot briefing for a topic, glob (`iris-*`), or source file.
+    /// `detail` is "summary", "scan", or "full"; `focus` is comma-separated categories.
+    pub fn reconstruct(&self, query: &str, detail: &str, since_hours: Option<u64>,
+                       focus: Option<&str>) -> Result<String, AmrError> {
+        if query.trim().is_empty() { return Err(AmrError::InvalidArg("query required".into())); }
+        Ok(crate::reconstruct::run(self.at(), query, detail, since_hours, focus)?)
+    }
+
+    /// Compact data.log (drop tombstoned entries) under the directory lock,
+    /// then rebuild and persist index.bin.
+    pub fn maintain(&self) -> Result<Maintenance, AmrError> {
+        let compact = {
+            let dir = self.at();
+            let _lock = crate::lock::FileLock::acquire(dir)?;
+            crate::datalog::compact_log(dir)?
+        };
+        let index = self.reindex()?;
+        Ok(Maintenance { compact, index })
+    }
+
+    /// Rebuild index.bin from data.log now and persist it for hooks/C callers.
+    pub fn reindex(&self) -> Result<String, AmrError> {
+        let (msg, _) = crate::inverted::rebuild_and_persist(self.at()).map_err(AmrError::Io)?;
+        Ok(msg)
+    }
+
+    /// The shared index.bin (indexview.rs), rebuilt first if data.log changed
+    /// since the last build by any process.
+    pub fn index(&self) -> Result<Arc<IndexMap>, AmrError> {
+        crate::indexview::fresh(self.at())?
+            .ok_or_else(|| AmrError::NotFound("no index.bin".into()))
+    }
+}
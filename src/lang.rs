@@ -0,0 +1,85 @@
+//! Entry language, for mixed-language KBs: a stop-word profile classifier run
+//! at store time and recorded as `[lang: de]`. Entries stored before that (or
+//! imported) are classified when the index is built. Indexed in EntryMeta (v9)
+//! for the search `lang` filter and the per-language counts in `stats`.
+//!
+//! Each language scores the share of an entry's words found in its stop-word
+//! list; the best score wins if it clears `MIN_SHARE` over at least
+//! `MIN_HITS` words. Code, logs and one-liners stay unclassified.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Lang { En, De, Fr, Es, Nl }
+
+/// Stop words a language must hit before it is trusted.
+const MIN_HITS: usize = 3;
+/// Share of all words that must be the winner's stop words.
+const MIN_SHARE: f64 = 0.1;
+
+const EN: &[&str] = &["the", "and", "is", "are", "was", "of", "to", "in", "that", "it", "for", "with",
+    "this", "not", "be", "on", "as", "but", "or", "from", "have", "has", "when", "we", "you", "if", "an", "by"];
+const DE: &[&str] = &["der", "die", "das", "und", "ist", "sind", "nicht", "mit", "ein", "eine", "den", "dem",
+    "zu", "auf", "für", "von", "auch", "es", "wir", "sich", "wenn", "aber", "oder", "wird", "werden", "noch", "bei", "nach"];
+const FR: &[&str] = &["le", "la", "les", "et", "est", "des", "une", "un", "du", "que", "qui", "pas", "pour",
+    "dans", "sur", "avec", "ce", "il", "nous", "vous", "sont", "mais", "ou", "au", "aux", "ne", "se"];
+const ES: &[&str] = &["el", "los", "las", "y", "es", "una", "del", "que", "en", "por", "para", "con", "no",
+    "se", "lo", "como", "pero", "su", "al", "son", "está", "hay", "cuando", "muy"];
+const NL: &[&str] = &["de", "het", "een", "en", "is", "van", "niet", "met", "op", "voor", "dat", "die", "zijn",
+    "ook", "maar", "als", "bij", "wordt", "naar", "worden", "nog", "wij", "je"];
+
+impl Lang {
+    pub const ALL: [Lang; 5] = [Self::En, Self::De, Self::Fr, Self::Es, Self::Nl];
+
+    pub fn as_str(self) -> &'static str {
+        match self { Self::En => "en", Self::De => "de", Self::Fr => "fr", Self::Es => "es", Self::Nl => "nl" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.as_str() == s.trim())
+    }
+
+    /// EntryMeta byte; 0 means unclassified.
+    pub fn code(self) -> u8 {
+        match self { Self::En => 1, Self::De => 2, Self::Fr => 3, Self::Es => 4, Self::Nl => 5 }
+    }
+
+    pub fn from_code(c: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|l| l.code() == c)
+    }
+
+    fn stop_words(self) -> &'static [&'static str] {
+        match self { Self::En => EN, Self::De => DE, Self::Fr => FR, Self::Es => ES, Self::Nl => NL }
+    }
+}
+
+/// "en, de, fr, es or nl", for error messages.
+pub fn names() -> String {
+    let all: Vec<&str> = Lang::ALL.iter().map(|l| l.as_str()).collect();
+    format!("{} or {}", all[..all.len() - 1].join(", "), all[all.len() - 1])
+}
+
+impl std::fmt::Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(self.as_str()) }
+}
+
+/// The language of `text`'s prose (metadata lines skipped), if any is clear.
+/// ```
+/// use amaranthine::lang::{detect, Lang};
+/// assert_eq!(detect("Der Cache wird nicht geleert, wenn die Datei fehlt."), Some(Lang::De));
+/// assert_eq!(detect("The cache is not cleared when the file is missing."), Some(Lang::En));
+/// assert_eq!(detect("fn main() { x.unwrap() }"), None);
+/// ```
+pub fn detect(text: &str) -> Option<Lang> {
+    let mut words = 0usize;
+    let mut hits = [0usize; Lang::ALL.len()];
+    for line in text.lines().filter(|l| !crate::text::is_metadata_line(l)) {
+        for w in line.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()) {
+            words += 1;
+            let w = w.to_lowercase();
+            for (i, l) in Lang::ALL.iter().enumerate() {
+                if l.stop_words().contains(&w.as_str()) { hits[i] += 1; }
+            }
+        }
+    }
+    let (best, &n) = hits.iter().enumerate().max_by_key(|&(i, n)| (*n, std::cmp::Reverse(i)))?;
+    (n >= MIN_HITS && n as f64 >= words as f64 * MIN_SHARE).then_some(Lang::ALL[best])
+}
@@ -3,46 +3,101 @@
 //! Binary: uses modules via `use amaranthine::*`
 //! C/FFI: links libamaranthine.dylib, queries index at ~200ns
 
+pub mod address;
+pub mod ambient;
+pub mod answer;
 pub mod binquery;
+pub mod briefdiff;
 pub mod briefing;
 pub mod cache;
+pub mod cachefile;
 pub mod callgraph;
+pub mod cancel;
 pub mod cffi;
 pub mod codepath;
+pub mod collection;
 pub mod compact;
 pub mod compress;
 pub mod crash;
+pub mod csvimport;
 pub mod config;
+pub mod correct;
+pub mod costcap;
 pub mod context;
 pub mod datalog;
+pub mod decision;
 pub mod depgraph;
 pub mod delete;
 pub mod digest;
 pub mod edit;
+pub mod editor;
+pub mod embed;
+pub mod entryfmt;
+pub mod error;
+pub mod explain;
+pub mod expand;
 pub mod export;
+pub mod filter;
 pub mod format;
 pub mod fxhash;
+pub mod git;
+pub mod glossary;
+pub mod history;
 pub mod hook;
+pub mod ignore;
+pub mod impact;
+pub mod importance;
+pub mod inbox;
+pub mod include;
+pub mod indexview;
 pub mod install;
 pub mod intern;
 pub mod inverted;
 pub mod json;
+pub mod kb;
+pub mod lang;
 pub mod lock;
+pub mod logview;
+pub mod pattern;
 pub mod perf;
+pub mod phrase;
 pub mod mcp;
 pub mod migrate;
+pub mod noise;
+pub mod onboard;
+pub mod order;
+pub mod progress;
+pub mod provenance;
 pub mod prune;
 pub mod reconstruct;
+pub mod repl;
+pub mod review;
 pub mod reverse;
 pub mod score;
 pub mod search;
+pub mod selftest;
 pub mod session;
+pub mod settings;
+pub mod share;
+pub mod snapshot;
 pub mod sock;
+pub mod spell;
 pub mod stats;
 pub mod store;
+pub mod storehook;
+pub mod summarize;
+pub mod tail;
 pub mod text;
+pub mod theme;
 pub mod time;
+pub mod timeline;
+pub mod tokplugin;
 pub mod topics;
+pub mod topicuid;
+pub mod tracegraph;
+pub mod vocab;
+pub mod warm;
+pub mod webhook;
 pub mod xref;
 
 // --- C FFI: direct in-process query, no MCP overhead ---
@@ -55,30 +110,66 @@ pub struct AmrIndex {
     data: Vec<u8>,
     path: String,
     mtime: SystemTime,
+    generation: u64,
     state: cffi::QueryState,
 }
 
 /// C-compatible result from zero-alloc search.
 pub use cffi::RawResult as AmrResult;
 
-/// Open an index file, load into memory. Returns null on failure.
+/// C-compatible index statistics filled by amr_info_struct.
+pub use cffi::IndexInfo as AmrInfo;
+
+thread_local! {
+    static LAST_ERR: std::cell::Cell<i32> = const { std::cell::Cell::new(0) };
+}
+
+/// Record a failure for amr_last_error. Returns the code for direct use.
+fn set_err(e: &error::AmrError) -> i32 {
+    let code = e.code();
+    LAST_ERR.with(|c| c.set(code));
+    code
+}
+
+/// Read + validate an index file. Header check so a corrupt file fails at open, not per query.
+fn load_index(path: &str) -> Result<(Vec<u8>, SystemTime), error::AmrError> {
+    let data = std::fs::read(path).map_err(|e| error::AmrError::io(path, e))?;
+    binquery::read_header(&data)?;
+    let mtime = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    Ok((data, mtime))
+}
+
+/// index.gen generation of the KB directory holding index.bin.
+fn index_generation(path: &str) -> u64 {
+    std::path::Path::new(path).parent().map_or(0, indexview::generation)
+}
+
+/// Error code of the last failed call on this thread: 0 = none, else negative
+/// AMR_E_* (not found, corrupt, locked, invalid arg, io). Not cleared on success.
+#[no_mangle]
+pub extern "C" fn amr_last_error() -> i32 {
+    LAST_ERR.with(|c| c.get())
+}
+
+/// Open an index file, load into memory. Returns null on failure (see amr_last_error).
 #[no_mangle]
 pub extern "C" fn amr_open(path: *const c_char) -> *mut AmrIndex {
-    if path.is_null() { return std::ptr::null_mut(); }
+    let bad_arg = || error::AmrError::InvalidArg("path must be non-null UTF-8".into());
+    if path.is_null() { set_err(&bad_arg()); return std::ptr::null_mut(); }
     let path_str = match unsafe { CStr::from_ptr(path) }.to_str() {
         Ok(s) => s,
-        Err(_) => return std::ptr::null_mut(),
+        Err(_) => { set_err(&bad_arg()); return std::ptr::null_mut(); }
     };
-    let data = match std::fs::read(path_str) {
+    let (data, mtime) = match load_index(path_str) {
         Ok(d) => d,
-        Err(_) => return std::ptr::null_mut(),
+        Err(e) => { set_err(&e); return std::ptr::null_mut(); }
     };
-    let mtime = std::fs::metadata(path_str)
-        .and_then(|m| m.modified())
-        .unwrap_or(SystemTime::UNIX_EPOCH);
     let num_entries = binquery::entry_count(&data).unwrap_or(0);
     let state = cffi::QueryState::new(num_entries);
-    Box::into_raw(Box::new(AmrIndex { data, path: path_str.into(), mtime, state }))
+    let generation = index_generation(path_str);
+    Box::into_raw(Box::new(AmrIndex { data, path: path_str.into(), mtime, generation, state }))
 }
 
 /// Search the index. Caller must free result with amr_free_str.
@@ -92,7 +183,7 @@ pub extern "C" fn amr_search(idx: *const AmrIndex, query: *const c_char, limit:
     };
     let result = match binquery::search(&h.data, q, limit as usize) {
         Ok(r) => r,
-        Err(e) => format!("error: {e}"),
+        Err(e) => { set_err(&e); format!("error: {e}") }
     };
     CString::new(result).map(|c| c.into_raw()).unwrap_or(std::ptr::null_mut())
 }
@@ -104,11 +195,28 @@ pub extern "C" fn amr_info(idx: *const AmrIndex) -> *mut c_char {
     let h = unsafe { &*idx };
     let result = match binquery::index_info(&h.data) {
         Ok(r) => r,
-        Err(e) => format!("error: {e}"),
+        Err(e) => { set_err(&e); format!("error: {e}") }
     };
     CString::new(result).map(|c| c.into_raw()).unwrap_or(std::ptr::null_mut())
 }
 
+/// Fill `out` with index statistics. Returns 0=success, negative AMR_E_* on failure.
+///
+/// # Safety
+/// `idx` is null or a live handle from amr_open; `out` is null or points to
+/// writable memory for one AmrInfo.
+#[no_mangle]
+pub unsafe extern "C" fn amr_info_struct(idx: *const AmrIndex, out: *mut AmrInfo) -> i32 {
+    if idx.is_null() || out.is_null() {
+        return set_err(&error::AmrError::InvalidArg("null index handle or out pointer".into()));
+    }
+    let h = unsafe { &*idx };
+    match cffi::info(&h.data, h.generation) {
+        Ok(info) => { unsafe { *out = info; } 0 }
+        Err(e) => set_err(&e),
+    }
+}
+
 /// Check if index file changed. Returns 1=stale, 0=fresh, -1=error.
 #[no_mangle]
 pub extern "C" fn amr_is_stale(idx: *const AmrIndex) -> i32 {
@@ -120,26 +228,50 @@ pub extern "C" fn amr_is_stale(idx: *const AmrIndex) -> i32 {
     }
 }
 
-/// Reload index from disk. Returns 0=success, -1=failure.
+/// Reload index from disk. Returns 0=success, negative AMR_E_* on failure.
+/// On failure the previously loaded data stays in place.
 #[no_mangle]
 pub extern "C" fn amr_reload(idx: *mut AmrIndex) -> i32 {
-    if idx.is_null() { return -1; }
+    if idx.is_null() { return set_err(&error::AmrError::InvalidArg("null index handle".into())); }
     let h = unsafe { &mut *idx };
-    match std::fs::read(&h.path) {
-        Ok(data) => {
-            h.mtime = std::fs::metadata(&h.path)
-                .and_then(|m| m.modified())
-                .unwrap_or(SystemTime::UNIX_EPOCH);
+    match load_index(&h.path) {
+        Ok((data, mtime)) => {
+            h.mtime = mtime;
+            h.generation = index_generation(&h.path);
             let n = binquery::entry_count(&data).unwrap_or(0);
             h.state = cffi::QueryState::new(n);
             h.data = data;
             0
         }
-        Err(_) => -1,
+        Err(e) => set_err(&e),
+    }
+}
+
+/// Autocomplete: up to `limit` indexed terms starting with `prefix`, most common
+/// first, as `term\tdf` lines (df = entries containing the term), from the
+/// handle's index data. Caller must free with amr_free_str; null on failure.
+///
+/// # Safety
+/// `idx` is null or a live handle from amr_open; `prefix` is null or a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn amr_terms(idx: *const AmrIndex, prefix: *const c_char, limit: u32) -> *mut c_char {
+    if idx.is_null() || prefix.is_null() {
+        set_err(&error::AmrError::InvalidArg("null index handle or prefix".into()));
+        return std::ptr::null_mut();
+    }
+    let h = unsafe { &*idx };
+    let Ok(p) = unsafe { CStr::from_ptr(prefix) }.to_str() else {
+        set_err(&error::AmrError::InvalidArg("prefix must be UTF-8".into()));
+        return std::ptr::null_mut();
+    };
+    match vocab::tsv(&h.data, p, limit as usize) {
+        Ok(r) => CString::new(r).map(|c| c.into_raw()).unwrap_or(std::ptr::null_mut()),
+        Err(e) => { set_err(&e); std::ptr::null_mut() }
     }
 }
 
-/// Free a string returned by amr_search or amr_info.
+/// Free a string returned by amr_search, amr_info or amr_terms.
 #[no_mangle]
 pub extern "C" fn amr_free_str(s: *mut c_char) {
     if !s.is_null() { unsafe { drop(CString::from_raw(s)); } }
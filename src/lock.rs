@@ -1,6 +1,7 @@
 use std::fs::{File, OpenOptions};
 use std::os::unix::io::AsRawFd;
 use std::path::Path;
+use crate::error::AmrError;
 
 extern "C" {
     fn flock(fd: i32, operation: i32) -> i32;
@@ -15,16 +16,21 @@ pub struct FileLock {
 }
 
 impl FileLock {
-    pub fn acquire(dir: &Path) -> Result<Self, String> {
-        let lockpath = dir.join(".lock");
+    pub fn acquire(dir: &Path) -> Result<Self, AmrError> {
+        Self::acquire_named(dir, ".lock")
+    }
+
+    /// Exclusive lock on `dir/name`, for work that must not wait on data writers.
+    pub fn acquire_named(dir: &Path, name: &str) -> Result<Self, AmrError> {
+        let lockpath = dir.join(name);
         let file = OpenOptions::new()
             .create(true)
             .write(true)
             .open(&lockpath)
-            .map_err(|e| format!("lock: {e}"))?;
+            .map_err(|e| AmrError::io("lock", e))?;
         let ret = unsafe { flock(file.as_raw_fd(), LOCK_EX) };
         if ret != 0 {
-            return Err("failed to acquire lock".into());
+            return Err(AmrError::Locked("failed to acquire lock".into()));
         }
         Ok(FileLock { _file: file })
     }
@@ -0,0 +1,68 @@
+//! Shared read-only mmap of data.log for zero-copy entry hydration.
+//! One stat(2) per query instead of open + seek + read + two heap buffers.
+//! Cached per dir, remapped only when data.log (len, mtime) changes. The log is
+//! append-only and compaction renames a new file in, so an older mapping stays
+//! valid while held.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use crate::error::AmrError;
+
+extern "C" {
+    fn mmap(addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, off: i64) -> *mut u8;
+    fn munmap(addr: *mut u8, len: usize) -> i32;
+}
+
+/// A live mapping of data.log. Unmapped when the last Arc drops.
+pub struct LogMap {
+    ptr: *mut u8,
+    len: usize,
+    mtime: SystemTime,
+}
+
+// Safety: the mapping is PROT_READ and never mutated through `ptr`.
+unsafe impl Send for LogMap {}
+unsafe impl Sync for LogMap {}
+
+impl LogMap {
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for LogMap {
+    fn drop(&mut self) {
+        unsafe { munmap(self.ptr, self.len); }
+    }
+}
+
+/// One mapping per KB dir, so a process serving several never hands out
+/// another dir's log.
+static VIEWS: Mutex<Vec<(PathBuf, Arc<LogMap>)>> = Mutex::new(Vec::new());
+
+/// Current mapping of dir/data.log, remapping if the file grew or was replaced.
+pub fn view(dir: &Path) -> Result<Arc<LogMap>, AmrError> {
+    let log_path = crate::config::log_path(dir);
+    let meta = std::fs::metadata(&log_path).map_err(|e| AmrError::io("stat data.log", e))?;
+    let len = meta.len() as usize;
+    let mtime = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let mut views = VIEWS.lock().map_err(|e| AmrError::Locked(e.to_string()))?;
+    if let Some((_, m)) = views.iter().find(|(d, m)| d == dir && m.len == len && m.mtime == mtime) {
+        return Ok(Arc::clone(m));
+    }
+    if len == 0 { return Err(AmrError::Corrupt("data.log is empty".into())); }
+
+    use std::os::unix::io::AsRawFd;
+    let f = std::fs::File::open(&log_path).map_err(|e| AmrError::io("open data.log", e))?;
+    let ptr = unsafe { mmap(std::ptr::null_mut(), len, 1 /* PROT_READ */, 2 /* MAP_PRIVATE */, f.as_raw_fd(), 0) };
+    drop(f); // mapping outlives the fd
+    if ptr.is_null() || ptr as usize == usize::MAX { return Err(AmrError::Io("mmap data.log failed".into())); }
+
+    let map = Arc::new(LogMap { ptr, len, mtime });
+    views.retain(|(d, _)| d != dir);
+    views.push((dir.to_path_buf(), Arc::clone(&map)));
+    Ok(map)
+}
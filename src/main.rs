@@ -1,6 +1,8 @@
 use amaranthine::{codepath, config, search, store, context, delete, edit,
     topics, prune, digest, stats, compact, export, xref, migrate, mcp,
-    hook, install, time, json};
+    hook, install, time, json, warm, address, pattern, review, order, collection, share, repl, editor, answer, glossary, timeline, correct, reconstruct, decision, tail, csvimport, history, settings, vocab, inbox, onboard, embed, selftest, snapshot};
+use amaranthine::error::AmrError;
+use amaranthine::provenance::Provenance;
 use std::env;
 
 fn main() {
@@ -8,6 +10,7 @@ fn main() {
 
     let mut dir_override: Option<String> = None;
     let mut plain = false;
+    let mut theme: Option<String> = None;
     let mut cmd_start = 0;
     let mut i = 0;
 
@@ -25,6 +28,10 @@ fn main() {
             plain = true;
             i += 1;
             cmd_start = i;
+        } else if a == "--theme" {
+            theme = args.get(i + 1).cloned();
+            i += 2;
+            cmd_start = i;
         } else if a == "-h" || a == "--help" {
             print_help();
             return;
@@ -37,10 +44,22 @@ fn main() {
         dir_override = env::var("AMARANTHINE_DIR").ok();
     }
 
+    let theme = match theme.as_deref().map(amaranthine::theme::Theme::parse) {
+        Some(Some(t)) => t,
+        Some(None) => {
+            let e = AmrError::InvalidArg("--theme: expected none, 16 or 256".into());
+            eprintln!("error: {e}");
+            std::process::exit(e.exit_code());
+        }
+        None => amaranthine::theme::Theme::from_env(),
+    };
+    amaranthine::theme::set(theme);
+
     let dir = config::resolve_dir(dir_override);
+    amaranthine::settings::apply(&dir);
     let cmd = &args[cmd_start..];
 
-    let result: Result<String, String> = match cmd.first().map(|s| s.as_str()) {
+    let result: Result<String, AmrError> = match cmd.first().map(|s| s.as_str()) {
         Some("store") if cmd.len() >= 3 => {
             let tags = parse_flag_str(cmd, "--tags");
             let force = cmd.iter().any(|a| a == "--force" || a == "-f");
@@ -53,13 +72,23 @@ fn main() {
                 })
                 .map(|s| s.as_str()).collect();
             let text = text_parts.join(" ");
-            store::run_full(&dir, &cmd[1], &text, tags.as_deref(), force, None)
+            store::run_full(&dir, &cmd[1], &text, tags.as_deref(), force, None, Some(Provenance::Human)).map_err(Into::into)
+        }
+        Some("store") if cmd.len() == 2 => {
+            store::run_full(&dir, &cmd[1], "-", None, false, None, Some(Provenance::Human)).map_err(Into::into)
         }
-        Some("store") if cmd.len() == 2 => store::run(&dir, &cmd[1], "-"),
         Some("store") => Err("usage: store <topic> <text|-> [--tags t1,t2]".into()),
-        Some("append") if cmd.len() >= 3 => store::append(&dir, &cmd[1], &cmd[2..].join(" ")),
-        Some("append") if cmd.len() == 2 => store::append(&dir, &cmd[1], "-"),
+        Some("append") if cmd.len() >= 3 => store::append(&dir, &cmd[1], &cmd[2..].join(" ")).map_err(Into::into),
+        Some("append") if cmd.len() == 2 => store::append(&dir, &cmd[1], "-").map_err(Into::into),
         Some("append") => Err("usage: append <topic> <text|-> (adds to last entry)".into()),
+        Some("file-inbox") => {
+            let topic = parse_flag_str(cmd, "--topic");
+            inbox::file(&dir, &inbox::Filing {
+                apply: cmd.iter().any(|a| a == "--apply"),
+                index: parse_flag_value(cmd, "--index"),
+                topic: topic.as_deref(),
+            })
+        }
         Some("search") if cmd.len() >= 2 => {
             let brief = cmd.iter().any(|a| a == "--brief" || a == "-b");
             let count_only = cmd.iter().any(|a| a == "--count" || a == "-c");
@@ -68,47 +97,136 @@ fn main() {
             let after = parse_flag_str(cmd, "--after").and_then(|s| time::parse_date_days(&s));
             let before = parse_flag_str(cmd, "--before").and_then(|s| time::parse_date_days(&s));
             let tag = parse_flag_str(cmd, "--tag");
+            let topic = parse_flag_str(cmd, "--topic");
+            let bad_topic = topic.as_deref().filter(|t| pattern::is_pattern(t))
+                .and_then(|t| pattern::TopicPattern::parse(t).err());
             let or_mode = cmd.iter().any(|a| a == "--or");
+            let history = cmd.iter().any(|a| a == "--history");
             let mode = if or_mode { search::SearchMode::Or } else { search::SearchMode::And };
-            let filter = search::Filter { after, before, tag, topic: None, mode };
+            let provenance = match parse_flag_str(cmd, "--provenance") {
+                None => Ok(None),
+                Some(p) => Provenance::parse(&p).map(Some).ok_or_else(|| AmrError::InvalidArg(format!(
+                    "--provenance: expected agent, human, imported or trace, got '{p}'"))),
+            };
+            let lang = match parse_flag_str(cmd, "--lang") {
+                None => Ok(None),
+                Some(l) => amaranthine::lang::Lang::parse(&l).map(Some).ok_or_else(|| AmrError::InvalidArg(format!(
+                    "--lang: expected {}, got '{l}'", amaranthine::lang::names()))),
+            };
+            let min_match = match parse_flag_str(cmd, "--min-match") {
+                None => Ok(search::MinMatch::All),
+                Some(m) => search::MinMatch::parse(&m).ok_or_else(|| AmrError::InvalidArg(format!(
+                    "--min-match: expected a term count, a percentage (75%) or 'all', got '{m}'"))),
+            };
+            let filter = search::Filter { after, before, tag, topic, mode,
+                provenance: provenance.as_ref().ok().copied().flatten(),
+                lang: lang.as_ref().ok().copied().flatten(),
+                min_match: min_match.as_ref().copied().unwrap_or(search::MinMatch::All),
+                boost_topics: parse_flag_str(cmd, "--boost").map(|b| b.split(',').map(|t| t.trim().to_string())
+                    .filter(|t| !t.is_empty()).collect()).unwrap_or_default(),
+                hybrid: cmd.iter().any(|a| a == "--hybrid") || settings::load(&dir).flag("hybrid_search") == Some(true) };
             let skip = ["--brief", "-b", "--count", "-c", "--topics", "-t",
-                        "--limit", "--after", "--before", "--tag", "--or"];
+                        "--limit", "--after", "--before", "--tag", "--topic", "--min-match", "--or",
+                        "--provenance", "--lang", "--boost", "--history", "--hybrid", "--dirs"];
             let query_parts: Vec<&str> = cmd[1..].iter()
                 .filter(|a| !skip.contains(&a.as_str()))
                 .filter(|a| {
                     let prev = cmd.iter().position(|x| x == *a);
                     prev.map_or(true, |i| {
-                        i == 0 || !["--limit", "--after", "--before", "--tag"].contains(&cmd[i - 1].as_str())
+                        i == 0 || !["--limit", "--after", "--before", "--tag", "--topic", "--min-match",
+                            "--provenance", "--lang", "--boost", "--dirs"].contains(&cmd[i - 1].as_str())
                     })
                 })
                 .map(|s| s.as_str()).collect();
             let q = query_parts.join(" ");
-            if count_only {
-                search::count(&dir, &q, &filter)
+            if let Some(e) = bad_topic.or(min_match.err()).or(provenance.err()).or(lang.err()) {
+                Err(e)
+            } else if let Some(dirs) = parse_flag_str(cmd, "--dirs") {
+                config::resolve_dirs(&dirs).and_then(|dirs| search::run_federated(&dirs, &q, limit, &filter))
+                    .map_err(Into::into)
+            } else if count_only {
+                search::count(&dir, &q, &filter).map_err(Into::into)
             } else if topics_only {
-                search::run_topics(&dir, &q, &filter)
-            } else if brief {
-                search::run_brief(&dir, &q, limit, &filter, None)
+                search::run_topics(&dir, &q, &filter).map_err(Into::into)
             } else {
-                search::run(&dir, &q, plain, limit, &filter, None)
+                let result = if brief { search::run_brief(&dir, &q, limit, &filter, None) }
+                    else { search::run(&dir, &q, plain, limit, &filter, None) };
+                match result {
+                    Ok(r) if history => history::search(&dir, &q, or_mode, limit.unwrap_or(history::DEFAULT_LIMIT))
+                        .map(|h| format!("{r}{h}")),
+                    r => r,
+                }
+            }
+        }
+        Some("search") => Err("usage: search <query> [--brief|--count|--topics] [--limit N] [--after DATE] [--before DATE] [--tag TAG] [--topic PAT] [--min-match N|P%] [--provenance WHO] [--lang LANG] [--boost TOPICS] [--history] [--hybrid] [--dirs A,B]".into()),
+        Some("answer") if cmd.len() >= 2 => {
+            let limit = parse_flag_value(cmd, "--limit").unwrap_or(5);
+            let mut q: Vec<&str> = Vec::new();
+            let mut args = cmd[1..].iter();
+            while let Some(a) = args.next() {
+                if a == "--limit" { args.next(); } else { q.push(a); }
+            }
+            answer::run(&dir, &q.join(" "), limit, &search::Filter::none(), None)
+        }
+        Some("answer") => Err("usage: answer <question> [--limit N]".into()),
+        Some("semantic") if cmd.len() >= 2 => {
+            let limit = parse_flag_value(cmd, "--limit").unwrap_or(embed::DEFAULT_LIMIT);
+            let weight = parse_flag_value::<f64>(cmd, "--weight");
+            let mut q: Vec<&str> = Vec::new();
+            let mut args = cmd[1..].iter();
+            while let Some(a) = args.next() {
+                if a == "--limit" || a == "--weight" { args.next(); } else { q.push(a); }
             }
+            embed::run(&dir, &q.join(" "), limit, weight, &search::Filter::none())
+        }
+        Some("semantic") => Err("usage: semantic <query> [--limit N] [--weight 0-1]".into()),
+        Some("define") if cmd.len() >= 2 => glossary::define(&dir, &cmd[1..].join(" ")),
+        Some("define") => Err("usage: define <term>".into()),
+        Some("terms") => {
+            let limit = parse_flag_value(cmd, "--limit").unwrap_or(vocab::DEFAULT_LIMIT);
+            let prefix = cmd.get(1).filter(|p| !p.starts_with("--")).map_or("", |p| p.as_str());
+            vocab::run(&dir, None, prefix, limit)
+        }
+        Some("timeline") if cmd.len() >= 2 => timeline::run(&dir, &cmd[1..].join(" ")),
+        Some("timeline") => Err("usage: timeline <query>".into()),
+        Some(c @ ("tail" | "watch")) => {
+            // watch: JSON Lines from the current end, for tooling
+            let watch = c == "watch";
+            let n: usize = parse_flag_value(cmd, "-n").unwrap_or(if watch { 0 } else { 5 });
+            let json = watch || cmd.iter().any(|a| a == "--json");
+            let topic = parse_flag_str(cmd, "--topic").map(|t| pattern::TopicPattern::parse(&t)).transpose();
+            topic.and_then(|topic| tail::follow(&tail::Watch { topic, tag: parse_flag_str(cmd, "--tag"), json, dir: &dir }, n))
+                .map(|()| String::new())
         }
-        Some("search") => Err("usage: search <query> [--brief|--count|--topics] [--limit N] [--after DATE] [--before DATE] [--tag TAG]".into()),
+        Some("correct") if cmd.len() >= 3 && address::is_address(&cmd[1]) => {
+            entry_at(&dir, &cmd[1]).and_then(|(topic, idx)|
+                correct::run(&dir, &topic, idx, &cmd[2..].join(" "), Some(Provenance::Human)))
+        }
+        Some("correct") => Err("usage: correct <address> <text>".into()),
         Some("context") => {
             let brief = cmd.iter().any(|a| a == "--brief" || a == "-b");
+            let diff = cmd.iter().any(|a| a == "--diff");
             let query_parts: Vec<&str> = cmd[1..].iter()
-                .filter(|a| *a != "--brief" && *a != "-b")
+                .filter(|a| *a != "--brief" && *a != "-b" && *a != "--diff")
                 .map(|s| s.as_str()).collect();
             let q = if query_parts.is_empty() { None } else { Some(query_parts.join(" ")) };
-            context::run_inner_pub(&dir, q.as_deref(), plain, brief)
+            match q {
+                Some(q) if diff => reconstruct::run_diff(&dir, &q, None, None).map_err(Into::into),
+                None if diff => Err("usage: context <query> --diff".into()),
+                q => context::run_inner_pub(&dir, q.as_deref(), plain, brief),
+            }
+        }
+        Some("delete") if cmd.len() >= 2 && address::is_address(&cmd[1]) => {
+            entry_at(&dir, &cmd[1]).and_then(|(topic, idx)| delete::run_by_index(&dir, &topic, idx))
         }
         Some("delete") if cmd.len() >= 2 => {
             let last = cmd.iter().any(|a| a == "--last");
             let all = cmd.iter().any(|a| a == "--all");
             let match_str = parse_flag_str(cmd, "--match");
-            delete::run(&dir, &cmd[1], last, all, match_str.as_deref())
+            let confirm = cmd.iter().any(|a| a == "--confirm");
+            delete::run_pattern(&dir, &cmd[1], last, all, match_str.as_deref(), confirm)
         }
-        Some("delete") => Err("usage: delete <topic> [--last|--all|--match <str>]".into()),
+        Some("delete") => Err("usage: delete <topic|pattern> [--last|--all|--match <str>] [--confirm] | delete <topic:N|topic:uid:HEX>".into()),
         Some("edit") if cmd.len() >= 4 => {
             let match_str = parse_flag_str(cmd, "--match");
             match match_str {
@@ -128,61 +246,170 @@ fn main() {
             }
         }
         Some("edit") => Err("usage: edit <topic> --match <substring> <new text>".into()),
+        Some("edit-topic") if cmd.len() >= 2 => editor::run(&dir, &cmd[1]),
+        Some("edit-topic") => Err("usage: edit-topic <topic>".into()),
         Some("index") => Err("index command removed in v4 (no .md files)".into()),
         Some("recent") => {
             let days = cmd.get(1).and_then(|s| s.parse().ok()).unwrap_or(7u64);
-            topics::recent(&dir, days, plain)
+            let group = match parse_flag_str(cmd, "--by").as_deref() {
+                None => Ok(None),
+                Some("day") => Ok(Some(topics::RecentGroup::Day)),
+                Some("session") => Ok(Some(topics::RecentGroup::Session)),
+                Some(g) => Err(AmrError::InvalidArg(format!("--by: expected day or session, got '{g}'"))),
+            };
+            let collapse = cmd.iter().any(|a| a == "--collapse");
+            let topic = parse_flag_str(cmd, "--topic");
+            group.and_then(|group| topics::recent_view(&dir, days, None,
+                &topics::RecentView { group, collapse, topic }, plain).map_err(Into::into))
         }
-        Some("topics") => topics::list(&dir),
+        Some("topics") => match parse_flag_str(cmd, "--changed-since") {
+            Some(since) => digest::changed_since(&dir, &since).map_err(Into::into),
+            None => topics::list(&dir).map_err(Into::into),
+        },
         Some("prune") => {
             let stale = parse_flag_value(cmd, "--stale").unwrap_or(30u64);
-            prune::run(&dir, stale, plain)
+            prune::run(&dir, stale, plain).map_err(Into::into)
+        }
+        Some("digest") => match parse_flag_str(cmd, "--since") {
+            Some(since) => digest::since(&dir, &since),
+            None => digest::run(&dir),
+        }.map_err(Into::into),
+        Some("stats") if cmd.iter().any(|a| a == "--heatmap") => {
+            let weeks = parse_flag_value(cmd, "--weeks").unwrap_or(12usize);
+            let topic = parse_flag_str(cmd, "--topic");
+            stats::heatmap(&dir, weeks, topic.as_deref(), cmd.iter().any(|a| a == "--json"))
+        }
+        Some("stats") => stats::stats(&dir).map_err(Into::into),
+        Some("warm") => {
+            let corpus = cmd.iter().any(|a| a == "--corpus");
+            warm::run(&dir, corpus)
+        }
+        Some("tags") => stats::list_tags(&dir).map_err(Into::into),
+        Some("entries") if cmd.len() >= 2 && address::is_address(&cmd[1]) => {
+            let context = parse_flag_value(cmd, "--context").unwrap_or(2);
+            entry_at(&dir, &cmd[1]).and_then(|(topic, idx)| match parse_flag_str(cmd, "--match") {
+                Some(m) => stats::get_entry_lines(&dir, &topic, idx, &m, context),
+                None => stats::get_entry(&dir, &topic, idx),
+            })
         }
-        Some("digest") => digest::run(&dir),
-        Some("stats") => stats::stats(&dir),
-        Some("tags") => stats::list_tags(&dir),
         Some("entries") if cmd.len() >= 2 => {
             let match_str = parse_flag_str(cmd, "--match");
             stats::list_entries(&dir, &cmd[1], match_str.as_deref())
         }
-        Some("entries") => Err("usage: entries <topic> [--match <str>]".into()),
+        Some("entries") => Err("usage: entries <topic|pattern> [--match <str>] | entries <topic:N|topic:uid:HEX> [--match <str> [--context N]]".into()),
+        Some("share") if cmd.len() >= 2 && address::is_address(&cmd[1]) => {
+            let links = cmd.iter().any(|a| a == "--links");
+            entry_at(&dir, &cmd[1]).and_then(|(topic, idx)| share::run(&dir, &topic, idx, links))
+        }
+        Some("share") => Err("usage: share <topic:N|topic:uid:HEX> [--links]".into()),
         Some("compact") if cmd.len() >= 2 => {
             let apply = cmd.iter().any(|a| a == "--apply");
             compact::run(&dir, &cmd[1], apply)
         }
-        Some("compact") => compact::scan(&dir),
-        Some("export") => export::export(&dir),
+        Some("compact") => compact::scan(&dir).map_err(Into::into),
+        Some("export") => {
+            let scope = export::Scope {
+                topic: parse_flag_str(cmd, "--topic"),
+                query: parse_flag_str(cmd, "--query"),
+                tag: parse_flag_str(cmd, "--tag"),
+            };
+            if cmd.iter().any(|a| a == "--rag") {
+                let d = export::Chunking::default();
+                let c = export::Chunking {
+                    max_tokens: parse_flag_value(cmd, "--chunk-tokens").unwrap_or(d.max_tokens),
+                    overlap: parse_flag_value(cmd, "--overlap").unwrap_or(d.overlap),
+                };
+                export::export_rag(&dir, &scope, &c).map_err(Into::into)
+            } else {
+                export::export_scoped(&dir, &scope).map_err(Into::into)
+            }
+        }
+        Some("import") if cmd.len() >= 3 && cmd[1] == "--csv" => {
+            match (parse_flag_str(cmd, "--map"), std::fs::read_to_string(&cmd[2])) {
+                (None, _) => Err("import --csv needs --map field=column,...".into()),
+                (Some(map), Ok(csv)) => csvimport::import(&dir, &csv, &map, parse_flag_str(cmd, "--topic").as_deref()),
+                (_, Err(e)) => Err(AmrError::io(&cmd[2], e)),
+            }
+        }
         Some("import") if cmd.len() >= 2 => {
             match std::fs::read_to_string(&cmd[1]) {
-                Ok(json) => export::import(&dir, &json),
-                Err(e) => Err(e.to_string()),
+                Ok(json) => export::import(&dir, &json).map_err(Into::into),
+                Err(e) => Err(AmrError::io(&cmd[1], e)),
             }
         }
         Some("import") => Err("usage: import <file>".into()),
+        Some("collection") if cmd.len() <= 1 || cmd[1] == "list" => collection::list(&dir),
+        Some("collection") if cmd.len() == 3 && cmd[1] == "show" => collection::show(&dir, &cmd[2]),
+        Some("collection") if cmd.len() >= 4 && cmd[1] == "add" => collection::add(&dir, &cmd[2], &cmd[3..].join(" ")),
+        Some("collection") if cmd.len() >= 4 && cmd[1] == "remove" => collection::remove(&dir, &cmd[2], &cmd[3..].join(" ")),
+        Some("collection") => Err("usage: collection [list] | collection show <name> | collection add|remove <name> <address...>".into()),
+        Some("reorder") if cmd.len() >= 3 && cmd[2] == "--clear" => order::reorder(&dir, &cmd[1], ""),
+        Some("reorder") if cmd.len() >= 3 => order::reorder(&dir, &cmd[1], &cmd[2..].join(" ")),
+        Some("reorder") => Err("usage: reorder <topic> <index|address ...> | reorder <topic> --clear".into()),
+        Some("decide") if cmd.len() >= 3 => {
+            let flags = ["--status", "--alt", "--supersedes"];
+            let text: Vec<&str> = cmd[2..].iter().enumerate()
+                .filter(|(i, a)| !flags.contains(&a.as_str()) && (*i == 0 || !flags.contains(&cmd[i + 1].as_str())))
+                .map(|(_, a)| a.as_str()).collect();
+            let status = match parse_flag_str(cmd, "--status") {
+                None => Ok(decision::Status::Accepted),
+                Some(s) => decision::Status::parse(&s).ok_or_else(|| AmrError::InvalidArg(format!(
+                    "--status: expected proposed or accepted, got '{s}'"))),
+            };
+            let alt = parse_flag_str(cmd, "--alt").unwrap_or_default();
+            let supersedes = parse_flag_str(cmd, "--supersedes").map(|a| entry_at(&dir, &a)).transpose();
+            status.and_then(|status| supersedes.map(|s| (status, s))).and_then(|(status, supersedes)| {
+                decision::record(&dir, &cmd[1], &text.join(" "), &decision::Record {
+                    status, alternatives: alt.split('|').collect(), links: None,
+                    supersedes: supersedes.as_ref().map(|(t, i)| (t.as_str(), *i)),
+                    provenance: Some(Provenance::Human),
+                })
+            })
+        }
+        Some("decide") => Err("usage: decide <topic> <text> [--status proposed|accepted] [--alt \"A | B\"] [--supersedes ADDR]".into()),
+        Some("decisions") if cmd.len() >= 4 && cmd[1] == "set" => {
+            match decision::Status::parse(&cmd[3]) {
+                Some(status) => entry_at(&dir, &cmd[2]).and_then(|(topic, idx)| decision::set_status(&dir, &topic, idx, status)),
+                None => Err(AmrError::InvalidArg(format!("expected proposed, accepted or superseded, got '{}'", cmd[3]))),
+            }
+        }
+        Some("decisions") if cmd.len() <= 2 && cmd.get(1).is_none_or(|s| decision::Status::parse(s).is_some()) =>
+            decision::list(&dir, cmd.get(1).and_then(|s| decision::Status::parse(s))),
+        Some("decisions") => Err("usage: decisions [proposed|accepted|superseded] | decisions set <address> <status>".into()),
+        Some("review") if cmd.len() <= 1 || cmd[1] == "list" => review::list(&dir),
+        Some("review") if cmd.len() >= 3 && (cmd[1] == "approve" || cmd[1] == "reject") => {
+            entry_at(&dir, &cmd[2]).and_then(|(topic, idx)| {
+                if cmd[1] == "approve" { review::approve(&dir, &topic, idx) } else { review::reject(&dir, &topic, idx) }
+            })
+        }
+        Some("review") => Err("usage: review [list] | review approve|reject <topic:N|topic:uid:HEX>".into()),
         Some("xref") if cmd.len() >= 2 => xref::refs_for(&dir, &cmd[1]),
         Some("xref") => Err("usage: xref <topic>".into()),
         Some("codepath") if cmd.len() >= 3 => {
             let glob = parse_flag_str(cmd, "--glob").unwrap_or_else(|| "*.rs".into());
             let ctx: usize = parse_flag_value(cmd, "--context").unwrap_or(2);
             let store_topic = parse_flag_str(cmd, "--store");
-            match codepath::run(&cmd[1], std::path::Path::new(&cmd[2]), &glob, ctx) {
-                Ok(result) => {
+            let run = codepath::Roots::parse(&cmd[2], amaranthine::ignore::Ignore::load(&dir))
+                .and_then(|roots| Ok((codepath::run(&cmd[1], &roots, &glob, ctx)?, roots)));
+            match run {
+                Ok((result, roots)) => {
                     if let Some(ref topic) = store_topic {
-                        let source = format!("{}/**/{}", &cmd[2], glob);
-                        if let Err(e) = store::run_full(&dir, topic, &result, Some("structural,coupling"), true, Some(&source)) {
+                        let source = roots.source_glob(&glob);
+                        if let Err(e) = store::run_full(&dir, topic, &result, Some("structural,coupling"), true, Some(&source),
+                            Some(Provenance::Trace)) {
                             return eprintln!("stored codepath but store failed: {e}");
                         }
                         eprintln!("stored under topic: {topic}");
                     }
                     Ok(result)
                 }
-                Err(e) => Err(e),
+                Err(e) => Err(e.into()),
             }
         }
-        Some("codepath") => Err("usage: codepath <pattern> <path> [--glob *.rs] [--context 2] [--store <topic>]".into()),
+        Some("codepath") => Err("usage: codepath <pattern> <path[,path...]> [--glob *.rs] [--context 2] [--store <topic>]".into()),
         Some("migrate") => {
             let apply = cmd.iter().any(|a| a == "--apply");
-            migrate::run(&dir, apply)
+            migrate::run(&dir, apply).map_err(Into::into)
         }
         Some("call") if cmd.len() >= 2 => {
             let tool = &cmd[1];
@@ -195,26 +422,42 @@ fn main() {
                     .collect();
                 Some(json::Value::Obj(pairs))
             } else { None };
-            mcp::dispatch(tool, args.as_ref(), &dir)
+            mcp::dispatch(tool, args.as_ref(), &dir).map_err(Into::into)
         }
         Some("call") => Err("usage: call <tool> [key=value ...]".into()),
+        Some("repl") => repl::run(&dir, plain),
         Some("serve") => {
             let d = if cmd.len() >= 3 && (cmd[1] == "--dir" || cmd[1] == "-d") {
                 std::path::PathBuf::from(&cmd[2])
             } else { dir.clone() };
-            mcp::run(&d).map(|()| String::new())
+            mcp::run(&d).map(|()| String::new()).map_err(Into::into)
         }
-        Some("install") => install::run(&dir).map(|()| String::new()),
-        Some("init") => config::init(cmd.get(1).map(|s| s.as_str())).map(|()| String::new()),
-        Some("hook") if cmd.len() >= 2 => hook::run(&cmd[1], &dir),
-        Some("hook") => Err("usage: hook <ambient|post-build|stop|subagent-start>".into()),
+        Some("install") => install::run(&dir).map(|()| String::new()).map_err(Into::into),
+        Some("init") if cmd.iter().any(|a| a == "--onboard" || a == "--hooks") => {
+            let target = cmd.get(1).filter(|a| !a.starts_with("--")).map_or(dir.clone(), std::path::PathBuf::from);
+            onboard::run(&target, cmd.iter().any(|a| a == "--hooks")).map(|()| String::new()).map_err(Into::into)
+        }
+        Some("snapshot") => match cmd.get(1).map(|s| s.as_str()) {
+            Some("create") => snapshot::create(&dir, parse_flag_str(cmd, "--label").as_deref()),
+            Some("list") => snapshot::list(&dir),
+            Some("restore") if cmd.len() >= 3 => snapshot::restore(&dir, &cmd[2]),
+            _ => Err("usage: snapshot create [--label text] | list | restore <id|latest>".into()),
+        },
+        Some("selftest") => selftest::run(cmd.iter().any(|a| a == "--keep")).map_err(Into::into),
+        Some("init") => config::init(cmd.get(1).map(|s| s.as_str())).map(|()| String::new()).map_err(Into::into),
+        Some("hook") if cmd.len() >= 3 && cmd[1] == "test" => {
+            let input = parse_flag_str(&cmd, "--input");
+            hook::test(&cmd[2], &dir, input.as_deref(), cmd.iter().any(|a| a == "--keep-state")).map_err(Into::into)
+        }
+        Some("hook") if cmd.len() >= 2 && cmd[1] != "test" => hook::run(&cmd[1], &dir).map_err(Into::into),
+        Some("hook") => Err("usage: hook <ambient|post-build|stop|subagent-start>\n       hook test <type> [--input FILE] [--keep-state]".into()),
         Some("help") | None => { print_help(); Ok(String::new()) }
-        Some(c) => Err(format!("unknown command: {c}")),
+        Some(c) => Err(AmrError::InvalidArg(format!("unknown command: {c}"))),
     };
 
     match result {
         Ok(msg) => { if !msg.is_empty() { print!("{msg}"); } }
-        Err(e) => { eprintln!("error: {e}"); std::process::exit(1); }
+        Err(e) => { eprintln!("error: {e}"); std::process::exit(e.exit_code()); }
     }
 }
 
@@ -225,6 +468,13 @@ fn parse_flag_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Option
         .and_then(|s| s.parse().ok())
 }
 
+/// Entry address (`topic:3`, `topic:uid:ab12cd`) → (topic, index).
+fn entry_at(dir: &std::path::Path, raw: &str) -> Result<(String, usize), AmrError> {
+    let addr = address::parse(raw)?;
+    let idx = address::resolve(dir, &addr)?;
+    Ok((addr.topic.to_string(), idx))
+}
+
 fn parse_flag_str(args: &[String], flag: &str) -> Option<String> {
     args.iter()
         .position(|a| a == flag)
@@ -239,7 +489,16 @@ fn print_help() {
         "COMMANDS:\n",
         "  store <topic> <text|-> [--tags t1,t2]  Store entry with optional tags\n",
         "  append <topic> <text|->      Add to last entry (no new timestamp)\n",
+        "  file-inbox [--apply] [--index N [--topic T]]  Suggest or apply topics for inbox entries\n",
         "  search <query> [FLAGS]       Search entries\n",
+        "  answer <question> [--limit N]  Quote the sentences that answer it, with addresses\n",
+        "  semantic <query> [--limit N] [--weight W]  Embedding similarity blended with BM25 (embed_backend)\n",
+        "  define <term>                What a term means here; updates the glossary topic\n",
+        "  terms [prefix] [--limit N]   Corpus terms starting with prefix, by entry count\n",
+        "  timeline <query>             Matching entries by week, oldest first, with chains\n",
+        "  tail [-n N] [--topic PAT] [--tag T] [--json]  Print the last N entries, then follow new ones as they land\n",
+        "  watch [-n N] [--topic PAT] [--tag T]  Follow new entries as JSON Lines (tail --json, from the end)\n",
+        "  correct <addr> <text>        Store a correction, tag the wrong entry superseded\n",
         "    --brief, -b                Quick results (topic + first line)\n",
         "    --count, -c                Just count matches\n",
         "    --topics, -t               Which topics matched + hit count\n",
@@ -247,18 +506,46 @@ fn print_help() {
         "    --after YYYY-MM-DD         Entries on or after date\n",
         "    --before YYYY-MM-DD        Entries on or before date\n",
         "    --tag TAG                  Filter to entries with tag\n",
+        "    --topic PAT                Filter to a topic, glob (iris-*) or /regex/\n",
+        "    --min-match N|P%           AND needs only N (or P%) of the terms\n",
+        "    --provenance WHO           agent, human, imported or trace entries only\n",
+        "    --lang LANG                Entries in one language: en, de, fr, es or nl\n",
+        "    --history                  Also search superseded and deleted versions still in data.log\n",
+        "    --hybrid                   Run index and corpus scan, fuse rankings when they disagree\n",
+        "    --boost a,b                Rank these topics (or patterns) higher, keep the rest\n",
+        "    --dirs a,b,c               Search these KB dirs together, brief results labelled by project\n",
         "  context [query] [--brief]    Session briefing (--brief: topics only)\n",
+        "  context <query> --diff       Only entries new, changed or newly stale since the last --diff\n",
         "  delete <topic> --last|--all|--match <str>  Remove entries\n",
+        "    (topic may be a glob or /regex/; --confirm to act on several topics)\n",
+        "  delete <topic:N|topic:uid:HEX>              Remove one addressed entry\n",
         "  edit <topic> --match <str> <text>           Update matching entry\n",
-        "  recent [days]                Entries from last N days (default: 7)\n",
+        "  edit-topic <topic>           Edit a whole topic in $EDITOR; changes apply on save\n",
+        "  recent [days] [--by day|session] [--collapse] [--topic PAT]  Entries from last N days (default: 7)\n",
         "  topics                       List topics with counts\n",
+        "  topics --changed-since <ts>  Only topics whose content changed since ts (for sync tools)\n",
         "  prune [--stale N]            Flag stale topics (default: 30 days)\n",
         "  stats                        Topic count, entry count, date range, tags\n",
+        "  stats --heatmap [--weeks N] [--topic T] [--json]  Per-day entry counts (default: 12 weeks)\n",
         "  tags                         List all tags with counts\n",
-        "  entries <topic> [--match X]  List entries with index numbers\n",
+        "  warm [--corpus]              Prime index/data.log pages (--corpus: load cache)\n",
+        "  entries <topic|PAT> [--match X]  List entries with index numbers\n",
+        "  entries <topic:N|topic:uid:HEX>  Show one addressed entry (--match X: only matching lines, --context N)\n",
+        "  share <topic:N|topic:uid:HEX> [--links]  Entry as a markdown block to paste\n",
         "  compact [topic] [--apply]    Find/merge duplicate entries\n",
-        "  export                       Export all topics as JSON\n",
+        "  export [--topic PAT] [--query Q] [--tag T]  Export all topics (or a slice) as JSON\n",
+        "    --rag [--chunk-tokens N] [--overlap N]  Chunked JSON Lines records for vector stores\n",
         "  import <file|->              Import topics from JSON\n",
+        "  import --csv <file> --map topic=COL,text=COL[,tags=COL,date=COL,source=COL] [--topic T]\n",
+        "                               One entry per CSV row; COL is a header name or 1-based number\n",
+        "  collection [list]            List saved reading lists\n",
+        "  collection show <name>       Every entry in the list, in order\n",
+        "  collection add|remove <name> <address...>  Edit a list (remove also takes positions)\n",
+        "  reorder <topic> <i j k...>   Show these entries first in read/entries (--clear drops it)\n",
+        "  decide <topic> <text> [--status S] [--alt \"A | B\"] [--supersedes ADDR]  Record a decision\n",
+        "  decisions [STATUS]           Decision log by status; decisions set <addr> <status>\n",
+        "  review [list]                List entries pending review (review_queue = on)\n",
+        "  review approve|reject <topic:N|topic:uid:HEX>  Promote or delete a pending entry\n",
         "  xref <topic>                 Find cross-references in other topics\n",
         "  migrate [--apply]            Find/fix entries without timestamps\n",
         "  codepath <pat> <dir> [FLAGS] Search codebase, categorize access patterns\n",
@@ -266,12 +553,21 @@ fn print_help() {
         "    --context N                Lines of context (default: 2)\n",
         "    --store TOPIC              Store results under an amaranthine topic\n",
         "  digest                       Compact summary for MEMORY.md\n",
+        "  digest --since <date|last>   Only entries added/superseded/removed since then\n",
         "  call <tool> [key=value ...]  Call an MCP tool directly (for testing)\n",
+        "  repl                         Line commands on stdin, cache and index kept warm (? for help)\n",
         "  serve                        MCP server over stdio\n",
         "  install                      Add to Claude Code settings\n",
-        "  init [path]                  Initialize memory directory\n\n",
+        "  init [path]                  Initialize memory directory\n",
+        "  init [path] --onboard [--hooks]  Also seed amaranthine-howto, optionally add hooks, self-test MCP\n",
+        "  snapshot create [--label t]  Point-in-time copy of the KB directory (content-addressed)\n",
+        "  snapshot list | restore <id|latest>  List snapshots / roll back (current state saved first)\n",
+        "  selftest [--keep]            Store, index, search, brief, xref and a hook in a scratch KB\n\n",
         "OPTIONS:\n",
         "  -d, --dir <DIR>   Memory directory (or AMARANTHINE_DIR)\n",
         "  -p, --plain       Strip colors for programmatic use\n",
+        "  --theme <t>       Colors: none, 16 (default) or 256; honors NO_COLOR\n",
+        "\nEXIT CODES:\n",
+        "  0 ok  1 IO/other  2 not found  3 lock held  4 corrupt index  5 invalid args\n",
     ));
 }
@@ -1,22 +1,27 @@
+mod args;
+mod curate;
 mod dispatch;
+mod index;
+mod prompts;
+mod resolve;
+mod resources;
+mod search;
+mod store;
 mod tools;
 
 pub use dispatch::dispatch;
+pub(crate) use index::{ensure_index_fresh, load_index, recover_index, with_index};
+use index::{read_index, serving_foreign, startup_check, with_foreign_dir, IndexState};
 
 use crate::json::Value;
 use std::io::{self, BufRead, Write as _};
-use std::path::Path;
-use std::sync::{Mutex, RwLock};
+use crate::error::AmrError;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 static SESSION_LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
 
-struct ServerIndex { data: Vec<u8> }
-
-static INDEX: RwLock<Option<ServerIndex>> = RwLock::new(None);
-static INDEX_DIRTY: AtomicBool = AtomicBool::new(false);
-static DIRTY_AT: Mutex<Option<std::time::Instant>> = Mutex::new(None);
-
 pub(crate) fn log_session(msg: String) {
     if let Ok(mut log) = SESSION_LOG.lock() { log.push(msg); }
 }
@@ -25,8 +30,10 @@ pub fn run(dir: &Path) -> Result<(), String> {
     let stdin = io::stdin();
     let stdout = io::stdout();
 
-    ensure_datalog(dir);
+    let before = IndexState::of(dir);
+    let rebuilt = ensure_datalog(dir);
     recover_index(dir);
+    startup_check(dir, before, rebuilt);
 
     // Start Unix socket listener for hook queries against in-memory index
     let _sock_guard = crate::sock::start_listener(dir);
@@ -36,7 +43,7 @@ pub fn run(dir: &Path) -> Result<(), String> {
         // Self-audit: store binary UUID + git hash for crash correlation
         if let Some(audit) = build_audit_entry() {
             let _ = crate::store::run_full_ext(dir, "amaranthine-audit", &audit,
-                Some("system,reload"), true, None, None, None);
+                &crate::store::StoreOptions { force: true, ..crate::store::StoreOptions::tags("system,reload") });
             after_write(dir, "amaranthine-audit");
         }
         let mut out = stdout.lock();
@@ -44,20 +51,18 @@ pub fn run(dir: &Path) -> Result<(), String> {
         let _ = out.flush();
     }
 
-    // Reusable line buffer — avoids allocation per message
-    let mut line_buf = String::with_capacity(4096);
-    let stdin_lock = stdin.lock();
-    let mut reader = io::BufReader::new(stdin_lock);
+    // stdin is read on its own thread so notifications/cancelled can land while
+    // a tools/call is still running; everything else is queued to this loop.
+    let inflight: Arc<Mutex<InFlight>> = Arc::default();
+    let (tx, rx) = mpsc::channel::<Result<String, String>>();
+    {
+        let inflight = Arc::clone(&inflight);
+        std::thread::spawn(move || read_stdin(stdin, &inflight, &tx));
+    }
 
-    loop {
-        line_buf.clear();
-        match reader.read_line(&mut line_buf) {
-            Ok(0) => break, // EOF
-            Ok(_) => {}
-            Err(e) => { return Err(e.to_string()); }
-        }
+    for line_buf in rx {
+        let line_buf = line_buf?;
         let line = line_buf.trim();
-        if line.is_empty() || line.len() > 10_000_000 { continue; }
         let msg = match crate::json::parse(line) {
             Ok(v) => v,
             Err(_) => continue,
@@ -78,9 +83,15 @@ pub fn run(dir: &Path) -> Result<(), String> {
             "notifications/initialized" | "initialized" => {}
             "tools/list" => {
                 let id_json = id_to_json(id);
-                let tools_json = tools::tool_list_json();
+                let tools_json = tools::tool_list_json(dir);
+                let startup = index::take_startup_report();
                 let mut out = stdout.lock();
-                let _ = write!(out, r#"{{"jsonrpc":"2.0","id":{id_json},"result":{tools_json}}}"#);
+                let _ = match startup {
+                    // Splice `_meta` into the cached {"tools":[...]} object
+                    Some(report) => write!(out, r#"{{"jsonrpc":"2.0","id":{id_json},"result":{},"_meta":{{"amaranthine/startup":{report}}}}}}}"#,
+                        &tools_json[..tools_json.len() - 1]),
+                    None => write!(out, r#"{{"jsonrpc":"2.0","id":{id_json},"result":{tools_json}}}"#),
+                };
                 let _ = writeln!(out);
                 let _ = out.flush();
             }
@@ -98,8 +109,38 @@ pub fn run(dir: &Path) -> Result<(), String> {
                     continue;
                 }
                 let args = p.and_then(|p| p.get("arguments"));
+                let target = match request_dir(dir, args) {
+                    Ok(t) => t,
+                    Err(e) => {
+                        let mut out = stdout.lock();
+                        let _ = write_rpc_err(&mut out, &id_json, &e);
+                        let _ = out.flush();
+                        continue;
+                    }
+                };
+                // Client opted into progress: stream notifications/progress while the tool runs
+                let token = p.and_then(|p| p.get("_meta"))
+                    .and_then(|m| m.get("progressToken")).map(|t| t.to_string());
+                let Some(cancel) = begin_call(&inflight, &id_json) else { continue };
+                let call = || crate::cancel::with_flag(Arc::clone(&cancel), || match &target {
+                    Some(other) => with_foreign_dir(|| dispatch::dispatch(name, args, other)),
+                    None => dispatch::dispatch(name, args, dir),
+                });
+                let result = match token {
+                    Some(token) => crate::progress::with_sink(
+                        move |step| {
+                            let mut out = io::stdout().lock();
+                            let _ = write_progress(&mut out, &token, step);
+                            let _ = out.flush();
+                        },
+                        call),
+                    None => call(),
+                };
+                if let Ok(mut f) = inflight.lock() { f.current = None; }
+                // Cancelled requests get no response (MCP spec); the client has moved on
+                if cancel.load(Ordering::Relaxed) { continue; }
                 let mut out = stdout.lock();
-                let ok = match dispatch::dispatch(name, args, dir) {
+                let ok = match result {
                     Ok(ref text) => write_rpc_ok(&mut out, &id_json, text),
                     Err(ref e) => write_rpc_err(&mut out, &id_json, e),
                 };
@@ -109,6 +150,25 @@ pub fn run(dir: &Path) -> Result<(), String> {
                 }
                 let _ = out.flush();
             }
+            "resources/list" | "resources/templates/list" | "resources/read"
+            | "prompts/list" | "prompts/get" => {
+                let id_json = id_to_json(id);
+                let p = msg.get("params");
+                let param = |k: &str| p.and_then(|p| p.get(k)).and_then(|v| v.as_str()).unwrap_or("");
+                let result = match method {
+                    "resources/list" => resources::list_json(dir),
+                    "resources/templates/list" => Ok(resources::templates_json()),
+                    "resources/read" => resources::read_json(dir, param("uri")),
+                    "prompts/list" => Ok(prompts::list_json()),
+                    _ => prompts::get_json(dir, param("name"), p.and_then(|p| p.get("arguments"))),
+                };
+                let mut out = stdout.lock();
+                let _ = match result {
+                    Ok(json) => writeln!(out, r#"{{"jsonrpc":"2.0","id":{id_json},"result":{json}}}"#),
+                    Err(ref e) => write_rpc_err(&mut out, &id_json, e),
+                };
+                let _ = out.flush();
+            }
             "ping" => {
                 let id_json = id_to_json(id);
                 let mut out = stdout.lock();
@@ -129,6 +189,63 @@ pub fn run(dir: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// Request currently in dispatch, plus cancellations that arrived for requests
+/// still queued behind it (capped — a client cancelling far ahead is not our problem).
+#[derive(Default)]
+struct InFlight {
+    current: Option<(String, Arc<AtomicBool>)>,
+    early: Vec<String>,
+}
+
+const MAX_EARLY_CANCELS: usize = 64;
+
+/// Stdin reader thread: handles notifications/cancelled inline, forwards every
+/// other line. Reuses one line buffer; ends on EOF or when the main loop exits.
+fn read_stdin(stdin: io::Stdin, inflight: &Mutex<InFlight>, tx: &mpsc::Sender<Result<String, String>>) {
+    let mut reader = io::BufReader::new(stdin.lock());
+    let mut line_buf = String::with_capacity(4096);
+    loop {
+        line_buf.clear();
+        match reader.read_line(&mut line_buf) {
+            Ok(0) => return, // EOF
+            Ok(_) => {}
+            Err(e) => { let _ = tx.send(Err(e.to_string())); return; }
+        }
+        let line = line_buf.trim();
+        if line.is_empty() || line.len() > 10_000_000 { continue; }
+        // Substring pre-check keeps the hot path to one parse (on the main loop)
+        if line.contains("notifications/cancelled") {
+            if let Ok(msg) = crate::json::parse(line) {
+                if msg.get("method").and_then(|v| v.as_str()) == Some("notifications/cancelled") {
+                    let id = msg.get("params").and_then(|p| p.get("requestId"));
+                    if let (Some(id), Ok(mut f)) = (id, inflight.lock()) {
+                        let id_json = id_to_json(Some(id)).to_string();
+                        match &f.current {
+                            Some((cur, flag)) if *cur == id_json => flag.store(true, Ordering::Relaxed),
+                            _ => if f.early.len() < MAX_EARLY_CANCELS { f.early.push(id_json) },
+                        }
+                    }
+                    continue;
+                }
+            }
+        }
+        if tx.send(Ok(line.to_string())).is_err() { return; }
+    }
+}
+
+/// Register `id_json` as the in-flight request and return its cancel flag.
+/// None when the request was already cancelled while queued.
+fn begin_call(inflight: &Mutex<InFlight>, id_json: &str) -> Option<Arc<AtomicBool>> {
+    let flag = Arc::new(AtomicBool::new(false));
+    let Ok(mut f) = inflight.lock() else { return Some(flag) };
+    if let Some(pos) = f.early.iter().position(|e| e == id_json) {
+        f.early.swap_remove(pos);
+        return None;
+    }
+    f.current = Some((id_json.to_string(), Arc::clone(&flag)));
+    Some(flag)
+}
+
 /// Write id Value to stack buffer — zero heap allocation for the 99% case (integer IDs).
 /// Returns a small stack string that derefs to &str.
 fn id_to_json(id: Option<&Value>) -> IdBuf {
@@ -185,11 +302,24 @@ fn write_rpc_ok(w: &mut impl io::Write, id_json: &str, text: &str) -> io::Result
 }
 
 /// Streaming JSON-RPC error response — writes directly to stdout, zero intermediate String.
-fn write_rpc_err(w: &mut impl io::Write, id_json: &str, msg: &str) -> io::Result<()> {
+/// Code comes from AmrError::rpc_code; `data.kind` carries the error kind for clients.
+fn write_rpc_err(w: &mut impl io::Write, id_json: &str, err: &crate::error::AmrError) -> io::Result<()> {
     w.write_all(b"{\"jsonrpc\":\"2.0\",\"id\":")?;
     w.write_all(id_json.as_bytes())?;
-    w.write_all(b",\"error\":{\"code\":-32603,\"message\":\"")?;
-    write_json_escaped(w, msg)?;
+    write!(w, ",\"error\":{{\"code\":{},\"message\":\"", err.rpc_code())?;
+    write_json_escaped(w, err.message())?;
+    writeln!(w, "\",\"data\":{{\"kind\":\"{}\"}}}}}}", err.kind())
+}
+
+/// notifications/progress for a tools/call that carried `_meta.progressToken`.
+/// `message` carries partial status (files scanned, hits so far) for display.
+fn write_progress(w: &mut impl io::Write, token: &str, step: &crate::progress::Progress) -> io::Result<()> {
+    w.write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{\"progressToken\":")?;
+    w.write_all(token.as_bytes())?;
+    write!(w, ",\"progress\":{}", step.done)?;
+    if let Some(t) = step.total { write!(w, ",\"total\":{t}")?; }
+    w.write_all(b",\"message\":\"")?;
+    write_json_escaped(w, step.message)?;
     w.write_all(b"\"}}\n")
 }
 
@@ -260,7 +390,9 @@ fn do_reload() {
     eprintln!("reload failed: {_err}");
 }
 
-fn ensure_datalog(dir: &Path) {
+/// Ensure data.log exists (migrating legacy .md files), then rebuild index.bin.
+/// Returns the rebuild summary line or error.
+fn ensure_datalog(dir: &Path) -> Result<String, String> {
     if !crate::config::data_log_exists(dir) {
         if let Ok(files) = crate::config::list_topic_files(dir) {
             if !files.is_empty() {
@@ -274,95 +406,78 @@ fn ensure_datalog(dir: &Path) {
             } else { let _ = crate::datalog::ensure_log(dir); }
         } else { let _ = crate::datalog::ensure_log(dir); }
     }
-    match crate::inverted::rebuild_and_persist(dir) {
-        Ok((_, bytes)) => store_index(bytes),
-        Err(_) => {} // no index yet, load_index in run() will try disk
-    }
+    // On error there is no index yet; recover_index in run() will try disk
+    let (msg, _) = crate::inverted::rebuild_and_persist(dir)?;
+    load_index(dir);
+    Ok(msg)
 }
 
-/// Validate existing index.bin; if corrupted or wrong version, rebuild from data.log.
-/// Called on startup before first query, and on any index read failure.
-pub(crate) fn recover_index(dir: &Path) {
-    let index_path = dir.join("index.bin");
-    let needs_rebuild = match std::fs::read(&index_path) {
-        Ok(data) => crate::binquery::read_header(&data).is_err(),
-        Err(_) => true,
-    };
-    if needs_rebuild {
-        eprintln!("amaranthine: index.bin invalid, rebuilding from data.log...");
-        match crate::inverted::rebuild_and_persist(dir) {
-            Ok((msg, bytes)) => {
-                eprintln!("amaranthine: {}", msg.lines().next().unwrap_or("rebuilt"));
-                store_index(bytes);
-            }
-            Err(e) => eprintln!("amaranthine: rebuild failed: {e}"),
-        }
-    }
+/// Resolve the optional per-call `dir` argument. Only dirs listed in
+/// `allowed_dirs` in the startup dir's config are served; None = startup dir.
+fn request_dir(base: &Path, args: Option<&Value>) -> Result<Option<PathBuf>, AmrError> {
+    let Some(req) = args.and_then(|a| a.get("dir")).and_then(|v| v.as_str())
+        .map(str::trim).filter(|s| !s.is_empty()) else { return Ok(None) };
+    let canon = |p: &Path| std::fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let want = canon(&crate::settings::expand_home(req));
+    if want == canon(base) { return Ok(None); }
+    check_allowed(base, req)?;
+    if !want.is_dir() { return Err(AmrError::NotFound(format!("{} is not a directory", want.display()))); }
+    crate::datalog::ensure_log(&want)?;
+    Ok(Some(want))
 }
 
-pub(crate) fn load_index(dir: &Path) {
-    let index_path = dir.join("index.bin");
-    if let Ok(data) = std::fs::read(&index_path) {
-        store_index(data);
+/// Err unless `req` is `base` or listed in `base`'s `allowed_dirs`. Also
+/// gates the dirs of a federated search (`search dirs=...`).
+pub(crate) fn check_allowed(base: &Path, req: &str) -> Result<(), AmrError> {
+    let canon = |p: &Path| std::fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let want = canon(&crate::settings::expand_home(req));
+    if want == canon(base) { return Ok(()); }
+    let settings = crate::settings::load(base);
+    let allowed = settings.list("allowed_dirs").into_iter()
+        .any(|d| canon(&crate::settings::expand_home(d)) == want);
+    if !allowed {
+        return Err(AmrError::InvalidArg(format!(
+            "dir not allowed: {req} (add it to allowed_dirs in {})",
+            crate::settings::config_path(base).display())));
     }
+    Ok(())
 }
 
-pub(crate) fn store_index(data: Vec<u8>) {
-    if let Ok(mut guard) = INDEX.write() {
-        *guard = Some(ServerIndex { data });
-    }
-}
-
-/// Borrow cached index data via closure. Returns None if no index loaded.
-/// Uses RwLock read guard — concurrent reads don't block each other.
-pub(crate) fn with_index<F, R>(f: F) -> Option<R>
-where F: FnOnce(&[u8]) -> R {
-    INDEX.read().ok().and_then(|guard| guard.as_ref().map(|idx| f(&idx.data)))
-}
-
-pub(crate) fn after_write(_dir: &Path, _topic: &str) {
-    INDEX_DIRTY.store(true, Ordering::Release);
-    // Record when dirty flag was set for debounce
-    if let Ok(mut guard) = DIRTY_AT.lock() {
-        if guard.is_none() { *guard = Some(std::time::Instant::now()); }
-    }
+pub(crate) fn after_write(dir: &Path, topic: &str) {
+    crate::digest::note_write(dir, topic);
+    // Foreign dirs have no in-memory index: refresh their index.bin for hooks now.
+    // The server's own index catches up on the next read (ensure_index_fresh).
+    if serving_foreign() { let _ = crate::inverted::rebuild_and_persist(dir); }
 }
 
-/// Rebuild index if dirty and debounce window (50ms) has elapsed.
-/// Burst writes within the window are coalesced into a single rebuild.
-/// Persists index.bin to disk (atomic rename) so hook mmap always reads fresh data.
-/// v6.6: single DIRTY_AT lock acquisition (was two: check + clear).
-pub(crate) fn ensure_index_fresh(dir: &Path) {
-    if !INDEX_DIRTY.load(Ordering::Acquire) { return; }
-    // Single lock: check debounce AND clear in one acquisition
-    let should_rebuild = DIRTY_AT.lock().ok().map_or(false, |mut g| {
-        match *g {
-            Some(t) if t.elapsed() < std::time::Duration::from_millis(50) => false,
-            _ => {
-                // Only clear if we win the CAS
-                if INDEX_DIRTY.compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
-                    *g = None;
-                    true
-                } else { false }
-            }
-        }
-    });
-    if should_rebuild {
-        match crate::inverted::rebuild(dir) {
-            Ok((_, bytes)) => {
-                let tmp = dir.join("index.bin.tmp");
-                let target = dir.join("index.bin");
-                let _ = std::fs::write(&tmp, &bytes)
-                    .and_then(|_| std::fs::rename(&tmp, &target));
-                store_index(bytes);
-            }
-            Err(_) => load_index(dir),
+/// `reload_config` tool: re-read `<dir>/config` and apply what can't apply
+/// itself. Most keys are read per call and take effect immediately; importance
+/// (`tag_weights`) and source freshness (`freshness`) are baked into index.bin,
+/// and the advertised tool list (`allowed_dirs`) is cached, so those are
+/// refreshed here.
+pub(crate) fn reload_config(dir: &Path) -> Result<String, AmrError> {
+    let (old, new) = crate::settings::reload(dir)?;
+    let changed = crate::settings::changed_keys(&old, &new);
+    let path = crate::settings::config_path(dir);
+    if changed.is_empty() { return Ok(format!("{}: no changes\n", path.display())); }
+    let mut out = format!("{}: reloaded ({} changed)\n", path.display(), changed.len());
+    for key in &changed {
+        let value = new.list(key).join(", ");
+        let value = if value.is_empty() { "(default)".to_string() } else { value };
+        out.push_str(&format!("  {key} = {value}"));
+        match key.as_str() {
+            "tag_weights" => { after_write(dir, ""); out.push_str(" — importance rescored on next query"); }
+            "freshness" => { after_write(dir, ""); out.push_str(" — freshness rescored on next query"); }
+            "allowed_dirs" => { tools::invalidate(); out.push_str(" — tool list refreshed; re-list tools"); }
+            _ => {}
         }
+        out.push('\n');
     }
+    Ok(out)
 }
 
 /// Pre-serialized initialize result — zero allocation, written directly to stdout.
-const INIT_RESULT: &str = r#"{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"amaranthine","version":"10.0.0"}}"#;
+const INIT_RESULT: &str = r#"{"protocolVersion":"2024-11-05","capabilities":{"tools":{},"resources":{},"prompts":{}},"serverInfo":{"name":"amaranthine","version":"10.0.0"}}"#;
 
 /// Build audit entry with binary UUID and git hash for crash correlation.
 fn build_audit_entry() -> Option<String> {
@@ -0,0 +1,108 @@
+//! Tool argument extraction: string/bool accessors over the JSON args, and the
+//! search `Filter` the search-family tools share.
+
+use crate::json::Value;
+use std::path::Path;
+use crate::error::AmrError;
+
+/// Borrow string value from args — zero allocation for the common case (string values).
+/// Returns "" if key missing or value is not a string.
+pub(super) fn arg_ref<'a>(args: Option<&'a Value>, key: &str) -> &'a str {
+    args.and_then(|a| a.get(key))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+/// Owned string from args — needed for Num/Bool value conversion.
+pub(super) fn arg_str(args: Option<&Value>, key: &str) -> String {
+    args.and_then(|a| a.get(key))
+        .map(|v| match v {
+            Value::Str(s) => s.clone(),
+            Value::Num(n) => if n.fract() == 0.0 { format!("{}", *n as i64) } else { n.to_string() },
+            Value::Bool(b) => if *b { "true" } else { "false" }.into(),
+            _ => String::new(),
+        })
+        .unwrap_or_default()
+}
+
+pub(super) fn arg_bool(args: Option<&Value>, key: &str) -> bool {
+    let s = arg_ref(args, key);
+    s == "true" || s == "1"
+}
+
+/// Errors only on a malformed topic glob / regex, min_match, provenance or lang.
+pub(super) fn build_filter(dir: &Path, args: Option<&Value>) -> Result<crate::search::Filter, AmrError> {
+    let after_raw = arg_ref(args, "after");
+    let before_raw = arg_ref(args, "before");
+    // days/hours shortcuts: convert to after= date if after is not set
+    let after = if after_raw.is_empty() {
+        let days = arg_ref(args, "days").parse::<u64>().ok();
+        let hours = arg_ref(args, "hours").parse::<u64>().ok();
+        crate::time::relative_to_date(days, hours).unwrap_or_default()
+    } else {
+        crate::time::resolve_date_shortcut(after_raw)
+    };
+    let before = crate::time::resolve_date_shortcut(before_raw);
+    let tag = arg_ref(args, "tag");
+    let topic = arg_ref(args, "topic");
+    if crate::pattern::is_pattern(topic) { crate::pattern::TopicPattern::parse(topic)?; }
+    let mode = match arg_ref(args, "mode") {
+        "or" => crate::search::SearchMode::Or,
+        _ => crate::search::SearchMode::And,
+    };
+    let min_match = match arg_ref(args, "min_match") {
+        "" => crate::search::MinMatch::All,
+        m => crate::search::MinMatch::parse(m).ok_or_else(|| AmrError::InvalidArg(format!(
+            "min_match: expected a term count ('3'), a percentage ('75%') or 'all', got '{m}'")))?,
+    };
+    // Without boost_topics, lean toward what this session has been working in
+    // (topics the ambient hook injected from); 'none' turns that off
+    let boost_topics: Vec<String> = match arg_ref(args, "boost_topics") {
+        "" => crate::session::Session::load(dir).map(|s| s.focus_topics).unwrap_or_default(),
+        "none" => Vec::new(),
+        b => {
+            let list: Vec<String> = b.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect();
+            for t in list.iter().filter(|t| crate::pattern::is_pattern(t)) { crate::pattern::TopicPattern::parse(t)?; }
+            list
+        }
+    };
+    let provenance = match arg_ref(args, "provenance") {
+        "" => None,
+        p => Some(crate::provenance::Provenance::parse(p).ok_or_else(|| AmrError::InvalidArg(format!(
+            "provenance: expected agent, human, imported or trace, got '{p}'")))?),
+    };
+    let lang = match arg_ref(args, "lang") {
+        "" => None,
+        l => Some(crate::lang::Lang::parse(l).ok_or_else(|| AmrError::InvalidArg(format!(
+            "lang: expected {}, got '{l}'", crate::lang::names())))?),
+    };
+    Ok(crate::search::Filter {
+        after: if after.is_empty() { None } else { crate::time::parse_date_days(&after) },
+        before: if before.is_empty() { None } else { crate::time::parse_date_days(&before) },
+        tag: if tag.is_empty() { None } else { Some(tag.to_string()) },
+        topic: if topic.is_empty() { None } else { Some(topic.to_string()) },
+        provenance,
+        lang,
+        mode,
+        min_match,
+        boost_topics,
+        hybrid: match arg_ref(args, "hybrid") {
+            "" => crate::settings::load(dir).flag("hybrid_search") == Some(true),
+            h => h == "true" || h == "1",
+        },
+    })
+}
+
+/// Phase-aware default search limit from session state.
+/// Research phase: 10 (broad exploration). Build phase: 5 (focused).
+/// Debug phase: 8 (targeted). Unknown: None (use existing default).
+pub(super) fn phase_aware_limit(dir: &Path) -> Option<usize> {
+    let session = crate::session::Session::load(dir)?;
+    match session.phase {
+        crate::session::Phase::Research => Some(10),
+        crate::session::Phase::Build => Some(5),
+        crate::session::Phase::Verify => Some(5),
+        crate::session::Phase::Debug => Some(8),
+        crate::session::Phase::Unknown => None,
+    }
+}
@@ -0,0 +1,95 @@
+//! Curation tools: filing inbox entries, decision records and the review
+//! queue for held agent entries.
+
+use crate::json::Value;
+use std::path::Path;
+use crate::error::AmrError;
+use super::args::{arg_bool, arg_ref};
+use super::resolve::resolve_address;
+
+/// `file_inbox`: suggest topics for unfiled entries, or file them.
+pub(super) fn file_inbox(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let index = arg_ref(args, "index");
+    let index = if index.is_empty() { None } else {
+        Some(index.parse().map_err(|_| AmrError::InvalidArg(format!("invalid index: '{index}'")))?)
+    };
+    let topic = Some(arg_ref(args, "topic")).filter(|t| !t.is_empty());
+    let f = crate::inbox::Filing { apply: arg_bool(args, "apply"), index, topic };
+    let result = crate::inbox::file(dir, &f)?;
+    if f.apply || topic.is_some() { super::after_write(dir, crate::inbox::TOPIC); }
+    Ok(result)
+}
+
+/// `decide`: record a decision, optionally superseding an earlier one.
+pub(super) fn decide(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let topic = arg_ref(args, "topic");
+    let status = match arg_ref(args, "status") {
+        "" => crate::decision::Status::Accepted,
+        s => crate::decision::Status::parse(s).ok_or_else(|| AmrError::InvalidArg(format!(
+            "status: expected proposed or accepted, got '{s}'")))?,
+    };
+    let supersedes = match arg_ref(args, "supersedes") {
+        "" => None,
+        raw => Some(resolve_address(dir, raw)?),
+    };
+    let links = arg_ref(args, "links");
+    let provenance = match arg_ref(args, "provenance") {
+        "" => crate::provenance::Provenance::Agent,
+        p => crate::provenance::Provenance::parse(p).ok_or_else(|| AmrError::InvalidArg(format!(
+            "provenance: expected agent or human, got '{p}'")))?,
+    };
+    let record = crate::decision::Record {
+        status,
+        alternatives: arg_ref(args, "alternatives").split('|').collect(),
+        links: if links.is_empty() { None } else { Some(links) },
+        supersedes: supersedes.as_ref().map(|(t, i)| (t.as_str(), *i)),
+        provenance: Some(provenance),
+    };
+    let result = crate::decision::record(dir, topic, arg_ref(args, "text"), &record)?;
+    if let Some((t, _)) = &supersedes { if t != topic { super::after_write(dir, t); } }
+    super::after_write(dir, topic);
+    Ok(result)
+}
+
+/// `decisions`: list by status, or set one's status.
+pub(super) fn decisions(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let status = match arg_ref(args, "status") {
+        "" => None,
+        s => Some(crate::decision::Status::parse(s).ok_or_else(|| AmrError::InvalidArg(format!(
+            "status: expected proposed, accepted or superseded, got '{s}'")))?),
+    };
+    match arg_ref(args, "action") {
+        "" | "list" => crate::decision::list(dir, status),
+        "set" => {
+            let topic = arg_ref(args, "topic");
+            let i = arg_ref(args, "index");
+            let idx = i.parse::<usize>().map_err(|_| AmrError::InvalidArg(
+                "topic (a decision address, or topic + index) is required".into()))?;
+            let status = status.ok_or_else(|| AmrError::InvalidArg("status is required".into()))?;
+            let result = crate::decision::set_status(dir, topic, idx, status)?;
+            super::after_write(dir, topic);
+            Ok(result)
+        }
+        a => Err(AmrError::InvalidArg(format!("action: expected list or set, got '{a}'"))),
+    }
+}
+
+/// `review`: list the entries held for review, approve or reject one.
+pub(super) fn review(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let topic = arg_ref(args, "topic");
+    let idx = || -> Result<usize, AmrError> {
+        let i = arg_ref(args, "index");
+        if topic.is_empty() || i.is_empty() {
+            return Err(AmrError::InvalidArg("topic (an entry address, or topic + index) is required".into()));
+        }
+        i.parse().map_err(|_| AmrError::InvalidArg(format!("invalid index: '{i}'")))
+    };
+    let result = match arg_ref(args, "action") {
+        "" | "list" => return crate::review::list(dir),
+        "approve" => crate::review::approve(dir, topic, idx()?)?,
+        "reject" => crate::review::reject(dir, topic, idx()?)?,
+        a => return Err(AmrError::InvalidArg(format!("action: expected list, approve or reject, got '{a}'"))),
+    };
+    super::after_write(dir, topic);
+    Ok(result)
+}
@@ -1,39 +1,26 @@
 use crate::json::Value;
 use std::path::Path;
+use crate::error::AmrError;
+use super::args::{arg_bool, arg_ref, arg_str, build_filter};
+use super::resolve::{resolve_entry_address, resolve_topic_uids};
 
-pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String, String> {
+pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String, AmrError> {
+    crate::settings::apply(dir);
+    let resolved = resolve_topic_uids(args, dir)?;
+    let args = resolved.as_ref().or(args);
+    let addressed = resolve_entry_address(name, args, dir)?;
+    let args = addressed.as_ref().or(args);
     // Deferred index rebuild: only for read operations.
-    // Write ops (store, append, batch, delete, etc.) will dirty the index anyway.
+    // Write ops (store, append, batch, delete, etc.) skip it: the next read rebuilds once for the burst.
     match name {
         "store" | "append" | "batch" | "delete" | "append_entry"
         | "revise" | "rename" | "merge" | "tag"
-        | "import" | "reindex" | "session" => {}
+        | "import" | "reindex" | "session" | "reload_config" | "trace_query" | "review" | "reorder" | "collection"
+        | "decide" | "decisions" | "tail" => {}
         _ => super::ensure_index_fresh(dir),
     }
     match name {
-        "store" => {
-            let topic = arg_ref(args, "topic");
-            let text = arg_ref(args, "text");
-            let tags = arg_ref(args, "tags");
-            let tags = if tags.is_empty() { None } else { Some(tags) };
-            let force = arg_bool(args, "force");
-            let terse = arg_bool(args, "terse");
-            let source = arg_ref(args, "source");
-            let source = if source.is_empty() { None } else { Some(source) };
-            let conf_str = arg_ref(args, "confidence");
-            let confidence = conf_str.parse::<f64>().ok().filter(|c| *c >= 0.0 && *c <= 1.0);
-            let links = arg_ref(args, "links");
-            let links = if links.is_empty() { None } else { Some(links) };
-            let result = crate::store::run_full_ext(dir, topic, text, tags, force, source, confidence, links)?;
-            super::after_write(dir, topic);
-            super::log_session(format!("[{}] {}", topic,
-                result.lines().next().unwrap_or("stored")));
-            if terse {
-                Ok(result.lines().next().unwrap_or(&result).to_string())
-            } else {
-                Ok(result)
-            }
-        }
+        "store" => super::store::store(dir, args),
         "append" | "append_entry" => {
             let topic = arg_ref(args, "topic");
             let text = arg_ref(args, "text");
@@ -42,151 +29,20 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             let tag = arg_ref(args, "tag");
             let result = if !idx_str.is_empty() {
                 let idx: usize = idx_str.parse()
-                    .map_err(|_| format!("invalid index: '{idx_str}'"))?;
+                    .map_err(|_| AmrError::InvalidArg(format!("invalid index: '{idx_str}'")))?;
                 crate::edit::append_by_index(dir, topic, idx, text)
             } else if !tag.is_empty() {
                 crate::edit::append_by_tag(dir, topic, tag, text)
             } else if !needle.is_empty() {
                 crate::edit::append(dir, topic, needle, text)
             } else {
-                crate::store::append(dir, topic, text)
+                Ok(crate::store::append(dir, topic, text)?)
             }?;
             super::after_write(dir, topic);
             Ok(result)
         }
-        "batch" => {
-            let verbose = arg_bool(args, "verbose");
-            let items = args.and_then(|a| a.get("entries"))
-                .and_then(|v| match v { Value::Arr(a) => Some(a), _ => None })
-                .ok_or("entries must be an array")?;
-            if items.len() > 30 {
-                return Err(format!(
-                    "batch too large ({} entries, max 30). Split into smaller batch calls.",
-                    items.len()
-                ));
-            }
-            let _lock = crate::lock::FileLock::acquire(dir)?;
-            // F3: Open file once, write N entries, fsync once (was N opens + N fsyncs)
-            crate::config::ensure_dir(dir)?;
-            let log_path = crate::datalog::ensure_log(dir)?;
-            let mut log_file = std::fs::OpenOptions::new().append(true).open(&log_path)
-                .map_err(|e| format!("open data.log: {e}"))?;
-            let mut ok_count = 0;
-            let mut results = Vec::new();
-            let mut seen: Vec<(String, String)> = Vec::new();
-            let mut batch_tokens: Vec<(String, crate::fxhash::FxHashSet<String>)> = Vec::new();
-            'batch: for (i, item) in items.iter().enumerate() {
-                let topic = item.get("topic").and_then(|v| v.as_str()).unwrap_or("");
-                let text = item.get("text").and_then(|v| v.as_str()).unwrap_or("");
-                let tags = item.get("tags").and_then(|v| v.as_str());
-                let source = item.get("source").and_then(|v| v.as_str());
-                if topic.is_empty() || text.is_empty() {
-                    results.push(format!("  [{}] skipped: missing topic or text", i + 1));
-                    continue;
-                }
-                let key = (
-                    topic.to_lowercase(),
-                    text.chars().take(60).collect::<String>().to_lowercase(),
-                );
-                if seen.iter().any(|s| s.0 == key.0 && s.1 == key.1) {
-                    results.push(format!("  [{}] skipped: duplicate within batch", i + 1));
-                    continue;
-                }
-                seen.push(key);
-                // Token-based semantic dupe check within batch
-                let new_tokens: crate::fxhash::FxHashSet<String> = crate::text::tokenize(text)
-                    .into_iter().filter(|t| t.len() >= 3).collect();
-                if new_tokens.len() >= 6 {
-                    let mut is_dupe = false;
-                    for (prev_topic, prev_tokens) in &batch_tokens {
-                        if *prev_topic != topic { continue; }
-                        let intersection = new_tokens.iter()
-                            .filter(|t| prev_tokens.contains(*t)).count();
-                        let union = new_tokens.len() + prev_tokens.len() - intersection;
-                        if union > 0 && intersection as f64 / union as f64 > 0.70 {
-                            results.push(format!("  [{}] skipped: similar to earlier batch entry", i + 1));
-                            is_dupe = true;
-                            break;
-                        }
-                    }
-                    if is_dupe { continue 'batch; }
-                    batch_tokens.push((topic.to_string(), new_tokens));
-                }
-                match crate::store::run_batch_entry_to(&mut log_file, topic, text, tags, source) {
-                    Ok(msg) => {
-                        ok_count += 1;
-                        let first = msg.lines().next().unwrap_or(&msg);
-                        results.push(format!("  [{}] {}", i + 1, first));
-                        super::log_session(format!("[{}] {}", topic, first));
-                    }
-                    Err(e) => {
-                        let first = e.lines().next().unwrap_or(&e);
-                        results.push(format!("  [{}] err: {}", i + 1, first));
-                    }
-                }
-            }
-            // Single fsync after all entries written
-            if ok_count > 0 {
-                let _ = log_file.sync_all();
-            }
-            drop(log_file);
-            drop(_lock);
-            if ok_count > 0 {
-                super::after_write(dir, "");
-            }
-            if verbose {
-                Ok(format!("batch: {ok_count}/{} stored\n{}", items.len(), results.join("\n")))
-            } else {
-                Ok(format!("batch: {ok_count}/{} stored", items.len()))
-            }
-        }
-        "search" => {
-            let query = arg_ref(args, "query");
-            let detail = arg_ref(args, "detail");
-            let filter = build_filter(args);
-            // v10: Phase-aware default limit — build phase gets tighter results
-            let explicit_limit = arg_ref(args, "limit").parse::<usize>().ok();
-            let session_limit = if explicit_limit.is_none() {
-                phase_aware_limit(dir)
-            } else {
-                explicit_limit
-            };
-            match detail {
-                "count" => crate::search::count(dir, query, &filter),
-                "topics" => crate::search::run_topics(dir, query, &filter),
-                "grouped" => {
-                    let guard = super::INDEX.read().map_err(|e| e.to_string())?;
-                    let idx = guard.as_ref().map(|i| i.data.as_slice());
-                    let result = crate::search::run_grouped(dir, query, session_limit, &filter, idx);
-                    drop(guard);
-                    result
-                }
-                "index" => {
-                    let limit = explicit_limit.unwrap_or_else(|| session_limit.unwrap_or(10));
-                    let guard = super::INDEX.read().map_err(|e| e.to_string())?;
-                    let data = match guard.as_ref() {
-                        Some(idx) => std::borrow::Cow::Borrowed(idx.data.as_slice()),
-                        None => {
-                            drop(guard);
-                            std::borrow::Cow::Owned(std::fs::read(dir.join("index.bin"))
-                                .map_err(|e| format!("index.bin: {e}"))?)
-                        }
-                    };
-                    crate::binquery::search(&data, query, limit)
-                }
-                _ => {
-                    let guard = super::INDEX.read().map_err(|e| e.to_string())?;
-                    let idx = guard.as_ref().map(|i| i.data.as_slice());
-                    let result = match detail {
-                        "full" => crate::search::run(dir, query, true, session_limit, &filter, idx),
-                        "brief" => crate::search::run_brief(dir, query, session_limit, &filter, idx),
-                        _ => crate::search::run_medium(dir, query, session_limit, &filter, idx),
-                    };
-                    drop(guard);
-                    result
-                }
-            }
-        }
+        "batch" => super::store::batch(dir, args),
+        "search" => super::search::search(dir, args),
         "context" => {
             // Legacy: redirect to brief
             let q = arg_ref(args, "query");
@@ -194,36 +50,50 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             let brief = arg_bool(args, "brief");
             crate::context::run_inner_pub(dir, q, true, brief)
         }
-        "topics" => crate::topics::list_compact(dir),
+        "topics" => {
+            let since = arg_ref(args, "changed_since");
+            if !since.is_empty() { crate::digest::changed_since(dir, since).map_err(Into::into) }
+            else if arg_bool(args, "uids") { crate::topics::list_uids(dir).map_err(Into::into) }
+            else { crate::topics::list_compact(dir).map_err(Into::into) }
+        }
         "recent" => {
-            let h = arg_ref(args, "hours");
-            if let Ok(hours) = h.parse::<u64>() {
-                crate::topics::recent_hours(dir, hours, true)
-            } else {
-                let d = arg_ref(args, "days");
-                let days = d.parse().unwrap_or(7u64);
-                crate::topics::recent(dir, days, true)
-            }
+            let hours = arg_ref(args, "hours").parse::<u64>().ok();
+            let days = arg_ref(args, "days").parse().unwrap_or(7u64);
+            let group = match arg_ref(args, "group") {
+                "" | "none" => None,
+                "day" => Some(crate::topics::RecentGroup::Day),
+                "session" => Some(crate::topics::RecentGroup::Session),
+                g => return Err(AmrError::InvalidArg(format!("group: expected day or session, got '{g}'"))),
+            };
+            let topic = arg_ref(args, "topic");
+            if crate::pattern::is_pattern(topic) { crate::pattern::TopicPattern::parse(topic)?; }
+            let view = crate::topics::RecentView {
+                group, collapse: arg_bool(args, "collapse"),
+                topic: if topic.is_empty() { None } else { Some(topic.to_string()) },
+            };
+            crate::topics::recent_view(dir, days, hours, &view, true).map_err(Into::into)
         }
         "delete" => {
             let topic = arg_ref(args, "topic");
             let all = arg_bool(args, "all");
+            let confirm = arg_bool(args, "confirm");
             let result = if all {
-                crate::delete::run(dir, topic, false, true, None)
+                crate::delete::run_pattern(dir, topic, false, true, None, confirm)
             } else {
                 let idx_str = arg_ref(args, "index");
                 let m = arg_ref(args, "match_str");
                 if !idx_str.is_empty() {
                     let idx: usize = idx_str.parse()
-                        .map_err(|_| format!("invalid index: '{idx_str}'"))?;
-                    crate::delete::run_by_index(dir, topic, idx)
+                        .map_err(|_| AmrError::InvalidArg(format!("invalid index: '{idx_str}'")))?;
+                    crate::delete::run_by_index(dir, &crate::stats::single_topic(dir, topic)?, idx)
                 } else if !m.is_empty() {
-                    crate::delete::run(dir, topic, false, false, Some(m))
+                    crate::delete::run_pattern(dir, topic, false, false, Some(m), confirm)
                 } else {
-                    crate::delete::run(dir, topic, true, false, None)
+                    crate::delete::run_pattern(dir, topic, true, false, None, confirm)
                 }
             }?;
             super::after_write(dir, topic);
+            crate::webhook::emit_result(dir, "delete", topic, &result);
             Ok(result)
         }
         "revise" => {
@@ -233,7 +103,7 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             let needle = arg_ref(args, "match_str");
             let result = if !idx_str.is_empty() {
                 let idx: usize = idx_str.parse()
-                    .map_err(|_| format!("invalid index: '{idx_str}'"))?;
+                    .map_err(|_| AmrError::InvalidArg(format!("invalid index: '{idx_str}'")))?;
                 crate::edit::run_by_index(dir, topic, idx, text)
             } else {
                 crate::edit::run(dir, topic, needle, text)
@@ -241,27 +111,54 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             super::after_write(dir, topic);
             Ok(result)
         }
+        "correct" => {
+            let topic = arg_ref(args, "topic");
+            let idx_str = arg_ref(args, "index");
+            let idx: usize = idx_str.parse()
+                .map_err(|_| AmrError::InvalidArg(format!("correct needs an entry address or topic + index, got index '{idx_str}'")))?;
+            let result = crate::correct::run(dir, topic, idx, arg_ref(args, "text"),
+                Some(crate::provenance::Provenance::Agent))?;
+            super::after_write(dir, topic);
+            Ok(result)
+        }
         "read" => {
             let topic = arg_ref(args, "topic");
-            crate::topics::read_topic(dir, topic)
+            let from = match arg_str(args, "from").as_str() {
+                "" => 0,
+                f => f.parse::<usize>().map_err(|_| AmrError::InvalidArg(format!("invalid from: '{f}'")))?,
+            };
+            let count = match arg_str(args, "count").as_str() {
+                "" => crate::topics::DEFAULT_READ_COUNT,
+                "all" => usize::MAX,
+                c => c.parse::<usize>().map_err(|_| AmrError::InvalidArg(format!("invalid count: '{c}'")))?,
+            };
+            crate::topics::read_topic(dir, topic, from, Some(count))
         }
         "stats" => {
             let detail = arg_ref(args, "detail");
             match detail {
-                "tags" => crate::stats::list_tags(dir),
+                "tags" => crate::stats::list_tags(dir).map_err(Into::into),
+                "heatmap" => {
+                    let weeks = arg_ref(args, "weeks").parse().unwrap_or(12);
+                    let topic = Some(arg_ref(args, "topic")).filter(|t| !t.is_empty());
+                    let json = arg_ref(args, "format") == "json";
+                    crate::stats::heatmap(dir, weeks, topic, json)
+                }
                 "index" => {
-                    let guard = super::INDEX.read().map_err(|e| e.to_string())?;
-                    let data = match guard.as_ref() {
-                        Some(idx) => std::borrow::Cow::Borrowed(idx.data.as_slice()),
+                    let guard = super::read_index()?;
+                    let shared;
+                    let data = match guard.get() {
+                        Some(idx) => idx.bytes(),
                         None => {
                             drop(guard);
-                            std::borrow::Cow::Owned(std::fs::read(dir.join("index.bin"))
-                                .map_err(|e| format!("index.bin: {e}"))?)
+                            shared = crate::indexview::fresh(dir)?
+                                .ok_or_else(|| AmrError::NotFound("no index.bin".into()))?;
+                            shared.bytes()
                         }
                     };
-                    crate::binquery::index_info(&data)
+                    crate::binquery::index_info(data)
                 }
-                _ => crate::stats::stats_fast(dir),
+                _ => crate::stats::stats_fast(dir).map_err(Into::into),
             }
         }
         "entries" => {
@@ -269,48 +166,91 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             let idx_str = arg_ref(args, "index");
             if !idx_str.is_empty() {
                 let idx: usize = idx_str.parse()
-                    .map_err(|_| format!("invalid index: '{idx_str}'"))?;
-                crate::stats::get_entry(dir, topic, idx)
+                    .map_err(|_| AmrError::InvalidArg(format!("invalid index: '{idx_str}'")))?;
+                match arg_ref(args, "match_str") {
+                    "" => crate::stats::get_entry(dir, topic, idx),
+                    m => {
+                        let context = arg_ref(args, "context").parse::<usize>().unwrap_or(2);
+                        crate::stats::get_entry_lines(dir, topic, idx, m, context)
+                    }
+                }
             } else {
                 let m = arg_ref(args, "match_str");
                 let match_str = if m.is_empty() { None } else { Some(m) };
                 crate::stats::list_entries(dir, topic, match_str)
             }
         }
+        "share" => {
+            let topic = arg_ref(args, "topic");
+            let idx_str = arg_ref(args, "index");
+            let idx: usize = idx_str.parse()
+                .map_err(|_| AmrError::InvalidArg(format!("share needs an entry address or topic + index, got index '{idx_str}'")))?;
+            crate::share::run(dir, &crate::stats::single_topic(dir, topic)?, idx, arg_bool(args, "links"))
+        }
         "prune" => {
             let d = arg_ref(args, "days");
             let days = d.parse().unwrap_or(30u64);
-            crate::prune::run(dir, days, true)
+            crate::prune::run(dir, days, true).map_err(Into::into)
         }
         "compact" => {
             let mode = arg_ref(args, "mode");
             if mode == "migrate" {
                 let apply = arg_ref(args, "apply") == "true";
-                return crate::migrate::run(dir, apply);
+                return Ok(crate::migrate::run(dir, apply)?);
             }
             let log = arg_bool(args, "log");
             if log {
-                let result = crate::datalog::compact_log(dir)?;
+                let result = {
+                    let _lock = crate::lock::FileLock::acquire(dir)?;
+                    crate::datalog::compact_log(dir)?
+                };
                 super::after_write(dir, "");
                 return Ok(result);
             }
             let topic = arg_ref(args, "topic");
             let apply = arg_ref(args, "apply") == "true";
             let result = if topic.is_empty() {
-                crate::compact::scan(dir)
+                crate::compact::scan(dir)?
             } else {
-                crate::compact::run(dir, topic, apply)
-            }?;
+                crate::compact::run(dir, topic, apply)?
+            };
             if apply { super::after_write(dir, ""); }
             Ok(result)
         }
-        "export" => crate::export::export(dir),
+        "export" => {
+            let opt = |k: &str| Some(arg_ref(args, k)).filter(|v| !v.is_empty()).map(str::to_string);
+            let scope = crate::export::Scope { topic: opt("topic"), query: opt("query"), tag: opt("tag") };
+            if arg_ref(args, "rag") == "true" {
+                let d = crate::export::Chunking::default();
+                let c = crate::export::Chunking {
+                    max_tokens: arg_ref(args, "chunk_tokens").parse().unwrap_or(d.max_tokens),
+                    overlap: arg_ref(args, "overlap").parse().unwrap_or(d.overlap),
+                };
+                return crate::export::export_rag(dir, &scope, &c).map_err(Into::into);
+            }
+            crate::export::export_scoped(dir, &scope).map_err(Into::into)
+        }
         "import" => {
-            let json = arg_ref(args, "json");
-            let result = crate::export::import(dir, json)?;
+            let csv = arg_ref(args, "csv");
+            let result = if csv.is_empty() {
+                crate::export::import(dir, arg_ref(args, "json"))?
+            } else {
+                let topic = Some(arg_ref(args, "topic")).filter(|t| !t.is_empty());
+                crate::csvimport::import(dir, csv, arg_ref(args, "map"), topic)?
+            };
             super::after_write(dir, "");
             Ok(result)
         }
+        "explain" => {
+            let query = arg_ref(args, "query");
+            let filter = build_filter(dir, args)?;
+            let limit = arg_ref(args, "limit").parse::<usize>().unwrap_or(5);
+            let guard = super::read_index()?;
+            let idx = guard.get().map(|i| i.bytes());
+            let result = crate::explain::run(dir, query, &filter, limit, idx);
+            drop(guard);
+            result.map_err(Into::into)
+        }
         "xref" => {
             let topic = arg_ref(args, "topic");
             crate::xref::refs_for(dir, topic)
@@ -327,8 +267,10 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             let into = arg_ref(args, "into");
             let result = crate::edit::merge_topics(dir, from, into)?;
             super::after_write(dir, into);
+            crate::webhook::emit_result(dir, "merge", into, &result);
             Ok(result)
         }
+        "file_inbox" => super::curate::file_inbox(dir, args),
         "tag" => {
             let topic = arg_ref(args, "topic");
             let idx_str = arg_ref(args, "index");
@@ -336,7 +278,7 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             let add_tags = arg_ref(args, "tags");
             let rm_tags = arg_ref(args, "remove");
             let idx = if !idx_str.is_empty() {
-                Some(idx_str.parse::<usize>().map_err(|_| format!("invalid index: '{idx_str}'"))?)
+                Some(idx_str.parse::<usize>().map_err(|_| AmrError::InvalidArg(format!("invalid index: '{idx_str}'")))?)
             } else { None };
             let needle = if needle.is_empty() { None } else { Some(needle) };
             let add = if add_tags.is_empty() { None } else { Some(add_tags) };
@@ -345,11 +287,52 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             super::after_write(dir, topic);
             Ok(result)
         }
+        "reorder" => {
+            let topic = arg_ref(args, "topic");
+            let order = arg_ref(args, "order");
+            if order.trim().is_empty() && !arg_bool(args, "clear") {
+                return Err(AmrError::InvalidArg("order is required (or clear=true to drop the manual order)".into()));
+            }
+            let result = crate::order::reorder(dir, topic, order)?;
+            super::after_write(dir, topic);
+            Ok(result)
+        }
+        "collection" => {
+            let name = arg_ref(args, "name");
+            let entries = arg_ref(args, "entries");
+            match arg_ref(args, "action") {
+                "list" => crate::collection::list(dir),
+                "" if name.is_empty() => crate::collection::list(dir),
+                "" | "show" => crate::collection::show(dir, name),
+                "add" => crate::collection::add(dir, name, entries),
+                "remove" => crate::collection::remove(dir, name, entries),
+                a => Err(AmrError::InvalidArg(format!("action: expected list, show, add or remove, got '{a}'"))),
+            }
+        }
         "reindex" => {
-            let (result, bytes) = crate::inverted::rebuild_and_persist(dir)?;
-            super::store_index(bytes);
+            let (result, _) = crate::inverted::rebuild_and_persist(dir)?;
+            super::load_index(dir);
             Ok(result)
         }
+        "warm" => crate::warm::run(dir, arg_bool(args, "corpus")),
+        "reload_config" => super::reload_config(dir),
+        "ambient_preview" => {
+            let file = arg_ref(args, "file");
+            if file.is_empty() { return Err(AmrError::InvalidArg("file is required".into())); }
+            let syms: Vec<&str> = arg_ref(args, "syms").split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+            let guard = super::read_index()?;
+            let shared;
+            let data = match guard.get() {
+                Some(idx) => idx.bytes(),
+                None => {
+                    drop(guard);
+                    shared = crate::indexview::fresh(dir)?
+                        .ok_or_else(|| AmrError::NotFound("no index.bin".into()))?;
+                    shared.bytes()
+                }
+            };
+            crate::ambient::ambient_preview(dir, data, file, &syms)
+        }
         "session" => {
             let action = arg_ref(args, "action");
             match action {
@@ -405,7 +388,7 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
                         out.push_str("no active session\n");
                     }
                     // Also show store log
-                    let log = super::SESSION_LOG.lock().map_err(|e| e.to_string())?;
+                    let log = super::SESSION_LOG.lock().map_err(|e| AmrError::Locked(e.to_string()))?;
                     if !log.is_empty() {
                         out.push_str(&format!("\n{} stores this session:\n", log.len()));
                         for entry in log.iter() {
@@ -418,6 +401,28 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
                 }
             }
         }
+        "answer" => super::search::answer(dir, args),
+        "semantic_search" => super::search::semantic(dir, args),
+        "timeline" => crate::timeline::run(dir, arg_ref(args, "query")),
+        "tail" => {
+            let topic = arg_ref(args, "topic");
+            let topic = if topic.is_empty() { None } else { Some(crate::pattern::TopicPattern::parse(topic)?) };
+            let limit = arg_ref(args, "limit").parse::<usize>().unwrap_or(5);
+            let wait = arg_ref(args, "wait").parse::<u64>().unwrap_or(0);
+            let tag = Some(arg_ref(args, "tag")).filter(|t| !t.is_empty()).map(str::to_string);
+            let w = crate::tail::Watch { topic, tag, json: arg_ref(args, "format") == "json", dir };
+            crate::tail::run(&w, Some(arg_ref(args, "cursor")), limit, wait)
+        }
+        "define" => {
+            let result = crate::glossary::define(dir, arg_ref(args, "term"))?;
+            super::after_write(dir, crate::glossary::TOPIC);
+            Ok(result)
+        }
+        "terms" => {
+            let limit = arg_ref(args, "limit").parse().unwrap_or(crate::vocab::DEFAULT_LIMIT);
+            let guard = super::read_index()?;
+            crate::vocab::run(dir, guard.get().map(|i| i.bytes()), arg_ref(args, "prefix"), limit)
+        }
         "brief" => {
             let query = arg_ref(args, "query");
             if query.is_empty() {
@@ -431,7 +436,11 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
                 let since_hours = since_str.parse::<u64>().ok();
                 let focus_str = arg_ref(args, "focus");
                 let focus = if focus_str.is_empty() { None } else { Some(focus_str) };
-                crate::reconstruct::run(dir, query, detail, since_hours, focus)
+                if arg_bool(args, "diff") {
+                    crate::reconstruct::run_diff(dir, query, since_hours, focus).map_err(Into::into)
+                } else {
+                    crate::reconstruct::run(dir, query, detail, since_hours, focus).map_err(Into::into)
+                }
             }
         }
         "trace" => {
@@ -440,7 +449,7 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
             let path_str = arg_str(args, "path");
             let glob = arg_str(args, "glob");
             let glob = if glob.is_empty() { "*.rs" } else { glob.as_str() };
-            let p = Path::new(&path_str);
+            let p = &crate::codepath::Roots::parse(&path_str, crate::ignore::Ignore::load(dir))?;
             let result = match mode {
                 "codepath" => {
                     let ctx = arg_str(args, "context").parse::<usize>().unwrap_or(2);
@@ -488,91 +497,46 @@ pub fn dispatch(name: &str, args: Option<&Value>, dir: &Path) -> Result<String,
                     _ => "structural,callgraph,raw-data",
                 };
                 let tags = if tags_str.is_empty() { default_tags } else { tags_str.as_str() };
-                let source = format!("{}/**/{}", path_str, glob);
-                crate::store::run_full(dir, &store_topic, &result, Some(tags), true, Some(&source))?;
+                let source = p.source_glob(glob);
+                crate::store::run_full(dir, &store_topic, &result, Some(tags), true, Some(&source),
+                    Some(crate::provenance::Provenance::Trace))?;
                 super::after_write(dir, &store_topic);
+                let graph = crate::callgraph::graph(p, glob)?;
+                let note = crate::tracegraph::save(dir, &store_topic, &graph)?;
+                return Ok(format!("{result}\n{note}\n"));
             }
             Ok(result)
         }
+        "impact" => {
+            let glob = arg_ref(args, "glob");
+            let glob = if glob.is_empty() { "*.rs" } else { glob };
+            let depth = arg_str(args, "depth").parse::<usize>().unwrap_or(3);
+            crate::impact::run(dir, arg_ref(args, "target"), arg_ref(args, "path"), glob, depth)
+        }
+        "trace_query" => {
+            let depth = arg_str(args, "depth").parse::<usize>().unwrap_or(2);
+            let direction = arg_ref(args, "direction");
+            let direction = if direction.is_empty() { "both" } else { direction };
+            crate::tracegraph::query(dir, arg_ref(args, "topic"), arg_ref(args, "symbol"), direction, depth)
+        }
+        "decide" => super::curate::decide(dir, args),
+        "decisions" => super::curate::decisions(dir, args),
+        "review" => super::curate::review(dir, args),
         "graph" => {
             let focus = arg_ref(args, "focus");
-            if focus.is_empty() { crate::depgraph::run(dir) }
-            else { crate::depgraph::run_focused(dir, focus) }
+            if focus.is_empty() { crate::depgraph::run(dir).map_err(Into::into) }
+            else { crate::depgraph::run_focused(dir, focus).map_err(Into::into) }
         }
         "stale" => {
             let refresh = arg_bool(args, "refresh");
-            if refresh {
-                crate::stats::refresh_stale(dir)
+            if arg_bool(args, "orphans") {
+                crate::stats::check_orphans(dir).map_err(Into::into)
+            } else if refresh {
+                crate::stats::refresh_stale(dir).map_err(Into::into)
             } else {
-                crate::stats::check_stale(dir)
+                crate::stats::check_stale(dir).map_err(Into::into)
             }
         }
-        _ => Err(format!("unknown tool: {name}")),
-    }
-}
-
-/// Borrow string value from args — zero allocation for the common case (string values).
-/// Returns "" if key missing or value is not a string.
-fn arg_ref<'a>(args: Option<&'a Value>, key: &str) -> &'a str {
-    args.and_then(|a| a.get(key))
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-}
-
-/// Owned string from args — needed for Num/Bool value conversion.
-fn arg_str(args: Option<&Value>, key: &str) -> String {
-    args.and_then(|a| a.get(key))
-        .map(|v| match v {
-            Value::Str(s) => s.clone(),
-            Value::Num(n) => if n.fract() == 0.0 { format!("{}", *n as i64) } else { n.to_string() },
-            Value::Bool(b) => if *b { "true" } else { "false" }.into(),
-            _ => String::new(),
-        })
-        .unwrap_or_default()
-}
-
-fn arg_bool(args: Option<&Value>, key: &str) -> bool {
-    let s = arg_ref(args, key);
-    s == "true" || s == "1"
-}
-
-fn build_filter(args: Option<&Value>) -> crate::search::Filter {
-    let after_raw = arg_ref(args, "after");
-    let before_raw = arg_ref(args, "before");
-    // days/hours shortcuts: convert to after= date if after is not set
-    let after = if after_raw.is_empty() {
-        let days = arg_ref(args, "days").parse::<u64>().ok();
-        let hours = arg_ref(args, "hours").parse::<u64>().ok();
-        crate::time::relative_to_date(days, hours).unwrap_or_default()
-    } else {
-        crate::time::resolve_date_shortcut(after_raw)
-    };
-    let before = crate::time::resolve_date_shortcut(before_raw);
-    let tag = arg_ref(args, "tag");
-    let topic = arg_ref(args, "topic");
-    let mode = match arg_ref(args, "mode") {
-        "or" => crate::search::SearchMode::Or,
-        _ => crate::search::SearchMode::And,
-    };
-    crate::search::Filter {
-        after: if after.is_empty() { None } else { crate::time::parse_date_days(&after) },
-        before: if before.is_empty() { None } else { crate::time::parse_date_days(&before) },
-        tag: if tag.is_empty() { None } else { Some(tag.to_string()) },
-        topic: if topic.is_empty() { None } else { Some(topic.to_string()) },
-        mode,
-    }
-}
-
-/// Phase-aware default search limit from session state.
-/// Research phase: 10 (broad exploration). Build phase: 5 (focused).
-/// Debug phase: 8 (targeted). Unknown: None (use existing default).
-fn phase_aware_limit(dir: &Path) -> Option<usize> {
-    let session = crate::session::Session::load(dir)?;
-    match session.phase {
-        crate::session::Phase::Research => Some(10),
-        crate::session::Phase::Build => Some(5),
-        crate::session::Phase::Verify => Some(5),
-        crate::session::Phase::Debug => Some(8),
-        crate::session::Phase::Unknown => None,
+        _ => Err(AmrError::InvalidArg(format!("unknown tool: {name}"))),
     }
 }
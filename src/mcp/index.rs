@@ -0,0 +1,157 @@
+//! The server's in-memory index: one shared index.bin snapshot for the
+//! startup dir, swapped on rebuild while running queries keep theirs, plus
+//! the startup self-check and the per-thread foreign-dir flag that keeps
+//! calls against other dirs off the snapshot.
+
+use crate::error::AmrError;
+use crate::json::Value;
+use std::cell::Cell;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// The shared index.bin mapping (see indexview.rs) at the generation last
+/// loaded. Readers clone the Arc and let go of the lock at once, so a query
+/// runs against the snapshot it started with: a rebuild maps the new index
+/// beside it and the swap only waits for other swaps, never for queries.
+/// The old mapping goes away when its last query drops it.
+static INDEX: RwLock<Option<Arc<crate::indexview::IndexMap>>> = RwLock::new(None);
+
+/// index.bin as found on disk, before startup touches it.
+pub(super) enum IndexState { Current, Missing, Version(u32), Corrupt(String) }
+
+impl IndexState {
+    pub(super) fn of(dir: &Path) -> Self {
+        let Ok(data) = std::fs::read(dir.join("index.bin")) else { return IndexState::Missing };
+        let Err(e) = crate::binquery::read_header(&data) else { return IndexState::Current };
+        // Valid magic with a different version = built by another binary release
+        let version = (data.len() >= 8 && data[..4] == crate::format::MAGIC)
+            .then(|| u32::from_le_bytes([data[4], data[5], data[6], data[7]]));
+        match version {
+            Some(v) if v != crate::format::VERSION => IndexState::Version(v),
+            _ => IndexState::Corrupt(e.to_string()),
+        }
+    }
+}
+
+/// Startup self-check result, sent once as `_meta` on the first tools/list.
+static STARTUP_REPORT: Mutex<Option<String>> = Mutex::new(None);
+
+/// The startup report, if startup had anything to report; only once.
+pub(super) fn take_startup_report() -> Option<String> {
+    STARTUP_REPORT.lock().ok().and_then(|mut r| r.take())
+}
+
+/// Record what startup found and did when index.bin wasn't usable as-is:
+/// an index from another binary version, a corrupt file, or none at all.
+pub(super) fn startup_check(dir: &Path, before: IndexState, rebuilt: Result<String, String>) {
+    let found = match &before {
+        IndexState::Current => return,
+        IndexState::Missing => "missing".to_string(),
+        IndexState::Version(v) => format!("format v{v}"),
+        IndexState::Corrupt(e) => format!("corrupt: {e}"),
+    };
+    let (action, detail) = match rebuilt {
+        Ok(msg) => ("rebuilt", msg),
+        Err(e) if !crate::config::data_log_exists(dir) => ("unavailable", format!("no data.log to rebuild from: {e}")),
+        Err(e) => ("rebuild_failed", e),
+    };
+    eprintln!("amaranthine: index.bin {found} (expected format v{}) → {action}: {detail}",
+        crate::format::VERSION);
+    let report = Value::Obj(vec![
+        ("binary".into(), Value::Str(env!("CARGO_PKG_VERSION").into())),
+        ("indexFormat".into(), Value::Num(crate::format::VERSION as f64)),
+        ("found".into(), Value::Str(found)),
+        ("action".into(), Value::Str(action.into())),
+        ("detail".into(), Value::Str(detail)),
+    ]);
+    if let Ok(mut r) = STARTUP_REPORT.lock() { *r = Some(report.to_string()); }
+}
+
+/// Validate existing index.bin; if corrupted or wrong version, rebuild from data.log.
+/// Called on startup before first query, and on any index read failure.
+pub(crate) fn recover_index(dir: &Path) {
+    let index_path = dir.join("index.bin");
+    let needs_rebuild = match std::fs::read(&index_path) {
+        Ok(data) => crate::binquery::read_header(&data).is_err(),
+        Err(_) => true,
+    };
+    if needs_rebuild {
+        eprintln!("amaranthine: index.bin invalid, rebuilding from data.log...");
+        match crate::inverted::rebuild_and_persist(dir) {
+            Ok((msg, _)) => {
+                eprintln!("amaranthine: {}", msg.lines().next().unwrap_or("rebuilt"));
+                load_index(dir);
+            }
+            Err(e) => eprintln!("amaranthine: rebuild failed: {e}"),
+        }
+    }
+}
+
+/// Map the published index.bin without rebuilding.
+pub(crate) fn load_index(dir: &Path) {
+    if let Ok(Some(map)) = crate::indexview::view(dir) {
+        store_index(map);
+    }
+}
+
+fn store_index(map: Arc<crate::indexview::IndexMap>) {
+    if serving_foreign() { return; }
+    // Swap under the lock; the old mapping is released (unmapped, if no query
+    // holds it) after the lock is let go
+    let old = INDEX.write().ok().and_then(|mut guard| guard.replace(map));
+    drop(old);
+}
+
+/// The current index snapshot; None while serving a foreign dir or before
+/// the first load.
+fn snapshot() -> Option<Arc<crate::indexview::IndexMap>> {
+    if serving_foreign() { return None; }
+    INDEX.read().ok().and_then(|guard| guard.clone())
+}
+
+/// Borrow cached index data via closure. Returns None if no index loaded.
+/// Runs on a snapshot, outside the lock.
+pub(crate) fn with_index<F, R>(f: F) -> Option<R>
+where F: FnOnce(&[u8]) -> R {
+    snapshot().map(|map| f(map.bytes()))
+}
+
+/// A query's index snapshot; empty while serving a foreign dir, so callers
+/// fall back to the corpus scan or that dir's index.bin.
+pub(super) struct IndexGuard(Option<Arc<crate::indexview::IndexMap>>);
+
+impl IndexGuard {
+    pub(super) fn get(&self) -> Option<&crate::indexview::IndexMap> { self.0.as_deref() }
+}
+
+pub(super) fn read_index() -> Result<IndexGuard, AmrError> {
+    Ok(IndexGuard(snapshot()))
+}
+
+thread_local! {
+    /// Set while a tools/call runs against a `dir` override. The in-memory
+    /// index only ever holds the startup dir's data.
+    static FOREIGN: Cell<bool> = const { Cell::new(false) };
+}
+
+pub(super) fn serving_foreign() -> bool { FOREIGN.with(|f| f.get()) }
+
+pub(super) fn with_foreign_dir<R>(f: impl FnOnce() -> R) -> R {
+    FOREIGN.with(|c| c.set(true));
+    let out = f();
+    FOREIGN.with(|c| c.set(false));
+    out
+}
+
+/// Bring the served index up to date with data.log before a read, so a read
+/// after a write always sees it. Write tools skip this, so a burst of writes
+/// still costs one rebuild, at the next read. Changes from other processes are
+/// picked up too: if someone else already rebuilt, this only remaps their
+/// index.bin, and concurrent readers wait on one rebuild (see indexview.rs).
+/// Queries already running keep their snapshot while the new one is swapped in.
+pub(crate) fn ensure_index_fresh(dir: &Path) {
+    if serving_foreign() { return; }
+    let Ok(Some(map)) = crate::indexview::fresh(dir) else { return };
+    let held = snapshot().map(|m| m.generation());
+    if held != Some(map.generation()) { store_index(map); }
+}
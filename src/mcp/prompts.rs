@@ -0,0 +1,88 @@
+//! MCP prompts: curated workflow templates filled with live knowledge-base data.
+//! prompts/get interpolates the current topic list / topic contents / recent
+//! entries, so a frontend can offer them as one-click actions.
+
+use crate::error::AmrError;
+use crate::json::Value;
+use std::path::Path;
+
+/// (name, description, [(arg, description, required)])
+type PromptDef = (&'static str, &'static str, &'static [(&'static str, &'static str, bool)]);
+
+const PROMPTS: &[PromptDef] = &[
+    ("summarize-topic", "Summarize everything stored in one topic",
+        &[("topic", "Topic name", true)]),
+    ("session-kickoff", "Start a session from the topic list and recent entries",
+        &[("hours", "Look-back window for recent entries (default: 24)", false)]),
+    ("store-decision", "Record a decision with rationale in the right topic",
+        &[("decision", "The decision that was made", true),
+          ("topic", "Target topic (default: pick from the topic list)", false)]),
+];
+
+/// prompts/list result. Static — arguments are declared, data is filled at get time.
+pub fn list_json() -> String {
+    let prompts = PROMPTS.iter().map(|(name, desc, args)| Value::Obj(vec![
+        ("name".into(), Value::Str((*name).into())),
+        ("description".into(), Value::Str((*desc).into())),
+        ("arguments".into(), Value::Arr(args.iter().map(|(a, d, req)| Value::Obj(vec![
+            ("name".into(), Value::Str((*a).into())),
+            ("description".into(), Value::Str((*d).into())),
+            ("required".into(), Value::Bool(*req)),
+        ])).collect())),
+    ])).collect();
+    Value::Obj(vec![("prompts".into(), Value::Arr(prompts))]).to_string()
+}
+
+/// prompts/get result: a single user message with live data interpolated.
+pub fn get_json(dir: &Path, name: &str, args: Option<&Value>) -> Result<String, AmrError> {
+    let arg = |k: &str| args.and_then(|a| a.get(k)).and_then(|v| v.as_str())
+        .map(str::trim).filter(|s| !s.is_empty());
+    let (desc, text) = match name {
+        "summarize-topic" => {
+            let topic = arg("topic").ok_or_else(|| AmrError::InvalidArg("topic required".into()))?;
+            let body = crate::topics::read_topic(dir, topic, 0, None)?;
+            (format!("Summary of topic '{topic}'"), format!(
+                "Summarize the amaranthine topic '{topic}' below. Lead with the current state, \
+                 then key decisions and open problems. Flag entries that contradict each other \
+                 or look outdated.\n\n{body}"))
+        }
+        "session-kickoff" => {
+            let hours = match arg("hours") {
+                Some(h) => h.parse::<u64>()
+                    .map_err(|_| AmrError::InvalidArg(format!("hours: not a number: {h}")))?,
+                None => 24,
+            };
+            let topics = crate::topics::list_compact(dir)?;
+            let recent = crate::topics::recent_hours(dir, hours, true)?;
+            ("Session kickoff".into(), format!(
+                "You are starting a session with amaranthine as persistent memory. Review the \
+                 topics and recent work below, say what was in progress, and propose next steps. \
+                 Use the search and brief tools before re-deriving anything already stored.\n\n\
+                 ## Topics\n{topics}\n## Last {hours}h\n{recent}"))
+        }
+        "store-decision" => {
+            let decision = arg("decision").ok_or_else(|| AmrError::InvalidArg("decision required".into()))?;
+            let target = match arg("topic") {
+                Some(t) => format!("Store it in topic '{}'.", crate::config::sanitize_topic(t)),
+                None => format!("Pick the best existing topic from this list, or a new \
+                                 kebab-case name if none fits:\n{}", crate::topics::list_compact(dir)?),
+            };
+            ("Record a decision".into(), format!(
+                "Record this decision with the amaranthine store tool, tags \"decision\". \
+                 Write it as: the decision, why (alternatives considered and why they lost), \
+                 and what would make us revisit it. Search first and use revise instead if an \
+                 existing entry already covers it.\n\nDecision: {decision}\n\n{target}"))
+        }
+        _ => return Err(AmrError::InvalidArg(format!("unknown prompt: {name}"))),
+    };
+    Ok(Value::Obj(vec![
+        ("description".into(), Value::Str(desc)),
+        ("messages".into(), Value::Arr(vec![Value::Obj(vec![
+            ("role".into(), Value::Str("user".into())),
+            ("content".into(), Value::Obj(vec![
+                ("type".into(), Value::Str("text".into())),
+                ("text".into(), Value::Str(text)),
+            ])),
+        ])])),
+    ]).to_string())
+}
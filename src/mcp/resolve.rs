@@ -0,0 +1,82 @@
+//! Topic UIDs and entry addresses in tool args, rewritten to plain topic
+//! names and indexes before dispatch, so each tool only sees names.
+
+use crate::json::Value;
+use std::path::Path;
+use crate::error::AmrError;
+use super::args::{arg_bool, arg_ref};
+
+/// Topic args may be given as stable UIDs (`#` + 16 hex, from `topics uids=true`).
+/// Returns a copy of `args` with those swapped for names; None when there are none.
+pub(super) fn resolve_topic_uids(args: Option<&Value>, dir: &Path) -> Result<Option<Value>, AmrError> {
+    const TOPIC_ARGS: &[&str] = &["topic", "from", "into"];
+    let Some(a) = args else { return Ok(None) };
+    let uids: Vec<(&str, u64)> = TOPIC_ARGS.iter()
+        .filter_map(|k| Some((*k, crate::topicuid::parse(a.get(k)?.as_str()?)?)))
+        .collect();
+    if uids.is_empty() { return Ok(None); }
+    let mut out = a.clone();
+    for (key, uid) in uids {
+        let name = topic_for_uid(dir, uid).ok_or_else(|| AmrError::NotFound(
+            format!("no topic with uid {}", crate::topicuid::format(uid))))?;
+        out.set(key, Value::Str(name));
+    }
+    Ok(Some(out))
+}
+
+/// Expand an entry address (address.rs) passed as `topic` into topic + index
+/// for the single-entry tools, and address-form store `links` into topic:index.
+pub(super) fn resolve_entry_address(name: &str, args: Option<&Value>, dir: &Path) -> Result<Option<Value>, AmrError> {
+    let Some(a) = args else { return Ok(None) };
+    match name {
+        "entries" | "delete" | "revise" | "tag" | "append" | "append_entry" | "review" | "share" | "correct"
+        | "decisions" => {
+            let raw = arg_ref(args, "topic");
+            if !crate::address::is_address(raw) { return Ok(None); }
+            // On entries, match_str filters the addressed entry's lines
+            let match_str = name != "entries" && !arg_ref(args, "match_str").is_empty();
+            if !arg_ref(args, "index").is_empty() || match_str || arg_bool(args, "all") {
+                return Err(AmrError::InvalidArg(format!(
+                    "'{raw}' already names one entry; drop index/match_str/all")));
+            }
+            let (topic, idx) = resolve_address(dir, raw)?;
+            let mut out = a.clone();
+            out.set("topic", Value::Str(topic));
+            out.set("index", Value::Str(idx.to_string()));
+            Ok(Some(out))
+        }
+        "store" | "decide" => {
+            let links = arg_ref(args, "links");
+            if !links.contains("uid:") && !links.contains('#') { return Ok(None); }
+            let links = links.split_whitespace()
+                .map(|l| resolve_address(dir, l).map(|(t, i)| format!("{t}:{i}")))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut out = a.clone();
+            out.set("links", Value::Str(links.join(" ")));
+            Ok(Some(out))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Entry address → (topic name, 0-based index). The topic part may be a topic UID.
+pub(super) fn resolve_address(dir: &Path, raw: &str) -> Result<(String, usize), AmrError> {
+    let addr = crate::address::parse(raw)?;
+    let topic = match crate::topicuid::parse(addr.topic) {
+        Some(uid) => topic_for_uid(dir, uid).ok_or_else(|| AmrError::NotFound(
+            format!("no topic with uid {}", crate::topicuid::format(uid))))?,
+        None => addr.topic.to_string(),
+    };
+    let idx = crate::address::resolve(dir, &crate::address::Address { topic: &topic, sel: addr.sel })?;
+    Ok((topic, idx))
+}
+
+/// UID → topic name via the server index, falling back to a corpus scan.
+fn topic_for_uid(dir: &Path, uid: u64) -> Option<String> {
+    let from_index = super::read_index().ok().and_then(|g| {
+        g.get().and_then(|idx| crate::topicuid::topic_by_uid(idx.bytes(), uid)).map(|(_, name)| name)
+    });
+    from_index.or_else(|| crate::cache::with_corpus(dir, |cached| {
+        cached.iter().find(|e| crate::format::topic_uid(&e.topic) == uid).map(|e| e.topic.to_string())
+    }).ok().flatten())
+}
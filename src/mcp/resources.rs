@@ -0,0 +1,57 @@
+//! MCP resources: every topic is browsable as `amaranthine://topic/<name>`.
+//! resources/read returns the same markdown as the read_topic tool.
+
+use crate::error::AmrError;
+use crate::json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const TOPIC_PREFIX: &str = "amaranthine://topic/";
+
+/// resources/list result: one resource per topic, sorted by name.
+pub fn list_json(dir: &Path) -> Result<String, AmrError> {
+    if !crate::config::log_path(dir).exists() {
+        return Ok(r#"{"resources":[]}"#.into());
+    }
+    let resources = crate::cache::with_corpus(dir, |cached| {
+        // name → (entries, newest entry date)
+        let mut topics: BTreeMap<&str, (usize, i32, String)> = BTreeMap::new();
+        for e in cached {
+            let t = topics.entry(e.topic.as_str()).or_insert((0, i32::MIN, String::new()));
+            t.0 += 1;
+            if e.timestamp_min >= t.1 { t.1 = e.timestamp_min; t.2 = e.date_str(); }
+        }
+        topics.into_iter().map(|(name, (count, _, last))| Value::Obj(vec![
+            ("uri".into(), Value::Str(format!("{TOPIC_PREFIX}{name}"))),
+            ("name".into(), Value::Str(name.into())),
+            ("description".into(), Value::Str(format!("{count} entries, last updated {last}"))),
+            ("mimeType".into(), Value::Str("text/markdown".into())),
+        ])).collect::<Vec<_>>()
+    })?;
+    Ok(Value::Obj(vec![("resources".into(), Value::Arr(resources))]).to_string())
+}
+
+/// resources/templates/list result: the topic URI pattern, for clients that
+/// construct URIs instead of listing.
+pub fn templates_json() -> String {
+    Value::Obj(vec![("resourceTemplates".into(), Value::Arr(vec![Value::Obj(vec![
+        ("uriTemplate".into(), Value::Str(format!("{TOPIC_PREFIX}{{name}}"))),
+        ("name".into(), Value::Str("topic".into())),
+        ("description".into(), Value::Str("All entries in a topic, oldest first".into())),
+        ("mimeType".into(), Value::Str("text/markdown".into())),
+    ])]))]).to_string()
+}
+
+/// resources/read result for `uri`. Unknown topics are NotFound; anything
+/// outside the amaranthine://topic/ scheme is InvalidArg.
+pub fn read_json(dir: &Path, uri: &str) -> Result<String, AmrError> {
+    let name = uri.strip_prefix(TOPIC_PREFIX)
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| AmrError::InvalidArg(format!("unsupported resource uri: {uri}")))?;
+    let text = crate::topics::read_topic(dir, name, 0, None)?;
+    Ok(Value::Obj(vec![("contents".into(), Value::Arr(vec![Value::Obj(vec![
+        ("uri".into(), Value::Str(uri.into())),
+        ("mimeType".into(), Value::Str("text/markdown".into())),
+        ("text".into(), Value::Str(text)),
+    ])]))]).to_string())
+}
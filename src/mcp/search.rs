@@ -0,0 +1,93 @@
+//! Search-family tools: `search` at every detail level, `answer` and
+//! `semantic_search`.
+
+use crate::json::Value;
+use std::path::Path;
+use crate::error::AmrError;
+use super::args::{arg_bool, arg_ref, build_filter, phase_aware_limit};
+
+/// `search` at every detail level, federated over `dirs` when given.
+pub(super) fn search(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let query = arg_ref(args, "query");
+    let detail = arg_ref(args, "detail");
+    let filter = build_filter(dir, args)?;
+    // v10: Phase-aware default limit — build phase gets tighter results
+    let explicit_limit = arg_ref(args, "limit").parse::<usize>().ok();
+    let session_limit = if explicit_limit.is_none() {
+        phase_aware_limit(dir)
+    } else {
+        explicit_limit
+    };
+    let dirs = arg_ref(args, "dirs");
+    if !dirs.is_empty() {
+        for d in dirs.split(',').map(str::trim).filter(|d| !d.is_empty()) { super::check_allowed(dir, d)?; }
+        let dirs = crate::config::resolve_dirs(dirs)?;
+        return crate::search::run_federated(&dirs, query, session_limit, &filter);
+    }
+    match detail {
+        "count" => crate::search::count(dir, query, &filter),
+        "topics" => crate::search::run_topics(dir, query, &filter),
+        "grouped" => {
+            let guard = super::read_index()?;
+            let idx = guard.get().map(|i| i.bytes());
+            let result = crate::search::run_grouped(dir, query, session_limit, &filter, idx);
+            drop(guard);
+            result
+        }
+        "index" => {
+            let limit = explicit_limit.unwrap_or_else(|| session_limit.unwrap_or(10));
+            let guard = super::read_index()?;
+            let shared;
+            let data = match guard.get() {
+                Some(idx) => idx.bytes(),
+                None => {
+                    drop(guard);
+                    shared = crate::indexview::fresh(dir)?
+                        .ok_or_else(|| AmrError::NotFound("no index.bin".into()))?;
+                    shared.bytes()
+                }
+            };
+            let mut out = crate::binquery::search(data, query, limit)?;
+            if let Some(r) = crate::expand::take_resolution() {
+                if !out.ends_with('\n') { out.push('\n'); }
+                out.push_str("resolved: ");
+                out.push_str(&r);
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        _ => {
+            let guard = super::read_index()?;
+            let idx = guard.get().map(|i| i.bytes());
+            let result = match detail {
+                "full" => crate::search::run(dir, query, true, session_limit, &filter, idx),
+                "brief" => crate::search::run_brief(dir, query, session_limit, &filter, idx),
+                _ => crate::search::run_medium(dir, query, session_limit, &filter, idx),
+            };
+            drop(guard);
+            if !arg_bool(args, "include_history") { return result; }
+            let history = crate::history::search(dir, query, filter.mode == crate::search::SearchMode::Or,
+                explicit_limit.unwrap_or(crate::history::DEFAULT_LIMIT))?;
+            Ok(format!("{}{history}", result?))
+        }
+    }
+}
+
+/// `answer`: the best-matching entries condensed into one answer.
+pub(super) fn answer(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let filter = build_filter(dir, args)?;
+    let limit = arg_ref(args, "limit").parse::<usize>().unwrap_or(5);
+    let guard = super::read_index()?;
+    let idx = guard.get().map(|i| i.bytes());
+    let result = crate::answer::run(dir, arg_ref(args, "query"), limit, &filter, idx);
+    drop(guard);
+    result
+}
+
+/// `semantic_search`: embeddings blended with BM25 (embed.rs).
+pub(super) fn semantic(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let filter = build_filter(dir, args)?;
+    let limit = arg_ref(args, "limit").parse::<usize>().unwrap_or(crate::embed::DEFAULT_LIMIT);
+    let weight = arg_ref(args, "weight").parse::<f64>().ok();
+    crate::embed::run(dir, arg_ref(args, "query"), limit, weight, &filter)
+}
@@ -0,0 +1,148 @@
+//! `store` and `batch`: the write tools that add entries.
+
+use crate::json::Value;
+use std::path::Path;
+use crate::error::AmrError;
+use super::args::{arg_bool, arg_ref};
+
+/// `store`: one entry, with store hooks and the webhook event.
+pub(super) fn store(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let topic = match arg_ref(args, "topic") { "" => crate::inbox::TOPIC, t => t };
+    let text = arg_ref(args, "text");
+    let tags = arg_ref(args, "tags");
+    let tags = if tags.is_empty() { None } else { Some(tags) };
+    let force = arg_bool(args, "force");
+    let terse = arg_bool(args, "terse");
+    let source = arg_ref(args, "source");
+    let source = if source.is_empty() { None } else { Some(source) };
+    let conf_str = arg_ref(args, "confidence");
+    let confidence = conf_str.parse::<f64>().ok().filter(|c| *c >= 0.0 && *c <= 1.0);
+    let links = arg_ref(args, "links");
+    let links = if links.is_empty() { None } else { Some(links) };
+    let provenance = match arg_ref(args, "provenance") {
+        "" => crate::provenance::Provenance::Agent,
+        p => crate::provenance::Provenance::parse(p).ok_or_else(|| AmrError::InvalidArg(format!(
+            "provenance: expected agent, human, imported or trace, got '{p}'")))?,
+    };
+    let (mut result, entry) = crate::store::run_entry(dir, topic, text, &crate::store::StoreOptions {
+        tags, source, confidence, links, force, provenance: Some(provenance),
+    })?;
+    // pre_store may have moved, rewritten or retagged it
+    let topic = entry.topic.as_str();
+    if topic == crate::inbox::TOPIC && arg_ref(args, "topic").is_empty() {
+        result.push_str("\n  unfiled: file_inbox suggests a topic for it");
+    }
+    super::after_write(dir, topic);
+    super::log_session(format!("[{}] {}", topic,
+        result.lines().next().unwrap_or("stored")));
+    let tags: Vec<&str> = entry.tags.as_deref()
+        .map(|t| t.split(',').map(str::trim).filter(|t| !t.is_empty()).collect()).unwrap_or_default();
+    // Held entries stay hidden until approved, webhooks included
+    if !crate::review::holds(dir, entry.provenance) {
+        crate::webhook::emit(dir, &crate::webhook::Event { kind: "store", topic,
+            detail: result.lines().next().unwrap_or("stored"), tags: &tags, entry: Some(&entry.text) });
+    }
+    if terse {
+        Ok(result.lines().next().unwrap_or(&result).to_string())
+    } else {
+        Ok(result)
+    }
+}
+
+/// `batch`: up to 30 entries under one lock and one fsync, deduped within the batch.
+pub(super) fn batch(dir: &Path, args: Option<&Value>) -> Result<String, AmrError> {
+    let verbose = arg_bool(args, "verbose");
+    let items = args.and_then(|a| a.get("entries"))
+        .and_then(|v| match v { Value::Arr(a) => Some(a), _ => None })
+        .ok_or("entries must be an array")?;
+    if items.len() > 30 {
+        return Err(AmrError::InvalidArg(format!(
+            "batch too large ({} entries, max 30). Split into smaller batch calls.",
+            items.len()
+        )));
+    }
+    let _lock = crate::lock::FileLock::acquire(dir)?;
+    // F3: Open file once, write N entries, fsync once (was N opens + N fsyncs)
+    crate::config::ensure_dir(dir)?;
+    let log_path = crate::datalog::ensure_log(dir)?;
+    let mut log_file = std::fs::OpenOptions::new().append(true).open(&log_path)
+        .map_err(|e| AmrError::io("open data.log", e))?;
+    let mut ok_count = 0;
+    let mut results = Vec::new();
+    let mut seen: Vec<(String, String)> = Vec::new();
+    let mut batch_tokens: Vec<(String, crate::fxhash::FxHashSet<String>)> = Vec::new();
+    let held = crate::review::holds(dir, Some(crate::provenance::Provenance::Agent));
+    'batch: for (i, item) in items.iter().enumerate() {
+        let topic = item.get("topic").and_then(|v| v.as_str()).unwrap_or("");
+        let text = item.get("text").and_then(|v| v.as_str()).unwrap_or("");
+        let tags = item.get("tags").and_then(|v| v.as_str());
+        let held_tags = held.then(|| match tags {
+            Some(t) if !t.is_empty() => format!("{t}, {}", crate::review::TAG),
+            _ => crate::review::TAG.to_string(),
+        });
+        let tags = held_tags.as_deref().or(tags);
+        let source = item.get("source").and_then(|v| v.as_str());
+        if topic.is_empty() || text.is_empty() {
+            results.push(format!("  [{}] skipped: missing topic or text", i + 1));
+            continue;
+        }
+        let key = (
+            topic.to_lowercase(),
+            text.chars().take(60).collect::<String>().to_lowercase(),
+        );
+        if seen.iter().any(|s| s.0 == key.0 && s.1 == key.1) {
+            results.push(format!("  [{}] skipped: duplicate within batch", i + 1));
+            continue;
+        }
+        seen.push(key);
+        // Token-based semantic dupe check within batch
+        let new_tokens: crate::fxhash::FxHashSet<String> = crate::text::tokenize(text)
+            .into_iter().filter(|t| t.len() >= 3).collect();
+        if new_tokens.len() >= 6 {
+            let mut is_dupe = false;
+            for (prev_topic, prev_tokens) in &batch_tokens {
+                if *prev_topic != topic { continue; }
+                let intersection = new_tokens.iter()
+                    .filter(|t| prev_tokens.contains(*t)).count();
+                let union = new_tokens.len() + prev_tokens.len() - intersection;
+                if union > 0 && intersection as f64 / union as f64 > 0.70 {
+                    results.push(format!("  [{}] skipped: similar to earlier batch entry", i + 1));
+                    is_dupe = true;
+                    break;
+                }
+            }
+            if is_dupe { continue 'batch; }
+            batch_tokens.push((topic.to_string(), new_tokens));
+        }
+        let commit = crate::git::record(dir, source);
+        match crate::store::run_batch_entry_to(&mut log_file, topic, text, tags, source, commit.as_deref(),
+            Some(crate::provenance::Provenance::Agent)) {
+            Ok(msg) => {
+                ok_count += 1;
+                let first = msg.lines().next().unwrap_or(&msg);
+                results.push(format!("  [{}] {}", i + 1, first));
+                super::log_session(format!("[{}] {}", topic, first));
+            }
+            Err(e) => {
+                let e = e.to_string();
+                let first = e.lines().next().unwrap_or(&e);
+                results.push(format!("  [{}] err: {}", i + 1, first));
+            }
+        }
+    }
+    // Single fsync after all entries written
+    if ok_count > 0 {
+        let _ = log_file.sync_all();
+    }
+    drop(log_file);
+    drop(_lock);
+    if ok_count > 0 {
+        super::after_write(dir, "");
+    }
+    let pending = if held && ok_count > 0 { " (pending review)" } else { "" };
+    if verbose {
+        Ok(format!("batch: {ok_count}/{} stored{pending}\n{}", items.len(), results.join("\n")))
+    } else {
+        Ok(format!("batch: {ok_count}/{} stored{pending}", items.len()))
+    }
+}
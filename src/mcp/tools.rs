@@ -1,20 +1,43 @@
 use crate::json::Value;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 static TOOL_CACHE: Mutex<Option<Arc<str>>> = Mutex::new(None);
 
 /// Return pre-serialized JSON for the tools/list result object.
 /// Cached after first call — Arc avoids cloning the ~15KB JSON string.
-pub fn tool_list_json() -> Arc<str> {
+/// The `dir` argument is only advertised when `dir`'s config sets allowed_dirs.
+pub fn tool_list_json(dir: &Path) -> Arc<str> {
     if let Ok(guard) = TOOL_CACHE.lock() {
         if let Some(cached) = &*guard { return Arc::clone(cached); }
     }
-    let result = Value::Obj(vec![("tools".into(), tool_list())]);
+    let mut tools = tool_list();
+    if !crate::settings::load(dir).list("allowed_dirs").is_empty() { add_dir_arg(&mut tools); }
+    let result = Value::Obj(vec![("tools".into(), tools)]);
     let json: Arc<str> = result.to_string().into();
     if let Ok(mut guard) = TOOL_CACHE.lock() { *guard = Some(Arc::clone(&json)); }
     json
 }
 
+/// Drop the cached tool list (its `dir` argument depends on allowed_dirs).
+pub fn invalidate() {
+    if let Ok(mut guard) = TOOL_CACHE.lock() { *guard = None; }
+}
+
+/// Add the optional per-call knowledge-base override to every tool but _reload.
+fn add_dir_arg(tools: &mut Value) {
+    let Value::Arr(list) = tools else { return };
+    for t in list.iter_mut().filter(|t| t.get("name").and_then(|n| n.as_str()) != Some("_reload")) {
+        if let Some(props) = t.get_mut("inputSchema").and_then(|s| s.get_mut("properties")) {
+            props.set("dir", Value::Obj(vec![
+                ("type".into(), Value::Str("string".into())),
+                ("description".into(), Value::Str(
+                    "Knowledge-base directory to use instead of the server's (must be in allowed_dirs)".into())),
+            ]));
+        }
+    }
+}
+
 pub fn tool(name: &str, desc: &str, req: &[&str], props: &[(&str, &str, &str)]) -> Value {
     Value::Obj(vec![
         ("name".into(), Value::Str(name.into())),
@@ -92,71 +115,156 @@ const SEARCH_FILTER_PROPS: &[(&str, &str, &str)] = &[
     ("days", "string", "Number of days (shortcut for after=N-days-ago)"),
     ("hours", "string", "Number of hours (overrides days)"),
     ("tag", "string", "Only entries with this tag"),
-    ("topic", "string", "Limit search to a single topic"),
+    ("topic", "string", "Limit search to a topic, a glob ('iris-*') or an anchored /regex/ ('/iris-(core|ui)/')"),
+    ("provenance", "string", "Only entries written by: 'agent', 'human', 'imported' or 'trace'"),
+    ("lang", "string", "Only entries in this language: 'en', 'de', 'fr', 'es' or 'nl' (detected at store time)"),
+    ("boost_topics", "string", "Comma-separated topics (names, globs or /regex/) to rank higher without excluding others. Default: topics this session has been working in; 'none' to disable"),
     ("mode", "string", "Search mode: 'and' (default, all terms must match) or 'or' (any term matches)"),
+    ("min_match", "string", "AND mode: minimum query terms an entry must contain, as a count ('3') or percentage ('75%'). Default: all terms"),
+    ("hybrid", "string", "'true' runs the corpus scan beside the index and fuses the rankings (reciprocal rank fusion) when they disagree, e.g. for 1-char terms the index skips. Default: the hybrid_search setting"),
 ];
 
 pub fn tool_list() -> Value {
     let search_props: Vec<(&str, &str, &str)> = [
         ("query", "string", "Search query"),
         ("detail", "string", "Result detail level: 'full' (complete entry), 'medium' (default, 2 lines), 'brief' (topic+first line), 'count' (match count only), 'topics' (hits per topic), 'grouped' (results by topic), or 'index' (binary index search)"),
+        ("include_history", "string", "Set to 'true' to also search superseded (edited) and deleted versions still in data.log, listed after the results and marked as such. Full, medium and brief detail only"),
+        ("dirs", "string", "Comma-separated knowledge-base dirs (this one and/or allowed_dirs) to search together: results merged by score, brief, each labelled with its project. Ignores detail"),
+    ].into_iter()
+        .chain(SEARCH_FILTER_PROPS.iter().copied())
+        .collect();
+
+    let answer_props: Vec<(&str, &str, &str)> = [
+        ("query", "string", "Question or lookup (e.g. 'what is the merge window default')"),
+        ("limit", "string", "Max sentences (default: 5)"),
+    ].into_iter()
+        .chain(SEARCH_FILTER_PROPS.iter().copied().filter(|(k, _, _)| *k != "limit"))
+        .collect();
+
+    let semantic_props: Vec<(&str, &str, &str)> = [
+        ("query", "string", "What you are looking for, in your own words"),
+        ("limit", "string", "Max results (default: 10)"),
+        ("weight", "string", "Share of the score from embedding similarity, 0-1; the rest is BM25 (default: semantic_weight config, else 0.5)"),
+    ].into_iter()
+        .chain(SEARCH_FILTER_PROPS.iter().copied().filter(|(k, _, _)| !matches!(*k, "limit" | "mode" | "min_match" | "hybrid" | "boost_topics")))
+        .collect();
+
+    let explain_props: Vec<(&str, &str, &str)> = [
+        ("query", "string", "Search query to explain"),
     ].into_iter()
         .chain(SEARCH_FILTER_PROPS.iter().copied())
         .collect();
 
     Value::Arr(vec![
         // === PRIMARY TOOLS (use these most) ===
-        tool("store", "Store a timestamped knowledge entry under a topic. Warns on duplicate content.",
-            &["topic", "text"],
-            &[("topic", "string", "Topic name"),
+        tool("store", "Store a timestamped knowledge entry under a topic. Warns on duplicate content; suggests append (or auto-appends, per merge_policy config) when the text continues the latest entry. Without a topic the entry lands in 'inbox' for file_inbox to place.",
+            &["text"],
+            &[("topic", "string", "Topic name (default: inbox)"),
               ("text", "string", "Entry content"),
               ("tags", "string", "Comma-separated tags (e.g. 'bug,p0,iris')"),
               ("force", "string", "Set to 'true' to bypass duplicate detection"),
               ("source", "string", "Source file reference: 'path/to/file:line'. Enables staleness detection."),
               ("terse", "string", "Set to 'true' for minimal response (just first line)"),
               ("confidence", "string", "Confidence level 0.0-1.0 (default: 1.0). Affects search ranking."),
-              ("links", "string", "Space-separated references: 'topic:index topic:index'. Creates narrative links.")]),
+              ("links", "string", "Space-separated entry addresses: 'topic:index' or 'topic:uid:HEX'. Creates narrative links."),
+              ("provenance", "string", "Who wrote it: 'agent' (default) or 'human' when recording the user's own words")]),
         batch_tool(),
-        tool("search", "Search all knowledge files (case-insensitive). Splits CamelCase/snake_case. Falls back to OR when AND finds nothing. Use detail param: 'full' (complete entry), 'medium' (default, 2 lines), 'brief' (topic+first line), 'count' (match count only), 'topics' (hits per topic).",
+        tool("search", "Search all knowledge files (case-insensitive). Splits CamelCase/snake_case. Falls back to OR when AND finds nothing; relaxed or empty multi-term searches end with 'relaxed:', 'term_hits:' (entries per term) and 'unmatched:' lines to guide reformulation. Use detail param: 'full' (complete entry), 'medium' (default, 2 lines), 'brief' (topic+first line), 'count' (match count only), 'topics' (hits per topic).",
             &[], &search_props),
-        tool("brief", "One-shot compressed briefing for a topic or pattern. Primary way to load a mental model. Default output is a ~15-line summary; use detail='scan' for category one-liners, detail='full' for complete entries. Use since=N for entries from last N hours only. Supports glob patterns like 'iris-*' for multi-topic views. Without query: session start briefing (activity-weighted topics + velocity).",
+        tool("answer", "Extractive answer for a simple factual lookup: the sentences from the top matching entries that cover the most query terms, each cited by entry address, instead of whole entries. Quotes only, never generated text.",
+            &["query"], &answer_props),
+        tool("semantic_search", "Search by meaning: embedding cosine similarity blended with BM25, for knowledge stored in other words than the query's (e.g. 'renew credentials' for 'token refresh'). Needs embed_backend in config; entry vectors live in vectors.bin and are embedded on first use. Each result shows its blended, cosine and BM25 scores.",
+            &["query"], &semantic_props),
+        tool("define", "What a term means in this knowledge base: the sentence that introduces it ('X is…', 'X =', 'X:'), with its address, or its first mention. Keeps the generated glossary topic up to date.",
+            &["term"],
+            &[("term", "string", "Term to define (e.g. 'TF-mismatch', 'merge window')")]),
+        tool("terms", "Autocomplete against the corpus vocabulary: terms starting with a prefix, with the number of entries containing each, most common first.",
+            &[],
+            &[("prefix", "string", "Start of the term (e.g. 'sch'); empty lists the most common terms"),
+              ("limit", "string", "Max terms (default: 20)")]),
+        tool("brief", "One-shot compressed briefing for a topic or pattern. Primary way to load a mental model. Default output is a ~15-line summary; use detail='scan' for category one-liners, detail='full' for complete entries. Use since=N for entries from last N hours only. Supports glob patterns like 'iris-*' for multi-topic views. Use diff=true on repeated briefings to see only what changed since the last one. Without query: session start briefing (activity-weighted topics + velocity).",
             &[],
             &[("query", "string", "Topic, keyword, or glob pattern (e.g. 'iris-*', 'engine', 'amaranthine-codebase')"),
               ("detail", "string", "Output tier: 'summary' (default, ~15 lines), 'scan' (category one-liners), 'full' (complete entries)"),
               ("since", "string", "Only entries from last N hours (e.g. '24' for last day, '48' for 2 days)"),
               ("focus", "string", "Comma-separated category names to show (e.g. 'gotchas,invariants'). Only matching categories appear in output."),
-              ("compact", "string", "Set to 'true' for compact meta-briefing (top 5 topics only)")]),
-        tool("read", "Read the full contents of a specific topic file.",
+              ("compact", "string", "Set to 'true' for compact meta-briefing (top 5 topics only)"),
+              ("diff", "string", "Set to 'true' to show only entries new, changed or newly stale since the last diff briefing for this query")]),
+        tool("timeline", "How understanding of a subsystem evolved: matching entries oldest first, grouped by week, with supersede and chain relations inlined.",
+            &["query"],
+            &[("query", "string", "Topic name (substring), glob ('iris-*'), /regex/, or keywords every entry must contain")]),
+        tool("tail", "Follow data.log: entries (and deletions) appended by any process since `cursor` — watch what an agent stores during a long run. Without cursor: the last `limit` entries. Ends with the cursor for the next call. Waits up to `wait` seconds for something new; with a progressToken it watches the whole window and streams each arrival as a progress notification.",
+            &[],
+            &[("cursor", "string", "Cursor from the previous tail (generation:offset); omit to start"),
+              ("wait", "string", "Seconds to wait for new entries (default: 0, max: 60)"),
+              ("topic", "string", "Only these topics: name, glob ('iris-*') or /regex/"),
+              ("tag", "string", "Only entries with this tag"),
+              ("format", "string", "'json' for one JSON object per record (event, topic, uid, date, tags, source, text), ending with {\"cursor\": ...}"),
+              ("limit", "string", "Entries shown when starting without a cursor (default: 5)")]),
+        tool("read", "Read the entries of a topic, oldest first. Returns up to 50 entries per call; a partial read ends with the `from` to continue at.",
             &["topic"],
-            &[("topic", "string", "Topic name")]),
+            &[("topic", "string", "Topic name"),
+              ("from", "string", "First entry index to return (0-based, default: 0)"),
+              ("count", "string", "Max entries to return (default: 50, 'all' for no cap)")]),
 
         // === WRITE TOOLS ===
         tool("append", "Add text to the last entry in a topic (no new timestamp). Use when adding related info to a recent entry. Pass index/match_str/tag to target a specific entry instead.",
             &["topic", "text"],
-            &[("topic", "string", "Topic name"),
+            &[("topic", "string", "Topic name, or an entry address (topic:3, topic:uid:ab12cd) naming one entry"),
               ("text", "string", "Text to append"),
               ("index", "string", "Entry index number (from entries)"),
               ("match_str", "string", "Substring to find the entry to append to"),
               ("tag", "string", "Append to most recent entry with this tag")]),
-        tool("delete", "Delete entries or entire topic. Use index/match_str to target specific entries, or all=true for entire topic.",
+        tool("delete", "Delete entries or entire topic. Use index/match_str to target specific entries, or all=true for entire topic. A glob or /regex/ topic that matches several topics requires confirm=true.",
             &["topic"],
-            &[("topic", "string", "Topic name"),
+            &[("topic", "string", "Topic name, glob ('iris-*'), /regex/, or an entry address (topic:3, topic:uid:ab12cd) naming one entry"),
               ("index", "string", "Delete entry by index number (from entries)"),
               ("match_str", "string", "Delete entry matching this substring"),
-              ("all", "string", "Set to 'true' to delete entire topic")]),
+              ("all", "string", "Set to 'true' to delete entire topic"),
+              ("confirm", "string", "Set to 'true' to act on every topic a pattern matches")]),
         tool("revise", "Overwrite an existing entry's text (keeps timestamp). Adds [modified] marker.",
             &["topic", "text"],
-            &[("topic", "string", "Topic name"),
+            &[("topic", "string", "Topic name, or an entry address (topic:3, topic:uid:ab12cd) naming one entry"),
               ("match_str", "string", "Substring to find the entry to revise"),
               ("index", "string", "Entry index number (from entries)"),
               ("text", "string", "Replacement text for the entry")]),
+        tool("correct", "An entry turned out wrong (e.g. one the ambient hook injected): store the correction in its topic, link it, tag the wrong entry 'superseded', and stop it being injected again this session.",
+            &["topic", "text"],
+            &[("topic", "string", "Entry address of the wrong entry (topic:3, topic:uid:ab12cd), or a topic name with index"),
+              ("index", "string", "Entry index number, when topic is a plain name"),
+              ("text", "string", "The correct information")]),
         tool("tag", "Add or remove tags on an existing entry.",
             &["topic", "tags"],
-            &[("topic", "string", "Topic name"),
+            &[("topic", "string", "Topic name, or an entry address (topic:3, topic:uid:ab12cd) naming one entry"),
               ("index", "string", "Entry index number (from entries)"),
               ("match_str", "string", "Substring to find the entry"),
               ("tags", "string", "Comma-separated tags to add"),
               ("remove", "string", "Comma-separated tags to remove")]),
+        tool("decide", "Record a structured decision: status (proposed/accepted), the alternatives not chosen, links to the entries that implement it. supersedes marks an earlier decision superseded and chains the two.",
+            &["topic", "text"],
+            &[("topic", "string", "Topic name"),
+              ("text", "string", "The decision and why"),
+              ("status", "string", "proposed or accepted (default)"),
+              ("alternatives", "string", "Options considered and rejected, separated by '|'"),
+              ("links", "string", "Space-separated addresses of implementing entries (topic:3, topic:uid:ab12cd)"),
+              ("supersedes", "string", "Address of the decision this one replaces"),
+              ("provenance", "string", "Who wrote it: 'agent' (default) or 'human'")]),
+        tool("decisions", "Decision log: decision-tagged entries grouped by status (proposed, accepted, superseded) with alternatives, supersede chains and implementing entries. action=set changes one decision's status.",
+            &[],
+            &[("action", "string", "Operation: list (default), set"),
+              ("status", "string", "list: only this status; set: the new status"),
+              ("topic", "string", "set: decision address (topic:3, topic:uid:ab12cd), or a topic name with index"),
+              ("index", "string", "set: entry index, when topic is a plain name")]),
+        tool("review", "Review queue (config review_queue = on): agent-stored entries wait tagged pending-review, hidden from search and briefings. list shows them; approve makes one searchable and tags it validated; reject deletes it.",
+            &[],
+            &[("action", "string", "Operation: list (default), approve, reject"),
+              ("topic", "string", "Entry address from list (topic:3, topic:uid:ab12cd), or a topic name with index"),
+              ("index", "string", "Entry index number, when topic is a plain name")]),
+        tool("reorder", "Set a manual display order for a reference-list topic: read and entries show the listed entries first, in this order, then the rest chronologically. Search stays score-ordered.",
+            &["topic"],
+            &[("topic", "string", "Topic name"),
+              ("order", "string", "Entry indices (from entries) or addresses, space-separated, in the order to show them"),
+              ("clear", "string", "Set to 'true' (with no order) to drop the manual order")]),
         tool("rename", "Rename a topic. All entries preserved.",
             &["topic", "new_name"],
             &[("topic", "string", "Current topic name"),
@@ -165,27 +273,53 @@ pub fn tool_list() -> Value {
             &["from", "into"],
             &[("from", "string", "Source topic to merge FROM (will be deleted)"),
               ("into", "string", "Target topic to merge INTO")]),
+        tool("file_inbox", "Suggest topics for entries stored without one (the 'inbox' topic), by vocabulary similarity to existing topics; apply=true moves each to its best match.",
+            &[],
+            &[("apply", "string", "Set to 'true' to move entries to their first suggestion (entries without a close topic stay)"),
+              ("index", "string", "Only this inbox entry"),
+              ("topic", "string", "With index: file the entry to this topic instead")]),
 
         // === BROWSE TOOLS ===
         tool("topics", "List all topic files with entry and line counts.",
-            &[], &[]),
-        tool("recent", "Show entries from last N days (or hours) across all topics.",
+            &[], &[("uids", "string", "Set to 'true' to include stable topic UIDs (#hex), accepted wherever a topic name is"),
+                   ("changed_since", "string", "Only topics whose content changed since this time (YYYY-MM-DD [HH:MM]); pass the reply's 'as of' next time")]),
+        tool("recent", "Show entries from last N days (or hours) across all topics. Use group='day' or group='session' for a per-day / per-session view with counts.",
             &[],
             &[("days", "string", "Number of days (default: 7)"),
-              ("hours", "string", "Number of hours (overrides days for finer granularity)")]),
-        tool("entries", "List entries in a topic with index numbers. Use before delete/revise/tag. Pass index to fetch a single entry.",
+              ("hours", "string", "Number of hours (overrides days for finer granularity)"),
+              ("group", "string", "Group by 'day' or 'session' (sessions from sessions.log)"),
+              ("collapse", "string", "Set to 'true' to fold batch-stored runs (3+ entries, same topic and minute) to one preview line each"),
+              ("topic", "string", "Only entries in this topic, glob ('iris-*') or /regex/")]),
+        tool("entries", "List entries in a topic with index numbers. Use before delete/revise/tag. Pass index to fetch a single entry; add match_str to get only its matching lines (with context) from a long entry. A glob or /regex/ topic lists each matching topic under its own heading.",
             &["topic"],
-            &[("topic", "string", "Topic name"),
-              ("match_str", "string", "Only show entries matching this substring"),
-              ("index", "string", "Fetch a single entry by index (0-based)")]),
-        tool("stats", "Show stats: topic count, entry count, date range, tag count. Use detail='tags' for all tags with counts, detail='index' for binary index health.",
+            &[("topic", "string", "Topic name, glob ('iris-*'), /regex/, or an entry address (topic:3, topic:uid:ab12cd) naming one entry"),
+              ("match_str", "string", "Only show entries matching this substring; for a single entry, only its lines containing it"),
+              ("index", "string", "Fetch a single entry by index (0-based)"),
+              ("context", "string", "Lines shown around each matching line of a single entry (default: 2)")]),
+        tool("collection", "Named reading lists of entries across topics (e.g. 'onboarding'): show renders every entry in list order, in one call — hand one to a new agent. add/remove edit the list; with no name, lists collections.",
+            &[],
+            &[("action", "string", "Operation: show (default with name), list (default without), add, remove"),
+              ("name", "string", "Collection name"),
+              ("entries", "string", "add/remove: entry addresses (topic:3, topic:uid:ab12cd), space-separated; remove also takes positions from show (1, 2…)")]),
+        tool("share", "One entry as a self-contained markdown block (title, address, date, tags, source, provenance, body) to paste into a PR or chat.",
+            &["topic"],
+            &[("topic", "string", "Entry address (topic:3, topic:uid:ab12cd), or a topic name with index"),
+              ("index", "string", "Entry index, when topic is a plain name"),
+              ("links", "string", "Set to 'true' to append the entries it links to, one level deep")]),
+        tool("stats", "Show stats: topic count, entry count, date range, tag count. Use detail='tags' for all tags with counts, detail='index' for binary index health, detail='heatmap' for a per-day entry-count grid that shows when capture lapsed.",
             &[],
-            &[("detail", "string", "Output: default (overview), 'tags' (all tags with counts), 'index' (binary index stats)")]),
+            &[("detail", "string", "Output: default (overview), 'tags' (all tags with counts), 'index' (binary index stats), 'heatmap' (per-day entry counts)"),
+              ("weeks", "string", "Heatmap: weeks to cover, ending this week (default: 12)"),
+              ("topic", "string", "Heatmap: count one topic only (default: all topics)"),
+              ("format", "string", "Heatmap: 'text' (default, grid) or 'json' (per-day counts)")]),
 
         // === ANALYSIS TOOLS ===
-        tool("stale", "Scan entries with [source:] metadata and report which source files changed. Use refresh=true to see stale entries alongside current source code.",
+        tool("stale", "Scan entries with [source:] metadata and report which source files changed. Use refresh=true to see stale entries alongside current source code, orphans=true to list entries whose source file was deleted or moved (with likely new paths).",
             &[],
-            &[("refresh", "string", "Set to 'true' to show stale entries + current source side-by-side")]),
+            &[("refresh", "string", "Set to 'true' to show stale entries + current source side-by-side"),
+              ("orphans", "string", "Set to 'true' to list entries whose source file no longer exists")]),
+        tool("explain", "Explain how a search query is planned and ranked: tokenization, index vs scan path, per-term document frequencies, filters, AND→OR relaxation, and a per-result BM25/boost breakdown. Use to debug why an entry did or didn't show up.",
+            &["query"], &explain_props),
         tool("xref", "Find cross-references: entries in other topics that mention this topic.",
             &["topic"],
             &[("topic", "string", "Topic to find references for")]),
@@ -194,7 +328,7 @@ pub fn tool_list() -> Value {
             &[("focus", "string", "Glob pattern to filter topics (e.g. 'iris-*')")]),
         tool("trace", "Analyze a codebase: trace function callers/callees (callgraph), find access sites (codepath), map architecture (reverse), find core vs dead code (core), find similar/thin files (simplify), debug crashes (crash), or profile perf antipatterns (perf).",
             &["path"],
-            &[("path", "string", "Codebase directory to search"),
+            &[("path", "string", "Codebase directory to search; several comma-separated (workspace members, app + shared lib) are traced as one codebase, with paths shown relative to their common parent"),
               ("pattern", "string", "Function name, search string, or crash/stack trace text (required for callgraph/codepath/crash)"),
               ("mode", "string", "Analysis type: 'callgraph' (default), 'codepath', 'reverse', 'core', 'simplify', 'crash', 'perf'"),
               ("glob", "string", "File filter suffix (default: *.rs)"),
@@ -202,9 +336,23 @@ pub fn tool_list() -> Value {
               ("direction", "string", "callgraph direction: callers|callees|both (default: both)"),
               ("context", "string", "Lines of context for codepath (default: 2)"),
               ("entry", "string", "Entry point function for core/perf mode (default for core: 'main|run')"),
-              ("store_topic", "string", "If set, store results under this topic"),
+              ("store_topic", "string", "If set, store results under this topic, plus the codebase's call graph for trace_query"),
               ("tags", "string", "Tags for stored entry")]),
 
+        tool("trace_query", "Answer callers/callees of a function from call graphs saved by trace store_topic, without re-tracing the code.",
+            &["symbol"],
+            &[("symbol", "string", "Function name"),
+              ("topic", "string", "Graph to ask (the trace's store_topic); omit to ask every stored graph"),
+              ("direction", "string", "callers|callees|both (default: both)"),
+              ("depth", "string", "Hops to follow (default: 2, max: 5)")]),
+
+        tool("impact", "Before a risky change: walk callers of a function (or of every function in a file) outward N hops, list the affected files, and join them with source-linked entries — invariants, gotchas and decisions first.",
+            &["target"],
+            &[("target", "string", "Function name, or a file path (suffix match, e.g. 'src/cache.rs')"),
+              ("path", "string", "Codebase root(s), comma-separated, to trace live; omit to use a graph saved by trace store_topic"),
+              ("glob", "string", "File filter suffix for a live trace (default: *.rs)"),
+              ("depth", "string", "Caller hops to follow (default: 3, max: 5)")]),
+
         // === MAINTENANCE TOOLS ===
         tool("compact", "Find and merge duplicate entries within a topic. Use log=true to rewrite data.log. Use mode='migrate' to fix entries without timestamps.",
             &[],
@@ -215,19 +363,37 @@ pub fn tool_list() -> Value {
         tool("prune", "Flag stale topics (no entries in N days).",
             &[],
             &[("days", "string", "Stale threshold in days (default: 30)")]),
-        tool("export", "Export all topics as structured JSON for backup.",
-            &[], &[]),
-        tool("import", "Import topics from JSON (merges with existing data).",
-            &["json"],
-            &[("json", "string", "JSON string to import")]),
+        tool("export", "Export topics as structured JSON: everything for backup, or a slice to share (a topic, a tag, entries matching a query). import reads either.",
+            &[],
+            &[("topic", "string", "Only this topic, glob ('iris-*') or /regex/"),
+              ("query", "string", "Only entries containing every term of this query"),
+              ("tag", "string", "Only entries with this tag"),
+              ("rag", "string", "Set to 'true' for chunked JSON Lines records (topic, tags, date, source, uid per chunk) for vector-store ingestion"),
+              ("chunk_tokens", "string", "rag: max tokens per chunk (default: 256)"),
+              ("overlap", "string", "rag: tokens shared by consecutive chunks (default: 32)")]),
+        tool("import", "Import topics from JSON (merges with existing data), or rows of a CSV with a column mapping.",
+            &[],
+            &[("json", "string", "JSON string to import"),
+              ("csv", "string", "CSV text (first row is the header) to import one entry per row"),
+              ("map", "string", "CSV mapping: topic=COL,text=COL,tags=COL,date=COL,source=COL (COL: header name or 1-based number; text required)"),
+              ("topic", "string", "CSV: topic for rows without a mapped topic")]),
         tool("reindex", "Rebuild the binary inverted index from all topic files.",
             &[], &[]),
+        tool("warm", "Pre-load index and data.log pages (and optionally the corpus cache) so the first queries are fast. Reports time taken.",
+            &[],
+            &[("corpus", "string", "Also load the corpus cache: 'true' or 'false' (default: false)")]),
         tool("session", "Show session defaults. Use action param: set_phase (research/build/verify/debug), add_focus (track a topic), note (queue text for batch storage on stop).",
             &[],
             &[("action", "string", "Operation: show (default), set_phase, add_focus, note"),
               ("phase", "string", "Phase name for set_phase: research, build, verify, debug"),
               ("topic", "string", "Topic name for add_focus"),
               ("text", "string", "Note text for note action")]),
+        tool("ambient_preview", "Show what the PreToolUse hook would inject for a file: each ambient layer's entries with scores and addresses, entries hidden by session dedup, and the exact injected text. Changes nothing.",
+            &["file"],
+            &[("file", "string", "File path as the hook would see it (absolute, or relative to the working directory)"),
+              ("syms", "string", "Comma-separated removed symbols, to preview the Edit refactor-impact layer")]),
+        tool("reload_config", "Re-read the knowledge base's config file without restarting. Reports changed keys; rejects the whole file if a known key has a bad value.",
+            &[], &[]),
         tool("_reload", "Re-exec the server binary to pick up code changes.",
             &[], &[]),
     ])
@@ -0,0 +1,80 @@
+//! Store-time detection of machine noise: tool output pasted verbatim instead
+//! of a finding. Signals are ANSI escapes, a JSON blob, line-length shape
+//! (minified lines, or hundreds of near-identical short log lines) and byte
+//! entropy (base64, hashes). ANSI alone is enough; otherwise two signals are.
+//! Flagged entries are tagged `raw-dump` so briefings skip them; the
+//! `raw_dumps` setting (off | tag | truncate, default tag) also cuts them down.
+
+/// Below this nothing is flagged: short pastes are cheap and often intended.
+const MIN_BYTES: usize = 600;
+/// `raw_dumps = truncate` keeps this much text.
+pub const TRUNCATE_BYTES: usize = 2000;
+
+pub const TAG: &str = "raw-dump";
+
+/// The signals that fired, e.g. ["ansi escapes", "json blob"].
+pub fn detect(text: &str) -> Vec<&'static str> {
+    if text.len() < MIN_BYTES { return Vec::new(); }
+    let mut hits = Vec::new();
+    if text.contains("\x1b[") { hits.push("ansi escapes"); }
+    if is_json_blob(text) { hits.push("json blob"); }
+    if let Some(shape) = line_shape(text) { hits.push(shape); }
+    if entropy(text) > 5.2 { hits.push("high entropy"); }
+    let flagged = hits.contains(&"ansi escapes") || hits.len() >= 2;
+    if flagged { hits } else { Vec::new() }
+}
+
+/// Text with ANSI CSI sequences removed.
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' && chars.peek() == Some(&'[') {
+            chars.next();
+            // Parameters/intermediates run until a final byte in @..~
+            for c in chars.by_ref() { if ('@'..='~').contains(&c) { break; } }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// First TRUNCATE_BYTES of `text` (on a line boundary when one is close),
+/// with a marker saying how much was dropped.
+pub fn truncate(text: &str) -> String {
+    if text.len() <= TRUNCATE_BYTES { return text.to_string(); }
+    let cut = crate::text::truncate(text, TRUNCATE_BYTES);
+    let cut = cut.rfind('\n').filter(|&i| i > TRUNCATE_BYTES / 2).map_or(cut, |i| &cut[..i]);
+    format!("{cut}\n[raw-dump truncated: {} of {} bytes dropped]", text.len() - cut.len(), text.len())
+}
+
+fn is_json_blob(text: &str) -> bool {
+    let t = text.trim();
+    let framed = (t.starts_with('{') && t.ends_with('}')) || (t.starts_with('[') && t.ends_with(']'));
+    // Quoted keys per KB: prose with a JSON snippet stays well below this
+    framed && t.matches("\":").count() * 1000 / t.len().max(1) >= 8
+}
+
+/// "minified lines" (most text on lines over 400 chars) or "log-shaped lines"
+/// (200+ lines, nearly all short with the same leading 8 bytes).
+fn line_shape(text: &str) -> Option<&'static str> {
+    let lines: Vec<&str> = text.lines().collect();
+    let long: usize = lines.iter().filter(|l| l.len() > 400).map(|l| l.len()).sum();
+    if long * 2 > text.len() { return Some("minified lines"); }
+    if lines.len() >= 200 {
+        let mut prefixes: crate::fxhash::FxHashMap<&[u8], usize> = crate::fxhash::FxHashMap::default();
+        for l in &lines { *prefixes.entry(&l.as_bytes()[..l.len().min(8)]).or_insert(0) += 1; }
+        let top = prefixes.values().copied().max().unwrap_or(0);
+        if top * 2 > lines.len() { return Some("log-shaped lines"); }
+    }
+    None
+}
+
+/// Shannon entropy in bits per byte. English prose sits near 4.2–4.6.
+fn entropy(text: &str) -> f64 {
+    let mut counts = [0usize; 256];
+    for &b in text.as_bytes() { counts[b as usize] += 1; }
+    let n = text.len() as f64;
+    counts.iter().filter(|&&c| c > 0).map(|&c| { let p = c as f64 / n; -p * p.log2() }).sum()
+}
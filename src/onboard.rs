@@ -0,0 +1,148 @@
+//! First-run onboarding (`init --onboard`): besides creating the directory,
+//! seeds an `amaranthine-howto` topic so the first search finds something and
+//! shows how entries are meant to look, optionally registers the Claude Code
+//! hooks (`--hooks`, as `install` does), and self-tests the MCP wiring by
+//! starting the registered server binary on the new KB and talking JSON-RPC
+//! to it: initialize, tools/list, then a search that must find the seed.
+//! Seeding is skipped when the topic already exists, so re-running is safe.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+pub const TOPIC: &str = "amaranthine-howto";
+/// How long the MCP self-test waits for the server to answer and exit.
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// (tags, text) of each seed entry.
+const SEED: &[(&str, &str)] = &[
+    ("howto, store",
+     "Store one finding per entry: `store <topic> <text> --tags a,b`. Name topics after the \
+      subsystem (auth-tokens, build-ci), not the date. Add `[source: src/file.rs:42]` so stale \
+      entries can be spotted when the code moves."),
+    ("howto, search",
+     "Search with `search <query>`: every term must match unless `--min-match` relaxes it. \
+      Narrow with `--topic 'auth-*'`, `--tag gotcha` or `--after 2026-01-01`; `--brief` lists \
+      one line per hit. Index searches take phrases: `\"token bucket\" NEAR/5 refill`."),
+    ("howto, brief",
+     "Start a task with a briefing: `context <query>` (MCP `brief`) compresses every matching \
+      entry into one read, grouped by topic, newest first. `--diff` shows only what changed \
+      since the last diff run."),
+    ("howto, maintenance",
+     "Keep entries true: `edit` or `correct <topic:N> <text>` when a fact changes (the old \
+      wording stays searchable with `--history`), `delete` what no longer applies, and \
+      `compact` to merge duplicates. `prune` lists topics nobody has touched in 30 days."),
+    ("howto, hooks",
+     "With hooks installed, reading or editing a file injects the entries that mention it, a \
+      failed build surfaces matching gotchas, and the end of a session reminds you to store what \
+      was learned. `hook test ambient` shows what a hook would print."),
+    ("howto, inbox",
+     "Not sure where something belongs? `store` it without a topic: it lands in `inbox`, and \
+      `file-inbox` suggests the closest existing topics (`--apply` files them)."),
+];
+
+/// Onboard `dir`, created if missing: seed, optionally hook, self-test.
+/// Prints each step as it goes, like `install`.
+pub fn run(dir: &Path, hooks: bool) -> Result<(), String> {
+    crate::config::ensure_dir(dir).map_err(|e| e.to_string())?;
+    println!("initialized: {}", dir.display());
+    println!("{}", seed(dir)?);
+
+    let registered = registered_server();
+    let exe = match &registered {
+        Some(p) => p.clone(),
+        None => std::env::current_exe().map_err(|e| e.to_string())?,
+    };
+    if hooks {
+        let home = std::env::var("HOME").map_err(|_| "HOME not set")?;
+        crate::install::update_hooks(&PathBuf::from(home).join(".claude/settings.json"), &exe.to_string_lossy())?;
+    } else {
+        println!("hooks: skipped (--hooks registers them in ~/.claude/settings.json)");
+    }
+
+    match &registered {
+        Some(p) => println!("mcp: registered in ~/.claude.json → {}", p.display()),
+        None => println!("mcp: no working amaranthine server in ~/.claude.json (run `amaranthine install`); testing this binary"),
+    }
+    let report = self_test(&exe, dir).map_err(|e| format!("mcp self-test failed: {e}"))?;
+    println!("mcp self-test: ok — {report}");
+    println!("\nnext: amaranthine search \"briefing\"   # or: amaranthine entries {TOPIC}");
+    Ok(())
+}
+
+/// Store the seed entries unless the topic already has some.
+fn seed(dir: &Path) -> Result<String, String> {
+    let log_path = crate::config::log_path(dir);
+    if log_path.exists() {
+        let have = crate::delete::topic_entries(&log_path, TOPIC).map_err(|e| e.to_string())?.len();
+        if have > 0 { return Ok(format!("{TOPIC}: already seeded ({have} entries)")); }
+    }
+    for (tags, text) in SEED {
+        crate::store::run_full(dir, TOPIC, text, Some(tags), true, None,
+            Some(crate::provenance::Provenance::Imported)).map_err(|e| e.to_string())?;
+    }
+    Ok(format!("{TOPIC}: seeded {} entries", SEED.len()))
+}
+
+/// The MCP server command ~/.claude.json points at, if that file exists.
+fn registered_server() -> Option<PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    let text = std::fs::read_to_string(PathBuf::from(home).join(".claude.json")).ok()?;
+    let config = crate::json::parse(&text).ok()?;
+    let cmd = config.get("mcpServers")?.get("amaranthine")?.get("command")?.as_str()?;
+    Some(PathBuf::from(cmd)).filter(|p| p.exists())
+}
+
+/// Run `exe serve` on `dir` and check it answers initialize, tools/list and
+/// a search of the seed topic. Returns a one-line summary.
+fn self_test(exe: &Path, dir: &Path) -> Result<String, String> {
+    let requests = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"amaranthine-onboard","version":"0"}}}"#.to_string(),
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#.to_string(),
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#.to_string(),
+        format!(r#"{{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{{"name":"search","arguments":{{"query":"briefing","topic":"{TOPIC}","detail":"brief"}}}}}}"#),
+    ];
+    let mut child = Command::new(exe).arg("serve").arg("--dir").arg(dir)
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null())
+        .spawn().map_err(|e| format!("can't start {}: {e}", exe.display()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Closing stdin afterwards ends the server once it has answered
+        stdin.write_all(format!("{}\n", requests.join("\n")).as_bytes())
+            .map_err(|e| format!("write to server: {e}"))?;
+    }
+    let mut stdout = child.stdout.take().ok_or("no server stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = stdout.read_to_string(&mut buf);
+        buf
+    });
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if start.elapsed() < SELF_TEST_TIMEOUT => std::thread::sleep(Duration::from_millis(20)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("server did not finish within {}s", SELF_TEST_TIMEOUT.as_secs()));
+            }
+        }
+    }
+    let text = reader.join().map_err(|_| "reading server output failed")?;
+    let replies: Vec<crate::json::Value> = text.lines().filter_map(|l| crate::json::parse(l).ok()).collect();
+    let reply = |id: f64| replies.iter().find(|r| r.get("id").and_then(|v| v.as_f64()) == Some(id));
+    let result = |id: f64, what: &str| -> Result<&crate::json::Value, String> {
+        let r = reply(id).ok_or(format!("no reply to {what}"))?;
+        if let Some(e) = r.get("error") { return Err(format!("{what}: {e}")); }
+        r.get("result").ok_or(format!("{what}: reply has no result"))
+    };
+    result(1.0, "initialize")?;
+    let tools = match result(2.0, "tools/list")?.get("tools") {
+        Some(crate::json::Value::Arr(t)) => t.len(),
+        _ => return Err("tools/list: no tools".into()),
+    };
+    let found = result(3.0, "search")?.to_string();
+    if !found.contains(TOPIC) { return Err(format!("search did not find the {TOPIC} seed")); }
+    Ok(format!("{} answered initialize, tools/list ({tools} tools) and a search of {TOPIC}", exe.display()))
+}
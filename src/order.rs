@@ -0,0 +1,68 @@
+//! Manual entry order for reference-list topics. An entry may carry
+//! `[order: N]`; `read` and `entries` show keyed entries first by key, then
+//! the rest in log order (so chronological topics are untouched). Search stays
+//! score-ordered. Indices shown by `entries` are still log indices, so
+//! addresses keep working.
+//!
+//! `reorder` sets the keys for a whole topic at once: the listed entries get
+//! 1, 2, 3… and every other entry loses its key. An empty list clears them.
+
+use crate::error::AmrError;
+use std::path::Path;
+
+/// `[order: N]` from an entry body.
+pub fn key(body: &str) -> Option<u32> {
+    crate::entryfmt::field(body, "order").and_then(|s| s.parse().ok())
+}
+
+/// Stable sort into display order: keyed entries by key, then unkeyed in place.
+pub fn arrange<T>(items: &mut [T], body: impl Fn(&T) -> &str) {
+    items.sort_by_key(|e| key(body(e)).map_or((1, 0), |k| (0, k)));
+}
+
+/// Give the entries named by `order` (indices or addresses in `topic`, space or
+/// comma separated) keys 1..n; drop the key from all others. Only entries whose
+/// key changes are rewritten (append + tombstone, timestamp kept), which moves
+/// them to the end of the log — their indices change, their uids don't.
+pub fn reorder(dir: &Path, topic: &str, order: &str) -> Result<String, AmrError> {
+    let topic = crate::stats::single_topic(dir, topic)?;
+    let mut wanted: Vec<usize> = Vec::new();
+    for tok in order.split([' ', ',']).map(str::trim).filter(|t| !t.is_empty()) {
+        let idx = if crate::address::is_address(tok) {
+            let addr = crate::address::parse(tok)?;
+            if addr.topic != topic {
+                return Err(AmrError::InvalidArg(format!("{tok} is not in topic {topic}")));
+            }
+            crate::address::resolve(dir, &addr)?
+        } else {
+            tok.parse().map_err(|_| AmrError::InvalidArg(format!("invalid index: '{tok}'")))?
+        };
+        if wanted.contains(&idx) { return Err(AmrError::InvalidArg(format!("index {idx} listed twice"))); }
+        wanted.push(idx);
+    }
+
+    let _lock = crate::lock::FileLock::acquire(dir)?;
+    let log_path = crate::config::log_path(dir);
+    let entries = crate::delete::topic_entries(&log_path, &topic)?;
+    if entries.is_empty() { return Err(AmrError::NotFound(format!("topic '{topic}' not found"))); }
+    if let Some(&bad) = wanted.iter().find(|&&i| i >= entries.len()) {
+        return Err(AmrError::NotFound(format!("index {bad} out of range (topic has {} entries, 0-{})",
+            entries.len(), entries.len() - 1)));
+    }
+    let mut rewritten = 0;
+    for (i, e) in entries.iter().enumerate() {
+        let new_key = wanted.iter().position(|&w| w == i).map(|p| p as u32 + 1);
+        if key(&e.body) == new_key { continue; }
+        let body = crate::entryfmt::set(&e.body, "order", new_key.map(|k| k.to_string()).as_deref());
+        crate::datalog::append_entry(&log_path, &topic, &body, e.timestamp_min)?;
+        crate::datalog::append_delete(&log_path, e.offset)?;
+        rewritten += 1;
+    }
+    drop(_lock);
+    let head = if wanted.is_empty() {
+        format!("cleared manual order in {topic} ({rewritten} entries rewritten)\n")
+    } else {
+        format!("{} entries ordered first in {topic} ({rewritten} rewritten)\n", wanted.len())
+    };
+    Ok(head + &crate::stats::list_entries(dir, &topic, None)?)
+}
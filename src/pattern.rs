@@ -0,0 +1,193 @@
+//! Topic patterns for filters: a plain name matches exactly, `iris-*` is a glob
+//! (`*`, `?`, `[a-z]`), and `/iris-(core|ui)/` is a regex matched against the
+//! whole name. Topic names never contain `*?[/`, so the syntax is unambiguous.
+//! Regex subset: literals, `.`, `[...]`/`[^...]` with ranges, `\d \w \s`,
+//! `( | )`, and `* + ?` (greedy, backtracking). Both compile to one matcher.
+
+use crate::error::AmrError;
+
+pub enum TopicPattern {
+    Exact(String),
+    Match { source: String, nodes: Vec<Node> },
+}
+
+pub enum Node {
+    Char(char),
+    Any,
+    /// (ranges, negated)
+    Class(Vec<(char, char)>, bool),
+    /// Alternatives, each a sequence.
+    Group(Vec<Vec<Node>>),
+    Repeat(Box<Node>, usize, usize),
+}
+
+/// True if `s` uses glob or regex syntax rather than naming one topic.
+pub fn is_pattern(s: &str) -> bool {
+    s.contains(['*', '?', '[']) || is_regex(s)
+}
+
+fn is_regex(s: &str) -> bool { s.len() >= 2 && s.starts_with('/') && s.ends_with('/') }
+
+impl TopicPattern {
+    pub fn parse(s: &str) -> Result<Self, AmrError> {
+        let s = s.trim();
+        if is_regex(s) {
+            let body = &s[1..s.len() - 1];
+            let body = body.strip_prefix('^').unwrap_or(body);
+            let body = body.strip_suffix('$').filter(|b| !b.ends_with('\\')).unwrap_or(body);
+            let chars: Vec<char> = body.chars().collect();
+            let mut pos = 0;
+            let nodes = parse_alts(&chars, &mut pos)
+                .map_err(|e| AmrError::InvalidArg(format!("bad topic regex {s}: {e}")))?;
+            if pos != chars.len() {
+                return Err(AmrError::InvalidArg(format!("bad topic regex {s}: unmatched ')'")));
+            }
+            return Ok(Self::Match { source: s.to_string(), nodes: vec![Node::Group(nodes)] });
+        }
+        if is_pattern(s) {
+            let nodes = parse_glob(s).map_err(|e| AmrError::InvalidArg(format!("bad topic glob {s}: {e}")))?;
+            return Ok(Self::Match { source: s.to_string(), nodes });
+        }
+        Ok(Self::Exact(s.to_string()))
+    }
+
+    pub fn is_exact(&self) -> bool { matches!(self, Self::Exact(_)) }
+
+    pub fn as_str(&self) -> &str {
+        match self { Self::Exact(s) | Self::Match { source: s, .. } => s }
+    }
+
+    pub fn matches(&self, topic: &str) -> bool {
+        match self {
+            Self::Exact(s) => s == topic,
+            Self::Match { nodes, .. } => {
+                let chars: Vec<char> = topic.chars().collect();
+                seq(nodes, &chars, 0, &|p| p == chars.len())
+            }
+        }
+    }
+
+    /// Matching names from `topics`, in the given order.
+    pub fn select<'a>(&self, topics: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+        topics.into_iter().filter(|t| self.matches(t)).collect()
+    }
+}
+
+/// Topics named by `s`: itself if it is a plain name, else every existing
+/// topic the pattern matches (sorted). NotFound if a pattern matches none.
+pub fn expand(dir: &std::path::Path, s: &str) -> Result<Vec<String>, AmrError> {
+    if !is_pattern(s) { return Ok(vec![s.to_string()]); }
+    let pat = TopicPattern::parse(s)?;
+    let names = crate::cache::with_corpus(dir, |cached| {
+        let all: std::collections::BTreeSet<&str> = cached.iter().map(|e| e.topic.as_str()).collect();
+        pat.select(all).into_iter().map(str::to_string).collect::<Vec<_>>()
+    })?;
+    if names.is_empty() { return Err(AmrError::NotFound(format!("no topics match {s}"))); }
+    Ok(names)
+}
+
+fn parse_glob(s: &str) -> Result<Vec<Node>, String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        out.push(match chars[i] {
+            '*' => Node::Repeat(Box::new(Node::Any), 0, usize::MAX),
+            '?' => Node::Any,
+            '[' => { i += 1; parse_class(&chars, &mut i)? }
+            c => Node::Char(c),
+        });
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// `chars[*i]` is just past '['; leaves `*i` on the closing ']'.
+fn parse_class(chars: &[char], i: &mut usize) -> Result<Node, String> {
+    let negated = chars.get(*i) == Some(&'^');
+    if negated { *i += 1; }
+    let mut ranges = Vec::new();
+    while *i < chars.len() && (chars[*i] != ']' || ranges.is_empty()) {
+        let lo = chars[*i];
+        if chars.get(*i + 1) == Some(&'-') && chars.get(*i + 2).is_some_and(|&c| c != ']') {
+            ranges.push((lo, chars[*i + 2]));
+            *i += 3;
+        } else {
+            ranges.push((lo, lo));
+            *i += 1;
+        }
+    }
+    if *i >= chars.len() { return Err("unclosed '['".into()); }
+    Ok(Node::Class(ranges, negated))
+}
+
+fn parse_alts(chars: &[char], pos: &mut usize) -> Result<Vec<Vec<Node>>, String> {
+    let mut alts = vec![Vec::new()];
+    while *pos < chars.len() {
+        let c = chars[*pos];
+        *pos += 1;
+        let atom = match c {
+            ')' => { *pos -= 1; break; }
+            '|' => { alts.push(Vec::new()); continue; }
+            '(' => {
+                let inner = parse_alts(chars, pos)?;
+                if chars.get(*pos) != Some(&')') { return Err("unclosed '('".into()); }
+                *pos += 1;
+                Node::Group(inner)
+            }
+            '[' => { let n = parse_class(chars, pos)?; *pos += 1; n }
+            '.' => Node::Any,
+            '\\' => {
+                let e = *chars.get(*pos).ok_or("trailing '\\'")?;
+                *pos += 1;
+                match e {
+                    'd' => Node::Class(vec![('0', '9')], false),
+                    'w' => Node::Class(vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')], false),
+                    's' => Node::Class(vec![(' ', ' '), ('\t', '\t')], false),
+                    c => Node::Char(c),
+                }
+            }
+            '*' | '+' | '?' => return Err(format!("'{c}' has nothing to repeat")),
+            c => Node::Char(c),
+        };
+        let atom = match chars.get(*pos) {
+            Some('*') => Node::Repeat(Box::new(atom), 0, usize::MAX),
+            Some('+') => Node::Repeat(Box::new(atom), 1, usize::MAX),
+            Some('?') => Node::Repeat(Box::new(atom), 0, 1),
+            _ => { alts.last_mut().unwrap().push(atom); continue; }
+        };
+        *pos += 1;
+        alts.last_mut().unwrap().push(atom);
+    }
+    Ok(alts)
+}
+
+/// Match `nodes` at `pos`, then hand the end position to `cont`.
+fn seq(nodes: &[Node], s: &[char], pos: usize, cont: &dyn Fn(usize) -> bool) -> bool {
+    let Some((first, rest)) = nodes.split_first() else { return cont(pos) };
+    match first {
+        Node::Repeat(n, min, max) => repeat(n, *min, *max, 0, s, pos, &|p| seq(rest, s, p, cont)),
+        n => one(n, s, pos, &|p| seq(rest, s, p, cont)),
+    }
+}
+
+fn repeat(n: &Node, min: usize, max: usize, count: usize, s: &[char], pos: usize,
+          cont: &dyn Fn(usize) -> bool) -> bool {
+    // Greedy: take one more (only if it consumes input), else stop here
+    if count < max && one(n, s, pos, &|p| p > pos && repeat(n, min, max, count + 1, s, p, cont)) {
+        return true;
+    }
+    count >= min && cont(pos)
+}
+
+fn one(n: &Node, s: &[char], pos: usize, cont: &dyn Fn(usize) -> bool) -> bool {
+    match n {
+        Node::Char(c) => s.get(pos) == Some(c) && cont(pos + 1),
+        Node::Any => pos < s.len() && cont(pos + 1),
+        Node::Class(ranges, neg) => s.get(pos).is_some_and(|c| {
+            ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(c)) != *neg
+        }) && cont(pos + 1),
+        Node::Group(alts) => alts.iter().any(|alt| seq(alt, s, pos, cont)),
+        Node::Repeat(inner, min, max) => repeat(inner, *min, *max, 0, s, pos, cont),
+    }
+}
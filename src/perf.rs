@@ -3,7 +3,7 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Write;
-use std::path::Path;
+use crate::codepath::Roots;
 
 struct PerfFn {
     name: String,
@@ -12,22 +12,18 @@ struct PerfFn {
     antipatterns: Vec<(usize, &'static str, &'static str)>, // (line, category, detail)
 }
 
-pub fn run(path: &Path, glob_suffix: &str, entry: &str, depth: usize) -> Result<String, String> {
+pub fn run(roots: &Roots, glob_suffix: &str, entry: &str, depth: usize) -> Result<String, String> {
     if entry.is_empty() { return Err("entry function name is required".into()); }
-    if !path.is_dir() { return Err(format!("{} is not a directory", path.display())); }
 
     let suffix = glob_suffix.trim_start_matches('*');
-    let mut fps = Vec::new();
-    crate::codepath::walk_files(path, suffix, &mut fps)?;
-    fps.sort();
+    let fps = roots.files(suffix)?;
 
     // Extract all function definitions and their bodies
     let mut all_fns: BTreeMap<String, (String, usize, usize)> = BTreeMap::new(); // name → (file, start, end)
     let mut files: Vec<(String, Vec<String>)> = Vec::new();
 
-    for fp in &fps {
-        let content = match std::fs::read_to_string(fp) { Ok(c) => c, Err(_) => continue };
-        let rel = fp.strip_prefix(path).unwrap_or(fp).to_string_lossy().to_string();
+    for (fp, rel) in fps {
+        let content = match std::fs::read_to_string(&fp) { Ok(c) => c, Err(_) => continue };
         let lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
         let fns = extract_fns(&lines);
         for (name, start, end) in &fns {
@@ -102,7 +98,7 @@ pub fn run(path: &Path, glob_suffix: &str, entry: &str, depth: usize) -> Result<
     // Output
     let mut out = String::new();
     let _ = writeln!(out, "=== PERF: {}() depth={} in {} ({}) ===\n",
-        entry, depth, path.display(), glob_suffix);
+        entry, depth, roots, glob_suffix);
 
     if chain.is_empty() {
         let _ = writeln!(out, "Function '{}' not found in codebase.", entry);
@@ -0,0 +1,117 @@
+//! Phrase and proximity operators for index search: `"token bucket"` needs the
+//! words adjacent and in order; `a NEAR/5 b` needs the operands (words or
+//! quoted phrases) within 5 words of each other, in either order (bare `NEAR`
+//! = `DEFAULT_NEAR`). binquery checks them against index.bin's positional
+//! postings (format v10) after BM25 scoring, so they only narrow the
+//! bag-of-words hits, which still rank as usual. Positions are token indexes
+//! from `text::tokenize`, the same tokenizer for query and body.
+
+/// Distance for a bare `NEAR`.
+pub const DEFAULT_NEAR: u16 = 10;
+
+/// One operator: `left` as a phrase, and optionally `right` within `near`.
+#[derive(Debug, PartialEq)]
+pub struct Constraint {
+    pub left: Vec<String>,
+    pub near: Option<(u16, Vec<String>)>,
+}
+
+/// A query split into its plain words (for scoring) and its operators.
+#[derive(Debug, PartialEq, Default)]
+pub struct Parsed {
+    pub text: String,
+    pub constraints: Vec<Constraint>,
+}
+
+enum Unit { Words(Vec<String>, bool), Near(u16) }
+
+/// Parse `query`. Without quotes or NEAR it comes back as is, constraint-free.
+/// ```
+/// use amaranthine::phrase::parse;
+/// let p = parse(r#""token bucket" NEAR/5 refill"#);
+/// assert_eq!(p.text, "token bucket refill");
+/// assert_eq!(p.constraints.len(), 1);
+/// assert_eq!(p.constraints[0].near, Some((5, vec!["refill".to_string()])));
+/// assert!(parse("token bucket").constraints.is_empty());
+/// ```
+pub fn parse(query: &str) -> Parsed {
+    if !query.contains('"') && !query.contains("NEAR") {
+        return Parsed { text: query.to_string(), constraints: Vec::new() };
+    }
+    let mut units = Vec::new();
+    let mut words = Vec::new();
+    for (i, part) in query.split('"').enumerate() {
+        // Odd parts sit between quotes (an unclosed quote runs to the end)
+        if i % 2 == 1 {
+            words.push(part.trim().to_string());
+            units.push(Unit::Words(crate::text::tokenize(part), true));
+            continue;
+        }
+        for w in part.split_whitespace() {
+            match near_op(w) {
+                Some(k) => units.push(Unit::Near(k)),
+                None => {
+                    words.push(w.to_string());
+                    units.push(Unit::Words(crate::text::tokenize(w), false));
+                }
+            }
+        }
+    }
+    let mut constraints = Vec::new();
+    let mut i = 0;
+    while i < units.len() {
+        match (&units[i], units.get(i + 1), units.get(i + 2)) {
+            (Unit::Words(l, _), Some(Unit::Near(k)), Some(Unit::Words(r, _))) if !l.is_empty() && !r.is_empty() => {
+                constraints.push(Constraint { left: l.clone(), near: Some((*k, r.clone())) });
+                i += 3;
+            }
+            (Unit::Words(l, true), _, _) if l.len() > 1 => {
+                constraints.push(Constraint { left: l.clone(), near: None });
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    let text = words.into_iter().filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" ");
+    Parsed { text, constraints }
+}
+
+/// `NEAR` or `NEAR/k` (uppercase, so the word "near" stays a word).
+fn near_op(w: &str) -> Option<u16> {
+    let rest = w.strip_prefix("NEAR")?;
+    if rest.is_empty() { return Some(DEFAULT_NEAR); }
+    rest.strip_prefix('/')?.parse().ok()
+}
+
+impl Constraint {
+    /// Every term the constraint reads positions for.
+    pub fn terms(&self) -> impl Iterator<Item = &String> {
+        self.left.iter().chain(self.near.iter().flat_map(|(_, r)| r.iter()))
+    }
+
+    /// Whether an entry satisfies it, given each term's sorted positions there.
+    pub fn holds<'a>(&self, positions: &dyn Fn(&str) -> &'a [u16]) -> bool {
+        let left = spans(&self.left, positions);
+        match &self.near {
+            None => !left.is_empty(),
+            Some((k, right)) => {
+                let right = spans(right, positions);
+                left.iter().any(|&(ls, le)| right.iter().any(|&(rs, re)| {
+                    let gap = rs.saturating_sub(le).max(ls.saturating_sub(re));
+                    gap <= *k
+                }))
+            }
+        }
+    }
+}
+
+/// (first, last) positions of each place `words` occur in a row.
+fn spans<'a>(words: &[String], positions: &dyn Fn(&str) -> &'a [u16]) -> Vec<(u16, u16)> {
+    let Some(first) = words.first() else { return Vec::new() };
+    positions(first).iter().filter_map(|&p| {
+        let ok = words.iter().enumerate().skip(1).all(|(i, w)| {
+            p.checked_add(i as u16).is_some_and(|want| positions(w).binary_search(&want).is_ok())
+        });
+        ok.then(|| (p, p + words.len() as u16 - 1))
+    }).collect()
+}
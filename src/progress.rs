@@ -0,0 +1,52 @@
+//! Progress reporting for long operations (reindex, compact, trace).
+//! Callers install a per-thread sink around the work; deep code calls `report`.
+//! With no sink installed `report` is a thread-local read and nothing else.
+//! Reports are throttled to one per 100ms; the final step (done == total) always goes out.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// One progress step. `total` is None while the amount of work is still unknown
+/// (e.g. during file discovery). `message` carries partial status for display.
+pub struct Progress<'a> {
+    pub done: u64,
+    pub total: Option<u64>,
+    pub message: &'a str,
+}
+
+type Sink = Box<dyn FnMut(&Progress)>;
+
+struct State { sink: Sink, last: Option<Instant> }
+
+thread_local! {
+    static SINK: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Run `f` with `sink` receiving this thread's progress reports. Restores the
+/// previous sink afterwards, so nested calls report to the innermost sink.
+pub fn with_sink<R>(sink: impl FnMut(&Progress) + 'static, f: impl FnOnce() -> R) -> R {
+    let prev = SINK.with(|s| s.borrow_mut().replace(State { sink: Box::new(sink), last: None }));
+    let out = f();
+    SINK.with(|s| *s.borrow_mut() = prev);
+    out
+}
+
+/// True when a sink is installed — lets callers skip building messages.
+pub fn active() -> bool {
+    SINK.with(|s| s.borrow().is_some())
+}
+
+/// Report a step to the installed sink, if any.
+pub fn report(done: u64, total: Option<u64>, message: &str) {
+    SINK.with(|s| {
+        let mut guard = s.borrow_mut();
+        let Some(state) = guard.as_mut() else { return };
+        let now = Instant::now();
+        let last_step = total.is_some_and(|t| done >= t);
+        if !last_step && state.last.is_some_and(|t| now.duration_since(t) < MIN_INTERVAL) { return; }
+        state.last = Some(now);
+        (state.sink)(&Progress { done, total, message });
+    });
+}
@@ -0,0 +1,62 @@
+//! Who wrote an entry, recorded at store time as `[provenance: …]`:
+//! `agent` (MCP store/batch_store), `human` (CLI store, or a library caller
+//! saying so), `imported` (import, unless the entry already carried one) and
+//! `trace` (stored trace/codepath output). Entries older than this have none.
+//! Indexed in EntryMeta (v8) for the search `provenance` filter.
+//!
+//! Agent entries nobody has vouched for — no `validated` or `pinned` tag — are
+//! marked in briefings, or also ranked lower: `agent_entries` in `<dir>/config`
+//! (annotate | downweight | off, default annotate).
+
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Provenance { Agent, Human, Imported, Trace }
+
+/// Tag a person adds to vouch for an agent entry.
+pub const VALIDATED_TAG: &str = "validated";
+
+/// Briefing relevance multiplier for unvalidated agent entries under downweight.
+pub const DOWNWEIGHT: f64 = 0.5;
+
+impl Provenance {
+    pub const ALL: [Provenance; 4] = [Self::Agent, Self::Human, Self::Imported, Self::Trace];
+
+    pub fn as_str(self) -> &'static str {
+        match self { Self::Agent => "agent", Self::Human => "human", Self::Imported => "imported", Self::Trace => "trace" }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.as_str() == s.trim())
+    }
+
+    /// EntryMeta byte; 0 means not recorded.
+    pub fn code(self) -> u8 {
+        match self { Self::Agent => 1, Self::Human => 2, Self::Imported => 3, Self::Trace => 4 }
+    }
+
+    pub fn from_code(c: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.code() == c)
+    }
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(self.as_str()) }
+}
+
+/// Written by an agent and never validated (or pinned) by a person.
+pub fn unvalidated(p: Option<Provenance>, tags: &[String]) -> bool {
+    p == Some(Provenance::Agent) && !tags.iter().any(|t| t == VALIDATED_TAG || t == "pinned")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AgentPolicy { Annotate, Downweight, Off }
+
+/// `agent_entries` setting; unknown values fall back to annotate.
+pub fn policy(dir: &Path) -> AgentPolicy {
+    match crate::settings::load(dir).get("agent_entries") {
+        Some("downweight") => AgentPolicy::Downweight,
+        Some("off") => AgentPolicy::Off,
+        _ => AgentPolicy::Annotate,
+    }
+}
@@ -1,6 +1,7 @@
 use crate::time;
 use std::fmt::Write;
 use std::path::Path;
+use crate::theme::{paint, Role};
 
 pub fn run(dir: &Path, stale_days: u64, plain: bool) -> Result<String, String> {
     let log_path = crate::config::log_path(dir);
@@ -9,24 +10,34 @@ pub fn run(dir: &Path, stale_days: u64, plain: bool) -> Result<String, String> {
         let today = time::LocalTime::now().to_days();
         let cutoff = today - stale_days as i64;
 
-        // Find newest entry per topic
-        let mut newest: std::collections::BTreeMap<&str, i64> = std::collections::BTreeMap::new();
-        for e in cached {
+        // Newest entry and highest importance per topic
+        let importance = crate::importance::compute(dir, cached);
+        let mut newest: std::collections::BTreeMap<&str, (i64, u8)> = std::collections::BTreeMap::new();
+        for (e, &imp) in cached.iter().zip(&importance) {
             let days = e.timestamp_min as i64 / 1440;
-            let cur = newest.entry(&e.topic).or_insert(0);
-            if days > *cur { *cur = days; }
+            let cur = newest.entry(&e.topic).or_insert((0, 0));
+            if days > cur.0 { cur.0 = days; }
+            cur.1 = cur.1.max(imp);
         }
 
+        // Tiered retention: important topics are kept regardless of age,
+        // low-importance ones go stale at half the threshold.
+        let low_cutoff = today - (stale_days / 2) as i64;
         let mut stale = 0;
+        let mut kept = 0;
         let mut out = String::new();
-        for (name, latest) in &newest {
-            if *latest == 0 {
+        for (name, &(latest, imp)) in &newest {
+            let low = imp < crate::importance::LOW;
+            let (cutoff, days) = if low { (low_cutoff, stale_days / 2) } else { (cutoff, stale_days) };
+            let tier = if low { ", low importance" } else { "" };
+            if latest == 0 {
                 if plain { let _ = writeln!(out, "no dates: {name}"); }
-                else { let _ = writeln!(out, "\x1b[1;31mno dates:\x1b[0m {name}"); }
+                else { let _ = writeln!(out, "{} {name}", paint(Role::Error, "no dates:")); }
                 stale += 1;
-            } else if *latest < cutoff {
-                if plain { let _ = writeln!(out, "stale: {name} (last entry > {stale_days} days ago)"); }
-                else { let _ = writeln!(out, "\x1b[1;33mstale:\x1b[0m {name} (> {stale_days} days)"); }
+            } else if latest < cutoff {
+                if imp >= crate::importance::HIGH { kept += 1; continue; }
+                if plain { let _ = writeln!(out, "stale: {name} (last entry > {days} days ago{tier})"); }
+                else { let _ = writeln!(out, "{} {name} (> {days} days{tier})", paint(Role::Warn, "stale:")); }
                 stale += 1;
             }
         }
@@ -35,6 +46,9 @@ pub fn run(dir: &Path, stale_days: u64, plain: bool) -> Result<String, String> {
         } else {
             let _ = writeln!(out, "\n{stale} stale topic(s) — review manually");
         }
+        if kept > 0 {
+            let _ = writeln!(out, "{kept} old topic(s) kept: importance >= {}", crate::importance::HIGH);
+        }
         out
-    })
+    }).map_err(Into::into)
 }
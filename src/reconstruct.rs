@@ -9,8 +9,24 @@ use std::path::Path;
 use crate::compress::RawEntry;
 use crate::fxhash::{FxHashMap, FxHashSet};
 
+/// Matched entries past which a briefing samples instead of classifying all.
+pub const SAMPLE_ABOVE: usize = 400;
+/// Entries a sampled briefing keeps: a third each most recent, most linked-to
+/// and most relevant (overlaps filled from relevance).
+pub const SAMPLE_SIZE: usize = 300;
+
 pub fn run(dir: &Path, query: &str, detail: &str, since_hours: Option<u64>,
            focus: Option<&str>) -> Result<String, String> {
+    build(dir, query, detail, since_hours, focus, false)
+}
+
+/// Only what changed since the last diff briefing for `query` (briefdiff.rs).
+pub fn run_diff(dir: &Path, query: &str, since_hours: Option<u64>, focus: Option<&str>) -> Result<String, String> {
+    build(dir, query, "summary", since_hours, focus, true)
+}
+
+fn build(dir: &Path, query: &str, detail: &str, since_hours: Option<u64>,
+         focus: Option<&str>, diff: bool) -> Result<String, String> {
     let q = query.to_lowercase();
     let is_glob = q.contains('*');
     let is_source_query = query.contains('.') && !query.contains(' ');
@@ -24,6 +40,13 @@ pub fn run(dir: &Path, query: &str, detail: &str, since_hours: Option<u64>,
         f.split(',').map(|c| c.trim().to_uppercase()).filter(|c| !c.is_empty()).collect()
     );
 
+    let agent_policy = crate::provenance::policy(dir);
+    let unvalidated = |e: &crate::cache::CachedEntry| agent_policy != crate::provenance::AgentPolicy::Off
+        && crate::provenance::unvalidated(e.provenance(), e.tags());
+    let agent_weight = |u: bool| {
+        if u && agent_policy == crate::provenance::AgentPolicy::Downweight { crate::provenance::DOWNWEIGHT } else { 1.0 }
+    };
+
     crate::cache::with_corpus(dir, |cached| {
         // Identify primary topics (glob or substring match)
         let mut primary_set: BTreeSet<&str> = BTreeSet::new();
@@ -82,6 +105,8 @@ pub fn run(dir: &Path, query: &str, detail: &str, since_hours: Option<u64>,
                 relevance *= 1.0 + 1.0 / (1.0 + days_old as f64 / 7.0);
             }
             relevance *= e.confidence();
+            let unvalidated = unvalidated(e);
+            relevance *= agent_weight(unvalidated);
             let tidx = offset_tidx.get(&e.offset).copied().unwrap_or(0);
             let link_in = link_in_counts.get(&link_key(e.topic.as_str(), tidx))
                 .copied().unwrap_or(0);
@@ -93,10 +118,12 @@ pub fn run(dir: &Path, query: &str, detail: &str, since_hours: Option<u64>,
             }
 
             entries.push(RawEntry {
-                topic: e.topic.to_string(), body: e.body.clone(),
+                topic: e.topic.to_string(), index: tidx,
+                uid: crate::address::entry_uid(e.timestamp_min, &e.body),
+                body: crate::include::expand_in(cached, e.timestamp_min, &e.body).into_owned(),
                 timestamp_min: e.timestamp_min, days_old,
                 tags: e.tags().to_vec(), relevance,
-                confidence: e.confidence(), link_in,
+                confidence: e.confidence(), link_in, unvalidated,
             });
         }
 
@@ -122,13 +149,17 @@ pub fn run(dir: &Path, query: &str, detail: &str, since_hours: Option<u64>,
                                 let le_tidx = offset_tidx.get(&le.offset).copied().unwrap_or(0);
                                 let le_link_in = link_in_counts.get(&link_key(le.topic.as_str(), le_tidx))
                                     .copied().unwrap_or(0);
+                                let le_unvalidated = unvalidated(le);
                                 entries.push(RawEntry {
-                                    topic: le.topic.to_string(),
-                                    body: format!("[linked from: {}:{}]\n{}", e.topic, link_idx, le.body),
+                                    topic: le.topic.to_string(), index: le_tidx,
+                                    uid: crate::address::entry_uid(le.timestamp_min, &le.body),
+                                    body: format!("{}\n{}", crate::entryfmt::line("linked from", &format!("{}:{link_idx}", e.topic)),
+                                        crate::include::expand_in(cached, le.timestamp_min, &le.body)),
                                     timestamp_min: le.timestamp_min, days_old,
                                     tags: le.tags().to_vec(),
-                                    relevance: 3.0 * le.confidence(),
+                                    relevance: 3.0 * le.confidence() * agent_weight(le_unvalidated),
                                     confidence: le.confidence(), link_in: le_link_in,
+                                    unvalidated: le_unvalidated,
                                 });
                                 matched_offsets.insert(le.offset);
                             }
@@ -138,21 +169,83 @@ pub fn run(dir: &Path, query: &str, detail: &str, since_hours: Option<u64>,
             }
         }
 
+        entries.retain(|e| !e.tags.iter().any(|t| t == crate::review::TAG));
+        // Verbatim tool output (noise.rs) would crowd out the findings
+        let before = entries.len();
+        entries.retain(|e| !e.tags.iter().any(|t| t == crate::noise::TAG));
+        let dumps = before - entries.len();
+        let omitted = if dumps > 0 {
+            format!("({dumps} raw-dump entries omitted — search or read shows them)\n")
+        } else { String::new() };
+
         if entries.is_empty() {
             return if since_hours.is_some() {
-                format!("No new entries for '{}' in the last {}h.\n", query, since_hours.unwrap())
+                format!("No new entries for '{}' in the last {}h.\n{omitted}", query, since_hours.unwrap())
             } else {
-                format!("No entries found for '{query}'.\n")
+                format!("No entries found for '{query}'.\n{omitted}")
             };
         }
 
         let primary: Vec<String> = primary_set.iter().map(|s| s.to_string()).collect();
+        // A diff briefing needs every entry, or unsampled ones would read as removed
+        let (entries, sampled) = if diff { (entries, String::new()) } else { sample(entries) };
+        let omitted = format!("{sampled}{omitted}");
         let raw_count = entries.len();
         let compressed = crate::compress::compress(entries);
+        if diff {
+            let mut out = crate::briefdiff::render(dir, query, &compressed, focus_cats.as_deref());
+            out.push_str(&omitted);
+            return out;
+        }
         let d = crate::briefing::Detail::from_str(detail);
-        crate::briefing::format(&compressed, query, raw_count, &primary, d, since_hours,
-                                focus_cats.as_deref())
-    })
+        // A stored trace names every module, so it doesn't count as covering one
+        let covered: Vec<(Option<&str>, &str)> = cached.iter()
+            .filter(|e| e.provenance() != Some(crate::provenance::Provenance::Trace))
+            .filter(|e| e.tags().iter().any(|t| crate::briefing::ARCHITECTURE_TAGS.contains(&t.as_str())))
+            .map(|e| (e.source(), e.body.as_str())).collect();
+        let code_gaps = crate::briefing::code_gaps(dir, &primary, &covered);
+        let mut out = crate::briefing::format(&compressed, d, &crate::briefing::View {
+            query, raw_count, primary: &primary, since: since_hours, focus: focus_cats.as_deref(), code_gaps: &code_gaps,
+        });
+        out.push_str(&omitted);
+        let tokens = crate::text::approx_tokens_of(&out);
+        out.push_str(&format!("({} in this briefing)\n", crate::text::format_tokens(tokens)));
+        out
+    }).map_err(Into::into)
+}
+
+/// Stratified sample of an oversized match set (more than SAMPLE_ABOVE), so a
+/// 2000-entry topic briefs in bounded time: the SAMPLE_SIZE / 3 newest, the
+/// most linked-to, then the most relevant fill the rest. Pinned entries always
+/// stay. Returns the kept entries (in their original order) and a note saying
+/// what was left out, empty when nothing was.
+fn sample(entries: Vec<RawEntry>) -> (Vec<RawEntry>, String) {
+    let total = entries.len();
+    if total <= SAMPLE_ABOVE { return (entries, String::new()); }
+    let third = SAMPLE_SIZE / 3;
+    let mut keep = vec![false; total];
+    for (i, e) in entries.iter().enumerate() { keep[i] = e.tags.iter().any(|t| t == "pinned"); }
+    let mut order: Vec<usize> = (0..total).collect();
+    let take = |order: &[usize], n: usize, keep: &mut [bool]| {
+        let mut added = 0;
+        for &i in order {
+            if added >= n { break; }
+            if !keep[i] { keep[i] = true; added += 1; }
+        }
+    };
+    order.sort_by(|&a, &b| entries[b].timestamp_min.cmp(&entries[a].timestamp_min));
+    take(&order, third, &mut keep);
+    order.sort_by(|&a, &b| entries[b].link_in.cmp(&entries[a].link_in)
+        .then(entries[b].relevance.total_cmp(&entries[a].relevance)));
+    let linked = entries.iter().filter(|e| e.link_in > 0).count().min(third);
+    take(&order, linked, &mut keep);
+    let room = SAMPLE_SIZE.saturating_sub(keep.iter().filter(|k| **k).count());
+    order.sort_by(|&a, &b| entries[b].relevance.total_cmp(&entries[a].relevance));
+    take(&order, room, &mut keep);
+    let kept: Vec<RawEntry> = entries.into_iter().zip(keep).filter(|(_, k)| *k).map(|(e, _)| e).collect();
+    let note = format!("(sampled {} of {total} matching entries: newest, most linked-to and most relevant — \
+        narrow with since=, focus= or a more specific query to see the rest)\n", kept.len());
+    (kept, note)
 }
 
 /// Check if a [source:] path matches a query file name.
@@ -0,0 +1,83 @@
+//! `amaranthine repl`: one process reading line commands from stdin, so bulk
+//! curation doesn't pay process startup, corpus load and index mmap per
+//! command. The corpus cache and shared index stay warm between lines; each
+//! store updates the cache in place and the index rebuilds once on the next
+//! search. Stores are recorded as `human`.
+//!
+//! ```text
+//! s <topic> <text>     store
+//! /<query>             search (brief)
+//! // <query>           search (full entries)
+//! t <topic>            read a topic
+//! e <topic|address>    list entries / show one
+//! d <address>          delete one entry
+//! ?                    help        q  quit
+//! ```
+
+use crate::error::AmrError;
+use crate::provenance::Provenance;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+const HELP: &str = "s <topic> <text>  store · /<query>  search · // <query>  full search · t <topic>  read\n\
+e <topic|address>  entries · d <address>  delete · ?  help · q  quit\n";
+
+pub fn run(dir: &Path, plain: bool) -> Result<String, AmrError> {
+    crate::cache::preload(dir)?;
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    let mut stderr = std::io::stderr();
+    let _ = write!(stderr, "amaranthine repl — {} (? for help)\namr> ", dir.display());
+    let _ = stderr.flush();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        if line == "q" || line == "quit" { break; }
+        if !line.is_empty() {
+            match exec(dir, line, plain) {
+                Ok(out) => { let _ = write!(stdout, "{out}"); if !out.ends_with('\n') { let _ = writeln!(stdout); } }
+                Err(e) => { let _ = writeln!(stderr, "error: {e}"); }
+            }
+            let _ = stdout.flush();
+        }
+        let _ = write!(stderr, "amr> ");
+        let _ = stderr.flush();
+    }
+    Ok(String::new())
+}
+
+fn exec(dir: &Path, line: &str, plain: bool) -> Result<String, AmrError> {
+    let filter = crate::search::Filter::none();
+    if let Some(q) = line.strip_prefix("//") {
+        return crate::search::run(dir, q.trim(), plain, Some(10), &filter, None);
+    }
+    if let Some(q) = line.strip_prefix('/') {
+        return crate::search::run_brief(dir, q.trim(), Some(20), &filter, None);
+    }
+    let (cmd, rest) = line.split_once(' ').map_or((line, ""), |(c, r)| (c, r.trim()));
+    match (cmd, rest) {
+        ("?" | "help", _) => Ok(HELP.into()),
+        ("s", r) => {
+            let (topic, text) = r.split_once(' ')
+                .ok_or_else(|| AmrError::InvalidArg("usage: s <topic> <text>".into()))?;
+            crate::store::run_full(dir, topic, text.trim(), None, false, None, Some(Provenance::Human))
+        }
+        ("t", t) if !t.is_empty() => crate::topics::read_topic(dir, t, 0, None),
+        ("e", a) if crate::address::is_address(a) => {
+            let (topic, idx) = at(dir, a)?;
+            crate::stats::get_entry(dir, &topic, idx)
+        }
+        ("e", t) if !t.is_empty() => crate::stats::list_entries(dir, t, None),
+        ("d", a) if crate::address::is_address(a) => {
+            let (topic, idx) = at(dir, a)?;
+            crate::delete::run_by_index(dir, &topic, idx)
+        }
+        _ => Err(AmrError::InvalidArg(format!("unknown command '{line}' (? for help)"))),
+    }
+}
+
+fn at(dir: &Path, raw: &str) -> Result<(String, usize), AmrError> {
+    let addr = crate::address::parse(raw)?;
+    let idx = crate::address::resolve(dir, &addr)?;
+    Ok((addr.topic.to_string(), idx))
+}
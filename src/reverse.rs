@@ -3,24 +3,21 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Write;
-use std::path::Path;
+use crate::codepath::Roots;
 
 // ── reverse: module-level architecture map ──────────────────────────
 
-pub fn reverse(path: &Path, glob_suffix: &str) -> Result<String, String> {
-    if !path.is_dir() { return Err(format!("{} is not a directory", path.display())); }
+pub fn reverse(roots: &Roots, glob_suffix: &str) -> Result<String, String> {
     let suffix = glob_suffix.trim_start_matches('*');
-    let mut fps = Vec::new();
-    crate::codepath::walk_files(path, suffix, &mut fps)?;
-    fps.sort();
+    let fps = roots.files(suffix)?;
 
     let mut modules: BTreeMap<String, ModInfo> = BTreeMap::new();
     let mut all_fns: Vec<FnInfo> = Vec::new();
     let mut total_lines = 0usize;
 
-    for fp in &fps {
-        let content = match std::fs::read_to_string(fp) { Ok(c) => c, Err(_) => continue };
-        let rel = fp.strip_prefix(path).unwrap_or(fp).to_string_lossy().to_string();
+    for (fp, rel) in fps {
+        crate::cancel::check()?;
+        let content = match std::fs::read_to_string(&fp) { Ok(c) => c, Err(_) => continue };
         let loc = content.lines().count();
         total_lines += loc;
         let fns = extract_symbols(&content);
@@ -36,6 +33,7 @@ pub fn reverse(path: &Path, glob_suffix: &str) -> Result<String, String> {
     let mut in_degree: BTreeMap<&str, usize> = BTreeMap::new();
     let mut out_degree: BTreeMap<&str, usize> = BTreeMap::new();
     for (file, info) in &modules {
+        crate::cancel::check()?;
         for f in &info.fns {
             if !f.is_pub { continue; }
             for (other_file, other_info) in &modules {
@@ -53,7 +51,7 @@ pub fn reverse(path: &Path, glob_suffix: &str) -> Result<String, String> {
 
     let mut out = String::new();
     let _ = writeln!(out, "=== ARCHITECTURE: {} ({} files, {}L, {}) ===\n",
-        path.display(), modules.len(), total_lines, glob_suffix);
+        roots, modules.len(), total_lines, glob_suffix);
 
     // Rank modules by centrality (in + out degree)
     let mut ranked: Vec<(&str, usize, usize, usize, usize, usize)> = modules.iter()
@@ -110,19 +108,16 @@ pub fn reverse(path: &Path, glob_suffix: &str) -> Result<String, String> {
 
 // ── core: reachability from entry points ────────────────────────────
 
-pub fn core(path: &Path, glob_suffix: &str, entry_pattern: &str) -> Result<String, String> {
-    if !path.is_dir() { return Err(format!("{} is not a directory", path.display())); }
+pub fn core(roots: &Roots, glob_suffix: &str, entry_pattern: &str) -> Result<String, String> {
     let suffix = glob_suffix.trim_start_matches('*');
-    let mut fps = Vec::new();
-    crate::codepath::walk_files(path, suffix, &mut fps)?;
-    fps.sort();
+    let fps = roots.files(suffix)?;
 
     let mut all_fns: Vec<FnInfo> = Vec::new();
     let mut file_contents: Vec<(String, String)> = Vec::new();
 
-    for fp in &fps {
-        let content = match std::fs::read_to_string(fp) { Ok(c) => c, Err(_) => continue };
-        let rel = fp.strip_prefix(path).unwrap_or(fp).to_string_lossy().to_string();
+    for (fp, rel) in fps {
+        crate::cancel::check()?;
+        let content = match std::fs::read_to_string(&fp) { Ok(c) => c, Err(_) => continue };
         let fns = extract_symbols(&content);
         all_fns.extend(fns.iter().map(|f| FnInfo {
             name: f.name.clone(), file: rel.clone(), line: f.line,
@@ -182,7 +177,7 @@ pub fn core(path: &Path, glob_suffix: &str, entry_pattern: &str) -> Result<Strin
 
     let mut out = String::new();
     let _ = writeln!(out, "=== CORE: {} ({} entries, {}) ===\n",
-        path.display(), entries.len(), glob_suffix);
+        roots, entries.len(), glob_suffix);
 
     let _ = writeln!(out, "ENTRIES ({}):", entries.len());
     for e in &entries {
@@ -219,12 +214,9 @@ pub fn core(path: &Path, glob_suffix: &str, entry_pattern: &str) -> Result<Strin
 
 // ── simplify: similarity + thin wrapper detection ───────────────────
 
-pub fn simplify(path: &Path, glob_suffix: &str) -> Result<String, String> {
-    if !path.is_dir() { return Err(format!("{} is not a directory", path.display())); }
+pub fn simplify(roots: &Roots, glob_suffix: &str) -> Result<String, String> {
     let suffix = glob_suffix.trim_start_matches('*');
-    let mut fps = Vec::new();
-    crate::codepath::walk_files(path, suffix, &mut fps)?;
-    fps.sort();
+    let fps = roots.files(suffix)?;
 
     struct FileInfo {
         rel: String,
@@ -237,9 +229,9 @@ pub fn simplify(path: &Path, glob_suffix: &str) -> Result<String, String> {
     let mut files: Vec<FileInfo> = Vec::new();
     let mut total_loc = 0usize;
 
-    for fp in &fps {
-        let content = match std::fs::read_to_string(fp) { Ok(c) => c, Err(_) => continue };
-        let rel = fp.strip_prefix(path).unwrap_or(fp).to_string_lossy().to_string();
+    for (fp, rel) in fps {
+        crate::cancel::check()?;
+        let content = match std::fs::read_to_string(&fp) { Ok(c) => c, Err(_) => continue };
         let loc = content.lines().count();
         total_loc += loc;
         let syms = extract_symbols(&content);
@@ -254,12 +246,13 @@ pub fn simplify(path: &Path, glob_suffix: &str) -> Result<String, String> {
 
     let mut out = String::new();
     let _ = writeln!(out, "=== SIMPLIFY: {} ({} files, {}L, {}) ===\n",
-        path.display(), files.len(), total_loc, glob_suffix);
+        roots, files.len(), total_loc, glob_suffix);
 
     // Cross-file Jaccard similarity
     let _ = writeln!(out, "SIMILAR FILE PAIRS (>40% token overlap):");
     let mut pairs: Vec<(usize, usize, f64)> = Vec::new();
     for i in 0..files.len() {
+        crate::cancel::check()?;
         // Cap comparisons per file to avoid O(n^2) blowup on large codebases
         let mut pair_count = 0;
         for j in (i + 1)..files.len() {
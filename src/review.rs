@@ -0,0 +1,70 @@
+//! Review queue: with `review_queue = on` in `<dir>/config`, entries an agent
+//! stores (store, batch) are tagged `pending-review` and stay out of search,
+//! the index and briefings until someone looks at them. `review list` shows
+//! the queue; `approve` swaps the tag for `validated` (provenance.rs), `reject`
+//! deletes the entry. Entries stay readable by address or `read` meanwhile.
+
+use crate::error::AmrError;
+use crate::provenance::Provenance;
+use std::fmt::Write;
+use std::path::Path;
+
+pub const TAG: &str = "pending-review";
+
+/// Whether a new entry from `provenance` lands in the queue.
+pub fn holds(dir: &Path, provenance: Option<Provenance>) -> bool {
+    provenance == Some(Provenance::Agent) && crate::settings::load(dir).flag("review_queue") == Some(true)
+}
+
+/// Pending entries across topics, oldest first, one `[topic:N]` line each.
+pub fn list(dir: &Path) -> Result<String, AmrError> {
+    let entries = crate::datalog::iter_live(&crate::config::log_path(dir))?;
+    let mut per_topic: crate::fxhash::FxHashMap<&str, usize> = crate::fxhash::FxHashMap::default();
+    let mut out = String::new();
+    let mut n = 0;
+    for e in &entries {
+        let slot = per_topic.entry(e.topic.as_str()).or_default();
+        let idx = *slot;
+        *slot += 1;
+        if !is_pending(&e.body) { continue; }
+        n += 1;
+        let preview = e.body.lines()
+            .find(|l| !l.trim().is_empty() && !crate::text::is_metadata_line(l))
+            .map(|l| crate::text::truncate(l.trim(), 80)).unwrap_or("(empty)");
+        let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+        let _ = writeln!(out, "  [{}] {} — {preview}", crate::address::format_both(&e.topic, idx, uid),
+            crate::time::minutes_to_date_str(e.timestamp_min));
+    }
+    if n == 0 { return Ok("review queue empty\n".into()); }
+    Ok(format!("{n} pending review (approve or reject by address):\n{out}"))
+}
+
+/// Promote a pending entry: drop `pending-review`, add `validated`.
+pub fn approve(dir: &Path, topic: &str, idx: usize) -> Result<String, AmrError> {
+    check_pending(dir, topic, idx)?;
+    let msg = crate::edit::tag_entry(dir, topic, Some(idx), None,
+        Some(crate::provenance::VALIDATED_TAG), Some(TAG))?;
+    Ok(format!("approved: {msg}"))
+}
+
+/// Delete a pending entry.
+pub fn reject(dir: &Path, topic: &str, idx: usize) -> Result<String, AmrError> {
+    check_pending(dir, topic, idx)?;
+    let msg = crate::delete::run_by_index(dir, topic, idx)?;
+    Ok(format!("rejected: {msg}"))
+}
+
+/// approve/reject only act on queued entries, so a stale index can't hit a curated one.
+fn check_pending(dir: &Path, topic: &str, idx: usize) -> Result<(), AmrError> {
+    let entries = crate::delete::topic_entries(&crate::config::log_path(dir), topic)?;
+    let e = entries.get(idx).ok_or_else(|| AmrError::NotFound(format!(
+        "index {idx} out of range (topic has {} entries)", entries.len())))?;
+    if !is_pending(&e.body) {
+        return Err(AmrError::InvalidArg(format!("{topic}:{idx} is not pending review")));
+    }
+    Ok(())
+}
+
+fn is_pending(body: &str) -> bool {
+    body.lines().any(|l| crate::text::parse_tags_raw(Some(l)).iter().any(|t| t.eq_ignore_ascii_case(TAG)))
+}
@@ -3,105 +3,172 @@
 //! Tag-filtered queries stay on index path when tag is in top-32 bitmap.
 
 use crate::fxhash::{FxHashSet, FxHashMap};
+use std::ops::ControlFlow;
 use std::path::Path;
+use crate::error::AmrError;
+pub use crate::filter::{Filter, MinMatch, SearchMode};
+use crate::filter::{build_filter_pred, in_scope, passes_filter_cached};
 pub const BM25_K1: f64 = 1.2;
 pub const BM25_B: f64 = 0.75;
+/// Pseudo-entries of corpus-average length mixed into each topic's avgdl, so a
+/// topic with a handful of entries doesn't normalize only against itself.
+pub const TOPIC_AVGDL_PRIOR: f64 = 5.0;
+
+/// Ceiling of the proximity bonus: every query term present, adjacent.
+pub const PROXIMITY_BOOST: f64 = 0.3;
+
+/// Score multiplier for entries in `Filter::boost_topics`.
+pub const TOPIC_BOOST: f64 = 2.0;
+
+/// Hybrid search: below this top-K overlap between the index and corpus-scan
+/// rankings, the two are fused instead of trusting the index alone.
+pub const HYBRID_MIN_OVERLAP: f64 = 0.5;
+/// Reciprocal rank fusion constant: a result scores Σ 1 / (RRF_K + rank).
+pub const RRF_K: f64 = 60.0;
+
+/// Score multiplier for query terms that occur close together in `text`:
+/// 1 + PROXIMITY_BOOST × coverage × tightness, where coverage is the share of
+/// terms (beyond the first) present and tightness = terms / covering window
+/// (see text::term_span). 1.0 for single-term queries or single-term matches.
+pub fn proximity_mult(text: &str, terms: &[String]) -> f64 {
+    let Some((k, span)) = crate::text::term_span(text, terms) else { return 1.0 };
+    let coverage = (k - 1) as f64 / (terms.len() - 1) as f64;
+    1.0 + PROXIMITY_BOOST * coverage * (k as f64 / span.max(k) as f64)
+}
+
+/// BM25 length normalization is per topic: verbose structural topics would
+/// otherwise set a corpus-wide avgdl that makes concise entries elsewhere look
+/// short and over-boosted (or long ones in terse topics look padded). The
+/// topic's own average, shrunk toward `global` by TOPIC_AVGDL_PRIOR.
+pub fn cohort_avgdl(topic_words: usize, topic_entries: usize, global: f64) -> f64 {
+    (topic_words as f64 + TOPIC_AVGDL_PRIOR * global) / (topic_entries as f64 + TOPIC_AVGDL_PRIOR)
+}
+
+/// (global avgdl, per-topic cohort avgdl) over a scored entry set.
+pub(crate) fn cohort_avgdls<'a>(entries: &[&'a crate::cache::CachedEntry]) -> (f64, FxHashMap<&'a str, f64>) {
+    let mut per: FxHashMap<&str, (usize, usize)> = FxHashMap::default();
+    let mut total = 0;
+    for e in entries {
+        total += e.word_count;
+        let t = per.entry(e.topic.as_str()).or_default();
+        t.0 += e.word_count;
+        t.1 += 1;
+    }
+    let global = if entries.is_empty() { 1.0 } else { total as f64 / entries.len() as f64 };
+    let per = per.into_iter().map(|(k, (w, c))| (k, cohort_avgdl(w, c, global))).collect();
+    (global, per)
+}
 
 /// A scored search result.
 pub struct ScoredResult {
     pub name: String,
     pub lines: Vec<String>,
     pub score: f64,
+    /// data.log offset of the entry (see address::lookup).
+    pub offset: u32,
 }
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum SearchMode { And, Or }
-
-/// Filter options for search (date range + tag + topic scope + mode).
-pub struct Filter {
-    pub after: Option<i64>,
-    pub before: Option<i64>,
-    pub tag: Option<String>,
-    pub topic: Option<String>,
-    pub mode: SearchMode,
+fn boosted(boost: &[crate::pattern::TopicPattern], topic: &str) -> bool {
+    boost.iter().any(|p| p.matches(topic))
 }
 
-impl Filter {
-    pub fn none() -> Self {
-        Self { after: None, before: None, tag: None, topic: None, mode: SearchMode::And }
-    }
-    pub fn is_active(&self) -> bool {
-        self.after.is_some() || self.before.is_some() || self.tag.is_some() || self.topic.is_some()
+/// Check if tokens contain enough query terms. O(terms) via HashMap key lookup.
+#[inline]
+pub fn matches_tokens(tf_map: &FxHashMap<String, usize>, terms: &[String], need: MinMatch) -> bool {
+    if terms.is_empty() { return true; }
+    match need {
+        MinMatch::All => terms.iter().all(|t| tf_map.contains_key(t)),
+        need => terms.iter().filter(|t| tf_map.contains_key(*t)).count() >= need.required(terms.len()),
     }
 }
 
-/// Check if tokens match query terms in given mode. O(terms) via HashMap key lookup.
+/// Multipliers on an entry's BM25 sum: topic name holds a term, boost_topics
+/// (TOPIC_BOOST), tags hold terms, term proximity.
+pub(crate) struct Boosts { pub topic: f64, pub focus: f64, pub tags: f64, pub proximity: f64 }
+
+/// Corpus-scan score of one entry: BM25 over `terms` times its `Boosts`, 0 if
+/// no term matches. Each term's (index, tf, BM25 part) goes to `part`, so
+/// explain breaks down this very score.
 #[inline]
-pub fn matches_tokens(tf_map: &FxHashMap<String, usize>, terms: &[String], mode: SearchMode) -> bool {
-    if terms.is_empty() { return true; }
-    match mode {
-        SearchMode::And => terms.iter().all(|t| tf_map.contains_key(t)),
-        SearchMode::Or => terms.iter().any(|t| tf_map.contains_key(t)),
+pub(crate) fn score_entry(e: &crate::cache::CachedEntry, terms: &[String], n: f64, dfs: &[usize],
+                          avgdls: &FxHashMap<&str, f64>, boost: &[crate::pattern::TopicPattern],
+                          mut part: impl FnMut(usize, usize, f64)) -> (f64, Boosts) {
+    let mut b = Boosts { topic: 1.0, focus: 1.0, tags: 1.0, proximity: 1.0 };
+    let avgdl = avgdls.get(e.topic.as_str()).copied().unwrap_or(1.0);
+    let len_norm = 1.0 - BM25_B + BM25_B * e.word_count as f64 / avgdl.max(1.0);
+    let mut score = 0.0;
+    for (i, term) in terms.iter().enumerate() {
+        let tf = *e.tf_map.get(term).unwrap_or(&0);
+        if tf == 0 { part(i, 0, 0.0); continue; }
+        let df = dfs[i] as f64;
+        let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+        let tf_f = tf as f64;
+        let p = idf * (tf_f * (BM25_K1 + 1.0)) / (tf_f + BM25_K1 * len_norm);
+        part(i, tf, p);
+        score += p;
+    }
+    if score == 0.0 { return (0.0, b); }
+    debug_assert!(e.topic.chars().all(|c| !c.is_uppercase()));
+    if terms.iter().any(|t| e.topic.contains(t.as_str())) { b.topic = 1.5; }
+    if boosted(boost, &e.topic) { b.focus = TOPIC_BOOST; }
+    if !e.tags().is_empty() {
+        let tag_hits = terms.iter()
+            .filter(|t| e.tags().iter().any(|tag| tag.contains(t.as_str())))
+            .count();
+        b.tags = 1.0 + 0.3 * tag_hits as f64;
     }
+    // Positions are only worth a body scan when two or more terms matched
+    if terms.iter().filter(|t| e.tf_map.contains_key(*t)).count() >= 2 {
+        b.proximity = proximity_mult(&e.body, terms);
+    }
+    (score * b.topic * b.focus * b.tags * b.proximity, b)
 }
 
 /// BM25 score on borrowed cache entries. Two-phase: score first, extract lines for top-K only.
 /// Phase 1 does zero String allocations. Phase 2 only allocates for `limit` entries.
 fn score_cached_mode(entries: &[&crate::cache::CachedEntry], terms: &[String],
-                     mode: SearchMode, n: f64, avgdl: f64, dfs: &[usize],
-                     limit: usize)
+                     need: MinMatch, avgdls: &FxHashMap<&str, f64>, dfs: &[usize],
+                     boost: &[crate::pattern::TopicPattern], limit: usize)
     -> Vec<ScoredResult>
 {
+    let n = entries.len() as f64;
     // Phase 1: Score only — zero String allocations
     let mut scored: Vec<(f64, usize)> = entries.iter().enumerate()
-        .filter(|(_, e)| matches_tokens(&e.tf_map, terms, mode))
+        .filter(|(_, e)| matches_tokens(&e.tf_map, terms, need))
         .filter_map(|(idx, e)| {
-            let len_norm = 1.0 - BM25_B + BM25_B * e.word_count as f64 / avgdl.max(1.0);
-            let mut score = 0.0;
-            for (i, term) in terms.iter().enumerate() {
-                let tf = *e.tf_map.get(term).unwrap_or(&0) as f64;
-                if tf == 0.0 { continue; }
-                let df = dfs[i] as f64;
-                let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
-                score += idf * (tf * (BM25_K1 + 1.0)) / (tf + BM25_K1 * len_norm);
-            }
-            if score == 0.0 { return None; }
-            debug_assert!(e.topic.chars().all(|c| !c.is_uppercase()));
-            if terms.iter().any(|t| e.topic.contains(t.as_str())) { score *= 1.5; }
-            if !e.tags().is_empty() {
-                let tag_hits = terms.iter()
-                    .filter(|t| e.tags().iter().any(|tag| tag.contains(t.as_str())))
-                    .count();
-                if tag_hits > 0 { score *= 1.0 + 0.3 * tag_hits as f64; }
-            }
-            Some((score, idx))
+            let (score, _) = score_entry(e, terms, n, dfs, avgdls, boost, |_, _, _| {});
+            (score > 0.0).then_some((score, idx))
         })
         .collect();
-    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    // Quickselect the top-K (O(N)), then sort only those K — not the whole match set
+    let by_score = |a: &(f64, usize), b: &(f64, usize)|
+        b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal).then(a.1.cmp(&b.1));
+    if limit == 0 { scored.clear(); }
+    else if limit < scored.len() {
+        scored.select_nth_unstable_by(limit - 1, by_score);
+        scored.truncate(limit);
+    }
+    scored.sort_unstable_by(by_score);
     // Phase 2: Extract lines ONLY for top-K entries
-    scored.truncate(limit);
     scored.iter().map(|&(score, idx)| {
         let e = entries[idx];
-        let mut lines = vec![format!("## {}", e.date_str())];
+        let mut lines = vec![crate::entryfmt::header(&e.date_str())];
         for line in e.body.lines() { lines.push(line.to_string()); }
-        ScoredResult { name: e.topic.to_string(), lines, score }
+        ScoredResult { name: e.topic.to_string(), lines, score, offset: e.offset }
     }).collect()
 }
 
 /// Score on cache with AND→OR fallback. Borrows token_set/tf_map from cache.
 fn score_on_cache(dir: &Path, terms: &[String], filter: &Filter, limit: Option<usize>)
-    -> Result<(Vec<ScoredResult>, bool), String>
+    -> Result<(Vec<ScoredResult>, bool), AmrError>
 {
+    let scope = filter.topic_pattern();
+    let boost = filter.boost_patterns();
     crate::cache::with_corpus(dir, |cached| {
         let filtered: Vec<&crate::cache::CachedEntry> = cached.iter()
-            .filter(|e| {
-                if let Some(ref t) = filter.topic { if e.topic != *t { return false; } }
-                passes_filter_cached(e, filter)
-            })
+            .filter(|e| in_scope(&scope, &e.topic) && passes_filter_cached(e, filter))
             .collect();
-        let n = filtered.len() as f64;
-        let total_words: usize = filtered.iter().map(|e| e.word_count).sum();
-        let avgdl = if filtered.is_empty() { 1.0 } else { total_words as f64 / n };
+        let (_, avgdls) = cohort_avgdls(&filtered);
         // Single-pass DF computation: count all term document frequencies in one scan.
         // Was: O(terms * entries) separate scans. Now: O(entries) single scan.
         let mut dfs = vec![0usize; terms.len()];
@@ -111,10 +178,10 @@ fn score_on_cache(dir: &Path, terms: &[String], filter: &Filter, limit: Option<u
             }
         }
         let cap = limit.unwrap_or(filtered.len());
-        let mut results = score_cached_mode(&filtered, terms, filter.mode, n, avgdl, &dfs, cap);
+        let mut results = score_cached_mode(&filtered, terms, filter.need(filter.mode), &avgdls, &dfs, &boost, cap);
         let mut fallback = false;
         if results.is_empty() && filter.mode == SearchMode::And && terms.len() >= 2 {
-            results = score_cached_mode(&filtered, terms, SearchMode::Or, n, avgdl, &dfs, cap);
+            results = score_cached_mode(&filtered, terms, filter.need(SearchMode::Or), &avgdls, &dfs, &boost, cap);
             fallback = !results.is_empty();
         }
         (results, fallback)
@@ -123,15 +190,16 @@ fn score_on_cache(dir: &Path, terms: &[String], filter: &Filter, limit: Option<u
 
 /// Count matches per topic directly on cache. Zero clones.
 pub fn topic_matches_cached(dir: &Path, terms: &[String], filter: &Filter)
-    -> Result<(Vec<(String, usize)>, bool), String>
+    -> Result<(Vec<(String, usize)>, bool), AmrError>
 {
+    let scope = filter.topic_pattern();
     crate::cache::with_corpus(dir, |cached| {
         let count_fn = |mode: SearchMode| -> Vec<(String, usize)> {
             let mut hits: FxHashMap<&str, usize> = FxHashMap::default();
             for e in cached {
-                if let Some(ref t) = filter.topic { if e.topic != *t { continue; } }
+                if !in_scope(&scope, &e.topic) { continue; }
                 if !passes_filter_cached(e, filter) { continue; }
-                if matches_tokens(&e.tf_map, terms, mode) {
+                if matches_tokens(&e.tf_map, terms, filter.need(mode)) {
                     *hits.entry(&e.topic).or_insert(0) += 1;
                 }
             }
@@ -149,16 +217,17 @@ pub fn topic_matches_cached(dir: &Path, terms: &[String], filter: &Filter)
 
 /// Count total matches + unique topics on cache. Zero clones.
 pub fn count_on_cache(dir: &Path, terms: &[String], filter: &Filter)
-    -> Result<(usize, usize, bool), String>
+    -> Result<(usize, usize, bool), AmrError>
 {
+    let scope = filter.topic_pattern();
     crate::cache::with_corpus(dir, |cached| {
         let do_count = |mode: SearchMode| -> (usize, usize) {
             let mut total = 0;
             let mut topics: FxHashSet<&str> = FxHashSet::default();
             for e in cached {
-                if let Some(ref t) = filter.topic { if e.topic != *t { continue; } }
+                if !in_scope(&scope, &e.topic) { continue; }
                 if !passes_filter_cached(e, filter) { continue; }
-                if matches_tokens(&e.tf_map, terms, mode) {
+                if matches_tokens(&e.tf_map, terms, filter.need(mode)) {
                     total += 1;
                     topics.insert(&e.topic);
                 }
@@ -175,36 +244,52 @@ pub fn count_on_cache(dir: &Path, terms: &[String], filter: &Filter)
     })
 }
 
-fn passes_filter_cached(e: &crate::cache::CachedEntry, f: &Filter) -> bool {
-    if f.after.is_some() || f.before.is_some() {
-        let days = e.day();
-        if let Some(after) = f.after { if days < after { return false; } }
-        if let Some(before) = f.before { if days > before { return false; } }
-    }
-    if let Some(ref tag) = f.tag {
-        if !e.has_tag(tag) { return false; }
-    }
-    true
-}
-
 /// Unified search: tries binary index first, falls back to cached corpus scan.
 /// Tag-filtered queries use index path when tag is in top-32 bitmap.
 /// full_body=false uses index snippets only (no data.log I/O) for brief/medium.
 pub fn search_scored(dir: &Path, terms: &[String], filter: &Filter, limit: Option<usize>,
                      index_data: Option<&[u8]>, full_body: bool)
-    -> Result<(Vec<ScoredResult>, bool), String>
+    -> Result<(Vec<ScoredResult>, bool), AmrError>
 {
+    let mut results = Vec::new();
+    let (_, fallback) = visit_hits(dir, terms, filter, limit, index_data, full_body, |r| {
+        results.push(r);
+        ControlFlow::Continue(())
+    })?;
+    Ok((results, fallback))
+}
+
+/// Streaming form of search_scored: same ranking, results handed to `f`
+/// best-first. Returning Break stops early — on the index path, remaining hits
+/// are never materialized into lines. Returns (visited, AND→OR fallback used).
+/// The corpus-scan path ranks fully before the first callback.
+pub fn visit_hits<F>(dir: &Path, terms: &[String], filter: &Filter, limit: Option<usize>,
+                     index_data: Option<&[u8]>, full_body: bool, mut f: F)
+    -> Result<(usize, bool), AmrError>
+where F: FnMut(ScoredResult) -> ControlFlow<()> {
+    let visit_all = |results: Vec<ScoredResult>, f: &mut F| {
+        let mut n = 0;
+        for r in results {
+            n += 1;
+            if f(r).is_break() { break; }
+        }
+        n
+    };
+    // Drop a truncation note left by an earlier query on this thread
+    let _ = crate::costcap::take_truncation();
+    let _ = crate::expand::take_resolution();
     if terms.is_empty() {
-        return score_on_cache(dir, terms, filter, limit);
+        let (results, fallback) = score_on_cache(dir, terms, filter, limit)?;
+        return Ok((visit_all(results, &mut f), fallback));
     }
 
-    // Try index path — prefer cached data, fall back to disk read
+    // Try index path — prefer cached data, fall back to the shared index.bin
     let fallback_data;
     let data = match index_data {
         Some(d) => Some(d),
         None => {
-            fallback_data = std::fs::read(dir.join("index.bin")).ok();
-            fallback_data.as_deref()
+            fallback_data = crate::indexview::fresh(dir).ok().flatten();
+            fallback_data.as_deref().map(|m| m.bytes())
         }
     };
     if let Some(data) = data {
@@ -213,123 +298,219 @@ pub fn search_scored(dir: &Path, terms: &[String], filter: &Filter, limit: Optio
             Some(tag) => crate::binquery::resolve_tag(data, tag).is_some(),
         };
         if tag_on_index {
-            if let Ok(result) = score_via_index(dir, data, terms, filter, limit, full_body) {
-                return Ok(result);
+            if let Ok((hits, fallback)) = index_hits(data, terms, filter, limit) {
+                // Hybrid goes on to the scan: an empty index result is the widest divergence
+                if hits.is_empty() && !filter.hybrid { return Ok((0, false)); }
+                let boost = filter.boost_patterns();
+                if let Ok(mut h) = Hydrator::new(dir, data, terms, full_body, &boost) {
+                    if filter.hybrid {
+                        let indexed: Vec<ScoredResult> = h.rank(&hits).into_iter().take(limit.unwrap_or(usize::MAX))
+                            .map(|(i, score)| h.materialize(&hits[i], score)).collect();
+                        let (scanned, scan_fallback) = score_on_cache(dir, terms, filter, limit)?;
+                        let results = fuse(indexed, scanned, limit);
+                        return Ok((visit_all(results, &mut f), fallback || scan_fallback));
+                    }
+                    let mut n = 0;
+                    // Boosting over-fetches (index_hits); keep the caller's limit
+                    for (i, score) in h.rank(&hits).into_iter().take(limit.unwrap_or(usize::MAX)) {
+                        n += 1;
+                        if f(h.materialize(&hits[i], score)).is_break() { break; }
+                    }
+                    return Ok((n, fallback));
+                }
             }
         }
     }
 
     // Fallback: score on borrowed cache entries (no clone storm)
-    score_on_cache(dir, terms, filter, limit)
+    let (results, fallback) = score_on_cache(dir, terms, filter, limit)?;
+    Ok((visit_all(results, &mut f), fallback))
+}
+
+/// Hybrid merge of the index and corpus-scan rankings. The index misses what
+/// it doesn't store (1-char tokens, tags past the top-32 bitmap), so when the
+/// two top-K sets overlap less than HYBRID_MIN_OVERLAP the results are ranked
+/// by reciprocal rank fusion over both lists; otherwise the index ranking
+/// stands. A fused result's score is its RRF score.
+fn fuse(indexed: Vec<ScoredResult>, scanned: Vec<ScoredResult>, limit: Option<usize>) -> Vec<ScoredResult> {
+    let k = limit.unwrap_or(20).max(1);
+    let top = |v: &[ScoredResult]| v.iter().take(k).map(|r| r.offset).collect::<FxHashSet<u32>>();
+    let (a, b) = (top(&indexed), top(&scanned));
+    let larger = a.len().max(b.len());
+    if larger == 0 { return indexed; }
+    if a.intersection(&b).count() as f64 / larger as f64 >= HYBRID_MIN_OVERLAP { return indexed; }
+    let mut fused: FxHashMap<u32, (f64, ScoredResult)> = FxHashMap::default();
+    for list in [indexed, scanned] {
+        for (rank, r) in list.into_iter().enumerate() {
+            let rrf = 1.0 / (RRF_K + rank as f64 + 1.0);
+            // First list wins the body: index hydration already picked the lines
+            fused.entry(r.offset).and_modify(|e| e.0 += rrf).or_insert((rrf, r));
+        }
+    }
+    let mut out: Vec<(f64, ScoredResult)> = fused.into_values().collect();
+    out.sort_by(|x, y| y.0.partial_cmp(&x.0).unwrap_or(std::cmp::Ordering::Equal).then(x.1.offset.cmp(&y.1.offset)));
+    out.into_iter().take(limit.unwrap_or(usize::MAX)).map(|(s, mut r)| { r.score = s; r }).collect()
 }
 
-/// Score using binary inverted index with FilterPred for pre-scoring elimination.
-fn score_via_index(dir: &Path, index_data: &[u8], terms: &[String],
-                   filter: &Filter, limit: Option<usize>, full_body: bool)
-    -> Result<(Vec<ScoredResult>, bool), String>
+/// Index top-K with FilterPred pre-scoring elimination and AND→OR relaxation.
+/// Returns (hits, relaxed).
+fn index_hits(index_data: &[u8], terms: &[String], filter: &Filter, limit: Option<usize>)
+    -> Result<(Vec<crate::binquery::SearchHit>, bool), AmrError>
 {
     let pred = build_filter_pred(index_data, filter);
     let index_limit = limit.unwrap_or(20);
+    // Boosted topics can rank past the unboosted top-K; fetch deeper to reach them
+    let index_limit = if filter.boost_topics.is_empty() { index_limit } else { index_limit * 3 };
     let query_str = terms.join(" ");
-    let hits = crate::binquery::search_v2_filtered(index_data, &query_str, &pred, index_limit)?;
+    let hits = crate::binquery::search_v2_min(index_data, &query_str, &pred, filter.need(filter.mode), index_limit)?;
 
     if hits.is_empty() && filter.mode == SearchMode::And && terms.len() >= 2 {
         let or_hits = crate::binquery::search_v2_or(index_data, &query_str, &pred, index_limit)?;
-        if !or_hits.is_empty() {
-            return hydrate_index_hits(dir, index_data, terms, &or_hits, true, full_body);
-        }
-        return Ok((Vec::new(), false));
+        let relaxed = !or_hits.is_empty();
+        return Ok((or_hits, relaxed));
     }
-
-    hydrate_index_hits(dir, index_data, terms, &hits, false, full_body)
+    Ok((hits, false))
 }
 
-fn build_filter_pred(index_data: &[u8], filter: &Filter) -> crate::binquery::FilterPred {
-    let topic_id = match &filter.topic {
-        Some(name) => crate::binquery::resolve_topic(index_data, name),
-        None => None,
-    };
-    let after_days = filter.after.map(|d| d.max(0) as u16).unwrap_or(0);
-    let before_days = filter.before.map(|d| d.min(u16::MAX as i64) as u16).unwrap_or(u16::MAX);
-    let tag_mask = match &filter.tag {
-        Some(tag) => crate::binquery::resolve_tag(index_data, tag)
-            .map(|bit| 1u32 << bit).unwrap_or(0),
-        None => 0,
-    };
-    crate::binquery::FilterPred { topic_id, after_days, before_days, tag_mask }
+/// Hydrates index hits into ScoredResults in two steps so streaming callers
+/// only pay for what they consume: `rank` applies topic/tag/proximity boosts (bodies are
+/// borrowed from the data.log mmap, no allocation), `materialize` builds lines.
+/// full_body=true: complete entry bodies from data.log (full/grouped output).
+/// full_body=false: index snippets + tag bitmap only (zero data.log I/O).
+struct Hydrator<'a> {
+    dir: &'a Path,
+    index_data: &'a [u8],
+    terms: &'a [String],
+    full_body: bool,
+    boost: &'a [crate::pattern::TopicPattern],
+    name_cache: FxHashMap<u16, String>,
+    log_map: Option<std::sync::Arc<crate::logview::LogMap>>,
+    log_file: Option<std::fs::File>,
 }
 
-/// Hydrate index hits into ScoredResults.
-/// full_body=true: reads data.log for complete entry bodies (for full/grouped output).
-/// full_body=false: uses index snippets + tag bitmap only (zero data.log I/O).
-fn hydrate_index_hits(dir: &Path, index_data: &[u8], terms: &[String],
-                      hits: &[crate::binquery::SearchHit], fallback: bool, full_body: bool)
-    -> Result<(Vec<ScoredResult>, bool), String>
-{
-    if hits.is_empty() { return Ok((Vec::new(), false)); }
-
-    let mut name_cache: FxHashMap<u16, String> = FxHashMap::default();
-    // Only open data.log when full body is needed
-    let mut log_file = if full_body {
-        let log_path = crate::config::log_path(dir);
-        Some(std::fs::File::open(&log_path).map_err(|e| format!("open data.log: {e}"))?)
-    } else { None };
-    let mut results = Vec::with_capacity(hits.len());
-
-    for hit in hits {
-        // Entry API: single lookup, clone only when building ScoredResult
-        use std::collections::hash_map::Entry;
-        let topic_ref = match name_cache.entry(hit.topic_id) {
-            Entry::Occupied(e) => e.into_mut(),
-            Entry::Vacant(e) => match crate::binquery::topic_name(index_data, hit.topic_id) {
-                Ok(n) => e.insert(n),
-                Err(_) => continue,
-            },
-        };
-        let mut score = hit.score;
-
-        // Topic-name boost — topic names are already lowercase (config::sanitize_topic)
-        if terms.iter().any(|t| topic_ref.contains(t.as_str())) { score *= 1.5; }
-
-        if full_body {
-            // Full hydration: read entry body from data.log
-            let entry = crate::datalog::read_entry_from(log_file.as_mut().unwrap(), hit.log_offset)
-                .unwrap_or(crate::datalog::LogEntry {
-                    offset: hit.log_offset, topic: topic_ref.clone(),
-                    body: String::new(), timestamp_min: hit.date_minutes,
-                });
-            // Tag boost from body — tags already stored lowercase
-            for line in entry.body.lines() {
-                if line.starts_with("[tags: ") {
-                    let tag_hits = terms.iter().filter(|t| line.contains(t.as_str())).count();
-                    if tag_hits > 0 { score *= 1.0 + 0.3 * tag_hits as f64; }
-                    break;
-                }
+impl<'a> Hydrator<'a> {
+    fn new(dir: &'a Path, index_data: &'a [u8], terms: &'a [String], full_body: bool,
+           boost: &'a [crate::pattern::TopicPattern]) -> Result<Self, AmrError>
+    {
+        // Only map data.log when full body is needed — shared mmap, bodies borrowed in place
+        let log_map = if full_body { Some(crate::logview::view(dir)?) } else { None };
+        Ok(Self { dir, index_data, terms, full_body, boost, name_cache: FxHashMap::default(),
+                  log_map, log_file: None })
+    }
+
+    /// Boosted scores, best-first, as (index into hits, score).
+    /// Hits whose topic name can't be resolved are dropped.
+    fn rank(&mut self, hits: &[crate::binquery::SearchHit]) -> Vec<(usize, f64)> {
+        let mut ranked = Vec::with_capacity(hits.len());
+        for (i, hit) in hits.iter().enumerate() {
+            let (terms, boost) = (self.terms, self.boost);
+            let Some(topic) = self.topic(hit.topic_id) else { continue };
+            let mut score = hit.score;
+            // Topic-name boost — topic names are already lowercase (config::sanitize_topic)
+            if terms.iter().any(|t| topic.contains(t.as_str())) { score *= 1.5; }
+            if boosted(boost, topic) { score *= TOPIC_BOOST; }
+            // Tag boost — tags already stored lowercase
+            let tag_hits = if self.full_body {
+                self.with_body(hit, |body, _| {
+                    body.lines().find(|l| crate::entryfmt::is_field(l, "tags"))
+                        .map_or(0, |l| terms.iter().filter(|t| l.contains(t.as_str())).count())
+                })
+            } else {
+                crate::binquery::reconstruct_tags(self.index_data, hit.entry_id).ok().flatten()
+                    .map_or(0, |tl| terms.iter().filter(|t| tl.contains(t.as_str())).count())
+            };
+            if tag_hits > 0 { score *= 1.0 + 0.3 * tag_hits as f64; }
+            // Proximity — full bodies when mapped, else the index snippet window
+            if terms.len() >= 2 {
+                score *= if self.full_body {
+                    self.with_body(hit, |body, _| proximity_mult(body, terms))
+                } else {
+                    // Drop the "[topic] " label so topic-name terms don't count as positions
+                    let content = hit.snippet.split_once("] ").map_or(hit.snippet.as_str(), |(_, c)| c);
+                    proximity_mult(content, terms)
+                };
             }
-            let date = crate::time::minutes_to_date_str(entry.timestamp_min);
-            let mut lines = vec![format!("## {date}")];
-            for line in entry.body.lines() { lines.push(line.to_string()); }
-            results.push(ScoredResult { name: topic_ref.clone(), lines, score });
+            ranked.push((i, score));
+        }
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ranked
+    }
+
+    fn materialize(&mut self, hit: &crate::binquery::SearchHit, score: f64) -> ScoredResult {
+        let name = self.topic(hit.topic_id).cloned().unwrap_or_default();
+        if self.full_body {
+            let lines = self.with_body(hit, |body, ts_min| {
+                let date = crate::time::minutes_to_date_str(ts_min);
+                let mut lines = Vec::with_capacity(8);
+                lines.push(crate::entryfmt::header(&date));
+                for line in body.lines() { lines.push(line.to_string()); }
+                lines
+            });
+            ScoredResult { name, lines, score, offset: hit.log_offset }
         } else {
             // Light hydration: build lines from index data only (zero data.log I/O)
-            let tag_line = crate::binquery::reconstruct_tags(index_data, hit.entry_id).ok().flatten();
-            // Tag boost from reconstructed bitmap tags — already lowercase
-            if let Some(ref tl) = tag_line {
-                let tag_hits = terms.iter().filter(|t| tl.contains(t.as_str())).count();
-                if tag_hits > 0 { score *= 1.0 + 0.3 * tag_hits as f64; }
-            }
+            let tag_line = crate::binquery::reconstruct_tags(self.index_data, hit.entry_id).ok().flatten();
             let date = crate::time::minutes_to_date_str(hit.date_minutes);
-            let mut lines = vec![format!("## {date}")];
+            let mut lines = vec![crate::entryfmt::header(&date)];
             if let Some(tl) = tag_line { lines.push(tl); }
             // Extract content from snippet (strip "[topic] date " prefix)
-            let prefix = format!("[{}] {} ", topic_ref, date);
+            let prefix = format!("[{}] {} ", name, date);
             let content = hit.snippet.strip_prefix(&prefix).unwrap_or(&hit.snippet);
             if !content.is_empty() { lines.push(content.to_string()); }
-            results.push(ScoredResult { name: topic_ref.clone(), lines, score });
+            ScoredResult { name, lines, score, offset: hit.log_offset }
         }
     }
-    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-    Ok((results, fallback))
+
+    fn topic(&mut self, topic_id: u16) -> Option<&String> {
+        use std::collections::hash_map::Entry;
+        match self.name_cache.entry(topic_id) {
+            Entry::Occupied(e) => Some(e.into_mut()),
+            Entry::Vacant(e) => crate::binquery::topic_name(self.index_data, topic_id).ok()
+                .map(|n| &*e.insert(n)),
+        }
+    }
+
+    /// Borrow the entry body from the data.log mapping (zero-copy).
+    /// Non-UTF-8 bodies fall back to a lossy pread of the single record.
+    fn with_body<R>(&mut self, hit: &crate::binquery::SearchHit, f: impl FnOnce(&str, i32) -> R) -> R {
+        let mapped = self.log_map.as_ref()
+            .and_then(|m| crate::datalog::entry_ref(m.bytes(), hit.log_offset));
+        if let Some(e) = mapped { return f(e.body, e.timestamp_min); }
+        if self.log_file.is_none() {
+            self.log_file = std::fs::File::open(crate::config::log_path(self.dir)).ok();
+        }
+        let owned = self.log_file.as_mut()
+            .and_then(|file| crate::datalog::read_entry_from(file, hit.log_offset).ok());
+        match &owned {
+            Some(e) => f(&e.body, e.timestamp_min),
+            None => f("", hit.date_minutes),
+        }
+    }
+}
+
+/// Why a query was relaxed: entries in filter scope containing each term, and
+/// the most distinct terms any one entry contains.
+pub struct TermStats {
+    pub per_term: Vec<(String, usize)>,
+    pub best_overlap: usize,
+}
+
+/// Per-term match counts under `filter`, for relaxation feedback. One corpus scan.
+pub fn term_stats(dir: &Path, terms: &[String], filter: &Filter) -> Result<TermStats, AmrError> {
+    let scope = filter.topic_pattern();
+    crate::cache::with_corpus(dir, |cached| {
+        let mut counts = vec![0usize; terms.len()];
+        let mut best_overlap = 0;
+        for e in cached {
+            if !in_scope(&scope, &e.topic) || !passes_filter_cached(e, filter) { continue; }
+            let mut overlap = 0;
+            for (i, t) in terms.iter().enumerate() {
+                if e.tf_map.contains_key(t) { counts[i] += 1; overlap += 1; }
+            }
+            best_overlap = best_overlap.max(overlap);
+        }
+        TermStats { per_term: terms.iter().cloned().zip(counts).collect(), best_overlap }
+    })
 }
 
 /// Collect all tags from cache for no-match suggestions.
@@ -2,31 +2,37 @@
 //! Scoring lives in score.rs (index-accelerated with corpus fallback).
 
 use std::fmt::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use crate::error::AmrError;
 use crate::text::{query_terms, truncate, extract_tags};
-pub use crate::score::{Filter, SearchMode};
+use crate::theme::{paint, Role};
+pub use crate::score::{Filter, MinMatch, SearchMode};
 
 pub fn run(dir: &Path, query: &str, plain: bool, limit: Option<usize>, filter: &Filter,
-           index_data: Option<&[u8]>) -> Result<String, String> {
+           index_data: Option<&[u8]>) -> Result<String, AmrError> {
     let terms = query_terms(query);
-    if terms.is_empty() && !filter.is_active() { return Err("provide a query or filter".into()); }
+    if terms.is_empty() && !filter.is_active() { return Err(AmrError::InvalidArg("provide a query or filter".into())); }
     let (results, fallback) = crate::score::search_scored(dir, &terms, filter, limit, index_data, true)?;
     let total = results.len();
     let show = limit.map(|l| total.min(l)).unwrap_or(total);
 
+    let addrs = addresses(dir, &results[..show]);
     let mut out = String::new();
     if fallback { let _ = writeln!(out, "(no exact match — showing {} OR results)", results.len()); }
     let mut last_file = String::new();
     for r in results.iter().take(show) {
         if r.name != last_file {
             if plain { let _ = writeln!(out, "\n--- {} ---", r.name); }
-            else { let _ = writeln!(out, "\n\x1b[1;36m--- {} ---\x1b[0m", r.name); }
+            else { let _ = writeln!(out, "\n{}", paint(Role::Heading, &format!("--- {} ---", r.name))); }
             last_file = r.name.clone();
         }
+        if let Some(&(idx, uid)) = addrs.get(&r.offset) {
+            let _ = writeln!(out, "@ {}", crate::address::format_both(&r.name, idx, uid));
+        }
         for line in r.lines.iter() {
             if !terms.is_empty() && terms.iter().any(|t| contains_ci(line, t)) {
                 if plain { let _ = writeln!(out, "> {line}"); }
-                else { let _ = writeln!(out, "\x1b[1;33m{line}\x1b[0m"); }
+                else { let _ = writeln!(out, "{}", paint(Role::Match, line)); }
             } else { let _ = writeln!(out, "{line}"); }
         }
         let _ = writeln!(out);
@@ -34,16 +40,18 @@ pub fn run(dir: &Path, query: &str, plain: bool, limit: Option<usize>, filter: &
     if total == 0 { out.push_str(&no_match_message(query, filter, dir)); }
     else if show < total { let _ = writeln!(out, "(showing {show} of {total} matches)"); }
     else { let _ = writeln!(out, "{total} matching section(s)"); }
+    relaxation_footer(&mut out, dir, &terms, filter, fallback, total);
     Ok(out)
 }
 
 pub fn run_brief(dir: &Path, query: &str, limit: Option<usize>, filter: &Filter,
-                 index_data: Option<&[u8]>) -> Result<String, String> {
+                 index_data: Option<&[u8]>) -> Result<String, AmrError> {
     let terms = query_terms(query);
-    if terms.is_empty() && !filter.is_active() { return Err("provide a query or filter".into()); }
+    if terms.is_empty() && !filter.is_active() { return Err(AmrError::InvalidArg("provide a query or filter".into())); }
     let (results, fallback) = crate::score::search_scored(dir, &terms, filter, limit, index_data, false)?;
     let total = results.len();
     let show = limit.map(|l| total.min(l)).unwrap_or(total);
+    let addrs = addresses(dir, &results[..show]);
     let mut out = String::new();
     if fallback { let _ = writeln!(out, "(no exact match — showing OR results)"); }
     for r in results.iter().take(show) {
@@ -53,29 +61,31 @@ pub fn run_brief(dir: &Path, query: &str, limit: Option<usize>, filter: &Filter,
             .find(|l| !crate::text::is_metadata_line(l) && !l.trim().is_empty())
             .map(|l| truncate(l.trim().trim_start_matches("- "), 80))
             .unwrap_or("");
-        let _ = writeln!(out, "  [{}] {content}{tag_suffix}", r.name);
+        let _ = writeln!(out, "  [{}] {content}{tag_suffix}", label(r, &addrs));
     }
     if total == 0 { out.push_str(&no_match_message(query, filter, dir)); }
     else { let _ = writeln!(out, "{total} match(es)"); }
+    relaxation_footer(&mut out, dir, &terms, filter, fallback, total);
     Ok(out)
 }
 
 pub fn run_medium(dir: &Path, query: &str, limit: Option<usize>, filter: &Filter,
-                  index_data: Option<&[u8]>) -> Result<String, String> {
+                  index_data: Option<&[u8]>) -> Result<String, AmrError> {
     let terms = query_terms(query);
-    if terms.is_empty() && !filter.is_active() { return Err("provide a query or filter".into()); }
+    if terms.is_empty() && !filter.is_active() { return Err(AmrError::InvalidArg("provide a query or filter".into())); }
     let (results, fallback) = crate::score::search_scored(dir, &terms, filter, limit, index_data, false)?;
     let total = results.len();
     let show = limit.map(|l| total.min(l)).unwrap_or(total);
+    let addrs = addresses(dir, &results[..show]);
     let mut out = String::new();
     if fallback { let _ = writeln!(out, "(no exact match — showing OR results)"); }
     for r in results.iter().take(show) {
         let header = r.lines.first().map(|s| s.as_str()).unwrap_or("??");
         let tags = extract_tags(&r.lines);
         if let Some(ref t) = tags {
-            let _ = writeln!(out, "  [{}] {} {}", r.name, header.trim_start_matches("## "), t);
+            let _ = writeln!(out, "  [{}] {} {}", label(r, &addrs), crate::entryfmt::header_date(header).unwrap_or(header), t);
         } else {
-            let _ = writeln!(out, "  [{}] {}", r.name, header.trim_start_matches("## "));
+            let _ = writeln!(out, "  [{}] {}", label(r, &addrs), crate::entryfmt::header_date(header).unwrap_or(header));
         }
         let mut content_lines = 0;
         for line in r.lines.iter().skip(1) {
@@ -88,12 +98,54 @@ pub fn run_medium(dir: &Path, query: &str, limit: Option<usize>, filter: &Filter
     if total == 0 { out.push_str(&no_match_message(query, filter, dir)); }
     else if show < total { let _ = writeln!(out, "{total} match(es), showing {show}"); }
     else { let _ = writeln!(out, "{total} match(es)"); }
+    relaxation_footer(&mut out, dir, &terms, filter, fallback, total);
     Ok(out)
 }
 
-pub fn run_topics(dir: &Path, query: &str, filter: &Filter) -> Result<String, String> {
+/// One query over several knowledge bases (config::resolve_dirs): each dir is
+/// scored on its own index, then the hits are merged by BM25 score and shown
+/// brief-style under their source's label. Scores from different corpora are
+/// only roughly comparable (each has its own idf): good enough to interleave
+/// a cross-project lookup, not to rank within one KB.
+pub fn run_federated(dirs: &[(String, PathBuf)], query: &str, limit: Option<usize>,
+                     filter: &Filter) -> Result<String, AmrError> {
     let terms = query_terms(query);
-    if terms.is_empty() && !filter.is_active() { return Err("provide a query or filter".into()); }
+    if terms.is_empty() && !filter.is_active() { return Err(AmrError::InvalidArg("provide a query or filter".into())); }
+    let mut hits: Vec<(f64, String)> = Vec::new();
+    let mut per_dir = Vec::new();
+    let mut relaxed_in = Vec::new();
+    for (name, dir) in dirs {
+        let index = crate::indexview::fresh(dir)?;
+        let (results, fallback) = crate::score::search_scored(
+            dir, &terms, filter, limit, index.as_ref().map(|m| m.bytes()), false)?;
+        if fallback && !results.is_empty() { relaxed_in.push(name.as_str()); }
+        let addrs = addresses(dir, &results);
+        for r in &results {
+            let tags = extract_tags(&r.lines).map(|t| format!(" {t}")).unwrap_or_default();
+            let content = r.lines.iter().skip(1)
+                .find(|l| !crate::text::is_metadata_line(l) && !l.trim().is_empty())
+                .map(|l| truncate(l.trim().trim_start_matches("- "), 80))
+                .unwrap_or("");
+            hits.push((r.score, format!("  [{name}] [{}] {content}{tags}", label(r, &addrs))));
+        }
+        per_dir.push(format!("{name} {}", results.len()));
+    }
+    hits.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let total = hits.len();
+    let show = limit.map(|l| total.min(l)).unwrap_or(total);
+    let mut out = String::new();
+    if !relaxed_in.is_empty() { let _ = writeln!(out, "(no exact match in {} — showing OR results there)", relaxed_in.join(", ")); }
+    for (_, line) in hits.iter().take(show) { let _ = writeln!(out, "{line}"); }
+    if total == 0 { let _ = write!(out, "0 matches for '{query}'"); }
+    else if show < total { let _ = write!(out, "{total} match(es), showing {show}"); }
+    else { let _ = write!(out, "{total} match(es)"); }
+    let _ = writeln!(out, " across {} dirs ({})", dirs.len(), per_dir.join(", "));
+    Ok(out)
+}
+
+pub fn run_topics(dir: &Path, query: &str, filter: &Filter) -> Result<String, AmrError> {
+    let terms = query_terms(query);
+    if terms.is_empty() && !filter.is_active() { return Err(AmrError::InvalidArg("provide a query or filter".into())); }
     let (hits, fallback) = crate::score::topic_matches_cached(dir, &terms, filter)?;
     let total: usize = hits.iter().map(|(_, n)| n).sum();
     let mut out = String::new();
@@ -103,28 +155,36 @@ pub fn run_topics(dir: &Path, query: &str, filter: &Filter) -> Result<String, St
         for (topic, n) in &hits { let _ = writeln!(out, "  {topic}: {n} hit{}", if *n == 1 { "" } else { "s" }); }
         let _ = writeln!(out, "{total} match(es) across {} topic(s)", hits.len());
     }
+    relaxation_footer(&mut out, dir, &terms, filter, fallback, total);
     Ok(out)
 }
 
-pub fn count(dir: &Path, query: &str, filter: &Filter) -> Result<String, String> {
+pub fn count(dir: &Path, query: &str, filter: &Filter) -> Result<String, AmrError> {
     let terms = query_terms(query);
-    if terms.is_empty() && !filter.is_active() { return Err("provide a query or filter".into()); }
+    if terms.is_empty() && !filter.is_active() { return Err(AmrError::InvalidArg("provide a query or filter".into())); }
     let (total, topics, fallback) = crate::score::count_on_cache(dir, &terms, filter)?;
-    if total > 0 {
+    let mut out = if total > 0 {
         let prefix = if fallback { "(OR fallback) " } else { "" };
-        Ok(format!("{prefix}{total} matches across {topics} topics for '{query}'"))
+        format!("{prefix}{total} matches across {topics} topics for '{query}'")
     } else {
-        Ok(format!("0 matches for '{query}'"))
-    }
+        format!("0 matches for '{query}'")
+    };
+    let mut hint = String::new();
+    if total == 0 { spelling_hint(&mut hint, dir, query); }
+    if !hint.is_empty() || relaxed(&terms, filter, fallback, total) { out.push('\n'); }
+    out.push_str(&hint);
+    relaxation_footer(&mut out, dir, &terms, filter, fallback, total);
+    Ok(out)
 }
 
 pub fn run_grouped(dir: &Path, query: &str, limit_per_topic: Option<usize>, filter: &Filter,
-                   index_data: Option<&[u8]>) -> Result<String, String> {
+                   index_data: Option<&[u8]>) -> Result<String, AmrError> {
     let terms = query_terms(query);
-    if terms.is_empty() { return Err("query required for entity search".into()); }
+    if terms.is_empty() { return Err(AmrError::InvalidArg("query required for entity search".into())); }
     let (results, fallback) = crate::score::search_scored(dir, &terms, filter, None, index_data, true)?;
     if results.is_empty() { return Ok(no_match_message(query, filter, dir)); }
     let cap = limit_per_topic.unwrap_or(5);
+    let addrs = addresses(dir, &results);
     let mut groups: std::collections::BTreeMap<String, Vec<&crate::score::ScoredResult>> = std::collections::BTreeMap::new();
     for r in &results { groups.entry(r.name.clone()).or_default().push(r); }
     let mut topic_order: Vec<(String, f64)> = groups.iter()
@@ -134,12 +194,17 @@ pub fn run_grouped(dir: &Path, query: &str, limit_per_topic: Option<usize>, filt
     let mut out = String::new();
     if fallback { let _ = writeln!(out, "(no exact match — showing OR results)"); }
     let _ = writeln!(out, "'{}' across {} topics ({} matches):\n", query, groups.len(), total);
+    let mut footer = String::new();
+    relaxation_footer(&mut footer, dir, &terms, filter, fallback, total);
     for (name, _) in &topic_order {
         let entries = &groups[name];
         let _ = writeln!(out, "[{}] {} matches", name, entries.len());
         for r in entries.iter().take(cap) {
             let header = r.lines.first().map(|s| s.as_str()).unwrap_or("??");
-            let _ = write!(out, "  {} — ", header.trim_start_matches("## "));
+            match addrs.get(&r.offset) {
+                Some((idx, _)) => { let _ = write!(out, "  {name}:{idx} {} — ", crate::entryfmt::header_date(header).unwrap_or(header)); }
+                None => { let _ = write!(out, "  {} — ", crate::entryfmt::header_date(header).unwrap_or(header)); }
+            }
             if let Some(line) = r.lines.iter().skip(1)
                 .find(|l| !crate::text::is_metadata_line(l) && !l.trim().is_empty()) {
                 let _ = writeln!(out, "{}", truncate(line.trim(), 90));
@@ -148,9 +213,65 @@ pub fn run_grouped(dir: &Path, query: &str, limit_per_topic: Option<usize>, filt
         if entries.len() > cap { let _ = writeln!(out, "  ...and {} more", entries.len() - cap); }
         let _ = writeln!(out);
     }
+    out.push_str(&footer);
     Ok(out)
 }
 
+/// True if results were loosened from strict AND: OR fallback, a `min_match`
+/// below all terms, or a multi-term query that found nothing at all.
+fn relaxed(terms: &[String], filter: &Filter, fallback: bool, total: usize) -> bool {
+    if terms.len() < 2 { return false; }
+    let partial = filter.mode == SearchMode::And && filter.min_match.required(terms.len()) < terms.len();
+    fallback || partial || total == 0
+}
+
+/// Footer explaining a relaxed query so callers can reformulate: how matching
+/// was loosened, entries per term, and which terms matched nothing (a query
+/// with no results at all gets it too — OR was tried and also failed). Fixed
+/// `key: value` lines, one per fact:
+///   relaxed: and→or (no entry has all 3 terms; best has 2)
+///   term_hits: cache=12 writer=3 qqq=0
+///   unmatched: qqq
+///   truncated: over the cost cap (max_postings = 200000): kept the rarest 9 of 15 terms, dropped ...
+///   resolved: tokenzie→tokenize tokeniz*→tokenize,tokenizer
+fn relaxation_footer(out: &mut String, dir: &Path, terms: &[String], filter: &Filter,
+                     fallback: bool, total: usize) {
+    if let Some(t) = crate::costcap::take_truncation() { let _ = writeln!(out, "truncated: {t}"); }
+    if let Some(r) = crate::expand::take_resolution() { let _ = writeln!(out, "resolved: {r}"); }
+    if !relaxed(terms, filter, fallback, total) { return; }
+    let Ok(stats) = crate::score::term_stats(dir, terms, filter) else { return };
+    let n = terms.len();
+    let _ = if fallback {
+        writeln!(out, "relaxed: and→or (no entry has all {n} terms; best has {})", stats.best_overlap)
+    } else if total == 0 {
+        writeln!(out, "relaxed: and→or (no entry has any of the {n} terms)")
+    } else {
+        writeln!(out, "relaxed: min_match {} of {n} (best has {})",
+            filter.min_match.required(n), stats.best_overlap)
+    };
+    let hits: Vec<String> = stats.per_term.iter().map(|(t, c)| format!("{t}={c}")).collect();
+    let _ = writeln!(out, "term_hits: {}", hits.join(" "));
+    let unmatched: Vec<&str> = stats.per_term.iter().filter(|(_, c)| *c == 0).map(|(t, _)| t.as_str()).collect();
+    if !unmatched.is_empty() { let _ = writeln!(out, "unmatched: {}", unmatched.join(" ")); }
+    if fallback && stats.best_overlap >= 2 && stats.best_overlap < n {
+        let _ = writeln!(out, "hint: min_match={} requires the best overlap instead of any one term", stats.best_overlap);
+    }
+}
+
+/// Entry index + uid for each shown result (address.rs).
+fn addresses(dir: &Path, shown: &[crate::score::ScoredResult]) -> crate::fxhash::FxHashMap<u32, (usize, u64)> {
+    let offsets: Vec<u32> = shown.iter().map(|r| r.offset).collect();
+    crate::address::lookup(dir, &offsets)
+}
+
+/// `topic:N` entry address, or just the topic if the entry can't be placed.
+fn label(r: &crate::score::ScoredResult, addrs: &crate::fxhash::FxHashMap<u32, (usize, u64)>) -> String {
+    match addrs.get(&r.offset) {
+        Some((idx, _)) => format!("{}:{idx}", r.name),
+        None => r.name.clone(),
+    }
+}
+
 /// Case-insensitive substring check without allocation.
 /// Needle must already be lowercase (query_terms guarantees this).
 #[inline]
@@ -177,5 +298,17 @@ fn no_match_message(query: &str, filter: &Filter, dir: &Path) -> String {
         }
         let _ = writeln!(msg, "no entries with tag '{}' match '{}'", tag, query);
     } else { let _ = writeln!(msg, "no matches for '{query}'"); }
+    spelling_hint(&mut msg, dir, query);
     msg
 }
+
+/// `did you mean "cache writer"? (cahce→cache)` when some query terms match
+/// no entry but are a typo away from a corpus term (spell.rs).
+fn spelling_hint(out: &mut String, dir: &Path, query: &str) {
+    let terms = query_terms(query);
+    let fixes = crate::spell::suggest(dir, &terms);
+    if fixes.is_empty() { return; }
+    let each: Vec<String> = fixes.iter().map(|(bad, good)| format!("{bad}→{good}")).collect();
+    let _ = writeln!(out, "did you mean \"{}\"? ({})",
+        crate::spell::corrected_query(&terms, &fixes), each.join(", "));
+}
@@ -0,0 +1,124 @@
+//! `selftest`: a one-shot check that a freshly installed binary works end to
+//! end. In a scratch KB under the temp dir it stores a few entries, rebuilds
+//! index.bin and queries it directly, then runs search, brief and xref through
+//! MCP dispatch (the path the server takes) and feeds a simulated PreToolUse
+//! event to the ambient hook. Each step must produce its expected entry. The
+//! scratch KB runs on default settings (the user's tokenizer plugin and
+//! snippet style don't apply) and is removed afterwards unless `keep`.
+
+use crate::json::Value;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// (topic, tags, source, text) of each scratch entry.
+const ENTRIES: &[(&str, &str, Option<&str>, &str)] = &[
+    ("selftest-auth", "gotcha", Some("src/auth.rs:10"),
+     "Session tokens expire after 15 minutes; refresh rotates them and revokes the old pair."),
+    ("selftest-build", "build", None,
+     "A linker error after touching the token cache means a stale build; see selftest-auth for the expiry rules."),
+    ("selftest-notes", "howto", None,
+     "Briefings group every matching entry by topic, newest first."),
+];
+
+/// One check: a detail line on success, what went wrong otherwise.
+type Step = fn(&Path) -> Result<String, String>;
+
+/// Run every step and report one line each. Err if any step failed.
+pub fn run(keep: bool) -> Result<String, String> {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    let dir = std::env::temp_dir().join(format!("amaranthine-selftest-{}-{nanos}", std::process::id()));
+    crate::config::ensure_dir(&dir).map_err(|e| e.to_string())?;
+    crate::settings::apply(&dir);
+
+    let mut out = format!("selftest: {}\n", dir.display());
+    let mut passed = 0;
+    let steps: [(&str, Step); 6] = [
+        ("store", store), ("index", index), ("search", search),
+        ("brief", brief), ("xref", xref), ("hook", hook),
+    ];
+    for (name, step) in steps {
+        let t0 = Instant::now();
+        let result = step(&dir);
+        let ms = t0.elapsed().as_secs_f64() * 1000.0;
+        match result {
+            Ok(detail) => { passed += 1; let _ = writeln!(out, "  ok    {name:<7} {detail}  ({ms:.1}ms)"); }
+            Err(e) => { let _ = writeln!(out, "  FAIL  {name:<7} {e}  ({ms:.1}ms)"); }
+        }
+    }
+
+    let _ = write!(out, "{passed}/{} checks passed; ", steps.len());
+    if keep {
+        let _ = writeln!(out, "kept {}", dir.display());
+    } else {
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = writeln!(out, "removed the scratch KB");
+    }
+    if passed == steps.len() { Ok(out) }
+    else { Err(format!("{} of {} checks failed\n{}", steps.len() - passed, steps.len(), out.trim_end())) }
+}
+
+/// Err naming `want` unless `output` contains it.
+fn expect(output: &str, want: &str, what: &str) -> Result<(), String> {
+    if output.contains(want) { Ok(()) } else { Err(format!("{what}: expected \"{want}\" in the output")) }
+}
+
+fn store(dir: &Path) -> Result<String, String> {
+    for (topic, tags, source, text) in ENTRIES {
+        crate::store::run_full(dir, topic, text, Some(tags), true, *source,
+            Some(crate::provenance::Provenance::Human)).map_err(|e| format!("{topic}: {e}"))?;
+    }
+    let stored = crate::delete::topic_entries(&crate::config::log_path(dir), ENTRIES[0].0)
+        .map_err(|e| e.to_string())?.len();
+    if stored != 1 { return Err(format!("{}: read back {stored} entries, expected 1", ENTRIES[0].0)); }
+    Ok(format!("{} entries in data.log", ENTRIES.len()))
+}
+
+fn index(dir: &Path) -> Result<String, String> {
+    let (_, bytes) = crate::inverted::rebuild_and_persist(dir)?;
+    let hits = crate::binquery::search(&bytes, "tokens expire", 5).map_err(|e| e.to_string())?;
+    expect(&hits, "selftest-auth", "index search")?;
+    Ok(format!("index.bin rebuilt ({} bytes), queried directly", bytes.len()))
+}
+
+/// A tools/call as the MCP server would run it.
+fn call(dir: &Path, tool: &str, args: &[(&str, &str)]) -> Result<String, String> {
+    let args = Value::Obj(args.iter().map(|(k, v)| (k.to_string(), Value::Str(v.to_string()))).collect());
+    crate::mcp::dispatch(tool, Some(&args), dir).map_err(|e| format!("{tool}: {e}"))
+}
+
+fn search(dir: &Path) -> Result<String, String> {
+    let out = call(dir, "search", &[("query", "refresh tokens")])?;
+    expect(&out, "selftest-auth", "search")?;
+    let out = call(dir, "search", &[("query", "stale build"), ("tag", "build")])?;
+    expect(&out, "selftest-build", "tag-filtered search")?;
+    Ok("plain and tag-filtered queries found their entries".into())
+}
+
+fn brief(dir: &Path) -> Result<String, String> {
+    let out = call(dir, "brief", &[("query", "tokens")])?;
+    expect(&out, "selftest-auth", "brief")?;
+    Ok(format!("{} lines", out.lines().count()))
+}
+
+fn xref(dir: &Path) -> Result<String, String> {
+    let out = call(dir, "xref", &[("topic", "selftest-auth")])?;
+    expect(&out, "selftest-build", "xref")?;
+    Ok("selftest-build mentions selftest-auth".into())
+}
+
+/// PreToolUse Read of the file an entry cites: the ambient hook must inject it.
+fn hook(dir: &Path) -> Result<String, String> {
+    let file: PathBuf = dir.join("src/auth.rs");
+    let event = Value::Obj(vec![
+        ("tool_name".into(), Value::Str("Read".into())),
+        ("tool_input".into(), Value::Obj(vec![("file_path".into(), Value::Str(file.to_string_lossy().into_owned()))])),
+    ]);
+    let input = dir.join("event.json");
+    std::fs::write(&input, event.to_string()).map_err(|e| e.to_string())?;
+    let out = crate::hook::test("ambient", dir, Some(&input.to_string_lossy()), false)?;
+    expect(&out, "valid JSON", "ambient hook")?;
+    expect(&out, "expire after 15 minutes", "ambient hook")?;
+    Ok("Read of src/auth.rs injected its entry".into())
+}
@@ -8,6 +8,8 @@
 //!
 //! Session identity: TTY name + 4h idle timeout.
 //! Concurrency: flock for single-writer, multi-reader.
+//! Session starts are appended to sessions.log (`<local minute> <id>` per line)
+//! so `recent` can group entries by the session that wrote them.
 
 use std::fs::{File, OpenOptions};
 use std::io::Read;
@@ -79,6 +81,33 @@ fn session_path(dir: &Path) -> PathBuf {
     dir.join("session.json")
 }
 
+fn sessions_log_path(dir: &Path) -> PathBuf {
+    dir.join("sessions.log")
+}
+
+/// Best-effort: a lost line only means those entries show up ungrouped.
+/// Files session tracking writes, for callers that must leave them as they
+/// found them (`hook test`).
+pub fn state_files(dir: &Path) -> [PathBuf; 2] { [session_path(dir), sessions_log_path(dir)] }
+
+fn record_start(dir: &Path, id: &str) {
+    use std::io::Write;
+    let line = format!("{} {id}\n", crate::time::LocalTime::now().to_minutes());
+    let _ = OpenOptions::new().create(true).append(true).open(sessions_log_path(dir))
+        .and_then(|mut f| f.write_all(line.as_bytes()));
+}
+
+/// Recorded session starts as (local minute, id), oldest first.
+pub fn starts(dir: &Path) -> Vec<(i64, String)> {
+    let text = std::fs::read_to_string(sessions_log_path(dir)).unwrap_or_default();
+    let mut out: Vec<(i64, String)> = text.lines()
+        .filter_map(|l| l.split_once(' '))
+        .filter_map(|(m, id)| Some((m.parse().ok()?, id.trim().to_string())))
+        .collect();
+    out.sort_by_key(|(m, _)| *m);
+    out
+}
+
 impl Session {
     /// Create a fresh session with TTY-based identity.
     pub fn new() -> Self {
@@ -120,9 +149,13 @@ impl Session {
         Some(s)
     }
 
-    /// Load or create a new session.
+    /// Load or create a new session. New sessions are recorded in sessions.log.
     pub fn load_or_new(dir: &Path) -> Self {
-        Self::load(dir).unwrap_or_else(Self::new)
+        Self::load(dir).unwrap_or_else(|| {
+            let s = Self::new();
+            record_start(dir, &s.id);
+            s
+        })
     }
 
     /// Save session to disk with flock for atomicity.
@@ -183,6 +216,12 @@ impl Session {
         self.phase = self.detect_phase();
     }
 
+    /// Errors of the failing build while the phase is Debug; None otherwise.
+    pub fn failing_build(&self) -> Option<&[String]> {
+        if self.phase != Phase::Debug { return None; }
+        self.last_build.as_ref().filter(|b| !b.ok).map(|b| b.errors.as_slice())
+    }
+
     /// Add a topic to focus set (deduped).
     pub fn add_focus_topic(&mut self, topic: &str) {
         if !self.focus_topics.iter().any(|t| t == topic) {
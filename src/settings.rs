@@ -0,0 +1,232 @@
+//! `<dir>/config`: optional `key = value` settings for a knowledge base.
+//! `#` starts a comment; unknown keys are ignored; a key may repeat, and list
+//! values are comma-separated across all occurrences. Missing file → defaults.
+//! Loaded once per dir and re-read only when the file's mtime/length change;
+//! `reload` forces a re-read (the `reload_config` tool). Readers hold an
+//! `Arc<Settings>`, so a reload swaps the whole file in at once.
+//!
+//! ```text
+//! # dirs a single MCP server may serve via the per-call `dir` argument
+//! allowed_dirs = ~/work/api/.amaranthine, ~/work/web/.amaranthine
+//! ```
+
+use crate::error::AmrError;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+pub fn config_path(dir: &Path) -> PathBuf { dir.join("config") }
+
+#[derive(Default)]
+pub struct Settings {
+    pairs: Vec<(String, String)>,
+}
+
+type Stamp = (SystemTime, u64);
+
+static CACHE: Mutex<Vec<(PathBuf, Stamp, Arc<Settings>)>> = Mutex::new(Vec::new());
+
+impl Settings {
+    pub fn parse(text: &str) -> Self {
+        let pairs = text.lines()
+            .map(|l| l.split_once('#').map_or(l, |(before, _)| before).trim())
+            .filter_map(|l| l.split_once('='))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .filter(|(k, _)| !k.is_empty())
+            .collect();
+        Self { pairs }
+    }
+
+    /// Last value set for `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// All comma-separated values for `key`, across repeated lines, in file order.
+    pub fn list(&self, key: &str) -> Vec<&str> {
+        self.pairs.iter().filter(|(k, _)| k == key)
+            .flat_map(|(_, v)| v.split(','))
+            .map(str::trim).filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    /// Known keys whose values would be ignored, as `key = value: expected ...`.
+    pub fn problems(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut bad = |k: &str, v: &str, want: &str| out.push(format!("{k} = {v}: expected {want}"));
+        if let Some(v) = self.get("merge_policy").filter(|v| !matches!(*v, "off" | "suggest" | "append")) {
+            bad("merge_policy", v, "off, suggest or append");
+        }
+        if let Some(v) = self.get("merge_threshold").filter(|v| !v.parse::<f64>().is_ok_and(|t| (0.0..=1.0).contains(&t))) {
+            bad("merge_threshold", v, "a number from 0 to 1");
+        }
+        if let Some(v) = self.get("merge_window_hours").filter(|v| v.parse::<i64>().is_err()) {
+            bad("merge_window_hours", v, "whole hours");
+        }
+        if let Some(v) = self.get("raw_dumps").filter(|v| !matches!(*v, "off" | "tag" | "truncate")) {
+            bad("raw_dumps", v, "off, tag or truncate");
+        }
+        for k in ["summarize_large", "summarize_attach"] {
+            if let Some(v) = self.get(k).filter(|_| self.flag(k).is_none()) { bad(k, v, "on or off"); }
+        }
+        for k in ["summarize_lines", "summarize_keep"] {
+            if let Some(v) = self.get(k).filter(|v| !v.parse::<usize>().is_ok_and(|n| n > 0)) {
+                bad(k, v, "a positive number of lines");
+            }
+        }
+        if let Some(v) = self.get("freshness").filter(|v| !matches!(*v, "mtime" | "git")) {
+            bad("freshness", v, "mtime or git");
+        }
+        if let Some(v) = self.get("agent_entries").filter(|v| !matches!(*v, "annotate" | "downweight" | "off")) {
+            bad("agent_entries", v, "annotate, downweight or off");
+        }
+        if let Some(v) = self.get("auto_links").filter(|_| self.flag("auto_links").is_none()) {
+            bad("auto_links", v, "on or off");
+        }
+        if let Some(v) = self.get("review_queue").filter(|_| self.flag("review_queue").is_none()) {
+            bad("review_queue", v, "on or off");
+        }
+        if let Some(v) = self.get("hybrid_search").filter(|_| self.flag("hybrid_search").is_none()) {
+            bad("hybrid_search", v, "on or off");
+        }
+        if let Some(v) = self.get("webhooks_enabled").filter(|_| self.flag("webhooks_enabled").is_none()) {
+            bad("webhooks_enabled", v, "on or off");
+        }
+        for u in self.list("webhooks").into_iter().filter(|u| !u.starts_with("http://") && !u.starts_with("https://")) {
+            bad("webhooks", u, "http:// or https:// URLs");
+        }
+        for k in ["max_topic_entries", "max_entries", "max_postings", "snapshot_keep", "snapshot_keep_days"] {
+            if let Some(v) = self.get(k).filter(|v| v.parse::<usize>().is_err()) {
+                bad(k, v, "a whole number (0 = no limit)");
+            }
+        }
+        if let Some(v) = self.get("snippet_length").filter(|v| !v.parse::<usize>().is_ok_and(|n| n > 0)) {
+            bad("snippet_length", v, "a positive number of bytes");
+        }
+        for k in ["snippet_metadata", "snippet_prose"] {
+            if let Some(v) = self.get(k).filter(|_| self.flag(k).is_none()) { bad(k, v, "on or off"); }
+        }
+        if let Some(v) = self.get("embed_backend").filter(|v| !matches!(*v, "hash" | "command" | "http")) {
+            bad("embed_backend", v, "hash, command or http");
+        }
+        if let Some(v) = self.get("embed_url").filter(|u| !u.starts_with("http://") && !u.starts_with("https://")) {
+            bad("embed_url", v, "an http:// or https:// URL");
+        }
+        if let Some(v) = self.get("semantic_weight").filter(|v| !v.parse::<f64>().is_ok_and(|w| (0.0..=1.0).contains(&w))) {
+            bad("semantic_weight", v, "a number from 0 to 1");
+        }
+        if let Some(v) = self.get("trace_max_file_kb").filter(|v| v.parse::<u64>().is_err()) {
+            bad("trace_max_file_kb", v, "whole KB (0 = no cap)");
+        }
+        if let Some(v) = self.get("trace_gitignore").filter(|_| self.flag("trace_gitignore").is_none()) {
+            bad("trace_gitignore", v, "on or off");
+        }
+        let layers = crate::ambient::AMBIENT_LAYER_KEYS;
+        for l in self.list("ambient_layers").into_iter().filter(|l| !layers.contains(l)) {
+            bad("ambient_layers", l, "source, symbols, related, structural or refactor");
+        }
+        for kv in self.list("ambient_caps") {
+            let ok = kv.split_once(':')
+                .is_some_and(|(l, n)| layers.contains(&l.trim()) && n.trim().parse::<usize>().is_ok());
+            if !ok { bad("ambient_caps", kv, "layer:count"); }
+        }
+        for kv in self.list("tag_weights") {
+            if kv.split_once(':').and_then(|(_, w)| w.trim().parse::<f64>().ok()).is_none() {
+                bad("tag_weights", kv, "tag:number");
+            }
+        }
+        out
+    }
+
+    /// `true/yes/on/1` or `false/no/off/0`; anything else → None.
+    pub fn flag(&self, key: &str) -> Option<bool> {
+        match self.get(key)?.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(true),
+            "false" | "no" | "off" | "0" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Settings for `dir`, cached until the config file changes.
+pub fn load(dir: &Path) -> Arc<Settings> {
+    let path = config_path(dir);
+    let (exists, stamp) = stamp(&path);
+    let Ok(mut cache) = CACHE.lock() else { return Arc::new(read(&path)) };
+    if let Some((_, s, settings)) = cache.iter().find(|(d, _, _)| d == dir) {
+        if *s == stamp { return Arc::clone(settings); }
+    }
+    let settings = Arc::new(if exists { read(&path) } else { Settings::default() });
+    cache.retain(|(d, _, _)| d != dir);
+    cache.push((dir.to_path_buf(), stamp, Arc::clone(&settings)));
+    settings
+}
+
+/// The dir and settings the process-wide knobs were last applied from.
+static APPLIED: Mutex<Option<(PathBuf, Arc<Settings>)>> = Mutex::new(None);
+
+/// Point the process-wide settings (tokenizer plugin, postings cap, snippet
+/// style) at `dir`. Cheap when neither the dir nor its config changed since the
+/// last call, so it runs wherever the dir becomes known: CLI start, each MCP
+/// call, each `KnowledgeBase` operation.
+pub fn apply(dir: &Path) {
+    let settings = load(dir);
+    if let Ok(mut g) = APPLIED.lock() {
+        if g.as_ref().is_some_and(|(d, s)| d == dir && Arc::ptr_eq(s, &settings)) { return; }
+        *g = Some((dir.to_path_buf(), settings));
+    }
+    crate::tokplugin::activate(dir);
+    crate::costcap::configure(dir);
+    crate::cache::configure(dir);
+}
+
+/// Re-read `dir`'s config now, regardless of its stamp: (previous, current).
+/// "Previous" is what readers last saw, so a file never loaded compares as empty.
+/// All or nothing: if any known key has a bad value, readers keep the previous
+/// settings (until the file changes again) and the problems are returned.
+pub fn reload(dir: &Path) -> Result<(Arc<Settings>, Arc<Settings>), AmrError> {
+    let path = config_path(dir);
+    let (exists, stamp) = stamp(&path);
+    let new = if exists {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| AmrError::Io(format!("{}: {e}", path.display())))?;
+        Settings::parse(&text)
+    } else { Settings::default() };
+    let problems = new.problems();
+    let mut cache = CACHE.lock().map_err(|_| AmrError::Locked("settings cache poisoned".into()))?;
+    let old = cache.iter().position(|(d, _, _)| d == dir)
+        .map(|i| cache.swap_remove(i).2).unwrap_or_default();
+    let keep = if problems.is_empty() { Arc::new(new) } else { Arc::clone(&old) };
+    cache.push((dir.to_path_buf(), stamp, Arc::clone(&keep)));
+    if !problems.is_empty() {
+        return Err(AmrError::InvalidArg(format!("{} not applied:\n  {}",
+            path.display(), problems.join("\n  "))));
+    }
+    Ok((old, keep))
+}
+
+/// Keys whose effective value differs between `old` and `new`, sorted.
+pub fn changed_keys(old: &Settings, new: &Settings) -> Vec<String> {
+    let keys: std::collections::BTreeSet<&str> = old.pairs.iter().chain(&new.pairs)
+        .map(|(k, _)| k.as_str()).collect();
+    keys.into_iter().filter(|k| old.list(k) != new.list(k)).map(str::to_string).collect()
+}
+
+/// (file exists, mtime/length stamp).
+fn stamp(path: &Path) -> (bool, Stamp) {
+    let meta = std::fs::metadata(path).ok();
+    (meta.is_some(), (meta.as_ref().and_then(|m| m.modified().ok()).unwrap_or(SystemTime::UNIX_EPOCH),
+                      meta.as_ref().map_or(0, |m| m.len())))
+}
+
+fn read(path: &Path) -> Settings {
+    std::fs::read_to_string(path).map(|t| Settings::parse(&t)).unwrap_or_default()
+}
+
+/// Expand a leading `~/` against $HOME.
+pub fn expand_home(p: &str) -> PathBuf {
+    match p.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(rest),
+        None => PathBuf::from(p),
+    }
+}
@@ -0,0 +1,55 @@
+//! `share`: one entry as a self-contained markdown block for a PR or chat —
+//! first line as the title, then date, address, tags, source and provenance,
+//! then the body. With `links`, the entries it links to follow one level
+//! deep as quoted blocks (their own links are not followed).
+
+use crate::error::AmrError;
+use std::fmt::Write;
+use std::path::Path;
+
+pub fn run(dir: &Path, topic: &str, idx: usize, links: bool) -> Result<String, AmrError> {
+    let log_path = crate::config::log_path(dir);
+    let entries = crate::delete::topic_entries(&log_path, topic)?;
+    let e = entries.get(idx).ok_or_else(|| AmrError::NotFound(format!(
+        "index {idx} out of range (topic has {} entries)", entries.len())))?;
+    let mut out = String::new();
+    block(&mut out, topic, idx, e, "");
+    if links {
+        let meta = crate::text::extract_all_metadata(&e.body);
+        for (lt, li) in &meta.links {
+            let linked = crate::delete::topic_entries(&log_path, lt)?;
+            match linked.get(*li) {
+                Some(le) => {
+                    out.push('\n');
+                    block(&mut out, lt, *li, le, "> ");
+                }
+                None => { let _ = writeln!(out, "\n> _{lt}:{li} — no longer exists_"); }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Markdown for one entry; `quote` prefixes every line (linked entries).
+fn block(out: &mut String, topic: &str, idx: usize, e: &crate::datalog::LogEntry, quote: &str) {
+    let meta = crate::text::extract_all_metadata(&e.body);
+    let mut lines = e.body.lines().filter(|l| !crate::text::is_metadata_line(l)).map(str::trim_end)
+        .skip_while(|l| l.trim().is_empty());
+    let title = lines.next().unwrap_or("(empty)").trim().trim_start_matches("- ");
+    let uid = crate::address::entry_uid(e.timestamp_min, &e.body);
+    let _ = writeln!(out, "{quote}### {title}");
+    let mut facts = vec![
+        format!("`{}`", crate::address::format_both(topic, idx, uid)),
+        crate::time::minutes_to_date_str(e.timestamp_min),
+    ];
+    if !meta.tags.is_empty() { facts.push(format!("tags: {}", meta.tags.join(", "))); }
+    if let Some(s) = &meta.source { facts.push(format!("source: `{s}`")); }
+    if let Some(p) = meta.provenance { facts.push(format!("by {p}")); }
+    let _ = writeln!(out, "{quote}{}", facts.join(" · "));
+    let rest: Vec<&str> = lines.collect();
+    let end = rest.iter().rposition(|l| !l.trim().is_empty()).map_or(0, |i| i + 1);
+    if end > 0 {
+        let _ = writeln!(out, "{}", quote.trim_end());
+        for l in &rest[..end] { let _ = writeln!(out, "{quote}{l}"); }
+    }
+}
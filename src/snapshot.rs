@@ -0,0 +1,255 @@
+//! Point-in-time snapshots of the whole KB directory, for undoing a risky
+//! compaction, migration or bulk edit: `snapshot create|list|restore`.
+//!
+//! Content-addressed: each file is stored once under `snapshots/objects/`,
+//! named by FNV-1a hash and length, and a snapshot is a manifest in
+//! `snapshots/<id>` listing `object path` per file. Unchanged files (config,
+//! graphs, an index.bin nobody rebuilt) cost nothing after the first
+//! snapshot. Dotfiles (locks) and the snapshots themselves are left out.
+//! Restore writes the files back (each to a temp file renamed into place;
+//! index.bin through `indexview::publish`), removes ones the snapshot didn't
+//! have, and always snapshots the current state first, so it can itself be
+//! undone.
+//!
+//! Retention runs after each `create` (not after a restore, which could drop
+//! the snapshot it just restored): the `snapshot_keep` newest stay (default
+//! 20, 0 = all) and, with `snapshot_keep_days`, none older than that; the
+//! newest always survives. Objects no manifest lists are then deleted.
+
+use crate::error::AmrError;
+use crate::fxhash::FxHashSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+pub const DIR: &str = "snapshots";
+const OBJECTS: &str = "objects";
+const MAGIC: &str = "amaranthine-snapshot 1";
+const DEFAULT_KEEP: usize = 20;
+
+/// A parsed manifest.
+struct Manifest {
+    id: String,
+    created_min: i64,
+    label: String,
+    /// (object name, length, path relative to the KB dir)
+    files: Vec<(String, u64, String)>,
+}
+
+fn root(dir: &Path) -> PathBuf { dir.join(DIR) }
+
+/// `snapshot create [--label text]`.
+pub fn create(dir: &Path, label: Option<&str>) -> Result<String, AmrError> {
+    if !dir.is_dir() { return Err(AmrError::NotFound(format!("{} does not exist", dir.display()))); }
+    let _lock = crate::lock::FileLock::acquire(dir)?;
+    let m = capture(dir, label.unwrap_or(""))?;
+    let bytes: u64 = m.files.iter().map(|(_, len, _)| len).sum();
+    let mut out = format!("snapshot {}: {} files, {} KB\n", m.id, m.files.len(), bytes.div_ceil(1024));
+    out.push_str(&retain(dir)?);
+    Ok(out)
+}
+
+/// `snapshot list`, newest first.
+pub fn list(dir: &Path) -> Result<String, AmrError> {
+    let all = manifests(dir)?;
+    if all.is_empty() { return Ok("no snapshots (create one with `snapshot create`)\n".into()); }
+    let stored: u64 = std::fs::read_dir(root(dir).join(OBJECTS))?.flatten()
+        .filter_map(|e| e.metadata().ok()).map(|m| m.len()).sum();
+    let mut out = format!("{} snapshots, {} KB stored\n", all.len(), stored.div_ceil(1024));
+    for m in all.iter().rev() {
+        let bytes: u64 = m.files.iter().map(|(_, len, _)| len).sum();
+        let _ = write!(out, "  {}  {}  {} files, {} KB", m.id,
+            crate::time::minutes_to_date_str(m.created_min as i32), m.files.len(), bytes.div_ceil(1024));
+        if !m.label.is_empty() { let _ = write!(out, "  {}", m.label); }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// `snapshot restore <id|latest>`.
+pub fn restore(dir: &Path, id: &str) -> Result<String, AmrError> {
+    let all = manifests(dir)?;
+    let target = match id {
+        "latest" => all.last(),
+        _ => all.iter().find(|m| m.id == id),
+    }.ok_or_else(|| AmrError::NotFound(format!("no snapshot {id} (see `snapshot list`)")))?;
+    // Every object must be intact before anything is touched
+    let objects = root(dir).join(OBJECTS);
+    for (obj, len, path) in &target.files {
+        let ok = std::fs::read(objects.join(obj)).is_ok_and(|b| b.len() as u64 == *len && object_name(&b) == *obj);
+        if !ok { return Err(AmrError::Corrupt(format!("snapshot {}: object for {path} is missing or damaged", target.id))); }
+    }
+
+    let lock = crate::lock::FileLock::acquire(dir)?;
+    let backup = capture(dir, &format!("before restoring {}", target.id))?;
+    let keep: FxHashSet<&str> = target.files.iter().map(|(_, _, p)| p.as_str()).collect();
+    let mut removed = 0;
+    for rel in walk(dir)? {
+        // index.bin is replaced below or left for `fresh` to rebuild, never unlinked
+        if keep.contains(rel.as_str()) || is_index(&rel) { continue; }
+        std::fs::remove_file(dir.join(&rel))?;
+        removed += 1;
+    }
+    // Replace by rename only, so a reader mapping the old file keeps a whole one
+    let (mut index, mut written) = (None, 0);
+    for (obj, _, rel) in &target.files {
+        if is_index(rel) {
+            if rel == "index.bin" { index = Some(obj); }
+            continue;
+        }
+        let path = dir.join(rel);
+        let parent = path.parent().unwrap_or(dir);
+        std::fs::create_dir_all(parent)?;
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let tmp = parent.join(format!(".{name}.restore.tmp"));
+        std::fs::copy(objects.join(obj), &tmp)?;
+        std::fs::rename(&tmp, &path)?;
+        written += 1;
+    }
+    // A new generation, so no cached view of the current index survives; the
+    // stamp is left empty because the restored data.log has a new mtime, and
+    // `fresh` below rebuilds from it
+    if let Some(obj) = index {
+        crate::indexview::publish(dir, &std::fs::read(objects.join(obj))?, None)?;
+        written += 1;
+    }
+    drop(lock);
+    crate::cache::invalidate();
+    let reindex = match crate::indexview::fresh(dir) {
+        Ok(_) => String::new(),
+        Err(e) => format!("\nindex rebuild failed ({e}); the next search retries it"),
+    };
+    Ok(format!("restored {}: {written} files written, {removed} removed\nprevious state saved as snapshot {}{reindex}\n",
+        target.id, backup.id))
+}
+
+/// index.bin and its generation stamp, which restore goes through
+/// `indexview::publish` for instead of writing.
+fn is_index(rel: &str) -> bool { rel == "index.bin" || rel == "index.gen" }
+
+/// Store `dir`'s files and write the manifest. Caller holds the KB lock.
+fn capture(dir: &Path, label: &str) -> Result<Manifest, AmrError> {
+    let objects = root(dir).join(OBJECTS);
+    std::fs::create_dir_all(&objects)?;
+    let mut files = Vec::new();
+    for rel in walk(dir)? {
+        let bytes = std::fs::read(dir.join(&rel))?;
+        let obj = object_name(&bytes);
+        let path = objects.join(&obj);
+        if !path.exists() {
+            let tmp = objects.join(format!(".{obj}.tmp"));
+            std::fs::write(&tmp, &bytes)?;
+            std::fs::rename(&tmp, &path)?;
+        }
+        files.push((obj, bytes.len() as u64, rel));
+    }
+
+    let now = crate::time::LocalTime::now();
+    let base = format!("{:04}{:02}{:02}-{:02}{:02}", now.year, now.month, now.day, now.hour, now.min);
+    // Past every same-minute id, even ones retention dropped, so ids sort by age
+    let n = manifests(dir)?.iter().filter_map(|m| {
+        let (stamp, n) = id_order(&m.id);
+        (stamp == base).then_some(n)
+    }).max().map_or(1, |n| n + 1);
+    let id = if n == 1 { base } else { format!("{base}-{n}") };
+    let label = label.replace('\n', " ");
+    let mut text = format!("{MAGIC}\ncreated {}\nlabel {label}\n", now.to_minutes());
+    for (obj, len, rel) in &files { let _ = writeln!(text, "{obj} {len} {rel}"); }
+    let tmp = root(dir).join(format!(".{id}.tmp"));
+    std::fs::write(&tmp, text)?;
+    std::fs::rename(&tmp, root(dir).join(&id))?;
+    Ok(Manifest { id, created_min: now.to_minutes(), label, files })
+}
+
+/// Apply the retention settings, then drop unreferenced objects.
+/// Returns a line saying what went, or "" when nothing did.
+fn retain(dir: &Path) -> Result<String, AmrError> {
+    let settings = crate::settings::load(dir);
+    let keep = settings.get("snapshot_keep").and_then(|v| v.parse::<usize>().ok()).unwrap_or(DEFAULT_KEEP);
+    let max_days = settings.get("snapshot_keep_days").and_then(|v| v.parse::<i64>().ok()).filter(|&d| d > 0);
+    let all = manifests(dir)?;
+    let now = crate::time::LocalTime::now().to_minutes();
+    let n = all.len();
+    let mut dropped = Vec::new();
+    for (i, m) in all.iter().enumerate() {
+        let from_newest = n - 1 - i;
+        if from_newest == 0 { continue; }
+        let too_many = keep > 0 && from_newest >= keep;
+        let too_old = max_days.is_some_and(|d| now - m.created_min > d * 1440);
+        if too_many || too_old {
+            std::fs::remove_file(root(dir).join(&m.id))?;
+            dropped.push(m.id.as_str());
+        }
+    }
+    let live: FxHashSet<&str> = all.iter().filter(|m| !dropped.contains(&m.id.as_str()))
+        .flat_map(|m| m.files.iter().map(|(obj, _, _)| obj.as_str())).collect();
+    let mut freed = 0u64;
+    for e in std::fs::read_dir(root(dir).join(OBJECTS))?.flatten() {
+        let name = e.file_name().to_string_lossy().into_owned();
+        if live.contains(name.as_str()) { continue; }
+        freed += e.metadata().map_or(0, |m| m.len());
+        std::fs::remove_file(e.path())?;
+    }
+    if dropped.is_empty() && freed == 0 { return Ok(String::new()); }
+    Ok(format!("retention: dropped {} snapshot(s) ({}), freed {} KB\n",
+        dropped.len(), dropped.join(", "), freed.div_ceil(1024)))
+}
+
+/// Every manifest, oldest first. Unreadable ones are skipped.
+fn manifests(dir: &Path) -> Result<Vec<Manifest>, AmrError> {
+    let Ok(entries) = std::fs::read_dir(root(dir)) else { return Ok(Vec::new()) };
+    let mut all: Vec<Manifest> = entries.flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()) && !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| parse(&e.file_name().to_string_lossy(), &std::fs::read_to_string(e.path()).ok()?))
+        .collect();
+    all.sort_by(|a, b| a.created_min.cmp(&b.created_min).then_with(|| id_order(&a.id).cmp(&id_order(&b.id))));
+    Ok(all)
+}
+
+/// (minute stamp, same-minute counter), so `-10` sorts after `-9`.
+fn id_order(id: &str) -> (&str, u32) {
+    match id.split_once('-').and_then(|(_, rest)| rest.split_once('-')) {
+        Some((_, n)) => (&id[..id.len() - n.len() - 1], n.parse().unwrap_or(0)),
+        None => (id, 1),
+    }
+}
+
+fn parse(id: &str, text: &str) -> Option<Manifest> {
+    let mut lines = text.lines();
+    if lines.next()? != MAGIC { return None; }
+    let created_min = lines.next()?.strip_prefix("created ")?.parse().ok()?;
+    let label = lines.next()?.strip_prefix("label")?.trim().to_string();
+    let files = lines.map(|l| {
+        let (obj, rest) = l.split_once(' ')?;
+        let (len, rel) = rest.split_once(' ')?;
+        Some((obj.to_string(), len.parse().ok()?, rel.to_string()))
+    }).collect::<Option<Vec<_>>>()?;
+    Some(Manifest { id: id.to_string(), created_min, label, files })
+}
+
+/// Regular files under `dir` as `/`-joined relative paths, minus dotfiles
+/// and the snapshots directory.
+fn walk(dir: &Path) -> Result<Vec<String>, AmrError> {
+    let mut out = Vec::new();
+    let mut stack = vec![(dir.to_path_buf(), String::new())];
+    while let Some((path, prefix)) = stack.pop() {
+        for e in std::fs::read_dir(&path)?.flatten() {
+            let name = e.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') || (prefix.is_empty() && name == DIR) { continue; }
+            let rel = if prefix.is_empty() { name } else { format!("{prefix}/{name}") };
+            match e.file_type() {
+                Ok(t) if t.is_dir() => stack.push((e.path(), rel)),
+                Ok(t) if t.is_file() => out.push(rel),
+                _ => {}
+            }
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// FNV-1a of the bytes, plus the length.
+fn object_name(bytes: &[u8]) -> String {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in bytes { h ^= *b as u64; h = h.wrapping_mul(0x100000001b3); }
+    format!("{h:016x}-{}", bytes.len())
+}
@@ -1,16 +1,84 @@
 //! Unix domain socket for hook queries against the in-memory index.
 //! MCP server spawns a listener thread; hook processes connect for zero-I/O queries.
+//!
+//! Two lanes: hook requests (`ambient`, `hook_ambient`, `topics`) and the rest
+//! (`search`). The listener reads each request and queues it by lane; a hook
+//! worker serves only the hook lane, a general worker serves the hook lane
+//! first and then the other, so ambient context never waits behind a slow
+//! search. Both lanes are bounded: a request that finds its lane full is
+//! answered empty at once (the hook stays silent) rather than queued past the
+//! client's 50ms read timeout.
 
+use std::collections::VecDeque;
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Hook requests that may wait; hooks are quick, so a few cover a burst.
+const HOOK_QUEUE: usize = 8;
+const QUERY_QUEUE: usize = 32;
+
+/// A read request waiting for a worker.
+struct Job {
+    stream: UnixStream,
+    line: String,
+}
+
+#[derive(Default)]
+struct Lanes {
+    hook: VecDeque<Job>,
+    query: VecDeque<Job>,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Queue {
+    lanes: Mutex<Lanes>,
+    ready: Condvar,
+}
+
+impl Queue {
+    /// Queue `job` on its lane, or hand it back if that lane is full.
+    fn push(&self, job: Job, hook: bool) -> Result<(), Job> {
+        let Ok(mut lanes) = self.lanes.lock() else { return Err(job) };
+        let (lane, cap) = if hook { (&mut lanes.hook, HOOK_QUEUE) } else { (&mut lanes.query, QUERY_QUEUE) };
+        if lane.len() >= cap { return Err(job); }
+        lane.push_back(job);
+        drop(lanes);
+        self.ready.notify_all();
+        Ok(())
+    }
+
+    /// Next job, hook lane first; `hook_only` workers never take the other.
+    /// None once the listener has stopped.
+    fn pop(&self, hook_only: bool) -> Option<Job> {
+        let mut lanes = self.lanes.lock().ok()?;
+        loop {
+            if let Some(job) = lanes.hook.pop_front() { return Some(job); }
+            if !hook_only { if let Some(job) = lanes.query.pop_front() { return Some(job); } }
+            if lanes.closed { return None; }
+            lanes = self.ready.wait(lanes).ok()?;
+        }
+    }
+
+    fn close(&self) {
+        if let Ok(mut lanes) = self.lanes.lock() { lanes.closed = true; }
+        self.ready.notify_all();
+    }
+}
+
+/// Ops served on the hook lane.
+fn is_hook_op(op: &str) -> bool {
+    matches!(op, "ambient" | "hook_ambient" | "topics")
+}
 
 /// Socket path: ~/.amaranthine/hook.sock
 pub fn sock_path(dir: &Path) -> PathBuf {
     dir.join("hook.sock")
 }
 
-/// Start the socket listener thread. Returns the join handle.
+/// Start the socket listener thread and its two workers.
 /// Cleans up the socket file on drop via the returned guard.
 pub fn start_listener(dir: &Path) -> Option<SockGuard> {
     let path = sock_path(dir);
@@ -25,12 +93,22 @@ pub fn start_listener(dir: &Path) -> Option<SockGuard> {
     };
     // Non-blocking accept with 500ms timeout for clean shutdown
     listener.set_nonblocking(false).ok();
-    let dir2 = dir.to_path_buf();
+    let queue = Arc::new(Queue::default());
+    for hook_only in [true, false] {
+        let (queue, dir) = (Arc::clone(&queue), dir.to_path_buf());
+        std::thread::spawn(move || {
+            while let Some(job) = queue.pop(hook_only) { respond(job, &dir); }
+        });
+    }
     let path2 = path.clone();
     let handle = std::thread::spawn(move || {
         for stream in listener.incoming() {
             match stream {
-                Ok(s) => { handle_conn(s, &dir2); }
+                Ok(s) => {
+                    let Some(line) = read_request(&s) else { continue };
+                    let hook = is_hook_op(crate::hook::extract_json_str(&line, "op").unwrap_or(""));
+                    if let Err(job) = queue.push(Job { stream: s, line }, hook) { reply(job.stream, ""); }
+                }
                 Err(e) => {
                     // Check if socket file was removed (shutdown signal)
                     if !path2.exists() { break; }
@@ -40,6 +118,7 @@ pub fn start_listener(dir: &Path) -> Option<SockGuard> {
                 }
             }
         }
+        queue.close();
     });
     Some(SockGuard { path, _handle: handle })
 }
@@ -55,36 +134,42 @@ impl Drop for SockGuard {
     }
 }
 
-/// Handle a single hook query connection.
-/// Uses a 512-byte BufReader (hook requests are small JSON, ~100-200 bytes).
-fn handle_conn(stream: UnixStream, _dir: &Path) {
+/// Read one request line. Uses a 512-byte BufReader (hook requests are
+/// small JSON, ~100-200 bytes). None for an empty or unreadable request.
+fn read_request(stream: &UnixStream) -> Option<String> {
     // 100ms timeout to avoid blocking the listener thread
     stream.set_read_timeout(Some(std::time::Duration::from_millis(100))).ok();
     stream.set_write_timeout(Some(std::time::Duration::from_millis(100))).ok();
 
-    let mut reader = BufReader::with_capacity(512, &stream);
+    let mut reader = BufReader::with_capacity(512, stream);
     let mut line = String::with_capacity(256);
-    if reader.read_line(&mut line).is_err() { return; }
+    reader.read_line(&mut line).ok()?;
     let line = line.trim();
-    if line.is_empty() { return; }
+    if line.is_empty() { None } else { Some(line.to_string()) }
+}
 
+/// Answer a queued request.
+fn respond(job: Job, dir: &Path) {
+    let line = job.line.as_str();
     // Fast-path: extract "op" without full JSON parse for the common case
     let op = crate::hook::extract_json_str(line, "op").unwrap_or("");
     let result = match op {
-        "search" => {
-            let req = match crate::json::parse(line) { Ok(v) => v, Err(_) => return };
-            handle_search(&req)
-        }
+        "search" => match crate::json::parse(line) {
+            Ok(req) => handle_search(&req),
+            Err(_) => return,
+        },
         "topics" => handle_topics(),
-        "ambient" => handle_ambient_fast(line),
-        "hook_ambient" => handle_hook_relay(line),
+        "ambient" => handle_ambient_fast(line, dir),
+        "hook_ambient" => handle_hook_relay(line, dir),
         _ => String::new(),
     };
+    reply(job.stream, &result);
+}
 
-    let mut writer = stream;
-    let _ = writer.write_all(result.as_bytes());
-    let _ = writer.write_all(b"\n");
-    let _ = writer.flush();
+fn reply(mut stream: UnixStream, result: &str) {
+    let _ = stream.write_all(result.as_bytes());
+    let _ = stream.write_all(b"\n");
+    let _ = stream.flush();
 }
 
 /// Search the in-memory index.
@@ -119,7 +204,7 @@ fn handle_topics() -> String {
 /// Combined ambient hook query with fast string extraction — no full JSON parse needed.
 /// Request: {"op":"ambient","stem":"cache","path":"/full/path/to/cache.rs","syms":["removed1","removed2"]}
 /// v7.3: passes file_path for smart ambient (source-path matching + symbol extraction).
-fn handle_ambient_fast(line: &str) -> String {
+fn handle_ambient_fast(line: &str, dir: &Path) -> String {
     let stem = match crate::hook::extract_json_str(line, "stem") {
         Some(s) if !s.is_empty() => s,
         _ => return String::new(),
@@ -127,7 +212,7 @@ fn handle_ambient_fast(line: &str) -> String {
     let file_path = crate::hook::extract_json_str(line, "\"path\"").unwrap_or("");
     let syms = extract_syms_array(line);
     crate::mcp::with_index(|data| {
-        crate::hook::query_ambient(data, stem, file_path, &syms, None)
+        crate::ambient::query_ambient(dir, data, stem, file_path, &syms, None)
     }).unwrap_or_default()
 }
 
@@ -159,7 +244,7 @@ fn extract_syms_array(line: &str) -> Vec<&str> {
 /// Ambient: {"op":"hook_ambient","tool_name":"Read","tool_input":{"file_path":"..."}}
 /// Subagent: {"op":"hook_ambient","type":"subagent-start"}
 /// Returns complete hook JSON (with hookSpecificOutput wrapper).
-fn handle_hook_relay(line: &str) -> String {
+fn handle_hook_relay(line: &str, dir: &Path) -> String {
     let htype = crate::hook::extract_json_str(line, "type").unwrap_or("");
     if htype == "subagent-start" {
         let topics = handle_topics();
@@ -202,7 +287,7 @@ fn handle_hook_relay(line: &str) -> String {
     let sym_refs: Vec<&str> = syms.iter().map(|s| s.as_str()).collect();
 
     let ctx = crate::mcp::with_index(|data| {
-        crate::hook::query_ambient(data, stem, path, &sym_refs, None)
+        crate::ambient::query_ambient(dir, data, stem, path, &sym_refs, None)
     }).unwrap_or_default();
     if ctx.is_empty() { return String::new(); }
     crate::hook::hook_output(&ctx)
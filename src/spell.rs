@@ -0,0 +1,81 @@
+//! "Did you mean" for zero-result queries. Index AND searches already swap a
+//! one-edit typo for an indexed term (binquery); this covers what is left, so
+//! the dictionary is the corpus vocabulary (tf_map keys, with document
+//! frequency). A query term with no entries is replaced by the closest known
+//! term within 1 edit (2 for terms over 5 chars; optimal string alignment:
+//! insert, delete, substitute, swap adjacent); ties go to the higher document
+//! frequency.
+
+use crate::fxhash::FxHashMap;
+use std::path::Path;
+
+/// Terms this short are left alone: one edit turns them into anything.
+const MIN_LEN: usize = 4;
+
+/// Edits allowed for a term of `len` chars.
+fn max_edits(len: usize) -> usize { if len <= 5 { 1 } else { 2 } }
+
+/// (unknown term, suggestion) for every query term that matches no entry and
+/// has a close dictionary term. Empty if nothing looks misspelled.
+pub fn suggest(dir: &Path, terms: &[String]) -> Vec<(String, String)> {
+    if terms.is_empty() { return Vec::new(); }
+    crate::cache::with_corpus(dir, |cached| {
+        let mut df: FxHashMap<&str, usize> = FxHashMap::default();
+        for e in cached {
+            for t in e.tf_map.keys() { *df.entry(t.as_str()).or_insert(0) += 1; }
+        }
+        terms.iter().filter(|t| !df.contains_key(t.as_str()) && t.chars().count() >= MIN_LEN)
+            .filter_map(|t| closest(t, &df).map(|s| (t.clone(), s.to_string())))
+            .collect()
+    }).unwrap_or_default()
+}
+
+/// The query with each suggestion substituted, for a one-line retry hint.
+pub fn corrected_query(terms: &[String], fixes: &[(String, String)]) -> String {
+    terms.iter()
+        .map(|t| fixes.iter().find(|(bad, _)| bad == t).map_or(t.as_str(), |(_, good)| good.as_str()))
+        .collect::<Vec<_>>().join(" ")
+}
+
+fn closest<'a>(term: &str, df: &FxHashMap<&'a str, usize>) -> Option<&'a str> {
+    let chars: Vec<char> = term.chars().collect();
+    let limit = max_edits(chars.len());
+    let mut best: Option<(usize, usize, &str)> = None; // (distance, df, term)
+    for (&cand, &n) in df {
+        let clen = cand.chars().count();
+        if clen.abs_diff(chars.len()) > limit || clen < MIN_LEN - 1 { continue; }
+        let Some(d) = distance(&chars, cand, limit) else { continue };
+        let better = match best {
+            None => true,
+            Some((bd, bn, bt)) => (d, std::cmp::Reverse(n), cand) < (bd, std::cmp::Reverse(bn), bt),
+        };
+        if better { best = Some((d, n, cand)); }
+    }
+    best.map(|(_, _, t)| t)
+}
+
+/// Optimal string alignment distance, or None once it must exceed `limit`.
+pub(crate) fn distance(a: &[char], b: &str, limit: usize) -> Option<usize> {
+    let b: Vec<char> = b.chars().collect();
+    let (n, m) = (a.len(), b.len());
+    let mut prev2 = vec![0usize; m + 1];
+    let mut prev: Vec<usize> = (0..=m).collect();
+    let mut cur = vec![0usize; m + 1];
+    for i in 1..=n {
+        cur[0] = i;
+        let mut row_min = cur[0];
+        for j in 1..=m {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut v = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                v = v.min(prev2[j - 2] + 1);
+            }
+            cur[j] = v;
+            row_min = row_min.min(v);
+        }
+        if row_min > limit { return None; }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    Some(prev[m]).filter(|&d| d <= limit)
+}
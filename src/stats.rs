@@ -1,3 +1,4 @@
+use crate::error::AmrError;
 use std::collections::BTreeMap;
 use std::fmt::Write;
 use std::path::Path;
@@ -20,7 +21,7 @@ pub fn list_tags(dir: &Path) -> Result<String, String> {
             let _ = writeln!(out, "\n{} unique tags across {} entries", tags.len(), tags.values().sum::<usize>());
         }
         out
-    })
+    }).map_err(Into::into)
 }
 
 /// Index-backed stats: reads header + entry metadata from in-memory index.
@@ -29,8 +30,8 @@ pub fn stats_fast(dir: &Path) -> Result<String, String> {
     let result = crate::mcp::with_index(|data| stats_from_index(data))
         .flatten()
         .or_else(|| {
-            std::fs::read(dir.join("index.bin")).ok()
-                .and_then(|data| stats_from_index(&data))
+            crate::indexview::fresh(dir).ok().flatten()
+                .and_then(|map| stats_from_index(map.bytes()))
         });
     match result {
         Some(s) => Ok(s),
@@ -46,6 +47,7 @@ fn stats_from_index(data: &[u8]) -> Option<String> {
     let mut oldest: Option<u16> = None;
     let mut newest: Option<u16> = None;
     let mut tagged = 0u32;
+    let mut langs = [0usize; 256];
     for i in 0..n {
         let off = meta_off + i * std::mem::size_of::<EntryMeta>();
         let m = crate::binquery::read_at::<EntryMeta>(data, off).ok()?;
@@ -55,6 +57,7 @@ fn stats_from_index(data: &[u8]) -> Option<String> {
             newest = Some(newest.map_or(ed, |n: u16| n.max(ed)));
         }
         if { m.tag_bitmap } != 0 { tagged += 1; }
+        langs[m.lang as usize] += 1;
     }
     let now_days = crate::time::LocalTime::now().to_days();
     let mut out = String::new();
@@ -62,6 +65,7 @@ fn stats_from_index(data: &[u8]) -> Option<String> {
     let _ = writeln!(out, "entries:        {n}");
     let _ = writeln!(out, "tagged entries: {tagged}");
     let _ = writeln!(out, "unique tags:    {}", { hdr.num_tags });
+    write_langs(&mut out, &langs);
     if let (Some(o), Some(n)) = (oldest, newest) {
         let _ = writeln!(out, "oldest entry:   {} days ago", now_days - o as i64);
         let _ = writeln!(out, "newest entry:   {} days ago", now_days - n as i64);
@@ -76,8 +80,10 @@ pub fn stats(dir: &Path) -> Result<String, String> {
         let mut tagged = 0usize;
         let mut oldest: Option<i32> = None;
         let mut newest: Option<i32> = None;
+        let mut langs = [0usize; 256];
         for e in cached {
             topics.insert(&e.topic);
+            langs[e.lang().map_or(0, |l| l.code()) as usize] += 1;
             if e.timestamp_min != 0 {
                 oldest = Some(oldest.map_or(e.timestamp_min, |o: i32| o.min(e.timestamp_min)));
                 newest = Some(newest.map_or(e.timestamp_min, |n: i32| n.max(e.timestamp_min)));
@@ -93,15 +99,132 @@ pub fn stats(dir: &Path) -> Result<String, String> {
         let _ = writeln!(out, "entries:        {}", cached.len());
         let _ = writeln!(out, "tagged entries: {tagged}");
         let _ = writeln!(out, "unique tags:    {}", tags.len());
+        write_langs(&mut out, &langs);
         if let (Some(o), Some(n)) = (oldest, newest) {
             let _ = writeln!(out, "oldest entry:   {} days ago", now_days - (o as i64 / 1440));
             let _ = writeln!(out, "newest entry:   {} days ago", now_days - (n as i64 / 1440));
         }
         out
-    })
+    }).map_err(Into::into)
+}
+
+/// "languages:      en 40, de 12, ? 3" from entry counts by Lang::code, most
+/// entries first; `?` = unclassified.
+fn write_langs(out: &mut String, counts: &[usize; 256]) {
+    let mut by_lang: Vec<(u8, usize)> = (0..=255u8).map(|c| (c, counts[c as usize])).filter(|(_, n)| *n > 0).collect();
+    if by_lang.is_empty() { return; }
+    by_lang.sort_by(|a, b| b.1.cmp(&a.1).then((a.0 == 0).cmp(&(b.0 == 0))).then(a.0.cmp(&b.0)));
+    let parts: Vec<String> = by_lang.iter()
+        .map(|(code, n)| format!("{} {n}", crate::lang::Lang::from_code(*code).map_or("?", |l| l.as_str()))).collect();
+    let _ = writeln!(out, "languages:      {}", parts.join(", "));
+}
+
+/// Per-day entry counts for the last `weeks` weeks (Monday-aligned, ending
+/// with the current week), overall or for one topic. Text grid or JSON.
+pub fn heatmap(dir: &Path, weeks: usize, topic: Option<&str>, json: bool) -> Result<String, AmrError> {
+    let weeks = weeks.clamp(1, 104);
+    let today = crate::time::LocalTime::now().to_days();
+    let start = today - weekday(today) - (weeks as i64 - 1) * 7;
+    let (counts, found) = crate::cache::with_corpus(dir, |cached| {
+        let mut counts = vec![0u32; (today - start + 1) as usize];
+        let mut found = topic.is_none();
+        for e in cached {
+            if topic.is_some_and(|t| e.topic.as_str() != t) { continue; }
+            found = true;
+            if e.timestamp_min == 0 { continue; }
+            let day = e.timestamp_min as i64 / 1440;
+            if (start..=today).contains(&day) { counts[(day - start) as usize] += 1; }
+        }
+        (counts, found)
+    })?;
+    if !found { return Err(AmrError::NotFound(format!("topic '{}' not found", topic.unwrap_or_default()))); }
+    let total: u32 = counts.iter().sum();
+    let active = counts.iter().filter(|&&c| c > 0).count();
+    let (gap, gap_end) = longest_gap(&counts);
+    let date = |d: i64| {
+        let (y, m, dd) = crate::time::days_from_civil(d);
+        format!("{y:04}-{m:02}-{dd:02}")
+    };
+
+    if json {
+        use crate::json::Value;
+        let days = counts.iter().enumerate().map(|(i, &c)| Value::Obj(vec![
+            ("date".into(), Value::Str(date(start + i as i64))),
+            ("count".into(), Value::Num(c as f64)),
+        ])).collect();
+        let root = Value::Obj(vec![
+            ("topic".into(), topic.map_or(Value::Null, |t| Value::Str(t.into()))),
+            ("weeks".into(), Value::Num(weeks as f64)),
+            ("start".into(), Value::Str(date(start))),
+            ("end".into(), Value::Str(date(today))),
+            ("total".into(), Value::Num(total as f64)),
+            ("active_days".into(), Value::Num(active as f64)),
+            ("longest_gap_days".into(), Value::Num(gap as f64)),
+            ("days".into(), Value::Arr(days)),
+        ]);
+        return Ok(root.to_string());
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{}: {weeks} weeks, {} – {}",
+        topic.unwrap_or("all topics"), date(start), date(today));
+    // Month labels over the column where each month's first Monday falls
+    let mut header = vec![' '; weeks * 2];
+    let mut last_month = 0;
+    for w in 0..weeks {
+        let (_, m, _) = crate::time::days_from_civil(start + w as i64 * 7);
+        let fits = w * 2 + 3 <= header.len() && (w == 0 || header[w * 2 - 1] == ' ');
+        if m != last_month && fits {
+            for (k, c) in MONTHS[m as usize - 1].chars().enumerate() { header[w * 2 + k] = c; }
+        }
+        last_month = m;
+    }
+    let _ = writeln!(out, "    {}", header.iter().collect::<String>().trim_end());
+    for (row, name) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"].iter().enumerate() {
+        let _ = write!(out, "{name} ");
+        for w in 0..weeks {
+            let i = w * 7 + row;
+            let cell = counts.get(i).map_or(' ', |&c| shade(c));
+            let _ = write!(out, "{cell} ");
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+    }
+    let _ = writeln!(out, "    · 0  ░ 1  ▒ 2-3  ▓ 4-7  █ 8+");
+    let _ = writeln!(out, "\n{total} entries on {active} of {} days", counts.len());
+    if gap > 0 {
+        let end = start + gap_end as i64;
+        let _ = writeln!(out, "longest gap: {gap} days ({} – {})", date(end - gap as i64 + 1), date(end));
+    }
+    if let Some(last) = counts.iter().rposition(|&c| c > 0) {
+        let idle = counts.len() - 1 - last;
+        if idle > 0 { let _ = writeln!(out, "last entry: {idle} days ago"); }
+    }
+    Ok(out)
+}
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun",
+                            "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// 0 = Monday. Day 0 (1970-01-01) was a Thursday.
+fn weekday(days: i64) -> i64 { (days + 3).rem_euclid(7) }
+
+fn shade(count: u32) -> char {
+    match count { 0 => '·', 1 => '░', 2..=3 => '▒', 4..=7 => '▓', _ => '█' }
+}
+
+/// (length, index of last day) of the longest run of zero-count days.
+fn longest_gap(counts: &[u32]) -> (usize, usize) {
+    let (mut best, mut best_end, mut run) = (0, 0, 0);
+    for (i, &c) in counts.iter().enumerate() {
+        run = if c == 0 { run + 1 } else { 0 };
+        if run > best { best = run; best_end = i; }
+    }
+    (best, best_end)
 }
 
 pub fn check_stale(dir: &Path) -> Result<String, String> {
+    let git = crate::git::enabled(dir);
     crate::cache::with_corpus(dir, |cached| {
         let mut stale = Vec::new();
         let mut checked = 0usize;
@@ -110,7 +233,8 @@ pub fn check_stale(dir: &Path) -> Result<String, String> {
             if let Some((ref src_path, _)) = crate::config::parse_source(&lines) {
                 checked += 1;
                 let date = e.date_str();
-                if let Some(msg) = crate::config::check_staleness(src_path, &date) {
+                let commit = crate::text::extract_commit(&e.body).filter(|_| git);
+                if let Some(msg) = crate::config::check_staleness(dir, src_path, &date, commit) {
                     let preview = lines.iter()
                         .find(|l| !l.starts_with('[') && !l.trim().is_empty())
                         .map(|l| l.trim()).unwrap_or("");
@@ -124,11 +248,77 @@ pub fn check_stale(dir: &Path) -> Result<String, String> {
         } else {
             format!("{} stale of {checked} sourced entries:\n{}", stale.len(), stale.join("\n"))
         }
-    })
+    }).map_err(Into::into)
+}
+
+/// Sourced entries whose file no longer exists (deleted or moved), each with
+/// where it probably went: the git rename chain for that path, else files
+/// elsewhere in the tree with the same name. Ambient context keyed on the old
+/// path silently stops matching until the source is fixed.
+pub fn check_orphans(dir: &Path) -> Result<String, String> {
+    let mut renames: Option<Vec<(String, String)>> = None;
+    let mut tree: Option<Vec<String>> = None;
+    let (found, checked) = crate::cache::with_corpus(dir, |cached| {
+        let mut counters: crate::fxhash::FxHashMap<&str, usize> = crate::fxhash::FxHashMap::default();
+        let mut found: Vec<(String, usize, String, Option<usize>)> = Vec::new();
+        let mut checked = 0usize;
+        for e in cached {
+            let slot = counters.entry(e.topic.as_str()).or_default();
+            let idx = *slot;
+            *slot += 1;
+            let lines: Vec<&str> = e.body.lines().collect();
+            let Some((path, line)) = crate::config::parse_source(&lines) else { continue };
+            if path.contains('*') { continue; }
+            checked += 1;
+            if crate::config::resolve_source(dir, &path).is_none() { found.push((e.topic.to_string(), idx, path, line)); }
+        }
+        (found, checked)
+    }).map_err(|e| e.to_string())?;
+    if found.is_empty() { return Ok(format!("checked {checked} sourced entries: every source file exists")); }
+
+    let mut out = format!("{} orphaned of {checked} sourced entries:\n", found.len());
+    for (topic, idx, path, line) in &found {
+        let at = line.map(|l| format!(":{l}")).unwrap_or_default();
+        let _ = writeln!(out, "  [{topic}:{idx}] {path}{at} — no longer exists");
+        let renames = renames.get_or_insert_with(|| crate::git::renames(2000));
+        if let Some(new) = follow_renames(dir, renames, path) {
+            let _ = writeln!(out, "    \u{2192} {new}{at} (git rename)");
+            continue;
+        }
+        let tree = tree.get_or_insert_with(|| {
+            let mut files = Vec::new();
+            let _ = crate::codepath::walk_files(Path::new("."), "", &crate::ignore::Ignore::load(dir), &mut files);
+            files.iter().map(|p| p.display().to_string().trim_start_matches("./").to_string()).collect()
+        });
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let same: Vec<&str> = tree.iter().filter(|f| f.rsplit('/').next() == Some(name))
+            .map(String::as_str).take(3).collect();
+        if same.is_empty() {
+            let _ = writeln!(out, "    no file named {name} in the tree — deleted?");
+        } else {
+            let _ = writeln!(out, "    maybe: {}", same.join(", "));
+        }
+    }
+    let _ = write!(out, "fix with revise (new [source:] line) or delete the entry");
+    Ok(out)
+}
+
+/// Where `path` ended up after a chain of renames, if it is still there.
+fn follow_renames(dir: &Path, renames: &[(String, String)], path: &str) -> Option<String> {
+    let mut cur = path.to_string();
+    for _ in 0..8 {
+        // Renames are newest first; walk forward in time from the oldest match
+        let Some((_, new)) = renames.iter().rev()
+            .find(|(old, _)| *old == cur || cur.ends_with(&format!("/{old}"))) else { break };
+        if *new == cur { break; }
+        cur = new.clone();
+    }
+    (cur != path && crate::config::resolve_source(dir, &cur).is_some()).then_some(cur)
 }
 
 /// For each stale entry, show the full entry text alongside the current source excerpt.
 pub fn refresh_stale(dir: &Path) -> Result<String, String> {
+    let git = crate::git::enabled(dir);
     crate::cache::with_corpus(dir, |cached| {
         let mut out = String::new();
         let mut stale_count = 0usize;
@@ -141,12 +331,13 @@ pub fn refresh_stale(dir: &Path) -> Result<String, String> {
             };
             checked += 1;
             let date = e.date_str();
-            if crate::config::check_staleness(&src_path, &date).is_none() { continue; }
+            let commit = crate::text::extract_commit(&e.body).filter(|_| git);
+            if crate::config::check_staleness(dir, &src_path, &date, commit).is_none() { continue; }
             stale_count += 1;
             let _ = writeln!(out, "--- STALE [{stale_count}] topic={} (written: {date}) ---", e.topic);
             for line in &lines { let _ = writeln!(out, "  {line}"); }
             let _ = writeln!(out, "--- CURRENT SOURCE: {} ---", src_path);
-            let _ = writeln!(out, "{}", source_excerpt(&src_path, src_line, 10));
+            let _ = writeln!(out, "{}", source_excerpt(dir, &src_path, src_line, 10));
             let _ = writeln!(out);
         }
         if stale_count == 0 {
@@ -155,11 +346,11 @@ pub fn refresh_stale(dir: &Path) -> Result<String, String> {
             let _ = write!(out, "{stale_count} stale of {checked} sourced entries");
             out
         }
-    })
+    }).map_err(Into::into)
 }
 
-fn source_excerpt(path: &str, line: Option<usize>, radius: usize) -> String {
-    let resolved = crate::config::resolve_source(path);
+fn source_excerpt(dir: &Path, path: &str, line: Option<usize>, radius: usize) -> String {
+    let resolved = crate::config::resolve_source(dir, path);
     let content = match resolved.and_then(|p| std::fs::read_to_string(p).ok()) {
         Some(c) => c,
         None => return format!("  (file not found: {path})"),
@@ -176,30 +367,93 @@ fn source_excerpt(path: &str, line: Option<usize>, radius: usize) -> String {
     out
 }
 
-pub fn get_entry(dir: &Path, topic: &str, idx: usize) -> Result<String, String> {
+pub fn get_entry(dir: &Path, topic: &str, idx: usize) -> Result<String, AmrError> {
+    let (date, body) = read_entry(dir, topic, idx)?;
+    Ok(format!("{}\n{body}", crate::entryfmt::header(&date)))
+}
+
+/// Lines of entry `topic:idx` containing `match_str` (case-insensitive), each
+/// with `context` lines around it — for raw-data entries too long to read whole.
+pub fn get_entry_lines(dir: &Path, topic: &str, idx: usize, match_str: &str, context: usize)
+    -> Result<String, AmrError>
+{
+    let (date, body) = read_entry(dir, topic, idx)?;
+    let needle = match_str.to_lowercase();
+    let lines: Vec<&str> = body.lines().collect();
+    let hits: Vec<usize> = (0..lines.len()).filter(|&i| lines[i].to_lowercase().contains(&needle)).collect();
+    let mut out = format!("{}\n{} of {} lines match '{match_str}'\n", crate::entryfmt::header(&date), hits.len(), lines.len());
+    let mut shown_to = 0;
+    for &h in &hits {
+        let start = h.saturating_sub(context).max(shown_to);
+        let end = (h + context + 1).min(lines.len());
+        if start >= end { continue; }
+        if shown_to > 0 && start > shown_to { out.push_str("  ...\n"); }
+        for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+            let marker = if hits.binary_search(&i).is_ok() { ">" } else { " " };
+            let _ = writeln!(out, " {marker}{:>4} {line}", i + 1);
+        }
+        shown_to = end;
+    }
+    Ok(out)
+}
+
+/// (date, body with includes expanded) of entry `topic:idx`; counts as a read.
+fn read_entry(dir: &Path, topic: &str, idx: usize) -> Result<(String, String), AmrError> {
+    let topic = &single_topic(dir, topic)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
-    if entries.is_empty() { return Err(format!("topic '{}' not found", topic)); }
+    if entries.is_empty() { return Err(AmrError::NotFound(format!("topic '{}' not found", topic))); }
     if idx >= entries.len() {
-        return Err(format!("index {idx} out of range (topic has {} entries, 0-{})",
-            entries.len(), entries.len().saturating_sub(1)));
+        return Err(AmrError::NotFound(format!("index {idx} out of range (topic has {} entries, 0-{})",
+            entries.len(), entries.len().saturating_sub(1))));
     }
     let e = &entries[idx];
+    crate::importance::record_reads(dir, &[e.offset]);
     let date = crate::time::minutes_to_date_str(e.timestamp_min);
-    Ok(format!("## {date}\n{}", e.body))
+    Ok((date, crate::include::expand(dir, e.timestamp_min, &e.body)))
+}
+
+/// A topic pattern that must name exactly one topic (index-based operations).
+pub fn single_topic(dir: &Path, topic: &str) -> Result<String, AmrError> {
+    let mut names = crate::pattern::expand(dir, topic)?;
+    if names.len() > 1 {
+        return Err(AmrError::InvalidArg(format!(
+            "{topic} matches {} topics ({}); an index needs exactly one", names.len(), names.join(", "))));
+    }
+    Ok(names.remove(0))
+}
+
+/// Entries of every topic matching `topic` (name, glob or /regex/).
+pub fn list_entries(dir: &Path, topic: &str, match_str: Option<&str>) -> Result<String, AmrError> {
+    if !crate::pattern::is_pattern(topic) { return list_topic_entries(dir, topic, match_str); }
+    let mut out = String::new();
+    for name in crate::pattern::expand(dir, topic)? {
+        let _ = writeln!(out, "### {name}");
+        out.push_str(&list_topic_entries(dir, &name, match_str)?);
+        out.push('\n');
+    }
+    Ok(out)
 }
 
-pub fn list_entries(dir: &Path, topic: &str, match_str: Option<&str>) -> Result<String, String> {
+fn list_topic_entries(dir: &Path, topic: &str, match_str: Option<&str>) -> Result<String, AmrError> {
     let log_path = crate::config::log_path(dir);
     let entries = crate::delete::topic_entries(&log_path, topic)?;
-    if entries.is_empty() { return Err(format!("topic '{}' not found", topic)); }
+    if entries.is_empty() { return Err(AmrError::NotFound(format!("topic '{}' not found", topic))); }
     let mut out = String::new();
     let mut shown = 0;
-    for (i, e) in entries.iter().enumerate() {
+    let mut shown_tokens = 0;
+    let all_tokens: usize = entries.iter().map(|e| crate::text::approx_tokens_of(&e.body)).sum();
+    // Log indices stay the labels (addresses), only the listing order changes
+    let mut listed: Vec<(usize, &crate::datalog::LogEntry)> = entries.iter().enumerate().collect();
+    crate::order::arrange(&mut listed, |(_, e)| &e.body);
+    let manual = listed.first().is_some_and(|(_, e)| crate::order::key(&e.body).is_some());
+    for (i, e) in listed {
         if let Some(needle) = match_str {
             if !e.body.to_lowercase().contains(&needle.to_lowercase()) { continue; }
         }
         shown += 1;
+        let tokens = crate::text::approx_tokens_of(&e.body);
+        shown_tokens += tokens;
         let date = crate::time::minutes_to_date_str(e.timestamp_min);
         let preview = e.body.lines()
             .find(|l| !l.trim().is_empty() && !crate::text::is_metadata_line(l))
@@ -208,12 +462,14 @@ pub fn list_entries(dir: &Path, topic: &str, match_str: Option<&str>) -> Result<
                 if t.len() > 70 { &t[..70] } else { t }
             })
             .unwrap_or("(empty)");
-        let _ = writeln!(out, "  [{i}] ## {date} — {preview}");
+        let _ = writeln!(out, "  [{i}] ## {date} — {preview} ({})", crate::text::format_tokens(tokens));
     }
     if shown == 0 {
         let _ = writeln!(out, "no entries{}", match_str.map(|s| format!(" matching \"{s}\"")).unwrap_or_default());
     } else {
-        let _ = writeln!(out, "\n{shown} of {} entries shown", entries.len());
+        let _ = writeln!(out, "\n{shown} of {} entries shown ({} of {}){}", entries.len(),
+            crate::text::format_tokens(shown_tokens), crate::text::format_tokens(all_tokens),
+            if manual { ", manual order" } else { "" });
     }
     Ok(out)
 }
@@ -1,49 +1,126 @@
 use crate::time::LocalTime;
 use std::io::{self, Read};
 use std::path::Path;
+use crate::error::AmrError;
+use crate::provenance::Provenance;
 
-pub fn run(dir: &Path, topic: &str, text: &str) -> Result<String, String> {
-    run_full(dir, topic, text, None, false, None)
+/// Optional metadata for a store (`run_full_ext`, `KnowledgeBase::store`).
+/// All fields default to off.
+#[derive(Default, Clone, Copy)]
+pub struct StoreOptions<'a> {
+    /// Comma-separated tags. None → auto-detected from the text prefix.
+    pub tags: Option<&'a str>,
+    /// `path/to/file:line` the entry describes (enables staleness checks).
+    pub source: Option<&'a str>,
+    /// 0.0–1.0; below 1.0 is recorded and down-weights ranking.
+    pub confidence: Option<f64>,
+    /// Space-separated `topic:index` narrative links.
+    pub links: Option<&'a str>,
+    /// Skip the near-duplicate check.
+    pub force: bool,
+    /// Who wrote it (provenance.rs). None → not recorded.
+    pub provenance: Option<crate::provenance::Provenance>,
 }
 
-pub fn run_with_tags(dir: &Path, topic: &str, text: &str, tags: Option<&str>) -> Result<String, String> {
-    run_full(dir, topic, text, tags, false, None)
+impl<'a> StoreOptions<'a> {
+    pub fn tags(tags: &'a str) -> Self { Self { tags: Some(tags), ..Self::default() } }
+}
+
+pub fn run(dir: &Path, topic: &str, text: &str) -> Result<String, AmrError> {
+    run_full(dir, topic, text, None, false, None, None)
+}
+
+pub fn run_with_tags(dir: &Path, topic: &str, text: &str, tags: Option<&str>) -> Result<String, AmrError> {
+    run_full(dir, topic, text, tags, false, None, None)
 }
 
 pub fn run_full(
     dir: &Path, topic: &str, text: &str, tags: Option<&str>,
-    force: bool, source: Option<&str>,
-) -> Result<String, String> {
-    run_full_ext(dir, topic, text, tags, force, source, None, None)
+    force: bool, source: Option<&str>, provenance: Option<Provenance>,
+) -> Result<String, AmrError> {
+    run_full_ext(dir, topic, text, &StoreOptions { tags, force, source, provenance, ..StoreOptions::default() })
 }
 
 pub fn run_full_conf(
     dir: &Path, topic: &str, text: &str, tags: Option<&str>,
     force: bool, source: Option<&str>, confidence: Option<f64>,
-) -> Result<String, String> {
-    run_full_ext(dir, topic, text, tags, force, source, confidence, None)
+) -> Result<String, AmrError> {
+    run_full_ext(dir, topic, text, &StoreOptions { tags, force, source, confidence, ..StoreOptions::default() })
 }
 
-pub fn run_full_ext(
-    dir: &Path, topic: &str, text: &str, tags: Option<&str>,
-    force: bool, source: Option<&str>, confidence: Option<f64>,
-    links: Option<&str>,
-) -> Result<String, String> {
+pub fn run_full_ext(dir: &Path, topic: &str, text: &str, opts: &StoreOptions) -> Result<String, AmrError> {
+    run_entry(dir, topic, text, opts).map(|(msg, _)| msg)
+}
+
+/// `run_full_ext`, also returning the entry as stored: topic, text and tags
+/// after pre_store had its say, for callers that report or forward them.
+pub fn run_entry(dir: &Path, topic: &str, text: &str, opts: &StoreOptions)
+    -> Result<(String, crate::storehook::Entry), AmrError>
+{
+    let text = read_text(text)?;
+    let entry = crate::storehook::pre_store(dir, crate::storehook::Entry {
+        topic: topic.to_string(), text, tags: opts.tags.map(String::from), source: opts.source.map(String::from),
+        provenance: opts.provenance,
+    })?;
+    let mut msg = write_entry(dir, &entry, opts.force, opts.confidence, opts.links)?;
+    if let Some(note) = crate::storehook::post_store(dir, &entry, &msg) { msg.push_str(&format!("\n  {note}")); }
+    Ok((msg, entry))
+}
+
+/// The store itself, under the KB lock; pre_store/post_store run outside it.
+fn write_entry(
+    dir: &Path, entry: &crate::storehook::Entry, force: bool, confidence: Option<f64>, links: Option<&str>,
+) -> Result<String, AmrError> {
+    let (topic, text, tags) = (entry.topic.as_str(), entry.text.clone(), entry.tags.as_deref());
+    let (source, provenance) = (entry.source.as_deref(), entry.provenance);
     crate::config::ensure_dir(dir)?;
     let _lock = crate::lock::FileLock::acquire(dir)?;
-    let text = read_text(text)?;
     let log_path = crate::datalog::ensure_log(dir)?;
+    let (text, summarized) = crate::summarize::screen(dir, text)?;
+    let (text, dump) = screen_raw_dump(dir, text);
 
     // Build body with metadata lines. Auto-detect tags from content when none given.
     let cleaned_tags = tags.map(|t| normalize_tags(t))
         .or_else(|| auto_detect_tags(&text));
-    let body = build_body(&text, cleaned_tags.as_deref(), source, confidence, links);
+    let cleaned_tags = match (&dump, cleaned_tags) {
+        (None, t) => t,
+        (Some(_), Some(t)) if !t.is_empty() => Some(normalize_tags(&format!("{t}, {}", crate::noise::TAG))),
+        (Some(_), _) => Some(crate::noise::TAG.to_string()),
+    };
+    let held = crate::review::holds(dir, provenance);
+    let cleaned_tags = match (held, cleaned_tags) {
+        (false, t) => t,
+        (true, Some(t)) if !t.is_empty() => Some(normalize_tags(&format!("{t}, {}", crate::review::TAG))),
+        (true, _) => Some(crate::review::TAG.to_string()),
+    };
+    let auto = auto_links(dir, topic, &text, links.unwrap_or(""));
+    let all_links = match (links.filter(|l| !l.is_empty()), auto.is_empty()) {
+        (l, true) => l.map(str::to_string),
+        (Some(l), false) => Some(format!("{l} {}", auto.join(" "))),
+        (None, false) => Some(auto.join(" ")),
+    };
+    let explicit_links = links.filter(|l| !l.is_empty());
+    let links = all_links.as_deref();
+    let commit = crate::git::record(dir, source);
+    let body = build_body(&text, cleaned_tags.as_deref(), source, commit.as_deref(), confidence, links, provenance);
 
     let ts = LocalTime::now();
     let ts_min = ts.to_minutes() as i32;
 
     // Dupe check
     let dupe_warn = if !force { check_dupe(dir, topic, &text) } else { None };
+    let merge = if !force && dupe_warn.is_none() { merge_candidate(dir, topic, &text, ts_min) } else { None };
+    // Held and raw-dump entries are always stored on their own: appending would
+    // drop the review/noise tag and publish the text through the existing entry.
+    let blocked = merge.as_ref().and_then(|m| new_metadata(&m.body, entry, confidence, explicit_links));
+    if let Some(m) = merge.as_ref().filter(|m| m.auto_append && !held && dump.is_none() && blocked.is_none()) {
+        // Extend the latest entry in place of a new one: same rewrite as append_by_index
+        let new_body = format!("{}\n{text}", m.body.trim_end());
+        crate::datalog::append_entry(&log_path, topic, &new_body, m.timestamp_min)?;
+        crate::datalog::append_delete(&log_path, m.offset)?;
+        return Ok(format!("auto-appended to entry [{}] in {topic} ({:.0}% overlap, {}; merge_policy = append)",
+            m.index, m.similarity * 100.0, age_label(m.age_min)));
+    }
     let topic_hint = suggest_topic(dir, topic);
 
     let offset = crate::datalog::append_entry(&log_path, topic, &body, ts_min)?;
@@ -58,7 +135,19 @@ pub fn run_full_ext(
         .map(|l| format!(" [links: {l}]")).unwrap_or_default();
     let mut msg = format!("stored in {topic}\n  @ {ts}{tag_echo}{conf_echo}{link_echo}\n{echo}");
     if let Some(hint) = topic_hint { msg.push_str(&format!("\n  note: {hint}")); }
+    if !auto.is_empty() { msg.push_str(&format!("\n  auto-linked: {}", auto.join(" "))); }
+    if held { msg.push_str("\n  pending review: hidden from search and briefings until `review approve`"); }
+    if let Some(ref s) = summarized { msg.push_str(&format!("\n  summarized: {s}")); }
+    if let Some(ref d) = dump { msg.push_str(&format!("\n  raw-dump: {d} — briefings skip it; store the finding, not the output")); }
     if let Some(ref dw) = dupe_warn { msg.push_str(&format!("\n  dupe warning: {dw}")); }
+    for w in growth_warnings(dir, topic) { msg.push_str(&format!("\n  growth: {w}")); }
+    if let Some(m) = merge {
+        msg.push_str(&format!("\n  suggest: append index={} ({:.0}% overlap with latest entry, {})",
+            m.index, m.similarity * 100.0, age_label(m.age_min)));
+        if let Some(what) = blocked.filter(|_| m.auto_append) {
+            msg.push_str(&format!(" — not auto-appended: the entry lacks this store's {what}"));
+        }
+    }
     if let Some(link_str) = links {
         let warn = validate_links(dir, link_str);
         if !warn.is_empty() { msg.push_str(&format!("\n  link warnings: {warn}")); }
@@ -66,79 +155,126 @@ pub fn run_full_ext(
     Ok(msg)
 }
 
+/// Soft limits from config (`max_topic_entries`, `max_entries`; unset or 0 = none).
+/// Past one, store still succeeds but says so, with the maintenance to run.
+fn growth_warnings(dir: &Path, topic: &str) -> Vec<String> {
+    let settings = crate::settings::load(dir);
+    let limit = |k: &str| settings.get(k).and_then(|v| v.parse::<usize>().ok()).filter(|&n| n > 0);
+    let (per_topic, total) = (limit("max_topic_entries"), limit("max_entries"));
+    if per_topic.is_none() && total.is_none() { return Vec::new(); }
+    let Ok((in_topic, all)) = crate::cache::with_corpus(dir, |cached| {
+        (cached.iter().filter(|e| e.topic.as_str() == topic).count(), cached.len())
+    }) else { return Vec::new() };
+    let mut out = Vec::new();
+    if let Some(max) = per_topic.filter(|&m| in_topic > m) {
+        out.push(format!("{topic} has {in_topic} entries (max_topic_entries = {max}) — \
+            merge duplicates with `compact {topic} --apply`, or split it into narrower topics"));
+    }
+    if let Some(max) = total.filter(|&m| all > m) {
+        out.push(format!("{all} entries in total (max_entries = {max}) — `prune` to find stale topics, \
+            archive them with `export --topic` then `delete`, and `compact log=true` to reclaim space"));
+    }
+    out
+}
+
 /// Lean write for batch_store — no lock, no dupe check.
 pub fn run_batch_entry(
     dir: &Path, topic: &str, text: &str, tags: Option<&str>, source: Option<&str>,
-) -> Result<String, String> {
+) -> Result<String, AmrError> {
     crate::config::ensure_dir(dir)?;
     let log_path = crate::datalog::ensure_log(dir)?;
     let cleaned_tags = tags.map(|t| normalize_tags(t));
-    let body = build_body(text, cleaned_tags.as_deref(), source, None, None);
+    let commit = crate::git::record(dir, source);
+    let body = build_body(text, cleaned_tags.as_deref(), source, commit.as_deref(), None, None, None);
     let ts_min = LocalTime::now().to_minutes() as i32;
     crate::datalog::append_entry(&log_path, topic, &body, ts_min)?;
     Ok(format!("stored in {topic}"))
 }
 
 /// F3: Lean write using pre-opened file handle — no lock, no dupe check, no fsync.
+/// `commit` is the caller's git::record for `source`.
 pub fn run_batch_entry_to(
     f: &mut std::fs::File, topic: &str, text: &str, tags: Option<&str>, source: Option<&str>,
-) -> Result<String, String> {
+    commit: Option<&str>, provenance: Option<Provenance>,
+) -> Result<String, AmrError> {
     let cleaned_tags = tags.map(|t| normalize_tags(t));
-    let body = build_body(text, cleaned_tags.as_deref(), source, None, None);
+    let body = build_body(text, cleaned_tags.as_deref(), source, commit, None, None, provenance);
     let ts_min = LocalTime::now().to_minutes() as i32;
     crate::datalog::append_entry_to(f, topic, &body, ts_min)?;
     Ok(format!("stored in {topic}"))
 }
 
 /// Import entry with explicit timestamp (preserves original dates on import).
+/// Marked `imported` unless the body already records its provenance.
 pub fn import_entry(
     dir: &Path, topic: &str, body: &str, tags: Option<&str>, ts_min: i32,
-) -> Result<String, String> {
+) -> Result<String, AmrError> {
     crate::config::ensure_dir(dir)?;
     let log_path = crate::datalog::ensure_log(dir)?;
     let cleaned_tags = tags.map(|t| normalize_tags(t));
-    let body = build_body(body, cleaned_tags.as_deref(), None, None, None);
+    let provenance = crate::text::extract_all_metadata(body).provenance.is_none()
+        .then_some(Provenance::Imported);
+    let body = build_body(body, cleaned_tags.as_deref(), None, None, None, None, provenance);
     crate::datalog::append_entry(&log_path, topic, &body, ts_min)?;
     Ok(format!("imported to {topic}"))
 }
 
 /// Append text to the last entry in a topic (no new timestamp).
-pub fn append(dir: &Path, topic: &str, text: &str) -> Result<String, String> {
+pub fn append(dir: &Path, topic: &str, text: &str) -> Result<String, AmrError> {
     let _lock = crate::lock::FileLock::acquire(dir)?;
     let text = read_text(text)?;
     let log_path = crate::config::log_path(dir);
     let entries = crate::datalog::iter_live(&log_path)?;
     let last = entries.iter().rev().find(|e| e.topic == topic)
-        .ok_or_else(|| format!("{topic} not found — use 'store' first"))?;
+        .ok_or_else(|| AmrError::NotFound(format!("{topic} not found — use 'store' first")))?;
     let new_body = format!("{}\n{text}", last.body.trim_end());
     crate::datalog::append_entry(&log_path, topic, &new_body, last.timestamp_min)?;
     crate::datalog::append_delete(&log_path, last.offset)?;
     Ok(format!("appended to last entry in {topic}"))
 }
 
-fn build_body(text: &str, tags: Option<&str>, source: Option<&str>,
-              confidence: Option<f64>, links: Option<&str>) -> String {
-    let mut body = String::new();
-    if let Some(t) = tags {
-        if !t.is_empty() { body.push_str(&format!("[tags: {t}]\n")); }
-    }
-    if let Some(src) = source { body.push_str(&format!("[source: {src}]\n")); }
-    if let Some(c) = confidence {
-        if c < 1.0 { body.push_str(&format!("[confidence: {c}]\n")); }
-    }
-    if let Some(l) = links {
-        if !l.is_empty() { body.push_str(&format!("[links: {l}]\n")); }
+fn build_body(text: &str, tags: Option<&str>, source: Option<&str>, commit: Option<&str>,
+              confidence: Option<f64>, links: Option<&str>, provenance: Option<Provenance>) -> String {
+    use crate::entryfmt::line;
+    let mut meta: Vec<String> = Vec::new();
+    if let Some(t) = tags.filter(|t| !t.is_empty()) { meta.push(line("tags", t)); }
+    if let Some(src) = source { meta.push(line("source", &crate::config::normalize_source(src))); }
+    if let Some(c) = commit { meta.push(line("commit", c)); }
+    if let Some(c) = confidence.filter(|c| *c < 1.0) { meta.push(line("confidence", &c.to_string())); }
+    if let Some(l) = links.filter(|l| !l.is_empty()) { meta.push(line("links", l)); }
+    if let Some(p) = provenance { meta.push(line("provenance", &p.to_string())); }
+    if crate::entryfmt::field(text, "lang").is_none() {
+        if let Some(l) = crate::lang::detect(text) { meta.push(line("lang", l.as_str())); }
     }
+    let mut body = String::new();
+    for m in meta { body.push_str(&m); body.push('\n'); }
     body.push_str(text);
     body
 }
 
-fn read_text(text: &str) -> Result<String, String> {
+/// `raw_dumps` setting: off | tag (default) | truncate. Returns the text to
+/// store (ANSI stripped, cut down under truncate) and what was detected.
+fn screen_raw_dump(dir: &Path, text: String) -> (String, Option<String>) {
+    let mode = crate::settings::load(dir).get("raw_dumps").unwrap_or("tag").to_string();
+    if mode == "off" { return (text, None); }
+    let signals = crate::noise::detect(&text);
+    if signals.is_empty() { return (text, None); }
+    let mut text = crate::noise::strip_ansi(&text);
+    let mut what = signals.join(", ");
+    if mode == "truncate" && text.len() > crate::noise::TRUNCATE_BYTES {
+        let before = text.len();
+        text = crate::noise::truncate(&text);
+        what.push_str(&format!("; truncated {before} → {} bytes", text.len()));
+    }
+    (text, Some(what))
+}
+
+fn read_text(text: &str) -> Result<String, AmrError> {
     if text == "-" {
         let mut buf = String::new();
-        io::stdin().read_to_string(&mut buf).map_err(|e| e.to_string())?;
+        io::stdin().read_to_string(&mut buf)?;
         let trimmed = buf.trim_end();
-        if trimmed.is_empty() { return Err("empty stdin".into()); }
+        if trimmed.is_empty() { return Err(AmrError::InvalidArg("empty stdin".into())); }
         Ok(trimmed.to_string())
     } else {
         Ok(text.to_string())
@@ -248,13 +384,77 @@ fn check_dupe(dir: &Path, topic: &str, new_text: &str) -> Option<String> {
     }).ok().flatten()
 }
 
+/// The topic's latest entry, when new text looks like a continuation of it.
+/// `merge_policy` setting: off | suggest (default) | append.
+struct MergeCandidate {
+    /// merge_policy = append: extend the entry instead of storing a new one.
+    auto_append: bool,
+    index: usize,
+    similarity: f64,
+    age_min: i64,
+    offset: u32,
+    timestamp_min: i32,
+    body: String,
+}
+
+/// Latest entry in `topic` if the new text overlaps it enough (token Jaccard
+/// ≥ merge_threshold, default 0.35) and it is recent (merge_window_hours,
+/// default 24). Below the dupe threshold by construction — check_dupe runs first.
+fn merge_candidate(dir: &Path, topic: &str, new_text: &str, now_min: i32) -> Option<MergeCandidate> {
+    let settings = crate::settings::load(dir);
+    let auto_append = match settings.get("merge_policy").unwrap_or("suggest") {
+        "off" => return None,
+        p => p == "append",
+    };
+    let threshold = settings.get("merge_threshold").and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.35);
+    let window_min = settings.get("merge_window_hours").and_then(|v| v.parse::<i64>().ok()).unwrap_or(24) * 60;
+    crate::cache::with_corpus(dir, |cached| {
+        let new_tokens: crate::fxhash::FxHashSet<String> = crate::text::tokenize(new_text)
+            .into_iter().filter(|t| t.len() >= 3).collect();
+        if new_tokens.len() < 4 { return None; }
+        let e = cached.iter().filter(|e| e.topic == topic).max_by_key(|e| e.offset)?;
+        let age_min = now_min as i64 - e.timestamp_min as i64;
+        if age_min > window_min { return None; }
+        let intersection = new_tokens.iter().filter(|t| e.tf_map.contains_key(*t)).count();
+        let union = new_tokens.len() + e.tf_map.len() - intersection;
+        let similarity = if union == 0 { 0.0 } else { intersection as f64 / union as f64 };
+        if similarity < threshold { return None; }
+        Some(MergeCandidate { auto_append, index: 0, similarity, age_min, offset: e.offset,
+            timestamp_min: e.timestamp_min, body: e.body.clone() })
+    }).ok().flatten().and_then(|mut m| {
+        // The index `append index=N` takes: log position within the topic, as append_by_index resolves it
+        let entries = crate::delete::topic_entries(&crate::config::log_path(dir), topic).ok()?;
+        m.index = entries.iter().position(|e| e.offset == m.offset)?;
+        Some(m)
+    })
+}
+
+/// What the caller's explicit metadata would add to `existing`, if anything.
+/// Auto-append only extends the text, so it is skipped when this is Some.
+fn new_metadata(existing: &str, entry: &crate::storehook::Entry, confidence: Option<f64>,
+                links: Option<&str>) -> Option<&'static str> {
+    let meta = crate::entryfmt::extract_all_metadata(existing);
+    let tags = entry.tags.as_deref().map(normalize_tags).unwrap_or_default();
+    if tags.split(", ").any(|t| !t.is_empty() && !meta.tags.iter().any(|m| m == t)) { return Some("tags"); }
+    let source = entry.source.as_deref().map(crate::config::normalize_source);
+    if source.is_some() && source != meta.source { return Some("source"); }
+    if confidence.is_some_and(|c| (c.min(1.0) - meta.confidence).abs() > 1e-9) { return Some("confidence"); }
+    if links.is_some() { return Some("links"); }
+    if entry.provenance.is_some() && entry.provenance != meta.provenance { return Some("provenance"); }
+    None
+}
+
+fn age_label(min: i64) -> String {
+    if min < 60 { format!("{}m old", min.max(0)) } else { format!("{}h old", min / 60) }
+}
+
 fn suggest_topic(dir: &Path, new_topic: &str) -> Option<String> {
-    // F4: Try cached index first, fall back to disk read
+    // F4: Try cached index first, fall back to the published one (staleness is harmless here)
     let topics = crate::mcp::with_index(|data| {
         crate::binquery::topic_table(data).ok()
     }).flatten().or_else(|| {
-        std::fs::read(dir.join("index.bin")).ok()
-            .and_then(|data| crate::binquery::topic_table(&data).ok())
+        crate::indexview::view(dir).ok().flatten()
+            .and_then(|map| crate::binquery::topic_table(map.bytes()).ok())
     })?;
     if topics.iter().any(|(_, name, _)| name == new_topic) { return None; }
     let parts: Vec<&str> = new_topic.split('-').collect();
@@ -268,6 +468,52 @@ fn suggest_topic(dir: &Path, new_topic: &str) -> Option<String> {
     Some(format!("new topic. similar: {}", similar.join(", ")))
 }
 
+const MAX_AUTO_LINKS: usize = 8;
+
+/// Links implied by the text itself (`auto_links = off` disables): each entry
+/// address it contains (`topic:3`, `topic:uid:ab12`), and each other topic it
+/// names as a whole word (4+ chars), linked to that topic's latest entry.
+/// Targets already in `explicit` are skipped. Returned as `topic:idx`.
+fn auto_links(dir: &Path, topic: &str, text: &str, explicit: &str) -> Vec<String> {
+    if crate::settings::load(dir).flag("auto_links") == Some(false) { return Vec::new(); }
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let addrs: Vec<crate::address::Address> = text.split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !is_word(c)))
+        .filter(|w| crate::address::is_address(w))
+        .filter_map(|w| crate::address::parse(w).ok())
+        .collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut linked: std::collections::BTreeSet<String> = explicit.split_whitespace()
+        .filter_map(|l| l.rsplit_once(':')).map(|(t, _)| t.to_string()).collect();
+    let push = |out: &mut Vec<String>, link: String| {
+        if !out.contains(&link) && !explicit.split_whitespace().any(|l| l == link) { out.push(link); }
+    };
+    for a in &addrs {
+        if let Ok(idx) = crate::address::resolve(dir, a) {
+            linked.insert(a.topic.to_string());
+            push(&mut out, format!("{}:{idx}", a.topic));
+        }
+    }
+    let _ = crate::cache::with_corpus(dir, |cached| {
+        let mut counts: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+        for e in cached { *counts.entry(e.topic.as_str()).or_default() += 1; }
+        // Drop addresses that point past the end of their topic
+        out.retain(|l| l.rsplit_once(':').is_some_and(|(t, i)| {
+            i.parse::<usize>().is_ok_and(|i| i < counts.get(t).copied().unwrap_or(0))
+        }));
+        let lower = text.to_lowercase();
+        for word in lower.split(|c: char| !is_word(c)) {
+            if word.len() < 4 || word == topic || linked.contains(word) { continue; }
+            if let Some(&n) = counts.get(word) {
+                linked.insert(word.to_string());
+                push(&mut out, format!("{word}:{}", n - 1));
+            }
+        }
+    });
+    out.truncate(MAX_AUTO_LINKS);
+    out
+}
+
 fn validate_links(dir: &Path, links: &str) -> String {
     let mut warnings = Vec::new();
     let _ = crate::cache::with_corpus(dir, |cached| {
@@ -0,0 +1,123 @@
+//! User scripts around `store`: `pre_store = <command>` and `post_store =
+//! <command>` in `<dir>/config`, run with `sh -c` and the entry as JSON on
+//! stdin (`{"topic","text","tags","source","provenance"}`). Lets a team
+//! enforce naming or tag conventions, or enrich entries, without forking.
+//!
+//! pre_store runs before anything is written. Exit non-zero to veto the store
+//! (stderr becomes the error). Exit 0 with empty stdout to accept the entry as
+//! is, or print a JSON object to change it: any of `topic`, `text`, `tags`
+//! (string or array) and `source` replace the stored values. A script that
+//! can't start, prints something that isn't a JSON object, or runs past
+//! `TIMEOUT` rejects the store, so a broken convention check never lets
+//! entries through unchecked.
+//!
+//! post_store runs after the write with `result` (store's reply) added to the
+//! JSON; its output is ignored and a failure is only noted in the reply.
+//! Both apply to `store` (CLI and MCP) and `decide`; batch and import write
+//! without them. Neither holds the KB lock, so a script may call amaranthine.
+
+use crate::error::AmrError;
+use crate::json::Value;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The entry as the scripts see it; pre_store may rewrite it.
+pub struct Entry {
+    pub topic: String,
+    pub text: String,
+    pub tags: Option<String>,
+    pub source: Option<String>,
+    pub provenance: Option<crate::provenance::Provenance>,
+}
+
+impl Entry {
+    fn to_json(&self, result: Option<&str>) -> String {
+        let opt = |v: &Option<String>| v.as_ref().map_or(Value::Null, |s| Value::Str(s.clone()));
+        let mut fields = vec![
+            ("topic".into(), Value::Str(self.topic.clone())),
+            ("text".into(), Value::Str(self.text.clone())),
+            ("tags".into(), opt(&self.tags)),
+            ("source".into(), opt(&self.source)),
+            ("provenance".into(), self.provenance.map_or(Value::Null, |p| Value::Str(p.as_str().into()))),
+        ];
+        if let Some(r) = result { fields.push(("result".into(), Value::Str(r.to_string()))); }
+        Value::Obj(fields).to_string()
+    }
+}
+
+/// Run pre_store on `e`: Ok with the (possibly rewritten) entry, or the veto.
+pub fn pre_store(dir: &Path, mut e: Entry) -> Result<Entry, AmrError> {
+    let Some(command) = command(dir, "pre_store") else { return Ok(e) };
+    let (ok, stdout, stderr) = run(&command, &e.to_json(None))
+        .map_err(|why| AmrError::InvalidArg(format!("pre_store '{command}' {why}; store rejected")))?;
+    if !ok {
+        let why = if stderr.trim().is_empty() { "no reason given" } else { stderr.trim() };
+        return Err(AmrError::InvalidArg(format!("pre_store rejected the entry: {why}")));
+    }
+    if stdout.trim().is_empty() { return Ok(e); }
+    let v = crate::json::parse(stdout.trim()).ok().filter(|v| matches!(v, Value::Obj(_)))
+        .ok_or_else(|| AmrError::InvalidArg(format!(
+            "pre_store '{command}' printed something other than a JSON object; store rejected")))?;
+    let str_of = |k: &str| v.get(k).and_then(|x| x.as_str()).map(String::from);
+    if let Some(t) = str_of("topic").filter(|t| !t.trim().is_empty()) { e.topic = crate::config::sanitize_topic(&t); }
+    if let Some(t) = str_of("text").filter(|t| !t.trim().is_empty()) { e.text = t; }
+    if let Some(s) = str_of("source") { e.source = (!s.is_empty()).then_some(s); }
+    match v.get("tags") {
+        Some(Value::Str(t)) => e.tags = (!t.is_empty()).then(|| t.clone()),
+        Some(Value::Arr(items)) => {
+            let t: Vec<&str> = items.iter().filter_map(|i| i.as_str()).collect();
+            e.tags = (!t.is_empty()).then(|| t.join(","));
+        }
+        _ => {}
+    }
+    Ok(e)
+}
+
+/// Run post_store on `e` with store's reply; a note for the reply on failure.
+pub fn post_store(dir: &Path, e: &Entry, result: &str) -> Option<String> {
+    let command = command(dir, "post_store")?;
+    match run(&command, &e.to_json(Some(result))) {
+        Ok((true, _, _)) => None,
+        Ok((false, _, stderr)) if stderr.trim().is_empty() => Some("post_store failed".into()),
+        Ok((false, _, stderr)) => Some(format!("post_store failed: {}", stderr.trim())),
+        Err(why) => Some(format!("post_store '{command}' {why}")),
+    }
+}
+
+fn command(dir: &Path, key: &str) -> Option<String> {
+    crate::settings::load(dir).get(key).filter(|c| !c.trim().is_empty()).map(String::from)
+}
+
+/// (exit ok, stdout, stderr) of `command` fed `input`, or why it didn't finish.
+fn run(command: &str, input: &str) -> Result<(bool, String, String), String> {
+    let mut child = Command::new("sh").arg("-c").arg(command)
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().map_err(|e| format!("failed to start: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() { let _ = stdin.write_all(input.as_bytes()); }
+    // Drain both pipes on threads so a chatty script can't fill one and stall
+    let drain = |pipe: Option<Box<dyn Read + Send>>| std::thread::spawn(move || {
+        let mut s = String::new();
+        if let Some(mut p) = pipe { let _ = p.read_to_string(&mut s); }
+        s
+    });
+    let out = drain(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let err = drain(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(s)) => break s,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {}s", TIMEOUT.as_secs()));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    Ok((status.success(), out.join().unwrap_or_default(), err.join().unwrap_or_default()))
+}
@@ -0,0 +1,115 @@
+//! Store-time summarization of very large texts (`summarize_large = on`): an
+//! agent storing a 10k-line dump gets an entry of the first `summarize_keep`
+//! lines plus the key lines of the rest — errors, measurements, decisions —
+//! instead of the whole paste eating the KB and every briefing after it.
+//! Repeated key lines collapse by shape (digits ignored) into one with a
+//! count. With `summarize_attach` (default on) the full text is kept under
+//! `<dir>/attachments/` and the entry points at it with `[attachment: ...]`.
+
+use crate::error::AmrError;
+use crate::fxhash::FxHashMap;
+use std::path::Path;
+
+/// Texts longer than this many lines are summarized (`summarize_lines`)...
+const DEFAULT_LINES: usize = 200;
+/// ...or longer than this many bytes, however few lines they have.
+const MAX_BYTES: usize = 32 * 1024;
+/// Leading lines kept as they are (`summarize_keep`).
+const DEFAULT_KEEP: usize = 20;
+/// Key lines kept from the rest, first seen first.
+const KEY_LINES: usize = 30;
+/// Characters kept of any one line, so a minified line can't undo the cut.
+const LINE_CHARS: usize = 200;
+
+pub const ATTACHMENTS: &str = "attachments";
+
+const ERROR_WORDS: &[&str] = &["error", "panic", "fail", "fatal", "exception", "warning", "denied",
+    "timed out", "timeout", "refused", "abort"];
+const DECISION_WORDS: &[&str] = &["decided", "decision", "because", "instead", "root cause", "fixed",
+    "workaround", "must ", "should ", "todo", "note:"];
+const UNITS: &[&str] = &["%", "ms", "us", "ns", "s", "kb", "mb", "gb", "x", "req/s", "ops/s"];
+
+pub struct Summary {
+    pub text: String,
+    pub lines: usize,
+    pub key_lines: usize,
+}
+
+/// The first `keep` lines of `text`, a marker line, then its key lines.
+/// ```
+/// use amaranthine::summarize::summarize;
+/// let mut log = String::from("build started\n");
+/// for i in 0..500 { log.push_str(&format!("compiling crate {i}\n")); }
+/// log.push_str("error[E0308]: mismatched types in src/a.rs:10\n");
+/// log.push_str("error[E0308]: mismatched types in src/a.rs:99\n");
+/// log.push_str("finished in 42.5s\n");
+/// let s = summarize(&log, 2);
+/// assert_eq!(s.lines, 504);
+/// assert_eq!(s.key_lines, 2);
+/// assert!(s.text.starts_with("build started\ncompiling crate 0\n"));
+/// assert!(s.text.contains("mismatched types in src/a.rs:10  (×2)"));
+/// assert!(s.text.contains("finished in 42.5s"));
+/// assert!(!s.text.contains("compiling crate 7"));
+/// ```
+pub fn summarize(text: &str, keep: usize) -> Summary {
+    let lines: Vec<&str> = text.lines().collect();
+    let head: Vec<&str> = lines.iter().take(keep).map(|l| cut(l)).collect();
+    // Shape (digits dropped) → its slot in `keys`: (first line, count)
+    let mut shapes: FxHashMap<String, usize> = FxHashMap::default();
+    let mut keys: Vec<(&str, usize)> = Vec::new();
+    for line in lines.iter().skip(keep).map(|l| l.trim()).filter(|l| is_key(l)) {
+        let shape: String = line.chars().filter(|c| !c.is_ascii_digit()).collect();
+        match shapes.get(&shape) {
+            Some(&i) => keys[i].1 += 1,
+            None if keys.len() < KEY_LINES => { shapes.insert(shape, keys.len()); keys.push((cut(line), 1)); }
+            None => {}
+        }
+    }
+    let mut out = head.join("\n");
+    out.push_str(&format!("\n… [summarized: {} lines, {} KB; first {} kept, then {} key lines]",
+        lines.len(), text.len().div_ceil(1024), head.len(), keys.len()));
+    for (line, n) in &keys {
+        out.push('\n');
+        out.push_str(line);
+        if *n > 1 { out.push_str(&format!("  (×{n})")); }
+    }
+    Summary { text: out, lines: lines.len(), key_lines: keys.len() }
+}
+
+fn cut(line: &str) -> &str { crate::text::truncate(line, LINE_CHARS) }
+
+/// An error, a decision, or a measurement (a number with a unit).
+fn is_key(line: &str) -> bool {
+    let lower = line.to_ascii_lowercase();
+    if ERROR_WORDS.iter().chain(DECISION_WORDS).any(|w| lower.contains(w)) { return true; }
+    lower.split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')' | '='))
+        .filter(|w| w.starts_with(|c: char| c.is_ascii_digit()))
+        .any(|w| {
+            let unit = w.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+            UNITS.contains(&unit)
+        })
+}
+
+/// Summarize `text` if the settings ask for it and it is over the limit,
+/// writing the attachment first. Returns the text to store and a note for
+/// the store message. Runs under the store lock.
+pub(crate) fn screen(dir: &Path, text: String) -> Result<(String, Option<String>), AmrError> {
+    let settings = crate::settings::load(dir);
+    if settings.flag("summarize_large") != Some(true) { return Ok((text, None)); }
+    let number = |k: &str, default: usize| settings.get(k).and_then(|v| v.parse().ok()).filter(|&n| n > 0).unwrap_or(default);
+    let max_lines = number("summarize_lines", DEFAULT_LINES);
+    if text.lines().count() <= max_lines && text.len() <= MAX_BYTES { return Ok((text, None)); }
+
+    let keep = number("summarize_keep", DEFAULT_KEEP);
+    let summary = summarize(&text, keep);
+    let mut note = format!("{} lines ({} KB) cut to {} leading + {} key lines",
+        summary.lines, text.len().div_ceil(1024), summary.lines.min(keep), summary.key_lines);
+    if settings.flag("summarize_attach") == Some(false) { return Ok((summary.text, Some(note))); }
+
+    let name = format!("{ATTACHMENTS}/{:016x}.txt", crate::format::hash_term(&text));
+    let path = dir.join(&name);
+    std::fs::create_dir_all(dir.join(ATTACHMENTS))?;
+    std::fs::write(&path, &text)?;
+    note.push_str(&format!("; full text in {}", path.display()));
+    Ok((format!("{}\n{}", crate::entryfmt::line("attachment", &name), summary.text), Some(note)))
+}
@@ -0,0 +1,184 @@
+//! `tail`: follow data.log and print entries as any process appends them —
+//! for watching what an agent stores during a long autonomous run. Reads only
+//! the bytes past a cursor; a record still being written is left for the next
+//! poll. Tombstones print as deletions (a revise shows as new + deleted).
+//!
+//! Cursors are `generation:offset` (datalog::log_generation): compaction
+//! rewrites every offset, so a cursor from an older generation restarts at
+//! the end of the log instead of reading from the middle of a record.
+//!
+//! CLI `tail` prints the last few entries and then polls until interrupted.
+//! MCP `tail` returns what arrived after `cursor`, waiting up to `wait`
+//! seconds for the first new record. With a progressToken it watches the whole
+//! `wait` window instead, streaming each arrival as a progress notification.
+//!
+//! Both filter by topic pattern and tag. `json` (CLI `watch`, or `tail
+//! --json`) prints one JSON object per record instead of a summary line —
+//! `{"event":"store"|"delete","topic","uid","date","tags","source","text"}` —
+//! for tooling that consumes live updates; MCP ends with `{"cursor":...}`.
+
+use crate::error::AmrError;
+use crate::json::Value;
+use std::fmt::Write;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub const POLL: Duration = Duration::from_millis(500);
+/// Longest MCP wait, so a call never outlives a client timeout.
+pub const MAX_WAIT_SECS: u64 = 60;
+
+/// Position in data.log: (log generation, byte offset).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Cursor { pub generation: u64, pub offset: u64 }
+
+impl Cursor {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (g, o) = s.trim().split_once(':')?;
+        Some(Cursor { generation: g.parse().ok()?, offset: o.parse().ok()? })
+    }
+
+    pub fn format(self) -> String { format!("{}:{}", self.generation, self.offset) }
+
+    /// End of the log as it is now.
+    pub fn end(dir: &Path) -> Self {
+        let len = std::fs::metadata(crate::config::log_path(dir)).map_or(0, |m| m.len());
+        Cursor { generation: crate::datalog::log_generation(dir), offset: len }
+    }
+}
+
+/// Which entries to show, and how.
+pub struct Watch<'a> {
+    pub topic: Option<crate::pattern::TopicPattern>,
+    pub tag: Option<String>,
+    /// JSON Lines records instead of summary lines.
+    pub json: bool,
+    pub dir: &'a Path,
+}
+
+/// New records after `cursor`, formatted one per line, and the cursor to
+/// continue from. A cursor from another log generation restarts at the end.
+pub fn poll(w: &Watch, cursor: Cursor) -> Result<(Vec<String>, Cursor), AmrError> {
+    let now = Cursor::end(w.dir);
+    if now.generation != cursor.generation || now.offset < cursor.offset {
+        let note = if w.json { r#"{"event":"compacted"}"# } else { "(data.log was compacted — following from the new end)" };
+        return Ok((vec![note.into()], now));
+    }
+    if now.offset == cursor.offset { return Ok((Vec::new(), cursor)); }
+    let log_path = crate::config::log_path(w.dir);
+    let start = cursor.offset.max(crate::datalog::LOG_HEADER_SIZE);
+    let mut data = Vec::new();
+    let mut f = std::fs::File::open(&log_path).map_err(|e| AmrError::io("open data.log", e))?;
+    f.seek(SeekFrom::Start(start))?;
+    f.read_to_end(&mut data)?;
+    let mut pos = 0;
+    let mut lines = Vec::new();
+    while let Some(len) = crate::datalog::record_len(&data, pos) {
+        if data[pos] == 0x01 {
+            if let Some(e) = crate::datalog::entry_ref(&data, pos as u32) {
+                if w.shows(e.topic, e.body) { lines.push(w.line(e.topic, e.timestamp_min, e.body, false)); }
+            }
+        } else {
+            let target = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]);
+            if let Ok(e) = crate::datalog::read_entry_from(&mut f, target) {
+                if w.shows(&e.topic, &e.body) { lines.push(w.line(&e.topic, e.timestamp_min, &e.body, true)); }
+            }
+        }
+        pos += len;
+    }
+    Ok((lines, Cursor { generation: now.generation, offset: start + pos as u64 }))
+}
+
+impl Watch<'_> {
+    fn shows(&self, topic: &str, body: &str) -> bool {
+        self.topic.as_ref().is_none_or(|p| p.matches(topic))
+            && self.tag.as_deref().is_none_or(|t| crate::text::extract_all_metadata(body).tags.iter().any(|x| x == t))
+    }
+
+    fn line(&self, topic: &str, ts: i32, body: &str, deleted: bool) -> String {
+        let uid = crate::address::format_uid(crate::address::entry_uid(ts, body));
+        let time = crate::time::minutes_to_date_str(ts);
+        if self.json {
+            let meta = crate::text::extract_all_metadata(body);
+            let content: Vec<&str> = body.lines().filter(|l| !crate::text::is_metadata_line(l)).collect();
+            return Value::Obj(vec![
+                ("event".into(), Value::Str(if deleted { "delete" } else { "store" }.into())),
+                ("topic".into(), Value::Str(topic.into())),
+                ("uid".into(), Value::Str(uid)),
+                ("date".into(), Value::Str(time)),
+                ("tags".into(), Value::Arr(meta.tags.into_iter().map(Value::Str).collect())),
+                ("source".into(), meta.source.map_or(Value::Null, Value::Str)),
+                ("text".into(), Value::Str(content.join("\n").trim().to_string())),
+            ]).to_string();
+        }
+        let what = if deleted { "deleted " } else { "" };
+        let tags = crate::text::extract_tags(&body.lines().collect::<Vec<_>>())
+            .map_or(String::new(), |t| format!("  {t}"));
+        format!("{time} {what}[{topic}:uid:{uid}] {}{tags}",
+            crate::text::truncate(crate::compress::first_content(body).trim(), 100))
+    }
+}
+
+/// The last `n` live entries shown by `w`, oldest first, and the end cursor.
+pub fn recent(w: &Watch, n: usize) -> Result<(Vec<String>, Cursor), AmrError> {
+    let end = Cursor::end(w.dir);
+    let log_path = crate::config::log_path(w.dir);
+    if !log_path.exists() { return Ok((Vec::new(), end)); }
+    let entries = crate::datalog::iter_live(&log_path)?;
+    let shown: Vec<String> = entries.iter().filter(|e| w.shows(&e.topic, &e.body)).rev().take(n)
+        .map(|e| w.line(&e.topic, e.timestamp_min, &e.body, false)).collect();
+    Ok((shown.into_iter().rev().collect(), end))
+}
+
+/// MCP form: records after `cursor` (None: the last `n` entries), waiting up
+/// to `wait_secs` for something new. Ends with the cursor to pass next time.
+pub fn run(w: &Watch, cursor: Option<&str>, n: usize, wait_secs: u64) -> Result<String, AmrError> {
+    let (mut lines, mut cur) = match cursor.filter(|c| !c.is_empty()) {
+        None => recent(w, n)?,
+        Some(c) => {
+            let c = Cursor::parse(c).ok_or_else(|| AmrError::InvalidArg(format!(
+                "bad cursor '{c}' (expected generation:offset from a previous tail)")))?;
+            poll(w, c)?
+        }
+    };
+    let deadline = Instant::now() + Duration::from_secs(wait_secs.min(MAX_WAIT_SECS));
+    // Streaming callers watch the whole window; others return on the first arrival
+    let streaming = crate::progress::active();
+    while (streaming || lines.is_empty()) && Instant::now() < deadline {
+        crate::cancel::check()?;
+        std::thread::sleep(POLL);
+        let (new, next) = poll(w, cur)?;
+        for l in new {
+            lines.push(l);
+            crate::progress::report(lines.len() as u64, None, &lines[lines.len() - 1]);
+        }
+        cur = next;
+    }
+    let mut out = String::new();
+    for l in &lines { let _ = writeln!(out, "{l}"); }
+    if w.json {
+        let _ = writeln!(out, "{}", Value::Obj(vec![("cursor".into(), Value::Str(cur.format()))]));
+        return Ok(out);
+    }
+    if lines.is_empty() { let _ = writeln!(out, "(nothing new)"); }
+    let _ = writeln!(out, "cursor={} (pass as cursor to continue)", cur.format());
+    Ok(out)
+}
+
+/// CLI form: the last `n` entries, then every new record until interrupted.
+pub fn follow(w: &Watch, n: usize) -> Result<(), AmrError> {
+    let (lines, mut cur) = recent(w, n)?;
+    let mut stdout = std::io::stdout();
+    let mut print = |lines: &[String]| {
+        use std::io::Write as _;
+        for l in lines { let _ = writeln!(stdout, "{l}"); }
+        let _ = stdout.flush();
+    };
+    print(&lines);
+    loop {
+        std::thread::sleep(POLL);
+        let (new, next) = poll(w, cur)?;
+        print(&new);
+        cur = next;
+    }
+}
@@ -10,6 +10,9 @@ const SEARCH_STOP_WORDS: &[&str] = &[
     "only", "other", "very", "after", "before", "most", "same", "both",
 ];
 
+/// Whether search drops `term` from queries.
+pub fn is_stop_word(term: &str) -> bool { SEARCH_STOP_WORDS.contains(&term) }
+
 /// Tokenize text: split on non-alphanumeric, expand CamelCase, lowercase.
 /// Used by query_terms (+ stop words), cache.rs corpus loading, and inverted.rs.
 /// Uses byte-level ASCII fast path (~30% faster) with Unicode fallback.
@@ -79,6 +82,7 @@ fn emit_segment(original: &str, lower: String, tokens: &mut Vec<String>) {
 }
 
 /// Build tf_map directly during tokenization — no intermediate Vec<String>.
+/// Tokens from a configured tokenizer plugin (tokplugin.rs) are counted too.
 /// Only allocates String keys for unique tokens (first occurrence).
 /// Reuses a stack buffer for ASCII lowercasing (~30% of tokens are repeats → zero alloc).
 pub fn tokenize_into_tfmap(text: &str, tf_map: &mut crate::fxhash::FxHashMap<String, usize>) -> usize {
@@ -128,6 +132,10 @@ pub fn tokenize_into_tfmap(text: &str, tf_map: &mut crate::fxhash::FxHashMap<Str
         if let Some(c) = tf_map.get_mut(lower_str) { *c += 1; }
         else { tf_map.insert(lower_str.to_string(), 1); }
     }
+    for t in crate::tokplugin::extra_tokens(text) {
+        word_count += 1;
+        *tf_map.entry(t).or_insert(0) += 1;
+    }
     word_count
 }
 
@@ -154,19 +162,23 @@ fn emit_segment_tfmap(original: &str, lower: &str, tf_map: &mut crate::fxhash::F
     count
 }
 
-/// Extract search terms: tokenize + filter stop words + dedup.
+/// Extract search terms: tokenize + plugin tokens + filter stop words + dedup.
 /// Uses FxHashSet for O(1) dedup instead of O(n) Vec::contains.
 pub fn query_terms(query: &str) -> Vec<String> {
     let mut terms = Vec::with_capacity(8);
     let mut seen = crate::fxhash::FxHashSet::default();
-    for token in tokenize(query) {
-        if SEARCH_STOP_WORDS.contains(&token.as_str()) { continue; }
+    for token in tokenize(query).into_iter().chain(crate::tokplugin::extra_tokens(query)) {
+        if is_stop_word(&token) { continue; }
         if seen.insert(token.clone()) { terms.push(token); }
     }
     terms
 }
 
 /// Split CamelCase and snake_case/kebab-case into component words.
+/// Uppercase runs stay together as acronyms: `HTTPServer` → http, server;
+/// `BM25Index` → bm25, index; `parseIOError` → parse, io, error. A run's last
+/// capital starts the next word when lowercase follows, unless that lowercase
+/// is a lone plural `s` (`URLs` → urls). Digits attach to the word before them.
 /// Uses byte-level scanning for ASCII content.
 fn split_compound_ascii(s: &str) -> Vec<String> {
     let mut parts = Vec::with_capacity(4);
@@ -174,13 +186,12 @@ fn split_compound_ascii(s: &str) -> Vec<String> {
         if segment.is_empty() { continue; }
         let bytes = segment.as_bytes();
         if bytes.iter().all(|b| b.is_ascii()) {
-            // ASCII fast path: detect uppercase transitions on bytes
+            // ASCII fast path: detect word boundaries on bytes
+            let kinds: Vec<Kind> = bytes.iter().map(|&b| Kind::of(b as char)).collect();
             let mut start = 0;
             for i in 1..bytes.len() {
-                if bytes[i].is_ascii_uppercase() {
-                    if i > start {
-                        parts.push(ascii_lower(&bytes[start..i]));
-                    }
+                if word_starts_at(&kinds, i) {
+                    parts.push(ascii_lower(&bytes[start..i]));
                     start = i;
                 }
             }
@@ -191,9 +202,11 @@ fn split_compound_ascii(s: &str) -> Vec<String> {
             // Unicode fallback
             let mut current = String::new();
             let chars: Vec<char> = segment.chars().collect();
+            let kinds: Vec<Kind> = chars.iter().map(|&c| Kind::of(c)).collect();
             for i in 0..chars.len() {
-                if i > 0 && chars[i].is_uppercase() {
-                    if !current.is_empty() { parts.push(current.to_lowercase()); current = String::new(); }
+                if i > 0 && word_starts_at(&kinds, i) && !current.is_empty() {
+                    parts.push(current.to_lowercase());
+                    current = String::new();
                 }
                 current.push(chars[i]);
             }
@@ -203,90 +216,140 @@ fn split_compound_ascii(s: &str) -> Vec<String> {
     parts
 }
 
-/// Truncate a string to max bytes at a char boundary.
-#[inline]
-pub fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max { return s; }
-    let mut end = max;
-    while end > 0 && !s.is_char_boundary(end) { end -= 1; }
-    &s[..end]
+/// Character class for compound splitting. `PluralS` is a lowercase 's'.
+#[derive(Clone, Copy, PartialEq)]
+enum Kind { Upper, Lower, PluralS, Digit }
+
+impl Kind {
+    #[inline]
+    fn of(c: char) -> Kind {
+        if c.is_uppercase() { Kind::Upper }
+        else if c.is_numeric() { Kind::Digit }
+        else if c == 's' { Kind::PluralS }
+        else { Kind::Lower }
+    }
+
+    #[inline]
+    fn is_lower(self) -> bool { matches!(self, Kind::Lower | Kind::PluralS) }
 }
 
-/// Check if a line is metadata (tags, source, type, modified, etc.).
-/// Fast reject: all metadata lines start with '['.
+/// True if a new word starts at `i` (> 0).
 #[inline]
-pub fn is_metadata_line(line: &str) -> bool {
-    if !line.starts_with('[') { return false; }
-    line.starts_with("[tags:") || line.starts_with("[source:")
-        || line.starts_with("[type:") || line.starts_with("[modified:")
-        || line.starts_with("[tier:") || line.starts_with("[confidence:")
-        || line.starts_with("[links:") || line.starts_with("[linked from:")
+fn word_starts_at(kinds: &[Kind], i: usize) -> bool {
+    match (kinds[i - 1], kinds[i]) {
+        // fooBar, bm25Index
+        (prev, Kind::Upper) if prev != Kind::Upper => true,
+        // HTTPServer: the 'S' of "Server" ends the acronym run; URLs stays whole
+        (Kind::Upper, Kind::Upper) => match kinds.get(i + 1) {
+            Some(Kind::PluralS) => kinds.get(i + 2).is_some_and(|k| k.is_lower()),
+            Some(Kind::Lower) => true,
+            _ => false,
+        },
+        _ => false,
+    }
 }
 
-/// All metadata extracted from an entry body in a single pass.
-pub struct EntryMetadata {
-    pub source: Option<String>,
-    pub tags: Vec<String>,
-    pub confidence: f64,
-    pub links: Vec<(String, usize)>,
+/// Word positions a sentence end (`. ` `! ` `? ` or newline) adds, so terms
+/// in the same sentence sit closer than ones split across sentences.
+const SENTENCE_GAP: usize = 8;
+
+/// Proximity of query terms in `text`: (k, span) where k ≥ 2 is the number of
+/// distinct terms present and span the fewest word positions covering one
+/// occurrence of each. None if fewer than two terms occur. Metadata lines skipped.
+pub fn term_span(text: &str, terms: &[String]) -> Option<(usize, usize)> {
+    if terms.len() < 2 { return None; }
+    let mut hits: Vec<(usize, usize)> = Vec::new(); // (position, term index)
+    let mut pos = 0;
+    for line in text.lines().filter(|l| !is_metadata_line(l)) {
+        let mut rest = line;
+        while !rest.is_empty() {
+            let start = rest.find(|c: char| c.is_alphanumeric()).unwrap_or(rest.len());
+            let sep = &rest[..start];
+            if sep.contains(['.', '!', '?']) && sep.contains(char::is_whitespace) { pos += SENTENCE_GAP; }
+            rest = &rest[start..];
+            let end = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+            if end == 0 { break; }
+            for tok in tokenize(&rest[..end]) {
+                if let Some(t) = terms.iter().position(|t| *t == tok) { hits.push((pos, t)); }
+            }
+            pos += 1;
+            rest = &rest[end..];
+        }
+        pos += SENTENCE_GAP;
+    }
+    let mut seen = vec![0usize; terms.len()];
+    for &(_, t) in &hits { seen[t] += 1; }
+    let k = seen.iter().filter(|&&c| c > 0).count();
+    if k < 2 { return None; }
+    // Minimum window over the position-ordered hits covering all k terms
+    let mut count = vec![0usize; terms.len()];
+    let (mut have, mut lo, mut best) = (0, 0, usize::MAX);
+    for hi in 0..hits.len() {
+        if count[hits[hi].1] == 0 { have += 1; }
+        count[hits[hi].1] += 1;
+        while have == k {
+            best = best.min(hits[hi].0 - hits[lo].0 + 1);
+            count[hits[lo].1] -= 1;
+            if count[hits[lo].1] == 0 { have -= 1; }
+            lo += 1;
+        }
+    }
+    Some((k, best))
 }
 
-/// Extract all metadata from entry body in one scan.
-/// Replaces 4 separate line scans (source, tags, confidence, links).
-pub fn extract_all_metadata(body: &str) -> EntryMetadata {
-    let mut source = None;
-    let mut tags = Vec::new();
-    let mut confidence = 1.0;
-    let mut links = Vec::new();
+/// Truncate a string to max bytes at a char boundary.
+#[inline]
+/// Rough LLM token cost of `words` tokenizer words (`tokenize(..).len()`, or
+/// a stored word_count): ~4/3 per word for punctuation and identifier pieces.
+pub fn approx_tokens(words: usize) -> usize { words + words.div_ceil(3) }
+
+/// Token estimate of a rendered text.
+pub fn approx_tokens_of(s: &str) -> usize { approx_tokens(tokenize(s).len()) }
 
-    for line in body.lines() {
-        if !line.starts_with('[') { continue; }
-        if let Some(inner) = line.strip_prefix("[tags: ").and_then(|s| s.strip_suffix(']')) {
-            tags = inner.split(',').map(|t| t.trim().to_string())
-                .filter(|t| !t.is_empty()).collect();
-        } else if let Some(s) = line.strip_prefix("[source: ").and_then(|s| s.strip_suffix(']')) {
-            source = Some(s.trim().to_string());
-        } else if let Some(c) = line.strip_prefix("[confidence: ")
-            .and_then(|s| s.strip_suffix(']'))
-            .and_then(|s| s.trim().parse::<f64>().ok()) {
-            confidence = c;
-        } else if let Some(inner) = line.strip_prefix("[links: ").and_then(|s| s.strip_suffix(']')) {
-            links = inner.split_whitespace()
-                .filter_map(|pair| {
-                    let (topic, idx) = pair.rsplit_once(':')?;
-                    Some((topic.to_string(), idx.parse().ok()?))
-                })
-                .collect();
-        }
+/// "~340 tok", "~1.2k tok", "~18k tok".
+pub fn format_tokens(n: usize) -> String {
+    match n {
+        0..=999 => format!("~{n} tok"),
+        1000..=9999 => format!("~{:.1}k tok", n as f64 / 1000.0),
+        _ => format!("~{}k tok", (n + 500) / 1000),
     }
+}
 
-    EntryMetadata { source, tags, confidence, links }
+pub fn truncate(s: &str, max: usize) -> &str {
+    if s.len() <= max { return s; }
+    let mut end = max;
+    while end > 0 && !s.is_char_boundary(end) { end -= 1; }
+    &s[..end]
 }
 
+// Metadata lines are described and parsed in entryfmt.rs; re-exported here
+// for the many callers that already reach for text::.
+pub use crate::entryfmt::{is_metadata_line, extract_all_metadata, EntryMetadata};
+
 /// Extract [source: path/to/file] from entry body text.
 pub fn extract_source(body: &str) -> Option<String> {
-    body.lines()
-        .find_map(|l| l.strip_prefix("[source: ").and_then(|s| s.strip_suffix(']')))
-        .map(|s| s.trim().to_string())
+    crate::entryfmt::field(body, "source").map(str::to_string)
+}
+
+/// Extract [commit: sha] (git freshness, see git.rs) from entry body text.
+pub fn extract_commit(body: &str) -> Option<&str> {
+    crate::entryfmt::field(body, "commit").filter(|s| !s.is_empty())
 }
 
 /// Parse raw tags line "[tags: a, b, c]" → vec!["a", "b", "c"].
 /// Accepts CachedEntry.tags_raw or any "[tags: ...]" line.
 pub fn parse_tags_raw(raw: Option<&str>) -> Vec<&str> {
-    raw.and_then(|line| line.strip_prefix("[tags: ").and_then(|s| s.strip_suffix(']')))
-        .map(|inner| inner.split(',').map(|t| t.trim()).filter(|t| !t.is_empty()).collect())
+    raw.and_then(crate::entryfmt::parse_line)
+        .filter(|(k, _)| *k == "tags")
+        .map(|(k, v)| crate::entryfmt::split(k, v))
         .unwrap_or_default()
 }
 
 /// Extract [tags: ...] from entry lines, formatted as #tag1 #tag2.
 pub fn extract_tags(lines: &[impl AsRef<str>]) -> Option<String> {
-    for line in lines {
-        if let Some(inner) = line.as_ref().strip_prefix("[tags: ").and_then(|s| s.strip_suffix(']')) {
-            let tags: Vec<&str> = inner.split(',').map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
-            if !tags.is_empty() { return Some(tags.iter().map(|t| format!("#{t}")).collect::<Vec<_>>().join(" ")); }
-        }
-    }
-    None
+    lines.iter().map(|l| parse_tags_raw(Some(l.as_ref())))
+        .find(|tags| !tags.is_empty())
+        .map(|tags| tags.iter().map(|t| format!("#{t}")).collect::<Vec<_>>().join(" "))
 }
 
 /// Fast integer-to-string push without format!(). Handles full u32 range.
@@ -0,0 +1,84 @@
+//! Terminal output themes. Callers still choose between the plain layout
+//! (`plain = true`: no escapes, MCP and scripts) and the colored one; the
+//! theme decides which escapes the colored layout uses.
+//! CLI precedence: `--plain` > `--theme` > `NO_COLOR` > `AMARANTHINE_THEME` > 16-color.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Theme { None = 0, Ansi16 = 1, Ansi256 = 2 }
+
+/// What a piece of text is, not how it looks.
+#[derive(Clone, Copy)]
+pub enum Role {
+    /// Topic group header in search results (`--- topic ---`).
+    Heading,
+    /// Result line containing a query term.
+    Match,
+    /// Briefing section title (`== Top Topics ==`).
+    Section,
+    /// Topic label on an entry line (`[topic] ## date`).
+    Topic,
+    /// Group header (`=== 2026-10-14 — 3 entries ===`).
+    Strong,
+    Warn,
+    Error,
+}
+
+static THEME: AtomicU8 = AtomicU8::new(Theme::Ansi16 as u8);
+
+impl Theme {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "off" | "plain" => Some(Theme::None),
+            "16" | "ansi" | "ansi16" => Some(Theme::Ansi16),
+            "256" | "ansi256" => Some(Theme::Ansi256),
+            _ => None,
+        }
+    }
+
+    /// Theme from the environment: NO_COLOR (any non-empty value) wins over
+    /// AMARANTHINE_THEME; unset or unrecognized → 16-color.
+    pub fn from_env() -> Self {
+        if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) { return Theme::None; }
+        std::env::var("AMARANTHINE_THEME").ok()
+            .and_then(|s| Self::parse(&s))
+            .unwrap_or(Theme::Ansi16)
+    }
+
+    fn sgr(self, role: Role) -> &'static str {
+        match (self, role) {
+            (Theme::None, _) => "",
+            (Theme::Ansi16, Role::Heading | Role::Topic) => "1;36",
+            (Theme::Ansi16, Role::Match | Role::Warn) => "1;33",
+            (Theme::Ansi16, Role::Section) => "1;35",
+            (Theme::Ansi16, Role::Strong) => "1",
+            (Theme::Ansi16, Role::Error) => "1;31",
+            (Theme::Ansi256, Role::Heading) => "1;38;5;44",
+            (Theme::Ansi256, Role::Topic) => "38;5;74",
+            (Theme::Ansi256, Role::Match) => "1;38;5;221",
+            (Theme::Ansi256, Role::Section) => "1;38;5;141",
+            (Theme::Ansi256, Role::Strong) => "1;38;5;252",
+            (Theme::Ansi256, Role::Warn) => "1;38;5;208",
+            (Theme::Ansi256, Role::Error) => "1;38;5;196",
+        }
+    }
+}
+
+/// Set the process-wide theme (the CLI does this once at startup).
+pub fn set(theme: Theme) { THEME.store(theme as u8, Ordering::Relaxed); }
+
+pub fn current() -> Theme {
+    match THEME.load(Ordering::Relaxed) {
+        0 => Theme::None,
+        2 => Theme::Ansi256,
+        _ => Theme::Ansi16,
+    }
+}
+
+/// `text` wrapped in the current theme's escapes for `role`.
+pub fn paint(role: Role, text: &str) -> String {
+    let sgr = current().sgr(role);
+    if sgr.is_empty() { return text.to_string(); }
+    format!("\x1b[{sgr}m{text}\x1b[0m")
+}
@@ -0,0 +1,86 @@
+//! `timeline <query>`: how understanding of a subsystem evolved. Collects the
+//! entries of matching topics (name contains the query, or a glob / /regex/)
+//! plus entries containing every query term, runs them through the briefing
+//! compressor for supersede and temporal-chain relations, then prints them
+//! oldest first, grouped by week (Monday-aligned), relations inlined under
+//! the entry they annotate.
+
+use crate::compress::RawEntry;
+use crate::error::AmrError;
+use std::fmt::Write;
+use std::path::Path;
+
+pub fn run(dir: &Path, query: &str) -> Result<String, AmrError> {
+    let query = query.trim();
+    if query.is_empty() { return Err(AmrError::InvalidArg("query is required".into())); }
+    let pattern = if crate::pattern::is_pattern(query) { Some(crate::pattern::TopicPattern::parse(query)?) } else { None };
+    let q_topic = crate::config::sanitize_topic(query);
+    let terms = crate::text::query_terms(query);
+    let now_days = crate::time::LocalTime::now().to_days();
+
+    let raw = crate::cache::with_corpus(dir, |cached| {
+        let mut counters: crate::fxhash::FxHashMap<&str, usize> = crate::fxhash::FxHashMap::default();
+        let mut raw: Vec<RawEntry> = Vec::new();
+        for e in cached {
+            let slot = counters.entry(e.topic.as_str()).or_default();
+            let index = *slot;
+            *slot += 1;
+            let in_topic = match &pattern {
+                Some(p) => p.matches(&e.topic),
+                None => e.topic.as_str().contains(q_topic.as_str()),
+            };
+            let by_terms = !terms.is_empty()
+                && crate::score::matches_tokens(&e.tf_map, &terms, crate::score::MinMatch::All);
+            if !in_topic && !by_terms { continue; }
+            if e.tags().iter().any(|t| t == crate::review::TAG || t == crate::noise::TAG) { continue; }
+            raw.push(RawEntry {
+                topic: e.topic.to_string(), index,
+                uid: crate::address::entry_uid(e.timestamp_min, &e.body), body: e.body.clone(),
+                timestamp_min: e.timestamp_min, days_old: e.days_old(now_days),
+                tags: e.tags().to_vec(), relevance: 1.0, confidence: e.confidence(),
+                link_in: 0, unvalidated: false,
+            });
+        }
+        raw
+    })?;
+    if raw.is_empty() { return Err(AmrError::NotFound(format!("no entries match '{query}'"))); }
+    let n_raw = raw.len();
+
+    let mut entries = crate::compress::compress(raw);
+    entries.sort_by(|a, b| a.date.cmp(&b.date));
+    let week_of = |date: &str| {
+        let days = crate::time::parse_date_days(date.get(..10).unwrap_or(date)).unwrap_or(0);
+        let (y, m, d) = crate::time::days_from_civil(days - (days + 3).rem_euclid(7));
+        format!("{y:04}-{m:02}-{d:02}")
+    };
+    let weeks: Vec<String> = {
+        let mut w: Vec<String> = entries.iter().map(|e| week_of(&e.date)).collect();
+        w.dedup();
+        w
+    };
+
+    let mut out = String::new();
+    let day = |d: &str| d.get(..10).unwrap_or(d).to_string();
+    let span = format!("{} \u{2192} {}", day(&entries[0].date), entries.last().map_or(String::new(), |e| day(&e.date)));
+    let merged = n_raw - entries.len();
+    let merged = if merged > 0 { format!(", {merged} duplicates folded") } else { String::new() };
+    let _ = writeln!(out, "=== TIMELINE: {query} === {} entries, {} week{} ({span}){merged}",
+        entries.len(), weeks.len(), if weeks.len() == 1 { "" } else { "s" });
+    let mut current = String::new();
+    for e in &entries {
+        let week = week_of(&e.date);
+        if week != current {
+            let n = entries.iter().filter(|x| week_of(&x.date) == week).count();
+            let _ = writeln!(out, "\n## week of {week} ({n})");
+            current = week;
+        }
+        let also = if e.also_in.is_empty() { String::new() } else { format!(" (also in {})", e.also_in.join(", ")) };
+        let when = e.date.get(5..).unwrap_or(&e.date);
+        let _ = writeln!(out, "  {when} [{}] {}{also}", e.addr(),
+            crate::text::truncate(crate::compress::first_content(&e.body).trim(), 100));
+        if let Some(chain) = &e.chain {
+            let _ = writeln!(out, "      \u{21b3} {}", crate::text::truncate(chain, 120));
+        }
+    }
+    Ok(out)
+}
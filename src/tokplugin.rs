@@ -0,0 +1,118 @@
+//! External tokenizer plugin. `tokenizer_command = <shell command>` in
+//! `<dir>/config` adds domain tokens (chemical names, ticket IDs, ...) to the
+//! builtin ones, identically at index time (`text::tokenize_into_tfmap`, which
+//! feeds the corpus cache and index.bin) and query time (`text::query_terms`).
+//!
+//! Each command starts once per process. Each text goes to its stdin as one
+//! line (newlines folded to spaces); it answers with one line of
+//! whitespace-separated tokens (lowercased here, empty line = none) and should
+//! exit on EOF. Match case-insensitively: queries may arrive lowercased, and
+//! the index path re-tokenizes the joined query terms. A command that fails to
+//! start, exits, or takes longer than `TIMEOUT` to answer is dropped for the
+//! rest of the process and the builtin tokens stand alone. Changing the setting re-tokenizes: `tokenizer.fp`
+//! records the command the corpus was tokenized with, and on a mismatch the
+//! corpus cache is dropped and index.bin rebuilt.
+
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+pub fn fp_path(dir: &Path) -> std::path::PathBuf { dir.join("tokenizer.fp") }
+
+struct Running { child: Child, stdin: ChildStdin, lines: Receiver<String> }
+
+struct Plugin { command: String, running: Option<Running>, failed: bool }
+
+/// Every command seen this process, each started on first use and kept, so an
+/// MCP server moving between dirs doesn't restart them; `active` is the
+/// current dir's.
+struct Plugins { all: Vec<Plugin>, active: Option<usize> }
+
+static PLUGINS: Mutex<Plugins> = Mutex::new(Plugins { all: Vec::new(), active: None });
+/// False while no command is configured, so the builtin path never locks.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Use `dir`'s tokenizer_command for this process. Called through
+/// `settings::apply` wherever the KB dir becomes known.
+pub fn activate(dir: &Path) {
+    let command = crate::settings::load(dir).get("tokenizer_command").unwrap_or("").to_string();
+    if let Ok(mut g) = PLUGINS.lock() {
+        let active = if command.is_empty() { None } else {
+            Some(g.all.iter().position(|p| p.command == command).unwrap_or_else(|| {
+                g.all.push(Plugin { command: command.clone(), running: None, failed: false });
+                g.all.len() - 1
+            }))
+        };
+        g.active = active;
+        ENABLED.store(active.is_some(), Ordering::Relaxed);
+    }
+    retokenize_if_changed(dir, &command);
+}
+
+/// Tokens the plugin adds for `text`. Empty without a working plugin.
+pub fn extra_tokens(text: &str) -> Vec<String> {
+    if !ENABLED.load(Ordering::Relaxed) { return Vec::new(); }
+    let Ok(mut g) = PLUGINS.lock() else { return Vec::new() };
+    let Some(i) = g.active else { return Vec::new() };
+    let p = &mut g.all[i];
+    if p.failed { return Vec::new(); }
+    match ask(p, text) {
+        Some(line) => line.split_whitespace().map(str::to_lowercase).filter(|t| t.len() >= 2).collect(),
+        None => {
+            eprintln!("tokenizer_command '{}' failed; using the builtin tokenizer", p.command);
+            p.failed = true;
+            if let Some(r) = p.running.take() { kill(r); }
+            Vec::new()
+        }
+    }
+}
+
+fn ask(p: &mut Plugin, text: &str) -> Option<String> {
+    if p.running.is_none() { p.running = Some(spawn(&p.command)?); }
+    let r = p.running.as_mut()?;
+    let line: String = text.chars().map(|c| if c == '\n' || c == '\r' { ' ' } else { c }).collect();
+    writeln!(r.stdin, "{line}").ok()?;
+    r.stdin.flush().ok()?;
+    r.lines.recv_timeout(TIMEOUT).ok()
+}
+
+fn spawn(command: &str) -> Option<Running> {
+    let mut child = Command::new("sh").arg("-c").arg(command)
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null())
+        .spawn().ok()?;
+    let stdin = child.stdin.take()?;
+    let stdout = child.stdout.take()?;
+    // Reader thread so a stuck plugin costs TIMEOUT, not a hung process
+    let (tx, lines) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() { break; }
+        }
+    });
+    Some(Running { child, stdin, lines })
+}
+
+fn kill(r: Running) {
+    let Running { mut child, stdin, .. } = r;
+    drop(stdin);
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Drop tokenized state built with another command (or none).
+fn retokenize_if_changed(dir: &Path, command: &str) {
+    let fp = fp_path(dir);
+    let recorded = std::fs::read_to_string(&fp).unwrap_or_default();
+    if recorded.trim_end_matches('\n') == command { return; }
+    let _ = if command.is_empty() { std::fs::remove_file(&fp) } else { std::fs::write(&fp, format!("{command}\n")) };
+    let _ = std::fs::remove_file(crate::cachefile::cache_path(dir));
+    crate::cache::invalidate();
+    if crate::config::data_log_exists(dir) { let _ = crate::inverted::rebuild_and_persist(dir); }
+}
@@ -1,6 +1,8 @@
+use crate::error::AmrError;
 use crate::time;
 use std::fmt::Write;
 use std::path::Path;
+use crate::theme::{paint, Role};
 
 pub fn list(dir: &Path) -> Result<String, String> {
     list_inner(dir, false)
@@ -8,23 +10,41 @@ pub fn list(dir: &Path) -> Result<String, String> {
 
 pub fn list_compact(dir: &Path) -> Result<String, String> {
     // Fast path: read topic table from binary index (zero corpus scan)
-    let from_index = crate::mcp::with_index(|data| {
-        crate::binquery::topic_table(data).ok()
-    }).flatten().or_else(|| {
-        std::fs::read(dir.join("index.bin")).ok()
-            .and_then(|data| crate::binquery::topic_table(&data).ok())
+    let table = |data: &[u8]| Some((crate::binquery::topic_table(data).ok()?,
+                                    crate::binquery::topic_words(data).ok()?));
+    let from_index = crate::mcp::with_index(table).flatten().or_else(|| {
+        crate::indexview::fresh(dir).ok().flatten().and_then(|map| table(map.bytes()))
     });
-    if let Some(topics) = from_index {
+    if let Some((topics, words)) = from_index {
         let mut out = String::new();
-        for (_, name, count) in &topics {
-            let _ = writeln!(out, "  {:<24} {:>3} entries", name, count);
+        let mut total = 0;
+        for (id, name, count) in &topics {
+            let tok = crate::text::approx_tokens(words.get(*id as usize).copied().unwrap_or(0));
+            total += tok;
+            let _ = writeln!(out, "  {:<24} {:>3} entries  {}", name, count, crate::text::format_tokens(tok));
         }
+        token_footer(&mut out, topics.len(), total);
         return Ok(out);
     }
     // Fallback: corpus scan
     list_inner(dir, true)
 }
 
+/// Topic table with stable UIDs (`#` + 16 hex): usable in place of a topic
+/// name in tool args and search filters, and unchanged across rebuilds.
+pub fn list_uids(dir: &Path) -> Result<String, String> {
+    crate::cache::with_corpus(dir, |cached| {
+        let mut counts: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+        for e in cached { *counts.entry(e.topic.as_str()).or_default() += 1; }
+        let mut out = String::new();
+        for (name, count) in &counts {
+            let uid = crate::topicuid::format(crate::format::topic_uid(name));
+            let _ = writeln!(out, "  {name:<24} {count:>3} entries  {uid}");
+        }
+        out
+    }).map_err(Into::into)
+}
+
 fn list_inner(dir: &Path, compact: bool) -> Result<String, String> {
     let log_path = crate::config::log_path(dir);
     if !log_path.exists() { return Ok("no data.log found\n".into()); }
@@ -34,6 +54,7 @@ fn list_inner(dir: &Path, compact: bool) -> Result<String, String> {
         for e in cached {
             let info = topics.entry(e.topic.to_string()).or_default();
             info.count += 1;
+            info.tokens += crate::text::approx_tokens(e.word_count);
             for t in e.tags() { info.tags.insert(t.clone()); }
             info.last_preview = entry_preview(&e.body);
         }
@@ -41,19 +62,29 @@ fn list_inner(dir: &Path, compact: bool) -> Result<String, String> {
         for (name, info) in &topics {
             let tag_str = if info.tags.is_empty() { String::new() }
                 else { format!(" [tags: {}]", info.tags.iter().cloned().collect::<Vec<_>>().join(", ")) };
+            let tok = crate::text::format_tokens(info.tokens);
             if compact {
-                let _ = writeln!(out, "  {name:<24} {:>3} entries{tag_str}", info.count);
+                let _ = writeln!(out, "  {name:<24} {:>3} entries  {tok}{tag_str}", info.count);
             } else {
-                let _ = writeln!(out, "  {name:<24} {:>3} entries  |{tag_str} {}", info.count, info.last_preview);
+                let _ = writeln!(out, "  {name:<24} {:>3} entries  {tok:<11}|{tag_str} {}", info.count, info.last_preview);
             }
         }
+        token_footer(&mut out, topics.len(), topics.values().map(|i| i.tokens).sum());
         out
-    })
+    }).map_err(Into::into)
+}
+
+/// "12 topics, ~48k tok total (estimate: ~4/3 tokens per word)"
+fn token_footer(out: &mut String, topics: usize, tokens: usize) {
+    let _ = writeln!(out, "\n{topics} topics, {} total (estimate: ~4/3 tokens per word)",
+        crate::text::format_tokens(tokens));
 }
 
 #[derive(Default)]
 struct TopicInfo {
     count: usize,
+    /// text::approx_tokens over the topic's entries.
+    tokens: usize,
     tags: std::collections::BTreeSet<String>,
     last_preview: String,
 }
@@ -75,61 +106,163 @@ fn entry_preview(body: &str) -> String {
         .unwrap_or_else(|| "(empty)".into())
 }
 
-pub fn read_topic(dir: &Path, topic: &str) -> Result<String, String> {
+/// Entries shown by the read tool when no count is given.
+pub const DEFAULT_READ_COUNT: usize = 50;
+
+/// Entries `from..from+count` of a topic (all remaining if count is None), oldest
+/// first. A partial read ends with a hint naming the next `from`.
+pub fn read_topic(dir: &Path, topic: &str, from: usize, count: Option<usize>) -> Result<String, AmrError> {
     let f = crate::config::sanitize_topic(topic);
-    crate::cache::with_corpus(dir, |cached| {
-        let group: Vec<_> = cached.iter().filter(|e| e.topic == f).collect();
-        if group.is_empty() { return Err(format!("topic '{f}' not found")); }
+    let (out, offsets) = crate::cache::with_corpus(dir, |cached| {
+        let mut group: Vec<_> = cached.iter().filter(|e| e.topic == f).collect();
+        crate::order::arrange(&mut group, |e| &e.body);
+        if group.is_empty() { return Err(AmrError::NotFound(format!("topic '{f}' not found"))); }
+        let total = group.len();
+        if from >= total {
+            return Err(AmrError::InvalidArg(format!("from={from} is past the end (topic has {total} entries, 0-{})", total - 1)));
+        }
+        let end = count.map_or(total, |c| total.min(from.saturating_add(c.max(1))));
+        let shown = &group[from..end];
+        let offsets: Vec<u32> = shown.iter().map(|e| e.offset).collect();
         let mut out = String::new();
-        for e in &group {
-            out.push_str(&format!("## {}\n{}\n\n", e.date_str(), e.body.trim()));
+        for e in shown {
+            let body = crate::include::expand_in(cached, e.timestamp_min, &e.body);
+            out.push_str(&format!("{}\n{}\n\n", crate::entryfmt::header(&e.date_str()), body.trim()));
         }
-        Ok(out)
-    })?
+        if from > 0 || end < total {
+            let _ = write!(out, "(entries {from}-{} of {total}", end - 1);
+            if end < total { let _ = write!(out, "; continue with from={end}"); }
+            out.push_str(")\n");
+        }
+        Ok((out, offsets))
+    })??;
+    // Outside the corpus lock: it's file I/O
+    crate::importance::record_reads(dir, &offsets);
+    Ok(out)
 }
 
 pub fn recent(dir: &Path, days: u64, plain: bool) -> Result<String, String> {
-    recent_inner(dir, Some(days), None, plain)
+    recent_inner(dir, Some(days), None, &RecentView::default(), plain)
 }
 
 pub fn recent_hours(dir: &Path, hours: u64, plain: bool) -> Result<String, String> {
-    recent_inner(dir, None, Some(hours), plain)
+    recent_inner(dir, None, Some(hours), &RecentView::default(), plain)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RecentGroup { Day, Session }
+
+/// Layout for `recent`: optional grouping, and whether to fold batch runs
+/// (≥ BATCH_RUN entries stored in one topic in the same minute) to one line each.
+/// `topic` narrows to one topic or a glob / /regex/ pattern.
+#[derive(Default)]
+pub struct RecentView {
+    pub group: Option<RecentGroup>,
+    pub collapse: bool,
+    pub topic: Option<String>,
 }
 
-fn recent_inner(dir: &Path, days: Option<u64>, hours: Option<u64>, plain: bool) -> Result<String, String> {
+const BATCH_RUN: usize = 3;
+
+/// `recent` over the last N days (or hours, if given) with a grouped layout.
+pub fn recent_view(dir: &Path, days: u64, hours: Option<u64>, view: &RecentView, plain: bool)
+    -> Result<String, String>
+{
+    match hours {
+        Some(h) => recent_inner(dir, None, Some(h), view, plain),
+        None => recent_inner(dir, Some(days), None, view, plain),
+    }
+}
+
+fn recent_inner(dir: &Path, days: Option<u64>, hours: Option<u64>, view: &RecentView, plain: bool)
+    -> Result<String, String>
+{
     let log_path = crate::config::log_path(dir);
     if !log_path.exists() { return Ok("no data.log found\n".into()); }
+    let sessions = if view.group == Some(RecentGroup::Session) { crate::session::starts(dir) } else { Vec::new() };
+    let scope = view.topic.as_deref().map(crate::pattern::TopicPattern::parse).transpose()?;
     crate::cache::with_corpus(dir, |cached| {
         let now = time::LocalTime::now();
         let use_minutes = hours.is_some();
         let cutoff_min = now.to_minutes() - hours.unwrap_or(0) as i64 * 60;
         let cutoff_day = now.to_days() - days.unwrap_or(7) as i64;
 
-        let mut found = 0;
+        let mut picked: Vec<&crate::cache::CachedEntry> = cached.iter().filter(|e| if use_minutes {
+            e.timestamp_min as i64 >= cutoff_min
+        } else {
+            e.day() >= cutoff_day
+        }).filter(|e| scope.as_ref().is_none_or(|p| p.matches(&e.topic))).collect();
         let mut out = String::new();
-        for e in cached {
-            let is_recent = if use_minutes {
-                e.timestamp_min as i64 >= cutoff_min
-            } else {
-                e.day() >= cutoff_day
-            };
-            if !is_recent { continue; }
-            let date = e.date_str();
-            if plain {
-                let _ = writeln!(out, "[{}] ## {}", e.topic, date);
-            } else {
-                let _ = writeln!(out, "\x1b[1;36m[{}]\x1b[0m ## {}", e.topic, date);
-            }
-            for line in e.body.lines() {
-                if !line.is_empty() { let _ = writeln!(out, "  {line}"); }
-            }
-            found += 1;
-        }
-        if found == 0 {
+        if picked.is_empty() {
             let label = if use_minutes { format!("{} hours", hours.unwrap_or(0)) }
                 else { format!("{} days", days.unwrap_or(7)) };
             let _ = writeln!(out, "no entries in the last {label}");
+            return out;
+        }
+        let Some(group) = view.group else {
+            write_entries(&mut out, &picked, view.collapse, plain);
+            return out;
+        };
+
+        // Grouped views read chronologically (revised entries sit at the log's end)
+        picked.sort_by_key(|e| e.timestamp_min);
+        let key = |e: &crate::cache::CachedEntry| -> String {
+            match group {
+                RecentGroup::Day => e.date_str()[..10].to_string(),
+                RecentGroup::Session => {
+                    // Latest session started at or before the entry
+                    let at = sessions.partition_point(|(m, _)| *m <= e.timestamp_min as i64);
+                    match at.checked_sub(1).map(|i| &sessions[i]) {
+                        Some((m, id)) => format!("session {id} (started {})",
+                            time::minutes_to_date_str(*m as i32)),
+                        None => "no recorded session".into(),
+                    }
+                }
+            }
+        };
+        let mut start = 0;
+        while start < picked.len() {
+            let k = key(picked[start]);
+            let mut end = start + 1;
+            while end < picked.len() && key(picked[end]) == k { end += 1; }
+            let n = end - start;
+            let header = format!("=== {k} — {n} entr{} ===", if n == 1 { "y" } else { "ies" });
+            if plain { let _ = writeln!(out, "{header}"); }
+            else { let _ = writeln!(out, "{}", paint(Role::Strong, &header)); }
+            write_entries(&mut out, &picked[start..end], view.collapse, plain);
+            out.push('\n');
+            start = end;
         }
         out
-    })
+    }).map_err(Into::into)
+}
+
+fn write_entries(out: &mut String, entries: &[&crate::cache::CachedEntry], collapse: bool, plain: bool) {
+    let mut i = 0;
+    while i < entries.len() {
+        let e = entries[i];
+        let mut run = 1;
+        if collapse {
+            while i + run < entries.len() && entries[i + run].topic == e.topic
+                && entries[i + run].timestamp_min == e.timestamp_min { run += 1; }
+            if run < BATCH_RUN { run = 1; }
+        }
+        let date = e.date_str();
+        let suffix = if run > 1 { format!(" — batch of {run}") } else { String::new() };
+        if plain {
+            let _ = writeln!(out, "[{}] ## {}{suffix}", e.topic, date);
+        } else {
+            let _ = writeln!(out, "{} ## {}{suffix}", paint(Role::Topic, &format!("[{}]", e.topic)), date);
+        }
+        if run > 1 {
+            for b in &entries[i..i + run] {
+                let _ = writeln!(out, "  - {}", crate::text::truncate(b.preview(), 80));
+            }
+        } else {
+            for line in e.body.lines() {
+                if !line.is_empty() { let _ = writeln!(out, "  {line}"); }
+            }
+        }
+        i += run;
+    }
 }
@@ -0,0 +1,32 @@
+//! Stable topic UIDs (index v5+): `format::topic_uid` hashes the name, so a
+//! topic keeps its UID across rebuilds. Written `#` + 16 hex digits, they
+//! stand in for topic names in tool args (`topics uids=true` lists them).
+
+use crate::binquery::{read_at, read_header, topic_name};
+use crate::error::AmrError;
+use crate::format::TopicEntry;
+
+/// Display form of a topic UID: `#` + 16 lowercase hex digits.
+pub fn format(uid: u64) -> String { format!("#{uid:016x}") }
+
+/// Parse the `format` form. Plain names never parse (no `#`).
+pub fn parse(s: &str) -> Option<u64> {
+    let hex = s.strip_prefix('#')?;
+    if hex.len() != 16 { return None; }
+    u64::from_str_radix(hex, 16).ok()
+}
+
+/// UID of each topic, indexed by topic_id.
+pub fn uids(data: &[u8]) -> Result<Vec<u64>, AmrError> {
+    let hdr = read_header(data)?;
+    let top_off = { hdr.topics_off } as usize;
+    (0..{ hdr.num_topics } as usize)
+        .map(|i| read_at::<TopicEntry>(data, top_off + i * std::mem::size_of::<TopicEntry>()).map(|te| te.uid))
+        .collect()
+}
+
+/// (topic_id, name) for a topic UID.
+pub fn topic_by_uid(data: &[u8], uid: u64) -> Option<(u16, String)> {
+    let id = uids(data).ok()?.iter().position(|&u| u == uid)? as u16;
+    Some((id, topic_name(data, id).ok()?))
+}
@@ -0,0 +1,164 @@
+//! Stored call graphs. A `trace` with `store_topic` also writes the whole
+//! codebase's definitions and calls to `<dir>/graphs/<topic>.graph`, so
+//! "who calls parse_sections" is answered later from the file (`trace_query`)
+//! without re-walking the code. Rewritten on every store to that topic.
+//!
+//! Format: tab-separated lines, one record each.
+//!   G  roots  glob  built_minutes      header
+//!   D  name  file  line                definition
+//!   C  caller  callee  file  line      call site
+
+use crate::error::AmrError;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+pub struct Def { pub name: String, pub file: String, pub line: usize }
+pub struct Call { pub caller: String, pub callee: String, pub file: String, pub line: usize }
+
+pub struct Graph {
+    pub roots: String,
+    pub glob: String,
+    pub built_min: i32,
+    pub defs: Vec<Def>,
+    pub calls: Vec<Call>,
+}
+
+fn graphs_dir(dir: &Path) -> PathBuf { dir.join("graphs") }
+
+fn graph_path(dir: &Path, topic: &str) -> PathBuf {
+    graphs_dir(dir).join(format!("{}.graph", crate::config::sanitize_topic(topic)))
+}
+
+/// Tabs and newlines can't appear in names or paths we write.
+fn field(s: &str) -> String { s.replace(['\t', '\n'], " ") }
+
+pub fn save(dir: &Path, topic: &str, g: &Graph) -> Result<String, String> {
+    std::fs::create_dir_all(graphs_dir(dir)).map_err(|e| format!("create graphs dir: {e}"))?;
+    let mut out = format!("G\t{}\t{}\t{}\n", field(&g.roots), field(&g.glob), g.built_min);
+    for d in &g.defs { let _ = writeln!(out, "D\t{}\t{}\t{}", d.name, field(&d.file), d.line); }
+    for c in &g.calls {
+        let _ = writeln!(out, "C\t{}\t{}\t{}\t{}", c.caller, c.callee, field(&c.file), c.line);
+    }
+    crate::config::atomic_write(&graph_path(dir, topic), &out)?;
+    Ok(format!("graph: {} fns, {} calls → trace_query topic={topic}", g.defs.len(), g.calls.len()))
+}
+
+pub fn load(dir: &Path, topic: &str) -> Result<Graph, AmrError> {
+    let path = graph_path(dir, topic);
+    let text = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AmrError::NotFound(format!(
+            "no stored graph for {topic} — run trace with store_topic={topic}")),
+        _ => AmrError::Io(format!("{}: {e}", path.display())),
+    })?;
+    let mut g = Graph { roots: String::new(), glob: String::new(), built_min: 0, defs: Vec::new(), calls: Vec::new() };
+    for (n, line) in text.lines().enumerate() {
+        let f: Vec<&str> = line.split('\t').collect();
+        let bad = || AmrError::Corrupt(format!("{}:{}: bad record", path.display(), n + 1));
+        match f.as_slice() {
+            ["G", roots, glob, built] => {
+                g.roots = roots.to_string();
+                g.glob = glob.to_string();
+                g.built_min = built.parse().map_err(|_| bad())?;
+            }
+            ["D", name, file, line] => g.defs.push(Def {
+                name: name.to_string(), file: file.to_string(), line: line.parse().map_err(|_| bad())?,
+            }),
+            ["C", caller, callee, file, line] => g.calls.push(Call {
+                caller: caller.to_string(), callee: callee.to_string(),
+                file: file.to_string(), line: line.parse().map_err(|_| bad())?,
+            }),
+            _ => return Err(bad()),
+        }
+    }
+    Ok(g)
+}
+
+/// Topics with a stored graph, sorted.
+pub fn topics(dir: &Path) -> Vec<String> {
+    let Ok(rd) = std::fs::read_dir(graphs_dir(dir)) else { return Vec::new() };
+    let mut out: Vec<String> = rd.flatten()
+        .filter_map(|e| e.file_name().to_str()?.strip_suffix(".graph").map(str::to_string))
+        .collect();
+    out.sort();
+    out
+}
+
+/// Callers and/or callees of `symbol` up to `depth` hops, from stored graphs:
+/// `topic`'s, or every stored graph when `topic` is empty.
+pub fn query(dir: &Path, topic: &str, symbol: &str, direction: &str, depth: usize)
+    -> Result<String, AmrError>
+{
+    if symbol.is_empty() { return Err(AmrError::InvalidArg("symbol is required".into())); }
+    if !matches!(direction, "callers" | "callees" | "both") {
+        return Err(AmrError::InvalidArg(format!("direction must be callers, callees or both, got {direction}")));
+    }
+    let names = if topic.is_empty() { topics(dir) } else { vec![topic.to_string()] };
+    if names.is_empty() {
+        return Err(AmrError::NotFound("no stored graphs — run trace with store_topic".into()));
+    }
+    let mut out = String::new();
+    for name in &names {
+        let g = load(dir, name)?;
+        let defs: Vec<&Def> = g.defs.iter().filter(|d| d.name == symbol).collect();
+        if defs.is_empty() && topic.is_empty() { continue; }
+        let _ = writeln!(out, "# {symbol} in graph {name} ({} {}, built {})",
+            g.roots, g.glob, crate::time::minutes_to_date_str(g.built_min));
+        if defs.is_empty() { let _ = writeln!(out, "  not defined in this graph"); continue; }
+        for d in &defs { let _ = writeln!(out, "DEF: {} ({}:{})", d.name, d.file, d.line); }
+        if direction != "callees" {
+            let _ = writeln!(out, "\nCALLERS:");
+            walk(&mut out, &g, symbol, depth, true);
+        }
+        if direction != "callers" {
+            let _ = writeln!(out, "\nCALLEES:");
+            walk(&mut out, &g, symbol, depth, false);
+        }
+        out.push('\n');
+    }
+    if out.is_empty() {
+        return Err(AmrError::NotFound(format!("{symbol} is not defined in any stored graph ({})", names.join(", "))));
+    }
+    Ok(out)
+}
+
+/// Breadth-first over call records; each function is listed once.
+fn walk(out: &mut String, g: &Graph, symbol: &str, depth: usize, callers: bool) {
+    let mut seen: BTreeSet<&str> = BTreeSet::from([symbol]);
+    let mut frontier = vec![symbol];
+    let mut any = false;
+    for d in 0..depth.clamp(1, 5) {
+        let mut next = Vec::new();
+        for c in &g.calls {
+            let (from, to) = if callers { (c.callee.as_str(), c.caller.as_str()) } else { (c.caller.as_str(), c.callee.as_str()) };
+            if !frontier.contains(&from) || !seen.insert(to) { continue; }
+            let arrow = if callers { '\u{2190}' } else { '\u{2192}' };
+            let _ = writeln!(out, "{}{arrow} {to} ({}:{})", "  ".repeat(d + 1), c.file, c.line);
+            next.push(to);
+            any = true;
+        }
+        if next.is_empty() { break; }
+        frontier = next;
+    }
+    if !any { let _ = writeln!(out, "  (none)"); }
+}
+
+/// Cross-file call degrees per file: (file, calls in from other files, calls
+/// out to other files), busiest first. The same centrality `reverse` ranks
+/// modules by, from the stored graph instead of a fresh walk.
+pub fn module_degrees(g: &Graph) -> Vec<(String, usize, usize)> {
+    let mut def_files: std::collections::BTreeMap<&str, Vec<&str>> = std::collections::BTreeMap::new();
+    for d in &g.defs { def_files.entry(d.name.as_str()).or_default().push(d.file.as_str()); }
+    let mut deg: std::collections::BTreeMap<&str, (usize, usize)> = std::collections::BTreeMap::new();
+    for c in &g.calls {
+        let Some(files) = def_files.get(c.callee.as_str()) else { continue };
+        // Ambiguous names (defined in several files) say nothing about who depends on whom
+        let [target] = files.as_slice() else { continue };
+        if *target == c.file { continue; }
+        deg.entry(target).or_default().0 += 1;
+        deg.entry(c.file.as_str()).or_default().1 += 1;
+    }
+    let mut out: Vec<(String, usize, usize)> = deg.into_iter().map(|(f, (i, o))| (f.to_string(), i, o)).collect();
+    out.sort_by(|a, b| (b.1 + b.2).cmp(&(a.1 + a.2)).then_with(|| a.0.cmp(&b.0)));
+    out
+}
@@ -0,0 +1,49 @@
+//! Query autocomplete: indexed terms starting with a prefix, with document
+//! frequencies (`terms prefix=sch`, CLI `terms`, FFI `amr_terms`). Read from
+//! index.bin's vocabulary section — sorted, so a prefix is one binary-searched
+//! run, and a term's postings count is its df. Stop words are left out:
+//! queries drop them.
+
+use crate::error::AmrError;
+use std::fmt::Write;
+use std::path::Path;
+
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// (term, entries containing it) for terms starting with `prefix`, most
+/// common first, then alphabetical. An empty prefix ranks the whole
+/// vocabulary. Also returns how many terms matched before `limit`.
+pub fn complete(data: &[u8], prefix: &str, limit: usize) -> Result<(Vec<(String, usize)>, usize), AmrError> {
+    let prefix = prefix.trim().to_lowercase();
+    let terms: Vec<(&str, usize)> = crate::expand::expand_prefix(data, &prefix, usize::MAX)?
+        .into_iter().filter(|v| !crate::text::is_stop_word(v.0)).map(|v| (v.0, v.2)).collect();
+    let total = terms.len();
+    Ok((terms.into_iter().take(limit).map(|(t, n)| (t.to_string(), n)).collect(), total))
+}
+
+/// `complete` as text for MCP and the CLI, on `index_data` when the caller
+/// holds it, else `dir`'s index.bin.
+pub fn run(dir: &Path, index_data: Option<&[u8]>, prefix: &str, limit: usize) -> Result<String, AmrError> {
+    let disk;
+    let data = match index_data {
+        Some(d) => d,
+        None => {
+            disk = crate::indexview::fresh(dir)?.ok_or_else(|| AmrError::NotFound("no index.bin".into()))?;
+            disk.bytes()
+        }
+    };
+    let (terms, total) = complete(data, prefix, limit)?;
+    let prefix = prefix.trim();
+    if terms.is_empty() { return Ok(format!("no terms start with '{prefix}'\n")); }
+    let mut out = if prefix.is_empty() { format!("{total} terms") } else { format!("{total} terms start with '{prefix}'") };
+    if total > terms.len() { let _ = write!(out, ", showing {}", terms.len()); }
+    out.push_str(" (term, entries):\n");
+    for (t, n) in &terms { let _ = writeln!(out, "  {t} {n}"); }
+    Ok(out)
+}
+
+/// `complete` as `term\tdf` lines, for bindings (amr_terms).
+pub fn tsv(data: &[u8], prefix: &str, limit: usize) -> Result<String, AmrError> {
+    let (terms, _) = complete(data, prefix, limit)?;
+    Ok(terms.iter().fold(String::new(), |mut out, (t, n)| { let _ = writeln!(out, "{t}\t{n}"); out }))
+}
@@ -0,0 +1,69 @@
+//! Page-cache priming: read index.bin and data.log front to back so the first
+//! queries after boot (mmapped hooks, cold server) don't pay for random IO.
+//! Optionally loads the corpus cache too. Reports bytes touched and time taken.
+
+use crate::error::AmrError;
+use crate::format::Header;
+use std::io::Read;
+use std::path::Path;
+use std::time::Instant;
+
+const PAGE: usize = 4096;
+
+pub fn run(dir: &Path, corpus: bool) -> Result<String, AmrError> {
+    let start = Instant::now();
+    let mut out = String::new();
+
+    let t = Instant::now();
+    match read_index(dir)? {
+        Some((total, postings, snippets)) => out.push_str(&format!(
+            "index.bin: {} (postings {}, snippets {}) in {}\n",
+            kb(total), kb(postings), kb(snippets), ms(t))),
+        None => out.push_str("index.bin: missing (run reindex)\n"),
+    }
+
+    let t = Instant::now();
+    if crate::config::log_path(dir).exists() {
+        let map = crate::logview::view(dir)?;
+        let bytes = map.bytes();
+        // one load per page faults the whole mapping in
+        let mut sum = 0u8;
+        for i in (0..bytes.len()).step_by(PAGE) { sum = sum.wrapping_add(bytes[i]); }
+        std::hint::black_box(sum);
+        out.push_str(&format!("data.log: {} in {}\n", kb(bytes.len()), ms(t)));
+    } else {
+        out.push_str("data.log: missing\n");
+    }
+
+    if corpus {
+        let t = Instant::now();
+        let n = crate::cache::preload(dir)?;
+        out.push_str(&format!("corpus: {n} entries in {}\n", ms(t)));
+    }
+    out.push_str(&format!("warm in {}", ms(start)));
+    Ok(out)
+}
+
+/// Sequentially read index.bin. Returns (total, postings, snippets) byte sizes.
+fn read_index(dir: &Path) -> Result<Option<(usize, usize, usize)>, AmrError> {
+    let mut f = match std::fs::File::open(dir.join("index.bin")) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(AmrError::io("open index.bin", e)),
+    };
+    let mut data = Vec::new();
+    f.read_to_end(&mut data).map_err(|e| AmrError::io("read index.bin", e))?;
+    if data.len() < std::mem::size_of::<Header>() {
+        return Err(AmrError::Corrupt("index.bin too small".into()));
+    }
+    let hdr = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const Header) };
+    let span = |from: u32, to: u32| (to as usize).saturating_sub(from as usize);
+    Ok(Some((data.len(), span(hdr.postings_off, hdr.meta_off), span(hdr.snippet_off, hdr.topics_off))))
+}
+
+fn kb(bytes: usize) -> String {
+    if bytes >= 1 << 20 { format!("{:.1}MB", bytes as f64 / (1 << 20) as f64) }
+    else { format!("{:.1}KB", bytes as f64 / 1024.0) }
+}
+
+fn ms(t: Instant) -> String { format!("{:.1}ms", t.elapsed().as_secs_f64() * 1000.0) }
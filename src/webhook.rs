@@ -0,0 +1,100 @@
+//! Outbound webhooks: after an MCP store, delete or merge, POST a small JSON
+//! event to every URL in `webhooks` (config), e.g. to post new decisions to a
+//! Slack channel. `webhooks_enabled = off` stops delivery without dropping the
+//! URLs.
+//!
+//! The event is `{"event","topic","detail","tags","entry","time","text"}`;
+//! `text` is a one-line summary, so a Slack incoming webhook shows it as is.
+//! Delivery runs on a background thread per URL through `curl` (https without
+//! a TLS dependency): each URL gets `ATTEMPTS` tries with a growing pause, then
+//! a line on stderr. A slow or dead endpoint never holds up the write.
+//! Entries held for review (review.rs) send no event: their text stays local
+//! until approved. Only http:// and https:// URLs are used.
+
+use crate::json::Value;
+use std::path::Path;
+use std::time::Duration;
+
+pub const ATTEMPTS: u32 = 3;
+const TIMEOUT_SECS: &str = "5";
+
+/// What happened, for the event payload.
+pub struct Event<'a> {
+    /// "store", "delete" or "merge".
+    pub kind: &'a str,
+    pub topic: &'a str,
+    /// First line of the tool's result ("stored in x (entry 3)", "merged a into b", ...).
+    pub detail: &'a str,
+    pub tags: &'a [&'a str],
+    /// Stored text, when the event carries one.
+    pub entry: Option<&'a str>,
+}
+
+/// An event with `result`'s first line as the detail and no entry text
+/// (delete, merge).
+pub fn emit_result(dir: &Path, kind: &str, topic: &str, result: &str) {
+    emit(dir, &Event { kind, topic, detail: result.lines().next().unwrap_or(kind), tags: &[], entry: None });
+}
+
+/// Configured URLs, empty when none are set or delivery is switched off.
+pub fn urls(dir: &Path) -> Vec<String> {
+    let settings = crate::settings::load(dir);
+    if settings.flag("webhooks_enabled") == Some(false) { return Vec::new(); }
+    settings.list("webhooks").into_iter()
+        .filter(|u| u.starts_with("http://") || u.starts_with("https://"))
+        .map(String::from).collect()
+}
+
+/// Send `e` to every configured URL in the background.
+pub fn emit(dir: &Path, e: &Event) {
+    let urls = urls(dir);
+    if urls.is_empty() { return; }
+    let payload = std::sync::Arc::new(payload(e).to_string());
+    // One thread per URL, so a dead endpoint's retries don't delay the others
+    for url in urls {
+        let payload = payload.clone();
+        std::thread::spawn(move || deliver(&url, &payload));
+    }
+}
+
+fn payload(e: &Event) -> Value {
+    let s = |v: &str| Value::Str(v.to_string());
+    let first = e.entry.map(|t| crate::text::truncate(crate::compress::first_content(t).trim(), 200).to_string());
+    let mut text = format!("[amaranthine] {} {}: {}", e.kind, e.topic, e.detail);
+    if let Some(f) = first.as_deref().filter(|f| !f.is_empty()) { text = format!("{text}\n> {f}"); }
+    Value::Obj(vec![
+        ("event".into(), s(e.kind)),
+        ("topic".into(), s(e.topic)),
+        ("detail".into(), s(e.detail)),
+        ("tags".into(), Value::Arr(e.tags.iter().map(|t| s(t)).collect())),
+        ("entry".into(), e.entry.map_or(Value::Null, s)),
+        ("time".into(), Value::Str(crate::time::LocalTime::now().to_string())),
+        ("text".into(), Value::Str(text)),
+    ])
+}
+
+fn deliver(url: &str, payload: &str) {
+    let mut last = String::new();
+    for attempt in 0..ATTEMPTS {
+        if attempt > 0 { std::thread::sleep(Duration::from_secs(1 << attempt)); }
+        match post(url, payload) {
+            Ok(()) => return,
+            Err(e) => last = e,
+        }
+    }
+    eprintln!("webhook {url}: giving up after {ATTEMPTS} attempts: {last}");
+}
+
+fn post(url: &str, payload: &str) -> Result<(), String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    let mut child = Command::new("curl")
+        .args(["-sS", "-f", "-X", "POST", "--max-time", TIMEOUT_SECS,
+               "-H", "Content-Type: application/json", "--data-binary", "@-", "--url", url])
+        .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
+        .spawn().map_err(|e| format!("can't run curl: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() { let _ = stdin.write_all(payload.as_bytes()); }
+    let out = child.wait_with_output().map_err(|e| e.to_string())?;
+    if out.status.success() { return Ok(()); }
+    Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+}
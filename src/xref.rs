@@ -1,9 +1,10 @@
+use crate::error::AmrError;
 use std::fmt::Write;
 use std::path::Path;
 
 /// Find all cross-references: entries in other topics that mention this topic.
 /// Uses binary index xref edges when available (~1ms), falls back to corpus scan.
-pub fn refs_for(dir: &Path, topic: &str) -> Result<String, String> {
+pub fn refs_for(dir: &Path, topic: &str) -> Result<String, AmrError> {
     let filename = crate::config::sanitize_topic(topic);
 
     // Try index path first (pre-computed xref edges)
@@ -55,10 +56,10 @@ fn refs_via_index(dir: &Path, filename: &str) -> Option<String> {
     }).flatten()
 }
 
-fn refs_via_corpus(dir: &Path, filename: &str) -> Result<String, String> {
+fn refs_via_corpus(dir: &Path, filename: &str) -> Result<String, AmrError> {
     crate::cache::with_corpus(dir, |cached| {
         if !cached.iter().any(|e| e.topic == filename) {
-            return Err(format!("topic '{}' not found", filename));
+            return Err(AmrError::NotFound(format!("topic '{}' not found", filename)));
         }
 
         let search_tokens = crate::text::tokenize(filename);
@@ -25,6 +25,9 @@ int main(int argc, char** argv) {
 
     char* info = amr_info(idx);
     if (info) { printf("%s\n", info); amr_free_str(info); }
+    AmrInfo st;
+    if (amr_info_struct(idx, &st) == AMR_OK)
+        printf("generation %llu, %llu bytes\n", (unsigned long long)st.generation, (unsigned long long)st.bytes);
 
     int N = 10000;
     double start, elapsed;